@@ -0,0 +1,281 @@
+//! Answers files.
+//!
+//! `--answers <file>` reads the choices of an unattended run from a YAML or
+//! JSON file instead of dozens of flags and prompts: which configs to use,
+//! how to create the worktree, template variables, and the responses to
+//! prompts. Flags given on the command line win over the file, and a run
+//! with an answers file never prompts.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use worktree_setup_operations::Label;
+
+use crate::args::{Args, Command, ExistingTarget, SetupArgs};
+
+/// Choices read from an answers file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Answers {
+    /// Worktree path, relative to the current directory.
+    #[serde(default)]
+    pub target_path: Option<PathBuf>,
+    /// Config patterns to select (`--config`).
+    #[serde(default)]
+    pub configs: Vec<String>,
+    /// Profiles to use (`--profile`).
+    #[serde(default)]
+    pub profiles: Vec<String>,
+    /// Branch to check out (`--branch`).
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Branch to create (`--new-branch`).
+    #[serde(default)]
+    pub new_branch: Option<String>,
+    /// Remote branch to track (`--remote-branch`).
+    #[serde(default)]
+    pub remote_branch: Option<String>,
+    /// Remote to use (`--remote`).
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Copy unstaged files (`--unstaged`/`--no-unstaged`).
+    #[serde(default)]
+    pub unstaged: Option<bool>,
+    /// Run post-setup commands; `false` is `--no-install`.
+    #[serde(default)]
+    pub install: Option<bool>,
+    /// Overwrite existing files (`setup --overwrite`).
+    #[serde(default)]
+    pub overwrite: Option<bool>,
+    /// Labels for the worktree (`--label`).
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Template variables, set in the environment of the run.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Responses to the prompts of an interactive run.
+    #[serde(default)]
+    pub prompts: PromptAnswers,
+}
+
+/// Responses to prompts.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PromptAnswers {
+    /// What to do with a target that exists but is not a worktree
+    /// (`--existing-target`).
+    #[serde(default)]
+    pub existing_target: Option<ExistingTarget>,
+    /// Go ahead with copies above `confirm_above` (`--yes`).
+    #[serde(default)]
+    pub confirm_large_copy: Option<bool>,
+    /// Replace files edited since the last setup (`--force-destructive`).
+    #[serde(default)]
+    pub replace_edited: Option<bool>,
+}
+
+/// Load an answers file: JSON if it ends in `.json`, YAML otherwise.
+///
+/// # Errors
+///
+/// * If the file cannot be read
+/// * If the file is not valid or has unknown fields
+pub fn load(path: &Path) -> Result<Answers, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read answers file {}: {e}", path.display()))?;
+    let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&content).map_err(|e| e.to_string())
+    };
+    parsed
+        .map_err(|e| format!("Invalid answers file {}: {e}", path.display()))
+        .map_err(Into::into)
+}
+
+/// Apply the answers file named by `--answers`, if any, to `args`.
+///
+/// Template variables are set in the process environment, so this must
+/// run before other threads are spawned.
+///
+/// # Errors
+///
+/// * If the answers file cannot be loaded
+pub fn apply(args: &mut Args) -> Result<(), Box<dyn std::error::Error>> {
+    let path = match &args.command {
+        None => args.answers.clone(),
+        Some(Command::Setup(setup_args)) => setup_args.answers.clone(),
+        Some(_) => None,
+    };
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let answers = load(&path)?;
+    log::debug!("Using answers from {}", path.display());
+
+    match &mut args.command {
+        None => answers.apply_to_create(args),
+        Some(Command::Setup(setup_args)) => answers.apply_to_setup(setup_args),
+        Some(_) => {}
+    }
+    for (name, value) in &answers.vars {
+        // SAFETY: We're setting these before any other threads are spawned
+        unsafe {
+            env::set_var(name, value);
+        }
+    }
+    Ok(())
+}
+
+impl Answers {
+    /// Fill in the worktree creation flags not given on the command line.
+    fn apply_to_create(&self, args: &mut Args) {
+        args.non_interactive = true;
+        fill(&mut args.target_path, self.target_path.as_ref());
+        fill_list(&mut args.configs, &self.configs);
+        fill_list(&mut args.profile, &self.profiles);
+        fill(&mut args.branch, self.branch.as_ref());
+        fill(&mut args.new_branch, self.new_branch.as_ref());
+        fill(&mut args.remote_branch, self.remote_branch.as_ref());
+        fill(&mut args.remote, self.remote.as_ref());
+        fill(
+            &mut args.existing_target,
+            self.prompts.existing_target.as_ref(),
+        );
+        self.apply_unstaged(&mut args.unstaged, &mut args.no_unstaged);
+        args.no_install |= self.install == Some(false);
+        args.yes |= self.prompts.confirm_large_copy == Some(true);
+        args.force_destructive |= self.prompts.replace_edited == Some(true);
+        self.add_labels(&mut args.labels);
+    }
+
+    /// Fill in the `setup` flags not given on the command line.
+    fn apply_to_setup(&self, args: &mut SetupArgs) {
+        args.non_interactive = true;
+        fill(&mut args.target_path, self.target_path.as_ref());
+        fill_list(&mut args.configs, &self.configs);
+        fill_list(&mut args.profile, &self.profiles);
+        self.apply_unstaged(&mut args.unstaged, &mut args.no_unstaged);
+        args.no_install |= self.install == Some(false);
+        args.overwrite |= self.overwrite == Some(true);
+        args.yes |= self.prompts.confirm_large_copy == Some(true);
+        args.force_destructive |= self.prompts.replace_edited == Some(true);
+        self.add_labels(&mut args.labels);
+    }
+
+    /// Set `--unstaged` or `--no-unstaged` unless either was given.
+    const fn apply_unstaged(&self, unstaged: &mut bool, no_unstaged: &mut bool) {
+        if *unstaged || *no_unstaged {
+            return;
+        }
+        match self.unstaged {
+            Some(true) => *unstaged = true,
+            Some(false) => *no_unstaged = true,
+            None => {}
+        }
+    }
+
+    /// Add the labels whose keys were not given with `--label`.
+    fn add_labels(&self, labels: &mut Vec<Label>) {
+        for (key, value) in &self.labels {
+            if !labels.iter().any(|label| &label.key == key) {
+                labels.push(Label {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Use `answer` for a flag that was not given.
+fn fill<T: Clone>(flag: &mut Option<T>, answer: Option<&T>) {
+    if flag.is_none() {
+        *flag = answer.cloned();
+    }
+}
+
+/// Use `answers` for a repeatable flag that was not given.
+fn fill_list(flag: &mut Vec<String>, answers: &[String]) {
+    if flag.is_empty() {
+        flag.extend_from_slice(answers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_answers_fill_unset_flags() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("setup.answers.yaml");
+        fs::write(
+            &path,
+            "targetPath: ../feature
+configs: [apps/web/*]
+newBranch: feature
+unstaged: false
+install: false
+labels:
+  ticket: ABC-1
+  owner: web
+prompts:
+  existingTarget: files-only
+  confirmLargeCopy: true
+",
+        )
+        .unwrap();
+        let answers = load(&path).unwrap();
+
+        let mut args = Args::parse_from([
+            "worktree-setup",
+            "--new-branch",
+            "other",
+            "--label",
+            "ticket=XYZ-9",
+        ]);
+        answers.apply_to_create(&mut args);
+
+        assert!(args.non_interactive);
+        assert_eq!(args.target_path, Some(PathBuf::from("../feature")));
+        assert_eq!(args.configs, vec!["apps/web/*"]);
+        assert_eq!(args.new_branch.as_deref(), Some("other"));
+        assert!(args.no_unstaged && !args.unstaged);
+        assert!(args.no_install && args.yes && !args.force_destructive);
+        assert_eq!(args.existing_target, Some(ExistingTarget::FilesOnly));
+        let labels: Vec<String> = args.labels.iter().map(ToString::to_string).collect();
+        assert_eq!(labels, vec!["ticket=XYZ-9", "owner=web"]);
+    }
+
+    #[test]
+    fn test_load_answers_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("answers.json");
+        fs::write(
+            &path,
+            r#"{"configs": ["a"], "vars": {"API_URL": "http://x"}}"#,
+        )
+        .unwrap();
+        let answers = load(&path).unwrap();
+        assert_eq!(answers.vars["API_URL"], "http://x");
+
+        fs::write(&path, r#"{"config": ["a"]}"#).unwrap();
+        let err = load(&path).unwrap_err().to_string();
+        assert!(err.starts_with("Invalid answers file"), "{err}");
+
+        let err = load(&dir.path().join("missing.yaml"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Failed to read answers file"), "{err}");
+    }
+}
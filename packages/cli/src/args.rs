@@ -4,9 +4,11 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use worktree_setup_operations::{Label, VerifyMode};
 
 /// CLI arguments for worktree-setup.
 #[derive(Debug, Parser)]
@@ -49,11 +51,34 @@ pub struct Args {
     #[arg(long = "no-infer-branch")]
     pub no_infer_branch: bool,
 
+    /// Directory a bare worktree name is created in (overrides
+    /// `create.target_base`).
+    ///
+    /// `worktree-setup feature-login --target-base ../wt` creates
+    /// `../wt/feature-login` on a branch named `feature-login`.
+    #[arg(long, value_name = "DIR")]
+    pub target_base: Option<PathBuf>,
+
     /// Force worktree creation even if the path is already registered.
     #[arg(long, short = 'f')]
     pub force: bool,
 
-    /// Specific config file to use (can be specified multiple times).
+    /// What to do when the target directory exists but is not a worktree
+    /// of this repository. Interactive runs ask; non-interactive runs
+    /// default to `files-only` with a warning.
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub existing_target: Option<ExistingTarget>,
+
+    /// Clone every file of an existing worktree (branch name or path)
+    /// into the new one instead of checking files out.
+    ///
+    /// Files are reflinked where the filesystem supports copy-on-write
+    /// (APFS, Btrfs), so dependencies and build caches come along almost
+    /// for free. Tracked files are then reset to the new worktree's HEAD.
+    #[arg(long, value_name = "WORKTREE")]
+    pub clone_from: Option<String>,
+
+    /// Config to use: path, directory, name, or `glob:` pattern (repeatable).
     #[arg(long = "config", short = 'c')]
     pub configs: Vec<String>,
 
@@ -65,6 +90,21 @@ pub struct Args {
     #[arg(long = "no-install")]
     pub no_install: bool,
 
+    /// Print post-setup commands instead of running them.
+    #[arg(long)]
+    pub print_commands: bool,
+
+    /// Hide post-setup command output, printing a pass/fail line per
+    /// command and the last `LINES` lines (default 20) of failing ones.
+    #[arg(
+        long,
+        value_name = "LINES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20"
+    )]
+    pub quiet_post_setup: Option<usize>,
+
     /// Copy unstaged and untracked files from main worktree.
     #[arg(long)]
     pub unstaged: bool,
@@ -73,21 +113,232 @@ pub struct Args {
     #[arg(long = "no-unstaged")]
     pub no_unstaged: bool,
 
+    /// Also apply configs found inside submodules.
+    ///
+    /// Submodules are initialized in the new worktree before file
+    /// operations run.
+    #[arg(long)]
+    pub submodules: bool,
+
     /// List discovered configs and exit.
     #[arg(long)]
     pub list: bool,
 
+    /// With `--list`, print the configs as a JSON document with their
+    /// metadata, format, and whether they load.
+    #[arg(long, requires = "list", conflicts_with = "paths_only")]
+    pub json: bool,
+
+    /// With `--list`, print only the config paths, one per line.
+    #[arg(long, requires = "list")]
+    pub paths_only: bool,
+
+    /// With `--list`, only list configs with this tag (repeatable; any
+    /// listed tag matches).
+    #[arg(long = "tag", value_name = "TAG", requires = "list")]
+    pub tags: Vec<String>,
+
+    /// With `--list`, only list configs under this directory, relative to
+    /// the repository root (repeatable).
+    #[arg(long = "dir", value_name = "DIR", requires = "list")]
+    pub dirs: Vec<PathBuf>,
+
     /// Run without prompts (requires target-path).
     #[arg(long)]
     pub non_interactive: bool,
 
+    /// Skip the confirmation for copies larger than `confirm_above`.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Replace files edited in the target worktree since the last setup
+    /// without asking. Otherwise interactive runs ask per config and
+    /// non-interactive runs skip them.
+    #[arg(long)]
+    pub force_destructive: bool,
+
+    /// Print how long each phase and the slowest file operations took.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Delete everything this run created if any file operation fails.
+    #[arg(long)]
+    pub transactional: bool,
+
+    /// Keep going when a file operation fails, then offer to retry the
+    /// failed ones.
+    #[arg(long, conflicts_with = "transactional")]
+    pub continue_on_error: bool,
+
+    /// Hash copied files against their sources: `full` (default) or
+    /// `sample:<percent>%`.
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "full"
+    )]
+    pub verify: Option<VerifyMode>,
+
+    /// How to show progress: `bar`, `plain` lines, or `none` (detected
+    /// from the terminal by default).
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with = "no_progress")]
+    pub progress: Option<ProgressMode>,
+
     /// Disable progress bars (useful for CI environments).
     #[arg(long = "no-progress")]
     pub no_progress: bool,
 
+    /// Rescan every directory instead of reusing cached file counts.
+    #[arg(long = "no-count-cache")]
+    pub no_count_cache: bool,
+
+    /// Run even if the planned copies exceed free space on the target.
+    #[arg(long)]
+    pub skip_space_check: bool,
+
+    /// Copy at most this many levels below each copied directory, unless
+    /// its entry sets `maxDepth`.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Copy files a directory copy may not read (e.g. root-owned) with
+    /// `sudo` instead of failing.
+    #[arg(long)]
+    pub sudo_fallback: bool,
+
+    /// Fail if a config entry's source does not exist in the main worktree,
+    /// instead of skipping it. Combine with `--dry-run` to check configs in
+    /// CI without copying anything.
+    #[arg(long, conflicts_with = "resume")]
+    pub check_sources: bool,
+
+    /// Finish an interrupted run on the target worktree, skipping the
+    /// operations it completed instead of planning again.
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Attach a `key=value` label to the worktree, such as
+    /// `ticket=ABC-123` (repeatable).
+    #[arg(long = "label", value_name = "KEY=VALUE")]
+    pub labels: Vec<Label>,
+
+    /// Read config selection, worktree options, template variables, and
+    /// prompt responses from a YAML or JSON file, and run without prompts.
+    #[arg(long, value_name = "FILE")]
+    pub answers: Option<PathBuf>,
+
     /// Enable verbose output.
     #[arg(long, short = 'v')]
     pub verbose: bool,
+
+    /// Also emit warnings and errors as CI annotations.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub output: OutputFormat,
+
+    /// How to render output: colored text, tab-separated records, JSON
+    /// events, or nothing but errors.
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub format: ReportFormat,
+
+    /// Read base configs named by URL in `extends` from the cache instead
+    /// of fetching them.
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Fail to load configs with unknown keys instead of warning about
+    /// them.
+    #[arg(long, global = true)]
+    pub strict_config: bool,
+
+    /// Operate on the repository containing this path instead of the one
+    /// containing the current directory.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub repo: Option<PathBuf>,
+
+    /// Also stream events and copy progress as JSON lines to this Unix
+    /// socket, FIFO, or file (e.g. `/dev/fd/3`).
+    #[arg(long, global = true, value_name = "PATH")]
+    pub progress_socket: Option<PathBuf>,
+
+    /// Add setup counters (runs, failures, bytes copied, duration) to this
+    /// Prometheus textfile.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub metrics_file: Option<PathBuf>,
+}
+
+/// Output backend selected with `--format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Colored terminal output.
+    #[default]
+    Human,
+    /// Stable tab-separated records for scripts.
+    Porcelain,
+    /// One JSON event per line.
+    Json,
+    /// Errors only.
+    Silent,
+}
+
+/// Progress display selected with `--progress`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Animated progress bars.
+    #[default]
+    Bar,
+    /// Periodic `copied N/M files (P%)` lines, readable over SSH, on dumb
+    /// terminals, and in logs.
+    Plain,
+    /// No progress output.
+    #[value(name = "none")]
+    Hidden,
+}
+
+impl ProgressMode {
+    /// Resolve `--progress` and `--no-progress`.
+    ///
+    /// Without either flag, uses plain lines when `TERM` is `dumb` or unset
+    /// or stderr is not a terminal, and bars otherwise.
+    #[must_use]
+    pub fn resolve(progress: Option<Self>, no_progress: bool) -> Self {
+        if no_progress {
+            return Self::Hidden;
+        }
+        progress.unwrap_or_else(|| {
+            let dumb = std::env::var("TERM").map_or(true, |term| term == "dumb");
+            if dumb || !std::io::stderr().is_terminal() {
+                Self::Plain
+            } else {
+                Self::Bar
+            }
+        })
+    }
+}
+
+/// How to handle a target directory that exists but is not a worktree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExistingTarget {
+    /// Register the directory as a worktree, keeping its files.
+    Adopt,
+    /// Stop without touching the directory.
+    Abort,
+    /// Apply the file operations to the directory as it is.
+    FilesOnly,
+}
+
+/// CI annotation format for warnings and errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Terminal output only.
+    #[default]
+    Human,
+    /// GitHub Actions workflow commands (`::warning::`, `::error::`).
+    Github,
+    /// GitLab Code Quality report (`gl-code-quality-report.json`).
+    Gitlab,
 }
 
 /// Subcommands for worktree-setup.
@@ -114,6 +365,125 @@ pub enum Command {
     /// worktree. When run from the main worktree (no path given), opens
     /// an interactive multi-select picker.
     Remove(RemoveArgs),
+
+    /// Remove old worktrees that are merged and idle.
+    ///
+    /// Removes linked worktrees with no uncommitted changes whose last
+    /// commit is older than the idle limit (14 days by default) and whose
+    /// commits are all merged into the base branch, after confirmation.
+    /// The policy is set in the `[gc]` section of the global config.
+    Gc(GcArgs),
+
+    /// Show what setup recorded for a worktree.
+    ///
+    /// Reads the manifest written by file operations. With `--changes`,
+    /// lists files modified in the worktree since setup, files whose
+    /// main-worktree sources changed since they were copied, and config
+    /// entries not yet applied.
+    Status(StatusArgs),
+
+    /// Show everything known about one worktree.
+    ///
+    /// Prints the worktree's branch, its main worktree, the configs applied
+    /// to it, the symlinks setup created (flagging broken ones), and when
+    /// file operations last ran.
+    Info(InfoArgs),
+
+    /// Explain what setup does with one path in a worktree.
+    ///
+    /// Lists the config entries covering the path, what each would do on
+    /// the next setup run, and whether the last run wrote the path or
+    /// skipped it and why.
+    Explain(ExplainArgs),
+
+    /// List the repository's worktrees with their labels.
+    ///
+    /// Shows each worktree's branch, the labels attached with `--label`,
+    /// and when setup last ran in it. `--label` keeps only worktrees
+    /// carrying every given label.
+    Worktrees(WorktreesArgs),
+
+    /// Generate a draft config from a hand-configured worktree.
+    ///
+    /// Compares the worktree's untracked and ignored paths with the main
+    /// worktree: symlinks into the main worktree become `symlinks` entries
+    /// and paths that also exist in the main worktree become `copy`
+    /// entries. Prints the draft `worktree.config.toml` to stdout.
+    Capture(CaptureArgs),
+
+    /// Package the files setup adds to a worktree into an archive.
+    ///
+    /// Runs the copies, templates, and unstaged-file copies of the selected
+    /// configs (all of them by default) into an empty directory, leaving
+    /// out symlinks, and archives the result with `tar`, so the overlay can
+    /// be unpacked over a checkout on machines without the main worktree.
+    Export(ExportArgs),
+
+    /// Apply an overlay archive written by `export` to a worktree.
+    ///
+    /// Unpacks the archive and copies each of its files into the worktree
+    /// (the current directory by default), skipping files that already
+    /// exist unless `--overwrite` is given, and records them in the setup
+    /// manifest like a setup run.
+    Import(ImportArgs),
+
+    /// Suggest config entries for common untracked files.
+    ///
+    /// Scans the main worktree for ignored `.env*` files, dependency and
+    /// large cache directories, and local override files that no config
+    /// covers yet, and prints suggested `copy`/`symlinks` entries.
+    Suggest(SuggestArgs),
+
+    /// Serve discovery, planning, and execution over JSON-RPC.
+    ///
+    /// Reads newline-delimited JSON-RPC 2.0 requests from stdin (or a unix
+    /// socket with `--socket`) so editor integrations can drive the tool
+    /// without re-spawning it for every action.
+    Serve(ServeArgs),
+
+    /// Run a command in every worktree.
+    ///
+    /// Runs the command after `--` in each worktree of the repository (or
+    /// the ones selected with `--worktree`) in parallel, prefixing every
+    /// output line with the worktree's branch. Exits non-zero if the
+    /// command fails in any worktree.
+    Exec(ExecArgs),
+
+    /// Re-run setup when a worktree switches branches.
+    ///
+    /// Installs a `post-checkout` hook (shared by every worktree of the
+    /// repository) that runs `worktree-setup setup` non-interactively after
+    /// each branch checkout in a linked worktree. An existing hook is kept
+    /// and run first; `--uninstall` puts it back.
+    InstallHook(InstallHookArgs),
+
+    /// Manage cached data.
+    ///
+    /// Caches live in `$XDG_CACHE_HOME/worktree-setup` (or the directory
+    /// in `WORKTREE_SETUP_CACHE_DIR`) and are rebuilt when missing.
+    Cache(CacheArgs),
+
+    /// Work with multi-repo workspaces.
+    ///
+    /// Workspaces are defined in `~/.config/worktree-setup/workspaces.toml`
+    /// and group related repositories that branch in lockstep.
+    Workspace(WorkspaceArgs),
+
+    /// Keep a pool of worktrees set up ahead of time.
+    ///
+    /// `pool ensure --size 5` creates and sets up worktrees until five are
+    /// ready, and `pool claim` hands one out, printing its path, and
+    /// creates a replacement in the background. Meant for agents and CI
+    /// jobs that start worktrees constantly.
+    Pool(PoolArgs),
+
+    /// Print the JSON Schema of the config format.
+    ///
+    /// Point an editor at the output (e.g. with a `#:schema` comment at
+    /// the top of `worktree.config.toml` for Taplo) to get completion and
+    /// validation of config keys. `--typescript` prints the same types as
+    /// TypeScript declarations for `worktree.config.ts` files.
+    Schema(SchemaArgs),
 }
 
 /// Arguments for the `setup` subcommand.
@@ -124,7 +494,7 @@ pub struct SetupArgs {
     #[arg(index = 1)]
     pub target_path: Option<PathBuf>,
 
-    /// Specific config file to use (can be specified multiple times).
+    /// Config to use: path, directory, name, or `glob:` pattern (repeatable).
     #[arg(long = "config", short = 'c')]
     pub configs: Vec<String>,
 
@@ -140,6 +510,34 @@ pub struct SetupArgs {
     #[arg(long = "no-install")]
     pub no_install: bool,
 
+    /// Skip file operations and only run the post-setup commands of the
+    /// selected configs, e.g. to reinstall dependencies after a pull.
+    #[arg(
+        long,
+        conflicts_with_all = ["no_files", "no_install", "overwrite", "resume", "check_sources"]
+    )]
+    pub post_setup_only: bool,
+
+    /// Print post-setup commands instead of running them.
+    #[arg(long)]
+    pub print_commands: bool,
+
+    /// Hide post-setup command output, printing a pass/fail line per
+    /// command and the last `LINES` lines (default 20) of failing ones.
+    #[arg(
+        long,
+        value_name = "LINES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20"
+    )]
+    pub quiet_post_setup: Option<usize>,
+
+    /// Show the file operations and post-setup commands setup would run,
+    /// without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
     /// Default overwrite existing files checkbox to checked.
     #[arg(long)]
     pub overwrite: bool,
@@ -152,14 +550,98 @@ pub struct SetupArgs {
     #[arg(long = "no-unstaged")]
     pub no_unstaged: bool,
 
+    /// Also apply configs found inside submodules.
+    ///
+    /// Submodules are initialized in the target before file operations run.
+    #[arg(long)]
+    pub submodules: bool,
+
     /// Run without prompts, using defaults (respecting flags).
     #[arg(long)]
     pub non_interactive: bool,
 
+    /// Skip the confirmation for copies larger than `confirm_above`.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Replace files edited in the target worktree since the last setup
+    /// without asking. Otherwise interactive runs ask per config and
+    /// non-interactive runs skip them.
+    #[arg(long)]
+    pub force_destructive: bool,
+
+    /// Print how long each phase and the slowest file operations took.
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Delete everything this run created if any file operation fails.
+    #[arg(long)]
+    pub transactional: bool,
+
+    /// Keep going when a file operation fails, then offer to retry the
+    /// failed ones.
+    #[arg(long, conflicts_with = "transactional")]
+    pub continue_on_error: bool,
+
+    /// Hash copied files against their sources: `full` (default) or
+    /// `sample:<percent>%`.
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "full"
+    )]
+    pub verify: Option<VerifyMode>,
+
+    /// How to show progress: `bar`, `plain` lines, or `none` (detected
+    /// from the terminal by default).
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with = "no_progress")]
+    pub progress: Option<ProgressMode>,
+
     /// Disable progress bars (useful for CI environments).
     #[arg(long = "no-progress")]
     pub no_progress: bool,
 
+    /// Rescan every directory instead of reusing cached file counts.
+    #[arg(long = "no-count-cache")]
+    pub no_count_cache: bool,
+
+    /// Run even if the planned copies exceed free space on the target.
+    #[arg(long)]
+    pub skip_space_check: bool,
+
+    /// Copy at most this many levels below each copied directory, unless
+    /// its entry sets `maxDepth`.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Copy files a directory copy may not read (e.g. root-owned) with
+    /// `sudo` instead of failing.
+    #[arg(long)]
+    pub sudo_fallback: bool,
+
+    /// Fail if a config entry's source does not exist in the main worktree,
+    /// instead of skipping it. Combine with `--dry-run` to check configs in
+    /// CI without copying anything.
+    #[arg(long, conflicts_with = "resume")]
+    pub check_sources: bool,
+
+    /// Finish an interrupted run on the target worktree, skipping the
+    /// operations it completed instead of planning again.
+    #[arg(long, conflicts_with = "dry_run")]
+    pub resume: bool,
+
+    /// Attach a `key=value` label to the worktree, such as
+    /// `ticket=ABC-123` (repeatable).
+    #[arg(long = "label", value_name = "KEY=VALUE")]
+    pub labels: Vec<Label>,
+
+    /// Read config selection, worktree options, template variables, and
+    /// prompt responses from a YAML or JSON file, and run without prompts.
+    #[arg(long, value_name = "FILE")]
+    pub answers: Option<PathBuf>,
+
     /// Enable verbose output.
     #[arg(long, short = 'v')]
     pub verbose: bool,
@@ -181,10 +663,10 @@ impl Args {
         }
     }
 
-    /// Determine if we should show progress bars.
+    /// Determine how to show progress.
     #[must_use]
-    pub const fn should_show_progress(&self) -> bool {
-        !self.no_progress
+    pub fn progress_mode(&self) -> ProgressMode {
+        ProgressMode::resolve(self.progress, self.no_progress)
     }
 }
 
@@ -204,10 +686,10 @@ impl SetupArgs {
         }
     }
 
-    /// Determine if we should show progress bars.
+    /// Determine how to show progress.
     #[must_use]
-    pub const fn should_show_progress(&self) -> bool {
-        !self.no_progress
+    pub fn progress_mode(&self) -> ProgressMode {
+        ProgressMode::resolve(self.progress, self.no_progress)
     }
 }
 
@@ -219,7 +701,7 @@ pub struct CleanArgs {
     #[arg(index = 1)]
     pub target_path: Option<PathBuf>,
 
-    /// Specific config file to use (can be specified multiple times).
+    /// Config to use: path, directory, name, or `glob:` pattern (repeatable).
     #[arg(long = "config", short = 'c')]
     pub configs: Vec<String>,
 
@@ -299,3 +781,400 @@ pub struct RemoveArgs {
     #[arg(long, short = 'v')]
     pub verbose: bool,
 }
+
+/// Arguments for the `gc` subcommand.
+#[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct GcArgs {
+    /// Only remove worktrees without commits or other activity for at
+    /// least this many days (overrides `gc.idle_days`).
+    #[arg(long, value_name = "DAYS")]
+    pub idle_days: Option<u64>,
+
+    /// Branch worktrees must be merged into (overrides `gc.base`).
+    #[arg(long, value_name = "BRANCH")]
+    pub base: Option<String>,
+
+    /// Also remove idle worktrees with commits not merged into the base.
+    #[arg(long)]
+    pub include_unmerged: bool,
+
+    /// Skip the confirmation prompt.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Show which worktrees would be removed without removing them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Run without prompts (requires --yes or --dry-run).
+    #[arg(long)]
+    pub non_interactive: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `status` subcommand.
+#[derive(Debug, Parser)]
+pub struct StatusArgs {
+    /// Path to the worktree (defaults to current directory).
+    #[arg(index = 1)]
+    pub target_path: Option<PathBuf>,
+
+    /// List changes since the last setup.
+    #[arg(long)]
+    pub changes: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `info` subcommand.
+#[derive(Debug, Parser)]
+pub struct InfoArgs {
+    /// Path to the worktree (defaults to current directory).
+    #[arg(index = 1)]
+    pub target_path: Option<PathBuf>,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `explain` subcommand.
+#[derive(Debug, Parser)]
+pub struct ExplainArgs {
+    /// Path inside a worktree, such as `apps/web/.env`.
+    #[arg(index = 1)]
+    pub path: PathBuf,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `worktrees` subcommand.
+#[derive(Debug, Parser)]
+pub struct WorktreesArgs {
+    /// Only list worktrees with this `key=value` label (repeatable).
+    #[arg(long = "label", value_name = "KEY=VALUE")]
+    pub labels: Vec<Label>,
+
+    /// Print the worktrees as JSON.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `install-hook` subcommand.
+#[derive(Debug, Parser)]
+pub struct InstallHookArgs {
+    /// Path inside the repository (defaults to current directory).
+    #[arg(index = 1)]
+    pub target_path: Option<PathBuf>,
+
+    /// Remove the hook, restoring any hook it replaced.
+    #[arg(long)]
+    pub uninstall: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `capture` subcommand.
+#[derive(Debug, Parser)]
+pub struct CaptureArgs {
+    /// Path to the configured worktree (defaults to current directory).
+    #[arg(index = 1)]
+    pub path: Option<PathBuf>,
+
+    /// Write the draft to `worktree.config.toml` in the main worktree
+    /// instead of printing it (fails if that file exists).
+    #[arg(long)]
+    pub write: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `export` subcommand.
+#[derive(Debug, Parser)]
+pub struct ExportArgs {
+    /// Path inside the repository (defaults to current directory).
+    #[arg(index = 1)]
+    pub path: Option<PathBuf>,
+
+    /// Archive to write, compressed according to its extension (such as
+    /// `.tar.zst` or `.tar.gz`). Named `--archive` because the global
+    /// `--output` selects CI annotations.
+    #[arg(long, short = 'a', value_name = "FILE")]
+    pub archive: PathBuf,
+
+    /// Config to export: path, directory, name, or `glob:` pattern (repeatable).
+    #[arg(long = "config", short = 'c')]
+    pub configs: Vec<String>,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `import` subcommand.
+#[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ImportArgs {
+    /// Overlay archive written by `export`.
+    #[arg(index = 1)]
+    pub archive: PathBuf,
+
+    /// Path to the target directory (defaults to current directory).
+    #[arg(index = 2)]
+    pub target_path: Option<PathBuf>,
+
+    /// Overwrite files that already exist.
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// Show what would be copied, changing nothing.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// How to show progress: `bar`, `plain` lines, or `none` (detected
+    /// from the terminal by default).
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with = "no_progress")]
+    pub progress: Option<ProgressMode>,
+
+    /// Disable progress bars (useful for CI environments).
+    #[arg(long = "no-progress")]
+    pub no_progress: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+impl ImportArgs {
+    /// Determine how to show progress.
+    #[must_use]
+    pub fn progress_mode(&self) -> ProgressMode {
+        ProgressMode::resolve(self.progress, self.no_progress)
+    }
+}
+
+/// Arguments for the `suggest` subcommand.
+#[derive(Debug, Parser)]
+pub struct SuggestArgs {
+    /// Path inside the repository (defaults to current directory).
+    #[arg(index = 1)]
+    pub path: Option<PathBuf>,
+
+    /// Append accepted suggestions to the root `worktree.config.toml`.
+    #[arg(long)]
+    pub apply: bool,
+
+    /// With `--apply`, accept every suggestion without prompting.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `serve` subcommand.
+#[derive(Debug, Parser)]
+pub struct ServeArgs {
+    /// Listen on this unix socket instead of stdio.
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+
+    /// Enable verbose output (logged to stderr).
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `exec` subcommand.
+#[derive(Debug, Parser)]
+pub struct ExecArgs {
+    /// Only run in worktrees whose branch or path matches (repeatable).
+    #[arg(long = "worktree", short = 'w', value_name = "PATTERN")]
+    pub worktrees: Vec<String>,
+
+    /// Skip the main worktree.
+    #[arg(long)]
+    pub skip_main: bool,
+
+    /// Number of worktrees to run in at once (defaults to all).
+    #[arg(long, short = 'j', value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+
+    /// Command to run; a single argument is run through `sh -c`.
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+/// Arguments for the `cache` subcommand.
+#[derive(Debug, Parser)]
+pub struct CacheArgs {
+    /// Cache action to run.
+    #[command(subcommand)]
+    pub command: CacheCommand,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+}
+
+/// Actions for the `cache` subcommand.
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Delete the current repository's caches.
+    Clear(CacheClearArgs),
+
+    /// Print the current repository's cache directory.
+    Dir,
+}
+
+/// Arguments for `cache clear`.
+#[derive(Debug, Parser)]
+pub struct CacheClearArgs {
+    /// Delete the caches of every repository.
+    #[arg(long)]
+    pub all: bool,
+}
+
+/// Arguments for the `workspace` subcommand.
+#[derive(Debug, Parser)]
+pub struct WorkspaceArgs {
+    /// Workspace action to run.
+    #[command(subcommand)]
+    pub command: WorkspaceCommand,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+}
+
+/// Actions for the `workspace` subcommand.
+#[derive(Debug, Subcommand)]
+pub enum WorkspaceCommand {
+    /// Create a worktree in every repo of a workspace and apply its configs.
+    Create(WorkspaceCreateArgs),
+
+    /// List defined workspaces.
+    List,
+}
+
+/// Arguments for `workspace create`.
+#[derive(Debug, Parser)]
+pub struct WorkspaceCreateArgs {
+    /// Name of the worktrees and of the branch created in each repo.
+    #[arg(index = 1)]
+    pub name: String,
+
+    /// Workspace to use (optional when only one is defined).
+    #[arg(long, short = 'w')]
+    pub workspace: Option<String>,
+
+    /// Skip post-setup commands.
+    #[arg(long = "no-install")]
+    pub no_install: bool,
+
+    /// How to show progress: `bar`, `plain` lines, or `none` (detected
+    /// from the terminal by default).
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with = "no_progress")]
+    pub progress: Option<ProgressMode>,
+
+    /// Disable progress bars (useful for CI environments).
+    #[arg(long = "no-progress")]
+    pub no_progress: bool,
+
+    /// Skip the confirmation for copies larger than `confirm_above`.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+/// Arguments for the `schema` subcommand.
+#[derive(Debug, Parser)]
+pub struct SchemaArgs {
+    /// Print TypeScript declarations of the config types and the
+    /// `defineConfig` helper (a `.d.ts` file) instead.
+    #[arg(long)]
+    pub typescript: bool,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+}
+
+/// Arguments for the `pool` subcommand.
+#[derive(Debug, Parser)]
+pub struct PoolArgs {
+    /// Pool action to run.
+    #[command(subcommand)]
+    pub command: PoolCommand,
+
+    /// Enable verbose output.
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+}
+
+/// Actions for the `pool` subcommand.
+#[derive(Debug, Subcommand)]
+pub enum PoolCommand {
+    /// Create and set up worktrees until the pool holds `--size` of them.
+    Ensure(PoolEnsureArgs),
+
+    /// Take a ready worktree from the pool and print its path.
+    Claim(PoolClaimArgs),
+}
+
+/// Arguments for `pool ensure`.
+#[derive(Debug, Parser)]
+pub struct PoolEnsureArgs {
+    /// Number of worktrees to keep in the pool.
+    #[arg(long, value_name = "N")]
+    pub size: usize,
+
+    /// Config to use: path, directory, name, or `glob:` pattern (repeatable).
+    #[arg(long = "config", short = 'c')]
+    pub configs: Vec<String>,
+
+    /// Use a named profile for config selection and defaults (repeatable).
+    #[arg(long)]
+    pub profile: Vec<String>,
+
+    /// Directory to create pool worktrees in (defaults to
+    /// `create.target_base`, or next to the main worktree).
+    #[arg(long, value_name = "DIR")]
+    pub target_base: Option<PathBuf>,
+
+    /// Skip running post-setup commands.
+    #[arg(long = "no-install")]
+    pub no_install: bool,
+}
+
+/// Arguments for `pool claim`.
+#[derive(Debug, Parser)]
+pub struct PoolClaimArgs {
+    /// Attach a `key=value` label to the claimed worktree, such as
+    /// `owner=agent-7` (repeatable).
+    #[arg(long = "label", value_name = "KEY=VALUE")]
+    pub labels: Vec<Label>,
+
+    /// Do not create a replacement worktree.
+    #[arg(long)]
+    pub no_replace: bool,
+}
@@ -0,0 +1,87 @@
+//! Cache subcommand.
+//!
+//! Caches (directory counts and fetched base configs) live under
+//! `$XDG_CACHE_HOME` and can be deleted at any time; they are rebuilt on
+//! the next run.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use colored::Colorize;
+use worktree_setup_git::discover_repo;
+use worktree_setup_operations::{
+    COUNT_CACHE_FILE, cache_root, repository_cache_dir, repository_data_dir,
+};
+
+use crate::args::{CacheArgs, CacheCommand};
+use crate::output;
+use crate::reporter::say;
+
+/// Run the `cache` subcommand.
+pub fn run(args: &CacheArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match &args.command {
+        CacheCommand::Dir => {
+            let dir = match discover_repo(&crate::start_dir(&env::current_dir()?)) {
+                Ok(repo) => repository_cache_dir(&repo),
+                Err(_) => cache_root().ok_or("Could not determine the cache directory")?,
+            };
+            say!("{}", dir.display());
+            Ok(())
+        }
+        CacheCommand::Clear(clear_args) if clear_args.all => {
+            let root = cache_root().ok_or("Could not determine the cache directory")?;
+            if remove_dir(&root)? {
+                say!("{} Cleared {}", "✓".green(), root.display());
+            } else {
+                output::print_info("Nothing is cached.");
+            }
+            Ok(())
+        }
+        CacheCommand::Clear(_) => clear_repository(),
+    }
+}
+
+/// Delete the caches of the repository containing the current directory.
+fn clear_repository() -> Result<(), Box<dyn std::error::Error>> {
+    let repo = discover_repo(&crate::start_dir(&env::current_dir()?))?;
+
+    // Without a cache root, caches share the state directory, so only the
+    // cache files are removed. Older versions kept the count cache there
+    // too, so it goes either way.
+    let mut cleared = remove_file(&repository_data_dir(&repo).join(COUNT_CACHE_FILE))?;
+    let dir = repository_cache_dir(&repo);
+    if cache_root().is_some() {
+        cleared |= remove_dir(&dir)?;
+    }
+
+    if cleared {
+        say!("{} Cleared {}", "✓".green(), dir.display());
+    } else {
+        output::print_info("Nothing is cached for this repository.");
+    }
+    Ok(())
+}
+
+/// Remove `dir` and everything in it, returning whether it existed.
+fn remove_dir(dir: &Path) -> io::Result<bool> {
+    match fs::remove_dir_all(dir) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Remove the file at `path`, returning whether it existed.
+fn remove_file(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
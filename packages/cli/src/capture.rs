@@ -0,0 +1,207 @@
+//! Capture subcommand.
+//!
+//! Turns a worktree that was set up by hand into a draft config: symlinks
+//! that point into the main worktree become `symlinks` entries, and
+//! untracked or ignored paths that also exist in the main worktree become
+//! `copy` entries.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use worktree_setup_git::{
+    UntrackedPath, discover_repo, get_main_worktree, get_untracked_and_ignored_paths,
+};
+
+use crate::args::CaptureArgs;
+use crate::output;
+
+/// Name of the config file written by `--write`.
+const CONFIG_FILE: &str = "worktree.config.toml";
+
+/// Entries captured from a worktree.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Captured {
+    /// Paths symlinked into the main worktree.
+    symlinks: Vec<String>,
+    /// Paths copied from the main worktree.
+    copy: Vec<String>,
+}
+
+/// Run the `capture` subcommand.
+pub fn run(args: &CaptureArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let target = crate::resolve_setup_target(&cwd, args.path.as_ref());
+
+    let repo = discover_repo(&target)?;
+    let main_worktree = get_main_worktree(&repo)?.path;
+    if same_path(&main_worktree, &target) {
+        return Err("capture needs a linked worktree, not the main worktree".into());
+    }
+
+    let paths = get_untracked_and_ignored_paths(&repo)?;
+    let captured = capture(&main_worktree, &target, &paths);
+    let name = target.file_name().map_or_else(
+        || target.display().to_string(),
+        |n| n.to_string_lossy().to_string(),
+    );
+    let draft = render_config(&name, &captured);
+
+    if !args.write {
+        print!("{draft}");
+        return Ok(());
+    }
+
+    let path = main_worktree.join(CONFIG_FILE);
+    if path.exists() {
+        return Err(format!(
+            "{} already exists; run without --write and merge the draft by hand",
+            path.display()
+        )
+        .into());
+    }
+    std::fs::write(&path, draft)?;
+    output::print_info(&format!(
+        "Wrote {} ({} symlinks, {} copies)",
+        path.display(),
+        captured.symlinks.len(),
+        captured.copy.len()
+    ));
+    Ok(())
+}
+
+/// Classify the untracked and ignored `paths` of `target`.
+///
+/// Paths that exist only in the worktree are left out: they were created
+/// there (e.g. build output) rather than brought over from the main
+/// worktree.
+fn capture(main_worktree: &Path, target: &Path, paths: &[UntrackedPath]) -> Captured {
+    let mut captured = Captured::default();
+
+    for untracked in paths {
+        let target_path = target.join(&untracked.path);
+        let main_path = main_worktree.join(&untracked.path);
+
+        let Ok(meta) = target_path.symlink_metadata() else {
+            continue;
+        };
+        if meta.file_type().is_symlink() {
+            if points_into(&target_path, main_worktree) {
+                captured.symlinks.push(untracked.path.clone());
+            }
+        } else if main_path.exists() {
+            captured.copy.push(untracked.path.clone());
+        }
+    }
+
+    captured
+}
+
+/// Whether the symlink at `link` resolves to a path inside `dir`.
+fn points_into(link: &Path, dir: &Path) -> bool {
+    match (link.canonicalize(), dir.canonicalize()) {
+        (Ok(resolved), Ok(dir)) => resolved.starts_with(dir),
+        _ => false,
+    }
+}
+
+/// Whether `a` and `b` refer to the same directory.
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Render `captured` as a draft `worktree.config.toml`.
+fn render_config(name: &str, captured: &Captured) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# Draft generated by `worktree-setup capture` from `{name}`."
+    );
+    let _ = writeln!(out, "# Review the entries before committing this file.");
+    let _ = writeln!(
+        out,
+        "description = {}",
+        toml_string(&format!("Captured from {name}"))
+    );
+
+    for (key, entries) in [("symlinks", &captured.symlinks), ("copy", &captured.copy)] {
+        if entries.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n{key} = [");
+        for entry in entries {
+            let _ = writeln!(out, "    {},", toml_string(entry));
+        }
+        let _ = writeln!(out, "]");
+    }
+
+    out
+}
+
+/// Quote `value` as a TOML basic string.
+pub fn toml_string(value: &str) -> String {
+    // JSON string escapes are a subset of TOML basic string escapes.
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{value}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn untracked(path: &str) -> UntrackedPath {
+        UntrackedPath {
+            path: path.to_string(),
+            ignored: true,
+            is_dir: false,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_classifies_symlinks_and_copies() {
+        let main = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+
+        std::fs::create_dir(main.path().join("node_modules")).unwrap();
+        std::fs::write(main.path().join(".env"), "A=1").unwrap();
+        std::os::unix::fs::symlink(
+            main.path().join("node_modules"),
+            target.path().join("node_modules"),
+        )
+        .unwrap();
+        std::fs::write(target.path().join(".env"), "A=1").unwrap();
+        std::fs::write(target.path().join("build.log"), "only here").unwrap();
+
+        let captured = capture(
+            main.path(),
+            target.path(),
+            &[
+                untracked(".env"),
+                untracked("build.log"),
+                untracked("node_modules"),
+            ],
+        );
+
+        assert_eq!(
+            captured,
+            Captured {
+                symlinks: vec!["node_modules".to_string()],
+                copy: vec![".env".to_string()],
+            }
+        );
+
+        let draft = render_config("feature", &captured);
+        let parsed: worktree_setup_config::Config = toml::from_str(&draft).unwrap();
+        assert_eq!(parsed.symlinks, vec!["node_modules"]);
+        assert_eq!(parsed.copy, vec![".env"]);
+        assert_eq!(parsed.description, "Captured from feature");
+    }
+}
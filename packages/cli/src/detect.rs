@@ -0,0 +1,164 @@
+//! Package-manager detection for default post-setup commands.
+//!
+//! Configs without `postSetup` can still get an install step: lockfiles in
+//! the config directory identify the package manager, and the matching
+//! install command is offered interactively or, with
+//! `[setup] post_setup_auto = true`, run automatically.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::Path;
+
+use worktree_setup_config::{GlobalConfig, LoadedConfig};
+
+/// JavaScript lockfiles and their install commands, in order of preference.
+///
+/// Only the first match is used, since a directory has one JS package
+/// manager even when stale lockfiles from another are lying around.
+const JS_LOCKFILES: &[(&str, &str)] = &[
+    ("bun.lockb", "bun install"),
+    ("bun.lock", "bun install"),
+    ("pnpm-lock.yaml", "pnpm install"),
+    ("yarn.lock", "yarn install"),
+    ("package-lock.json", "npm install"),
+];
+
+/// Lockfiles of other ecosystems, each detected independently.
+const OTHER_LOCKFILES: &[(&str, &str)] = &[
+    ("Cargo.lock", "cargo fetch"),
+    ("poetry.lock", "poetry install"),
+];
+
+/// Install commands for the lockfiles found directly in `dir`.
+#[must_use]
+pub fn install_commands(dir: &Path) -> Vec<&'static str> {
+    let js = JS_LOCKFILES
+        .iter()
+        .find(|(lockfile, _)| dir.join(lockfile).is_file())
+        .map(|&(_, command)| command);
+    let others = OTHER_LOCKFILES
+        .iter()
+        .filter(|(lockfile, _)| dir.join(lockfile).is_file())
+        .map(|&(_, command)| command);
+    js.into_iter().chain(others).collect()
+}
+
+/// Detected install commands for the selected configs that have no
+/// `postSetup` of their own.
+///
+/// Commands for configs below the repository root `cd` into the config's
+/// directory first, since post-setup commands run at the worktree root.
+/// Detection is on by default only when `interactive` (the commands are
+/// then offered in the usual post-setup prompt); `[setup] post_setup_auto`
+/// turns it on or off explicitly.
+#[must_use]
+pub fn detected_post_setup_commands(
+    configs: &[&LoadedConfig],
+    main_worktree: &Path,
+    global_config: &GlobalConfig,
+    interactive: bool,
+) -> Vec<String> {
+    if !global_config.setup.post_setup_auto.unwrap_or(interactive) {
+        return Vec::new();
+    }
+
+    let mut commands = Vec::new();
+    for config in configs.iter().filter(|c| c.config.post_setup.is_empty()) {
+        let relative = config
+            .config_dir
+            .strip_prefix(main_worktree)
+            .unwrap_or(&config.config_dir);
+        for command in install_commands(&config.config_dir) {
+            let command = if relative.as_os_str().is_empty() {
+                command.to_string()
+            } else {
+                format!(
+                    "cd {} && {command}",
+                    shell_quote(&relative.to_string_lossy())
+                )
+            };
+            if !commands.contains(&command) {
+                commands.push(command);
+            }
+        }
+    }
+    commands
+}
+
+/// Quote `value` for `sh` unless it only contains safe characters.
+fn shell_quote(value: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    use worktree_setup_config::Config;
+
+    fn loaded(dir: &Path, post_setup: &[&str]) -> LoadedConfig {
+        LoadedConfig {
+            config: Config {
+                post_setup: post_setup.iter().map(ToString::to_string).collect(),
+                ..Default::default()
+            },
+            config_path: dir.join("worktree.config.toml"),
+            config_dir: dir.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_install_commands_prefers_one_js_manager() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("package-lock.json"), "{}").unwrap();
+        fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "").unwrap();
+
+        assert_eq!(
+            install_commands(dir.path()),
+            vec!["pnpm install", "cargo fetch"]
+        );
+    }
+
+    #[test]
+    fn test_detected_post_setup_commands() {
+        let main = TempDir::new().unwrap();
+        let web = main.path().join("apps/my web");
+        fs::create_dir_all(&web).unwrap();
+        fs::write(web.join("bun.lockb"), "").unwrap();
+        fs::write(main.path().join("poetry.lock"), "").unwrap();
+
+        let root = loaded(main.path(), &[]);
+        let app = loaded(&web, &[]);
+        let custom = loaded(main.path(), &["make setup"]);
+        let configs = [&root, &app, &custom];
+
+        let global = GlobalConfig::default();
+        assert_eq!(
+            detected_post_setup_commands(&configs, main.path(), &global, true),
+            vec![
+                "poetry install".to_string(),
+                "cd 'apps/my web' && bun install".to_string(),
+            ]
+        );
+        assert!(detected_post_setup_commands(&configs, main.path(), &global, false).is_empty());
+
+        let mut auto = GlobalConfig::default();
+        auto.setup.post_setup_auto = Some(true);
+        assert_eq!(
+            detected_post_setup_commands(&configs, main.path(), &auto, false).len(),
+            2
+        );
+    }
+}
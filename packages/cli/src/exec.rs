@@ -0,0 +1,218 @@
+//! Exec subcommand.
+//!
+//! Runs one command in every worktree of the repository (or the ones picked
+//! with `--worktree`), several at a time, with each output line prefixed by
+//! the worktree it came from.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use colored::Colorize;
+use worktree_setup_git::{WorktreeInfo, discover_repo, get_worktrees};
+
+use crate::args::ExecArgs;
+use crate::output;
+use crate::reporter::{self, say};
+
+/// Run the `exec` subcommand.
+pub fn run(args: &ExecArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let repo = discover_repo(&crate::start_dir(&cwd))?;
+    let worktrees = select_worktrees(get_worktrees(&repo)?, &args.worktrees, args.skip_main)?;
+
+    let names: Vec<String> = worktrees.iter().map(worktree_label).collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    let jobs = args.jobs.unwrap_or(worktrees.len()).max(1);
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, std::io::Result<ExitStatus>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(worktrees.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(worktree) = worktrees.get(i) else {
+                            break;
+                        };
+                        let prefix = format!("{:<width$}", names[i]);
+                        done.push((i, run_in(worktree, &args.command, &prefix)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+
+    say!();
+    let mut failed = 0usize;
+    for (i, result) in &results {
+        match result {
+            Ok(status) if status.success() => {
+                say!("{} {}", "✓".green(), names[*i]);
+            }
+            Ok(status) => {
+                failed += 1;
+                say!("{} {} ({status})", "✗".red(), names[*i]);
+            }
+            Err(e) => {
+                failed += 1;
+                say!("{} {} (failed to start: {e})", "✗".red(), names[*i]);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("Command failed in {failed} of {} worktrees", results.len()).into());
+    }
+    Ok(())
+}
+
+/// Pick the worktrees to run in.
+///
+/// With `patterns`, keeps worktrees whose branch equals a pattern or whose
+/// path contains one. Worktrees whose directory is missing are skipped.
+fn select_worktrees(
+    worktrees: Vec<WorktreeInfo>,
+    patterns: &[String],
+    skip_main: bool,
+) -> Result<Vec<WorktreeInfo>, Box<dyn std::error::Error>> {
+    let selected: Vec<WorktreeInfo> = worktrees
+        .into_iter()
+        .filter(|wt| !(skip_main && wt.is_main))
+        .filter(|wt| patterns.is_empty() || patterns.iter().any(|p| matches_pattern(wt, p)))
+        .filter(|wt| {
+            let exists = wt.path.is_dir();
+            if !exists {
+                output::print_warning(&format!("Skipping missing worktree {}", wt.path.display()));
+            }
+            exists
+        })
+        .collect();
+
+    if selected.is_empty() {
+        return Err("No worktrees matched".into());
+    }
+    Ok(selected)
+}
+
+/// Whether `pattern` names `worktree` by branch or by path.
+fn matches_pattern(worktree: &WorktreeInfo, pattern: &str) -> bool {
+    worktree.branch.as_deref() == Some(pattern) || worktree.path.to_string_lossy().contains(pattern)
+}
+
+/// Short name shown in front of a worktree's output.
+fn worktree_label(worktree: &WorktreeInfo) -> String {
+    worktree.branch.clone().unwrap_or_else(|| {
+        worktree.path.file_name().map_or_else(
+            || worktree.path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        )
+    })
+}
+
+/// Run `command` in `worktree`, prefixing every output line.
+///
+/// A single argument is run through `sh -c` (like post-setup commands), so
+/// it may contain pipes and other shell syntax; several arguments are run
+/// as a program and its arguments.
+fn run_in(
+    worktree: &WorktreeInfo,
+    command: &[String],
+    prefix: &str,
+) -> std::io::Result<ExitStatus> {
+    let mut cmd = if let [single] = command {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", single]);
+        cmd
+    } else {
+        let mut cmd = Command::new(&command[0]);
+        cmd.args(&command[1..]);
+        cmd
+    };
+
+    let mut child = cmd
+        .current_dir(&worktree.path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let colored_prefix = prefix.cyan().to_string();
+    std::thread::scope(|scope| {
+        if let Some(stderr) = stderr {
+            scope.spawn(|| {
+                forward_lines(stderr, |line| eprintln!("{colored_prefix} │ {line}"));
+            });
+        }
+        if let Some(stdout) = stdout {
+            forward_lines(stdout, |line| {
+                reporter::get().message(&format!("{colored_prefix} │ {line}"));
+            });
+        }
+    });
+
+    child.wait()
+}
+
+/// Call `emit` with every line read from `reader`.
+fn forward_lines(reader: impl Read, emit: impl Fn(&str)) {
+    for line in BufReader::new(reader).lines() {
+        match line {
+            Ok(line) => emit(&line),
+            Err(e) => {
+                log::debug!("Stopped reading command output: {e}");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn worktree(path: &str, branch: Option<&str>, is_main: bool) -> WorktreeInfo {
+        WorktreeInfo {
+            path: PathBuf::from(path),
+            is_main,
+            branch: branch.map(ToString::to_string),
+            commit: None,
+        }
+    }
+
+    #[test]
+    fn test_matches_pattern_by_branch_or_path() {
+        let wt = worktree("/src/app-feature", Some("feature/login"), false);
+        assert!(matches_pattern(&wt, "feature/login"));
+        assert!(matches_pattern(&wt, "app-feature"));
+        assert!(!matches_pattern(&wt, "login"));
+        assert!(!matches_pattern(&wt, "main"));
+    }
+
+    #[test]
+    fn test_worktree_label_falls_back_to_directory() {
+        assert_eq!(
+            worktree_label(&worktree("/src/app", Some("main"), true)),
+            "main"
+        );
+        assert_eq!(
+            worktree_label(&worktree("/src/app-detached", None, false)),
+            "app-detached"
+        );
+    }
+}
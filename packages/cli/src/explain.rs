@@ -0,0 +1,302 @@
+//! Explain subcommand.
+//!
+//! Answers "why wasn't my `.env` copied?" for one path in a worktree: the
+//! config entries covering it, what each would do on the next setup run,
+//! and what the last run did with the path according to the setup
+//! manifest.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use colored::Colorize;
+use path_clean::PathClean;
+use worktree_setup_config::{LoadedConfig, load_global_config};
+use worktree_setup_git::{WorktreeInfo, discover_repo, get_repo_root, get_worktrees};
+use worktree_setup_operations::{
+    ApplyConfigOptions, Manifest, ManifestEntryKind, OperationType, PlannedOperation, StateStore,
+    load_worktree_ignore,
+};
+
+use crate::args::ExplainArgs;
+use crate::output;
+use crate::reporter::say;
+
+/// Run the `explain` subcommand.
+///
+/// # Errors
+///
+/// * If the path is not inside a worktree of a git repository
+/// * If the setup manifest or worktree state cannot be read
+/// * If planning the configs fails
+pub fn run(args: &ExplainArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let path = cwd.join(&args.path).clean();
+    let repo = discover_repo(existing_ancestor(&path))?;
+    let repo_root = get_repo_root(&repo)?;
+    let worktrees = get_worktrees(&repo)?;
+    let (worktree, relative) = locate(&path, &worktrees)
+        .ok_or_else(|| format!("{} is not inside a worktree", path.display()))?;
+    let main_worktree = worktrees
+        .iter()
+        .find(|wt| wt.is_main)
+        .ok_or("Could not find the main worktree")?;
+    let key = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    output::print_header("Worktree Explain");
+    say!("Path:     {key}");
+    say!("Worktree: {}\n", worktree.path.display());
+    if worktree.is_main {
+        output::print_info("This is the main worktree; setup copies from it, not into it.");
+        return Ok(());
+    }
+
+    let configs = crate::load_configs_quietly(&repo_root)?;
+    let state = StateStore::for_repository(&repo).load(&worktree.path)?;
+    let manifest = Manifest::load(&Manifest::path_for(&worktree.path)?)?;
+    let global_config = load_global_config(Some(&main_worktree.path))?;
+
+    // Check the configs this worktree was set up with, or every config if
+    // setup never ran in it.
+    let selected: Vec<&LoadedConfig> = configs
+        .iter()
+        .filter(|config| {
+            state.as_ref().is_none_or(|state| {
+                state.configs.is_empty() || state.configs.contains_key(&config.relative_path)
+            })
+        })
+        .collect();
+    let worktree_ignore = Arc::new(load_worktree_ignore(&main_worktree.path, &worktree.path));
+    let mut operations = crate::plan_selected_operations(
+        &selected,
+        &main_worktree.path,
+        &worktree.path,
+        &ApplyConfigOptions {
+            worktree_ignore: Some(Arc::clone(&worktree_ignore)),
+            ..ApplyConfigOptions::default()
+        },
+        &global_config,
+        &|_, _, _| {},
+    )?;
+    crate::skip_protected_branch_ops(&mut operations, &worktree.path, &global_config.setup);
+
+    let covering: Vec<&PlannedOperation> = operations
+        .iter()
+        .filter(|op| covers(op, &worktree.path, &relative))
+        .collect();
+    if covering.is_empty() {
+        say!(
+            "{}",
+            format!(
+                "No entry in the {} config{} checked covers this path.",
+                selected.len(),
+                if selected.len() == 1 { "" } else { "s" }
+            )
+            .yellow()
+        );
+        if worktree_ignore.is_ignored(&relative, main_worktree.path.join(&relative).is_dir()) {
+            say!("It is excluded by a .worktreeignore file in the main worktree.");
+        }
+        say!();
+    }
+    for op in &covering {
+        print_entry(op, &main_worktree.path);
+    }
+
+    say!("{}", "Last run".bold());
+    for line in last_run(manifest.as_ref(), &key) {
+        say!("  {line}");
+    }
+    Ok(())
+}
+
+/// Print the entry an operation was planned from and what it would do.
+fn print_entry(op: &PlannedOperation, main_worktree: &Path) {
+    let entry = op.origin.as_ref().map_or_else(
+        || "(no config entry)".to_string(),
+        |origin| format!("{} {}[{}]", origin.config, origin.field, origin.index),
+    );
+    match op.label() {
+        Some(label) => say!("{} — {}", entry.bold(), label.cyan()),
+        None => say!("{}", entry.bold()),
+    }
+    let source = op
+        .source
+        .strip_prefix(main_worktree)
+        .unwrap_or(&op.source)
+        .display();
+    let operation = match op.operation_type {
+        OperationType::Symlink => format!("symlink to {source}"),
+        OperationType::Overwrite => format!("copy of {source}, replacing what is there"),
+        OperationType::Template if op.template.is_some() => format!("rendered from {source}"),
+        OperationType::CopyGlob => format!("copy of {source} (matched by a glob)"),
+        OperationType::Copy | OperationType::Unstaged | OperationType::Template => {
+            format!("copy of {source}")
+        }
+    };
+    say!("  Operation:  {operation}");
+    let result = crate::planned_result(op);
+    if op.will_skip {
+        say!("  Next setup: {}", format!("skip ({result})").yellow());
+        if result == "exists" {
+            say!(
+                "  {}",
+                "Run `worktree-setup setup --overwrite` to replace it.".dimmed()
+            );
+        }
+    } else {
+        say!("  Next setup: {}", result.green());
+    }
+    say!();
+}
+
+/// What setup last did with the path `key`, according to the manifest:
+/// when it was written, and whether a later run skipped it.
+fn last_run(manifest: Option<&Manifest>, key: &str) -> Vec<String> {
+    let Some(manifest) = manifest else {
+        return vec!["Setup has not run in this worktree.".to_string()];
+    };
+    let within = |recorded: &str, separator: &str| {
+        if recorded == key {
+            String::new()
+        } else {
+            format!("{separator}{recorded}")
+        }
+    };
+
+    let mut lines = Vec::new();
+    if let Some((recorded, entry)) = manifest.file_covering(key) {
+        let action = match entry.kind {
+            ManifestEntryKind::File => "copied",
+            ManifestEntryKind::Symlink => "symlinked",
+            ManifestEntryKind::Rendered => "rendered",
+            ManifestEntryKind::FromRef => "written from a git ref",
+        };
+        lines.push(format!(
+            "{action}{} (setup last ran {})",
+            within(recorded, " as part of "),
+            output::format_age(manifest.applied_at)
+        ));
+    }
+    if let Some((recorded, entry)) = manifest.skip_covering(key) {
+        lines.push(format!(
+            "skipped{}: {}, {}",
+            within(recorded, " with "),
+            entry.reason,
+            output::format_age(entry.skipped_at)
+        ));
+    }
+    if lines.is_empty() {
+        lines.push("not touched by setup".to_string());
+    }
+    lines
+}
+
+/// Whether `op` writes `relative` (a path inside `worktree`), directly or
+/// as part of a directory.
+fn covers(op: &PlannedOperation, worktree: &Path, relative: &Path) -> bool {
+    let Ok(target) = op.target.strip_prefix(worktree) else {
+        return false;
+    };
+    target == relative
+        || ((op.is_directory || op.operation_type == OperationType::Symlink)
+            && relative.starts_with(target))
+}
+
+/// The worktree containing `path` (the innermost one, for nested
+/// worktrees), and `path` relative to it.
+fn locate<'a>(path: &Path, worktrees: &'a [WorktreeInfo]) -> Option<(&'a WorktreeInfo, PathBuf)> {
+    worktrees
+        .iter()
+        .filter_map(|wt| {
+            let canonical = wt.path.canonicalize().unwrap_or_else(|_| wt.path.clone());
+            let relative = path
+                .strip_prefix(&wt.path)
+                .or_else(|_| path.strip_prefix(canonical))
+                .ok()?;
+            Some((wt, relative.to_path_buf()))
+        })
+        .min_by_key(|(_, relative)| relative.components().count())
+}
+
+/// The deepest ancestor of `path` (or `path` itself) that exists.
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(target: &str, operation_type: OperationType, is_directory: bool) -> PlannedOperation {
+        PlannedOperation {
+            display_path: target.to_string(),
+            operation_type,
+            source: PathBuf::from("/main").join(target),
+            target: PathBuf::from("/target").join(target),
+            file_count: 1,
+            symlink_count: 0,
+            byte_count: 0,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    #[test]
+    fn test_covers() {
+        let worktree = Path::new("/target");
+        let env = op("apps/web/.env", OperationType::Copy, false);
+        assert!(covers(&env, worktree, Path::new("apps/web/.env")));
+        assert!(!covers(&env, worktree, Path::new("apps/web/.env.local")));
+
+        let assets = op("assets", OperationType::Copy, true);
+        assert!(covers(&assets, worktree, Path::new("assets/img/a.png")));
+        assert!(!covers(&assets, worktree, Path::new("assets-old")));
+
+        let link = op("node_modules", OperationType::Symlink, false);
+        assert!(covers(&link, worktree, Path::new("node_modules/react")));
+    }
+
+    #[test]
+    fn test_last_run() {
+        assert_eq!(
+            last_run(None, ".env"),
+            ["Setup has not run in this worktree."]
+        );
+
+        let mut manifest = Manifest::new();
+        assert_eq!(last_run(Some(&manifest), ".env"), ["not touched by setup"]);
+
+        manifest.record_skip(
+            &op("assets", OperationType::Copy, true),
+            Path::new("/target"),
+            "exists",
+        );
+        assert_eq!(
+            last_run(Some(&manifest), "assets/a.png"),
+            ["skipped with assets: exists, just now"]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Export subcommand.
+//!
+//! Packages the files setup would add to a fresh worktree (copies,
+//! rendered templates, and unstaged files, but not symlinks) into an
+//! archive, so the overlay can be unpacked over a checkout on machines
+//! without access to the main worktree, such as CI runners.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use path_clean::PathClean;
+use worktree_setup_config::{LoadedConfig, load_global_config, select_configs_by_patterns};
+use worktree_setup_git::{discover_repo, get_main_worktree};
+use worktree_setup_operations::{
+    ApplyConfigOptions, OperationType, PlannedOperation, execute_operation, load_worktree_ignore,
+};
+
+use crate::args::ExportArgs;
+use crate::output;
+
+/// Run the `export` subcommand.
+///
+/// # Errors
+///
+/// * If the path is not inside a git repository
+/// * If a `--config` pattern matches no config
+/// * If planning or executing an operation fails
+/// * If the selected configs add no files
+/// * If the archive cannot be written
+pub fn run(args: &ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let start = args
+        .path
+        .as_ref()
+        .map_or_else(|| crate::start_dir(&cwd), |path| cwd.join(path).clean());
+    let output_path = cwd.join(&args.archive).clean();
+
+    let repo = discover_repo(&start)?;
+    let main_worktree = get_main_worktree(&repo)?.path;
+    let configs = crate::load_configs_quietly(&main_worktree)?;
+    let selected: Vec<&LoadedConfig> = if args.configs.is_empty() {
+        configs.iter().collect()
+    } else {
+        select_configs_by_patterns(&configs, &args.configs)?
+            .into_iter()
+            .map(|i| &configs[i])
+            .collect()
+    };
+    let global_config = load_global_config(Some(&main_worktree))?;
+
+    // Operations run into an empty directory, so nothing is skipped as
+    // already existing, and the directory becomes the archive's root.
+    let staging = tempfile::Builder::new()
+        .prefix("worktree-setup-export-")
+        .tempdir()?;
+    let worktree_ignore = Arc::new(load_worktree_ignore(&main_worktree, staging.path()));
+    let mut operations = crate::plan_selected_operations(
+        &selected,
+        &main_worktree,
+        staging.path(),
+        &ApplyConfigOptions {
+            worktree_ignore: Some(Arc::clone(&worktree_ignore)),
+            ..ApplyConfigOptions::default()
+        },
+        &global_config,
+        &|_, _, _| {},
+    )?;
+    if let Some(filter) = crate::unstaged_filter(&selected, None, &global_config.setup) {
+        operations.extend(crate::plan_unstaged_files(
+            &main_worktree,
+            staging.path(),
+            &worktree_ignore,
+            &filter,
+        )?);
+    }
+
+    let operations = overlay_operations(operations);
+    if operations.is_empty() {
+        return Err("Nothing to export: the selected configs add no files".into());
+    }
+    for op in &operations {
+        log::debug!("Exporting {}", op.display_path);
+        execute_operation(op, |_, _| {})?;
+    }
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_archive(staging.path(), &output_path)?;
+
+    let files: u64 = operations.iter().map(|op| op.file_count).sum();
+    output::print_info(&format!(
+        "Exported {files} file{} from {} config{} to {}",
+        if files == 1 { "" } else { "s" },
+        selected.len(),
+        if selected.len() == 1 { "" } else { "s" },
+        output_path.display()
+    ));
+    Ok(())
+}
+
+/// Keep the operations that write files into the worktree.
+///
+/// Symlinks point into the main worktree, which the overlay is meant to do
+/// without, and entry hooks are left out because they expect to run in a
+/// real worktree.
+fn overlay_operations(operations: Vec<PlannedOperation>) -> Vec<PlannedOperation> {
+    operations
+        .into_iter()
+        .filter(|op| !op.will_skip && op.operation_type != OperationType::Symlink)
+        .map(|op| PlannedOperation { hooks: None, ..op })
+        .collect()
+}
+
+/// Archive the contents of `dir` into `output` with the system `tar`
+/// command, compressed according to the extension of `output`.
+fn write_archive(dir: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let result = Command::new("tar")
+        .arg("-caf")
+        .arg(output)
+        .arg("-C")
+        .arg(dir)
+        .arg(".")
+        .output()
+        .map_err(|e| format!("Failed to run tar: {e}"))?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to write {}: {}",
+            output.display(),
+            String::from_utf8_lossy(&result.stderr).trim()
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_archive() {
+        let dir = TempDir::new().unwrap();
+        let out = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("config")).unwrap();
+        fs::write(dir.path().join("config/app.env"), "PORT=1").unwrap();
+        let archive = out.path().join("overlay.tar.gz");
+
+        write_archive(dir.path(), &archive).unwrap();
+
+        let listing = Command::new("tar")
+            .arg("-tzf")
+            .arg(&archive)
+            .output()
+            .unwrap();
+        let listing = String::from_utf8_lossy(&listing.stdout);
+        assert!(listing.contains("./config/app.env"), "{listing}");
+
+        let err = write_archive(&dir.path().join("missing"), &archive).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to write"), "{err}");
+    }
+}
@@ -0,0 +1,298 @@
+//! Gc subcommand.
+//!
+//! Removes linked worktrees that are no longer worked on: no uncommitted
+//! changes, no commits or other activity within the idle limit, and (unless
+//! `--include-unmerged`) every commit merged into the base branch. The
+//! policy comes from the `[gc]` section of the global config and can be
+//! overridden per run.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::path::Path;
+
+use colored::Colorize;
+use worktree_setup_config::{GlobalConfig, load_global_config};
+use worktree_setup_git::{
+    WorktreeActivity, WorktreeInfo, discover_repo, get_default_branch, get_repo_root,
+    get_worktrees, remove_worktree, worktree_activity,
+};
+
+use crate::args::GcArgs;
+use crate::output;
+use crate::reporter::say;
+
+/// Seconds in a day.
+const DAY: i64 = 86_400;
+
+/// Which worktrees gc removes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Policy {
+    /// Branch worktrees are compared with.
+    base: String,
+    /// Days without activity before a worktree counts as idle.
+    idle_days: u64,
+    /// Whether unmerged worktrees are kept.
+    require_merged: bool,
+}
+
+impl Policy {
+    /// Resolve the policy from the arguments, then the global config, then
+    /// the defaults.
+    fn resolve(
+        args: &GcArgs,
+        global_config: &GlobalConfig,
+        default_branch: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let base = args
+            .base
+            .clone()
+            .or_else(|| global_config.gc.base.clone())
+            .or(default_branch)
+            .ok_or("Could not determine the default branch. Pass --base or set gc.base.")?;
+        Ok(Self {
+            base,
+            idle_days: args
+                .idle_days
+                .unwrap_or_else(|| global_config.gc.idle_days()),
+            require_merged: !args.include_unmerged && global_config.gc.require_merged(),
+        })
+    }
+
+    /// Why the worktree on `branch` is kept, or `None` if it should be
+    /// removed. `now` is the current time in seconds since the Unix epoch.
+    fn keep_reason(
+        &self,
+        branch: Option<&str>,
+        activity: &WorktreeActivity,
+        now: i64,
+    ) -> Option<String> {
+        if branch == Some(self.base.as_str()) {
+            return Some("is the base branch".to_string());
+        }
+        if activity.dirty {
+            return Some("has uncommitted changes".to_string());
+        }
+        if self.require_merged && !activity.is_merged() {
+            return Some(format!(
+                "{} commit{} not in {}",
+                activity.ahead,
+                if activity.ahead == 1 { "" } else { "s" },
+                self.base
+            ));
+        }
+        let idle_days = now.saturating_sub(activity.last_active) / DAY;
+        if idle_days < i64::try_from(self.idle_days).unwrap_or(i64::MAX) {
+            return Some(format!(
+                "last active {idle_days} day{} ago",
+                if idle_days == 1 { "" } else { "s" }
+            ));
+        }
+        None
+    }
+}
+
+/// Run the `gc` subcommand.
+///
+/// # Errors
+///
+/// * If the current directory is not inside a git repository
+/// * If the base branch cannot be determined
+/// * If confirmation is needed in non-interactive mode
+/// * If a worktree cannot be removed
+pub fn run(args: &GcArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let repo = discover_repo(&crate::start_dir(&cwd))?;
+    let repo_root = get_repo_root(&repo)?;
+    let global_config = load_global_config(Some(&repo_root))?;
+    let policy = Policy::resolve(args, &global_config, get_default_branch(&repo))?;
+
+    output::print_header("Worktree GC");
+    output::print_repo_info(&repo_root.to_string_lossy());
+    say!(
+        "Removing worktrees idle for {} day{}{}\n",
+        policy.idle_days,
+        if policy.idle_days == 1 { "" } else { "s" },
+        if policy.require_merged {
+            format!(" and merged into {}", policy.base)
+        } else {
+            String::new()
+        }
+    );
+
+    let candidates = select_candidates(&policy, &get_worktrees(&repo)?, &cwd);
+    if candidates.is_empty() {
+        say!("\n{}", "No worktrees to remove.".dimmed());
+        return Ok(());
+    }
+    if args.dry_run {
+        say!("\n{}", "Dry run — nothing was removed.".dimmed());
+        return Ok(());
+    }
+
+    if !args.yes {
+        if args.non_interactive {
+            return Err(
+                "Gc requires confirmation. Use --yes to skip, or --dry-run to preview.".into(),
+            );
+        }
+        let confirm = dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Remove {} worktree{}?",
+                candidates.len(),
+                if candidates.len() == 1 { "" } else { "s" }
+            ))
+            .default(false)
+            .interact()?;
+        if !confirm {
+            say!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let mut removed = 0;
+    let mut failed = 0;
+    let mut deleted_branches = Vec::new();
+    for worktree in &candidates {
+        if let Err(e) = remove_worktree(&repo, &worktree.path, false) {
+            output::print_error(&format!(
+                "Failed to remove {}: {e}",
+                worktree.path.display()
+            ));
+            failed += 1;
+            continue;
+        }
+        removed += 1;
+        if let Some(ref branch) = worktree.branch
+            && let Ok(Some(deleted)) = crate::handle_branch_deletion(
+                &repo,
+                branch,
+                global_config.remove.branch_delete,
+                args.non_interactive,
+                false,
+                false,
+            )
+        {
+            deleted_branches.push(deleted);
+        }
+    }
+    crate::prune_worktree_state(&repo);
+
+    say!();
+    output::print_remove_summary(removed, failed);
+    output::print_branch_delete_summary(&deleted_branches);
+    if failed > 0 {
+        return Err(format!("Failed to remove {failed} worktree(s)").into());
+    }
+    Ok(())
+}
+
+/// Print the verdict for every linked worktree and return those to remove.
+///
+/// The worktree containing `cwd` is always kept.
+fn select_candidates(policy: &Policy, worktrees: &[WorktreeInfo], cwd: &Path) -> Vec<WorktreeInfo> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX));
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+
+    let mut candidates = Vec::new();
+    for worktree in worktrees.iter().filter(|w| !w.is_main) {
+        let contains_cwd = worktree
+            .path
+            .canonicalize()
+            .is_ok_and(|path| cwd.starts_with(path));
+        let reason = if contains_cwd {
+            Some("contains the current directory".to_string())
+        } else {
+            match worktree_activity(&worktree.path, &policy.base) {
+                Ok(activity) => policy.keep_reason(worktree.branch.as_deref(), &activity, now),
+                Err(e) => Some(format!("could not inspect: {e}")),
+            }
+        };
+
+        let branch = worktree.branch.as_deref().unwrap_or("(detached)");
+        if let Some(reason) = reason {
+            say!(
+                "  {} {} {}",
+                "keep  ".dimmed(),
+                worktree.path.display(),
+                format!("[{branch}] {reason}").dimmed()
+            );
+        } else {
+            say!(
+                "  {} {} {}",
+                "remove".red(),
+                worktree.path.display(),
+                format!("[{branch}]").cyan()
+            );
+            candidates.push(worktree.clone());
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(dirty: bool, ahead: usize, idle_days: i64) -> WorktreeActivity {
+        WorktreeActivity {
+            dirty,
+            ahead,
+            behind: 0,
+            last_commit: 100 * DAY - idle_days * DAY,
+            last_active: 100 * DAY - idle_days * DAY,
+        }
+    }
+
+    #[test]
+    fn test_keep_reason() {
+        let mut policy = Policy {
+            base: "main".to_string(),
+            idle_days: 14,
+            require_merged: true,
+        };
+        let now = 100 * DAY;
+        let reason = |policy: &Policy, branch, activity| policy.keep_reason(branch, &activity, now);
+
+        assert_eq!(
+            reason(&policy, Some("feature"), activity(false, 0, 20)),
+            None
+        );
+        assert_eq!(reason(&policy, None, activity(false, 0, 14)), None);
+        assert_eq!(
+            reason(&policy, Some("main"), activity(false, 0, 20)).as_deref(),
+            Some("is the base branch")
+        );
+        assert_eq!(
+            reason(&policy, Some("feature"), activity(true, 0, 20)).as_deref(),
+            Some("has uncommitted changes")
+        );
+        assert_eq!(
+            reason(&policy, Some("feature"), activity(false, 3, 20)).as_deref(),
+            Some("3 commits not in main")
+        );
+        assert_eq!(
+            reason(&policy, Some("feature"), activity(false, 0, 1)).as_deref(),
+            Some("last active 1 day ago")
+        );
+        // A worktree created recently from an old commit is not idle.
+        let created_today = WorktreeActivity {
+            last_active: now,
+            ..activity(false, 0, 20)
+        };
+        assert_eq!(
+            reason(&policy, Some("feature"), created_today).as_deref(),
+            Some("last active 0 days ago")
+        );
+
+        policy.require_merged = false;
+        assert_eq!(
+            reason(&policy, Some("feature"), activity(false, 3, 20)),
+            None
+        );
+    }
+}
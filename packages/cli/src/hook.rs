@@ -0,0 +1,236 @@
+//! Install-hook subcommand.
+//!
+//! Installs a `post-checkout` hook that re-runs setup whenever a linked
+//! worktree switches branches, so files that depend on the branch (missing
+//! config entries, files the new branch's configs add) are filled in
+//! without a manual `worktree-setup setup`. An existing `post-checkout`
+//! hook is kept and run first.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use colored::Colorize;
+use worktree_setup_git::{discover_repo, get_hooks_dir, get_hooks_path};
+
+use crate::args::InstallHookArgs;
+use crate::output;
+use crate::reporter::say;
+
+/// Name of the hook Git runs after a checkout.
+const HOOK_NAME: &str = "post-checkout";
+
+/// Name an existing `post-checkout` hook is moved to so ours can run it.
+const CHAINED_HOOK_NAME: &str = "post-checkout.worktree-setup-chained";
+
+/// Line identifying a hook written by this tool.
+const MARKER: &str = "# worktree-setup post-checkout hook";
+
+/// Hook script, run by `sh`.
+///
+/// Setup only runs for branch checkouts (`$3` is 1) in linked worktrees,
+/// and not for the initial checkout of `git worktree add` (whose previous
+/// HEAD, `$1`, is all zeros), since creation runs setup itself.
+const HOOK_SCRIPT: &str = r#"#!/bin/sh
+# worktree-setup post-checkout hook
+# Installed by `worktree-setup install-hook`; remove it with
+# `worktree-setup install-hook --uninstall`.
+
+status=0
+chained="$(dirname "$0")/post-checkout.worktree-setup-chained"
+if [ -x "$chained" ]; then
+    "$chained" "$@" || status=$?
+fi
+
+if [ "$3" = "1" ] \
+    && [ "$(git rev-parse --git-dir)" != "$(git rev-parse --git-common-dir)" ] \
+    && command -v worktree-setup >/dev/null 2>&1; then
+    case "$1" in
+        *[!0]*)
+            worktree-setup setup --non-interactive --no-install --no-progress --format silent \
+                || echo "worktree-setup: setup after checkout failed" >&2
+            ;;
+    esac
+fi
+
+exit $status
+"#;
+
+/// What installing the hook did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstallOutcome {
+    /// A new hook was written.
+    Created,
+    /// An existing hook was moved aside and is run by ours.
+    Chained,
+    /// Our hook was already installed.
+    AlreadyInstalled,
+}
+
+/// What uninstalling the hook did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UninstallOutcome {
+    /// Our hook was removed.
+    Removed,
+    /// Our hook was removed and the hook it chained put back.
+    Restored,
+    /// No hook of ours was installed.
+    NotInstalled,
+}
+
+/// Run the `install-hook` subcommand.
+pub fn run(args: &InstallHookArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = crate::start_dir(&env::current_dir()?);
+    let start = args.target_path.as_deref().unwrap_or(&cwd);
+    let repo = discover_repo(start)?;
+    let hooks_dir = get_hooks_dir(&repo);
+
+    let hook = hooks_dir.join(HOOK_NAME);
+
+    if args.uninstall {
+        match uninstall(&hooks_dir)? {
+            UninstallOutcome::Removed => say!("{} Removed {}", "✓".green(), hook.display()),
+            UninstallOutcome::Restored => say!(
+                "{} Removed {} and restored the hook it chained",
+                "✓".green(),
+                hook.display()
+            ),
+            UninstallOutcome::NotInstalled => {
+                output::print_info("No worktree-setup post-checkout hook is installed.");
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = get_hooks_path(&repo) {
+        output::print_info(&format!(
+            "core.hooksPath is set; installing into {path} (check it in if it is tracked)"
+        ));
+    }
+
+    match install(&hooks_dir)? {
+        InstallOutcome::Created => say!("{} Installed {}", "✓".green(), hook.display()),
+        InstallOutcome::Chained => say!(
+            "{} Installed {}; the existing hook was moved to {CHAINED_HOOK_NAME} and still runs first",
+            "✓".green(),
+            hook.display()
+        ),
+        InstallOutcome::AlreadyInstalled => {
+            output::print_info(&format!("Already installed: {}", hook.display()));
+        }
+    }
+    output::print_info("Setup now re-runs in linked worktrees after every branch switch.");
+
+    Ok(())
+}
+
+/// Whether the hook at `path` was written by this tool.
+fn is_ours(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|script| script.contains(MARKER))
+}
+
+/// Install the hook into `hooks_dir`, moving an existing one aside.
+fn install(hooks_dir: &Path) -> io::Result<InstallOutcome> {
+    let hook = hooks_dir.join(HOOK_NAME);
+    if is_ours(&hook) {
+        return Ok(InstallOutcome::AlreadyInstalled);
+    }
+
+    fs::create_dir_all(hooks_dir)?;
+    let chained = hook.exists();
+    if chained {
+        let chained_hook = hooks_dir.join(CHAINED_HOOK_NAME);
+        if chained_hook.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists; move it away before installing",
+                    chained_hook.display()
+                ),
+            ));
+        }
+        fs::rename(&hook, &chained_hook)?;
+    }
+
+    fs::write(&hook, HOOK_SCRIPT)?;
+    make_executable(&hook)?;
+
+    Ok(if chained {
+        InstallOutcome::Chained
+    } else {
+        InstallOutcome::Created
+    })
+}
+
+/// Remove our hook from `hooks_dir`, restoring the hook it chained.
+fn uninstall(hooks_dir: &Path) -> io::Result<UninstallOutcome> {
+    let hook = hooks_dir.join(HOOK_NAME);
+    if !is_ours(&hook) {
+        return Ok(UninstallOutcome::NotInstalled);
+    }
+
+    fs::remove_file(&hook)?;
+    let chained_hook = hooks_dir.join(CHAINED_HOOK_NAME);
+    if chained_hook.exists() {
+        fs::rename(&chained_hook, &hook)?;
+        return Ok(UninstallOutcome::Restored);
+    }
+    Ok(UninstallOutcome::Removed)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+/// Hooks need no permission bits outside unix.
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_chains_existing_hook_and_uninstall_restores_it() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hooks = dir.path().join("hooks");
+        fs::create_dir(&hooks).unwrap();
+        fs::write(hooks.join(HOOK_NAME), "#!/bin/sh\necho existing\n").unwrap();
+
+        assert_eq!(install(&hooks).unwrap(), InstallOutcome::Chained);
+        assert!(is_ours(&hooks.join(HOOK_NAME)));
+        assert_eq!(
+            fs::read_to_string(hooks.join(CHAINED_HOOK_NAME)).unwrap(),
+            "#!/bin/sh\necho existing\n"
+        );
+        assert_eq!(install(&hooks).unwrap(), InstallOutcome::AlreadyInstalled);
+
+        assert_eq!(uninstall(&hooks).unwrap(), UninstallOutcome::Restored);
+        assert_eq!(
+            fs::read_to_string(hooks.join(HOOK_NAME)).unwrap(),
+            "#!/bin/sh\necho existing\n"
+        );
+        assert!(!hooks.join(CHAINED_HOOK_NAME).exists());
+        assert_eq!(uninstall(&hooks).unwrap(), UninstallOutcome::NotInstalled);
+    }
+
+    #[test]
+    fn test_install_creates_hooks_dir_and_uninstall_removes_hook() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let hooks = dir.path().join("hooks");
+
+        assert_eq!(install(&hooks).unwrap(), InstallOutcome::Created);
+        assert_eq!(uninstall(&hooks).unwrap(), UninstallOutcome::Removed);
+        assert!(!hooks.join(HOOK_NAME).exists());
+    }
+}
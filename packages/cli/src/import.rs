@@ -0,0 +1,181 @@
+//! Import subcommand.
+//!
+//! Applies an overlay archive written by `export` to a worktree: the
+//! archive is unpacked into a temporary directory and each file is copied
+//! like a setup copy, skipping files that already exist unless
+//! `--overwrite` is given, with the usual progress display and manifest
+//! recording.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use colored::Colorize;
+use path_clean::PathClean;
+use worktree_setup_config::load_global_config;
+use worktree_setup_operations::plan_overlay_operations;
+
+use crate::args::ImportArgs;
+use crate::output;
+use crate::reporter::say;
+use crate::{FileOpOptions, execute_and_record, file_progress_manager};
+
+/// Run the `import` subcommand.
+///
+/// # Errors
+///
+/// * If the archive cannot be unpacked
+/// * If the unpacked overlay cannot be read
+/// * If an operation fails
+pub fn run(args: &ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let archive = cwd.join(&args.archive).clean();
+    let target_path = crate::resolve_setup_target(&cwd, args.target_path.as_ref());
+    if !archive.is_file() {
+        return Err(format!("Archive not found: {}", archive.display()).into());
+    }
+
+    output::print_header("Worktree Import");
+    say!("Archive: {}", archive.display());
+    say!("Target:  {}\n", target_path.display());
+
+    let overlay = tempfile::Builder::new()
+        .prefix("worktree-setup-import-")
+        .tempdir()?;
+    extract_archive(&archive, overlay.path())?;
+    let operations = plan_overlay_operations(overlay.path(), &target_path, args.overwrite)?;
+    if operations.is_empty() {
+        output::print_warning("The archive contains no files. Nothing to do.");
+        return Ok(());
+    }
+
+    if args.dry_run {
+        crate::print_planned_operations(&operations);
+        say!("{}", "Dry run — nothing was changed.".dimmed());
+        return Ok(());
+    }
+
+    let global_config = load_global_config(Some(&target_path))?;
+    let options = FileOpOptions {
+        overwrite_existing: args.overwrite,
+        progress: args.progress_mode(),
+        non_interactive: true,
+        ..FileOpOptions::default()
+    };
+    let progress_mgr = file_progress_manager(options.progress, &global_config);
+    execute_and_record(
+        &operations,
+        &[],
+        &progress_mgr,
+        overlay.path(),
+        &target_path,
+        options,
+        &global_config,
+        None,
+    )?;
+
+    say!();
+    output::print_success();
+    Ok(())
+}
+
+/// Unpack `archive` into `dir` with the system `tar` command, which
+/// detects the compression.
+fn extract_archive(archive: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let result = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .output()
+        .map_err(|e| format!("Failed to run tar: {e}"))?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to unpack {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&result.stderr).trim()
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_import_overwrite_skips_protected_branch() {
+        let worktree = TempDir::new().unwrap();
+        git(worktree.path(), &["init", "-q", "-b", "main"]);
+        git(
+            worktree.path(),
+            &[
+                "-c",
+                "user.email=test@test.com",
+                "-c",
+                "user.name=Test",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "Initial commit",
+            ],
+        );
+        fs::write(
+            worktree.path().join(".worktree-setup.toml"),
+            "[setup]\nprotected_branches = [\"main\"]\n",
+        )
+        .unwrap();
+        fs::write(worktree.path().join("app.env"), "PORT=1").unwrap();
+
+        let overlay = TempDir::new().unwrap();
+        fs::write(overlay.path().join("app.env"), "PORT=2").unwrap();
+        fs::write(overlay.path().join("new.env"), "NEW=1").unwrap();
+        let archive = overlay.path().join("overlay.tar");
+        let status = Command::new("tar")
+            .arg("-cf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(overlay.path())
+            .args(["app.env", "new.env"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        run(&ImportArgs::parse_from([
+            "import".as_ref(),
+            archive.as_os_str(),
+            worktree.path().as_os_str(),
+            "--overwrite".as_ref(),
+            "--progress=none".as_ref(),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(worktree.path().join("app.env")).unwrap(),
+            "PORT=1"
+        );
+        assert_eq!(
+            fs::read_to_string(worktree.path().join("new.env")).unwrap(),
+            "NEW=1"
+        );
+    }
+}
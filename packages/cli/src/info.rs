@@ -0,0 +1,197 @@
+//! Info subcommand.
+//!
+//! Gathers everything recorded about one worktree — its branch, main
+//! worktree, applied configs, the symlinks setup created and whether they
+//! still resolve, and when file operations last ran — into a single view
+//! for debugging a worktree that does not behave like its siblings.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use worktree_setup_git::{discover_repo, get_main_worktree, get_repo_root, get_worktrees};
+use worktree_setup_operations::{Manifest, ManifestEntryKind, StateStore};
+
+use crate::args::InfoArgs;
+use crate::output;
+use crate::reporter::say;
+
+/// A symlink recorded in the setup manifest, as found on disk now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordedLink {
+    /// Path relative to the worktree.
+    path: String,
+    /// Where the link points, or `None` if it is no longer a symlink.
+    target: Option<PathBuf>,
+    /// Whether the link exists but its target does not.
+    broken: bool,
+}
+
+/// Run the `info` subcommand.
+pub fn run(args: &InfoArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let target_path = crate::resolve_setup_target(&cwd, args.target_path.as_ref());
+
+    let repo = discover_repo(&target_path)?;
+    let repo_root = get_repo_root(&repo)?;
+    let main_worktree = get_main_worktree(&repo)?;
+
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let target_canonical = canonical(&target_path);
+    let worktree = get_worktrees(&repo)?
+        .into_iter()
+        .find(|wt| canonical(&wt.path) == target_canonical);
+
+    output::print_header("Worktree Info");
+    say!("Worktree:      {}", target_path.display());
+    let branch = worktree.as_ref().map_or_else(
+        || "(not a registered worktree)".to_string(),
+        |wt| {
+            wt.branch.clone().unwrap_or_else(|| {
+                let commit = wt.commit.as_deref().unwrap_or("unknown commit");
+                format!("(detached at {})", &commit[..commit.len().min(8)])
+            })
+        },
+    );
+    say!("Branch:        {}", branch.cyan());
+    say!("Main worktree: {}", main_worktree.path.display());
+    if worktree.as_ref().is_some_and(|wt| wt.is_main) {
+        output::print_info("This is the main worktree; setup does not apply to it.");
+    }
+
+    let state = StateStore::for_repository(&repo).load(&target_path)?;
+    let manifest = Manifest::load(&Manifest::path_for(&target_path)?)?;
+    say!(
+        "Last sync:     {}",
+        manifest
+            .as_ref()
+            .map_or_else(|| "never".to_string(), |m| output::format_age(m.applied_at))
+    );
+    if let Some(state) = state.as_ref().filter(|s| !s.labels.is_empty()) {
+        let labels: Vec<String> = state
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        say!("Labels:        {}", labels.join(", ").yellow());
+    }
+    say!();
+
+    if let Some(state) = &state {
+        let configs = crate::load_configs_quietly(&repo_root)?;
+        output::print_applied_configs(&crate::applied_config_rows(state, &configs));
+    } else {
+        say!("{}\n", "No configs applied.".dimmed());
+    }
+
+    let links = manifest
+        .as_ref()
+        .map(|m| recorded_links(m, &target_path))
+        .unwrap_or_default();
+    print_links(&links);
+
+    Ok(())
+}
+
+/// Inspect every symlink recorded in `manifest` under `worktree`.
+fn recorded_links(manifest: &Manifest, worktree: &Path) -> Vec<RecordedLink> {
+    manifest
+        .files
+        .iter()
+        .filter(|(_, entry)| entry.kind == ManifestEntryKind::Symlink)
+        .map(|(rel, _)| {
+            let path = worktree.join(rel);
+            let target = fs::read_link(&path).ok();
+            RecordedLink {
+                path: rel.clone(),
+                broken: target.is_some() && !path.exists(),
+                target,
+            }
+        })
+        .collect()
+}
+
+/// Print recorded symlinks, then the broken ones again as a summary.
+fn print_links(links: &[RecordedLink]) {
+    if links.is_empty() {
+        say!("{}", "No symlinks recorded.".dimmed());
+        return;
+    }
+
+    say!("Symlinks ({}):", links.len());
+    for link in links {
+        match &link.target {
+            Some(target) if link.broken => say!(
+                "  {} {} -> {} {}",
+                "✗".red(),
+                link.path,
+                target.display(),
+                "(broken)".red()
+            ),
+            Some(target) => say!(
+                "  {} {} -> {}",
+                "•".dimmed(),
+                link.path,
+                target.display().to_string().dimmed()
+            ),
+            None => say!(
+                "  {} {} {}",
+                "✗".red(),
+                link.path,
+                "(no longer a symlink)".yellow()
+            ),
+        }
+    }
+    say!();
+
+    let broken = links.iter().filter(|l| l.broken).count();
+    if broken > 0 {
+        output::print_warning(&format!(
+            "{broken} broken symlink{}; run `worktree-setup setup --overwrite` to recreate",
+            if broken == 1 { "" } else { "s" }
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use worktree_setup_operations::ManifestEntry;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_recorded_links_flags_broken_and_replaced_links() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let worktree = dir.path();
+        fs::create_dir(worktree.join("real")).unwrap();
+        std::os::unix::fs::symlink(worktree.join("real"), worktree.join("ok")).unwrap();
+        std::os::unix::fs::symlink(worktree.join("gone"), worktree.join("dangling")).unwrap();
+        fs::write(worktree.join("replaced"), "file").unwrap();
+
+        let mut manifest = Manifest::default();
+        for name in ["ok", "dangling", "replaced"] {
+            manifest.files.insert(
+                name.to_string(),
+                ManifestEntry {
+                    source: name.to_string(),
+                    kind: ManifestEntryKind::Symlink,
+                    hash: None,
+                    origin: None,
+                    method: None,
+                },
+            );
+        }
+
+        let links = recorded_links(&manifest, worktree);
+        let find = |name: &str| links.iter().find(|l| l.path == name).unwrap();
+        assert!(!find("ok").broken);
+        assert_eq!(find("ok").target, Some(worktree.join("real")));
+        assert!(find("dangling").broken);
+        assert_eq!(find("replaced").target, None);
+    }
+}
@@ -18,14 +18,23 @@ use worktree_setup_git::{
     Repository, WorktreeCreateOptions, WorktreeInfo, fetch_remote, get_remote_branches, get_remotes,
 };
 
+use crate::args::ExistingTarget;
 use crate::output;
+use crate::reporter::{self, say};
 
 /// Select which configs to apply from a list.
 ///
+/// `preselected` sets which configs start checked; all are checked when
+/// `None`.
+///
 /// # Errors
 ///
 /// * If the user cancels the selection
-pub fn select_configs(configs: &[LoadedConfig]) -> io::Result<Vec<usize>> {
+pub fn select_configs(
+    configs: &[LoadedConfig],
+    preselected: Option<&[bool]>,
+) -> io::Result<Vec<usize>> {
+    reporter::get().prompt_needed("select_configs");
     if configs.len() == 1 {
         // If there's only one config, auto-select it
         return Ok(vec![0]);
@@ -33,12 +42,34 @@ pub fn select_configs(configs: &[LoadedConfig]) -> io::Result<Vec<usize>> {
 
     let items: Vec<String> = configs
         .iter()
-        .map(|c| format!("{} - {}", c.relative_path, c.config.description))
+        .map(|c| {
+            let label = format!("{} - {}", c.relative_path, c.config.label());
+            match c.config.metadata_summary() {
+                Some(metadata) => format!("{label} ({metadata})"),
+                None => label,
+            }
+        })
         .collect();
 
     let selections = MultiSelect::new()
         .with_prompt("Select configurations to apply")
         .items(&items)
+        .defaults(preselected.unwrap_or(&vec![true; items.len()]))
+        .interact()?;
+
+    Ok(selections)
+}
+
+/// Select which suggested config entries to add.
+///
+/// # Errors
+///
+/// * If the user cancels the selection
+pub fn select_suggestions(items: &[String]) -> io::Result<Vec<usize>> {
+    reporter::get().prompt_needed("select_suggestions");
+    let selections = MultiSelect::new()
+        .with_prompt("Select entries to add")
+        .items(items)
         .defaults(&vec![true; items.len()])
         .interact()?;
 
@@ -141,6 +172,7 @@ pub fn select_worktrees_with_sizes(
     result_rx: &mpsc::Receiver<WorktreeResolution>,
     done: &AtomicBool,
 ) -> io::Result<(Option<Vec<usize>>, Vec<WorktreeResolution>)> {
+    reporter::get().prompt_needed("select_worktrees");
     let count = worktrees.len();
     if count == 0 {
         return Ok((Some(Vec::new()), Vec::new()));
@@ -482,6 +514,7 @@ pub fn select_worktrees_for_removal(
     warning_rx: &mpsc::Receiver<WarningResolution>,
     done: &AtomicBool,
 ) -> io::Result<RemovalPickerResult> {
+    reporter::get().prompt_needed("select_worktrees_for_removal");
     let count = worktrees.len();
     if count == 0 {
         return Ok((Some(Vec::new()), Vec::new()));
@@ -742,6 +775,7 @@ fn render_removal_items(
 ///
 /// * If the user cancels the input
 pub fn prompt_worktree_path() -> io::Result<PathBuf> {
+    reporter::get().prompt_needed("worktree_path");
     let path: String = Input::new()
         .with_prompt("Enter the path for the new worktree")
         .interact_text()?;
@@ -891,7 +925,7 @@ fn prompt_remote_branch(
         .interact()?;
 
     if should_fetch {
-        println!("Fetching from {remote}...");
+        say!("Fetching from {remote}...");
         fetch_remote(repo, &remote)
             .map_err(|e| io::Error::other(format!("Failed to fetch: {e}")))?;
     }
@@ -900,7 +934,7 @@ fn prompt_remote_branch(
         .map_err(|e| io::Error::other(format!("Failed to list remote branches: {e}")))?;
 
     if remote_branches.is_empty() {
-        println!("No remote branches found. Using auto-named branch instead.");
+        say!("No remote branches found. Using auto-named branch instead.");
         return Ok(WorktreeCreateOptions::default());
     }
 
@@ -923,7 +957,7 @@ fn prompt_remote_branch(
             }
             // User declined — fall through to the full picker
         } else {
-            println!("No remote branch matching '{inferred_remote}' found. Showing all branches.");
+            say!("No remote branch matching '{inferred_remote}' found. Showing all branches.");
         }
     }
 
@@ -1069,6 +1103,7 @@ pub fn prompt_worktree_create(
     recent_branches: &[String],
     hints: &CreationProfileHints<'_>,
 ) -> io::Result<Option<WorktreeCreateOptions>> {
+    reporter::get().prompt_needed("worktree_create");
     // Step 1: Confirm creation (skip if auto_create)
     if !hints.auto_create {
         let should_create = Confirm::new()
@@ -1145,7 +1180,7 @@ pub fn prompt_worktree_create(
         },
         "existing" => {
             if branches.is_empty() {
-                println!("No local branches found. Using auto-named branch instead.");
+                say!("No local branches found. Using auto-named branch instead.");
                 WorktreeCreateOptions::default()
             } else {
                 let branch_idx = Select::new()
@@ -1176,12 +1211,104 @@ pub fn prompt_worktree_create(
 ///
 /// * If the user cancels the prompt
 pub fn prompt_run_install(default: bool) -> io::Result<bool> {
+    reporter::get().prompt_needed("run_install");
     Ok(Confirm::new()
         .with_prompt("Run post-setup commands (e.g., bun install)?")
         .default(default)
         .interact()?)
 }
 
+/// Offer `suggestion` in place of a worktree name that breaks
+/// `worktree_name_pattern`.
+///
+/// # Errors
+///
+/// * If the user cancels the prompt
+pub fn confirm_worktree_name(name: &str, suggestion: &str) -> io::Result<bool> {
+    reporter::get().prompt_needed("worktree_name");
+    Ok(Confirm::new()
+        .with_prompt(format!("Use '{suggestion}' instead of '{name}'?"))
+        .default(true)
+        .interact()?)
+}
+
+/// Confirm copying `bytes`, which exceed the `confirm_above` threshold.
+///
+/// # Errors
+///
+/// * If the prompt cannot be shown
+pub fn confirm_large_copy(bytes: u64) -> io::Result<bool> {
+    reporter::get().prompt_needed("confirm_large_copy");
+    Ok(Confirm::new()
+        .with_prompt(format!("Copy {}?", output::format_size(bytes)))
+        .default(false)
+        .interact()?)
+}
+
+/// Confirm replacing `count` files edited in the target worktree with
+/// those from `config`.
+///
+/// # Errors
+///
+/// * If the prompt cannot be shown
+pub fn confirm_replace_local_edits(config: &str, count: usize) -> io::Result<bool> {
+    reporter::get().prompt_needed("confirm_replace_local_edits");
+    Ok(Confirm::new()
+        .with_prompt(format!(
+            "Replace {count} locally edited file{} with the versions from {config}?",
+            if count == 1 { "" } else { "s" }
+        ))
+        .default(false)
+        .interact()?)
+}
+
+/// What to do about operations that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryChoice {
+    /// Retry every failed operation.
+    All,
+    /// Retry the failed operations at these indices.
+    Selected(Vec<usize>),
+    /// Leave the failures as they are.
+    Skip,
+}
+
+/// Ask what to do about the operations that failed, listed in `failures`.
+///
+/// # Errors
+///
+/// * If the prompt cannot be shown
+pub fn prompt_retry_failures(failures: &[String]) -> io::Result<RetryChoice> {
+    reporter::get().prompt_needed("retry_failures");
+    say!();
+    say!(
+        "{} operation{} failed:",
+        failures.len(),
+        if failures.len() == 1 { "" } else { "s" }
+    );
+    for failure in failures {
+        say!("  {} {failure}", "✗".red());
+    }
+    say!();
+
+    let choice = Select::new()
+        .with_prompt("Retry failed operations?")
+        .items(["Retry all", "Retry selected", "Skip"])
+        .default(0)
+        .interact()?;
+
+    Ok(match choice {
+        0 => RetryChoice::All,
+        1 => RetryChoice::Selected(
+            MultiSelect::new()
+                .with_prompt("Select operations to retry")
+                .items(failures)
+                .interact()?,
+        ),
+        _ => RetryChoice::Skip,
+    })
+}
+
 /// Drain any buffered keystrokes from stdin.
 ///
 /// Prevents stale input from leaking into the next interactive prompt.
@@ -1234,6 +1361,7 @@ pub enum StaleWorktreeAction {
 /// * If the user cancels the prompt
 #[must_use = "caller must act on the chosen recovery action"]
 pub fn prompt_stale_worktree_recovery() -> io::Result<StaleWorktreeAction> {
+    reporter::get().prompt_needed("stale_worktree_recovery");
     let options = [
         "Prune stale worktrees and retry",
         "Force create (overwrite registration)",
@@ -1253,6 +1381,37 @@ pub fn prompt_stale_worktree_recovery() -> io::Result<StaleWorktreeAction> {
     })
 }
 
+/// Prompt the user for how to handle a target directory that exists but
+/// is not a worktree of the repository.
+///
+/// # Errors
+///
+/// * If the terminal prompt fails
+#[must_use = "caller must act on the chosen action"]
+pub fn prompt_existing_target(target_path: &Path) -> io::Result<ExistingTarget> {
+    reporter::get().prompt_needed("existing_target");
+    let options = [
+        "Adopt it as a worktree (keeps its files)",
+        "Only apply file operations to it",
+        "Cancel",
+    ];
+
+    let choice = Select::new()
+        .with_prompt(format!(
+            "{} exists but is not a worktree of this repository. How would you like to proceed?",
+            target_path.display()
+        ))
+        .items(options)
+        .default(0)
+        .interact()?;
+
+    Ok(match choice {
+        0 => ExistingTarget::Adopt,
+        1 => ExistingTarget::FilesOnly,
+        _ => ExistingTarget::Abort,
+    })
+}
+
 /// Recovery action when a branch already exists during worktree creation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BranchExistsAction {
@@ -1274,6 +1433,7 @@ pub enum BranchExistsAction {
 /// * If the terminal prompt fails
 #[must_use = "caller must act on the chosen recovery action"]
 pub fn prompt_branch_exists_recovery(branch: &str) -> io::Result<BranchExistsAction> {
+    reporter::get().prompt_needed("branch_exists_recovery");
     let options = [
         "Use the existing branch",
         "Delete the branch and create fresh",
@@ -1336,6 +1496,7 @@ pub fn prompt_setup_operations(
     inputs: &SetupOperationInputs,
     post_setup_commands: &[&str],
 ) -> io::Result<SetupOperationChoices> {
+    reporter::get().prompt_needed("setup_operations");
     // Start with pre-determined values
     let mut result = SetupOperationChoices {
         run_files: inputs.files.unwrap_or(inputs.is_secondary_worktree),
@@ -0,0 +1,219 @@
+//! `--list` output.
+//!
+//! Lists the configs discovered in the repository, optionally filtered by
+//! tag or directory, as the usual colored list, as bare paths, or as a JSON
+//! document with each config's metadata, format, and whether it loads, so
+//! scripts and editor pickers can consume discovery results.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use path_clean::PathClean;
+use serde_json::{Value, json};
+use worktree_setup_config::{
+    ConfigError, DiscoveryOptions, LoadedConfig, PACKAGE_JSON, discover_configs_with_options,
+    is_package_json, load_configs_parallel,
+};
+
+use crate::args::Args;
+use crate::output;
+use crate::reporter::say;
+
+/// A discovered config and the result of loading it.
+struct ListedConfig {
+    /// Path relative to the repository root.
+    relative: PathBuf,
+    /// The loaded config, or why it failed to load.
+    loaded: Result<LoadedConfig, ConfigError>,
+}
+
+impl ListedConfig {
+    /// Whether the config passes the `--tag` and `--dir` filters.
+    ///
+    /// Configs that fail to load have no tags, so they never match a tag
+    /// filter.
+    fn matches(&self, tags: &[String], dirs: &[PathBuf]) -> bool {
+        let tag_match = tags.is_empty()
+            || self
+                .loaded
+                .as_ref()
+                .is_ok_and(|c| c.config.tags.iter().any(|tag| tags.contains(tag)));
+        let dir_match = dirs.is_empty()
+            || dirs.iter().any(|dir| {
+                let dir = dir.clean();
+                dir == Path::new(".") || self.relative.starts_with(dir)
+            });
+        tag_match && dir_match
+    }
+
+    /// The config as a JSON object.
+    fn to_json(&self) -> Value {
+        let format = if is_package_json(&self.relative) {
+            Some(PACKAGE_JSON.to_string())
+        } else {
+            self.relative
+                .extension()
+                .map(|e| match e.to_string_lossy().as_ref() {
+                    "yml" => "yaml".to_string(),
+                    "mjs" | "cjs" => "js".to_string(),
+                    other => other.to_string(),
+                })
+        };
+        let mut value = json!({
+            "path": self.relative.to_string_lossy(),
+            "format": format,
+            "valid": self.loaded.is_ok(),
+        });
+        match &self.loaded {
+            Ok(config) => {
+                let config = &config.config;
+                value["name"] = json!(config.name);
+                value["description"] = json!(config.description);
+                value["owner"] = json!(config.owner);
+                value["docsUrl"] = json!(config.docs_url);
+                value["estimatedSetupTime"] = json!(config.estimated_setup_time);
+                value["tags"] = json!(config.tags);
+                value["profiles"] = json!(config.profiles.keys().collect::<Vec<_>>());
+            }
+            Err(e) => {
+                value["error"] = json!(e.to_string());
+                value["errorCode"] = json!(e.code());
+            }
+        }
+        value
+    }
+}
+
+/// Run `--list`.
+///
+/// # Errors
+///
+/// * If config discovery fails
+pub fn run(args: &Args, repo_root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let machine_readable = args.json || args.paths_only;
+    if !machine_readable {
+        output::print_header("Worktree Setup");
+        output::print_repo_info(&repo_root.to_string_lossy());
+        say!();
+    }
+
+    let paths = discover_configs_with_options(
+        repo_root,
+        DiscoveryOptions {
+            include_submodules: args.submodules,
+        },
+    )?;
+
+    // Keep stdout clean for scripts; the warning still goes to stderr.
+    if machine_readable {
+        for path in crate::changed_ts_configs(&paths, repo_root) {
+            eprintln!(
+                "{} {}",
+                "Warning:".yellow().bold(),
+                crate::ts_change_warning(&path)
+            );
+        }
+    } else {
+        crate::warn_changed_ts_configs(&paths, repo_root);
+    }
+
+    let loaded = load_configs_parallel(&paths, repo_root);
+    let listed: Vec<ListedConfig> = paths
+        .into_iter()
+        .zip(loaded)
+        .map(|(path, loaded)| ListedConfig {
+            relative: path.strip_prefix(repo_root).unwrap_or(&path).to_path_buf(),
+            loaded,
+        })
+        .filter(|c| c.matches(&args.tags, &args.dirs))
+        .collect();
+
+    if args.json {
+        let configs: Vec<Value> = listed.iter().map(ListedConfig::to_json).collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "configs": configs }))?
+        );
+    } else if args.paths_only {
+        for config in &listed {
+            println!("{}", config.relative.display());
+        }
+    } else {
+        print_human(listed);
+    }
+    Ok(())
+}
+
+/// Print the listed configs and their profiles, warning about the ones
+/// that fail to load.
+fn print_human(listed: Vec<ListedConfig>) {
+    if listed.is_empty() {
+        say!("No config files found.\n");
+        return;
+    }
+
+    let mut configs = Vec::new();
+    for config in listed {
+        match config.loaded {
+            Ok(loaded) => configs.push(loaded),
+            Err(e) => output::print_file_warning(
+                &config.relative,
+                &format!("Failed to load {}: {e}", config.relative.display()),
+            ),
+        }
+    }
+
+    if configs.is_empty() {
+        output::print_warning("All config files failed to load.\n");
+        return;
+    }
+    output::print_config_list(&configs.iter().collect::<Vec<_>>());
+    output::print_profile_list(&crate::collect_profile_display_info(&configs));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use worktree_setup_config::Config;
+
+    fn listed(relative: &str, tags: &[&str]) -> ListedConfig {
+        ListedConfig {
+            relative: PathBuf::from(relative),
+            loaded: Ok(LoadedConfig {
+                config: Config {
+                    tags: tags.iter().map(ToString::to_string).collect(),
+                    ..Config::default()
+                },
+                config_path: PathBuf::from("/repo").join(relative),
+                config_dir: PathBuf::from("/repo"),
+                relative_path: relative.to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_listed_config_matches_filters() {
+        let web = listed("apps/web/worktree.config.toml", &["frontend"]);
+        let tags = ["frontend".to_string()];
+
+        assert!(web.matches(&[], &[]));
+        assert!(web.matches(&tags, &[PathBuf::from("apps/")]));
+        assert!(web.matches(&[], &[PathBuf::from("./apps/web")]));
+        assert!(!web.matches(&["backend".to_string()], &[]));
+        assert!(!web.matches(&[], &[PathBuf::from("app")]));
+
+        let broken = ListedConfig {
+            relative: PathBuf::from("apps/api/worktree.config.ts"),
+            loaded: Err(ConfigError::NoJsRuntime),
+        };
+        assert!(broken.matches(&[], &[PathBuf::from("apps")]));
+        assert!(!broken.matches(&tags, &[]));
+        assert_eq!(broken.to_json()["valid"], false);
+        assert_eq!(broken.to_json()["format"], "ts");
+        assert_eq!(broken.to_json()["errorCode"], "WTS-CFG-006");
+    }
+}
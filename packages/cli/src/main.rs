@@ -6,47 +6,118 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
+mod answers;
 mod args;
+mod cache;
+mod capture;
+mod detect;
+mod exec;
+mod explain;
+mod export;
+mod gc;
+mod hook;
+mod import;
+mod info;
 mod interactive;
+mod list;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod output;
+mod pool;
+mod post_setup;
 mod progress;
+mod reporter;
+mod resume;
+mod schema;
+mod serve;
+mod suggest;
+mod timing;
+mod workspace;
+mod worktrees;
 
 use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::mpsc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use clap::Parser;
 use colored::Colorize;
 use path_clean::PathClean;
 
-use args::{Args, CleanArgs, RemoveArgs, SetupArgs};
-use progress::ProgressManager;
+use args::{Args, CleanArgs, ExistingTarget, ProgressMode, RemoveArgs, SetupArgs, StatusArgs};
+use progress::{DEFAULT_STALL_WARNING, ProgressManager};
+use reporter::say;
+use timing::Phase;
 use worktree_setup_config::{
-    BranchDeletePolicy, CreationMethod, LoadedConfig, PostSetupKeyword, PostSetupMode,
-    ResolvedProfile, discover_configs, load_config, load_global_config, resolve_profiles,
+    BranchDeletePolicy, ConfigError, CreationMethod, DiscoveryOptions, LoadedConfig, PathAnchor,
+    PathSpec, PostSetupKeyword, PostSetupMode, RemoteOptions, ResolvedProfile, SetupConfig,
+    TsOptions, UnknownKey, apply_profile_excludes, apply_root_defaults, check_worktree_name,
+    discover_configs, discover_configs_with_options, expand_home, load_configs_parallel,
+    load_global_config, record_ts_configs, resolve_profiles, root_defaults,
+    select_configs_by_patterns, set_remote_options, set_strict_schema, set_ts_options,
 };
+use worktree_setup_copy::{CopyResult, STREAMING_COPY_THRESHOLD, clone_directory_into};
 use worktree_setup_git::{
-    GitError, Repository, WorktreeCreateOptions, WorktreeInfo, create_worktree, delete_branch,
-    discover_repo, fetch_remote, get_current_branch, get_default_branch, get_local_branches,
-    get_main_worktree, get_recent_branches, get_remotes, get_repo_root,
-    get_unstaged_and_untracked_files, get_worktrees, prune_worktrees, remove_worktree,
+    GitError, Repository, WorktreeCreateOptions, WorktreeInfo, adopt_cloned_files, adopt_directory,
+    create_worktree, delete_branch, discover_repo, fetch_remote, get_current_branch,
+    get_default_branch, get_local_branches, get_main_worktree, get_recent_branches, get_remotes,
+    get_repo_root, get_unstaged_and_untracked_files, get_workdir, get_worktrees, init_submodules,
+    open_env_repo, prune_worktrees, remove_worktree,
 };
+use worktree_setup_glob::WorktreeIgnore;
 use worktree_setup_operations::{
-    ApplyConfigOptions, OperationType, execute_operation, plan_operations_with_progress,
-    plan_unstaged_operations,
+    ApplyConfigOptions, CountCache, FailureAction, Label, Manifest, OperationError,
+    OperationOutcome, OperationResult, OperationType, PlanRunner, PlannedOperation, RunState,
+    SpaceCheck, StateStore, UnstagedFilter, VerifyMode, VerifyReport, WorktreeState,
+    copy_denied_with_sudo, denied_paths, execute_operation_outcome, execute_operation_with_timeout,
+    execute_plan_with, load_worktree_ignore, nested_target, plan_hooks_operation,
+    plan_operations_with_progress, plan_unstaged_operations, planned_copy_bytes,
+    remote_config_cache_dir, rescan_operation, sort_by_order, ts_config_store, verify_operation,
 };
 
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    let reporter_init = reporter::init(args.format, args.progress_socket.as_deref());
+    output::set_output_format(args.output);
+    set_remote_options(RemoteOptions {
+        cache_dir: remote_config_cache_dir(),
+        offline: args.offline,
+    });
+    set_strict_schema(args.strict_config);
+    // Only the global config file can restrict TypeScript configs, so a
+    // repository cannot lift the restrictions itself.
+    let security = load_global_config(None)
+        .map(|config| config.security)
+        .unwrap_or_default();
+    set_ts_options(TsOptions {
+        sandbox: security.ts_sandbox,
+        allowed_hashes: security.ts_config_hashes,
+    });
 
     // Set up logging based on top-level or subcommand verbose flag
     let verbose = match &args.command {
         Some(args::Command::Setup(setup_args)) => setup_args.verbose,
         Some(args::Command::Clean(clean_args)) => clean_args.verbose,
         Some(args::Command::Remove(remove_args)) => remove_args.verbose,
+        Some(args::Command::Gc(gc_args)) => gc_args.verbose,
+        Some(args::Command::Status(status_args)) => status_args.verbose,
+        Some(args::Command::Info(info_args)) => info_args.verbose,
+        Some(args::Command::Explain(explain_args)) => explain_args.verbose,
+        Some(args::Command::Worktrees(worktrees_args)) => worktrees_args.verbose,
+        Some(args::Command::InstallHook(hook_args)) => hook_args.verbose,
+        Some(args::Command::Cache(cache_args)) => cache_args.verbose,
+        Some(args::Command::Pool(pool_args)) => pool_args.verbose,
+        Some(args::Command::Schema(schema_args)) => schema_args.verbose,
+        Some(args::Command::Workspace(workspace_args)) => workspace_args.verbose,
+        Some(args::Command::Capture(capture_args)) => capture_args.verbose,
+        Some(args::Command::Export(export_args)) => export_args.verbose,
+        Some(args::Command::Import(import_args)) => import_args.verbose,
+        Some(args::Command::Suggest(suggest_args)) => suggest_args.verbose,
+        Some(args::Command::Serve(serve_args)) => serve_args.verbose,
+        Some(args::Command::Exec(exec_args)) => exec_args.verbose,
         None => args.verbose,
     };
 
@@ -58,19 +129,111 @@ fn main() {
     }
     pretty_env_logger::init();
 
+    if let Err(e) = reporter_init
+        .and_then(|()| init_metrics(args.metrics_file.as_deref()))
+        .and_then(|()| init_start_dir(args.repo.as_deref()))
+        .and_then(|()| answers::apply(&mut args))
+    {
+        output::print_error_report(e.as_ref());
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "metrics")]
+    let started = Instant::now();
     let result = match args.command {
         Some(args::Command::Setup(ref setup_args)) => run_setup(setup_args),
         Some(args::Command::Clean(ref clean_args)) => run_clean(clean_args),
         Some(args::Command::Remove(ref remove_args)) => run_remove(remove_args),
+        Some(args::Command::Gc(ref gc_args)) => gc::run(gc_args),
+        Some(args::Command::Status(ref status_args)) => run_status(status_args),
+        Some(args::Command::Info(ref info_args)) => info::run(info_args),
+        Some(args::Command::Explain(ref explain_args)) => explain::run(explain_args),
+        Some(args::Command::Worktrees(ref worktrees_args)) => worktrees::run(worktrees_args),
+        Some(args::Command::InstallHook(ref hook_args)) => hook::run(hook_args),
+        Some(args::Command::Cache(ref cache_args)) => cache::run(cache_args),
+        Some(args::Command::Pool(ref pool_args)) => pool::run(pool_args),
+        Some(args::Command::Schema(ref schema_args)) => schema::run(schema_args),
+        Some(args::Command::Workspace(ref workspace_args)) => workspace::run(workspace_args),
+        Some(args::Command::Capture(ref capture_args)) => capture::run(capture_args),
+        Some(args::Command::Export(ref export_args)) => export::run(export_args),
+        Some(args::Command::Import(ref import_args)) => import::run(import_args),
+        Some(args::Command::Suggest(ref suggest_args)) => suggest::run(suggest_args),
+        Some(args::Command::Serve(ref serve_args)) => serve::run(serve_args),
+        Some(args::Command::Exec(ref exec_args)) => exec::run(exec_args),
         None => run_create(&args),
     };
+    #[cfg(feature = "metrics")]
+    if matches!(args.command, None | Some(args::Command::Setup(_))) {
+        metrics::record_setup(result.is_ok(), started.elapsed());
+    }
 
     if let Err(e) = result {
-        output::print_error(&e.to_string());
+        output::print_error_report(e.as_ref());
         std::process::exit(1);
     }
 }
 
+/// Keep setup counters in the `--metrics-file`, if given.
+#[cfg(feature = "metrics")]
+fn init_metrics(path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    metrics::init(path)
+}
+
+/// Reject `--metrics-file`, which needs the `metrics` feature.
+#[cfg(not(feature = "metrics"))]
+fn init_metrics(path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    match path {
+        Some(_) => Err("built without metrics support (the `metrics` feature)".into()),
+        None => Ok(()),
+    }
+}
+
+/// Worktree named by `--repo` or the `GIT_DIR`/`GIT_WORK_TREE`
+/// environment variables, which commands start from in place of the
+/// current directory.
+static START_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resolve `--repo` (or else the git environment variables) into the
+/// directory commands start from.
+///
+/// The variables are cleared afterwards so that git commands run in other
+/// worktrees, such as post-setup commands, are not pointed back at this
+/// repository.
+fn init_start_dir(repo_path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(workdir) = repo_start_dir(repo_path, &env::current_dir()?)? {
+        log::debug!("Starting from {}", workdir.display());
+        let _ = START_DIR.set(workdir);
+        // SAFETY: We're clearing these before any other threads are spawned
+        unsafe {
+            env::remove_var("GIT_DIR");
+            env::remove_var("GIT_WORK_TREE");
+        }
+    }
+    Ok(())
+}
+
+/// The worktree containing `repo_path` (relative to `cwd`), or else the
+/// one named by the git environment variables, if either is given.
+fn repo_start_dir(
+    repo_path: Option<&Path>,
+    cwd: &Path,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let repo = match repo_path {
+        Some(path) => Some(discover_repo(&cwd.join(path).clean())?),
+        None => open_env_repo()?,
+    };
+    repo.map(|repo| Ok(get_workdir(&repo)?.clean())).transpose()
+}
+
+/// The directory a command starts from when given no path: the worktree
+/// named by `--repo` or the git environment variables, or else `cwd`.
+fn start_dir(cwd: &Path) -> PathBuf {
+    START_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| cwd.to_path_buf())
+}
+
 /// Discover and load configs from a repo root, printing status.
 ///
 /// Shows a spinner during discovery and loading to provide visual feedback,
@@ -80,6 +243,7 @@ fn main() {
 #[allow(clippy::literal_string_with_formatting_args)]
 fn discover_and_load_configs(
     repo_root: &Path,
+    include_submodules: bool,
 ) -> Result<Vec<LoadedConfig>, Box<dyn std::error::Error>> {
     let spinner = indicatif::ProgressBar::new_spinner();
     spinner.set_style(
@@ -91,12 +255,14 @@ fn discover_and_load_configs(
     spinner.enable_steady_tick(std::time::Duration::from_millis(80));
     spinner.set_message("Discovering configs...");
 
-    let config_paths = discover_configs(repo_root)?;
+    let config_paths = timing::time(Phase::Discovery, || {
+        discover_configs_with_options(repo_root, DiscoveryOptions { include_submodules })
+    })?;
 
     let mut all_configs: Vec<LoadedConfig> = Vec::new();
     if config_paths.is_empty() {
         spinner.finish_and_clear();
-        println!("No config files found.\n");
+        say!("No config files found.\n");
     } else {
         let total = config_paths.len();
         spinner.set_message(format!(
@@ -104,30 +270,36 @@ fn discover_and_load_configs(
             if total == 1 { "" } else { "s" }
         ));
 
-        let mut warnings: Vec<String> = Vec::new();
-        for path in config_paths {
-            match load_config(&path, repo_root) {
+        spinner.suspend(|| warn_changed_ts_configs(&config_paths, repo_root));
+
+        let mut warnings: Vec<(PathBuf, String)> = Vec::new();
+        let loaded = timing::time(Phase::Loading, || {
+            load_configs_parallel(&config_paths, repo_root)
+        });
+        for (path, loaded) in config_paths.into_iter().zip(loaded) {
+            match loaded {
                 Ok(config) => all_configs.push(config),
                 Err(e) => {
-                    warnings.push(format!("Failed to load {}: {}", path.display(), e));
+                    let message = format!("Failed to load {}: {}", path.display(), e);
+                    warnings.push((path, message));
                 }
             }
         }
 
         spinner.finish_and_clear();
+        warn_unknown_keys(&all_configs, repo_root);
+        warn_ignored_defaults(&apply_root_defaults(&mut all_configs));
 
-        // Print any warnings that occurred during loading
-        for warning in &warnings {
-            output::print_warning(warning);
+        // Print any warnings that occurred during loading, annotated with
+        // the config path relative to the repo root
+        for (path, warning) in &warnings {
+            output::print_file_warning(path.strip_prefix(repo_root).unwrap_or(path), warning);
         }
 
         if all_configs.is_empty() {
             output::print_warning("All config files failed to load.\n");
         } else {
-            let config_display: Vec<(String, String)> = all_configs
-                .iter()
-                .map(|c| (c.relative_path.clone(), c.config.description.clone()))
-                .collect();
+            let config_display: Vec<&LoadedConfig> = all_configs.iter().collect();
             output::print_config_list(&config_display);
         }
     }
@@ -138,10 +310,12 @@ fn discover_and_load_configs(
 /// Select configs from the loaded list, either interactively or by pattern.
 ///
 /// Returns indices into `all_configs` for the selected configs.
+/// `preselected` sets the initial checkboxes of the interactive prompt.
 fn select_configs(
     all_configs: &[LoadedConfig],
     config_patterns: &[String],
     non_interactive: bool,
+    preselected: Option<&[bool]>,
 ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
     if all_configs.is_empty() {
         return Ok(Vec::new());
@@ -149,22 +323,13 @@ fn select_configs(
 
     if !config_patterns.is_empty() {
         // Filter by provided patterns
-        Ok(all_configs
-            .iter()
-            .enumerate()
-            .filter(|(_, c)| {
-                config_patterns.iter().any(|p| {
-                    c.relative_path.contains(p) || c.config_path.to_string_lossy().contains(p)
-                })
-            })
-            .map(|(i, _)| i)
-            .collect())
+        Ok(select_configs_by_patterns(all_configs, config_patterns)?)
     } else if non_interactive {
         // Use all configs in non-interactive mode
         Ok((0..all_configs.len()).collect())
     } else {
         // Interactive selection
-        Ok(interactive::select_configs(all_configs)?)
+        Ok(interactive::select_configs(all_configs, preselected)?)
     }
 }
 
@@ -172,36 +337,103 @@ fn select_configs(
 ///
 /// When `--profile` is used, this resolves the named profiles against the
 /// profiles file and loaded configs, returning the resolved profile with
-/// config indices and merged defaults. Prints the profile info and selected
-/// configs.
+/// config indices and merged defaults, and drops the entries the profiles
+/// exclude from the configs. Prints the profile info and selected configs.
 ///
 /// # Errors
 ///
 /// * If any requested profile is not found
 fn resolve_and_print_profile(
     profile_names: &[String],
-    all_configs: &[LoadedConfig],
+    all_configs: &mut [LoadedConfig],
     repo_root: &Path,
 ) -> Result<ResolvedProfile, Box<dyn std::error::Error>> {
     let resolved = resolve_profiles(profile_names, all_configs, repo_root)?;
+    apply_profile_excludes(all_configs, profile_names);
 
     output::print_using_profile(&resolved.names);
 
-    let config_display: Vec<(String, String)> = resolved
+    let config_display: Vec<&LoadedConfig> = resolved
         .config_indices
         .iter()
-        .map(|&i| {
-            (
-                all_configs[i].relative_path.clone(),
-                all_configs[i].config.description.clone(),
-            )
-        })
+        .map(|&i| &all_configs[i])
         .collect();
     output::print_profile_configs(&config_display);
 
     Ok(resolved)
 }
 
+/// Resolve `--profile` (if given) layered over the root config's run
+/// policies (see [`worktree_setup_config::ConfigDefaults::policy`]).
+///
+/// Without `--profile`, the result carries only the root policies and
+/// does not select configs; it is `None` when neither sets anything.
+///
+/// # Errors
+///
+/// * If any requested profile is not found
+fn resolve_profile_and_root_defaults(
+    profile_names: &[String],
+    all_configs: &mut [LoadedConfig],
+    repo_root: &Path,
+) -> Result<Option<ResolvedProfile>, Box<dyn std::error::Error>> {
+    let profile = if profile_names.is_empty() {
+        None
+    } else {
+        Some(resolve_and_print_profile(
+            profile_names,
+            all_configs,
+            repo_root,
+        )?)
+    };
+    let Some(root) = root_defaults(all_configs) else {
+        return Ok(profile);
+    };
+
+    let mut resolved = profile.unwrap_or_default();
+    let mut defaults = root.policy();
+    defaults.merge(&resolved.defaults);
+    resolved.defaults = defaults;
+    Ok(Some(resolved))
+}
+
+/// How [`execute_file_operations`] applies a plan.
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct FileOpOptions {
+    /// `--unstaged`/`--no-unstaged` (or profile default), or `None` to use
+    /// the config default.
+    copy_unstaged_override: Option<bool>,
+    /// Overwrite targets that already exist.
+    overwrite_existing: bool,
+    /// How to show progress.
+    progress: ProgressMode,
+    /// Skip the `confirm_above` confirmation (`--yes`).
+    assume_yes: bool,
+    /// Fail instead of prompting for confirmation.
+    non_interactive: bool,
+    /// Delete everything created by this run if an operation fails.
+    transactional: bool,
+    /// Record failed operations and carry on (`--continue-on-error`).
+    continue_on_error: bool,
+    /// Compare copied files with their sources afterwards (`--verify`).
+    verify: Option<VerifyMode>,
+    /// Reuse directory counts from earlier runs (off with `--no-count-cache`).
+    count_cache: bool,
+    /// Run plans that exceed free space on the target (`--skip-space-check`).
+    skip_space_check: bool,
+    /// Depth limit for directory copies without a `maxDepth` (`--max-depth`).
+    max_depth: Option<usize>,
+    /// Retry permission-denied files with `sudo` (`--sudo-fallback`).
+    sudo_fallback: bool,
+    /// List the planned operations without executing them (`--dry-run`).
+    dry_run: bool,
+    /// Fail on entries whose source does not exist (`--check-sources`).
+    check_sources: bool,
+    /// Replace locally edited files without asking (`--force-destructive`).
+    force_destructive: bool,
+}
+
 /// Execute file operations for the given configs against a target worktree.
 ///
 /// This handles scanning, planning, progress display, unstaged file copying,
@@ -216,161 +448,1436 @@ fn execute_file_operations(
     selected_configs: &[&LoadedConfig],
     main_worktree_path: &Path,
     target_path: &Path,
-    copy_unstaged_override: Option<bool>,
-    overwrite_existing: bool,
-    show_progress: bool,
+    options: FileOpOptions,
     global_config: &worktree_setup_config::GlobalConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let progress_mgr = ProgressManager::new(show_progress);
+    let FileOpOptions {
+        copy_unstaged_override,
+        overwrite_existing,
+        progress,
+        ..
+    } = options;
+    let progress_mgr = file_progress_manager(progress, global_config);
 
     // Calculate total operations across all configs for scanning progress
-    let config_op_counts: Vec<usize> = selected_configs
-        .iter()
-        .map(|c| {
-            c.config.symlinks.len()
-                + c.config.copy.len()
-                + c.config.overwrite.len()
-                + c.config.copy_glob.len()
-                + c.config.templates.len()
-        })
-        .collect();
-    let total_ops: usize = config_op_counts.iter().sum();
+    let total_ops: usize = selected_configs.iter().map(|c| config_op_count(c)).sum();
 
-    // Create scanning progress bar
-    let scanning_bar = progress_mgr.create_scanning_bar(total_ops as u64);
+    // Load .worktreeignore rules once and share them across all configs
+    let worktree_ignore = Arc::new(load_setup_ignore(main_worktree_path, target_path));
+    let count_cache = options
+        .count_cache
+        .then(|| worktree_setup_git::open_repo(main_worktree_path).ok())
+        .flatten()
+        .map(|repo| Arc::new(CountCache::for_repository(&repo)));
 
-    // Plan all operations across all configs with progress
-    let mut all_operations = Vec::new();
-    let mut offset = 0usize;
-    for (config, &config_count) in selected_configs.iter().zip(&config_op_counts) {
-        // Resolve allow_path_escape per-config: per-config overrides global
-        let allow_path_escape = config
-            .config
-            .allow_path_escape
-            .unwrap_or(global_config.security.allow_path_escape);
-
-        let options = ApplyConfigOptions {
-            copy_unstaged: copy_unstaged_override,
-            overwrite_existing,
-            allow_path_escape,
-        };
+    // Show scanning progress bar
+    let scanning_bar = progress_mgr.start_scanning(total_ops as u64);
+    let scanned = AtomicU64::new(0);
 
-        let current_offset = offset;
-        let ops = plan_operations_with_progress(
-            config,
+    // Plan all operations across all configs with progress
+    let base_options = ApplyConfigOptions {
+        copy_unstaged: copy_unstaged_override,
+        overwrite_existing,
+        worktree_ignore: Some(Arc::clone(&worktree_ignore)),
+        count_cache: count_cache.clone(),
+        max_depth: options.max_depth,
+        ..ApplyConfigOptions::default()
+    };
+    let mut all_operations = timing::time(Phase::Planning, || {
+        plan_selected_operations(
+            selected_configs,
             main_worktree_path,
             target_path,
-            &options,
-            &|current, _total, path, file_count| {
-                scanning_bar.set_position((current_offset + current) as u64);
+            &base_options,
+            global_config,
+            &|current, path, file_count| {
+                // Configs report concurrently, so only ever move the bar forward.
+                let position = scanned.fetch_max(current as u64, Ordering::Relaxed);
+                scanning_bar.set_position(position.max(current as u64));
                 match file_count {
                     Some(n) => scanning_bar.set_message(format!("{path} ({n} files)")),
                     None => scanning_bar.set_message(path.to_string()),
                 }
             },
-        )?;
-        offset += config_count;
-        all_operations.extend(ops);
-    }
+        )
+    })?;
 
-    // Clear scanning progress bar
-    scanning_bar.finish_and_clear();
+    progress_mgr.finish_scanning();
+    if let Some(cache) = &count_cache
+        && let Err(e) = cache.save()
+    {
+        log::warn!("Failed to save count cache: {e}");
+    }
 
     // Handle copyUnstaged - check if any selected config enables it
-    let should_copy_unstaged = selected_configs
-        .iter()
-        .any(|c| copy_unstaged_override.unwrap_or(c.config.copy_unstaged));
-
-    if should_copy_unstaged {
-        println!("Checking for unstaged files...");
-        let repo = worktree_setup_git::open_repo(main_worktree_path)?;
-        let unstaged_files = get_unstaged_and_untracked_files(&repo)?;
-        if !unstaged_files.is_empty() {
-            println!(
+    if let Some(filter) = unstaged_filter(
+        selected_configs,
+        copy_unstaged_override,
+        &global_config.setup,
+    ) {
+        progress_mgr.println("Checking for unstaged files...");
+        let unstaged_ops = timing::time(Phase::Planning, || {
+            plan_unstaged_files(main_worktree_path, target_path, &worktree_ignore, &filter)
+        })?;
+        if !unstaged_ops.is_empty() {
+            progress_mgr.println(&format!(
                 "Found {} unstaged/untracked files to copy",
-                unstaged_files.len()
-            );
-            let unstaged_ops =
-                plan_unstaged_operations(&unstaged_files, main_worktree_path, target_path);
+                unstaged_ops.len()
+            ));
             all_operations.extend(unstaged_ops);
         }
     }
 
+    if global_config.setup.install_hooks == Some(true) {
+        all_operations.extend(plan_hooks_copy(main_worktree_path, target_path));
+    }
+
+    skip_protected_branch_ops(&mut all_operations, target_path, &global_config.setup);
+    if !options.force_destructive {
+        confirm_local_edits(&mut all_operations, target_path, options)?;
+    }
+    suggest_links_for_large_copies(&all_operations, main_worktree_path, target_path);
+    warn_unapplied_filters(&all_operations);
+    if options.check_sources {
+        check_missing_sources(&all_operations)?;
+    }
+
+    if options.dry_run {
+        print_planned_operations(&all_operations);
+        return Ok(());
+    }
+
+    check_disk_space(&all_operations, main_worktree_path, target_path, options)?;
+    confirm_large_copy(&all_operations, options, global_config)?;
+
+    let run_state = begin_run_state(
+        &all_operations,
+        selected_configs,
+        main_worktree_path,
+        target_path,
+    );
+    timing::time(Phase::Execution, || {
+        execute_and_record(
+            &all_operations,
+            selected_configs,
+            &progress_mgr,
+            main_worktree_path,
+            target_path,
+            options,
+            global_config,
+            run_state,
+        )
+    })
+}
+
+/// Progress display for file operations, warning about stalled copies
+/// after the configured `stall_warning`.
+fn file_progress_manager(
+    progress: ProgressMode,
+    global_config: &worktree_setup_config::GlobalConfig,
+) -> ProgressManager {
+    ProgressManager::new(progress).with_stall_warning(
+        global_config
+            .setup
+            .stall_warning
+            .map_or(DEFAULT_STALL_WARNING, Duration::from_secs),
+    )
+}
+
+/// Execute planned operations, then record them in the setup manifest and
+/// worktree state.
+///
+/// Destructive operations are skipped first if the target is on a
+/// protected branch, whichever command planned them.
+/// `run_state` tracks progress for `--resume`; it is removed once every
+/// operation has finished and kept otherwise.
+#[allow(clippy::too_many_arguments)]
+fn execute_and_record(
+    operations: &[PlannedOperation],
+    selected_configs: &[&LoadedConfig],
+    progress_mgr: &ProgressManager,
+    main_worktree_path: &Path,
+    target_path: &Path,
+    options: FileOpOptions,
+    global_config: &worktree_setup_config::GlobalConfig,
+    mut run_state: Option<RunState>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut operations = operations.to_vec();
+    skip_protected_branch_ops(&mut operations, target_path, &global_config.setup);
+    let operations = operations.as_slice();
+
+    let (manifest_path, mut manifest) = load_setup_manifest(target_path);
+    for config in selected_configs {
+        manifest.record_config(config);
+    }
+
     // Execute operations with progress
-    for op in &all_operations {
-        if op.will_skip {
-            let reason = op.skip_reason.as_deref().unwrap_or("skipped");
-            progress_mgr.print_result(&op.display_path, reason, false);
+    let mut execute = |operations: &[PlannedOperation]| {
+        execute_planned_operations(
+            operations,
+            progress_mgr,
+            &mut manifest,
+            &mut run_state,
+            main_worktree_path,
+            target_path,
+            &global_config.setup,
+            options,
+        )
+    };
+    let summary = execute(operations)
+        .and_then(|summary| retry_failed_operations(operations, summary, options, &mut execute));
+
+    // Clear any remaining progress bars
+    progress_mgr.clear();
+
+    let summary = match summary {
+        Ok(summary) => summary,
+        Err(e) => {
+            if run_state.is_some() {
+                print_resume_hint(target_path);
+            }
+            return Err(e);
+        }
+    };
+
+    save_setup_records(manifest_path, manifest, selected_configs, target_path);
+    if global_config.setup.auto_exclude == Some(true) {
+        exclude_created_paths(operations, target_path);
+    }
+    warn_unignored_symlinks(operations, target_path);
+
+    if let Some(state) = run_state {
+        if summary.failed.is_empty() && summary.timed_out == 0 {
+            if let Err(e) = state.finish() {
+                log::warn!("Failed to remove run state: {e}");
+            }
+        } else {
+            print_resume_hint(target_path);
+        }
+    }
+
+    if let Some(mode) = options.verify {
+        report_verification(mode, &summary.verify)?;
+    }
+
+    summary.into_result()
+}
+
+/// Save the plan about to run so an interrupted run can be resumed.
+///
+/// Returns `None` if the target is not a git worktree or the state cannot
+/// be written; the run goes ahead without it.
+fn begin_run_state(
+    operations: &[PlannedOperation],
+    selected_configs: &[&LoadedConfig],
+    main_worktree_path: &Path,
+    target_path: &Path,
+) -> Option<RunState> {
+    let configs = selected_configs
+        .iter()
+        .map(|config| config.relative_path.clone())
+        .collect();
+    RunState::path_for(target_path)
+        .and_then(|path| {
+            RunState::begin(&path, main_worktree_path, target_path, configs, operations)
+        })
+        .inspect_err(|e| log::warn!("Not saving run state: {e}"))
+        .ok()
+}
+
+/// Tell the user how to finish an incomplete run.
+fn print_resume_hint(target_path: &Path) {
+    output::print_info(&format!(
+        "Run `worktree-setup --resume {}` to finish the remaining operations.",
+        target_path.display()
+    ));
+}
+
+/// Add the files and symlinks this run created that git neither tracks
+/// nor ignores to the repository's `.git/info/exclude`.
+fn exclude_created_paths(operations: &[PlannedOperation], target_path: &Path) {
+    let created: Vec<PathBuf> = operations
+        .iter()
+        .filter(|op| !op.will_skip && op.target.symlink_metadata().is_ok())
+        .filter_map(|op| op.target.strip_prefix(target_path).ok())
+        .map(Path::to_path_buf)
+        .collect();
+    if created.is_empty() {
+        return;
+    }
+    let Ok(repo) = worktree_setup_git::open_repo(target_path) else {
+        return;
+    };
+    let added = worktree_setup_git::untracked_unignored(&repo, &created)
+        .and_then(|untracked| worktree_setup_git::add_to_info_exclude(&repo, &untracked));
+    match added {
+        Ok(0) => {}
+        Ok(added) => say!(
+            "{}",
+            format!(
+                "Added {added} path{} to .git/info/exclude",
+                if added == 1 { "" } else { "s" }
+            )
+            .dimmed()
+        ),
+        Err(e) => output::print_warning(&format!("Failed to update .git/info/exclude: {e}")),
+    }
+}
+
+/// Warn about symlinks this run created that git neither tracks nor
+/// ignores: they show up in `git status` and can end up committed.
+fn warn_unignored_symlinks(operations: &[PlannedOperation], target_path: &Path) {
+    let links: Vec<PathBuf> = operations
+        .iter()
+        .filter(|op| {
+            !op.will_skip && op.operation_type == OperationType::Symlink && op.target.is_symlink()
+        })
+        .filter_map(|op| op.target.strip_prefix(target_path).ok())
+        .map(Path::to_path_buf)
+        .collect();
+    if links.is_empty() {
+        return;
+    }
+    let Ok(repo) = worktree_setup_git::open_repo(target_path) else {
+        return;
+    };
+    let untracked = match worktree_setup_git::untracked_unignored(&repo, &links) {
+        Ok(untracked) => untracked,
+        Err(e) => {
+            log::warn!("Failed to check whether symlinks are ignored: {e}");
+            return;
+        }
+    };
+    if untracked.is_empty() {
+        return;
+    }
+
+    let names: Vec<String> = untracked
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    output::print_warning(&format!(
+        "Symlinks not ignored by git show up in `git status` and could be committed: {}. Add them to .gitignore without a trailing `/`, since git treats a symlink as a file.",
+        names.join(", ")
+    ));
+}
+
+/// Load the ignore rules for setting up `target_path`, warning when the
+/// target is nested inside the main worktree and so gets excluded.
+fn load_setup_ignore(main_worktree_path: &Path, target_path: &Path) -> WorktreeIgnore {
+    if let Some(nested) = nested_target(main_worktree_path, target_path) {
+        output::print_warning(&format!(
+            "{} is inside the main worktree; excluding {} from copies so it is not copied into itself",
+            target_path.display(),
+            nested.display()
+        ));
+    }
+    load_worktree_ignore(main_worktree_path, target_path)
+}
+
+/// Report what each planned operation would do, for `--dry-run`.
+fn print_planned_operations(operations: &[worktree_setup_operations::PlannedOperation]) {
+    for op in operations {
+        reporter::get().operation(&reporter::OperationReport {
+            path: &op.display_path,
+            result: planned_result(op),
+            success: !op.will_skip,
+            counts: (op.is_directory && !op.will_skip).then_some((
+                op.file_count,
+                op.symlink_count,
+                op.filtered_count,
+            )),
+            label: op.label(),
+        });
+    }
+}
+
+/// What a planned operation would do: its skip reason, or `would copy`
+/// and the like.
+fn planned_result(op: &PlannedOperation) -> &str {
+    if op.will_skip {
+        return op.skip_reason.as_deref().unwrap_or("skipped");
+    }
+    match op.operation_type {
+        OperationType::Symlink => "would symlink",
+        OperationType::Overwrite => "would overwrite",
+        OperationType::Template if op.template.is_some() => "would render",
+        OperationType::Copy
+        | OperationType::CopyGlob
+        | OperationType::Unstaged
+        | OperationType::Template => "would copy",
+    }
+}
+
+/// What happened while executing a plan.
+#[derive(Debug, Default)]
+struct ExecutionSummary {
+    /// Operations cancelled after `operation_timeout`.
+    timed_out: usize,
+    /// Operations that failed with `--continue-on-error`, as their index
+    /// in the plan and the error.
+    failed: Vec<(usize, String)>,
+    /// Combined `--verify` results for the copies made.
+    verify: VerifyReport,
+}
+
+/// Execute planned operations in order, recording results in `manifest`.
+///
+/// Timed-out operations are reported and skipped. With `--transactional`,
+/// any failure (including a timeout) stops the run, deletes every path it
+/// created, and restores every file it overwrote before returning the
+/// error. With `--continue-on-error`, a failed operation's partial output
+/// is undone the same way and the run goes on. With `--verify`, each copy
+/// is compared with its source right after it is made.
+#[allow(clippy::too_many_arguments)]
+fn execute_planned_operations(
+    operations: &[PlannedOperation],
+    progress_mgr: &ProgressManager,
+    manifest: &mut Manifest,
+    run_state: &mut Option<RunState>,
+    main_worktree_path: &Path,
+    target_path: &Path,
+    setup: &SetupConfig,
+    options: FileOpOptions,
+) -> Result<ExecutionSummary, Box<dyn std::error::Error>> {
+    progress_mgr.start_overall(
+        operations
+            .iter()
+            .filter(|op| !op.will_skip)
+            .map(|op| op.file_count)
+            .sum(),
+    );
+
+    let mut runner = PlanSteps {
+        progress_mgr,
+        manifest,
+        run_state,
+        main_worktree_path,
+        target_path,
+        setup,
+        options,
+        summary: ExecutionSummary::default(),
+        rolled_back: None,
+    };
+    let rollback_root = (options.transactional || options.continue_on_error).then_some(target_path);
+    match execute_plan_with(operations, rollback_root, &mut runner) {
+        Ok(()) => Ok(runner.summary),
+        Err(e) => Err(match runner.rolled_back {
+            Some((changed, failed)) if options.transactional => format!(
+                "{e}; rolled back {} of {changed} changed path{}",
+                changed - failed,
+                if changed == 1 { "" } else { "s" }
+            )
+            .into(),
+            _ => e.into(),
+        }),
+    }
+}
+
+/// [`PlanRunner`] that executes operations with progress output and
+/// records them in the setup manifest.
+struct PlanSteps<'a> {
+    progress_mgr: &'a ProgressManager,
+    manifest: &'a mut Manifest,
+    run_state: &'a mut Option<RunState>,
+    main_worktree_path: &'a Path,
+    target_path: &'a Path,
+    setup: &'a SetupConfig,
+    options: FileOpOptions,
+    summary: ExecutionSummary,
+    /// Paths the last rollback tried to undo, and how many it could not.
+    rolled_back: Option<(usize, usize)>,
+}
+
+impl PlanRunner for PlanSteps<'_> {
+    fn execute(&mut self, _index: usize, op: &PlannedOperation) -> Result<(), OperationError> {
+        if let Some(state) = self.run_state
+            && let Err(e) = state.start(op)
+        {
+            log::warn!("Failed to save run state: {e}");
+        }
+        let started = Instant::now();
+        let outcome = execute_with_progress(op, self.progress_mgr, self.setup, self.options);
+        timing::record_operation(&op.display_path, started.elapsed());
+        let outcome = outcome?;
+        if let Some(state) = self.run_state
+            && let Err(e) = state.finish_operation(op)
+        {
+            log::warn!("Failed to save run state: {e}");
+        }
+
+        match outcome.result {
+            worktree_setup_operations::OperationResult::Exists => {
+                self.manifest.record_skip(op, self.target_path, "exists");
+                return Ok(());
+            }
+            worktree_setup_operations::OperationResult::Skipped => {
+                self.manifest.record_skip(op, self.target_path, "not found");
+                return Ok(());
+            }
+            worktree_setup_operations::OperationResult::Created
+            | worktree_setup_operations::OperationResult::Overwritten => {}
+        }
+        #[cfg(feature = "metrics")]
+        metrics::add_copied(op);
+        if let Err(e) = self.manifest.record_operation(
+            op,
+            outcome.method,
+            self.main_worktree_path,
+            self.target_path,
+        ) {
+            log::warn!("Failed to record {} in manifest: {e}", op.display_path);
+        }
+        if let Some(mode) = self.options.verify {
+            self.summary.verify.merge(verify_operation(op, mode)?);
+        }
+        Ok(())
+    }
+
+    fn skipped(&mut self, _index: usize, op: &PlannedOperation) {
+        let reason = op.skip_reason.as_deref().unwrap_or("skipped");
+        self.progress_mgr
+            .print_result(&op.display_path, reason, false, op.label());
+        self.manifest.record_skip(op, self.target_path, reason);
+    }
+
+    fn failed(
+        &mut self,
+        index: usize,
+        op: &PlannedOperation,
+        error: &OperationError,
+    ) -> FailureAction {
+        if self.options.transactional {
+            self.progress_mgr.clear();
+            // Nothing is left to resume once the run is rolled back.
+            if let Some(state) = self.run_state.take()
+                && let Err(e) = state.finish()
+            {
+                log::warn!("Failed to remove run state: {e}");
+            }
+            return FailureAction::RollBack;
+        }
+
+        if let OperationError::Timeout { timeout, .. } = error {
+            self.progress_mgr.print_result(
+                &op.display_path,
+                &format!("timed out after {}s", timeout.as_secs()),
+                false,
+                op.label(),
+            );
+            self.summary.timed_out += 1;
+            return FailureAction::Continue;
+        }
+        if self.options.continue_on_error {
+            self.progress_mgr.print_result(
+                &op.display_path,
+                &format!("failed: {error}"),
+                false,
+                op.label(),
+            );
+            self.summary.failed.push((index, error.to_string()));
+            return FailureAction::Continue;
+        }
+        FailureAction::Stop
+    }
+
+    /// A timed-out operation has already been cancelled and waited for by
+    /// [`execute_operation_with_timeout`], so nothing writes into the
+    /// worktree while it is rolled back, unless it was left behind stuck
+    /// on a blocked read.
+    fn rolled_back(&mut self, changed: usize, failures: &[(PathBuf, std::io::Error)]) {
+        for (path, e) in failures {
+            output::print_warning(&format!("Could not roll back {}: {e}", path.display()));
+        }
+        self.rolled_back = Some((changed, failures.len()));
+    }
+}
+
+impl ExecutionSummary {
+    /// Fail if any operation timed out or failed.
+    fn into_result(self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.timed_out > 0 {
+            return Err(format!(
+                "{} operation{} timed out (operation_timeout)",
+                self.timed_out,
+                if self.timed_out == 1 { "" } else { "s" }
+            )
+            .into());
+        }
+
+        if !self.failed.is_empty() {
+            return Err(format!(
+                "{} operation{} failed",
+                self.failed.len(),
+                if self.failed.len() == 1 { "" } else { "s" }
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Merge in the `result` of retrying the failed operations at plan
+    /// `indices`: those that failed again keep their place, with the new
+    /// error.
+    fn merge_retry(&mut self, indices: &[usize], result: Self) {
+        self.failed.retain(|(index, _)| !indices.contains(index));
+        self.failed
+            .extend(result.failed.into_iter().map(|(i, e)| (indices[i], e)));
+        self.failed.sort_by_key(|(index, _)| *index);
+        self.timed_out += result.timed_out;
+        self.verify.merge(result.verify);
+    }
+}
+
+/// Offer to retry the operations that failed with `--continue-on-error`
+/// until they succeed or the user moves on. Non-interactive runs keep
+/// the failures.
+///
+/// Returns `summary` with the failures left over and the results of the
+/// retries merged in.
+fn retry_failed_operations<E>(
+    operations: &[PlannedOperation],
+    mut summary: ExecutionSummary,
+    options: FileOpOptions,
+    execute: &mut E,
+) -> Result<ExecutionSummary, Box<dyn std::error::Error>>
+where
+    E: FnMut(&[PlannedOperation]) -> Result<ExecutionSummary, Box<dyn std::error::Error>>,
+{
+    while !options.non_interactive && !summary.failed.is_empty() {
+        let labels: Vec<String> = summary
+            .failed
+            .iter()
+            .map(|(index, error)| format!("{} ({error})", operations[*index].display_path))
+            .collect();
+        let chosen = match interactive::prompt_retry_failures(&labels)? {
+            interactive::RetryChoice::All => (0..labels.len()).collect(),
+            interactive::RetryChoice::Selected(chosen) if !chosen.is_empty() => chosen,
+            interactive::RetryChoice::Selected(_) | interactive::RetryChoice::Skip => break,
+        };
+
+        let indices: Vec<usize> = chosen.iter().map(|&i| summary.failed[i].0).collect();
+        let retried: Vec<PlannedOperation> =
+            indices.iter().map(|&i| operations[i].clone()).collect();
+        let result = execute(&retried)?;
+        summary.merge_retry(&indices, result);
+    }
+
+    Ok(summary)
+}
+
+/// Print `--verify` results.
+///
+/// # Errors
+///
+/// * If any checked file is missing or differs from its source
+fn report_verification(
+    mode: VerifyMode,
+    report: &VerifyReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    output::print_info(&format!(
+        "Verified copies ({mode}): {report}, {} hashed",
+        output::format_size(report.bytes_checked)
+    ));
+    if report.is_ok() {
+        return Ok(());
+    }
+
+    for path in &report.mismatched {
+        reporter::get().warning(
+            Some(path),
+            &format!("Differs from source: {}", path.display()),
+        );
+    }
+    for path in &report.missing {
+        reporter::get().warning(
+            Some(path),
+            &format!("Missing or unreadable: {}", path.display()),
+        );
+    }
+    Err(format!("Verification failed: {report}").into())
+}
+
+/// Save the setup manifest and worktree state after operations ran.
+///
+/// Failures are reported as warnings: the files were already copied.
+fn save_setup_records(
+    manifest_path: Option<PathBuf>,
+    mut manifest: Manifest,
+    selected_configs: &[&LoadedConfig],
+    target_path: &Path,
+) {
+    if let Some(path) = manifest_path {
+        manifest.touch();
+        if let Err(e) = manifest.save(&path) {
+            output::print_warning(&format!("Failed to write setup manifest: {e}"));
+        }
+    }
+
+    if let Err(e) = record_worktree_state(selected_configs, target_path) {
+        output::print_warning(&format!("Failed to record worktree state: {e}"));
+    }
+}
+
+/// Refuse to execute a plan that does not fit on the target filesystem,
+/// and warn when it would nearly fill it.
+///
+/// A plan that does not fit only warns when the target can reflink copies
+/// from `main_worktree_path`, since reflinked files use little extra space.
+/// Skipped with `--skip-space-check`.
+///
+/// # Errors
+///
+/// * If the plan copies more bytes than are free on the target filesystem
+///   and the copies cannot be reflinked
+fn check_disk_space(
+    operations: &[PlannedOperation],
+    main_worktree_path: &Path,
+    target_path: &Path,
+    options: FileOpOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.skip_space_check {
+        return Ok(());
+    }
+    let check = SpaceCheck::new(operations, target_path);
+    if check.refuses(|| worktree_setup_copy::supports_reflink(main_worktree_path, target_path)) {
+        return Err(insufficient_space_message(&check, target_path).into());
+    }
+    if !check.is_sufficient() {
+        output::print_warning(&format!(
+            "{}; continuing because copies will be reflinked",
+            insufficient_space_message(&check, target_path)
+        ));
+    } else if check.is_tight()
+        && let Some(available) = check.available
+    {
+        output::print_warning(&format!(
+            "Setup will copy {} and only {} is free on {}",
+            output::format_size(check.required),
+            output::format_size(available),
+            target_path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Error message for a plan that does not fit on the target filesystem.
+fn insufficient_space_message(check: &SpaceCheck, target_path: &Path) -> String {
+    format!(
+        "Not enough disk space: setup would copy {} but only {} is free on {}",
+        output::format_size(check.required),
+        output::format_size(check.available.unwrap_or(0)),
+        target_path.display()
+    )
+}
+
+/// Number of operations listed when a plan exceeds `confirm_above`.
+const LARGEST_OPERATIONS_SHOWN: usize = 5;
+
+/// Ask before copying more than the `[setup] confirm_above` threshold.
+///
+/// Shows the largest operations and prompts, unless `--yes` was given.
+///
+/// # Errors
+///
+/// * If the threshold is exceeded in non-interactive mode without `--yes`
+/// * If the user declines
+fn confirm_large_copy(
+    operations: &[PlannedOperation],
+    options: FileOpOptions,
+    global_config: &worktree_setup_config::GlobalConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(threshold) = global_config.setup.confirm_above else {
+        return Ok(());
+    };
+    let total = planned_copy_bytes(operations);
+    if total <= threshold.0 || options.assume_yes {
+        return Ok(());
+    }
+
+    output::print_warning(&format!(
+        "Setup will copy {}, more than confirm_above ({}). Largest operations:",
+        output::format_size(total),
+        output::format_size(threshold.0)
+    ));
+    let mut largest: Vec<&PlannedOperation> = operations
+        .iter()
+        .filter(|op| !op.will_skip && op.byte_count > 0)
+        .collect();
+    largest.sort_by_key(|op| std::cmp::Reverse(op.byte_count));
+    for op in largest.iter().take(LARGEST_OPERATIONS_SHOWN) {
+        say!(
+            "  {:>10}  {}",
+            output::format_size(op.byte_count),
+            op.display_path
+        );
+    }
+    say!();
+
+    if options.non_interactive {
+        return Err("Copy size exceeds confirm_above; pass --yes to proceed".into());
+    }
+    if !interactive::confirm_large_copy(total)? {
+        return Err("Cancelled: copy size exceeds confirm_above".into());
+    }
+    Ok(())
+}
+
+/// Skip destructive operations when the target worktree is on a branch
+/// matching `[setup] protected_branches`, warning how many were skipped.
+///
+/// Overwrites, forced symlinks, and unstaged copies are marked skipped so
+/// they cannot clobber files in a protected checkout; everything else runs
+/// as usual. Operations that are already skipped are left alone, so this
+/// can run again on a plan it has seen.
+fn skip_protected_branch_ops(
+    operations: &mut [PlannedOperation],
+    target_path: &Path,
+    setup: &worktree_setup_config::SetupConfig,
+) {
+    let Some((branch, pattern, skipped)) =
+        mark_protected_branch_ops(operations, target_path, setup)
+    else {
+        return;
+    };
+    if skipped > 0 {
+        output::print_warning(&format!(
+            "{} is on protected branch {branch} (matches \"{pattern}\"); skipping {skipped} operation{} that would replace existing files",
+            target_path.display(),
+            if skipped == 1 { "" } else { "s" }
+        ));
+    }
+}
+
+/// Mark destructive operations skipped when the target worktree is on a
+/// protected branch, without printing anything.
+///
+/// Returns the branch, the pattern it matched, and how many operations
+/// were newly skipped, or `None` if the branch is not protected.
+fn mark_protected_branch_ops(
+    operations: &mut [PlannedOperation],
+    target_path: &Path,
+    setup: &worktree_setup_config::SetupConfig,
+) -> Option<(String, String, usize)> {
+    setup.protected_branches.as_ref()?;
+    let repo = worktree_setup_git::open_repo(target_path).ok()?;
+    let branch = get_current_branch(&repo).ok().flatten()?;
+    let pattern = setup.protected_branch_pattern(&branch)?.to_string();
+
+    let mut skipped = 0;
+    for op in operations.iter_mut().filter(|op| op.is_destructive()) {
+        op.will_skip = true;
+        op.skip_reason = Some(format!("protected branch {branch}"));
+        skipped += 1;
+    }
+    Some((branch, pattern, skipped))
+}
+
+/// Files listed by name when warning about local edits; the rest are
+/// counted.
+const LOCAL_EDITS_SHOWN: usize = 5;
+
+/// Guard files edited in the target worktree since the last setup from
+/// the destructive operations that would replace them.
+///
+/// Edits are grouped by the config whose operations would replace them.
+/// Interactive runs ask once per config; declined operations, and all of
+/// them in non-interactive and dry runs, are skipped with `local edits`.
+fn confirm_local_edits(
+    operations: &mut [PlannedOperation],
+    target_path: &Path,
+    options: FileOpOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::BTreeMap;
+    use std::fmt::Write as _;
+
+    let Ok(Some(manifest)) = Manifest::path_for(target_path).and_then(|path| Manifest::load(&path))
+    else {
+        return Ok(());
+    };
+
+    let mut by_config: BTreeMap<String, Vec<(usize, Vec<String>)>> = BTreeMap::new();
+    for (index, op) in operations.iter().enumerate() {
+        if !op.is_destructive() {
             continue;
         }
+        let edits = manifest.local_edits(op, target_path);
+        if !edits.is_empty() {
+            let config = op
+                .origin
+                .as_ref()
+                .map_or_else(|| "unstaged files".to_string(), |o| o.config.clone());
+            by_config.entry(config).or_default().push((index, edits));
+        }
+    }
 
-        let needs_progress_bar = op.is_directory && op.file_count > 1;
+    for (config, edited_ops) in by_config {
+        let files: Vec<&str> = edited_ops
+            .iter()
+            .flat_map(|(_, edits)| edits.iter().map(String::as_str))
+            .collect();
+        let mut listed = files
+            .iter()
+            .take(LOCAL_EDITS_SHOWN)
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ");
+        if files.len() > LOCAL_EDITS_SHOWN {
+            let _ = write!(listed, " and {} more", files.len() - LOCAL_EDITS_SHOWN);
+        }
+        output::print_warning(&format!(
+            "{config} would replace {} file{} edited since the last setup: {listed}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" }
+        ));
 
-        if needs_progress_bar {
-            let bar = progress_mgr.create_file_bar(&op.display_path, op.file_count);
+        let replace = !options.non_interactive
+            && !options.dry_run
+            && interactive::confirm_replace_local_edits(&config, files.len())?;
+        if !replace {
+            for (index, _) in &edited_ops {
+                operations[*index].will_skip = true;
+                operations[*index].skip_reason = Some("local edits".to_string());
+            }
+            if options.non_interactive {
+                say!(
+                    "{}",
+                    "Skipped; pass --force-destructive to replace them.".dimmed()
+                );
+            }
+        }
+    }
+    Ok(())
+}
 
-            let result = execute_operation(op, |completed, _total| {
-                bar.set_position(completed);
-            })?;
+/// `copy` entries at least this large get a hint to symlink them instead
+/// when the filesystem cannot clone files.
+const LINK_HINT_BYTES: u64 = 1024 * 1024 * 1024;
 
-            bar.finish_and_clear();
+/// Suggest symlinking large `copy` entries that will be copied byte by
+/// byte because the target filesystem cannot clone them.
+fn suggest_links_for_large_copies(
+    operations: &[PlannedOperation],
+    main_worktree_path: &Path,
+    target_path: &Path,
+) {
+    let large: Vec<(&PlannedOperation, &str)> = operations
+        .iter()
+        .filter(|op| !op.will_skip && op.byte_count >= LINK_HINT_BYTES)
+        .filter_map(|op| {
+            let origin = op.origin.as_ref().filter(|o| o.field == "copy")?;
+            Some((op, origin.config.as_str()))
+        })
+        .collect();
+    if large.is_empty() || worktree_setup_copy::supports_reflink(main_worktree_path, target_path) {
+        return;
+    }
 
-            let result_str = format_result_string(result, op.operation_type);
-            progress_mgr.print_result_with_count(&op.display_path, &result_str, op.file_count);
-        } else {
-            let result = execute_operation(op, |_, _| {})?;
-            let result_str = format_result_string(result, op.operation_type);
-            progress_mgr.print_result(&op.display_path, &result_str, true);
+    for (op, config) in large {
+        output::print_file_warning(
+            Path::new(config),
+            &format!(
+                "{}: copying {} in full, since this filesystem cannot clone files. If worktrees can share it, moving it from `copy` to `symlinks` in {config} makes setup much faster",
+                op.display_path,
+                output::format_size(op.byte_count)
+            ),
+        );
+    }
+}
+
+/// Warn about `copyFromRef` files that `.gitattributes` filters (e.g. Git
+/// LFS) on checkout but that are written as committed because the entry
+/// does not set `filters`. Warns once per entry.
+fn warn_unapplied_filters(operations: &[PlannedOperation]) {
+    use std::collections::BTreeMap;
+
+    // (config, entry index) -> (first file, its filter, number of files)
+    let mut entries: BTreeMap<(&str, usize), (&str, &str, usize)> = BTreeMap::new();
+    for op in operations.iter().filter(|op| !op.will_skip) {
+        let (Some(source), Some(origin)) = (&op.ref_source, &op.origin) else {
+            continue;
+        };
+        let Some(filter) = &source.unapplied_filter else {
+            continue;
+        };
+        entries
+            .entry((origin.config.as_str(), origin.index))
+            .or_insert((op.display_path.as_str(), filter.as_str(), 0))
+            .2 += 1;
+    }
+
+    for ((config, _), (path, filter, count)) in entries {
+        let others = match count {
+            1 => String::new(),
+            2 => " (and 1 other file)".to_string(),
+            n => format!(" (and {} other files)", n - 1),
+        };
+        output::print_file_warning(
+            Path::new(config),
+            &format!(
+                "{path}{others}: .gitattributes sets {filter}, but copyFromRef writes the committed content as-is. Set `filters = true` on the entry to write it as a checkout would"
+            ),
+        );
+    }
+}
+
+/// Report every entry whose source does not exist (`--check-sources`).
+///
+/// Planning skips such entries as "not found"; this turns each into an
+/// error attached to its config, so stale entries fail CI.
+///
+/// # Errors
+///
+/// * If any operation was skipped because its source was not found
+fn check_missing_sources(operations: &[PlannedOperation]) -> Result<(), OperationError> {
+    let mut count = 0;
+    for op in operations {
+        let Some(reason) = op
+            .skip_reason
+            .as_deref()
+            .filter(|r| r.starts_with("not found"))
+        else {
+            continue;
+        };
+        let entry = op.origin.as_ref().map_or_else(String::new, |origin| {
+            format!("{}[{}] ", origin.field, origin.index)
+        });
+        let message = format!("{entry}{}: source {reason}", op.display_path);
+        let config = op
+            .origin
+            .as_ref()
+            .map_or("", |origin| origin.config.as_str());
+        output::print_file_error(Path::new(config), &message);
+        count += 1;
+    }
+    if count > 0 {
+        return Err(OperationError::MissingSources { count });
+    }
+    Ok(())
+}
+
+/// Number of entries in a config that planning walks through.
+const fn config_op_count(config: &LoadedConfig) -> usize {
+    config.config.symlinks.len()
+        + config.config.copy.len()
+        + config.config.overwrite.len()
+        + config.config.copy_glob.len()
+        + config.config.templates.len()
+        + config.config.copy_from_ref.len()
+}
+
+/// Plan file operations for every selected config.
+///
+/// Each config gets `base_options` with `allow_path_escape` resolved from
+/// the config, falling back to the global security setting, and glob
+/// searches limited to `[setup] operation_timeout`. `on_progress`
+/// receives the operation index across all configs, the path being scanned,
+/// and the running file count during directory scans. The combined plan
+/// is sorted by entry `order`, keeping config order among equal orders.
+fn plan_selected_operations(
+    selected_configs: &[&LoadedConfig],
+    main_worktree_path: &Path,
+    target_path: &Path,
+    base_options: &ApplyConfigOptions,
+    global_config: &worktree_setup_config::GlobalConfig,
+    on_progress: &(dyn Fn(usize, &str, Option<u64>) + Sync),
+) -> Result<Vec<PlannedOperation>, OperationError> {
+    use rayon::prelude::*;
+
+    let offsets: Vec<usize> = selected_configs
+        .iter()
+        .scan(0usize, |offset, config| {
+            let current = *offset;
+            *offset += config_op_count(config);
+            Some(current)
+        })
+        .collect();
+    // One set of providers, so an archive shared by configs is extracted once.
+    let source_providers = base_options.source_providers.clone().unwrap_or_default();
+
+    // Configs are planned concurrently; collecting keeps them in order.
+    let planned: Vec<Result<Vec<PlannedOperation>, OperationError>> = selected_configs
+        .par_iter()
+        .zip(offsets)
+        .map(|(config, offset)| {
+            // Resolve allow_path_escape per-config: per-config overrides global
+            let options = ApplyConfigOptions {
+                allow_path_escape: config
+                    .config
+                    .allow_path_escape
+                    .unwrap_or(global_config.security.allow_path_escape),
+                source_providers: Some(Arc::clone(&source_providers)),
+                glob_timeout: global_config
+                    .setup
+                    .operation_timeout
+                    .map(Duration::from_secs),
+                ..base_options.clone()
+            };
+
+            plan_operations_with_progress(
+                config,
+                main_worktree_path,
+                target_path,
+                &options,
+                &|current, _total, path, file_count| {
+                    on_progress(offset + current, path, file_count);
+                },
+            )
+        })
+        .collect();
+
+    let mut all_operations = Vec::new();
+    for ops in planned {
+        all_operations.extend(ops?);
+    }
+    sort_by_order(&mut all_operations);
+    Ok(all_operations)
+}
+
+/// Plan copying the main worktree's `core.hooksPath` directory
+/// (`[setup] install_hooks`).
+fn plan_hooks_copy(main_worktree_path: &Path, target_path: &Path) -> Option<PlannedOperation> {
+    let repo = worktree_setup_git::open_repo(main_worktree_path).ok()?;
+    let hooks_path = worktree_setup_git::get_hooks_path(&repo)?;
+    plan_hooks_operation(&hooks_path, main_worktree_path, target_path)
+}
+
+/// Whether unstaged files should be copied for the selected configs.
+fn should_copy_unstaged(
+    selected_configs: &[&LoadedConfig],
+    copy_unstaged_override: Option<bool>,
+) -> bool {
+    selected_configs.iter().any(|c| {
+        copy_unstaged_override
+            .or(c.config.copy_unstaged)
+            .unwrap_or(false)
+    })
+}
+
+/// Which unstaged files to copy for the selected configs, or `None` if
+/// none of them copies unstaged files.
+fn unstaged_filter(
+    selected_configs: &[&LoadedConfig],
+    copy_unstaged_override: Option<bool>,
+    setup: &SetupConfig,
+) -> Option<UnstagedFilter> {
+    should_copy_unstaged(selected_configs, copy_unstaged_override).then(|| {
+        UnstagedFilter::for_configs(
+            selected_configs,
+            copy_unstaged_override,
+            setup.unstaged_scope.unwrap_or_default(),
+        )
+    })
+}
+
+/// Plan copies of the unstaged and untracked files from the main worktree
+/// that `filter` allows.
+fn plan_unstaged_files(
+    main_worktree_path: &Path,
+    target_path: &Path,
+    worktree_ignore: &WorktreeIgnore,
+    filter: &UnstagedFilter,
+) -> Result<Vec<PlannedOperation>, GitError> {
+    let repo = worktree_setup_git::open_repo(main_worktree_path)?;
+    let mut unstaged_files = get_unstaged_and_untracked_files(&repo)?;
+    let found = unstaged_files.len();
+    unstaged_files.retain(|file| filter.allows(file));
+    if unstaged_files.len() < found {
+        log::debug!(
+            "Left out {} unstaged files outside the configs' unstaged scope",
+            found - unstaged_files.len()
+        );
+    }
+    Ok(plan_unstaged_operations(
+        &unstaged_files,
+        main_worktree_path,
+        target_path,
+        worktree_ignore,
+    ))
+}
+
+/// Record the applied configs for a worktree in the repository state store.
+fn record_worktree_state(
+    selected_configs: &[&LoadedConfig],
+    target_path: &Path,
+) -> Result<(), OperationError> {
+    let store = StateStore::for_worktree(target_path)?;
+    let mut state = store
+        .load(target_path)?
+        .unwrap_or_else(|| WorktreeState::new(target_path));
+    for config in selected_configs {
+        state.record(config);
+    }
+    store.save(&state)
+}
+
+/// Attach `--label`s to the worktree at `target_path` in the state store.
+///
+/// Failures are reported as warnings so setup still runs.
+fn record_labels(target_path: &Path, labels: &[Label]) {
+    if labels.is_empty() {
+        return;
+    }
+    let result = StateStore::for_worktree(target_path).and_then(|store| {
+        let mut state = store
+            .load(target_path)?
+            .unwrap_or_else(|| WorktreeState::new(target_path));
+        state.add_labels(labels);
+        store.save(&state)
+    });
+    if let Err(e) = result {
+        output::print_warning(&format!("Failed to save worktree labels: {e}"));
+    }
+}
+
+/// Execute a single planned operation and print its result line.
+///
+/// Directory operations with more than one file get a progress bar. With
+/// `operation_timeout` set, the operation is cancelled once it elapses and
+/// [`OperationError::Timeout`] is returned. Files that vanish from a
+/// directory's source mid-copy are skipped with a warning; with
+/// `rescan_copies`, files added to it meanwhile are copied afterwards.
+fn execute_with_progress(
+    op: &worktree_setup_operations::PlannedOperation,
+    progress_mgr: &ProgressManager,
+    setup: &SetupConfig,
+    options: FileOpOptions,
+) -> Result<OperationOutcome, OperationError> {
+    let timeout = setup.operation_timeout.map(Duration::from_secs);
+    let run = |on_progress: &(dyn Fn(u64, u64) + Sync)| {
+        timeout.map_or_else(
+            || execute_operation_outcome(op, on_progress),
+            |timeout| execute_operation_with_timeout(op, timeout, on_progress),
+        )
+    };
+
+    let needs_progress_bar = op.is_directory && op.file_count > 1;
+    // Large single files are streamed when they cannot be reflinked.
+    let needs_bytes_bar = !op.is_directory
+        && !op.will_skip
+        && op.template.is_none()
+        && op.byte_count >= STREAMING_COPY_THRESHOLD;
+    let show_counts = op.is_directory && (op.file_count > 1 || op.filtered_count > 0);
+
+    let files_before = progress_mgr.files_done();
+    let progress_name = op.label().map_or_else(
+        || op.display_path.clone(),
+        |label| format!("{} — {label}", op.display_path),
+    );
+    progress_mgr.start_operation(&progress_name);
+    let outcome = if needs_progress_bar {
+        progress_mgr.start_directory(&progress_name, op.file_count);
+
+        let result = run(&|completed, _total| {
+            progress_mgr.set_directory_done(completed);
+            progress_mgr.set_files_done(files_before + completed);
+        });
+
+        progress_mgr.finish_directory();
+        result
+    } else if needs_bytes_bar {
+        progress_mgr.start_bytes(&progress_name, op.byte_count);
+        let result = run(&|bytes, _total| progress_mgr.set_bytes_done(bytes));
+        progress_mgr.finish_bytes();
+        result
+    } else {
+        run(&|_, _| {})
+    };
+    progress_mgr.finish_operation();
+    let outcome = outcome.or_else(|e| retry_denied_with_sudo(op, e, progress_mgr, options))?;
+    progress_mgr.set_files_done(files_before + op.file_count);
+    let result = outcome.result;
+
+    let result_str = format_result_string(result, op.operation_type);
+    if show_counts {
+        progress_mgr.print_result_with_count(
+            &op.display_path,
+            &result_str,
+            op.file_count,
+            op.symlink_count,
+            op.filtered_count,
+            op.label(),
+        );
+    } else {
+        progress_mgr.print_result(&op.display_path, &result_str, true, op.label());
+    }
+    let copied = matches!(
+        result,
+        worktree_setup_operations::OperationResult::Created
+            | worktree_setup_operations::OperationResult::Overwritten
+    );
+    if copied && op.special_count > 0 {
+        progress_mgr.warn(
+            Some(&op.source),
+            &format!(
+                "Skipped {} special file{} (sockets or device nodes) in {}",
+                op.special_count,
+                if op.special_count == 1 { "" } else { "s" },
+                op.display_path
+            ),
+        );
+    }
+    if outcome.files_vanished > 0 {
+        progress_mgr.warn(
+            Some(&op.source),
+            &format!(
+                "Skipped {} file{} deleted from {} while it was being copied",
+                outcome.files_vanished,
+                if outcome.files_vanished == 1 { "" } else { "s" },
+                op.display_path
+            ),
+        );
+    }
+    if copied && setup.rescan_copies == Some(true) {
+        let added = rescan_operation(op)?;
+        if added > 0 {
+            progress_mgr.info(&format!(
+                "Copied {added} file{} added to {} during the copy",
+                if added == 1 { "" } else { "s" },
+                op.display_path
+            ));
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Copy the paths a directory copy was denied with `sudo` when
+/// `--sudo-fallback` is set; any other error is returned as is.
+fn retry_denied_with_sudo(
+    op: &PlannedOperation,
+    error: OperationError,
+    progress_mgr: &ProgressManager,
+    options: FileOpOptions,
+) -> Result<OperationOutcome, OperationError> {
+    let Some(denied) = denied_paths(&error).filter(|_| options.sudo_fallback) else {
+        return Err(error);
+    };
+    progress_mgr.warn(
+        Some(&op.source),
+        &format!(
+            "Permission denied for {} path{} in {}; copying with sudo",
+            denied.len(),
+            if denied.len() == 1 { "" } else { "s" },
+            op.display_path
+        ),
+    );
+    progress_mgr.suspend(|| copy_denied_with_sudo(op, denied, options.non_interactive))?;
+    Ok(OperationResult::Created.into())
+}
+
+/// Load the setup manifest for a worktree so new operations can be merged in.
+///
+/// Returns the manifest path (`None` if the target is not a git worktree)
+/// and the existing manifest, or an empty one if none exists or it cannot
+/// be read.
+fn load_setup_manifest(target_path: &Path) -> (Option<PathBuf>, Manifest) {
+    let path = match Manifest::path_for(target_path) {
+        Ok(path) => path,
+        Err(e) => {
+            log::debug!("No manifest location for {}: {e}", target_path.display());
+            return (None, Manifest::new());
+        }
+    };
+
+    let manifest = match Manifest::load(&path) {
+        Ok(manifest) => manifest.unwrap_or_else(Manifest::new),
+        Err(e) => {
+            log::warn!("Ignoring unreadable manifest: {e}");
+            Manifest::new()
         }
-    }
+    };
 
-    // Clear any remaining progress bars
-    progress_mgr.clear();
+    (Some(path), manifest)
+}
 
-    Ok(())
+/// Where post-setup command output goes.
+///
+/// Inherits stdout, unless the reporter writes machine-readable output
+/// there, in which case command output is sent to stderr instead.
+fn child_stdout() -> std::process::Stdio {
+    if reporter::get().is_machine_readable() {
+        std::io::stderr().into()
+    } else {
+        std::process::Stdio::inherit()
+    }
 }
 
 /// Run post-setup commands in the target directory.
+///
+/// With `print_only` (`--print-commands`, `--dry-run`), the commands are
+/// listed with the directory and shell they would run in instead. With
+/// `quiet` (`--quiet-post-setup`), their output is captured and only that
+/// many of the last lines are shown for failing commands.
 fn run_post_setup_commands(
     commands: &[&str],
     target_path: &Path,
+    print_only: bool,
+    quiet: Option<usize>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if commands.is_empty() {
         return Ok(());
     }
 
-    println!("Running post-setup commands:");
-    for cmd in commands {
-        output::print_command(cmd);
+    if print_only {
+        say!("Post-setup commands (not run):");
+        for cmd in commands {
+            output::print_command(cmd);
+        }
+        say!(
+            "{}",
+            format!(
+                "  in {} via `sh -c`, with the current environment",
+                target_path.display()
+            )
+            .dimmed()
+        );
+        say!();
+        return Ok(());
+    }
+
+    timing::time(Phase::PostSetup, || {
+        if let Some(tail_lines) = quiet {
+            return post_setup::run_quietly(commands, target_path, tail_lines);
+        }
+
+        say!("Running post-setup commands:");
+        for cmd in commands {
+            output::print_command(cmd);
 
-        let mut child = Command::new("sh")
-            .args(["-c", cmd])
-            .current_dir(target_path)
-            .stdin(std::process::Stdio::inherit())
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .spawn()?;
+            let mut child = Command::new("sh")
+                .args(["-c", cmd])
+                .current_dir(target_path)
+                .stdin(std::process::Stdio::inherit())
+                .stdout(child_stdout())
+                .stderr(std::process::Stdio::inherit())
+                .spawn()?;
 
-        let status = child.wait()?;
+            let status = child.wait()?;
 
-        if !status.success() {
-            output::print_warning(&format!("Command failed: {cmd}"));
+            if !status.success() {
+                output::print_warning(&format!("Post-setup command failed: {cmd}"));
+            }
         }
-    }
-    println!();
+        say!();
 
-    Ok(())
+        Ok(())
+    })
 }
 
-/// Collect unique post-setup commands from configs.
-fn collect_post_setup_commands<'a>(configs: &[&'a LoadedConfig]) -> Vec<&'a str> {
+/// Collect unique post-setup commands from configs, followed by `detected`
+/// install commands (see [`detect::detected_post_setup_commands`]).
+fn collect_post_setup_commands<'a>(
+    configs: &[&'a LoadedConfig],
+    detected: &'a [String],
+) -> Vec<&'a str> {
     let mut unique_commands: Vec<&str> = Vec::new();
-    for config in configs {
-        for cmd in &config.config.post_setup {
-            let cmd_str = cmd.as_str();
-            if !unique_commands.contains(&cmd_str) {
-                unique_commands.push(cmd_str);
-            }
+    let configured = configs.iter().flat_map(|c| &c.config.post_setup);
+    for cmd in configured.chain(detected) {
+        let cmd_str = cmd.as_str();
+        if !unique_commands.contains(&cmd_str) {
+            unique_commands.push(cmd_str);
         }
     }
     unique_commands
@@ -445,13 +1952,21 @@ fn resolve_overwrite(overwrite_flag: bool, profile: Option<&ResolvedProfile>) ->
 
 /// Determine what operations to run in `setup` mode.
 ///
-/// Returns `(run_files, overwrite_existing, run_post_setup)`.
+/// With `--post-setup-only`, only the post-setup commands run, without the
+/// checklist. Returns `(run_files, overwrite_existing, run_post_setup)`.
 fn determine_setup_operations(
     args: &SetupArgs,
     resolved_profile: Option<&ResolvedProfile>,
     is_secondary_worktree: bool,
     unique_commands: &[&str],
 ) -> Result<(bool, bool, bool), Box<dyn std::error::Error>> {
+    if args.post_setup_only {
+        if unique_commands.is_empty() {
+            say!("No post-setup commands in the selected configs.");
+        }
+        return Ok((false, false, !unique_commands.is_empty()));
+    }
+
     // Resolve pre-determined values from CLI flags + profile
     let files_determined: Option<bool> = if args.no_files { Some(false) } else { None };
 
@@ -491,10 +2006,26 @@ fn determine_setup_operations(
 ///
 /// Applies worktree configs to an existing directory. On a secondary worktree,
 /// this runs file operations and post-setup commands. On the main worktree or
-/// a regular clone, only post-setup commands are run.
+/// a regular clone, or with `--post-setup-only`, only post-setup commands are
+/// run.
 fn run_setup(args: &SetupArgs) -> Result<(), Box<dyn std::error::Error>> {
     let cwd = env::current_dir()?;
     let target_path = resolve_setup_target(&cwd, args.target_path.as_ref());
+    if args.resume {
+        return resume::run(
+            &target_path,
+            FileOpOptions {
+                progress: args.progress_mode(),
+                assume_yes: args.yes,
+                non_interactive: args.non_interactive,
+                transactional: args.transactional,
+                continue_on_error: args.continue_on_error,
+                verify: args.verify,
+                sudo_fallback: args.sudo_fallback,
+                ..FileOpOptions::default()
+            },
+        );
+    }
 
     // Discover repository from the target directory
     let repo = discover_repo(&target_path)?;
@@ -502,19 +2033,22 @@ fn run_setup(args: &SetupArgs) -> Result<(), Box<dyn std::error::Error>> {
 
     output::print_header("Worktree Setup");
     output::print_repo_info(&repo_root.to_string_lossy());
-    println!();
+    say!();
+    if !args.dry_run {
+        record_labels(&target_path, &args.labels);
+    }
 
     // Determine if the target is a secondary worktree
     let main_worktree = get_main_worktree(&repo)?;
     let is_secondary_worktree = is_secondary(&target_path, &main_worktree.path);
 
-    if !is_secondary_worktree {
+    if !is_secondary_worktree && !args.post_setup_only {
         output::print_info("Not a secondary worktree. File operations will be skipped.");
-        println!();
+        say!();
     }
 
     // Discover and load configs
-    let all_configs = discover_and_load_configs(&repo_root)?;
+    let mut all_configs = discover_and_load_configs(&repo_root, args.submodules)?;
 
     if all_configs.is_empty() {
         output::print_warning("No configs found. Nothing to do.");
@@ -522,33 +2056,37 @@ fn run_setup(args: &SetupArgs) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Resolve profiles if --profile was provided
-    let resolved_profile = if args.profile.is_empty() {
-        None
-    } else {
-        Some(resolve_and_print_profile(
-            &args.profile,
-            &all_configs,
-            &repo_root,
-        )?)
-    };
+    let resolved_profile =
+        resolve_profile_and_root_defaults(&args.profile, &mut all_configs, &repo_root)?;
 
-    // Select configs: profile overrides normal selection
+    // Select configs: profile overrides normal selection. Configs applied
+    // to this worktree before start checked.
+    let preselected = applied_config_preselection(&all_configs, &target_path);
     let selected_indices = select_configs_or_profile(
         &all_configs,
         args.non_interactive,
         &args.configs,
         resolved_profile.as_ref(),
+        preselected.as_deref(),
     )?;
     let Some(selected_indices) = selected_indices else {
-        println!("No configs selected. Exiting.");
+        say!("No configs selected. Exiting.");
         return Ok(());
     };
 
     let selected_configs: Vec<&LoadedConfig> =
         selected_indices.iter().map(|&i| &all_configs[i]).collect();
 
+    let global_config = load_global_config(Some(&repo_root))?;
+
     // Collect post-setup commands for display in the checklist
-    let unique_commands = collect_post_setup_commands(&selected_configs);
+    let detected = detect::detected_post_setup_commands(
+        &selected_configs,
+        &main_worktree.path,
+        &global_config,
+        !args.non_interactive,
+    );
+    let unique_commands = collect_post_setup_commands(&selected_configs, &detected);
 
     // Determine what to run
     let (run_files, overwrite_existing, run_post_setup) = determine_setup_operations(
@@ -560,60 +2098,246 @@ fn run_setup(args: &SetupArgs) -> Result<(), Box<dyn std::error::Error>> {
 
     // Nothing selected
     if !run_files && !run_post_setup {
-        println!("Nothing selected. Exiting.");
+        say!("Nothing selected. Exiting.");
         return Ok(());
     }
 
-    // Execute file operations
     if run_files {
-        let copy_unstaged_override = args.copy_unstaged_override().or_else(|| {
-            resolved_profile
-                .as_ref()
-                .and_then(|p| p.defaults.copy_unstaged)
-        });
-
-        let global_config = load_global_config(Some(&repo_root))?;
-
-        println!("\nApplying file operations to: {}", target_path.display());
-        println!("Source (main worktree): {}\n", main_worktree.path.display());
-
-        execute_file_operations(
+        apply_setup_files(
+            args,
+            resolved_profile.as_ref(),
             &selected_configs,
             &main_worktree.path,
             &target_path,
-            copy_unstaged_override,
             overwrite_existing,
-            args.should_show_progress(),
             &global_config,
         )?;
-
-        println!();
     }
 
-    // Run post-setup commands
+    // Run post-setup commands, applying per-command filtering from the
+    // profile if present
     if run_post_setup {
-        // Apply per-command filtering from profile if present
         let resolved_cmds = resolve_post_setup_commands(
             args.no_install,
             resolved_profile.as_ref(),
             &unique_commands,
         );
         let cmds_to_run = resolved_cmds.as_deref().unwrap_or(&unique_commands);
-        run_post_setup_commands(cmds_to_run, &target_path)?;
+        let print_only = args.print_commands || args.dry_run;
+        run_post_setup_commands(cmds_to_run, &target_path, print_only, args.quiet_post_setup)?;
     }
 
+    if args.stats {
+        timing::print_stats();
+    }
+    if args.dry_run {
+        say!("{}", "Dry run — nothing was changed.".dimmed());
+        return Ok(());
+    }
     output::print_success();
     Ok(())
 }
 
+/// Apply the file operations of `setup` to `target_path`.
+fn apply_setup_files(
+    args: &SetupArgs,
+    resolved_profile: Option<&ResolvedProfile>,
+    selected_configs: &[&LoadedConfig],
+    main_worktree_path: &Path,
+    target_path: &Path,
+    overwrite_existing: bool,
+    global_config: &worktree_setup_config::GlobalConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let copy_unstaged_override = args
+        .copy_unstaged_override()
+        .or_else(|| resolved_profile.and_then(|p| p.defaults.copy_unstaged));
+
+    say!("\nApplying file operations to: {}", target_path.display());
+    say!("Source (main worktree): {}\n", main_worktree_path.display());
+
+    if args.submodules && !args.dry_run {
+        init_target_submodules(target_path)?;
+    }
+
+    execute_file_operations(
+        selected_configs,
+        main_worktree_path,
+        target_path,
+        FileOpOptions {
+            copy_unstaged_override,
+            overwrite_existing,
+            progress: args.progress_mode(),
+            assume_yes: args.yes,
+            non_interactive: args.non_interactive,
+            transactional: args.transactional,
+            continue_on_error: args.continue_on_error,
+            verify: args.verify,
+            count_cache: !args.no_count_cache,
+            skip_space_check: args.skip_space_check,
+            max_depth: args.max_depth,
+            sudo_fallback: args.sudo_fallback,
+            dry_run: args.dry_run,
+            check_sources: args.check_sources,
+            force_destructive: args.force_destructive,
+        },
+        global_config,
+    )?;
+
+    say!();
+
+    Ok(())
+}
+
+/// Run the `status` subcommand.
+///
+/// Prints the manifest recorded by the last setup of a worktree and, with
+/// `--changes`, what changed on either side since then.
+fn run_status(args: &StatusArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let target_path = resolve_setup_target(&cwd, args.target_path.as_ref());
+
+    let repo = discover_repo(&target_path)?;
+    let repo_root = get_repo_root(&repo)?;
+    let main_worktree = get_main_worktree(&repo)?;
+
+    output::print_header("Worktree Status");
+    output::print_repo_info(&repo_root.to_string_lossy());
+    say!("Worktree: {}\n", target_path.display());
+
+    let state = StateStore::for_repository(&repo).load(&target_path)?;
+    let manifest = Manifest::load(&Manifest::path_for(&target_path)?)?;
+
+    if state.is_none() && manifest.is_none() {
+        output::print_info(
+            "No setup recorded for this worktree. Run `worktree-setup setup` first.",
+        );
+        return Ok(());
+    }
+
+    let configs = load_configs_quietly(&repo_root)?;
+
+    if let Some(state) = &state {
+        output::print_applied_configs(&applied_config_rows(state, &configs));
+    }
+
+    if let Some(manifest) = &manifest {
+        output::print_manifest_summary(manifest);
+
+        if args.changes {
+            let changes = manifest.changes(&main_worktree.path, &target_path, &configs);
+            output::print_manifest_changes(&changes);
+        }
+    }
+
+    Ok(())
+}
+
+/// Load every config in the repository without listing them, warning
+/// about the ones that fail to load.
+fn load_configs_quietly(repo_root: &Path) -> Result<Vec<LoadedConfig>, Box<dyn std::error::Error>> {
+    let config_paths = discover_configs(repo_root)?;
+    warn_changed_ts_configs(&config_paths, repo_root);
+
+    let mut configs = Vec::new();
+    let loaded = load_configs_parallel(&config_paths, repo_root);
+    for (path, loaded) in config_paths.into_iter().zip(loaded) {
+        match loaded {
+            Ok(config) => configs.push(config),
+            Err(e) => {
+                output::print_file_warning(
+                    path.strip_prefix(repo_root).unwrap_or(&path),
+                    &format!("Failed to load {}: {e}", path.display()),
+                );
+            }
+        }
+    }
+    warn_unknown_keys(&configs, repo_root);
+    warn_ignored_defaults(&apply_root_defaults(&mut configs));
+    Ok(configs)
+}
+
+/// Warn about TypeScript and JavaScript configs about to run for the first
+/// time since they were added or edited, since loading one runs its code.
+fn warn_changed_ts_configs(config_paths: &[PathBuf], repo_root: &Path) {
+    for path in changed_ts_configs(config_paths, repo_root) {
+        output::print_file_warning(&path, &ts_change_warning(&path));
+    }
+}
+
+/// TypeScript and JavaScript configs in `config_paths` that are new or
+/// changed since they last ran, relative to `repo_root`, recording their
+/// current hashes.
+fn changed_ts_configs(config_paths: &[PathBuf], repo_root: &Path) -> Vec<PathBuf> {
+    let Some(store) = ts_config_store() else {
+        return Vec::new();
+    };
+    record_ts_configs(config_paths, &store)
+        .into_iter()
+        .map(|path| path.strip_prefix(repo_root).unwrap_or(&path).to_path_buf())
+        .collect()
+}
+
+/// Warning for a TypeScript or JavaScript config about to run for the
+/// first time since it was added or edited.
+fn ts_change_warning(relative: &Path) -> String {
+    format!(
+        "Config {} is new or changed since it last ran here; loading it runs its code",
+        relative.display()
+    )
+}
+
+/// Warn about keys in the files of `configs` that no config field reads,
+/// which are often misspellings.
+fn warn_unknown_keys(configs: &[LoadedConfig], repo_root: &Path) {
+    for key in configs.iter().flat_map(|c| &c.config.unknown_keys) {
+        let file = key.file.strip_prefix(repo_root).unwrap_or(&key.file);
+        let relative = UnknownKey {
+            file: file.to_path_buf(),
+            ..key.clone()
+        };
+        output::print_file_warning(file, &relative.to_string());
+    }
+}
+
+/// Warn about `defaults` tables outside the root config, which are ignored.
+fn warn_ignored_defaults(paths: &[String]) {
+    for path in paths {
+        output::print_file_warning(
+            Path::new(path),
+            &format!("Ignoring `defaults` in {path}: only the root config's defaults apply"),
+        );
+    }
+}
+
+/// Applied configs of `state` as `(config, applied_at, changed)`, where
+/// `changed` tells whether the config was edited after it was applied.
+fn applied_config_rows(
+    state: &WorktreeState,
+    configs: &[LoadedConfig],
+) -> Vec<(String, u64, bool)> {
+    state
+        .configs
+        .iter()
+        .map(|(path, applied)| {
+            let changed = configs
+                .iter()
+                .find(|c| &c.relative_path == path)
+                .and_then(|c| state.config_changed(c))
+                .unwrap_or(false);
+            (path.clone(), applied.applied_at, changed)
+        })
+        .collect()
+}
+
 /// Resolve the target path for the `setup` subcommand.
 ///
-/// Defaults to the current working directory if no path is provided.
-/// Exits the process if the resolved path does not exist.
+/// Defaults to the current working directory (or the worktree named by
+/// `--repo` or `GIT_DIR`/`GIT_WORK_TREE`) if no path is provided. Exits the process if
+/// the resolved path does not exist.
 #[must_use]
 fn resolve_setup_target(cwd: &Path, target: Option<&PathBuf>) -> PathBuf {
     let target_path = target.map_or_else(
-        || cwd.to_path_buf(),
+        || start_dir(cwd),
         |path| {
             if path.is_absolute() {
                 path.clone()
@@ -749,10 +2473,12 @@ fn resolve_clean_pattern_set(
             // Leading `/` means repo-root-relative (resolved against
             // `target_path`). Otherwise, resolve against the config's
             // directory mapped into the target worktree.
-            let (effective_pattern, base_dir) = pattern.strip_prefix('/').map_or_else(
-                || (pattern.as_str(), target_config_dir.clone()),
-                |stripped| (stripped, target_path.to_path_buf()),
-            );
+            let spec = PathSpec::parse(pattern);
+            let effective_pattern = spec.path();
+            let base_dir = match spec.anchor() {
+                PathAnchor::ConfigDir => target_config_dir.clone(),
+                PathAnchor::RepoRoot => target_path.to_path_buf(),
+            };
 
             match &run_base {
                 Some(current_base) if current_base == &base_dir => {
@@ -857,14 +2583,14 @@ fn run_remove(args: &RemoveArgs) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let cwd = env::current_dir()?;
-    let repo = discover_repo(&cwd)?;
+    let repo = discover_repo(&start_dir(&cwd))?;
     let repo_root = get_repo_root(&repo)?;
     let global_config = load_global_config(Some(&repo_root))?;
     let worktrees = get_worktrees(&repo)?;
 
     output::print_header("Worktree Remove");
     output::print_repo_info(&repo_root.to_string_lossy());
-    println!();
+    say!();
 
     // Mode 1: --worktrees flag — always interactive, regardless of CWD
     if args.worktrees {
@@ -966,7 +2692,7 @@ fn handle_branch_deletion(
     }
 
     if dry_run {
-        println!("  Would delete branch '{branch}'");
+        say!("  Would delete branch '{branch}'");
         return Ok(Some(branch.to_string()));
     }
 
@@ -1056,7 +2782,7 @@ fn run_remove_single(
                 true,
             )?;
         }
-        println!("\n{}", "Dry run — nothing was removed.".dimmed());
+        say!("\n{}", "Dry run — nothing was removed.".dimmed());
         return Ok(());
     }
 
@@ -1075,7 +2801,7 @@ fn run_remove_single(
             .interact()?;
 
         if !confirm {
-            println!("Cancelled.");
+            say!("Cancelled.");
             return Ok(());
         }
     }
@@ -1089,6 +2815,7 @@ fn run_remove_single(
     let force_remove = args.force || has_changes;
     match remove_worktree(repo, &wt.path, force_remove) {
         Ok(()) => {
+            prune_worktree_state(repo);
             output::print_remove_summary(1, 0);
         }
         Err(e) => {
@@ -1182,12 +2909,12 @@ fn run_remove_interactive(
         interactive::select_worktrees_for_removal(worktrees, &warning_rx, &checks_done)?;
 
     let Some(selected_indices) = selection else {
-        println!("Cancelled.");
+        say!("Cancelled.");
         return Ok(());
     };
 
     if selected_indices.is_empty() {
-        println!("No worktrees selected. Exiting.");
+        say!("No worktrees selected. Exiting.");
         return Ok(());
     }
 
@@ -1222,7 +2949,7 @@ fn run_remove_interactive(
                 )?;
             }
         }
-        println!("\n{}", "Dry run — nothing was removed.".dimmed());
+        say!("\n{}", "Dry run — nothing was removed.".dimmed());
         return Ok(());
     }
 
@@ -1238,7 +2965,7 @@ fn run_remove_interactive(
             .interact()?;
 
         if !confirm {
-            println!("Cancelled.");
+            say!("Cancelled.");
             return Ok(());
         }
     }
@@ -1312,6 +3039,10 @@ fn execute_worktree_removals(
         }
     }
 
+    if removed > 0 {
+        prune_worktree_state(repo);
+    }
+
     output::print_remove_summary(removed, failed);
     output::print_branch_delete_summary(&deleted_branches);
 
@@ -1322,6 +3053,14 @@ fn execute_worktree_removals(
     Ok(())
 }
 
+/// Drop state-store entries for worktrees that no longer exist.
+fn prune_worktree_state(repo: &Repository) {
+    match StateStore::for_repository(repo).prune() {
+        Ok(pruned) => log::debug!("Pruned state for {pruned} removed worktree(s)"),
+        Err(e) => log::warn!("Failed to prune worktree state: {e}"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Clean subcommand
 // ---------------------------------------------------------------------------
@@ -1474,12 +3213,12 @@ fn run_clean_multi_worktree(args: &CleanArgs) -> Result<(), Box<dyn std::error::
     let cwd = env::current_dir()?;
 
     // Discover repository from cwd
-    let repo = discover_repo(&cwd)?;
+    let repo = discover_repo(&start_dir(&cwd))?;
     let repo_root = get_repo_root(&repo)?;
 
     output::print_header("Worktree Clean (multi)");
     output::print_repo_info(&repo_root.to_string_lossy());
-    println!();
+    say!();
 
     // Get all worktrees
     let worktrees = get_worktrees(&repo)?;
@@ -1524,12 +3263,12 @@ fn run_clean_multi_worktree(args: &CleanArgs) -> Result<(), Box<dyn std::error::
         interactive::select_worktrees_with_sizes(&worktrees, &bg.rx, &bg.done)?;
 
     let Some(selected_indices) = selected_indices else {
-        println!("Cancelled.");
+        say!("Cancelled.");
         return Ok(());
     };
 
     if selected_indices.is_empty() {
-        println!("No worktrees selected. Exiting.");
+        say!("No worktrees selected. Exiting.");
         return Ok(());
     }
 
@@ -1562,12 +3301,12 @@ fn run_clean_multi_worktree(args: &CleanArgs) -> Result<(), Box<dyn std::error::
         .map(|g| (g.label.clone(), g.items.clone()))
         .collect();
 
-    println!();
+    say!();
     output::print_multi_worktree_clean_preview(&display_groups);
 
     // Dry run: stop here
     if args.dry_run {
-        println!("\n{}", "Dry run — nothing was deleted.".dimmed());
+        say!("\n{}", "Dry run — nothing was deleted.".dimmed());
         return Ok(());
     }
 
@@ -1685,7 +3424,7 @@ fn discover_and_select_clean_configs(
     args: &CleanArgs,
     repo_root: &Path,
 ) -> Result<Vec<LoadedConfig>, Box<dyn std::error::Error>> {
-    let all_configs = discover_and_load_configs(repo_root)?;
+    let mut all_configs = discover_and_load_configs(repo_root, false)?;
 
     if all_configs.is_empty() {
         output::print_warning("No configs found. Nothing to clean.");
@@ -1698,7 +3437,7 @@ fn discover_and_select_clean_configs(
     } else {
         Some(resolve_and_print_profile(
             &args.profile,
-            &all_configs,
+            &mut all_configs,
             repo_root,
         )?)
     };
@@ -1709,6 +3448,7 @@ fn discover_and_select_clean_configs(
         args.non_interactive,
         &args.configs,
         resolved_profile.as_ref(),
+        None,
     )?;
 
     let Some(selected_indices) = selected_indices else {
@@ -1804,7 +3544,7 @@ fn confirm_and_delete_multi(
             .interact()?;
 
         if !confirm {
-            println!("Cancelled.");
+            say!("Cancelled.");
             return Ok(());
         }
     }
@@ -1848,7 +3588,7 @@ fn confirm_and_delete_multi(
         }
     }
 
-    println!();
+    say!();
     output::print_multi_worktree_clean_summary(deleted_count, total_size, worktrees_cleaned);
 
     Ok(())
@@ -1865,10 +3605,10 @@ fn run_clean_single(args: &CleanArgs) -> Result<(), Box<dyn std::error::Error>>
 
     output::print_header("Worktree Clean");
     output::print_repo_info(&repo_root.to_string_lossy());
-    println!();
+    say!();
 
     // Discover and load configs
-    let all_configs = discover_and_load_configs(&repo_root)?;
+    let mut all_configs = discover_and_load_configs(&repo_root, false)?;
 
     if all_configs.is_empty() {
         output::print_warning("No configs found. Nothing to clean.");
@@ -1876,25 +3616,21 @@ fn run_clean_single(args: &CleanArgs) -> Result<(), Box<dyn std::error::Error>>
     }
 
     // Resolve profiles if --profile was provided
-    let resolved_profile = if args.profile.is_empty() {
-        None
-    } else {
-        Some(resolve_and_print_profile(
-            &args.profile,
-            &all_configs,
-            &repo_root,
-        )?)
-    };
+    let resolved_profile =
+        resolve_profile_and_root_defaults(&args.profile, &mut all_configs, &repo_root)?;
 
-    // Select configs: profile overrides normal selection
+    // Select configs: profile overrides normal selection. Configs applied
+    // to this worktree before start checked.
+    let preselected = applied_config_preselection(&all_configs, &target_path);
     let selected_indices = select_configs_or_profile(
         &all_configs,
         args.non_interactive,
         &args.configs,
         resolved_profile.as_ref(),
+        preselected.as_deref(),
     )?;
     let Some(selected_indices) = selected_indices else {
-        println!("No configs selected. Exiting.");
+        say!("No configs selected. Exiting.");
         return Ok(());
     };
 
@@ -1941,12 +3677,12 @@ fn run_clean_single(args: &CleanArgs) -> Result<(), Box<dyn std::error::Error>>
         .build()?;
     let items = size_items_in_pool(&resolved, &pool);
 
-    println!();
+    say!();
     output::print_clean_preview(&items);
 
     // Dry run: stop here
     if args.dry_run {
-        println!("\n{}", "Dry run — nothing was deleted.".dimmed());
+        say!("\n{}", "Dry run — nothing was deleted.".dimmed());
         return Ok(());
     }
 
@@ -1974,7 +3710,7 @@ fn confirm_and_delete(
             .interact()?;
 
         if !confirm {
-            println!("Cancelled.");
+            say!("Cancelled.");
             return Ok(());
         }
     }
@@ -2001,7 +3737,7 @@ fn confirm_and_delete(
         }
     }
 
-    println!();
+    say!();
     output::print_clean_summary(deleted_count, total_size);
 
     Ok(())
@@ -2027,8 +3763,10 @@ fn select_configs_or_profile(
     non_interactive: bool,
     config_patterns: &[String],
     profile: Option<&ResolvedProfile>,
+    preselected: Option<&[bool]>,
 ) -> Result<Option<Vec<usize>>, Box<dyn std::error::Error>> {
-    if let Some(p) = profile {
+    // A profile without names only carries the root config's defaults.
+    if let Some(p) = profile.filter(|p| !p.names.is_empty()) {
         if p.config_indices.is_empty() {
             output::print_warning("Profile matched no configs.");
             return Ok(None);
@@ -2036,13 +3774,30 @@ fn select_configs_or_profile(
         return Ok(Some(p.config_indices.clone()));
     }
 
-    let indices = select_configs(all_configs, config_patterns, non_interactive)?;
+    let indices = select_configs(all_configs, config_patterns, non_interactive, preselected)?;
     if indices.is_empty() {
         return Ok(None);
     }
     Ok(Some(indices))
 }
 
+/// Interactive preselection from the state store: the configs previously
+/// applied to `target_path`, or `None` if nothing is recorded for it.
+fn applied_config_preselection(
+    all_configs: &[LoadedConfig],
+    target_path: &Path,
+) -> Option<Vec<bool>> {
+    let state = StateStore::for_worktree(target_path)
+        .ok()?
+        .load(target_path)
+        .ok()??;
+    let preselected: Vec<bool> = all_configs
+        .iter()
+        .map(|c| state.configs.contains_key(&c.relative_path))
+        .collect();
+    preselected.contains(&true).then_some(preselected)
+}
+
 /// Collect profile display info from all loaded configs for `--list`.
 ///
 /// Aggregates profile names across all configs, deduplicates, and
@@ -2071,71 +3826,246 @@ fn collect_profile_display_info(all_configs: &[LoadedConfig]) -> Vec<(String, St
 
 // ─── Default flow (create + setup) ─────────────────────────────────────────
 
+/// Choose the options for creating the worktree at `target_path`, from
+/// the flags, profile defaults, and prompts. Returns `None` if the user
+/// declines to create the worktree.
+///
+/// Profile defaults are applied with the following priority:
+/// * CLI flag > profile default > interactive prompt / builtin default
+///
+/// Profile defaults used:
+/// * `remote` — remote name for remote branch fetch
+/// * `base_branch` — base branch for new branch creation
+/// * `new_branch` — when `true`, auto-create a branch named after the worktree
+/// * `auto_create` — skip the "Create worktree?" confirmation
+/// * `creation_method` — skip the creation method picker
+///
+/// A `name_only` invocation (see [`resolve_create_target`]) without branch
+/// flags or a profile `creation_method` skips the prompts and checks out
+/// the branch named like the worktree, creating it if needed.
+fn worktree_create_options(
+    args: &Args,
+    repo: &worktree_setup_git::Repository,
+    target_path: &Path,
+    profile: Option<&ResolvedProfile>,
+    name_only: bool,
+) -> Result<Option<WorktreeCreateOptions>, Box<dyn std::error::Error>> {
+    let profile_defaults = profile.map(|p| &p.defaults);
+    let worktree_name = target_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("worktree");
+
+    // Build creation hints from profile defaults + CLI flags
+    let effective_remote = args
+        .remote
+        .as_deref()
+        .or_else(|| profile_defaults.and_then(|d| d.remote.as_deref()));
+    let creation_method = profile_defaults.and_then(|d| d.creation_method.as_ref());
+    let auto_create = profile_defaults
+        .and_then(|d| d.auto_create)
+        .unwrap_or(false);
+    let profile_base_branch = profile_defaults.and_then(|d| d.base_branch.as_deref());
+    let profile_new_branch = profile_defaults.and_then(|d| d.new_branch).unwrap_or(false);
+
+    // Infer branch name for remote tracking (unless --no-infer-branch)
+    let is_remote =
+        creation_method == Some(&CreationMethod::Remote) || args.remote_branch.is_some();
+    let inferred_branch = if is_remote && !args.no_infer_branch {
+        Some(worktree_name)
+    } else {
+        None
+    };
+
+    let hints = interactive::CreationProfileHints {
+        auto_create,
+        creation_method,
+        base_branch: profile_base_branch,
+        new_branch: profile_new_branch,
+        remote_override: effective_remote,
+        inferred_branch,
+    };
+
+    let named_branch = name_only
+        && args.new_branch.is_none()
+        && args.remote_branch.is_none()
+        && creation_method.is_none();
+    Ok(if named_branch {
+        say!("Creating worktree at {}...", target_path.display());
+        Some(named_branch_options(args, repo, worktree_name)?)
+    } else if args.non_interactive {
+        Some(handle_creation_non_interactive(
+            args,
+            repo,
+            target_path,
+            &hints,
+            worktree_name,
+        )?)
+    } else {
+        let result = handle_creation_interactive(args, repo, target_path, &hints)?;
+        if result.is_some() {
+            say!("\nCreating worktree at {}...", target_path.display());
+        }
+        result
+    })
+}
+
+/// Handle a target directory that already exists.
+///
+/// Registered worktrees of the repository are set up as they are. For any
+/// other directory, `--existing-target` (or a prompt) decides whether it
+/// is adopted as a worktree, set up without one, or left alone.
+/// Non-interactive runs without the flag set it up without a worktree, as
+/// earlier versions did silently, and warn.
+fn handle_existing_target(
+    args: &Args,
+    repo: &worktree_setup_git::Repository,
+    target_path: &Path,
+    profile: Option<&ResolvedProfile>,
+    name_only: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let canonical = target_path.canonicalize()?;
+    let registered = get_worktrees(repo)?
+        .iter()
+        .any(|wt| wt.path.canonicalize().is_ok_and(|path| path == canonical));
+    if registered {
+        return Ok(());
+    }
+
+    let action = match args.existing_target {
+        Some(action) => action,
+        None if args.non_interactive => {
+            output::print_warning(&format!(
+                "{} exists but is not a worktree of this repository; applying file operations only. Pass --existing-target to choose.",
+                target_path.display()
+            ));
+            ExistingTarget::FilesOnly
+        }
+        None => interactive::prompt_existing_target(target_path)?,
+    };
+    match action {
+        ExistingTarget::FilesOnly => Ok(()),
+        ExistingTarget::Abort => Err(format!(
+            "{} exists but is not a worktree of this repository",
+            target_path.display()
+        )
+        .into()),
+        ExistingTarget::Adopt => {
+            let Some(options) =
+                worktree_create_options(args, repo, target_path, profile, name_only)?
+            else {
+                return Err("Worktree creation cancelled.".into());
+            };
+            adopt_directory(repo, target_path, &options)?;
+            output::print_info(&format!(
+                "Adopted {} as a worktree; files that differ from the checkout show as changes in `git status`.",
+                target_path.display()
+            ));
+            Ok(())
+        }
+    }
+}
+
 /// Handle worktree creation (both interactive and non-interactive).
 ///
-/// Profile defaults are applied with the following priority:
-/// * CLI flag > profile default > interactive prompt / builtin default
-///
-/// Profile defaults used:
-/// * `remote` — remote name for remote branch fetch
-/// * `base_branch` — base branch for new branch creation
-/// * `new_branch` — when `true`, auto-create a branch named after the worktree
-/// * `auto_create` — skip the "Create worktree?" confirmation
-/// * `creation_method` — skip the creation method picker
+/// See [`worktree_create_options`] for how the branch is chosen.
 fn handle_worktree_creation(
     args: &Args,
     repo: &worktree_setup_git::Repository,
     target_path: &Path,
     profile: Option<&ResolvedProfile>,
+    name_only: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let profile_defaults = profile.map(|p| &p.defaults);
-    let worktree_name = target_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("worktree");
-
-    // Build creation hints from profile defaults + CLI flags
-    let effective_remote = args
-        .remote
+    let clone_source = args
+        .clone_from
         .as_deref()
-        .or_else(|| profile_defaults.and_then(|d| d.remote.as_deref()));
-    let creation_method = profile_defaults.and_then(|d| d.creation_method.as_ref());
-    let auto_create = profile_defaults
-        .and_then(|d| d.auto_create)
-        .unwrap_or(false);
-    let profile_base_branch = profile_defaults.and_then(|d| d.base_branch.as_deref());
-    let profile_new_branch = profile_defaults.and_then(|d| d.new_branch).unwrap_or(false);
-
-    // Infer branch name for remote tracking (unless --no-infer-branch)
-    let is_remote =
-        creation_method == Some(&CreationMethod::Remote) || args.remote_branch.is_some();
-    let inferred_branch = if is_remote && !args.no_infer_branch {
-        Some(worktree_name)
-    } else {
-        None
+        .map(|spec| resolve_clone_source(repo, spec))
+        .transpose()?;
+    let Some(options) = worktree_create_options(args, repo, target_path, profile, name_only)?
+    else {
+        return Ok(());
     };
 
-    let hints = interactive::CreationProfileHints {
-        auto_create,
-        creation_method,
-        base_branch: profile_base_branch,
-        new_branch: profile_new_branch,
-        remote_override: effective_remote,
-        inferred_branch,
+    let Some(source) = clone_source else {
+        return create_worktree_with_recovery(repo, target_path, &options, args.non_interactive);
     };
 
-    let options = if args.non_interactive {
-        handle_creation_non_interactive(args, repo, target_path, &hints, worktree_name)?
-    } else {
-        let result = handle_creation_interactive(args, repo, target_path, &hints)?;
-        let Some(options) = result else {
-            return Ok(());
-        };
-        println!("\nCreating worktree at {}...", target_path.display());
-        options
+    let options = WorktreeCreateOptions {
+        no_checkout: true,
+        ..options
     };
+    create_worktree_with_recovery(repo, target_path, &options, args.non_interactive)?;
+    clone_worktree_files(&source, target_path, args.progress_mode())
+}
+
+/// Find the worktree named by `--clone-from`, by branch name or path.
+fn resolve_clone_source(
+    repo: &worktree_setup_git::Repository,
+    spec: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let spec_path = env::current_dir()?.join(spec).clean();
+    let spec_path = spec_path.canonicalize().unwrap_or(spec_path);
 
-    create_worktree_with_recovery(repo, target_path, &options, args.non_interactive)
+    get_worktrees(repo)?
+        .into_iter()
+        .find(|wt| {
+            wt.branch.as_deref() == Some(spec)
+                || wt.path.canonicalize().is_ok_and(|path| path == spec_path)
+        })
+        .map(|wt| wt.path)
+        .ok_or_else(|| format!("No worktree matches --clone-from '{spec}'").into())
+}
+
+/// Reflink every file of `source` into the freshly created `target`, then
+/// reset tracked files to the target's HEAD.
+///
+/// The source's `.git` entry is left out, and so are submodule checkouts
+/// (directories with a `.git` file), whose relative `gitdir` links would
+/// not resolve from the new worktree; `--submodules` checks them out
+/// afterwards.
+fn clone_worktree_files(
+    source: &Path,
+    target: &Path,
+    progress: ProgressMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    output::print_info(&format!("Cloning files from {}...", source.display()));
+
+    let label = source.display().to_string();
+    let progress_mgr = ProgressManager::new(progress);
+    progress_mgr.start_overall(0);
+    let git_dir = source.join(".git");
+    let result = clone_directory_into(
+        source,
+        target,
+        None,
+        move |path, is_dir| path != git_dir && !(is_dir && path.join(".git").is_file()),
+        |progress| {
+            progress_mgr.set_files_total(progress.files_total);
+            progress_mgr.set_files_done(progress.files_copied);
+        },
+    )?;
+    progress_mgr.clear();
+
+    adopt_cloned_files(source, target)?;
+
+    // The filtered count includes the source's `.git`, which would read
+    // as an ignored file here, so it is left out.
+    if let CopyResult::Created {
+        files_copied,
+        symlinks_copied,
+        ..
+    } = result
+    {
+        progress_mgr.print_result_with_count(
+            &label,
+            "cloned",
+            files_copied,
+            symlinks_copied,
+            0,
+            None,
+        );
+    }
+    Ok(())
 }
 
 /// Non-interactive worktree creation.
@@ -2153,15 +4083,15 @@ fn handle_creation_non_interactive(
     let branch = if let Some(ref remote_branch) = args.remote_branch {
         // Explicit --remote-branch: always use it
         let remote = resolve_remote_non_interactive(repo, hints.remote_override)?;
-        println!("Fetching from {remote}...");
+        say!("Fetching from {remote}...");
         fetch_remote(repo, &remote)?;
         Some(remote_branch.clone())
     } else if hints.creation_method == Some(&CreationMethod::Remote) && !args.no_infer_branch {
         // Profile says remote — infer branch from directory name
         let remote = resolve_remote_non_interactive(repo, hints.remote_override)?;
-        println!("Fetching from {remote}...");
+        say!("Fetching from {remote}...");
         fetch_remote(repo, &remote)?;
-        println!("Inferred remote branch: {remote}/{worktree_name}");
+        say!("Inferred remote branch: {remote}/{worktree_name}");
         Some(worktree_name.to_string())
     } else if hints.creation_method == Some(&CreationMethod::Remote) && args.no_infer_branch {
         return Err(
@@ -2201,12 +4131,38 @@ fn handle_creation_non_interactive(
 
     let detach = hints.creation_method == Some(&CreationMethod::Detach);
 
-    println!("Creating worktree at {}...", target_path.display());
+    say!("Creating worktree at {}...", target_path.display());
     Ok(WorktreeCreateOptions {
         branch,
         new_branch,
         force: args.force,
         detach,
+        no_checkout: false,
+    })
+}
+
+/// Creation options for a name-only invocation: check out the local branch
+/// named `name`, or create it (from `--branch`, if given) when it does not
+/// exist yet.
+fn named_branch_options(
+    args: &Args,
+    repo: &worktree_setup_git::Repository,
+    name: &str,
+) -> Result<WorktreeCreateOptions, GitError> {
+    let exists = get_local_branches(repo)?.iter().any(|b| b == name);
+    Ok(if exists {
+        WorktreeCreateOptions {
+            branch: Some(name.to_string()),
+            force: args.force,
+            ..Default::default()
+        }
+    } else {
+        WorktreeCreateOptions {
+            branch: args.branch.clone(),
+            new_branch: Some(name.to_string()),
+            force: args.force,
+            ..Default::default()
+        }
     })
 }
 
@@ -2263,14 +4219,14 @@ fn create_worktree_with_recovery(
             interactive::flush_stdin();
             match interactive::prompt_stale_worktree_recovery()? {
                 interactive::StaleWorktreeAction::Prune => {
-                    println!("Pruning stale worktrees...");
+                    say!("Pruning stale worktrees...");
                     prune_worktrees(repo)?;
-                    println!("Retrying worktree creation...");
+                    say!("Retrying worktree creation...");
                     create_worktree(repo, path, options)?;
                     Ok(())
                 }
                 interactive::StaleWorktreeAction::Force => {
-                    println!("Force creating worktree...");
+                    say!("Force creating worktree...");
                     let mut forced = options.clone();
                     forced.force = true;
                     create_worktree(repo, path, &forced)?;
@@ -2296,18 +4252,19 @@ fn create_worktree_with_recovery(
             interactive::flush_stdin();
             match interactive::prompt_branch_exists_recovery(&branch)? {
                 interactive::BranchExistsAction::UseExisting => {
-                    println!("Using existing branch '{branch}'...");
+                    say!("Using existing branch '{branch}'...");
                     let reuse_opts = WorktreeCreateOptions {
                         branch: Some(branch),
                         new_branch: None,
                         detach: options.detach,
                         force: options.force,
+                        no_checkout: options.no_checkout,
                     };
                     create_worktree(repo, path, &reuse_opts)?;
                     Ok(())
                 }
                 interactive::BranchExistsAction::DeleteAndCreate => {
-                    println!("Deleting branch '{branch}' and retrying...");
+                    say!("Deleting branch '{branch}' and retrying...");
                     delete_branch(repo, &branch, true)?;
                     create_worktree(repo, path, options)?;
                     Ok(())
@@ -2380,35 +4337,38 @@ fn resolve_remote_non_interactive(
 fn run_create(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     // Discover repository
     let cwd = env::current_dir()?;
-    let repo = discover_repo(&cwd)?;
+    if args.resume {
+        return resume::run(
+            &resolve_setup_target(&cwd, args.target_path.as_ref()),
+            FileOpOptions {
+                progress: args.progress_mode(),
+                assume_yes: args.yes,
+                non_interactive: args.non_interactive,
+                transactional: args.transactional,
+                continue_on_error: args.continue_on_error,
+                verify: args.verify,
+                sudo_fallback: args.sudo_fallback,
+                ..FileOpOptions::default()
+            },
+        );
+    }
+    let repo = discover_repo(&start_dir(&cwd))?;
     let repo_root = get_repo_root(&repo)?;
 
+    if args.list {
+        return list::run(args, &repo_root);
+    }
+
     output::print_header("Worktree Setup");
     output::print_repo_info(&repo_root.to_string_lossy());
-    println!();
+    say!();
 
     // Discover and load configs
-    let all_configs = discover_and_load_configs(&repo_root)?;
-
-    // If --list, print available profiles and exit
-    if args.list {
-        let profile_display = collect_profile_display_info(&all_configs);
-        if !profile_display.is_empty() {
-            output::print_profile_list(&profile_display);
-        }
-        return Ok(());
-    }
+    let mut all_configs = discover_and_load_configs(&repo_root, args.submodules)?;
 
     // Resolve profiles (if --profile was provided)
-    let resolved_profile = if args.profile.is_empty() {
-        None
-    } else {
-        Some(resolve_and_print_profile(
-            &args.profile,
-            &all_configs,
-            &repo_root,
-        )?)
-    };
+    let resolved_profile =
+        resolve_profile_and_root_defaults(&args.profile, &mut all_configs, &repo_root)?;
 
     // Select configs: profile overrides normal selection
     let selected_configs: Vec<&LoadedConfig> = if all_configs.is_empty() {
@@ -2418,33 +4378,20 @@ fn run_create(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         args.non_interactive,
         &args.configs,
         resolved_profile.as_ref(),
+        None,
     )? {
         indices.iter().map(|&i| &all_configs[i]).collect()
     } else {
-        println!("No configs selected. Exiting.");
+        say!("No configs selected. Exiting.");
         return Ok(());
     };
 
-    // Get target path
-    let target_path = if let Some(ref path) = args.target_path {
-        PathBuf::from(path)
-    } else if args.non_interactive {
-        output::print_error("Target path is required in non-interactive mode.");
-        std::process::exit(1);
-    } else {
-        interactive::prompt_worktree_path()?
-    };
-
-    // Make target path absolute and normalize (resolve . and .. components)
-    let target_path = if target_path.is_absolute() {
-        target_path
-    } else {
-        cwd.join(&target_path)
-    }
-    .clean();
-
     // Get main worktree
     let main_worktree = get_main_worktree(&repo)?;
+    let global_config = load_global_config(Some(&repo_root))?;
+
+    let (target_path, name_only) =
+        resolve_create_target(args, &cwd, &main_worktree.path, &global_config)?;
 
     // Check if target is the main worktree
     if target_path == main_worktree.path {
@@ -2455,9 +4402,26 @@ fn run_create(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Handle worktree creation
-    if !target_path.exists() {
-        handle_worktree_creation(args, &repo, &target_path, resolved_profile.as_ref())?;
-    }
+    let target_path = if target_path.exists() {
+        handle_existing_target(
+            args,
+            &repo,
+            &target_path,
+            resolved_profile.as_ref(),
+            name_only,
+        )?;
+        target_path
+    } else {
+        let target_path = enforce_worktree_name(target_path, &global_config, args.non_interactive)?;
+        handle_worktree_creation(
+            args,
+            &repo,
+            &target_path,
+            resolved_profile.as_ref(),
+            name_only,
+        )?;
+        target_path
+    };
 
     // Verify target exists
     if !target_path.exists() {
@@ -2467,10 +4431,10 @@ fn run_create(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         ));
         std::process::exit(1);
     }
+    record_labels(&target_path, &args.labels);
 
     // Apply config setup operations (only if configs were selected)
     if !selected_configs.is_empty() {
-        let global_config = load_global_config(Some(&repo_root))?;
         apply_create_operations(
             args,
             &selected_configs,
@@ -2481,10 +4445,110 @@ fn run_create(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         )?;
     }
 
+    if args.stats {
+        timing::print_stats();
+    }
     output::print_success();
     Ok(())
 }
 
+/// Resolve the absolute path of the worktree to create.
+///
+/// A bare name (no path separators) is placed under `--target-base`, or
+/// under `create.target_base` (relative to the main worktree) when the flag
+/// is not given. Returns the path and whether it was composed from a bare
+/// name this way.
+fn resolve_create_target(
+    args: &Args,
+    cwd: &Path,
+    main_worktree_path: &Path,
+    global_config: &worktree_setup_config::GlobalConfig,
+) -> Result<(PathBuf, bool), Box<dyn std::error::Error>> {
+    let target_path = if let Some(ref path) = args.target_path {
+        PathBuf::from(path)
+    } else if args.non_interactive {
+        output::print_error("Target path is required in non-interactive mode.");
+        std::process::exit(1);
+    } else {
+        interactive::prompt_worktree_path()?
+    };
+
+    let target_base = args.target_base.clone().or_else(|| {
+        global_config
+            .create
+            .target_base
+            .as_ref()
+            .map(|base| main_worktree_path.join(expand_home(base)))
+    });
+    let (target_path, name_only) = match target_base {
+        Some(base) if is_bare_name(&target_path) => (base.join(target_path), true),
+        _ => (target_path, false),
+    };
+
+    // Make target path absolute and normalize (resolve . and .. components)
+    let target_path = if target_path.is_absolute() {
+        target_path
+    } else {
+        cwd.join(&target_path)
+    }
+    .clean();
+
+    Ok((target_path, name_only))
+}
+
+/// Whether `path` is a single name such as `feature-login` rather than a
+/// path like `../feature-login` or `./feature-login`.
+fn is_bare_name(path: &Path) -> bool {
+    let mut components = path.components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    ) && !path.as_os_str().as_encoded_bytes().contains(&b'/')
+}
+
+/// Check the name of a worktree about to be created against
+/// `create.worktree_name_pattern`.
+///
+/// Interactively, a rejected name can be swapped for its sanitized
+/// suggestion; the returned path uses whichever name was accepted.
+fn enforce_worktree_name(
+    target_path: PathBuf,
+    global_config: &worktree_setup_config::GlobalConfig,
+    non_interactive: bool,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let Some(pattern) = global_config.create.worktree_name_pattern.as_deref() else {
+        return Ok(target_path);
+    };
+    let name = target_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match check_worktree_name(&name, pattern) {
+        Err(ConfigError::WorktreeNameMismatch {
+            suggestion: Some(suggestion),
+            ..
+        }) if !non_interactive => {
+            output::print_warning(&format!(
+                "Worktree name '{name}' does not match worktree_name_pattern '{pattern}'."
+            ));
+            if interactive::confirm_worktree_name(&name, &suggestion)? {
+                Ok(target_path.with_file_name(suggestion))
+            } else {
+                Err("Worktree name rejected by worktree_name_pattern.".into())
+            }
+        }
+        result => result.map(|()| target_path).map_err(Into::into),
+    }
+}
+
+/// Initialize submodules in the target so configs inside them can apply.
+fn init_target_submodules(target_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    output::print_info("Initializing submodules...");
+    init_submodules(target_path)?;
+    Ok(())
+}
+
 /// Apply file operations and post-setup commands during worktree creation.
 fn apply_create_operations(
     args: &Args,
@@ -2494,28 +4558,52 @@ fn apply_create_operations(
     target_path: &Path,
     global_config: &worktree_setup_config::GlobalConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("\nSetting up worktree: {}", target_path.display());
-    println!("Main worktree: {}\n", main_worktree_path.display());
+    say!("\nSetting up worktree: {}", target_path.display());
+    say!("Main worktree: {}\n", main_worktree_path.display());
 
     // Determine copy_unstaged: CLI flag > profile default > config default
     let copy_unstaged_override = args
         .copy_unstaged_override()
         .or_else(|| resolved_profile.and_then(|p| p.defaults.copy_unstaged));
 
+    if args.submodules {
+        init_target_submodules(target_path)?;
+    }
+
     execute_file_operations(
         selected_configs,
         main_worktree_path,
         target_path,
-        copy_unstaged_override,
-        false, // No overwrite in create flow (fresh worktree)
-        args.should_show_progress(),
+        FileOpOptions {
+            copy_unstaged_override,
+            overwrite_existing: false, // No overwrite in create flow (fresh worktree)
+            progress: args.progress_mode(),
+            assume_yes: args.yes,
+            non_interactive: args.non_interactive,
+            transactional: args.transactional,
+            continue_on_error: args.continue_on_error,
+            verify: args.verify,
+            count_cache: !args.no_count_cache,
+            skip_space_check: args.skip_space_check,
+            max_depth: args.max_depth,
+            sudo_fallback: args.sudo_fallback,
+            dry_run: false,
+            check_sources: args.check_sources,
+            force_destructive: args.force_destructive,
+        },
         global_config,
     )?;
 
-    println!();
+    say!();
 
     // Collect all post-setup commands
-    let unique_commands = collect_post_setup_commands(selected_configs);
+    let detected = detect::detected_post_setup_commands(
+        selected_configs,
+        main_worktree_path,
+        global_config,
+        !args.non_interactive,
+    );
+    let unique_commands = collect_post_setup_commands(selected_configs, &detected);
 
     if unique_commands.is_empty() {
         return Ok(());
@@ -2529,17 +4617,32 @@ fn apply_create_operations(
         Some(cmds) => {
             // Fully determined — run without prompting
             if !cmds.is_empty() {
-                run_post_setup_commands(&cmds, target_path)?;
+                run_post_setup_commands(
+                    &cmds,
+                    target_path,
+                    args.print_commands,
+                    args.quiet_post_setup,
+                )?;
             }
         }
         None => {
             // Not determined — prompt the user (or run all in non-interactive)
             if args.non_interactive {
-                run_post_setup_commands(&unique_commands, target_path)?;
+                run_post_setup_commands(
+                    &unique_commands,
+                    target_path,
+                    args.print_commands,
+                    args.quiet_post_setup,
+                )?;
             } else {
                 let should_run = interactive::prompt_run_install(true)?;
                 if should_run {
-                    run_post_setup_commands(&unique_commands, target_path)?;
+                    run_post_setup_commands(
+                        &unique_commands,
+                        target_path,
+                        args.print_commands,
+                        args.quiet_post_setup,
+                    )?;
                 }
             }
         }
@@ -2760,6 +4863,68 @@ mod tests {
         assert_eq!(result, Some(Vec::<&str>::new()));
     }
 
+    // ─── ExecutionSummary ───────────────────────────────────────────────
+
+    #[test]
+    fn test_execution_summary_merge_retry() {
+        let mut summary = ExecutionSummary {
+            failed: vec![
+                (1, "locked".to_string()),
+                (3, "denied".to_string()),
+                (4, "busy".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        // Operations 3 and 4 were retried; the second (4) failed again.
+        let retried = ExecutionSummary {
+            failed: vec![(1, "still busy".to_string())],
+            timed_out: 1,
+            ..Default::default()
+        };
+        summary.merge_retry(&[3, 4], retried);
+
+        assert_eq!(
+            summary.failed,
+            [(1, "locked".to_string()), (4, "still busy".to_string())]
+        );
+        assert_eq!(summary.timed_out, 1);
+        assert!(summary.into_result().is_err());
+        assert!(ExecutionSummary::default().into_result().is_ok());
+    }
+
+    // ─── repo_start_dir ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_repo_start_dir_from_repo_flag() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        std::fs::create_dir_all(repo_dir.path().join("apps/web")).unwrap();
+        let root = repo_dir.path().canonicalize().unwrap();
+
+        // Any path inside the repository names its worktree, relative to cwd
+        let start = repo_start_dir(Some(Path::new("web")), &root.join("apps"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(start.canonicalize().unwrap(), root);
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(repo_start_dir(Some(outside.path()), &root).is_err());
+    }
+
+    // ─── run_post_setup_commands ────────────────────────────────────────
+
+    #[test]
+    fn test_run_post_setup_commands_print_only_runs_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+
+        run_post_setup_commands(&["touch ran.txt"], dir.path(), true, None).unwrap();
+        assert!(!dir.path().join("ran.txt").exists());
+
+        run_post_setup_commands(&["touch ran.txt"], dir.path(), false, None).unwrap();
+        assert!(dir.path().join("ran.txt").exists());
+    }
+
     // ─── resolve_overwrite ─────────────────────────────────────────────
 
     #[test]
@@ -2806,6 +4971,46 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    // ─── determine_setup_operations ─────────────────────────────────────
+
+    #[test]
+    fn test_determine_setup_operations_post_setup_only() {
+        // --post-setup-only skips files even when the profile would
+        // overwrite, without prompting
+        let args = SetupArgs::parse_from(["setup", "--post-setup-only"]);
+        let profile = make_profile(ProfileDefaults {
+            overwrite_existing: Some(true),
+            ..Default::default()
+        });
+
+        let result =
+            determine_setup_operations(&args, Some(&profile), true, &["bun install"]).unwrap();
+        assert_eq!(result, (false, false, true));
+
+        // Nothing to run when the configs have no post-setup commands
+        let result = determine_setup_operations(&args, Some(&profile), true, &[]).unwrap();
+        assert_eq!(result, (false, false, false));
+    }
+
+    #[test]
+    fn test_post_setup_only_conflicts_with_file_flags() {
+        for flag in ["--no-files", "--no-install", "--overwrite"] {
+            assert!(SetupArgs::try_parse_from(["setup", "--post-setup-only", flag]).is_err());
+        }
+    }
+
+    // ─── is_bare_name ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_is_bare_name() {
+        assert!(is_bare_name(Path::new("feature-login")));
+        assert!(!is_bare_name(Path::new("./feature-login")));
+        assert!(!is_bare_name(Path::new("../wt/feature-login")));
+        assert!(!is_bare_name(Path::new("/tmp/feature-login")));
+        assert!(!is_bare_name(Path::new("feature-login/")));
+        assert!(!is_bare_name(Path::new("..")));
+    }
+
     // ─── format_size ────────────────────────────────────────────────────
 
     #[test]
@@ -4219,6 +6424,7 @@ mod tests {
             new_branch: Some("existing-branch".to_string()),
             detach: false,
             force: false,
+            no_checkout: false,
         };
 
         // Non-interactive should return an error, not prompt
@@ -4230,4 +6436,92 @@ mod tests {
             "error should mention branch already exists, got: {err_msg}"
         );
     }
+
+    // ─── protected branches ─────────────────────────────────────────────
+
+    fn planned_op(display_path: &str, operation_type: OperationType) -> PlannedOperation {
+        PlannedOperation {
+            display_path: display_path.to_string(),
+            operation_type,
+            source: PathBuf::from("/main").join(display_path),
+            target: PathBuf::from("/target").join(display_path),
+            file_count: 1,
+            symlink_count: 0,
+            byte_count: 0,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    #[test]
+    fn test_skip_protected_branch_ops() {
+        let dir = tempfile::tempdir().unwrap();
+        create_test_repo(dir.path());
+        Command::new("git")
+            .args(["checkout", "-q", "-b", "release/1.0"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        let mut operations = vec![
+            planned_op(".env", OperationType::Copy),
+            planned_op("dev.db", OperationType::Overwrite),
+            planned_op("notes.txt", OperationType::Unstaged),
+        ];
+
+        let unprotected = worktree_setup_config::SetupConfig {
+            protected_branches: Some(vec!["main".to_string()]),
+            ..Default::default()
+        };
+        skip_protected_branch_ops(&mut operations, dir.path(), &unprotected);
+        assert!(operations.iter().all(|op| !op.will_skip));
+
+        let protected = worktree_setup_config::SetupConfig {
+            protected_branches: Some(vec!["main".to_string(), "release/*".to_string()]),
+            ..Default::default()
+        };
+        skip_protected_branch_ops(&mut operations, dir.path(), &protected);
+        let skipped: Vec<_> = operations
+            .iter()
+            .map(|op| op.skip_reason.as_deref())
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                None,
+                Some("protected branch release/1.0"),
+                Some("protected branch release/1.0")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_missing_sources() {
+        let mut operations = vec![
+            planned_op(".env", OperationType::Copy),
+            planned_op("dev.db", OperationType::Overwrite),
+        ];
+        assert!(check_missing_sources(&operations).is_ok());
+
+        operations[0].will_skip = true;
+        operations[0].skip_reason = Some("exists".to_string());
+        assert!(check_missing_sources(&operations).is_ok());
+
+        operations[1].will_skip = true;
+        operations[1].skip_reason = Some("not found".to_string());
+        let err = check_missing_sources(&operations).unwrap_err();
+        assert_eq!(err.code(), "WTS-OPS-013");
+        assert_eq!(err.to_string(), "1 config entry has a missing source");
+    }
 }
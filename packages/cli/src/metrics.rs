@@ -0,0 +1,236 @@
+//! Provisioning metrics.
+//!
+//! With `--metrics-file`, every setup run (the default create flow,
+//! `setup`, `serve`'s `apply`, and the worktrees `pool ensure` sets up)
+//! adds to counters kept in a Prometheus text-format file, for the node
+//! exporter's textfile collector to pick up. The counters add up across
+//! processes: each run reads the file, adds its own numbers, and replaces
+//! the file, holding a lock file next to it meanwhile.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use worktree_setup_operations::{OperationType, PlannedOperation};
+
+/// Counter of setup runs that completed.
+const SETUPS: &str = "worktree_setup_setups_total";
+
+/// Counter of setup runs that failed.
+const FAILURES: &str = "worktree_setup_setup_failures_total";
+
+/// Counter of bytes copied into worktrees.
+const BYTES_COPIED: &str = "worktree_setup_bytes_copied_total";
+
+/// Summary of how long setup runs took.
+const DURATION: &str = "worktree_setup_setup_duration_seconds";
+
+/// File the counters are kept in, set by `--metrics-file`.
+static METRICS_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Bytes copied by the current run so far.
+static COPIED: AtomicU64 = AtomicU64::new(0);
+
+/// Counters kept in the metrics file.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Counters {
+    /// Setup runs that completed.
+    setups: u64,
+    /// Setup runs that failed.
+    failures: u64,
+    /// Bytes copied into worktrees.
+    bytes_copied: u64,
+    /// Total duration of all setup runs, in seconds.
+    duration_sum: f64,
+}
+
+/// Keep counters in the file at `path` (`--metrics-file`), relative to
+/// the current directory.
+///
+/// # Errors
+///
+/// * If the current directory cannot be determined
+pub fn init(path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let _ = METRICS_FILE.set(std::env::current_dir()?.join(path));
+    Ok(())
+}
+
+/// The metrics file, if `--metrics-file` was given.
+pub fn metrics_file() -> Option<&'static Path> {
+    METRICS_FILE.get().map(PathBuf::as_path)
+}
+
+/// Count the bytes `op` copied; symlinks copy nothing.
+pub fn add_copied(op: &PlannedOperation) {
+    if op.operation_type != OperationType::Symlink {
+        COPIED.fetch_add(op.byte_count, Ordering::Relaxed);
+    }
+}
+
+/// Add a finished setup run, with the bytes copied since the last one, to
+/// the metrics file. Failing to update the file is only logged.
+pub fn record_setup(succeeded: bool, elapsed: Duration) {
+    let copied = COPIED.swap(0, Ordering::Relaxed);
+    let Some(path) = metrics_file() else {
+        return;
+    };
+    let result = update(path, |counters| {
+        if succeeded {
+            counters.setups += 1;
+        } else {
+            counters.failures += 1;
+        }
+        counters.bytes_copied += copied;
+        counters.duration_sum += elapsed.as_secs_f64();
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to update metrics file {}: {e}", path.display());
+    }
+}
+
+/// Apply `change` to the counters in the file at `path` under its lock.
+fn update(path: &Path, change: impl FnOnce(&mut Counters)) -> std::io::Result<()> {
+    /// How long to wait for another run's lock before taking it over.
+    const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+    let lock = path.with_extension("lock");
+    let started = Instant::now();
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(_) => break,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                // A lock held this long was left behind by a killed run.
+                if started.elapsed() > LOCK_TIMEOUT {
+                    log::debug!("Taking over stale lock {}", lock.display());
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let result = (|| {
+        let mut counters = match fs::read_to_string(path) {
+            Ok(text) => parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Counters::default(),
+            Err(e) => return Err(e),
+        };
+        change(&mut counters);
+
+        // Replace the file in one step so a scrape never reads half of it.
+        let temp = path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&temp, render(&counters))?;
+        fs::rename(&temp, path)
+    })();
+    let _ = fs::remove_file(&lock);
+    result
+}
+
+/// Read the counters back from a metrics file; missing or unreadable
+/// samples count as zero.
+fn parse(text: &str) -> Counters {
+    let mut counters = Counters::default();
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let Some((name, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let value = value.trim();
+        match name {
+            SETUPS => counters.setups = value.parse().unwrap_or(0),
+            FAILURES => counters.failures = value.parse().unwrap_or(0),
+            BYTES_COPIED => counters.bytes_copied = value.parse().unwrap_or(0),
+            _ if name == format!("{DURATION}_sum") => {
+                counters.duration_sum = value.parse().unwrap_or(0.0);
+            }
+            _ => {}
+        }
+    }
+    counters
+}
+
+/// Render counters in the Prometheus text format.
+fn render(counters: &Counters) -> String {
+    let mut text = String::new();
+    for (name, help, value) in [
+        (SETUPS, "Setup runs that completed.", counters.setups),
+        (FAILURES, "Setup runs that failed.", counters.failures),
+        (
+            BYTES_COPIED,
+            "Bytes copied into worktrees by file operations.",
+            counters.bytes_copied,
+        ),
+    ] {
+        let _ = writeln!(text, "# HELP {name} {help}");
+        let _ = writeln!(text, "# TYPE {name} counter");
+        let _ = writeln!(text, "{name} {value}");
+    }
+    let _ = writeln!(text, "# HELP {DURATION} How long setup runs took.");
+    let _ = writeln!(text, "# TYPE {DURATION} summary");
+    let _ = writeln!(text, "{DURATION}_sum {}", counters.duration_sum);
+    let _ = writeln!(
+        text,
+        "{DURATION}_count {}",
+        counters.setups + counters.failures
+    );
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_parse_round_trip() {
+        let counters = Counters {
+            setups: 3,
+            failures: 1,
+            bytes_copied: 4096,
+            duration_sum: 12.5,
+        };
+        let text = render(&counters);
+        assert!(text.contains("worktree_setup_setups_total 3\n"));
+        assert!(text.contains("worktree_setup_setup_duration_seconds_count 4\n"));
+        assert_eq!(parse(&text), counters);
+    }
+
+    #[test]
+    fn test_update_adds_to_existing_counters() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("worktree-setup.prom");
+        for succeeded in [true, true, false] {
+            update(&path, |counters| {
+                if succeeded {
+                    counters.setups += 1;
+                } else {
+                    counters.failures += 1;
+                }
+                counters.bytes_copied += 100;
+                counters.duration_sum += 0.5;
+            })
+            .unwrap();
+        }
+
+        let counters = parse(&fs::read_to_string(&path).unwrap());
+        assert_eq!(
+            counters,
+            Counters {
+                setups: 2,
+                failures: 1,
+                bytes_copied: 300,
+                duration_sum: 1.5,
+            }
+        );
+        assert!(!path.with_extension("lock").exists());
+    }
+}
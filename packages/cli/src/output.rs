@@ -5,30 +5,29 @@
 #![allow(clippy::multiple_crate_versions)]
 
 use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
 
 use colored::Colorize;
+use worktree_setup_config::LoadedConfig;
+
+use crate::args::OutputFormat;
+use crate::reporter::{self, ErrorCode, say};
 
 /// Print a header message.
 pub fn print_header(message: &str) {
-    println!("\n🌳 {}\n", message.bold());
+    reporter::get().header(message);
 }
 
 /// Print repository info.
 pub fn print_repo_info(repo_root: &str) {
-    println!("Repository: {}", repo_root.cyan());
+    say!("Repository: {}", repo_root.cyan());
 }
 
 /// Print a list of discovered configs.
-pub fn print_config_list(configs: &[(String, String)]) {
-    println!(
-        "Found {} config{}:",
-        configs.len(),
-        if configs.len() == 1 { "" } else { "s" }
-    );
-    for (path, description) in configs {
-        println!("  {} {} - {}", "•".dimmed(), path.yellow(), description);
-    }
-    println!();
+pub fn print_config_list(configs: &[&LoadedConfig]) {
+    reporter::get().configs(configs);
 }
 
 /// Print a list of available profiles.
@@ -36,7 +35,7 @@ pub fn print_profile_list(profiles: &[(String, String, usize)]) {
     if profiles.is_empty() {
         return;
     }
-    println!(
+    say!(
         "Available profile{}:",
         if profiles.len() == 1 { "" } else { "s" }
     );
@@ -47,9 +46,9 @@ pub fn print_profile_list(profiles: &[(String, String, usize)]) {
             format!("{config_count} configs")
         };
         if description.is_empty() {
-            println!("  {} {} ({})", "•".dimmed(), name.cyan(), config_label);
+            say!("  {} {} ({})", "•".dimmed(), name.cyan(), config_label);
         } else {
-            println!(
+            say!(
                 "  {} {} - {} ({})",
                 "•".dimmed(),
                 name.cyan(),
@@ -58,7 +57,7 @@ pub fn print_profile_list(profiles: &[(String, String, usize)]) {
             );
         }
     }
-    println!();
+    say!();
 }
 
 /// Print profile usage info.
@@ -68,45 +67,163 @@ pub fn print_using_profile(names: &[String]) {
     } else {
         format!("Using profiles: {}", names.join(", "))
     };
-    println!("{}", label.cyan());
+    say!("{}", label.cyan());
 }
 
 /// Print the configs selected by a profile.
-pub fn print_profile_configs(configs: &[(String, String)]) {
-    println!(
+pub fn print_profile_configs(configs: &[&LoadedConfig]) {
+    say!(
         "Using {} config{}:",
         configs.len(),
         if configs.len() == 1 { "" } else { "s" }
     );
-    for (path, description) in configs {
-        println!("  {} {} - {}", "•".dimmed(), path.yellow(), description);
+    for config in configs {
+        say!("{}", reporter::format_config(config));
     }
-    println!();
+    say!();
 }
 
 /// Print a post-setup command being run.
 pub fn print_command(cmd: &str) {
-    println!("  {} {}", "$".dimmed(), cmd);
+    reporter::get().command(cmd);
 }
 
 /// Print success message.
 pub fn print_success() {
-    println!("✅ Worktree setup complete!");
+    reporter::get().success();
 }
 
 /// Print error message.
 pub fn print_error(message: &str) {
-    eprintln!("{} {}", "Error:".red().bold(), message);
+    reporter::get().error(message, None);
+    annotate(Severity::Error, None, message);
+}
+
+/// Print an error along with its code and help, when it (or an error it
+/// wraps) is one of the library errors.
+pub fn print_error_report(error: &(dyn std::error::Error + 'static)) {
+    let message = error.to_string();
+    let code = ErrorCode::of(error);
+    reporter::get().error(&message, code);
+    match code {
+        Some(code) => annotate(Severity::Error, None, &format!("[{}] {message}", code.code)),
+        None => annotate(Severity::Error, None, &message),
+    }
+}
+
+/// Print an error about a specific file (e.g. a stale config entry).
+///
+/// In CI output modes the annotation is attached to `file`.
+pub fn print_file_error(file: &Path, message: &str) {
+    reporter::get().error(&format!("{}: {message}", file.display()), None);
+    annotate(Severity::Error, Some(file), message);
 }
 
 /// Print a warning message.
 pub fn print_warning(message: &str) {
-    println!("{} {}", "Warning:".yellow().bold(), message);
+    reporter::get().warning(None, message);
+    annotate(Severity::Warning, None, message);
+}
+
+/// Print a warning about a specific file (e.g. a config that failed to load).
+///
+/// In CI output modes the annotation is attached to `file`.
+pub fn print_file_warning(file: &Path, message: &str) {
+    reporter::get().warning(Some(file), message);
+    annotate(Severity::Warning, Some(file), message);
+}
+
+/// Annotation format selected with `--output`.
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// GitLab Code Quality issues reported so far, rewritten to
+/// [`GITLAB_REPORT_FILE`] after each new issue.
+static GITLAB_ISSUES: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+
+/// Code Quality report written in `gitlab` output mode.
+pub const GITLAB_REPORT_FILE: &str = "gl-code-quality-report.json";
+
+/// Select the CI annotation format for warnings and errors.
+///
+/// Only the first call has an effect.
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+/// Severity of a CI annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+/// Emit a CI annotation for a warning or error in the selected format.
+fn annotate(severity: Severity, file: Option<&Path>, message: &str) {
+    match OUTPUT_FORMAT.get().copied().unwrap_or_default() {
+        OutputFormat::Human => {}
+        OutputFormat::Github => println!("{}", github_annotation(severity, file, message)),
+        OutputFormat::Gitlab => {
+            let report = {
+                let mut issues = GITLAB_ISSUES
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                issues.push(gitlab_issue(severity, file, message));
+                serde_json::to_string_pretty(&*issues).unwrap_or_default()
+            };
+            if let Err(e) = std::fs::write(GITLAB_REPORT_FILE, report) {
+                log::warn!("Failed to write {GITLAB_REPORT_FILE}: {e}");
+            }
+        }
+    }
+}
+
+/// Format a GitHub Actions workflow command (`::warning file=...::msg`).
+fn github_annotation(severity: Severity, file: Option<&Path>, message: &str) -> String {
+    let command = match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    // Workflow command data must escape `%`, CR, and LF; properties also
+    // escape `:` and `,`.
+    let data = message
+        .trim_end()
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    file.map_or_else(
+        || format!("::{command}::{data}"),
+        |file| {
+            let file = file
+                .to_string_lossy()
+                .replace('%', "%25")
+                .replace(':', "%3A")
+                .replace(',', "%2C");
+            format!("::{command} file={file}::{data}")
+        },
+    )
+}
+
+/// Build a GitLab Code Quality issue.
+fn gitlab_issue(severity: Severity, file: Option<&Path>, message: &str) -> serde_json::Value {
+    let path = file.map_or_else(String::new, |f| f.to_string_lossy().to_string());
+    let mut hasher = std::hash::DefaultHasher::new();
+    (&path, message).hash(&mut hasher);
+
+    serde_json::json!({
+        "description": message.trim_end(),
+        "check_name": "worktree-setup",
+        "fingerprint": format!("{:016x}", hasher.finish()),
+        "severity": match severity {
+            Severity::Warning => "minor",
+            Severity::Error => "major",
+        },
+        "location": { "path": path, "lines": { "begin": 1 } },
+    })
 }
 
 /// Print an informational message.
 pub fn print_info(message: &str) {
-    println!("{} {}", "Info:".cyan().bold(), message);
+    reporter::get().info(message);
 }
 
 /// An item resolved for cleaning (deletion).
@@ -187,7 +304,7 @@ fn display_size_cmp(left: u64, right: u64) -> Ordering {
 fn print_clean_item(item: &CleanItem, max_size: u64, indent: usize) {
     let padding = " ".repeat(indent);
     if item.is_empty_dir {
-        println!(
+        say!(
             "{padding}{} {} {}",
             "∅".cyan().bold(),
             "[empty dir]".cyan().bold(),
@@ -199,7 +316,7 @@ fn print_clean_item(item: &CleanItem, max_size: u64, indent: usize) {
     let type_label = if item.is_dir { "dir " } else { "file" };
     let type_label = format!("[{type_label}]");
     let size_label = color_by_size(&format_size(item.size), item.size);
-    println!(
+    say!(
         "{padding}{} {} {} {} ({size_label})",
         "•".dimmed(),
         type_label.dimmed(),
@@ -320,11 +437,11 @@ pub fn format_clean_stats_heat(stats: &CleanStats, max_size: u64) -> String {
 /// Prints a summary line with total count and total size.
 pub fn print_clean_preview(items: &[CleanItem]) {
     if items.is_empty() {
-        println!("Nothing to clean.");
+        say!("Nothing to clean.");
         return;
     }
 
-    println!(
+    say!(
         "Will delete {} item{}:",
         items.len(),
         if items.len() == 1 { "" } else { "s" }
@@ -338,7 +455,7 @@ pub fn print_clean_preview(items: &[CleanItem]) {
     }
 
     let stats = clean_stats(items);
-    println!(
+    say!(
         "\n  {} {}",
         "Total:".bold(),
         format_clean_stats_plain(&stats).bold()
@@ -347,7 +464,7 @@ pub fn print_clean_preview(items: &[CleanItem]) {
 
 /// Print a summary after cleaning completes.
 pub fn print_clean_summary(deleted_count: usize, total_size: u64) {
-    println!(
+    say!(
         "Deleted {} item{}, freed {}",
         deleted_count,
         if deleted_count == 1 { "" } else { "s" },
@@ -362,7 +479,7 @@ pub fn print_multi_worktree_clean_preview(groups: &[(String, Vec<CleanItem>)]) {
     let total_items: usize = groups.iter().map(|(_, items)| items.len()).sum();
 
     if total_items == 0 {
-        println!("Nothing to clean across selected worktrees.");
+        say!("Nothing to clean across selected worktrees.");
         return;
     }
 
@@ -391,7 +508,7 @@ pub fn print_multi_worktree_clean_preview(groups: &[(String, Vec<CleanItem>)]) {
 
     for (label, items) in sorted_groups {
         let stats = clean_stats(items);
-        println!(
+        say!(
             "  {} {}:",
             label.cyan().bold(),
             format!("({})", format_clean_stats_heat(&stats, max_group_size)).bold()
@@ -400,7 +517,7 @@ pub fn print_multi_worktree_clean_preview(groups: &[(String, Vec<CleanItem>)]) {
         for item in sorted_items_for_display(items) {
             print_clean_item(item, max_item_size, 4);
         }
-        println!();
+        say!();
     }
 
     let total_size: u64 = groups
@@ -418,7 +535,7 @@ pub fn print_multi_worktree_clean_preview(groups: &[(String, Vec<CleanItem>)]) {
         empty_dir_count,
         inaccessible: false,
     };
-    println!(
+    say!(
         "  {} {} across {worktree_count} worktree{}",
         "Total:".bold(),
         format_clean_stats_plain(&stats).bold(),
@@ -432,7 +549,7 @@ pub fn print_multi_worktree_clean_summary(
     total_size: u64,
     worktree_count: usize,
 ) {
-    println!(
+    say!(
         "Deleted {} item{} across {} worktree{}, freed {}",
         deleted_count,
         if deleted_count == 1 { "" } else { "s" },
@@ -462,11 +579,11 @@ pub struct RemoveDisplayInfo {
 /// uncommitted changes are flagged with a warning.
 pub fn print_remove_preview(worktrees: &[RemoveDisplayInfo]) {
     if worktrees.is_empty() {
-        println!("No worktrees to remove.");
+        say!("No worktrees to remove.");
         return;
     }
 
-    println!(
+    say!(
         "\nWill remove {} worktree{}:",
         worktrees.len(),
         if worktrees.len() == 1 { "" } else { "s" }
@@ -479,7 +596,7 @@ pub fn print_remove_preview(worktrees: &[RemoveDisplayInfo]) {
         } else {
             String::new()
         };
-        println!(
+        say!(
             "  {} {} {}{}",
             "•".dimmed(),
             label.cyan(),
@@ -487,13 +604,13 @@ pub fn print_remove_preview(worktrees: &[RemoveDisplayInfo]) {
             warning,
         );
     }
-    println!();
+    say!();
 }
 
 /// Print a summary after worktree removal completes.
 pub fn print_remove_summary(removed: usize, failed: usize) {
     if failed == 0 {
-        println!(
+        say!(
             "{}",
             format!(
                 "Removed {} worktree{}.",
@@ -503,7 +620,7 @@ pub fn print_remove_summary(removed: usize, failed: usize) {
             .green()
         );
     } else {
-        println!(
+        say!(
             "Removed {} worktree{}, {} failed.",
             removed,
             if removed == 1 { "" } else { "s" },
@@ -518,21 +635,165 @@ pub fn print_branch_delete_summary(deleted: &[String]) {
         return;
     }
 
-    println!(
+    say!(
         "Deleted {} branch{}:",
         deleted.len(),
         if deleted.len() == 1 { "" } else { "es" }
     );
     for branch in deleted {
-        println!("  {} {}", "•".dimmed(), branch.cyan());
+        say!("  {} {}", "•".dimmed(), branch.cyan());
     }
 }
 
 /// Print a note that the user's CWD was inside a removed worktree.
 pub fn print_cwd_removed_note() {
-    println!(
+    say!(
         "\n{} Your current directory was inside the removed worktree. Run {} to return to a valid directory.",
         "Note:".yellow().bold(),
         "cd ..".bold(),
     );
 }
+
+/// Format the time elapsed since a Unix timestamp (e.g., "3 hours ago").
+#[must_use]
+pub fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let secs = now.saturating_sub(timestamp);
+
+    let (value, unit) = if secs < 60 {
+        return "just now".to_string();
+    } else if secs < 3600 {
+        (secs / 60, "minute")
+    } else if secs < 86_400 {
+        (secs / 3600, "hour")
+    } else {
+        (secs / 86_400, "day")
+    };
+    format!("{value} {unit}{} ago", if value == 1 { "" } else { "s" })
+}
+
+/// Print the configs applied to a worktree as `(config, applied_at, changed)`.
+pub fn print_applied_configs(configs: &[(String, u64, bool)]) {
+    say!(
+        "Applied {} config{}:",
+        configs.len(),
+        if configs.len() == 1 { "" } else { "s" }
+    );
+    for (path, applied_at, changed) in configs {
+        let changed_label = if *changed {
+            format!(" {}", "(config changed since)".yellow())
+        } else {
+            String::new()
+        };
+        say!(
+            "  {} {} - {}{changed_label}",
+            "•".dimmed(),
+            path.yellow(),
+            format_age(*applied_at).dimmed(),
+        );
+    }
+    say!();
+}
+
+/// Print what the setup manifest recorded for a worktree, with the number
+/// of files each config wrote.
+pub fn print_manifest_summary(manifest: &worktree_setup_operations::Manifest) {
+    say!(
+        "Last file operations: {} ({} file{} recorded)",
+        format_age(manifest.applied_at).cyan(),
+        manifest.files.len(),
+        if manifest.files.len() == 1 { "" } else { "s" },
+    );
+
+    let by_config = manifest.files_by_config();
+    if by_config.keys().any(Option::is_some) {
+        for (config, count) in &by_config {
+            say!(
+                "  {} {} {}",
+                "•".dimmed(),
+                config.map_or_else(|| "(no config)".dimmed(), Colorize::yellow),
+                format!("{count} file{}", if *count == 1 { "" } else { "s" }).dimmed(),
+            );
+        }
+    }
+    say!();
+}
+
+/// Print changes detected between a worktree and its setup manifest.
+pub fn print_manifest_changes(changes: &worktree_setup_operations::ManifestChanges) {
+    if changes.is_empty() {
+        say!("{}", "No changes since last setup.".green());
+        return;
+    }
+
+    let sections: [(&str, &[String]); 4] = [
+        ("Modified in worktree", &changes.modified),
+        ("Deleted from worktree", &changes.deleted),
+        ("Source changed in main worktree", &changes.source_changed),
+        ("Source removed from main worktree", &changes.source_removed),
+    ];
+    for (title, paths) in sections {
+        if paths.is_empty() {
+            continue;
+        }
+        say!("{} ({}):", title.bold(), paths.len());
+        for path in paths {
+            say!("  {} {}", "•".dimmed(), path.yellow());
+        }
+        say!();
+    }
+
+    if !changes.new_entries.is_empty() {
+        say!(
+            "{} ({}):",
+            "Config entries not yet applied".bold(),
+            changes.new_entries.len()
+        );
+        for (config, entry) in &changes.new_entries {
+            say!("  {} {} {}", "•".dimmed(), entry.cyan(), config.dimmed());
+        }
+        say!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_annotation_escapes_data_and_properties() {
+        assert_eq!(
+            github_annotation(Severity::Error, None, "50% done\nnext\n"),
+            "::error::50%25 done%0Anext"
+        );
+        assert_eq!(
+            github_annotation(
+                Severity::Warning,
+                Some(Path::new("apps/a,b/worktree.config.ts")),
+                "Failed to load"
+            ),
+            "::warning file=apps/a%2Cb/worktree.config.ts::Failed to load"
+        );
+    }
+
+    #[test]
+    fn test_gitlab_issue_fields() {
+        let issue = gitlab_issue(
+            Severity::Warning,
+            Some(Path::new("worktree.config.toml")),
+            "bad config",
+        );
+        assert_eq!(issue["severity"], "minor");
+        assert_eq!(issue["location"]["path"], "worktree.config.toml");
+        assert_eq!(
+            issue["fingerprint"],
+            gitlab_issue(
+                Severity::Warning,
+                Some(Path::new("worktree.config.toml")),
+                "bad config",
+            )["fingerprint"]
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Pool subcommand.
+//!
+//! Keeps worktrees created and set up ahead of time, so tools that start
+//! worktrees constantly (coding agents, CI jobs) get a ready one at once
+//! instead of waiting for copies and installs. Pool worktrees are tracked
+//! in the state store by their `pool` label: `creating` while being set
+//! up, `ready` once done, `claimed` after `pool claim` handed them out, and
+//! `failed` if their setup failed or was interrupted.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+use worktree_setup_config::{expand_home, load_global_config};
+use worktree_setup_git::{discover_repo, get_main_worktree};
+use worktree_setup_operations::{Label, StateLock, StateStore, WorktreeState};
+
+use crate::args::{PoolArgs, PoolClaimArgs, PoolCommand, PoolEnsureArgs};
+#[cfg(feature = "metrics")]
+use crate::metrics;
+use crate::output;
+use crate::reporter::say;
+
+/// Label key marking pool worktrees.
+const POOL_LABEL: &str = "pool";
+
+/// `pool` label of a worktree being created and set up.
+const CREATING: &str = "creating";
+
+/// `pool` label of a worktree waiting to be claimed.
+const READY: &str = "ready";
+
+/// `pool` label of a worktree handed out by `pool claim`.
+const CLAIMED: &str = "claimed";
+
+/// `pool` label of a worktree whose setup failed; it is left for
+/// inspection and not counted in the pool.
+const FAILED: &str = "failed";
+
+/// Label recording the `--profile`s a pool worktree was set up with,
+/// comma-separated, so refills after a claim use them too.
+const PROFILE_LABEL: &str = "pool-profile";
+
+/// Label marking a pool worktree set up with `--no-install`.
+const NO_INSTALL_LABEL: &str = "pool-no-install";
+
+/// State store lock held by `pool ensure` while it fills the pool.
+const POOL_LOCK: &str = "pool";
+
+/// How often a waiting `pool ensure` retries the pool lock.
+const POOL_LOCK_POLL: Duration = Duration::from_millis(100);
+
+/// Run the `pool` subcommand.
+pub fn run(args: &PoolArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match &args.command {
+        PoolCommand::Ensure(ensure_args) => ensure(ensure_args),
+        PoolCommand::Claim(claim_args) => claim(claim_args),
+    }
+}
+
+/// The `pool` label with `value`.
+fn pool_label(value: &str) -> Label {
+    Label {
+        key: POOL_LABEL.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// Pool worktrees that are ready or still being set up.
+fn pooled(store: &StateStore) -> Vec<WorktreeState> {
+    store
+        .list()
+        .into_iter()
+        .filter(|state| {
+            state.worktree.exists()
+                && (state.has_labels(&[pool_label(READY)])
+                    || state.has_labels(&[pool_label(CREATING)]))
+        })
+        .collect()
+}
+
+/// Mark pool worktrees still labelled `creating` as `failed`, returning how
+/// many there were.
+///
+/// Only `pool ensure` creates pool worktrees, and it holds the pool lock
+/// while doing so, so while `_lock` is held any worktree still being
+/// created was left behind by a run that died.
+fn reap_abandoned(
+    store: &StateStore,
+    _lock: &StateLock,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let abandoned: Vec<WorktreeState> = store
+        .list()
+        .into_iter()
+        .filter(|state| state.has_labels(&[pool_label(CREATING)]))
+        .collect();
+    for state in &abandoned {
+        set_pool_label(store, &state.worktree, FAILED)?;
+    }
+    Ok(abandoned.len())
+}
+
+/// Create and set up worktrees until the pool holds `args.size`.
+fn ensure(args: &PoolEnsureArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let repo = discover_repo(&crate::start_dir(&cwd))?;
+    let main_worktree = get_main_worktree(&repo)?;
+    let store = StateStore::for_repository(&repo);
+
+    // Held while counting and filling, so concurrent runs (such as the
+    // refills of back-to-back claims) do not each create the missing
+    // worktrees; a later run waits and then tops up what is still missing.
+    let mut waiting = false;
+    let lock = loop {
+        if let Some(lock) = store.try_lock(POOL_LOCK)? {
+            break lock;
+        }
+        if !waiting {
+            output::print_info("Waiting for another `pool ensure` to finish");
+            waiting = true;
+        }
+        thread::sleep(POOL_LOCK_POLL);
+    };
+    let abandoned = reap_abandoned(&store, &lock)?;
+    if abandoned > 0 {
+        output::print_warning(&format!(
+            "Marked {abandoned} pool worktree{} left by an interrupted `pool ensure` as {FAILED}",
+            if abandoned == 1 { "" } else { "s" }
+        ));
+    }
+
+    let existing = pooled(&store).len();
+    if existing >= args.size {
+        output::print_info(&format!("The pool already holds {existing} worktrees."));
+        return Ok(());
+    }
+
+    let global_config = load_global_config(Some(&main_worktree.path))?;
+    let base = args
+        .target_base
+        .as_ref()
+        .map(|base| cwd.join(base))
+        .or_else(|| {
+            global_config
+                .create
+                .target_base
+                .as_ref()
+                .map(|base| main_worktree.path.join(expand_home(base)))
+        })
+        .or_else(|| main_worktree.path.parent().map(Path::to_path_buf))
+        .ok_or("Could not determine where to create pool worktrees")?;
+    let repo_name = main_worktree
+        .path
+        .file_name()
+        .map_or_else(|| "repo".into(), |name| name.to_string_lossy());
+
+    for _ in existing..args.size {
+        let name = pool_worktree_name(&repo_name, &base);
+        let target = base.join(&name);
+        if let Err(e) = create_pool_worktree(args, &main_worktree.path, &target, &name) {
+            if target.exists() {
+                let _ = set_pool_label(&store, &target, FAILED);
+            }
+            return Err(e);
+        }
+        set_pool_label(&store, &target, READY)?;
+    }
+
+    say!(
+        "{} The pool holds {} worktrees.",
+        "✓".green(),
+        pooled(&store).len()
+    );
+    Ok(())
+}
+
+/// Set the `pool` label of the worktree at `worktree` to `value`.
+fn set_pool_label(
+    store: &StateStore,
+    worktree: &Path,
+    value: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = store
+        .load(worktree)?
+        .unwrap_or_else(|| WorktreeState::new(worktree));
+    state.add_labels(&[pool_label(value)]);
+    store.save(&state)?;
+    Ok(())
+}
+
+/// A name for a new pool worktree under `base`, unique by creation time.
+fn pool_worktree_name(repo_name: &str, base: &Path) -> String {
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        let name = format!("{repo_name}-pool-{:010x}", nanos & 0xff_ffff_ffff);
+        if !base.join(&name).exists() {
+            return name;
+        }
+    }
+}
+
+/// Create and set up one pool worktree at `target` by running the default
+/// create flow, labelled as being created until it is ready.
+fn create_pool_worktree(
+    args: &PoolEnsureArgs,
+    main_worktree: &Path,
+    target: &Path,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .arg("--repo")
+        .arg(main_worktree)
+        .arg(target)
+        .args(["--non-interactive", "--no-progress", "--new-branch", name])
+        .arg("--label")
+        .arg(pool_label(CREATING).to_string());
+    if !args.profile.is_empty() {
+        command
+            .arg("--label")
+            .arg(format!("{PROFILE_LABEL}={}", args.profile.join(",")));
+    }
+    if args.no_install {
+        command
+            .arg("--label")
+            .arg(format!("{NO_INSTALL_LABEL}=true"));
+    }
+    #[cfg(feature = "metrics")]
+    if let Some(metrics_file) = metrics::metrics_file() {
+        command.arg("--metrics-file").arg(metrics_file);
+    }
+    for config in &args.configs {
+        command.args(["--config", config]);
+    }
+    for profile in &args.profile {
+        command.args(["--profile", profile]);
+    }
+    if args.no_install {
+        command.arg("--no-install");
+    }
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(format!("Failed to create pool worktree {}", target.display()).into());
+    }
+    Ok(())
+}
+
+/// Hand out a ready worktree and refill the pool in the background.
+fn claim(args: &PoolClaimArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let repo = discover_repo(&crate::start_dir(&cwd))?;
+    let main_worktree = get_main_worktree(&repo)?;
+    let store = StateStore::for_repository(&repo);
+
+    // Worktrees abandoned mid-setup only count while a `pool ensure` that
+    // could still finish them is running.
+    if let Some(lock) = store.try_lock(POOL_LOCK)? {
+        reap_abandoned(&store, &lock)?;
+    }
+    let size = pooled(&store).len();
+    let mut labels = vec![pool_label(CLAIMED)];
+    labels.extend(args.labels.iter().cloned());
+    let state = store.claim(&[pool_label(READY)], &labels)?.ok_or(
+        "No ready worktree in the pool. Fill it with `worktree-setup pool ensure --size <n>`.",
+    )?;
+
+    if !args.no_replace
+        && let Err(e) = spawn_refill(&main_worktree.path, &state, size)
+    {
+        output::print_warning(&format!("Failed to start refilling the pool: {e}"));
+    }
+
+    say!("{}", state.worktree.display());
+    Ok(())
+}
+
+/// Start `pool ensure --size <size>` in the background, detached from this
+/// process, setting up replacements next to the `claimed` worktree with
+/// its configs, profiles, and `--no-install`.
+fn spawn_refill(main_worktree: &Path, claimed: &WorktreeState, size: usize) -> std::io::Result<()> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .arg("--repo")
+        .arg(main_worktree)
+        .args(["pool", "ensure", "--size", &size.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(base) = claimed.worktree.parent() {
+        command.arg("--target-base").arg(base);
+    }
+    #[cfg(feature = "metrics")]
+    if let Some(metrics_file) = metrics::metrics_file() {
+        command.arg("--metrics-file").arg(metrics_file);
+    }
+    command.args(refill_args(claimed));
+    #[cfg(unix)]
+    {
+        // Keep a Ctrl-C meant for the caller from stopping the refill.
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+    }
+    command.spawn().map(drop)
+}
+
+/// `pool ensure` arguments that set up worktrees like `claimed`.
+fn refill_args(claimed: &WorktreeState) -> Vec<String> {
+    let mut args = Vec::new();
+    for config in claimed.configs.keys() {
+        args.extend(["--config".to_string(), config.clone()]);
+    }
+    if let Some(profiles) = claimed.labels.get(PROFILE_LABEL) {
+        for profile in profiles.split(',').filter(|p| !p.is_empty()) {
+            args.extend(["--profile".to_string(), profile.to_string()]);
+        }
+    }
+    if claimed.labels.contains_key(NO_INSTALL_LABEL) {
+        args.push("--no-install".to_string());
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pooled_counts_ready_and_creating() {
+        let state_dir = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().to_path_buf());
+        let worktrees: Vec<TempDir> = (0..4).map(|_| TempDir::new().unwrap()).collect();
+        for (worktree, value) in worktrees.iter().zip([READY, CREATING, CLAIMED, "other"]) {
+            let mut state = WorktreeState::new(worktree.path());
+            state.add_labels(&[pool_label(value)]);
+            store.save(&state).unwrap();
+        }
+        assert_eq!(pooled(&store).len(), 2);
+
+        let claimed = store
+            .claim(&[pool_label(READY)], &[pool_label(CLAIMED)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            claimed.worktree,
+            worktrees[0].path().canonicalize().unwrap()
+        );
+        assert_eq!(pooled(&store).len(), 1);
+    }
+
+    #[test]
+    fn test_reap_abandoned_marks_creating_failed() {
+        let state_dir = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().to_path_buf());
+        let worktrees: Vec<TempDir> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        for (worktree, value) in worktrees.iter().zip([READY, CREATING]) {
+            set_pool_label(&store, worktree.path(), value).unwrap();
+        }
+
+        let lock = store.try_lock(POOL_LOCK).unwrap().unwrap();
+        assert_eq!(reap_abandoned(&store, &lock).unwrap(), 1);
+        assert_eq!(pooled(&store).len(), 1);
+        let reaped = store.load(worktrees[1].path()).unwrap().unwrap();
+        assert!(reaped.has_labels(&[pool_label(FAILED)]));
+    }
+
+    #[test]
+    fn test_refill_args_repeat_profiles_and_no_install() {
+        let worktree = TempDir::new().unwrap();
+        let mut state = WorktreeState::new(worktree.path());
+        state.add_labels(&[
+            pool_label(CLAIMED),
+            format!("{PROFILE_LABEL}=agent,fast").parse().unwrap(),
+            format!("{NO_INSTALL_LABEL}=true").parse().unwrap(),
+        ]);
+        assert_eq!(
+            refill_args(&state),
+            ["--profile", "agent", "--profile", "fast", "--no-install"]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Quiet post-setup runs.
+//!
+//! With `--quiet-post-setup`, post-setup commands run with their output
+//! captured instead of streamed. Each command gets one pass/fail line, a
+//! failing command also shows the last lines it printed, and a summary
+//! follows, so installs like `npm ci` that print thousands of lines leave
+//! a short log.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Instant;
+
+use colored::Colorize;
+
+use crate::output;
+use crate::reporter::say;
+
+/// The last lines of a command's output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tail {
+    /// Kept lines, oldest first.
+    lines: VecDeque<String>,
+    /// How many lines to keep.
+    limit: usize,
+    /// Lines seen in total.
+    total: usize,
+}
+
+impl Tail {
+    const fn new(limit: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            limit,
+            total: 0,
+        }
+    }
+
+    /// Add a line, dropping the oldest once `limit` are kept.
+    fn push(&mut self, line: String) {
+        self.total += 1;
+        if self.limit == 0 {
+            return;
+        }
+        if self.lines.len() == self.limit {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+}
+
+/// Run `commands` in `target_path` with their output captured, printing
+/// the last `tail_lines` lines of each one that fails.
+pub fn run_quietly(
+    commands: &[&str],
+    target_path: &Path,
+    tail_lines: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    say!("Running post-setup commands (output hidden):");
+
+    let mut failed = 0usize;
+    for cmd in commands {
+        let started = Instant::now();
+        let (status, tail) = capture(cmd, target_path, tail_lines)?;
+        let elapsed = format!("({:.1}s)", started.elapsed().as_secs_f64()).dimmed();
+
+        if status.success() {
+            say!("  {} {cmd} {elapsed}", "✓".green());
+            continue;
+        }
+
+        failed += 1;
+        say!(
+            "  {} {cmd} {elapsed} {}",
+            "✗".red(),
+            status.to_string().red()
+        );
+        let hidden = tail.total - tail.lines.len();
+        if hidden > 0 {
+            say!(
+                "    {}",
+                format!(
+                    "... {hidden} earlier line{} hidden",
+                    if hidden == 1 { "" } else { "s" }
+                )
+                .dimmed()
+            );
+        }
+        for line in &tail.lines {
+            say!("    {} {line}", "│".dimmed());
+        }
+    }
+
+    say!();
+    if failed > 0 {
+        output::print_warning(&format!(
+            "{failed} of {} post-setup command{} failed",
+            commands.len(),
+            if commands.len() == 1 { "" } else { "s" }
+        ));
+    } else {
+        say!(
+            "{} {} post-setup command{} passed",
+            "✓".green(),
+            commands.len(),
+            if commands.len() == 1 { "" } else { "s" }
+        );
+    }
+    say!();
+
+    Ok(())
+}
+
+/// Run `cmd` through `sh -c` with stdout and stderr merged into one
+/// captured stream, keeping its last `tail_lines` lines.
+fn capture(cmd: &str, cwd: &Path, tail_lines: usize) -> io::Result<(ExitStatus, Tail)> {
+    let (reader, writer) = io::pipe()?;
+    let mut child = {
+        // The command holds the write ends; drop it once spawned so the
+        // reader sees end-of-file when the child exits.
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", cmd])
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .stdout(writer.try_clone()?)
+            .stderr(writer);
+        command.spawn()?
+    };
+
+    let mut tail = Tail::new(tail_lines);
+    for line in BufReader::new(reader).split(b'\n') {
+        tail.push(String::from_utf8_lossy(&line?).trim_end().to_string());
+    }
+
+    Ok((child.wait()?, tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_keeps_last_lines_and_counts_all() {
+        let mut tail = Tail::new(2);
+        for line in ["a", "b", "c"] {
+            tail.push(line.to_string());
+        }
+        assert_eq!(tail.lines, ["b", "c"]);
+        assert_eq!(tail.total, 3);
+
+        let mut none = Tail::new(0);
+        none.push("a".to_string());
+        assert!(none.lines.is_empty());
+        assert_eq!(none.total, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_merges_output_and_reports_failure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (status, tail) =
+            capture("echo one; echo two >&2; echo three; exit 3", dir.path(), 2).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(tail.lines, ["two", "three"]);
+        assert_eq!(tail.total, 3);
+    }
+}
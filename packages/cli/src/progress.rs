@@ -4,98 +4,734 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
-use std::io::Write;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use colored::Colorize;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 
-/// Progress bar manager for copy operations.
+use crate::args::ProgressMode;
+use crate::reporter::{self, OperationReport, ProgressReport};
+
+/// Minimum time between two lines in [`ProgressMode::Plain`].
+const PLAIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Minimum time between two throughput samples; closer updates are
+/// folded into the next sample.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time over which throughput is averaged. Older samples fade out
+/// exponentially, so the rate follows real changes (a slow directory
+/// after a fast one) without jumping with every update.
+const SMOOTHING_WINDOW: Duration = Duration::from_secs(10);
+
+/// How often the stall watchdog checks for progress.
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long an operation may go without progress before it is reported
+/// as stalled, unless `stall_warning` is set.
+pub const DEFAULT_STALL_WARNING: Duration = Duration::from_secs(30);
+
+/// Progress display for planning and executing file operations.
+///
+/// All bars live in a single [`MultiProgress`] and render together: the
+/// scanning bar (left in its finished state once planning completes), an
+/// overall bar counting files across every operation, and a bar for the
+/// directory currently being copied, attached only while one is active.
+/// A single large file being streamed gets a bar counting bytes instead.
+/// Result lines are printed above the bars without tearing them down.
+///
+/// In [`ProgressMode::Plain`] the bars stay hidden and the overall count is
+/// printed to stderr as a plain line at most every [`PLAIN_INTERVAL`], as
+/// are the bytes of a large file.
 pub struct ProgressManager {
-    multi: Arc<MultiProgress>,
-    enabled: bool,
+    multi: MultiProgress,
+    scanning: ProgressBar,
+    overall: ProgressBar,
+    directory: ProgressBar,
+    bytes: ProgressBar,
+    /// When and at which count the last plain line was printed, in plain
+    /// mode only.
+    plain: Option<Mutex<(Instant, u64)>>,
+    /// Smoothed rates of the overall, directory, and byte bars.
+    rates: Mutex<Rates>,
+    /// The running operation and its last progress, shared with the stall
+    /// watchdog.
+    activity: Arc<Mutex<Activity>>,
+    /// Stops the stall watchdog when dropped.
+    watchdog: Option<mpsc::Sender<()>>,
+}
+
+/// Smoothed rates of each bar.
+#[derive(Debug, Default)]
+struct Rates {
+    overall: Throughput,
+    directory: Throughput,
+    bytes: Throughput,
+}
+
+/// Exponential moving average of how fast a count grows.
+#[derive(Debug, Clone, Copy, Default)]
+struct Throughput {
+    /// Time and count of the last sample.
+    last: Option<(Instant, u64)>,
+    /// Smoothed units per second, once two samples were taken.
+    rate: Option<f64>,
+}
+
+impl Throughput {
+    /// Record that the count reached `position` at `now`.
+    ///
+    /// Returns whether a sample was taken, which happens at most every
+    /// [`SAMPLE_INTERVAL`].
+    #[allow(clippy::cast_precision_loss)]
+    fn update(&mut self, position: u64, now: Instant) -> bool {
+        let Some((at, previous)) = self.last else {
+            self.last = Some((now, position));
+            return true;
+        };
+        let elapsed = now.saturating_duration_since(at);
+        if elapsed < SAMPLE_INTERVAL {
+            return false;
+        }
+
+        let elapsed = elapsed.as_secs_f64();
+        let sample = position.saturating_sub(previous) as f64 / elapsed;
+        // Weight by elapsed time so irregular updates average correctly.
+        let weight = 1.0 - (-elapsed / SMOOTHING_WINDOW.as_secs_f64()).exp();
+        self.rate = Some(
+            self.rate
+                .map_or(sample, |rate| weight.mul_add(sample - rate, rate)),
+        );
+        self.last = Some((now, position));
+        true
+    }
+
+    /// Time left to reach `total` from `position` at the current rate.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn eta(&self, position: u64, total: u64) -> Option<Duration> {
+        let rate = self.rate.filter(|rate| *rate > 0.0)?;
+        let remaining = total.saturating_sub(position) as f64 / rate;
+        Some(Duration::from_secs(remaining.ceil() as u64))
+    }
+
+    /// Rate and ETA for display, e.g. `310 files/s, ETA 12s`, or an
+    /// empty string until a rate is known.
+    fn describe(&self, position: u64, total: u64, format_rate: impl Fn(f64) -> String) -> String {
+        let Some(rate) = self.rate else {
+            return String::new();
+        };
+        match self.eta(position, total) {
+            Some(eta) if total > position => {
+                format!("{}, ETA {}", format_rate(rate), format_eta(eta))
+            }
+            _ => format_rate(rate),
+        }
+    }
+}
+
+/// The operation being executed, as seen by the stall watchdog.
+#[derive(Debug)]
+struct Activity {
+    /// Label of the running operation, if any.
+    current: Option<String>,
+    /// When the running operation last made progress.
+    last_progress: Instant,
+    /// Whether the current stall was already reported.
+    warned: bool,
+}
+
+impl Activity {
+    /// Note progress, ending any stall.
+    fn progressed(&mut self) {
+        self.last_progress = Instant::now();
+        self.warned = false;
+    }
+
+    /// The label and duration of a stall that is due to be reported.
+    ///
+    /// Each stall is reported once; progress starts watching again.
+    fn take_stall(&mut self, now: Instant, after: Duration) -> Option<(String, Duration)> {
+        let label = self.current.as_ref()?;
+        let idle = now.saturating_duration_since(self.last_progress);
+        if self.warned || idle < after {
+            return None;
+        }
+        self.warned = true;
+        Some((label.clone(), idle))
+    }
 }
 
 impl ProgressManager {
     /// Create a new progress manager.
+    ///
+    /// Unless `mode` is [`ProgressMode::Bar`] every bar is hidden, but
+    /// result lines are still printed.
     #[must_use]
-    pub fn new(enabled: bool) -> Self {
+    pub fn new(mode: ProgressMode) -> Self {
+        let multi = if mode == ProgressMode::Bar {
+            MultiProgress::new()
+        } else {
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+        };
+
         Self {
-            multi: Arc::new(MultiProgress::new()),
-            enabled,
+            multi,
+            scanning: styled_bar(" Scanning [{bar:20.green/dim}] {pos}/{len} {msg}"),
+            overall: styled_bar(" Copying  [{bar:20.cyan/dim}] {pos}/{len} files {msg}"),
+            directory: styled_bar("  {prefix:<30} [{bar:25.green/dim}] {pos}/{len} files {msg}"),
+            bytes: styled_bar("  {prefix:<30} [{bar:25.green/dim}] {bytes}/{total_bytes} {msg}"),
+            plain: (mode == ProgressMode::Plain).then(|| Mutex::new((Instant::now(), 0))),
+            rates: Mutex::new(Rates::default()),
+            activity: Arc::new(Mutex::new(Activity {
+                current: None,
+                last_progress: Instant::now(),
+                warned: false,
+            })),
+            watchdog: None,
         }
     }
 
-    /// Create a progress bar for a directory copy operation.
+    /// Warn when an operation started with [`Self::start_operation`] makes
+    /// no progress for `after` (a zero duration turns this off).
     ///
-    /// Returns a `ProgressBar` that shows file count progress.
-    /// If progress is disabled, returns a hidden progress bar.
+    /// A stuck copy, typically from a slow or unresponsive network mount,
+    /// then prints a hint instead of leaving the bars frozen.
     #[must_use]
-    pub fn create_file_bar(&self, label: &str, total: u64) -> ProgressBar {
-        if !self.enabled {
-            return ProgressBar::hidden();
+    pub fn with_stall_warning(mut self, after: Duration) -> Self {
+        if after.is_zero() {
+            return self;
         }
 
-        let pb = self.multi.add(ProgressBar::new(total));
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("  {prefix:<30} [{bar:25.green/dim}] {pos}/{len} files")
-                .expect("Invalid progress bar template")
-                .progress_chars("━━─"),
-        );
-        pb.set_prefix(label.to_string());
-        pb
+        let (stop, stopped) = mpsc::channel::<()>();
+        let multi = self.multi.clone();
+        let activity = Arc::clone(&self.activity);
+        std::thread::spawn(move || {
+            while stopped.recv_timeout(STALL_CHECK_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+                let stall = match activity.lock() {
+                    Ok(mut activity) => activity.take_stall(Instant::now(), after),
+                    Err(_) => return,
+                };
+                if let Some((label, idle)) = stall {
+                    multi.suspend(|| {
+                        reporter::get().warning(None, &format_stall_warning(&label, idle));
+                    });
+                }
+            }
+        });
+        self.watchdog = Some(stop);
+        self
+    }
+
+    /// Mark `label` as the running operation for stall detection.
+    pub fn start_operation(&self, label: &str) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.current = Some(label.to_string());
+            activity.progressed();
+        }
     }
 
-    /// Print a completed operation result line.
+    /// Mark the running operation as finished.
+    pub fn finish_operation(&self) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.current = None;
+        }
+    }
+
+    /// Note progress of the running operation.
+    fn progressed(&self) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.progressed();
+        }
+    }
+
+    /// Update one of the smoothed rates to the progress in `report` and
+    /// describe it.
     ///
-    /// Shows a checkmark for success, bullet for skipped.
-    #[allow(clippy::unused_self)]
-    pub fn print_result(&self, label: &str, result: &str, is_success: bool) {
-        if is_success {
-            println!("{} {:<30} {}", "✓".green(), label, result.dimmed());
-        } else {
-            println!("{} {:<30} {}", "•".dimmed(), label, result.dimmed());
+    /// The progress is reported whenever a rate sample is taken and when
+    /// the count reaches its total.
+    fn update_rate(
+        &self,
+        rate: impl FnOnce(&mut Rates) -> &mut Throughput,
+        report: &ProgressReport<'_>,
+        format_rate: impl Fn(f64) -> String,
+    ) -> String {
+        let Ok(mut rates) = self.rates.lock() else {
+            return String::new();
+        };
+        let throughput = rate(&mut rates);
+        let sampled = throughput.update(report.done, Instant::now());
+        let described = throughput.describe(report.done, report.total, format_rate);
+        drop(rates);
+        if sampled || report.done >= report.total {
+            reporter::get().progress(report);
         }
-        // Flush to ensure output appears immediately
-        let _ = std::io::stdout().flush();
-    }
-
-    /// Print a result with file count for directory operations.
-    #[allow(clippy::unused_self)]
-    pub fn print_result_with_count(&self, label: &str, result: &str, file_count: u64) {
-        println!(
-            "{} {:<30} {} ({} files)",
-            "✓".green(),
-            label,
-            result.dimmed(),
-            file_count
-        );
-        let _ = std::io::stdout().flush();
+        described
     }
 
-    /// Create a progress bar for scanning operations.
+    /// Show the scanning bar for planning `total` config entries.
     ///
-    /// Shows current operation being scanned with file count during enumeration.
-    /// If progress is disabled, returns a hidden progress bar.
+    /// Returns the bar so the planner callback can update position and
+    /// message.
     #[must_use]
-    pub fn create_scanning_bar(&self, total: u64) -> ProgressBar {
-        if !self.enabled {
-            return ProgressBar::hidden();
+    pub fn start_scanning(&self, total: u64) -> &ProgressBar {
+        self.scanning.set_length(total);
+        self.multi.add(self.scanning.clone());
+        &self.scanning
+    }
+
+    /// Mark scanning as complete, leaving the bar on screen.
+    pub fn finish_scanning(&self) {
+        self.scanning.finish_with_message("done");
+    }
+
+    /// Show the overall bar for copying `total_files` files.
+    pub fn start_overall(&self, total_files: u64) {
+        self.overall.set_length(total_files);
+        self.overall.set_position(0);
+        if let Ok(mut rates) = self.rates.lock() {
+            rates.overall = Throughput::default();
         }
+        self.multi.add(self.overall.clone());
+    }
+
+    /// Number of files counted on the overall bar so far.
+    #[must_use]
+    pub fn files_done(&self) -> u64 {
+        self.overall.position()
+    }
+
+    /// Set the number of files counted on the overall bar.
+    pub fn set_files_done(&self, files: u64) {
+        self.overall.set_position(files);
+        let total = self.overall.length().unwrap_or(0);
+        let report = ProgressReport {
+            path: None,
+            unit: "files",
+            done: files,
+            total,
+        };
+        let rate = self.update_rate(|rates| &mut rates.overall, &report, format_file_rate);
+        self.overall.set_message(parenthesized(&rate));
+        self.progressed();
+        self.print_plain(files, &rate);
+    }
 
-        let pb = self.multi.add(ProgressBar::new(total));
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template(" Scanning [{bar:20.green/dim}] {pos}/{len} {msg}")
-                .expect("Invalid progress bar template")
-                .progress_chars("━━─"),
+    /// Update the overall total for copies that only learn it as they go.
+    pub fn set_files_total(&self, total: u64) {
+        self.overall.set_length(total);
+    }
+
+    /// Print a plain progress line, followed by `rate`, if due.
+    ///
+    /// A line is due when the count changed and either the interval has
+    /// passed since the last line or the copy just finished.
+    fn print_plain(&self, files: u64, rate: &str) {
+        let Some(plain) = &self.plain else {
+            return;
+        };
+        let total = self.overall.length().unwrap_or(0);
+        let Ok(mut last) = plain.lock() else {
+            return;
+        };
+        let (printed_at, printed_files) = *last;
+        let finished = total > 0 && files >= total;
+        if files == 0
+            || files == printed_files
+            || (!finished && printed_at.elapsed() < PLAIN_INTERVAL)
+        {
+            return;
+        }
+        *last = (Instant::now(), files);
+        eprintln!("{}", with_rate(format_plain_progress(files, total), rate));
+    }
+
+    /// Show the directory bar for copying `total` files under `label`.
+    pub fn start_directory(&self, label: &str, total: u64) {
+        self.directory.reset();
+        self.directory.set_length(total);
+        self.directory.set_prefix(label.to_string());
+        self.directory.set_message("");
+        if let Ok(mut rates) = self.rates.lock() {
+            rates.directory = Throughput::default();
+        }
+        self.multi.add(self.directory.clone());
+    }
+
+    /// Set the number of files copied in the current directory.
+    pub fn set_directory_done(&self, files: u64) {
+        self.directory.set_position(files);
+        let total = self.directory.length().unwrap_or(0);
+        let prefix = self.directory.prefix();
+        let report = ProgressReport {
+            path: Some(&prefix),
+            unit: "files",
+            done: files,
+            total,
+        };
+        let rate = self.update_rate(|rates| &mut rates.directory, &report, format_file_rate);
+        self.directory.set_message(parenthesized(&rate));
+        self.progressed();
+    }
+
+    /// Detach the directory bar once its copy finishes.
+    pub fn finish_directory(&self) {
+        self.multi.remove(&self.directory);
+    }
+
+    /// Show the byte bar for streaming a single file of `total` bytes.
+    pub fn start_bytes(&self, label: &str, total: u64) {
+        self.bytes.reset();
+        self.bytes.set_length(total);
+        self.bytes.set_prefix(label.to_string());
+        self.bytes.set_message("");
+        if let Ok(mut rates) = self.rates.lock() {
+            rates.bytes = Throughput::default();
+        }
+        self.multi.add(self.bytes.clone());
+    }
+
+    /// Set the number of bytes streamed so far.
+    ///
+    /// In plain mode a line is printed at most every [`PLAIN_INTERVAL`],
+    /// sharing the interval with file count lines.
+    pub fn set_bytes_done(&self, bytes: u64) {
+        self.bytes.set_position(bytes);
+        let total = self.bytes.length().unwrap_or(0);
+        let prefix = self.bytes.prefix();
+        let report = ProgressReport {
+            path: Some(&prefix),
+            unit: "bytes",
+            done: bytes,
+            total,
+        };
+        let rate = self.update_rate(|rates| &mut rates.bytes, &report, format_byte_rate);
+        self.bytes.set_message(parenthesized(&rate));
+        self.progressed();
+        let Some(plain) = &self.plain else {
+            return;
+        };
+        let Ok(mut last) = plain.lock() else {
+            return;
+        };
+        if last.0.elapsed() < PLAIN_INTERVAL {
+            return;
+        }
+        last.0 = Instant::now();
+        drop(last);
+        eprintln!(
+            "{}",
+            with_rate(
+                format_plain_bytes(&self.bytes.prefix(), bytes, total),
+                &rate
+            )
         );
-        pb
     }
 
-    /// Clear any active progress bars (for clean output after completion).
+    /// Detach the byte bar once its file finishes.
+    pub fn finish_bytes(&self) {
+        self.multi.remove(&self.bytes);
+    }
+
+    /// Run `f` with the progress bars hidden, e.g. while a command may
+    /// prompt on the terminal.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.multi.suspend(f)
+    }
+
+    /// Print a line above the progress bars.
+    pub fn println(&self, line: &str) {
+        self.multi.suspend(|| reporter::get().message(line));
+    }
+
+    /// Print an informational note above the progress bars.
+    pub fn info(&self, message: &str) {
+        self.multi.suspend(|| reporter::get().info(message));
+    }
+
+    /// Print a warning above the progress bars.
+    pub fn warn(&self, file: Option<&Path>, message: &str) {
+        self.multi
+            .suspend(|| reporter::get().warning(file, message));
+    }
+
+    /// Report a completed operation result line.
+    ///
+    /// Shows a checkmark for success, bullet for skipped.
+    pub fn print_result(
+        &self,
+        label: &str,
+        result: &str,
+        is_success: bool,
+        entry_label: Option<&str>,
+    ) {
+        self.multi.suspend(|| {
+            reporter::get().operation(&OperationReport {
+                path: label,
+                result,
+                success: is_success,
+                counts: None,
+                label: entry_label,
+            });
+        });
+    }
+
+    /// Print a result with file counts for directory operations.
+    ///
+    /// Symlinks and `.worktreeignore`-filtered files are listed separately
+    /// so the totals match what actually landed in the target.
+    pub fn print_result_with_count(
+        &self,
+        label: &str,
+        result: &str,
+        file_count: u64,
+        symlink_count: u64,
+        filtered_count: u64,
+        entry_label: Option<&str>,
+    ) {
+        self.multi.suspend(|| {
+            reporter::get().operation(&OperationReport {
+                path: label,
+                result,
+                success: true,
+                counts: Some((file_count, symlink_count, filtered_count)),
+                label: entry_label,
+            });
+        });
+    }
+
+    /// Clear every progress bar (for clean output after completion).
+    ///
+    /// The stall watchdog stops watching too.
     pub fn clear(&self) {
+        self.finish_operation();
+        self.scanning.finish_and_clear();
+        self.overall.finish_and_clear();
+        self.multi.remove(&self.directory);
+        self.multi.remove(&self.bytes);
         self.multi.clear().ok();
     }
 }
+
+/// Create a detached bar with the given template.
+///
+/// Bars are hidden until added to the manager's [`MultiProgress`].
+fn styled_bar(template: &str) -> ProgressBar {
+    let pb = ProgressBar::hidden();
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .expect("Invalid progress bar template")
+            .progress_chars("━━─"),
+    );
+    pb
+}
+
+/// Format a plain progress line, e.g. `copied 1200/5000 files (24%)`.
+#[must_use]
+pub fn format_plain_progress(files: u64, total: u64) -> String {
+    if total == 0 {
+        return format!("copied {files} files");
+    }
+    format!("copied {files}/{total} files ({}%)", files * 100 / total)
+}
+
+/// Format a plain byte progress line, e.g.
+/// `copying test.db: 1.20 GiB/5.00 GiB (24%)`.
+#[must_use]
+pub fn format_plain_bytes(label: &str, bytes: u64, total: u64) -> String {
+    if total == 0 {
+        return format!("copying {label}: {}", HumanBytes(bytes));
+    }
+    format!(
+        "copying {label}: {}/{} ({}%)",
+        HumanBytes(bytes),
+        HumanBytes(total),
+        bytes * 100 / total
+    )
+}
+
+/// Append a rate from [`Throughput::describe`] to a plain line.
+fn with_rate(line: String, rate: &str) -> String {
+    if rate.is_empty() {
+        line
+    } else {
+        format!("{line}, {rate}")
+    }
+}
+
+/// Wrap a bar message in parentheses, leaving an empty one empty.
+fn parenthesized(message: &str) -> String {
+    if message.is_empty() {
+        String::new()
+    } else {
+        format!("({message})")
+    }
+}
+
+/// Format a file rate, e.g. `310 files/s` or `2.5 files/s`.
+#[must_use]
+pub fn format_file_rate(rate: f64) -> String {
+    if rate < 10.0 {
+        format!("{rate:.1} files/s")
+    } else {
+        format!("{rate:.0} files/s")
+    }
+}
+
+/// Format a byte rate, e.g. `120.00 MiB/s`.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn format_byte_rate(rate: f64) -> String {
+    format!("{}/s", HumanBytes(rate as u64))
+}
+
+/// Format a remaining time, e.g. `42s`, `3m 05s`, or `1h 20m`.
+#[must_use]
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Format the hint printed when `label` made no progress for `idle`.
+#[must_use]
+pub fn format_stall_warning(label: &str, idle: Duration) -> String {
+    format!(
+        "No progress on {label} for {}s; a slow or unresponsive network mount is the usual cause \
+         (set operation_timeout in [setup] to give up on stuck operations)",
+        idle.as_secs()
+    )
+}
+
+/// Format the file-count breakdown shown after a directory operation.
+///
+/// `file_count` includes symlinks; they are split out so the reader sees
+/// regular files, preserved links and ignored files separately.
+#[must_use]
+pub fn format_file_counts(file_count: u64, symlink_count: u64, filtered_count: u64) -> String {
+    let mut parts = vec![format!(
+        "{} files",
+        file_count.saturating_sub(symlink_count)
+    )];
+    if symlink_count > 0 {
+        parts.push(format!("{symlink_count} symlinks"));
+    }
+    if filtered_count > 0 {
+        parts.push(format!("{filtered_count} ignored"));
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_manager_tracks_overall_files() {
+        let progress = ProgressManager::new(ProgressMode::Hidden);
+        let _ = progress.start_scanning(3);
+        progress.finish_scanning();
+        progress.start_overall(10);
+
+        progress.start_directory("node_modules", 4);
+        progress.set_directory_done(4);
+        progress.finish_directory();
+        progress.set_files_done(progress.files_done() + 4);
+
+        assert_eq!(progress.files_done(), 4);
+        progress.clear();
+    }
+
+    #[test]
+    fn test_format_plain_progress() {
+        assert_eq!(
+            format_plain_progress(1200, 5000),
+            "copied 1200/5000 files (24%)"
+        );
+        assert_eq!(format_plain_progress(7, 0), "copied 7 files");
+    }
+
+    #[test]
+    fn test_format_plain_bytes() {
+        assert_eq!(
+            format_plain_bytes("test.db", 1_288_490_189, 5 * 1024 * 1024 * 1024),
+            "copying test.db: 1.20 GiB/5.00 GiB (24%)"
+        );
+    }
+
+    #[test]
+    fn test_throughput_smooths_rate_and_estimates_eta() {
+        let start = Instant::now();
+        let mut throughput = Throughput::default();
+        throughput.update(0, start);
+        assert_eq!(throughput.eta(0, 100), None);
+
+        throughput.update(10, start + Duration::from_secs(1));
+        assert_eq!(throughput.rate, Some(10.0));
+        assert_eq!(throughput.eta(10, 100), Some(Duration::from_secs(9)));
+
+        // A burst moves the average only part of the way.
+        throughput.update(110, start + Duration::from_secs(2));
+        let rate = throughput.rate.unwrap();
+        assert!(rate > 10.0 && rate < 100.0, "{rate}");
+
+        // Updates closer than the sample interval are folded in later.
+        throughput.update(120, start + Duration::from_millis(2100));
+        assert_eq!(throughput.rate, Some(rate));
+    }
+
+    #[test]
+    fn test_activity_reports_each_stall_once() {
+        let start = Instant::now();
+        let after = Duration::from_secs(30);
+        let mut activity = Activity {
+            current: None,
+            last_progress: start,
+            warned: false,
+        };
+        assert_eq!(activity.take_stall(start + after, after), None);
+
+        activity.current = Some("node_modules".to_string());
+        assert_eq!(
+            activity.take_stall(start + Duration::from_secs(5), after),
+            None
+        );
+        assert_eq!(
+            activity.take_stall(start + after, after),
+            Some(("node_modules".to_string(), after))
+        );
+        assert_eq!(activity.take_stall(start + after * 2, after), None);
+    }
+
+    #[test]
+    fn test_format_rates_and_eta() {
+        assert_eq!(format_file_rate(310.4), "310 files/s");
+        assert_eq!(format_file_rate(2.5), "2.5 files/s");
+        assert_eq!(format_byte_rate(1_048_576.0), "1.00 MiB/s");
+        assert_eq!(format_eta(Duration::from_secs(42)), "42s");
+        assert_eq!(format_eta(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_eta(Duration::from_mins(80)), "1h 20m");
+    }
+
+    #[test]
+    fn test_format_file_counts_plain() {
+        assert_eq!(format_file_counts(12, 0, 0), "12 files");
+    }
+
+    #[test]
+    fn test_format_file_counts_with_symlinks_and_ignored() {
+        assert_eq!(
+            format_file_counts(12, 2, 5),
+            "10 files, 2 symlinks, 5 ignored"
+        );
+    }
+}
@@ -0,0 +1,714 @@
+//! Output backends.
+//!
+//! Every message, operation result, and prompt notification the CLI emits
+//! goes through the [`Reporter`] selected with `--format`, so the same
+//! command logic can drive a colored terminal UI, stable line-oriented
+//! output for scripts, JSON events for other frontends, or nothing at all.
+//! With `--progress-socket`, the same events are also streamed as JSON,
+//! together with copy progress, to a socket or file for dashboards.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use colored::Colorize;
+use serde_json::{Value, json};
+use worktree_setup_config::{ConfigError, LoadedConfig};
+use worktree_setup_copy::CopyError;
+use worktree_setup_git::GitError;
+use worktree_setup_operations::OperationError;
+
+use crate::args::ReportFormat;
+use crate::progress::format_file_counts;
+
+/// The result of one file operation, as reported to the user.
+#[derive(Debug, Clone, Copy)]
+pub struct OperationReport<'a> {
+    /// Display path of the operation.
+    pub path: &'a str,
+    /// Result (`"copied"`, `"exists"`, a skip reason, ...).
+    pub result: &'a str,
+    /// Whether the operation did something (as opposed to being skipped).
+    pub success: bool,
+    /// File, symlink, and ignored counts for directory operations.
+    pub counts: Option<(u64, u64, u64)>,
+    /// The config entry's `label`, if it has one.
+    pub label: Option<&'a str>,
+}
+
+/// Progress of a running copy.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressReport<'a> {
+    /// Display path of the directory or file being copied, or `None` for
+    /// the overall count across every operation.
+    pub path: Option<&'a str>,
+    /// What is counted: `"files"` or `"bytes"`.
+    pub unit: &'static str,
+    /// How many have been copied so far.
+    pub done: u64,
+    /// How many there are in total.
+    pub total: u64,
+}
+
+/// The stable code and remediation hint of a library error (see
+/// [`ConfigError::code`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// Code such as `WTS-CFG-002`.
+    pub code: &'static str,
+    /// How to fix the error.
+    pub help: &'static str,
+}
+
+impl ErrorCode {
+    /// The code of the first error in `error`'s source chain that has one.
+    pub fn of(error: &(dyn std::error::Error + 'static)) -> Option<Self> {
+        std::iter::successors(Some(error), |e| e.source()).find_map(|e| {
+            e.downcast_ref::<ConfigError>()
+                .map(|e| Self::new(e.code(), e.help()))
+                .or_else(|| {
+                    e.downcast_ref::<GitError>()
+                        .map(|e| Self::new(e.code(), e.help()))
+                })
+                .or_else(|| {
+                    e.downcast_ref::<OperationError>()
+                        .map(|e| Self::new(e.code(), e.help()))
+                })
+                .or_else(|| {
+                    e.downcast_ref::<CopyError>()
+                        .map(|e| Self::new(e.code(), e.help()))
+                })
+        })
+    }
+
+    const fn new(code: &'static str, help: &'static str) -> Self {
+        Self { code, help }
+    }
+}
+
+/// A backend that renders everything the CLI reports.
+pub trait Reporter: Send + Sync {
+    /// A section header (e.g. "Worktree Setup").
+    fn header(&self, title: &str);
+
+    /// Free-form human-readable text. Empty text is a blank line.
+    fn message(&self, text: &str);
+
+    /// An informational notice.
+    fn info(&self, message: &str);
+
+    /// A warning, optionally about a specific file.
+    fn warning(&self, file: Option<&Path>, message: &str);
+
+    /// An error, with its code when it came from a library error.
+    fn error(&self, message: &str, code: Option<ErrorCode>);
+
+    /// Configs discovered in the repository.
+    fn configs(&self, configs: &[&LoadedConfig]);
+
+    /// The result of one file operation.
+    fn operation(&self, report: &OperationReport<'_>);
+
+    /// A post-setup command about to run.
+    fn command(&self, command: &str);
+
+    /// An interactive prompt is about to be shown and needs user input.
+    fn prompt_needed(&self, prompt: &str);
+
+    /// The command finished successfully.
+    fn success(&self);
+
+    /// Progress of a running copy, sent at most a few times per second.
+    fn progress(&self, _report: &ProgressReport<'_>) {}
+
+    /// Whether stdout carries machine-readable output that child process
+    /// output must not be mixed into.
+    fn is_machine_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Format one config as `• path - label`, with its metadata on a dimmed
+/// second line when it has any.
+#[must_use]
+pub fn format_config(config: &LoadedConfig) -> String {
+    let line = format!(
+        "  {} {} - {}",
+        "•".dimmed(),
+        config.relative_path.yellow(),
+        config.config.label()
+    );
+    match config.config.metadata_summary() {
+        Some(metadata) => format!("{line}\n      {}", metadata.dimmed()),
+        None => line,
+    }
+}
+
+/// Colored terminal output (the default).
+#[derive(Debug, Default)]
+pub struct HumanReporter;
+
+impl Reporter for HumanReporter {
+    fn header(&self, title: &str) {
+        println!("\n🌳 {}\n", title.bold());
+    }
+
+    fn message(&self, text: &str) {
+        println!("{text}");
+    }
+
+    fn info(&self, message: &str) {
+        println!("{} {}", "Info:".cyan().bold(), message);
+    }
+
+    fn warning(&self, _file: Option<&Path>, message: &str) {
+        println!("{} {}", "Warning:".yellow().bold(), message);
+    }
+
+    fn error(&self, message: &str, code: Option<ErrorCode>) {
+        match code {
+            Some(code) => {
+                let label = format!("Error[{}]:", code.code);
+                eprintln!("{} {}", label.red().bold(), message);
+                eprintln!("  {} {}", "help:".cyan().bold(), code.help);
+            }
+            None => eprintln!("{} {}", "Error:".red().bold(), message),
+        }
+    }
+
+    fn configs(&self, configs: &[&LoadedConfig]) {
+        println!(
+            "Found {} config{}:",
+            configs.len(),
+            if configs.len() == 1 { "" } else { "s" }
+        );
+        for config in configs {
+            println!("{}", format_config(config));
+        }
+        println!();
+    }
+
+    fn operation(&self, report: &OperationReport<'_>) {
+        let marker = if report.success {
+            "✓".green()
+        } else {
+            "•".dimmed()
+        };
+        let label = report
+            .label
+            .map(|label| format!(" — {}", label.cyan()))
+            .unwrap_or_default();
+        match report.counts {
+            Some((files, symlinks, filtered)) => println!(
+                "{marker} {:<30} {} ({}){label}",
+                report.path,
+                report.result.dimmed(),
+                format_file_counts(files, symlinks, filtered)
+            ),
+            None => println!(
+                "{marker} {:<30} {}{label}",
+                report.path,
+                report.result.dimmed()
+            ),
+        }
+        // Flush to ensure output appears immediately
+        let _ = std::io::stdout().flush();
+    }
+
+    fn command(&self, command: &str) {
+        println!("  {} {}", "$".dimmed(), command);
+    }
+
+    fn prompt_needed(&self, _prompt: &str) {}
+
+    fn success(&self) {
+        println!("✅ Worktree setup complete!");
+    }
+}
+
+/// Stable, tab-separated, uncolored lines for scripts.
+///
+/// Each line starts with a record type: `info`, `warning`, `error`,
+/// `config`, `op`, `command`, `prompt`, or `done`. Free-form messages and
+/// headers are omitted.
+#[derive(Debug, Default)]
+pub struct PorcelainReporter;
+
+impl PorcelainReporter {
+    fn line(fields: &[&str]) {
+        let fields: Vec<String> = fields.iter().map(|f| sanitize(f)).collect();
+        println!("{}", fields.join("\t"));
+    }
+}
+
+impl Reporter for PorcelainReporter {
+    fn header(&self, _title: &str) {}
+
+    fn message(&self, _text: &str) {}
+
+    fn info(&self, message: &str) {
+        Self::line(&["info", message]);
+    }
+
+    fn warning(&self, file: Option<&Path>, message: &str) {
+        let file = file.map(|f| f.to_string_lossy()).unwrap_or_default();
+        Self::line(&["warning", &file, message]);
+    }
+
+    fn error(&self, message: &str, code: Option<ErrorCode>) {
+        Self::line(&["error", message, code.map_or("", |c| c.code)]);
+    }
+
+    fn configs(&self, configs: &[&LoadedConfig]) {
+        for config in configs {
+            Self::line(&["config", &config.relative_path, &config.config.description]);
+        }
+    }
+
+    fn operation(&self, report: &OperationReport<'_>) {
+        let files = report
+            .counts
+            .map_or_else(|| "1".to_string(), |(files, _, _)| files.to_string());
+        Self::line(&["op", report.result, report.path, &files]);
+    }
+
+    fn command(&self, command: &str) {
+        Self::line(&["command", command]);
+    }
+
+    fn prompt_needed(&self, prompt: &str) {
+        Self::line(&["prompt", prompt]);
+    }
+
+    fn success(&self) {
+        Self::line(&["done"]);
+    }
+
+    fn is_machine_readable(&self) -> bool {
+        true
+    }
+}
+
+/// One JSON object per line (`{"event": ..., ...}`) for other frontends.
+///
+/// Events go to stdout, or to a sink such as the `--progress-socket`,
+/// which also gets `progress` events.
+#[derive(Default)]
+pub struct JsonReporter {
+    sink: Option<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl JsonReporter {
+    /// Write events to `sink` instead of stdout.
+    #[must_use]
+    pub fn to_writer(sink: Box<dyn Write + Send>) -> Self {
+        Self {
+            sink: Some(Mutex::new(sink)),
+        }
+    }
+
+    fn emit(&self, event: &str, mut fields: Value) {
+        fields["event"] = Value::from(event);
+        match &self.sink {
+            None => {
+                println!("{fields}");
+                let _ = std::io::stdout().flush();
+            }
+            // A dashboard that went away must not fail the run.
+            Some(sink) => {
+                if let Ok(mut sink) = sink.lock() {
+                    let _ = writeln!(sink, "{fields}").and_then(|()| sink.flush());
+                }
+            }
+        }
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn header(&self, title: &str) {
+        self.emit("header", json!({ "title": title }));
+    }
+
+    fn message(&self, text: &str) {
+        let text = console::strip_ansi_codes(text);
+        if !text.trim().is_empty() {
+            self.emit("message", json!({ "text": text.trim() }));
+        }
+    }
+
+    fn info(&self, message: &str) {
+        self.emit("info", json!({ "message": message.trim_end() }));
+    }
+
+    fn warning(&self, file: Option<&Path>, message: &str) {
+        self.emit(
+            "warning",
+            json!({
+                "message": message.trim_end(),
+                "file": file.map(|f| f.to_string_lossy().to_string()),
+            }),
+        );
+    }
+
+    fn error(&self, message: &str, code: Option<ErrorCode>) {
+        self.emit(
+            "error",
+            json!({
+                "message": message.trim_end(),
+                "code": code.map(|c| c.code),
+                "help": code.map(|c| c.help),
+            }),
+        );
+    }
+
+    fn configs(&self, configs: &[&LoadedConfig]) {
+        let configs: Vec<Value> = configs
+            .iter()
+            .map(|c| {
+                json!({
+                    "path": c.relative_path,
+                    "description": c.config.description,
+                    "name": c.config.name,
+                    "owner": c.config.owner,
+                    "docsUrl": c.config.docs_url,
+                    "estimatedSetupTime": c.config.estimated_setup_time,
+                    "tags": c.config.tags,
+                })
+            })
+            .collect();
+        self.emit("configs", json!({ "configs": configs }));
+    }
+
+    fn operation(&self, report: &OperationReport<'_>) {
+        let mut fields = json!({
+            "path": report.path,
+            "result": report.result,
+            "success": report.success,
+        });
+        if let Some((files, symlinks, filtered)) = report.counts {
+            fields["files"] = Value::from(files);
+            fields["symlinks"] = Value::from(symlinks);
+            fields["ignored"] = Value::from(filtered);
+        }
+        if let Some(label) = report.label {
+            fields["label"] = Value::from(label);
+        }
+        self.emit("operation", fields);
+    }
+
+    fn command(&self, command: &str) {
+        self.emit("command", json!({ "command": command }));
+    }
+
+    fn prompt_needed(&self, prompt: &str) {
+        self.emit("prompt", json!({ "prompt": prompt }));
+    }
+
+    fn success(&self) {
+        self.emit("done", json!({}));
+    }
+
+    fn progress(&self, report: &ProgressReport<'_>) {
+        if self.sink.is_some() {
+            self.emit(
+                "progress",
+                json!({
+                    "path": report.path,
+                    "unit": report.unit,
+                    "done": report.done,
+                    "total": report.total,
+                }),
+            );
+        }
+    }
+
+    fn is_machine_readable(&self) -> bool {
+        self.sink.is_none()
+    }
+}
+
+/// Reports through the reporter selected with `--format` and also streams
+/// every event to the `--progress-socket`.
+struct TeeReporter {
+    primary: Box<dyn Reporter>,
+    events: JsonReporter,
+}
+
+impl Reporter for TeeReporter {
+    fn header(&self, title: &str) {
+        self.primary.header(title);
+        self.events.header(title);
+    }
+
+    fn message(&self, text: &str) {
+        self.primary.message(text);
+        self.events.message(text);
+    }
+
+    fn info(&self, message: &str) {
+        self.primary.info(message);
+        self.events.info(message);
+    }
+
+    fn warning(&self, file: Option<&Path>, message: &str) {
+        self.primary.warning(file, message);
+        self.events.warning(file, message);
+    }
+
+    fn error(&self, message: &str, code: Option<ErrorCode>) {
+        self.primary.error(message, code);
+        self.events.error(message, code);
+    }
+
+    fn configs(&self, configs: &[&LoadedConfig]) {
+        self.primary.configs(configs);
+        self.events.configs(configs);
+    }
+
+    fn operation(&self, report: &OperationReport<'_>) {
+        self.primary.operation(report);
+        self.events.operation(report);
+    }
+
+    fn command(&self, command: &str) {
+        self.primary.command(command);
+        self.events.command(command);
+    }
+
+    fn prompt_needed(&self, prompt: &str) {
+        self.primary.prompt_needed(prompt);
+        self.events.prompt_needed(prompt);
+    }
+
+    fn success(&self) {
+        self.primary.success();
+        self.events.success();
+    }
+
+    fn progress(&self, report: &ProgressReport<'_>) {
+        self.primary.progress(report);
+        self.events.progress(report);
+    }
+
+    fn is_machine_readable(&self) -> bool {
+        self.primary.is_machine_readable()
+    }
+}
+
+/// No output except errors, which still go to stderr.
+#[derive(Debug, Default)]
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn header(&self, _title: &str) {}
+
+    fn message(&self, _text: &str) {}
+
+    fn info(&self, _message: &str) {}
+
+    fn warning(&self, _file: Option<&Path>, _message: &str) {}
+
+    fn error(&self, message: &str, code: Option<ErrorCode>) {
+        match code {
+            Some(code) => eprintln!("Error[{}]: {message}", code.code),
+            None => eprintln!("Error: {message}"),
+        }
+    }
+
+    fn configs(&self, _configs: &[&LoadedConfig]) {}
+
+    fn operation(&self, _report: &OperationReport<'_>) {}
+
+    fn command(&self, _command: &str) {}
+
+    fn prompt_needed(&self, _prompt: &str) {}
+
+    fn success(&self) {}
+}
+
+static REPORTER: OnceLock<Box<dyn Reporter>> = OnceLock::new();
+
+/// Select the reporter for this process, also streaming events to
+/// `progress_socket` if given.
+///
+/// Only the first call has an effect.
+///
+/// # Errors
+///
+/// * If `progress_socket` cannot be opened; the reporter for `format` is
+///   selected regardless
+pub fn init(
+    format: ReportFormat,
+    progress_socket: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reporter: Box<dyn Reporter> = match format {
+        ReportFormat::Human => Box::new(HumanReporter),
+        ReportFormat::Porcelain => Box::new(PorcelainReporter),
+        ReportFormat::Json => Box::new(JsonReporter::default()),
+        ReportFormat::Silent => Box::new(SilentReporter),
+    };
+    let sink = progress_socket.map(|path| {
+        open_event_sink(path)
+            .map_err(|e| format!("Failed to open progress socket {}: {e}", path.display()))
+    });
+    let result = match sink {
+        Some(Ok(sink)) => {
+            reporter = Box::new(TeeReporter {
+                primary: reporter,
+                events: JsonReporter::to_writer(sink),
+            });
+            Ok(())
+        }
+        Some(Err(e)) => Err(e.into()),
+        None => Ok(()),
+    };
+    let _ = REPORTER.set(reporter);
+    result
+}
+
+/// Open the `--progress-socket` target: a Unix socket to connect to, or
+/// any other path (a FIFO, `/dev/fd/3`, a log file) to append to.
+fn open_event_sink(path: &Path) -> std::io::Result<Box<dyn Write + Send>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if std::fs::metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+            return Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?));
+        }
+    }
+    Ok(Box::new(
+        OpenOptions::new().append(true).create(true).open(path)?,
+    ))
+}
+
+/// The active reporter ([`HumanReporter`] if none was selected).
+pub fn get() -> &'static dyn Reporter {
+    REPORTER.get_or_init(|| Box::new(HumanReporter)).as_ref()
+}
+
+/// Report a line of free-form text through the active reporter.
+///
+/// Takes the same arguments as `println!`.
+macro_rules! say {
+    () => {
+        $crate::reporter::get().message("")
+    };
+    ($($arg:tt)*) => {
+        $crate::reporter::get().message(&format!($($arg)*))
+    };
+}
+
+pub(crate) use say;
+
+/// Strip control characters that would break a porcelain record.
+fn sanitize(field: &str) -> String {
+    field
+        .trim_end()
+        .chars()
+        .map(|c| {
+            if c == '\t' || c == '\n' || c == '\r' {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_porcelain_fields() {
+        assert_eq!(sanitize("a\tb\nc\n"), "a b c");
+    }
+
+    #[test]
+    fn test_error_code_of_wrapped_errors() {
+        let config: Box<dyn std::error::Error> = Box::new(ConfigError::NoJsRuntime);
+        assert_eq!(
+            ErrorCode::of(config.as_ref()).map(|c| c.code),
+            Some("WTS-CFG-006")
+        );
+
+        // Wrapped git errors keep their own code.
+        let git = OperationError::GitError(GitError::BareRepository);
+        let code = ErrorCode::of(&git).unwrap();
+        assert_eq!(code.code, "WTS-GIT-003");
+        assert_eq!(code.help, GitError::BareRepository.help());
+
+        let plain: Box<dyn std::error::Error> = "Cancelled".into();
+        assert_eq!(ErrorCode::of(plain.as_ref()), None);
+    }
+
+    #[test]
+    fn test_json_operation_events_carry_entry_label() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.ndjson");
+        let events = JsonReporter::to_writer(open_event_sink(&path).unwrap());
+
+        events.operation(&OperationReport {
+            path: "fixtures",
+            result: "copied",
+            success: true,
+            counts: None,
+            label: Some("seed database (~2 min)"),
+        });
+        events.operation(&OperationReport {
+            path: ".env",
+            result: "exists",
+            success: false,
+            counts: None,
+            label: None,
+        });
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["label"], "seed database (~2 min)");
+        assert!(lines[1].get("label").is_none());
+    }
+
+    #[test]
+    fn test_progress_socket_streams_events() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.ndjson");
+        let events = JsonReporter::to_writer(open_event_sink(&path).unwrap());
+        assert!(!events.is_machine_readable());
+
+        events.info("Copying");
+        events.progress(&ProgressReport {
+            path: Some("node_modules"),
+            unit: "files",
+            done: 3,
+            total: 10,
+        });
+
+        let lines: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "info");
+        assert_eq!(
+            lines[1],
+            json!({
+                "event": "progress",
+                "path": "node_modules",
+                "unit": "files",
+                "done": 3,
+                "total": 10,
+            })
+        );
+    }
+}
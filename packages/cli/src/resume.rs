@@ -0,0 +1,95 @@
+//! `--resume`: finishing an interrupted setup run.
+//!
+//! Setup saves its plan and which operations finished as it goes (see
+//! [`RunState`]). Resuming rebuilds the operations that had not finished
+//! and executes only those, without discovering configs, planning, or
+//! counting files again. Post-setup commands are not part of the saved
+//! plan and are not run.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::Path;
+
+use worktree_setup_config::{LoadedConfig, load_config, load_global_config};
+use worktree_setup_operations::RunState;
+
+use crate::output;
+use crate::reporter::say;
+use crate::{FileOpOptions, execute_and_record, file_progress_manager};
+
+/// Resume the interrupted run on `target_path`.
+///
+/// # Errors
+///
+/// * If there is no interrupted run for `target_path`
+/// * If the saved run state cannot be read
+/// * If an operation fails
+pub fn run(target_path: &Path, options: FileOpOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(state) = RunState::load(&RunState::path_for(target_path)?)? else {
+        return Err(format!(
+            "No interrupted setup run to resume for {}",
+            target_path.display()
+        )
+        .into());
+    };
+    let main_worktree_path = state.main_worktree().to_path_buf();
+
+    output::print_header("Worktree Setup");
+    say!("Resuming setup of: {}", target_path.display());
+    say!("Source (main worktree): {}\n", main_worktree_path.display());
+
+    for path in state.remove_partial()? {
+        output::print_info(&format!(
+            "Removed partial copy {}; copying it again",
+            path.display()
+        ));
+    }
+    let operations = state.pending();
+    output::print_info(&format!(
+        "{} of {} operations already done, {} left",
+        state.completed(),
+        state.total(),
+        operations.len()
+    ));
+
+    let global_config = load_global_config(Some(&main_worktree_path))?;
+    let configs = load_applied_configs(&state, &main_worktree_path);
+    let selected_configs: Vec<&LoadedConfig> = configs.iter().collect();
+    let progress_mgr = file_progress_manager(options.progress, &global_config);
+
+    execute_and_record(
+        &operations,
+        &selected_configs,
+        &progress_mgr,
+        &main_worktree_path,
+        target_path,
+        options,
+        &global_config,
+        Some(state),
+    )?;
+
+    say!();
+    output::print_success();
+    Ok(())
+}
+
+/// Load the configs the interrupted run applied, so the manifest and
+/// worktree state record them. Configs that no longer load are skipped
+/// with a warning.
+fn load_applied_configs(state: &RunState, main_worktree_path: &Path) -> Vec<LoadedConfig> {
+    state
+        .configs()
+        .iter()
+        .filter_map(|relative| {
+            match load_config(&main_worktree_path.join(relative), main_worktree_path) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    output::print_warning(&format!("Failed to load {relative}: {e}"));
+                    None
+                }
+            }
+        })
+        .collect()
+}
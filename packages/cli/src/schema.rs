@@ -0,0 +1,23 @@
+//! Schema subcommand.
+//!
+//! Prints the JSON Schema of the config format, generated from the config
+//! types, for editors to validate and complete config files against, or
+//! the TypeScript declarations generated from it.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use worktree_setup_config::Config;
+
+use crate::args::SchemaArgs;
+
+/// Run the `schema` subcommand.
+pub fn run(args: &SchemaArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.typescript {
+        print!("{}", Config::typescript_declarations());
+    } else {
+        println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+    }
+    Ok(())
+}
@@ -0,0 +1,723 @@
+//! `serve` subcommand: a long-running JSON-RPC 2.0 server.
+//!
+//! Editor integrations talk to the server over stdio (default) or a unix
+//! socket using newline-delimited JSON-RPC messages. Loaded configs are
+//! cached per repository between requests, so plugins can plan and apply
+//! repeatedly without re-spawning the tool or re-discovering configs.
+//!
+//! # Methods
+//!
+//! * `discover` — `{ path?, refresh?, submodules? }` → repository root and configs
+//! * `plan` — `{ target, path?, configs?, overwrite?, submodules? }` → planned operations
+//! * `apply` — same params as `plan`; sends `progress` notifications while
+//!   operations run, then returns result counts
+//! * `listWorktrees` — `{ path? }` → worktrees of the repository
+//! * `shutdown` — stop the server
+//!
+//! `path` is any directory inside the repository (defaults to the server's
+//! working directory). `configs` are patterns matched like `--config`.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use worktree_setup_config::{
+    DiscoveryOptions, LoadedConfig, apply_root_defaults, discover_configs_with_options,
+    load_configs_parallel, load_global_config, record_ts_configs, select_configs_by_patterns,
+};
+use worktree_setup_git::{discover_repo, get_main_worktree, get_repo_root, get_worktrees};
+use worktree_setup_operations::{
+    ApplyConfigOptions, OperationError, OperationOutcome, OperationResult, PlannedOperation,
+    SpaceCheck, execute_operation_outcome, load_worktree_ignore, ts_config_store,
+};
+
+use crate::args::ServeArgs;
+#[cfg(feature = "metrics")]
+use crate::metrics;
+
+/// JSON-RPC error code for malformed JSON.
+const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for a message that is not a valid request.
+const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code for an unknown method.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for bad method parameters.
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code for failures while handling a request.
+const SERVER_ERROR: i64 = -32000;
+
+/// Run the `serve` subcommand.
+pub fn run(args: &ServeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut server = Server::default();
+
+    if let Some(socket) = &args.socket {
+        return serve_socket(&mut server, socket);
+    }
+
+    log::info!("Serving JSON-RPC on stdio");
+    let stdin = io::stdin();
+    server.serve_connection(stdin.lock(), &mut io::stdout().lock())?;
+    Ok(())
+}
+
+/// Accept connections on a unix socket, one at a time, until `shutdown`.
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, socket: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::net::UnixListener;
+
+    if socket.exists() {
+        std::fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    log::info!("Serving JSON-RPC on {}", socket.display());
+
+    let result = (|| -> io::Result<()> {
+        for stream in listener.incoming() {
+            let mut stream = stream?;
+            let reader = BufReader::new(stream.try_clone()?);
+            if server.serve_connection(reader, &mut stream)? {
+                break;
+            }
+        }
+        Ok(())
+    })();
+
+    let _ = std::fs::remove_file(socket);
+    result.map_err(Into::into)
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &mut Server, _socket: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    Err("--socket is only supported on unix platforms".into())
+}
+
+/// An error returned to the client as a JSON-RPC error object.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(e: impl std::fmt::Display) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: format!("Invalid params: {e}"),
+        }
+    }
+
+    fn server(e: impl std::fmt::Display) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Parameters of `discover`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscoverParams {
+    path: Option<PathBuf>,
+    #[serde(default)]
+    refresh: bool,
+    #[serde(default)]
+    submodules: bool,
+}
+
+/// Parameters of `plan` and `apply`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlanParams {
+    target: PathBuf,
+    path: Option<PathBuf>,
+    #[serde(default)]
+    configs: Vec<String>,
+    #[serde(default)]
+    overwrite: bool,
+    #[serde(default)]
+    submodules: bool,
+}
+
+/// Parameters of `listWorktrees`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PathParams {
+    path: Option<PathBuf>,
+}
+
+/// Server state shared across requests and connections.
+#[derive(Debug, Default)]
+struct Server {
+    /// Loaded configs keyed by repository root and submodule inclusion.
+    configs: BTreeMap<(PathBuf, bool), Vec<LoadedConfig>>,
+}
+
+impl Server {
+    /// Handle requests from one connection until EOF or `shutdown`.
+    ///
+    /// Returns `true` if the client asked the server to shut down.
+    fn serve_connection(
+        &mut self,
+        reader: impl BufRead,
+        writer: &mut impl Write,
+    ) -> io::Result<bool> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut notify = |message: Value| {
+                if let Err(e) = write_message(writer, &message) {
+                    log::warn!("Failed to send notification: {e}");
+                }
+            };
+            let (response, shutdown) = self.handle_line(&line, &mut notify);
+
+            if let Some(response) = response {
+                write_message(writer, &response)?;
+            }
+            if shutdown {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Handle one message, returning the response (if any) and whether to
+    /// shut down. Requests without an `id` are notifications and get no
+    /// response.
+    fn handle_line(&mut self, line: &str, notify: &mut dyn FnMut(Value)) -> (Option<Value>, bool) {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return (
+                    Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+                    false,
+                );
+            }
+        };
+
+        let id = request.get("id").cloned();
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            let response = error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Request has no method",
+            );
+            return (Some(response), false);
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        log::debug!("Handling {method}");
+        let shutdown = method == "shutdown";
+        let result = self.dispatch(method, params, notify);
+
+        let Some(id) = id else {
+            if let Err(e) = result {
+                log::warn!("Notification {method} failed: {}", e.message);
+            }
+            return (None, shutdown);
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_response(id, e.code, &e.message),
+        };
+        (Some(response), shutdown)
+    }
+
+    fn dispatch(
+        &mut self,
+        method: &str,
+        params: Value,
+        notify: &mut dyn FnMut(Value),
+    ) -> Result<Value, RpcError> {
+        match method {
+            "discover" => self.discover(&parse_params(params)?),
+            "plan" => self.plan(&parse_params(params)?),
+            "apply" => {
+                let params = parse_params(params)?;
+                #[cfg(feature = "metrics")]
+                let started = Instant::now();
+                let result = self.apply(&params, notify);
+                #[cfg(feature = "metrics")]
+                metrics::record_setup(result.is_ok(), started.elapsed());
+                result
+            }
+            "listWorktrees" => list_worktrees(&parse_params(params)?),
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Method not found: {method}"),
+            }),
+        }
+    }
+
+    fn discover(&mut self, params: &DiscoverParams) -> Result<Value, RpcError> {
+        let repo_root = repo_root(params.path.as_deref())?;
+        let configs = self.configs_for(&repo_root, params.submodules, params.refresh)?;
+
+        Ok(json!({
+            "repoRoot": path_string(&repo_root),
+            "configs": configs.iter().map(config_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn plan(&mut self, params: &PlanParams) -> Result<Value, RpcError> {
+        let (_, _, operations) = self.plan_request(params)?;
+
+        Ok(json!({
+            "operations": operations.iter().map(operation_json).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn apply(
+        &mut self,
+        params: &PlanParams,
+        notify: &mut dyn FnMut(Value),
+    ) -> Result<Value, RpcError> {
+        let (main_worktree, selected, operations) = self.plan_request(params)?;
+        let total = operations.len();
+
+        let space = SpaceCheck::new(&operations, &params.target);
+        if space.refuses(|| worktree_setup_copy::supports_reflink(&main_worktree, &params.target)) {
+            return Err(RpcError::server(crate::insufficient_space_message(
+                &space,
+                &params.target,
+            )));
+        }
+
+        let (manifest_path, mut manifest) = crate::load_setup_manifest(&params.target);
+        for config in &selected {
+            manifest.record_config(config);
+        }
+
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (index, op) in operations.iter().enumerate() {
+            let result = if op.will_skip {
+                "skipped".to_string()
+            } else {
+                let outcome = execute_with_notifications(op, notify).map_err(RpcError::server)?;
+
+                if matches!(
+                    outcome.result,
+                    OperationResult::Created | OperationResult::Overwritten
+                ) {
+                    #[cfg(feature = "metrics")]
+                    metrics::add_copied(op);
+                    if let Err(e) = manifest.record_operation(
+                        op,
+                        outcome.method,
+                        &main_worktree,
+                        &params.target,
+                    ) {
+                        log::warn!("Failed to record {} in manifest: {e}", op.display_path);
+                    }
+                }
+                outcome.result.to_string()
+            };
+
+            notify(notification(
+                "progress",
+                json!({
+                    "index": index + 1,
+                    "total": total,
+                    "displayPath": op.display_path,
+                    "operation": op.operation_type.to_string(),
+                    "result": result,
+                    "skipReason": op.skip_reason,
+                }),
+            ));
+            *counts.entry(result).or_default() += 1;
+        }
+
+        if let Some(path) = manifest_path {
+            manifest.touch();
+            if let Err(e) = manifest.save(&path) {
+                log::warn!("Failed to write setup manifest: {e}");
+            }
+        }
+        if let Err(e) = crate::record_worktree_state(&selected, &params.target) {
+            log::warn!("Failed to record worktree state: {e}");
+        }
+
+        Ok(json!({ "total": total, "results": counts }))
+    }
+
+    /// Plan the operations requested by `plan` or `apply`.
+    ///
+    /// Returns the main worktree path, the selected configs, and the plan.
+    fn plan_request(
+        &mut self,
+        params: &PlanParams,
+    ) -> Result<(PathBuf, Vec<&LoadedConfig>, Vec<PlannedOperation>), RpcError> {
+        let repo =
+            discover_repo(&request_dir(params.path.as_deref())?).map_err(RpcError::server)?;
+        let repo_root = get_repo_root(&repo).map_err(RpcError::server)?;
+        let main_worktree = get_main_worktree(&repo).map_err(RpcError::server)?.path;
+        let global_config = load_global_config(Some(&repo_root)).map_err(RpcError::server)?;
+
+        if !params.target.is_dir() {
+            return Err(RpcError::invalid_params(format!(
+                "target does not exist: {}",
+                params.target.display()
+            )));
+        }
+
+        let configs = self.configs_for(&repo_root, params.submodules, false)?;
+        let selected: Vec<&LoadedConfig> = if params.configs.is_empty() {
+            configs.iter().collect()
+        } else {
+            select_configs_by_patterns(configs, &params.configs)
+                .map_err(|e| RpcError::invalid_params(e.to_string()))?
+                .into_iter()
+                .map(|i| &configs[i])
+                .collect()
+        };
+
+        let worktree_ignore = Arc::new(load_worktree_ignore(&main_worktree, &params.target));
+        let base_options = ApplyConfigOptions {
+            overwrite_existing: params.overwrite,
+            worktree_ignore: Some(Arc::clone(&worktree_ignore)),
+            ..Default::default()
+        };
+        let mut operations = crate::plan_selected_operations(
+            &selected,
+            &main_worktree,
+            &params.target,
+            &base_options,
+            &global_config,
+            &|_, _, _| {},
+        )
+        .map_err(RpcError::server)?;
+
+        if let Some(filter) = crate::unstaged_filter(&selected, None, &global_config.setup) {
+            operations.extend(
+                crate::plan_unstaged_files(
+                    &main_worktree,
+                    &params.target,
+                    &worktree_ignore,
+                    &filter,
+                )
+                .map_err(RpcError::server)?,
+            );
+        }
+        // Skip reasons travel with each operation; a warning on stdout
+        // would corrupt the JSON-RPC stream.
+        crate::mark_protected_branch_ops(&mut operations, &params.target, &global_config.setup);
+
+        Ok((main_worktree, selected, operations))
+    }
+
+    /// Cached configs for a repository, loading them on first use.
+    fn configs_for(
+        &mut self,
+        repo_root: &Path,
+        submodules: bool,
+        refresh: bool,
+    ) -> Result<&[LoadedConfig], RpcError> {
+        let key = (repo_root.to_path_buf(), submodules);
+        if refresh {
+            self.configs.remove(&key);
+        }
+
+        let configs = match self.configs.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(load_configs(repo_root, submodules)?),
+        };
+        Ok(configs)
+    }
+}
+
+/// How many files a directory operation copies between `fileProgress`
+/// notifications.
+const FILE_PROGRESS_INTERVAL: u64 = 100;
+
+/// Execute `op`, sending `fileProgress` notifications for directory copies.
+///
+/// The copy runs on a scoped thread so progress reported from the copy's
+/// worker threads can be forwarded to the client as it happens.
+fn execute_with_notifications(
+    op: &PlannedOperation,
+    notify: &mut dyn FnMut(Value),
+) -> Result<OperationOutcome, OperationError> {
+    if !op.is_directory {
+        return execute_operation_outcome(op, |_, _| {});
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|scope| {
+        let handle = scope.spawn(move || {
+            execute_operation_outcome(op, move |completed, total| {
+                if completed == total || completed % FILE_PROGRESS_INTERVAL == 0 {
+                    let _ = tx.send((completed, total));
+                }
+            })
+        });
+
+        // The channel closes once the copy finishes and drops its callback.
+        for (completed, total) in rx {
+            notify(notification(
+                "fileProgress",
+                json!({ "displayPath": op.display_path, "completed": completed, "total": total }),
+            ));
+        }
+
+        handle.join().expect("operation thread panicked")
+    })
+}
+
+/// Discover and load every config in a repository.
+///
+/// Configs that fail to load are logged and left out.
+fn load_configs(repo_root: &Path, submodules: bool) -> Result<Vec<LoadedConfig>, RpcError> {
+    let paths = discover_configs_with_options(
+        repo_root,
+        DiscoveryOptions {
+            include_submodules: submodules,
+        },
+    )
+    .map_err(RpcError::server)?;
+
+    if let Some(store) = ts_config_store() {
+        for path in record_ts_configs(&paths, &store) {
+            log::warn!(
+                "Config {} is new or changed since it last ran here",
+                path.display()
+            );
+        }
+    }
+
+    let mut configs: Vec<LoadedConfig> = paths
+        .iter()
+        .zip(load_configs_parallel(&paths, repo_root))
+        .filter_map(|(path, loaded)| match loaded {
+            Ok(config) => Some(config),
+            Err(e) => {
+                log::warn!("Failed to load {}: {e}", path.display());
+                None
+            }
+        })
+        .collect();
+    for path in apply_root_defaults(&mut configs) {
+        log::warn!("Ignoring `defaults` in {path}: only the root config's defaults apply");
+    }
+    Ok(configs)
+}
+
+fn list_worktrees(params: &PathParams) -> Result<Value, RpcError> {
+    let repo = discover_repo(&request_dir(params.path.as_deref())?).map_err(RpcError::server)?;
+    let worktrees = get_worktrees(&repo).map_err(RpcError::server)?;
+
+    Ok(json!({
+        "worktrees": worktrees
+            .iter()
+            .map(|wt| json!({
+                "path": path_string(&wt.path),
+                "isMain": wt.is_main,
+                "branch": wt.branch,
+                "commit": wt.commit,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// The directory a request refers to, defaulting to the directory the
+/// server started from.
+fn request_dir(path: Option<&Path>) -> Result<PathBuf, RpcError> {
+    path.map_or_else(
+        || {
+            std::env::current_dir()
+                .map(|cwd| crate::start_dir(&cwd))
+                .map_err(RpcError::server)
+        },
+        |p| Ok(p.to_path_buf()),
+    )
+}
+
+fn repo_root(path: Option<&Path>) -> Result<PathBuf, RpcError> {
+    let repo = discover_repo(&request_dir(path)?).map_err(RpcError::server)?;
+    get_repo_root(&repo).map_err(RpcError::server)
+}
+
+/// Deserialize method params, treating missing params as an empty object.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+fn config_json(config: &LoadedConfig) -> Value {
+    json!({
+        "path": config.relative_path,
+        "configPath": path_string(&config.config_path),
+        "description": config.config.description,
+        "name": config.config.name,
+        "owner": config.config.owner,
+        "docsUrl": config.config.docs_url,
+        "estimatedSetupTime": config.config.estimated_setup_time,
+        "tags": config.config.tags,
+    })
+}
+
+fn operation_json(op: &PlannedOperation) -> Value {
+    json!({
+        "displayPath": op.display_path,
+        "operation": op.operation_type.to_string(),
+        "source": path_string(&op.source),
+        "target": path_string(&op.target),
+        "isDirectory": op.is_directory,
+        "fileCount": op.file_count,
+        "byteCount": op.byte_count,
+        "willSkip": op.will_skip,
+        "skipReason": op.skip_reason,
+        "origin": op.origin,
+    })
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    let mut message = json!({ "jsonrpc": "2.0", "method": method });
+    message["params"] = params;
+    message
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    let mut response = json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+    });
+    response["id"] = id;
+    response
+}
+
+/// Write one message followed by a newline and flush.
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        fs::write(
+            dir.path().join("worktree.config.toml"),
+            "description = \"root\"\ncopy = [\".env\"]\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(".env"), "A=1").unwrap();
+
+        let sig = git2::Signature::now("Test", "test@test.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .unwrap();
+        dir
+    }
+
+    /// Run `requests` through a server and return every message it wrote.
+    fn exchange(server: &mut Server, requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| r.to_string() + "\n").collect();
+        let mut output = Vec::new();
+        server
+            .serve_connection(input.as_bytes(), &mut output)
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let mut server = Server::default();
+        let mut output = Vec::new();
+        server
+            .serve_connection(&b"not json\n"[..], &mut output)
+            .unwrap();
+        let parse_error: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(parse_error["error"]["code"], PARSE_ERROR);
+
+        let responses = exchange(
+            &mut server,
+            &[
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" }),
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "plan", "params": {} }),
+                json!({ "jsonrpc": "2.0", "method": "discover", "params": { "path": "/nonexistent" } }),
+            ],
+        );
+        assert_eq!(responses.len(), 2, "notifications get no response");
+        assert_eq!(responses[0]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[1]["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_discover_plan_apply() {
+        let repo = create_repo();
+        let target = TempDir::new().unwrap();
+        let path = path_string(repo.path());
+        let target_path = path_string(target.path());
+
+        let mut server = Server::default();
+        let messages = exchange(
+            &mut server,
+            &[
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "discover", "params": { "path": path } }),
+                json!({ "jsonrpc": "2.0", "id": 2, "method": "plan",
+                        "params": { "path": path, "target": target_path } }),
+                json!({ "jsonrpc": "2.0", "id": 3, "method": "apply",
+                        "params": { "path": path, "target": target_path } }),
+                json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }),
+                json!({ "jsonrpc": "2.0", "id": 5, "method": "discover" }),
+            ],
+        );
+
+        assert_eq!(messages[0]["id"], 1);
+        assert_eq!(
+            messages[0]["result"]["configs"][0]["path"],
+            "worktree.config.toml"
+        );
+        assert_eq!(messages[0]["result"]["configs"][0]["description"], "root");
+
+        assert_eq!(messages[1]["id"], 2);
+        let operations = &messages[1]["result"]["operations"];
+        assert_eq!(operations[0]["displayPath"], ".env");
+        assert_eq!(operations[0]["willSkip"], false);
+
+        assert_eq!(messages[2]["method"], "progress");
+        assert_eq!(messages[2]["params"]["result"], "created");
+        assert_eq!(messages[3]["id"], 3);
+        assert_eq!(messages[3]["result"]["results"]["created"], 1);
+        assert_eq!(
+            fs::read_to_string(target.path().join(".env")).unwrap(),
+            "A=1"
+        );
+
+        assert_eq!(messages[4]["id"], 4);
+        assert_eq!(messages.len(), 5, "requests after shutdown are not handled");
+        assert_eq!(server.configs.len(), 1, "configs are cached");
+    }
+}
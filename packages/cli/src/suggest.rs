@@ -0,0 +1,416 @@
+//! Suggest subcommand.
+//!
+//! Scans the main worktree for untracked or ignored paths that new
+//! worktrees commonly need — environment files, dependency directories,
+//! caches, local override files — and suggests `copy` or `symlinks`
+//! entries for the ones no config covers yet.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use colored::Colorize;
+use worktree_setup_config::{LoadedConfig, PathSpec, load_toml_config};
+use worktree_setup_git::{
+    UntrackedPath, discover_repo, get_main_worktree, get_untracked_and_ignored_paths,
+};
+
+use crate::args::SuggestArgs;
+use crate::capture::toml_string;
+use crate::reporter::say;
+use crate::{interactive, output};
+
+/// Name of the root config file suggestions are appended to.
+const CONFIG_FILE: &str = "worktree.config.toml";
+
+/// Directories that hold installed dependencies, shared via symlink.
+const DEPENDENCY_DIRS: &[&str] = &["node_modules", ".venv", "venv", "vendor"];
+
+/// Directories that hold tool caches, copied when large enough to matter.
+const CACHE_DIRS: &[&str] = &[
+    ".cache",
+    ".turbo",
+    ".parcel-cache",
+    ".gradle",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".ruff_cache",
+    "target",
+];
+
+/// Caches smaller than this are cheap to rebuild and are not suggested.
+const LARGE_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// A suggested config entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Suggestion {
+    /// Config key (`copy` or `symlinks`).
+    key: &'static str,
+    /// Path relative to the repository root.
+    path: String,
+    /// Why the entry is suggested.
+    reason: String,
+}
+
+/// Run the `suggest` subcommand.
+pub fn run(args: &SuggestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let start = args
+        .path
+        .as_ref()
+        .map_or_else(|| crate::start_dir(&cwd), |p| cwd.join(p));
+    let repo = discover_repo(&start)?;
+    let main_worktree = get_main_worktree(&repo)?.path;
+    let main_repo = discover_repo(&main_worktree)?;
+
+    output::print_header("Config Suggestions");
+
+    let configs = crate::discover_and_load_configs(&main_worktree, false)?;
+    let covered = covered_paths(&configs, &main_worktree);
+    let paths = get_untracked_and_ignored_paths(&main_repo)?;
+    let suggestions: Vec<Suggestion> = paths
+        .iter()
+        .filter_map(|p| classify(&main_worktree, p))
+        .filter(|s| !covered.contains(&s.path))
+        .collect();
+
+    if suggestions.is_empty() {
+        output::print_info("No suggestions; existing configs cover the usual candidates.");
+        return Ok(());
+    }
+
+    for s in &suggestions {
+        say!(
+            "  {} {:<30} {}",
+            format!("{:<8}", s.key).cyan(),
+            s.path,
+            s.reason.dimmed()
+        );
+    }
+    say!();
+
+    if !args.apply {
+        output::print_info("Run with --apply to add suggestions to worktree.config.toml.");
+        return Ok(());
+    }
+
+    let accepted: Vec<&Suggestion> = if args.yes {
+        suggestions.iter().collect()
+    } else {
+        let items: Vec<String> = suggestions
+            .iter()
+            .map(|s| format!("{} {} - {}", s.key, s.path, s.reason))
+            .collect();
+        interactive::select_suggestions(&items)?
+            .into_iter()
+            .map(|i| &suggestions[i])
+            .collect()
+    };
+    if accepted.is_empty() {
+        say!("No suggestions selected.");
+        return Ok(());
+    }
+
+    let path = main_worktree.join(CONFIG_FILE);
+    append_suggestions(&path, &accepted)?;
+    output::print_info(&format!(
+        "Added {} entries to {}",
+        accepted.len(),
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Suggest an entry for `untracked`, or `None` if it is not a candidate.
+fn classify(main_worktree: &Path, untracked: &UntrackedPath) -> Option<Suggestion> {
+    let name = untracked.path.rsplit('/').next().unwrap_or(&untracked.path);
+    let suggestion = |key, reason: &str| {
+        Some(Suggestion {
+            key,
+            path: untracked.path.clone(),
+            reason: reason.to_string(),
+        })
+    };
+
+    if untracked.is_dir {
+        if !untracked.ignored {
+            return None;
+        }
+        if DEPENDENCY_DIRS.contains(&name) {
+            return suggestion(
+                "symlinks",
+                "installed dependencies; share instead of reinstalling",
+            );
+        }
+        if name == ".direnv" {
+            return suggestion("copy", "direnv cache; avoids re-running environment setup");
+        }
+        if CACHE_DIRS.contains(&name) {
+            let size = worktree_setup_copy::disk_usage(&main_worktree.join(&untracked.path));
+            if size >= LARGE_CACHE_BYTES {
+                return suggestion(
+                    "copy",
+                    &format!(
+                        "{} cache; copying avoids a cold start",
+                        output::format_size(size)
+                    ),
+                );
+            }
+        }
+        return None;
+    }
+
+    if untracked.ignored && name.starts_with(".env") && !is_example(name) {
+        return suggestion("copy", "environment file ignored by git");
+    }
+    if is_local_override(name) {
+        return suggestion("copy", "local override file not tracked by git");
+    }
+    None
+}
+
+/// Whether `name` is a committed-style sample (`.env.example`).
+fn is_example(name: &str) -> bool {
+    [".example", ".sample", ".template", ".dist"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+/// Whether `name` looks like a local override (`settings.local.json`,
+/// `config.local`, `docker-compose.override.yml`).
+fn is_local_override(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("local"))
+        || name.contains(".local.")
+        || name.contains(".override.")
+}
+
+/// Root-relative paths that configs already symlink or copy.
+fn covered_paths(configs: &[LoadedConfig], repo_root: &Path) -> BTreeSet<String> {
+    let mut covered = BTreeSet::new();
+
+    for loaded in configs {
+        let config_dir = loaded
+            .config_dir
+            .strip_prefix(repo_root)
+            .unwrap_or(&loaded.config_dir);
+        let config = &loaded.config;
+        let entries = config
+            .symlinks
+            .iter()
+            .chain(&config.copy)
+            .chain(&config.overwrite);
+        for entry in entries {
+            let path = PathSpec::parse(entry.path()).worktree_relative(Path::new(""), config_dir);
+            covered.insert(path.to_string_lossy().to_string());
+        }
+    }
+
+    covered
+}
+
+/// Append `suggestions` to the TOML config at `path`, creating it if
+/// missing.
+///
+/// # Errors
+///
+/// * If the file cannot be read or written
+/// * If the edited file is not a valid config (the file is left unchanged)
+fn append_suggestions(
+    path: &Path,
+    suggestions: &[&Suggestion],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let original = if path.exists() {
+        Some(std::fs::read_to_string(path)?)
+    } else {
+        None
+    };
+
+    let mut contents = original
+        .clone()
+        .unwrap_or_else(|| "description = \"Shared worktree setup\"\n".to_string());
+    for key in ["symlinks", "copy"] {
+        let entries: Vec<&str> = suggestions
+            .iter()
+            .filter(|s| s.key == key)
+            .map(|s| s.path.as_str())
+            .collect();
+        if !entries.is_empty() {
+            contents = append_to_array(&contents, key, &entries);
+        }
+    }
+
+    std::fs::write(path, &contents)?;
+    if let Err(e) = load_toml_config(path) {
+        match original {
+            Some(original) => std::fs::write(path, original)?,
+            None => std::fs::remove_file(path)?,
+        }
+        return Err(format!("Could not update {}: {e}", path.display()).into());
+    }
+    Ok(())
+}
+
+/// Add `entries` to the top-level array `key` in TOML `contents`.
+///
+/// Appends to the existing array if there is one; otherwise inserts a new
+/// array before the first table header so it stays a top-level key.
+fn append_to_array(contents: &str, key: &str, entries: &[&str]) -> String {
+    let items = entries.iter().fold(String::new(), |mut out, e| {
+        let _ = writeln!(out, "    {},", toml_string(e));
+        out
+    });
+
+    if let Some((open, close)) = find_array(contents, key) {
+        let before = contents[..close].trim_end();
+        let after = &contents[close..];
+        let empty = before.ends_with('[');
+        let separator = if empty || before.ends_with(',') {
+            ""
+        } else {
+            ","
+        };
+        if !contents[open..close].contains('\n') {
+            let inline: Vec<String> = entries.iter().map(|e| toml_string(e)).collect();
+            let space = if empty { "" } else { " " };
+            return format!("{before}{separator}{space}{}{after}", inline.join(", "));
+        }
+        return format!("{before}{separator}\n{items}{after}");
+    }
+
+    let insert_at = contents
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .find(|&i| contents[i..].trim_start().starts_with('['))
+        .unwrap_or(contents.len());
+    let (head, tail) = contents.split_at(insert_at);
+    let head = if head.is_empty() || head.ends_with('\n') {
+        head.to_string()
+    } else {
+        format!("{head}\n")
+    };
+    format!("{head}\n{key} = [\n{items}]\n{tail}")
+}
+
+/// Byte offsets of the `[` and `]` delimiting the top-level array `key`,
+/// if present.
+fn find_array(contents: &str, key: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    let mut start = None;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            // Table header: later keys are not top-level.
+            break;
+        }
+        if let Some(rest) = trimmed.strip_prefix(key)
+            && let Some(rest) = rest.trim_start().strip_prefix('=')
+            && rest.trim_start().starts_with('[')
+        {
+            start = Some(offset + line.find('[')?);
+            break;
+        }
+        offset += line.len();
+    }
+
+    let start = start?;
+    let mut depth = 0usize;
+    let mut in_string = None;
+    let mut escaped = false;
+    for (i, c) in contents[start..].char_indices() {
+        match in_string {
+            Some(quote) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && quote == '"' {
+                    escaped = true;
+                } else if c == quote {
+                    in_string = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => in_string = Some(c),
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((start, start + i));
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn untracked(path: &str, ignored: bool, is_dir: bool) -> UntrackedPath {
+        UntrackedPath {
+            path: path.to_string(),
+            ignored,
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn test_classify_candidates() {
+        let dir = TempDir::new().unwrap();
+        let keys = |u: &UntrackedPath| classify(dir.path(), u).map(|s| s.key);
+
+        assert_eq!(keys(&untracked(".env", true, false)), Some("copy"));
+        assert_eq!(
+            keys(&untracked("apps/web/.env.local", true, false)),
+            Some("copy")
+        );
+        assert_eq!(keys(&untracked(".env.example", true, false)), None);
+        assert_eq!(
+            keys(&untracked("node_modules", true, true)),
+            Some("symlinks")
+        );
+        assert_eq!(
+            keys(&untracked("settings.local.json", false, false)),
+            Some("copy")
+        );
+        assert_eq!(keys(&untracked("notes.txt", false, false)), None);
+        // Small caches are cheap to rebuild.
+        assert_eq!(keys(&untracked(".turbo", true, true)), None);
+    }
+
+    #[test]
+    fn test_append_to_existing_array() {
+        let contents = "description = \"x\"\ncopy = [\n    \".env\"\n]\n\n[profiles.dev]\n";
+        let updated = append_to_array(contents, "copy", &[".env.local"]);
+        let config: worktree_setup_config::Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.copy, vec![".env", ".env.local"]);
+        assert!(config.profiles.contains_key("dev"));
+    }
+
+    #[test]
+    fn test_append_new_array_before_tables() {
+        let contents = "description = \"x\"\n\n[profiles.dev]\ndescription = \"Dev\"\n";
+        let updated = append_to_array(contents, "symlinks", &["node_modules"]);
+        let config: worktree_setup_config::Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+        assert_eq!(config.profiles["dev"].description, "Dev");
+    }
+
+    #[test]
+    fn test_append_skips_brackets_in_strings() {
+        let contents = "copy = [\"a]b\", 'c[d'] # done\n";
+        let updated = append_to_array(contents, "copy", &["e"]);
+        assert_eq!(updated, "copy = [\"a]b\", 'c[d', \"e\"] # done\n");
+        let config: worktree_setup_config::Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.copy, vec!["a]b", "c[d", "e"]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Timing breakdown of a setup run.
+//!
+//! Records how long each phase (discovery, loading, planning, execution,
+//! post-setup) and each file operation took. Durations are logged at debug
+//! level as they are recorded, so `--verbose` shows them in order, and
+//! `--stats` prints a summary once the run finishes.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+use crate::reporter::say;
+
+/// Operations listed by `--stats`, slowest first.
+const SLOWEST_SHOWN: usize = 5;
+
+/// A phase of a setup run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Finding config files.
+    Discovery,
+    /// Loading and evaluating config files.
+    Loading,
+    /// Turning configs into planned operations.
+    Planning,
+    /// Running the planned file operations.
+    Execution,
+    /// Running post-setup commands.
+    PostSetup,
+}
+
+impl Phase {
+    /// Every phase, in the order a run goes through them.
+    const ALL: [Self; 5] = [
+        Self::Discovery,
+        Self::Loading,
+        Self::Planning,
+        Self::Execution,
+        Self::PostSetup,
+    ];
+
+    /// Name shown in logs and `--stats`.
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Discovery => "discovery",
+            Self::Loading => "loading",
+            Self::Planning => "planning",
+            Self::Execution => "execution",
+            Self::PostSetup => "post-setup",
+        }
+    }
+}
+
+/// Durations recorded so far.
+struct Timings {
+    /// Total time per phase, indexed like [`Phase::ALL`]; `None` for
+    /// phases that did not run.
+    phases: [Option<Duration>; 5],
+    /// Time per file operation, by display path.
+    operations: Vec<(String, Duration)>,
+}
+
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings {
+    phases: [None; 5],
+    operations: Vec::new(),
+});
+
+/// Run `f` as (part of) `phase` and record how long it took.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    let elapsed = started.elapsed();
+    log::debug!("{} took {}", phase.name(), format_duration(elapsed));
+
+    if let Some(index) = Phase::ALL.iter().position(|p| *p == phase) {
+        *lock().phases[index].get_or_insert(Duration::ZERO) += elapsed;
+    }
+    result
+}
+
+/// Record how long the file operation for `display_path` took.
+pub fn record_operation(display_path: &str, elapsed: Duration) {
+    log::debug!("{display_path} took {}", format_duration(elapsed));
+    lock().operations.push((display_path.to_string(), elapsed));
+}
+
+/// Print the recorded phases and the slowest operations (`--stats`).
+pub fn print_stats() {
+    let (phases, operations) = {
+        let timings = lock();
+        (timings.phases, timings.operations.clone())
+    };
+    say!("{}", "Timing".bold());
+    for (phase, elapsed) in Phase::ALL.iter().zip(phases) {
+        if let Some(elapsed) = elapsed {
+            say!("  {:<12}{:>9}", phase.name(), format_duration(elapsed));
+        }
+    }
+
+    let slowest = slowest(&operations, SLOWEST_SHOWN);
+    if !slowest.is_empty() {
+        say!("\n{}", "Slowest operations".bold());
+        for (path, elapsed) in slowest {
+            say!("  {:>9}  {path}", format_duration(*elapsed));
+        }
+    }
+    say!();
+}
+
+/// The `count` slowest operations, slowest first.
+fn slowest(operations: &[(String, Duration)], count: usize) -> Vec<&(String, Duration)> {
+    let mut sorted: Vec<&(String, Duration)> = operations.iter().collect();
+    sorted.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+    sorted.truncate(count);
+    sorted
+}
+
+/// Format a duration as milliseconds below a second and seconds above.
+fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+/// Lock the recorded timings, even if a panicking thread poisoned them.
+fn lock() -> std::sync::MutexGuard<'static, Timings> {
+    TIMINGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_micros(42_250)), "42.2ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.50s");
+    }
+
+    #[test]
+    fn test_slowest() {
+        let operations = vec![
+            ("a".to_string(), Duration::from_millis(5)),
+            ("b".to_string(), Duration::from_millis(50)),
+            ("c".to_string(), Duration::from_millis(20)),
+        ];
+        let names: Vec<&str> = slowest(&operations, 2)
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect();
+        assert_eq!(names, ["b", "c"]);
+    }
+}
@@ -0,0 +1,217 @@
+//! Multi-repo workspace subcommand.
+//!
+//! Creates a matching worktree (and branch) in every repository of a
+//! workspace defined in `workspaces.toml`, then applies each repository's
+//! configs to its new worktree.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::Path;
+
+use colored::Colorize;
+use worktree_setup_config::{
+    LoadedConfig, Workspace, WorkspaceRepo, WorkspacesFile, check_worktree_name, expand_home,
+    load_global_config, load_workspaces, workspaces_path,
+};
+use worktree_setup_git::{
+    WorktreeCreateOptions, create_worktree, discover_repo, get_local_branches, get_main_worktree,
+    get_repo_root,
+};
+
+use crate::args::{ProgressMode, WorkspaceArgs, WorkspaceCommand, WorkspaceCreateArgs};
+use crate::output;
+use crate::reporter::say;
+
+/// Run the `workspace` subcommand.
+pub fn run(args: &WorkspaceArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let path = workspaces_path().ok_or("Could not determine the config directory")?;
+    let file = load_workspaces(&path)?;
+
+    match &args.command {
+        WorkspaceCommand::List => {
+            print_workspaces(&file, &path);
+            Ok(())
+        }
+        WorkspaceCommand::Create(create_args) => {
+            let (name, workspace) = select_workspace(&file, create_args.workspace.as_deref())?;
+            run_create(name, workspace, create_args)
+        }
+    }
+}
+
+/// Pick the workspace named `requested`, or the only one defined.
+fn select_workspace<'a>(
+    file: &'a WorkspacesFile,
+    requested: Option<&str>,
+) -> Result<(&'a str, &'a Workspace), Box<dyn std::error::Error>> {
+    if let Some(name) = requested {
+        return file
+            .workspaces
+            .get_key_value(name)
+            .map(|(k, v)| (k.as_str(), v))
+            .ok_or_else(|| format!("Workspace not found: '{name}'").into());
+    }
+
+    let mut iter = file.workspaces.iter();
+    match (iter.next(), iter.next()) {
+        (Some((name, workspace)), None) => Ok((name.as_str(), workspace)),
+        (None, _) => Err("No workspaces defined. Add one to workspaces.toml.".into()),
+        (Some(_), Some(_)) => {
+            let names: Vec<&str> = file.workspaces.keys().map(String::as_str).collect();
+            Err(format!(
+                "Multiple workspaces defined; pick one with --workspace ({})",
+                names.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+/// Print the defined workspaces and their repositories.
+fn print_workspaces(file: &WorkspacesFile, path: &Path) {
+    if file.workspaces.is_empty() {
+        output::print_info(&format!("No workspaces defined in {}", path.display()));
+        return;
+    }
+
+    for (name, workspace) in &file.workspaces {
+        if workspace.description.is_empty() {
+            say!("{}", name.cyan().bold());
+        } else {
+            say!("{} - {}", name.cyan().bold(), workspace.description);
+        }
+        for repo in &workspace.repos {
+            say!("  {} {}", "•".dimmed(), repo.path.display());
+        }
+    }
+}
+
+/// Create worktree `args.name` in every repo of `workspace`.
+///
+/// Repos are processed in order; a failure in one repo is reported and the
+/// remaining repos are still processed.
+fn run_create(
+    workspace_name: &str,
+    workspace: &Workspace,
+    args: &WorkspaceCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    output::print_header(&format!("Workspace {workspace_name}: {}", args.name));
+
+    let mut failed = 0usize;
+    for repo in &workspace.repos {
+        let target = workspace.worktree_path(repo, &args.name);
+        say!(
+            "{} {} → {}",
+            "▸".cyan(),
+            repo.path.display().to_string().bold(),
+            target.display()
+        );
+
+        if let Err(e) = create_and_apply(repo, &target, args) {
+            output::print_error(&format!("{}: {e}", repo.path.display()));
+            failed += 1;
+        }
+        say!();
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} repos failed", workspace.repos.len()).into());
+    }
+
+    output::print_success();
+    Ok(())
+}
+
+/// Create the worktree for one repo (if missing) and apply its configs.
+fn create_and_apply(
+    repo_def: &WorkspaceRepo,
+    target: &Path,
+    args: &WorkspaceCreateArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = discover_repo(&expand_home(&repo_def.path))?;
+    let repo_root = get_repo_root(&repo)?;
+    let main_worktree = get_main_worktree(&repo)?;
+    let global_config = load_global_config(Some(&repo_root))?;
+
+    if target.exists() {
+        output::print_info("Worktree already exists; applying configs.");
+    } else {
+        // The worktree is named after its branch, so the branch name has
+        // to follow the naming convention.
+        if let Some(pattern) = &global_config.create.worktree_name_pattern {
+            check_worktree_name(&args.name, pattern)?;
+        }
+        let branch_exists = get_local_branches(&repo)?.contains(&args.name);
+        let options = if branch_exists {
+            WorktreeCreateOptions {
+                branch: Some(args.name.clone()),
+                ..Default::default()
+            }
+        } else {
+            WorktreeCreateOptions {
+                new_branch: Some(args.name.clone()),
+                branch: repo_def.base_branch.clone(),
+                ..Default::default()
+            }
+        };
+        create_worktree(&repo, target, &options)?;
+    }
+
+    let mut all_configs = crate::discover_and_load_configs(&repo_root, false)?;
+    if all_configs.is_empty() {
+        return Ok(());
+    }
+
+    let profile = if repo_def.profile.is_empty() {
+        None
+    } else {
+        Some(crate::resolve_and_print_profile(
+            &repo_def.profile,
+            &mut all_configs,
+            &repo_root,
+        )?)
+    };
+    let selected: Vec<&LoadedConfig> = profile.as_ref().map_or_else(
+        || all_configs.iter().collect(),
+        |p| p.config_indices.iter().map(|&i| &all_configs[i]).collect(),
+    );
+
+    crate::execute_file_operations(
+        &selected,
+        &main_worktree.path,
+        target,
+        crate::FileOpOptions {
+            copy_unstaged_override: profile.as_ref().and_then(|p| p.defaults.copy_unstaged),
+            overwrite_existing: false,
+            progress: ProgressMode::resolve(args.progress, args.no_progress),
+            assume_yes: args.yes,
+            non_interactive: true,
+            transactional: false,
+            continue_on_error: false,
+            verify: None,
+            count_cache: true,
+            skip_space_check: false,
+            max_depth: None,
+            sudo_fallback: false,
+            dry_run: false,
+            check_sources: false,
+            force_destructive: false,
+        },
+        &global_config,
+    )?;
+
+    let detected = crate::detect::detected_post_setup_commands(
+        &selected,
+        &main_worktree.path,
+        &global_config,
+        false,
+    );
+    let commands = crate::collect_post_setup_commands(&selected, &detected);
+    let to_run = crate::resolve_post_setup_commands(args.no_install, profile.as_ref(), &commands)
+        .unwrap_or_else(|| commands.clone());
+    crate::run_post_setup_commands(&to_run, target, false, None)?;
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Worktrees subcommand.
+//!
+//! Lists every worktree of the repository with its branch, the labels
+//! attached with `--label` (purpose, ticket, owner), and when setup last
+//! ran in it, so many worktrees can be told apart and filtered by label.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::env;
+use std::path::PathBuf;
+
+use colored::Colorize;
+use serde_json::{Value, json};
+use worktree_setup_git::{discover_repo, get_worktrees};
+use worktree_setup_operations::{StateStore, WorktreeState};
+
+use crate::args::WorktreesArgs;
+use crate::output;
+use crate::reporter::say;
+
+/// A worktree with what the state store recorded about it.
+struct ListedWorktree {
+    path: PathBuf,
+    branch: Option<String>,
+    is_main: bool,
+    /// Recorded state, empty if setup never ran in the worktree.
+    state: WorktreeState,
+}
+
+impl ListedWorktree {
+    /// The worktree as a JSON object.
+    fn to_json(&self) -> Value {
+        json!({
+            "path": self.path.to_string_lossy(),
+            "branch": self.branch,
+            "main": self.is_main,
+            "labels": self.state.labels,
+            "lastSetup": self.state.last_applied(),
+        })
+    }
+}
+
+/// Run the `worktrees` subcommand.
+///
+/// # Errors
+///
+/// * If the current directory is not inside a git repository
+pub fn run(args: &WorktreesArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = env::current_dir()?;
+    let repo = discover_repo(&crate::start_dir(&cwd))?;
+    let store = StateStore::for_repository(&repo);
+
+    let listed: Vec<ListedWorktree> = get_worktrees(&repo)?
+        .into_iter()
+        .map(|worktree| ListedWorktree {
+            state: store
+                .load(&worktree.path)
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| WorktreeState::new(&worktree.path)),
+            path: worktree.path,
+            branch: worktree.branch,
+            is_main: worktree.is_main,
+        })
+        .filter(|worktree| worktree.state.has_labels(&args.labels))
+        .collect();
+
+    if args.json {
+        let worktrees: Vec<Value> = listed.iter().map(ListedWorktree::to_json).collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "worktrees": worktrees }))?
+        );
+        return Ok(());
+    }
+
+    output::print_header("Worktrees");
+    if listed.is_empty() {
+        say!("{}", "No worktrees match.".dimmed());
+        return Ok(());
+    }
+    print_human(&listed);
+    Ok(())
+}
+
+/// Print one aligned line per worktree.
+fn print_human(listed: &[ListedWorktree]) {
+    let branch = |worktree: &ListedWorktree| {
+        worktree
+            .branch
+            .clone()
+            .unwrap_or_else(|| "(detached)".to_string())
+    };
+    let path_width = listed
+        .iter()
+        .map(|w| w.path.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    let branch_width = listed.iter().map(|w| branch(w).len()).max().unwrap_or(0);
+
+    for worktree in listed {
+        let mut details: Vec<String> = worktree
+            .state
+            .labels
+            .iter()
+            .map(|(key, value)| format!("{key}={value}").yellow().to_string())
+            .collect();
+        if worktree.is_main {
+            details.push("(main)".dimmed().to_string());
+        } else if let Some(at) = worktree.state.last_applied() {
+            details.push(
+                format!("set up {}", output::format_age(at))
+                    .dimmed()
+                    .to_string(),
+            );
+        }
+        let line = format!(
+            "  {:<path_width$}  {}  {}",
+            worktree.path.display().to_string(),
+            format!("{:<branch_width$}", branch(worktree)).cyan(),
+            details.join(" ")
+        );
+        say!("{}", line.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use worktree_setup_operations::Label;
+
+    #[test]
+    fn test_listed_worktree_json() {
+        let mut state = WorktreeState::new(Path::new("/repo-review"));
+        state.add_labels(&["ticket=ABC-123".parse::<Label>().unwrap()]);
+        let worktree = ListedWorktree {
+            path: PathBuf::from("/repo-review"),
+            branch: Some("review".to_string()),
+            is_main: false,
+            state,
+        };
+
+        let json = worktree.to_json();
+        assert_eq!(json["labels"]["ticket"], "ABC-123");
+        assert_eq!(json["branch"], "review");
+        assert_eq!(json["lastSetup"], Value::Null);
+        assert_eq!(json["main"], false);
+    }
+}
@@ -12,25 +12,56 @@ use std::path::{Path, PathBuf};
 use worktree_setup_glob::DEFAULT_SKIP_DIRS;
 
 use crate::error::ConfigError;
+use crate::package_json::{PACKAGE_JSON, has_package_json_config};
 use crate::types::LoadedConfig;
 
+/// Options controlling config discovery.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiscoveryOptions {
+    /// Also discover configs inside submodule checkouts.
+    ///
+    /// Submodules are recognized by a `.git` file (not directory) at their
+    /// root. When `false`, such directories are pruned like
+    /// [`DEFAULT_SKIP_DIRS`].
+    pub include_submodules: bool,
+}
+
 /// Discover all worktree configuration files in a repository.
 ///
-/// Searches for files matching `worktree.config.{toml,ts}` and
-/// `worktree.*.config.{toml,ts}` patterns using fast parallel directory traversal.
+/// Equivalent to [`discover_configs_with_options`] with default options,
+/// so configs inside submodules are not discovered.
+///
+/// # Errors
+///
+/// * If the directory cannot be read
+pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    discover_configs_with_options(repo_root, DiscoveryOptions::default())
+}
+
+/// Discover worktree configuration files in a repository.
+///
+/// Searches for files matching `worktree.config.<ext>` and
+/// `worktree.*.config.<ext>` patterns, where `<ext>` is `toml`, `ts`, `js`,
+/// `mjs`, `cjs`, `yaml`, or `yml`, plus `package.json` files with a
+/// `"worktreeSetup"` field, using fast parallel directory traversal.
 ///
 /// Automatically prunes directories listed in [`DEFAULT_SKIP_DIRS`]
 /// (`node_modules`, `.git`, `target`) at the directory level so their
-/// contents are never traversed.
+/// contents are never traversed. Submodule checkouts are pruned the same
+/// way unless `options.include_submodules` is set.
 ///
 /// # Arguments
 ///
 /// * `repo_root` - Path to the repository root
+/// * `options` - Discovery options
 ///
 /// # Errors
 ///
 /// * If the directory cannot be read
-pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+pub fn discover_configs_with_options(
+    repo_root: &Path,
+    options: DiscoveryOptions,
+) -> Result<Vec<PathBuf>, ConfigError> {
     log::debug!("Discovering configs in {}", repo_root.display());
 
     let mut configs: Vec<PathBuf> = jwalk::WalkDirGeneric::<((), ())>::new(repo_root)
@@ -46,7 +77,7 @@ pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
         // concurrent use and keeps our jwalk parallelism policy
         // consistent across the workspace.
         .parallelism(jwalk::Parallelism::Serial)
-        .process_read_dir(|_depth, _path, _state, children| {
+        .process_read_dir(move |_depth, _path, _state, children| {
             // Prune directories we never want to enter.  Setting
             // `read_children_path = None` prevents jwalk from descending.
             // Removing entries entirely prevents them from appearing in
@@ -60,6 +91,9 @@ pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
                     if DEFAULT_SKIP_DIRS.iter().any(|&skip| name == skip) {
                         return false;
                     }
+                    if !options.include_submodules && is_submodule_checkout(&entry.path()) {
+                        return false;
+                    }
                 }
                 true
             });
@@ -72,11 +106,17 @@ pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
                 return false;
             }
 
-            // Match worktree.config.{toml,ts} or worktree.*.config.{toml,ts}
+            // Match worktree.config.<ext> or worktree.*.config.<ext>, or a
+            // package.json with a worktreeSetup field
             let name = entry.file_name().to_string_lossy();
+            if name == PACKAGE_JSON {
+                return has_package_json_config(&entry.path());
+            }
             name.starts_with("worktree")
                 && name.contains(".config.")
-                && (name.ends_with(".toml") || name.ends_with(".ts"))
+                && [".toml", ".ts", ".js", ".mjs", ".cjs", ".yaml", ".yml"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
         })
         .map(|entry| entry.path())
         .collect();
@@ -87,6 +127,11 @@ pub fn discover_configs(repo_root: &Path) -> Result<Vec<PathBuf>, ConfigError> {
     Ok(configs)
 }
 
+/// Whether `dir` is the root of a submodule checkout (has a `.git` file).
+fn is_submodule_checkout(dir: &Path) -> bool {
+    dir.join(".git").is_file()
+}
+
 /// Get a display name for a loaded configuration.
 ///
 /// Returns a short, human-readable name based on the config's directory.
@@ -121,6 +166,55 @@ mod tests {
         assert_eq!(get_config_display_name(&config), "my-app");
     }
 
+    #[test]
+    fn test_discover_configs_submodules_opt_in() {
+        use std::fs;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("worktree.config.toml"), "").unwrap();
+        let submodule = root.join("libs/sub");
+        fs::create_dir_all(&submodule).unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../../.git/modules/sub\n").unwrap();
+        fs::write(submodule.join("worktree.config.toml"), "").unwrap();
+        fs::write(root.join("worktree.local.config.yml"), "").unwrap();
+        fs::write(root.join("worktree.config.json"), "").unwrap();
+        fs::write(root.join("package.json"), r#"{"name": "root"}"#).unwrap();
+        fs::create_dir_all(root.join("apps/web")).unwrap();
+        fs::write(
+            root.join("apps/web/package.json"),
+            r#"{"worktreeSetup": {"copy": [".env"]}}"#,
+        )
+        .unwrap();
+
+        let found = discover_configs(root).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                root.join("apps/web/package.json"),
+                root.join("worktree.config.toml"),
+                root.join("worktree.local.config.yml"),
+            ]
+        );
+
+        let found = discover_configs_with_options(
+            root,
+            DiscoveryOptions {
+                include_submodules: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                root.join("apps/web/package.json"),
+                root.join("libs/sub/worktree.config.toml"),
+                root.join("worktree.config.toml"),
+                root.join("worktree.local.config.yml"),
+            ]
+        );
+    }
+
     /// **Regression test for jwalk busy-timeout bug**, analogous to
     /// `test_resolve_glob_concurrent_callers` in the `glob` crate.
     ///
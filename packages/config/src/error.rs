@@ -8,7 +8,12 @@ use std::path::PathBuf;
 
 use thiserror::Error;
 
+use crate::schema::{UnknownKey, format_unknown_keys};
+
 /// Errors that can occur during configuration loading.
+///
+/// Each variant has a stable [`code`](Self::code). New variants take the
+/// next unused number; codes are never reused.
 #[derive(Debug, Error)]
 pub enum ConfigError {
     /// Failed to read configuration file.
@@ -50,6 +55,15 @@ pub enum ConfigError {
         message: String,
     },
 
+    /// A TypeScript config's hash is not in `security.ts_config_hashes`.
+    #[error("TypeScript config {} is not allowed to run: its hash {hash} is not in security.ts_config_hashes", path.display())]
+    UntrustedTsConfig {
+        /// Path to the refused config.
+        path: PathBuf,
+        /// Hex BLAKE3 hash of the config.
+        hash: String,
+    },
+
     /// No JavaScript runtime (bun/deno) found.
     #[error("No JavaScript runtime found. Please install bun or deno.")]
     NoJsRuntime,
@@ -69,4 +83,214 @@ pub enum ConfigError {
     /// Profile not found.
     #[error("Profile not found: '{0}'. Use --list to see available profiles.")]
     ProfileNotFound(String),
+
+    /// A `--config` pattern matched no discovered config.
+    #[error("No config matches '{pattern}'.{}", format_suggestions(suggestions))]
+    NoConfigMatch {
+        /// The pattern that matched nothing.
+        pattern: String,
+        /// Similarly named configs, best match first.
+        suggestions: Vec<String>,
+    },
+
+    /// A `--config` pattern matched more than one config.
+    #[error("Config pattern '{pattern}' is ambiguous; it matches: {}. Use a path or a `glob:` pattern.", matches.join(", "))]
+    AmbiguousConfigPattern {
+        /// The ambiguous pattern.
+        pattern: String,
+        /// Relative paths of the matching configs.
+        matches: Vec<String>,
+    },
+
+    /// A `--config` pattern is malformed.
+    #[error("Invalid config pattern '{pattern}': {message}")]
+    InvalidConfigPattern {
+        /// The invalid pattern.
+        pattern: String,
+        /// Why the pattern is invalid.
+        message: String,
+    },
+
+    /// A base config named in `extends` could not be fetched.
+    #[error("Failed to fetch base config {url}: {message}")]
+    RemoteFetchError {
+        /// URL of the base config.
+        url: String,
+        /// Why fetching failed.
+        message: String,
+    },
+
+    /// A base config does not match its pinned checksum.
+    #[error(
+        "Base config {url} does not match its pinned checksum (expected {expected}, got {actual})"
+    )]
+    ChecksumMismatch {
+        /// URL of the base config.
+        url: String,
+        /// Checksum pinned in `extends`.
+        expected: String,
+        /// Checksum of the fetched content.
+        actual: String,
+    },
+
+    /// `--offline` was given and a base config is not cached.
+    #[error("Base config {0} is not cached; run without --offline to fetch it")]
+    OfflineUncached(String),
+
+    /// A config extends itself, directly or through other base configs.
+    #[error("Config extends itself through {0}")]
+    ExtendsCycle(String),
+
+    /// `create.worktree_name_pattern` is not a valid regular expression.
+    #[error("Invalid worktree_name_pattern '{pattern}': {message}")]
+    InvalidNamePattern {
+        /// The invalid pattern.
+        pattern: String,
+        /// Why the pattern is invalid.
+        message: String,
+    },
+
+    /// A new worktree's name does not match `create.worktree_name_pattern`.
+    #[error("Worktree name '{name}' does not match worktree_name_pattern '{pattern}'.{}", format_name_suggestion(suggestion.as_deref()))]
+    WorktreeNameMismatch {
+        /// The rejected name.
+        name: String,
+        /// The pattern it had to match.
+        pattern: String,
+        /// A sanitized name that matches, if one could be derived.
+        suggestion: Option<String>,
+    },
+
+    /// Failed to parse YAML configuration.
+    #[error("Failed to parse YAML config {}: {source}", path.display())]
+    YamlParseError {
+        /// Path to the file that couldn't be parsed.
+        path: PathBuf,
+        /// The underlying YAML error.
+        #[source]
+        source: serde_yaml::Error,
+    },
+
+    /// Failed to parse the config embedded in a `package.json`.
+    #[error("Failed to parse the worktreeSetup field of {}: {source}", path.display())]
+    PackageJsonParseError {
+        /// Path to the `package.json` that couldn't be parsed.
+        path: PathBuf,
+        /// The underlying JSON error.
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A config fragment includes itself, directly or through other
+    /// fragments.
+    #[error("Config includes itself through {0}")]
+    IncludeCycle(String),
+
+    /// A config file has keys no config field reads, with strict schema
+    /// checking on (see [`crate::set_strict_schema`]).
+    #[error("Unknown config keys:\n{}", format_unknown_keys(.0))]
+    UnknownKeys(Vec<UnknownKey>),
+}
+
+impl ConfigError {
+    /// Stable code identifying the kind of error, such as `WTS-CFG-001`.
+    ///
+    /// Codes never change meaning, so scripts can match on them and users
+    /// can search for them in the documentation.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::ReadError { .. } => "WTS-CFG-001",
+            Self::TomlParseError { .. } => "WTS-CFG-002",
+            Self::JsonParseError { .. } => "WTS-CFG-003",
+            Self::TypeScriptEvalError { .. } => "WTS-CFG-004",
+            Self::UntrustedTsConfig { .. } => "WTS-CFG-005",
+            Self::NoJsRuntime => "WTS-CFG-006",
+            Self::UnsupportedFormat(_) => "WTS-CFG-007",
+            Self::InvalidPath(_) => "WTS-CFG-008",
+            Self::IoError(_) => "WTS-CFG-009",
+            Self::ProfileNotFound(_) => "WTS-CFG-010",
+            Self::NoConfigMatch { .. } => "WTS-CFG-011",
+            Self::AmbiguousConfigPattern { .. } => "WTS-CFG-012",
+            Self::InvalidConfigPattern { .. } => "WTS-CFG-013",
+            Self::RemoteFetchError { .. } => "WTS-CFG-014",
+            Self::ChecksumMismatch { .. } => "WTS-CFG-015",
+            Self::OfflineUncached(_) => "WTS-CFG-016",
+            Self::ExtendsCycle(_) => "WTS-CFG-017",
+            Self::InvalidNamePattern { .. } => "WTS-CFG-018",
+            Self::WorktreeNameMismatch { .. } => "WTS-CFG-019",
+            Self::YamlParseError { .. } => "WTS-CFG-020",
+            Self::PackageJsonParseError { .. } => "WTS-CFG-021",
+            Self::IncludeCycle(_) => "WTS-CFG-022",
+            Self::UnknownKeys(_) => "WTS-CFG-023",
+        }
+    }
+
+    /// A suggestion for fixing the error.
+    #[must_use]
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::ReadError { .. } => "Check that the config file exists and is readable.",
+            Self::TomlParseError { .. } => "Fix the TOML syntax at the reported line and column.",
+            Self::JsonParseError { .. } => {
+                "Make sure the TypeScript config's default export is plain JSON-serializable data."
+            }
+            Self::TypeScriptEvalError { .. } => {
+                "Run the config with bun or deno directly to see the full error."
+            }
+            Self::UntrustedTsConfig { .. } => {
+                "Review the config, then add its hash to security.ts_config_hashes in the global config."
+            }
+            Self::NoJsRuntime => {
+                "Install bun or deno (or node, for JavaScript configs), or use a TOML config."
+            }
+            Self::UnsupportedFormat(_) => {
+                "Name the config worktree.config.<ext> or worktree.<name>.config.<ext>, with ext toml, ts, js, mjs, cjs, yaml, or yml."
+            }
+            Self::InvalidPath(_) => "Pass the path of an existing config file.",
+            Self::IoError(_) => "Check the permissions of the directories being searched.",
+            Self::ProfileNotFound(_) => "Run with --list to see the profiles the configs define.",
+            Self::NoConfigMatch { .. } => "Run with --list to see the discovered configs.",
+            Self::AmbiguousConfigPattern { .. } => {
+                "Use the config's path, or a glob: pattern to select several configs."
+            }
+            Self::InvalidConfigPattern { .. } => "Fix the glob syntax in the pattern.",
+            Self::RemoteFetchError { .. } => {
+                "Check the URL and your network connection, or use --offline once it is cached."
+            }
+            Self::ChecksumMismatch { .. } => {
+                "If the base config changed on purpose, update the pinned checksum in extends."
+            }
+            Self::OfflineUncached(_) => "Run once without --offline to cache the base config.",
+            Self::ExtendsCycle(_) => "Remove the extends entry that leads back to this config.",
+            Self::InvalidNamePattern { .. } => {
+                "Fix the regular expression in create.worktree_name_pattern."
+            }
+            Self::WorktreeNameMismatch { .. } => {
+                "Choose a name matching create.worktree_name_pattern, or use the suggested name."
+            }
+            Self::YamlParseError { .. } => "Fix the YAML syntax at the reported line and column.",
+            Self::PackageJsonParseError { .. } => {
+                "Make sure package.json is valid JSON and worktreeSetup holds config fields."
+            }
+            Self::IncludeCycle(_) => "Remove the include entry that leads back to this config.",
+            Self::UnknownKeys(_) => {
+                "Fix the spelling of each key or remove it; without --strict-config they are only warned about."
+            }
+        }
+    }
+}
+
+/// Render the sanitized alternative for a rejected worktree name.
+fn format_name_suggestion(suggestion: Option<&str>) -> String {
+    suggestion.map_or_else(String::new, |name| format!(" Try '{name}'."))
+}
+
+/// Render "did you mean?" suggestions for an error message.
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        " Use --list to see available configs.".to_string()
+    } else {
+        format!(" Did you mean: {}?", suggestions.join(", "))
+    }
 }
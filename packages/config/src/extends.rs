@@ -0,0 +1,555 @@
+//! Base configs named in `extends`, and fragments named in `include`.
+//!
+//! A config can extend other configs, named by path (relative to the
+//! extending config) or by `http(s)://` URL, so org-wide base configs can
+//! be maintained in one place. Fetched configs are cached, optionally
+//! pinned to a BLAKE3 checksum, and read from the cache alone in offline
+//! mode. Fetching needs the `remote` feature.
+//!
+//! Fragments named in `include` are spliced into the including config, so
+//! a large config can be split across files.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::ConfigError;
+use crate::package_json::{is_package_json, load_package_json_config};
+use crate::toml_loader::{load_toml_config, parse_toml_config};
+use crate::ts_loader::load_ts_config;
+use crate::types::Config;
+use crate::yaml_loader::load_yaml_config;
+
+/// How base configs are fetched from URLs.
+#[derive(Debug, Clone, Default)]
+pub struct RemoteOptions {
+    /// Directory fetched configs are cached in, or `None` to not cache.
+    pub cache_dir: Option<PathBuf>,
+    /// Read fetched configs from the cache only, never from the network.
+    pub offline: bool,
+}
+
+/// Options used by [`crate::load_config`], set once by the caller.
+static REMOTE_OPTIONS: OnceLock<RemoteOptions> = OnceLock::new();
+
+/// Set how [`crate::load_config`] fetches base configs. Only the first
+/// call has an effect; without one, nothing is cached.
+pub fn set_remote_options(options: RemoteOptions) {
+    if REMOTE_OPTIONS.set(options).is_err() {
+        log::debug!("Remote config options were already set");
+    }
+}
+
+/// Options set with [`set_remote_options`].
+pub fn remote_options() -> &'static RemoteOptions {
+    REMOTE_OPTIONS.get_or_init(RemoteOptions::default)
+}
+
+/// Where relative `extends` entries of a config resolve from.
+enum Base<'a> {
+    /// The directory of a config file.
+    Dir(&'a Path),
+    /// The URL a config was fetched from.
+    Url(&'a str),
+}
+
+/// Merge every config named in `config.extends` under `config`, loaded
+/// from `config_path`.
+///
+/// Base configs may extend further configs. Later entries win over
+/// earlier ones, and `config` wins over all of them. Each config's section
+/// for the current OS is merged over it first (see
+/// [`Config::apply_os_section`]), then the fragments it includes are
+/// spliced in (see [`Config::include_from`]).
+///
+/// # Errors
+///
+/// * If a base config or fragment cannot be read, fetched, or parsed
+/// * If a base config does not match its pinned checksum
+/// * If `options.offline` is set and a base config is not cached
+/// * If a config extends itself
+/// * If a fragment includes itself
+pub fn resolve_extends(
+    config: Config,
+    config_path: &Path,
+    options: &RemoteOptions,
+) -> Result<Config, ConfigError> {
+    let dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+    let mut chain = vec![location_key(
+        &config_path.to_string_lossy(),
+        &Base::Dir(dir),
+    )];
+    extend(config, &Base::Dir(dir), &mut chain, options)
+}
+
+/// Resolve `config`'s `extends` against `base`, with `chain` holding the
+/// configs being extended (to detect cycles).
+fn extend(
+    mut config: Config,
+    base: &Base<'_>,
+    chain: &mut Vec<String>,
+    options: &RemoteOptions,
+) -> Result<Config, ConfigError> {
+    config.apply_os_section();
+    let mut config = include(config, base, chain, options)?;
+    let mut merged: Option<Config> = None;
+    for entry in std::mem::take(&mut config.extends) {
+        let location = entry.location();
+        let key = location_key(location, base);
+        if chain.contains(&key) {
+            return Err(ConfigError::ExtendsCycle(location.to_string()));
+        }
+
+        let parsed = read_config(&key, entry.checksum(), options)?;
+        chain.push(key.clone());
+        let resolved = extend(parsed, &base_of(&key), chain, options);
+        chain.pop();
+        let parent = resolved?;
+
+        merged = Some(match merged {
+            Some(earlier) => {
+                let mut later = parent;
+                later.extend_from(earlier);
+                later
+            }
+            None => parent,
+        });
+    }
+
+    if let Some(merged) = merged {
+        config.extend_from(merged);
+    }
+    Ok(config)
+}
+
+/// Splice the fragments named in `config.include` into `config`, with
+/// `chain` holding the configs being resolved (to detect cycles).
+///
+/// Each fragment is resolved on its own first: its OS section, the
+/// fragments it includes, and the configs it extends.
+fn include(
+    mut config: Config,
+    base: &Base<'_>,
+    chain: &mut Vec<String>,
+    options: &RemoteOptions,
+) -> Result<Config, ConfigError> {
+    for location in std::mem::take(&mut config.include) {
+        let key = location_key(&location, base);
+        if chain.contains(&key) {
+            return Err(ConfigError::IncludeCycle(location));
+        }
+
+        let fragment = read_config(&key, None, options)?;
+        chain.push(key.clone());
+        let resolved = extend(fragment, &base_of(&key), chain, options);
+        chain.pop();
+        config.include_from(resolved?);
+    }
+    Ok(config)
+}
+
+/// Read and parse the config at `key` (a URL or an absolute path),
+/// checking it against `checksum` if one is pinned. Fetched configs are
+/// TOML; files are parsed according to their name.
+fn read_config(
+    key: &str,
+    checksum: Option<&str>,
+    options: &RemoteOptions,
+) -> Result<Config, ConfigError> {
+    if is_url(key) {
+        let content = fetch(key, checksum, options)?;
+        return parse_toml_config(Path::new(key), &content);
+    }
+
+    let path = PathBuf::from(key);
+    if let Some(expected) = checksum {
+        let content = fs::read(&path).map_err(|e| ConfigError::ReadError {
+            path: path.clone(),
+            source: e,
+        })?;
+        verify_checksum(key, &content, expected)?;
+    }
+    match path.extension().and_then(|e| e.to_str()) {
+        _ if is_package_json(&path) => load_package_json_config(&path),
+        Some("ts" | "js" | "mjs" | "cjs") => load_ts_config(&path),
+        Some("yaml" | "yml") => load_yaml_config(&path),
+        _ => load_toml_config(&path),
+    }
+}
+
+/// What locations inside the config at `key` are relative to.
+fn base_of(key: &str) -> Base<'_> {
+    if is_url(key) {
+        Base::Url(key)
+    } else {
+        Base::Dir(Path::new(key).parent().unwrap_or_else(|| Path::new("")))
+    }
+}
+
+/// Whether `location` is a URL rather than a path.
+fn is_url(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+/// Absolute URL or path of `location`, resolved against `base`.
+fn location_key(location: &str, base: &Base<'_>) -> String {
+    if is_url(location) {
+        return location.to_string();
+    }
+    match base {
+        Base::Dir(dir) => {
+            let path = dir.join(location);
+            path.canonicalize()
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        }
+        Base::Url(url) => {
+            let prefix = url.rfind('/').map_or(*url, |i| &url[..=i]);
+            format!("{prefix}{}", location.trim_start_matches("./"))
+        }
+    }
+}
+
+/// Content of the base config at `url`, from the cache or the network.
+///
+/// Content pinned by a checksum never changes, so a matching cached copy
+/// is used without a request. Otherwise the config is fetched, falling
+/// back to the cached copy if the request fails.
+fn fetch(
+    url: &str,
+    checksum: Option<&str>,
+    options: &RemoteOptions,
+) -> Result<String, ConfigError> {
+    let cache_file = options
+        .cache_dir
+        .as_ref()
+        .map(|dir| dir.join(cache_key(url)));
+    let cached = cache_file
+        .as_ref()
+        .and_then(|file| fs::read_to_string(file).ok());
+
+    if let (Some(content), Some(expected)) = (&cached, checksum)
+        && verify_checksum(url, content.as_bytes(), expected).is_ok()
+    {
+        log::debug!("Using cached base config {url}");
+        return Ok(content.clone());
+    }
+
+    if options.offline {
+        let content = cached.ok_or_else(|| ConfigError::OfflineUncached(url.to_string()))?;
+        if let Some(expected) = checksum {
+            verify_checksum(url, content.as_bytes(), expected)?;
+        }
+        return Ok(content);
+    }
+
+    log::debug!("Fetching base config {url}");
+    let content = match download(url) {
+        Ok(content) => content,
+        Err(message) => {
+            let Some(content) = cached else {
+                return Err(ConfigError::RemoteFetchError {
+                    url: url.to_string(),
+                    message,
+                });
+            };
+            log::warn!("Failed to fetch {url} ({message}); using the cached copy");
+            content
+        }
+    };
+    if let Some(expected) = checksum {
+        verify_checksum(url, content.as_bytes(), expected)?;
+    }
+
+    if let Some(file) = cache_file {
+        let written = file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&file, &content));
+        if let Err(e) = written {
+            log::debug!("Failed to cache {url} in {}: {e}", file.display());
+        }
+    }
+    Ok(content)
+}
+
+/// Name of the cache file for `url`.
+fn cache_key(url: &str) -> String {
+    let hash = blake3::hash(url.as_bytes()).to_hex();
+    format!("{}.toml", &hash[..32])
+}
+
+/// Check that `content` hashes to the hex BLAKE3 checksum `expected`.
+fn verify_checksum(location: &str, content: &[u8], expected: &str) -> Result<(), ConfigError> {
+    let actual = blake3::hash(content).to_hex().to_string();
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(ConfigError::ChecksumMismatch {
+            url: location.to_string(),
+            expected: expected.trim().to_string(),
+            actual,
+        })
+    }
+}
+
+/// Request the body of `url`.
+#[cfg(feature = "remote")]
+fn download(url: &str) -> Result<String, String> {
+    /// How long a single request may take.
+    const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .build()
+        .into();
+    agent
+        .get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Request the body of `url`.
+#[cfg(not(feature = "remote"))]
+fn download(_url: &str) -> Result<String, String> {
+    Err("built without remote config support (the `remote` feature)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn load(path: &Path, options: &RemoteOptions) -> Result<Config, ConfigError> {
+        resolve_extends(load_toml_config(path)?, path, options)
+    }
+
+    #[test]
+    fn test_extends_merges_base_configs_in_order() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("base")).unwrap();
+        fs::write(
+            dir.path().join("base/node.toml"),
+            "description = \"node\"\nsymlinks = [\"node_modules\"]\npostSetup = [\"bun install\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("base/web.toml"),
+            "extends = \"node.toml\"\ncopy = [\".env\"]\ncopyUnstaged = true\n",
+        )
+        .unwrap();
+        let path = dir.path().join("worktree.config.toml");
+        fs::write(
+            &path,
+            "extends = [\"base/web.toml\"]\ncopy = [\".env.local\"]\ncopyUnstaged = false\n",
+        )
+        .unwrap();
+
+        let config = load(&path, &RemoteOptions::default()).unwrap();
+
+        assert!(config.extends.is_empty());
+        assert_eq!(config.description, "node");
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+        assert_eq!(config.copy, vec![".env", ".env.local"]);
+        assert_eq!(config.post_setup, vec!["bun install"]);
+        assert_eq!(config.copy_unstaged, Some(false));
+    }
+
+    #[test]
+    fn test_os_sections_merge_on_their_os() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("base.toml"),
+            "copy = [\".env\"]\n[windows]\ncopy = [\".env.windows\"]\n\
+             [macos]\ncopy = [\".env.macos\"]\n[linux]\ncopy = [\".env.linux\"]\n",
+        )
+        .unwrap();
+        let path = dir.path().join("worktree.config.toml");
+        fs::write(
+            &path,
+            r#"extends = "base.toml"
+symlinks = ["node_modules"]
+postSetup = ["npm install"]
+copyUnstaged = true
+
+[windows]
+symlinks = ["target"]
+postSetup = ["npm.cmd run setup"]
+copyUnstaged = false
+
+[macos]
+postSetup = ["brew bundle"]
+
+[linux]
+postSetup = ["./setup.sh"]
+strictGlobs = true
+"#,
+        )
+        .unwrap();
+
+        let config = load(&path, &RemoteOptions::default()).unwrap();
+
+        assert!(config.windows.is_none() && config.macos.is_none() && config.linux.is_none());
+        let os = std::env::consts::OS;
+        let (symlinks, copy, post_setup, copy_unstaged, strict_globs) = match os {
+            "windows" => (
+                vec!["node_modules", "target"],
+                vec![".env", ".env.windows"],
+                vec!["npm install", "npm.cmd run setup"],
+                Some(false),
+                None,
+            ),
+            "macos" => (
+                vec!["node_modules"],
+                vec![".env", ".env.macos"],
+                vec!["npm install", "brew bundle"],
+                Some(true),
+                None,
+            ),
+            "linux" => (
+                vec!["node_modules"],
+                vec![".env", ".env.linux"],
+                vec!["npm install", "./setup.sh"],
+                Some(true),
+                Some(true),
+            ),
+            _ => (
+                vec!["node_modules"],
+                vec![".env"],
+                vec!["npm install"],
+                Some(true),
+                None,
+            ),
+        };
+        assert_eq!(config.symlinks, symlinks);
+        assert_eq!(config.copy, copy);
+        assert_eq!(config.post_setup, post_setup);
+        assert_eq!(config.copy_unstaged, copy_unstaged);
+        assert_eq!(config.strict_globs, strict_globs);
+    }
+
+    #[test]
+    fn test_include_splices_fragments() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("fragments")).unwrap();
+        fs::write(
+            dir.path().join("base.toml"),
+            "symlinks = [\"node_modules\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("fragments/env.toml"),
+            "include = \"cache.toml\"\ncopy = [\".env\"]\ncopyUnstaged = true\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("fragments/cache.toml"),
+            "extends = \"../base.toml\"\ncopy = [\".cache\"]\n",
+        )
+        .unwrap();
+        let path = dir.path().join("worktree.config.toml");
+        fs::write(
+            &path,
+            "include = [\"fragments/env.toml\"]\ncopy = [\"config.json\"]\ncopyUnstaged = false\n",
+        )
+        .unwrap();
+
+        let config = load(&path, &RemoteOptions::default()).unwrap();
+
+        assert!(config.include.is_empty());
+        assert_eq!(config.copy, vec!["config.json", ".env", ".cache"]);
+        assert_eq!(config.copy_unstaged, Some(false));
+        // The fragment's extends is resolved against the fragment.
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+    }
+
+    #[test]
+    fn test_include_detects_cycles() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.toml"), "include = \"b.toml\"\n").unwrap();
+        fs::write(dir.path().join("b.toml"), "include = \"a.toml\"\n").unwrap();
+
+        let result = load(&dir.path().join("a.toml"), &RemoteOptions::default());
+
+        assert!(matches!(result, Err(ConfigError::IncludeCycle(_))));
+    }
+
+    #[test]
+    fn test_extends_detects_cycles() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.toml"), "extends = \"b.toml\"\n").unwrap();
+        fs::write(dir.path().join("b.toml"), "extends = \"a.toml\"\n").unwrap();
+
+        let result = load(&dir.path().join("a.toml"), &RemoteOptions::default());
+
+        assert!(matches!(result, Err(ConfigError::ExtendsCycle(_))));
+    }
+
+    #[test]
+    fn test_extends_verifies_pinned_checksum() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("base.toml"), "copy = [\".env\"]\n").unwrap();
+        let checksum = blake3::hash(b"copy = [\".env\"]\n").to_hex().to_string();
+        let path = dir.path().join("worktree.config.toml");
+
+        fs::write(
+            &path,
+            format!("extends = {{ url = \"base.toml\", checksum = \"{checksum}\" }}\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            load(&path, &RemoteOptions::default()).unwrap().copy,
+            vec![".env"]
+        );
+
+        fs::write(
+            &path,
+            "extends = { url = \"base.toml\", checksum = \"00\" }\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load(&path, &RemoteOptions::default()),
+            Err(ConfigError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_offline_extends_reads_the_cache() {
+        let dir = TempDir::new().unwrap();
+        let cache = dir.path().join("cache");
+        let url = "https://example.com/configs/node.toml";
+        let path = dir.path().join("worktree.config.toml");
+        fs::write(&path, format!("extends = \"{url}\"\n")).unwrap();
+        let options = RemoteOptions {
+            cache_dir: Some(cache.clone()),
+            offline: true,
+        };
+
+        assert!(matches!(
+            load(&path, &options),
+            Err(ConfigError::OfflineUncached(_))
+        ));
+
+        fs::create_dir(&cache).unwrap();
+        fs::write(
+            cache.join(cache_key(url)),
+            "extends = \"common.toml\"\nsymlinks = [\"node_modules\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            cache.join(cache_key("https://example.com/configs/common.toml")),
+            "copy = [\".env\"]\n",
+        )
+        .unwrap();
+
+        let config = load(&path, &options).unwrap();
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+        assert_eq!(config.copy, vec![".env"]);
+    }
+}
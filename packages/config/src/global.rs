@@ -14,6 +14,22 @@
 //! ```toml
 //! [remove]
 //! branch_delete = "ASK"  # or "ALWAYS" or "NEVER"
+//!
+//! [gc]
+//! idle_days = 14
+//! require_merged = true
+//! base = "main"
+//!
+//! [create]
+//! worktree_name_pattern = "^[a-z0-9-]+$"
+//! target_base = "../worktrees"
+//!
+//! [setup]
+//! confirm_above = "5GB"
+//! operation_timeout = 300  # seconds
+//! install_hooks = true
+//! post_setup_auto = true
+//! protected_branches = ["main", "release/*"]
 //! ```
 
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
@@ -39,6 +55,17 @@ pub enum BranchDeletePolicy {
     Never,
 }
 
+/// Which unstaged/untracked files `copyUnstaged` replicates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnstagedScope {
+    /// Every unstaged/untracked file in the repository (default).
+    #[default]
+    Repo,
+    /// Only files under the directories of the configs enabling it.
+    Configs,
+}
+
 /// Configuration for the `remove` subcommand.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RemoveConfig {
@@ -47,6 +74,62 @@ pub struct RemoveConfig {
     pub branch_delete: BranchDeletePolicy,
 }
 
+/// Days since its last commit before a worktree counts as idle for `gc`.
+pub const DEFAULT_GC_IDLE_DAYS: u64 = 14;
+
+/// Which worktrees the `gc` subcommand removes.
+///
+/// A linked worktree is removed when it has no uncommitted changes, it has
+/// seen no commits or other activity for `idle_days`, and (unless
+/// `require_merged` is off) everything committed in it is merged into
+/// `base`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcConfig {
+    /// Days without commits or other activity in the worktree (default 14).
+    #[serde(default)]
+    pub idle_days: Option<u64>,
+
+    /// Only remove worktrees merged into `base` (default `true`).
+    #[serde(default)]
+    pub require_merged: Option<bool>,
+
+    /// Branch worktrees must be merged into (default: the repository's
+    /// default branch).
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+impl GcConfig {
+    /// `idle_days`, or its default.
+    #[must_use]
+    pub fn idle_days(&self) -> u64 {
+        self.idle_days.unwrap_or(DEFAULT_GC_IDLE_DAYS)
+    }
+
+    /// `require_merged`, or its default.
+    #[must_use]
+    pub fn require_merged(&self) -> bool {
+        self.require_merged.unwrap_or(true)
+    }
+}
+
+/// Settings for creating worktrees.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateConfig {
+    /// Regular expression every new worktree's directory name must match
+    /// (e.g. `"^[a-z0-9-]+$"`). See [`check_worktree_name`].
+    ///
+    /// [`check_worktree_name`]: crate::check_worktree_name
+    #[serde(default)]
+    pub worktree_name_pattern: Option<String>,
+
+    /// Directory a bare worktree name (`worktree-setup feature-login`) is
+    /// created in. Relative paths are resolved against the main worktree;
+    /// `~` expands to the home directory.
+    #[serde(default)]
+    pub target_base: Option<PathBuf>,
+}
+
 /// Security-related configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SecurityConfig {
@@ -59,6 +142,158 @@ pub struct SecurityConfig {
     /// Individual configs can override this with `allowPathEscape = true`.
     #[serde(default)]
     pub allow_path_escape: bool,
+
+    /// Evaluate TypeScript configs without host access: never fall back
+    /// to `bun`, and run `deno` with no permissions.
+    ///
+    /// Only read from the global config file, so a repository cannot turn
+    /// it off.
+    #[serde(default)]
+    pub ts_sandbox: bool,
+
+    /// Hex BLAKE3 hashes of the only TypeScript configs allowed to run;
+    /// empty allows all.
+    ///
+    /// Only read from the global config file, so a repository cannot
+    /// allow its own configs.
+    #[serde(default)]
+    pub ts_config_hashes: Vec<String>,
+}
+
+/// A size in bytes, written in config files as a number of bytes or as a
+/// string with a unit (`"500MB"`, `"1.5 GiB"`).
+///
+/// Units are powers of 1024 (`KB` and `KiB` are the same), matching how
+/// sizes are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "RawByteSize", into = "u64")]
+pub struct ByteSize(pub u64);
+
+/// How a [`ByteSize`] may be written in a config file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawByteSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<RawByteSize> for ByteSize {
+    type Error = String;
+
+    fn try_from(raw: RawByteSize) -> Result<Self, Self::Error> {
+        match raw {
+            RawByteSize::Bytes(bytes) => Ok(Self(bytes)),
+            RawByteSize::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid size '{s}': expected a number with a unit like 5GB"))?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            "T" | "TB" | "TIB" => 1 << 40,
+            other => return Err(format!("invalid size unit '{other}' in '{s}'")),
+        };
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+/// Settings for applying configs to a worktree.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SetupConfig {
+    /// Ask for confirmation (or require `--yes`) before copying more than
+    /// this many bytes.
+    #[serde(default)]
+    pub confirm_above: Option<ByteSize>,
+
+    /// Give up on a single copy or symlink operation after this many
+    /// seconds, mark it failed, and continue with the rest.
+    #[serde(default)]
+    pub operation_timeout: Option<u64>,
+
+    /// Warn when an operation makes no progress for this many seconds
+    /// (30 when unset, 0 turns the warning off).
+    #[serde(default)]
+    pub stall_warning: Option<u64>,
+
+    /// Copy the `core.hooksPath` directory into new worktrees when it is
+    /// relative and missing there (e.g. husky's generated `.husky/_`).
+    #[serde(default)]
+    pub install_hooks: Option<bool>,
+
+    /// For configs without `postSetup`, run the install command matching
+    /// their lockfiles. When unset, the commands are only offered in
+    /// interactive runs; `false` turns detection off.
+    #[serde(default)]
+    pub post_setup_auto: Option<bool>,
+
+    /// After copying a directory, enumerate its source again and copy
+    /// files added while the copy ran (e.g. by a running build).
+    #[serde(default)]
+    pub rescan_copies: Option<bool>,
+
+    /// Branch names or glob patterns (e.g. `release/*`) whose worktrees
+    /// setup never overwrites files in.
+    #[serde(default)]
+    pub protected_branches: Option<Vec<String>>,
+
+    /// Add the untracked files and symlinks setup creates to the
+    /// repository's `.git/info/exclude`, keeping `git status` clean
+    /// without editing the shared `.gitignore`.
+    #[serde(default)]
+    pub auto_exclude: Option<bool>,
+
+    /// Limit `copyUnstaged` to files under the directories of the configs
+    /// enabling it, instead of the whole repository.
+    #[serde(default)]
+    pub unstaged_scope: Option<UnstagedScope>,
+}
+
+impl SetupConfig {
+    /// The `protected_branches` pattern matching `branch`, if any.
+    ///
+    /// `*` does not match `/`, so `release/*` protects `release/1.0` but
+    /// not `release/1.0/hotfix`. Invalid patterns are ignored.
+    #[must_use]
+    pub fn protected_branch_pattern(&self, branch: &str) -> Option<&str> {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        self.protected_branches
+            .iter()
+            .flatten()
+            .find(|pattern| {
+                glob::Pattern::new(pattern).is_ok_and(|p| p.matches_with(branch, options))
+            })
+            .map(String::as_str)
+    }
 }
 
 /// Global configuration for worktree-setup.
@@ -67,13 +302,25 @@ pub struct SecurityConfig {
 /// If neither exists, all fields use their `Default` values.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GlobalConfig {
+    /// Settings for creating worktrees.
+    #[serde(default)]
+    pub create: CreateConfig,
+
     /// Settings for the `remove` subcommand.
     #[serde(default)]
     pub remove: RemoveConfig,
 
+    /// Policy for the `gc` subcommand.
+    #[serde(default)]
+    pub gc: GcConfig,
+
     /// Security-related settings.
     #[serde(default)]
     pub security: SecurityConfig,
+
+    /// Settings for applying configs.
+    #[serde(default)]
+    pub setup: SetupConfig,
 }
 
 impl GlobalConfig {
@@ -81,9 +328,59 @@ impl GlobalConfig {
     ///
     /// Values from `other` override values in `self`. This is used to
     /// layer repo-level config on top of global config.
-    pub const fn merge(&mut self, other: &Self) {
+    pub fn merge(&mut self, other: &Self) {
+        if other.create.worktree_name_pattern.is_some() {
+            self.create
+                .worktree_name_pattern
+                .clone_from(&other.create.worktree_name_pattern);
+        }
+        if other.create.target_base.is_some() {
+            self.create
+                .target_base
+                .clone_from(&other.create.target_base);
+        }
         self.remove.branch_delete = other.remove.branch_delete;
+        if other.gc.idle_days.is_some() {
+            self.gc.idle_days = other.gc.idle_days;
+        }
+        if other.gc.require_merged.is_some() {
+            self.gc.require_merged = other.gc.require_merged;
+        }
+        if other.gc.base.is_some() {
+            self.gc.base.clone_from(&other.gc.base);
+        }
         self.security.allow_path_escape = other.security.allow_path_escape;
+        // `ts_sandbox` and `ts_config_hashes` guard against code in the
+        // repository, so repo-level files cannot change them.
+        if other.setup.confirm_above.is_some() {
+            self.setup.confirm_above = other.setup.confirm_above;
+        }
+        if other.setup.operation_timeout.is_some() {
+            self.setup.operation_timeout = other.setup.operation_timeout;
+        }
+        if other.setup.stall_warning.is_some() {
+            self.setup.stall_warning = other.setup.stall_warning;
+        }
+        if other.setup.install_hooks.is_some() {
+            self.setup.install_hooks = other.setup.install_hooks;
+        }
+        if other.setup.post_setup_auto.is_some() {
+            self.setup.post_setup_auto = other.setup.post_setup_auto;
+        }
+        if other.setup.rescan_copies.is_some() {
+            self.setup.rescan_copies = other.setup.rescan_copies;
+        }
+        if other.setup.auto_exclude.is_some() {
+            self.setup.auto_exclude = other.setup.auto_exclude;
+        }
+        if other.setup.unstaged_scope.is_some() {
+            self.setup.unstaged_scope = other.setup.unstaged_scope;
+        }
+        if other.setup.protected_branches.is_some() {
+            self.setup
+                .protected_branches
+                .clone_from(&other.setup.protected_branches);
+        }
     }
 }
 
@@ -153,14 +450,12 @@ fn load_config_file(path: &Path) -> Result<Option<GlobalConfig>, ConfigError> {
 ///
 /// * If a config file exists but cannot be read or parsed
 pub fn load_global_config(repo_root: Option<&Path>) -> Result<GlobalConfig, ConfigError> {
-    let mut config = GlobalConfig::default();
-
-    // Layer 1: global config file
-    if let Some(global_path) = global_config_path()
-        && let Some(global) = load_config_file(&global_path)?
-    {
-        config.merge(&global);
-    }
+    // Layer 1: global config file, the only source of the settings
+    // `merge` leaves alone
+    let mut config = match global_config_path() {
+        Some(global_path) => load_config_file(&global_path)?.unwrap_or_default(),
+        None => GlobalConfig::default(),
+    };
 
     // Layer 2: repo-level config file (overrides global)
     if let Some(root) = repo_root {
@@ -188,6 +483,27 @@ mod tests {
         assert_eq!(config.remove.branch_delete, BranchDeletePolicy::Ask);
     }
 
+    #[test]
+    fn gc_policy_defaults_and_merge() {
+        let mut config = GlobalConfig::default();
+        assert_eq!(config.gc.idle_days(), DEFAULT_GC_IDLE_DAYS);
+        assert!(config.gc.require_merged());
+        assert_eq!(config.gc.base, None);
+
+        let repo: GlobalConfig = toml::from_str(
+            r#"
+[gc]
+idle_days = 30
+base = "develop"
+"#,
+        )
+        .unwrap();
+        config.merge(&repo);
+        assert_eq!(config.gc.idle_days(), 30);
+        assert!(config.gc.require_merged());
+        assert_eq!(config.gc.base.as_deref(), Some("develop"));
+    }
+
     #[test]
     fn parse_branch_delete_ask() {
         let toml_str = r#"
@@ -244,6 +560,65 @@ branch_delete = "NEVER"
         assert_eq!(base.remove.branch_delete, BranchDeletePolicy::Always);
     }
 
+    #[test]
+    fn parse_confirm_above_sizes() {
+        let config: GlobalConfig = toml::from_str("[setup]\nconfirm_above = \"5GB\"\n").unwrap();
+        assert_eq!(config.setup.confirm_above, Some(ByteSize(5 << 30)));
+
+        assert_eq!("1.5 MiB".parse(), Ok(ByteSize(3 << 19)));
+        assert_eq!("512".parse(), Ok(ByteSize(512)));
+        assert!("5 parsecs".parse::<ByteSize>().is_err());
+
+        let config: GlobalConfig = toml::from_str("[setup]\nconfirm_above = 1024\n").unwrap();
+        assert_eq!(config.setup.confirm_above, Some(ByteSize(1024)));
+    }
+
+    #[test]
+    fn merge_keeps_confirm_above_unless_overridden() {
+        let mut base = GlobalConfig {
+            setup: SetupConfig {
+                confirm_above: Some(ByteSize(10)),
+                operation_timeout: None,
+                stall_warning: None,
+                install_hooks: None,
+                post_setup_auto: None,
+                rescan_copies: None,
+                protected_branches: None,
+                auto_exclude: None,
+                unstaged_scope: None,
+            },
+            ..Default::default()
+        };
+        base.merge(&GlobalConfig::default());
+        assert_eq!(base.setup.confirm_above, Some(ByteSize(10)));
+    }
+
+    #[test]
+    fn merge_overrides_operation_timeout() {
+        let mut base: GlobalConfig = toml::from_str("[setup]\noperation_timeout = 60\n").unwrap();
+        let repo: GlobalConfig = toml::from_str("[setup]\noperation_timeout = 5\n").unwrap();
+        base.merge(&repo);
+        assert_eq!(base.setup.operation_timeout, Some(5));
+        base.merge(&GlobalConfig::default());
+        assert_eq!(base.setup.operation_timeout, Some(5));
+    }
+
+    #[test]
+    fn merge_overrides_worktree_name_pattern() {
+        let mut base: GlobalConfig =
+            toml::from_str("[create]\nworktree_name_pattern = \"^[a-z]+$\"\n").unwrap();
+        base.merge(&GlobalConfig::default());
+        assert_eq!(
+            base.create.worktree_name_pattern.as_deref(),
+            Some("^[a-z]+$")
+        );
+
+        let repo: GlobalConfig =
+            toml::from_str("[create]\nworktree_name_pattern = \"^wt-\"\n").unwrap();
+        base.merge(&repo);
+        assert_eq!(base.create.worktree_name_pattern.as_deref(), Some("^wt-"));
+    }
+
     #[test]
     fn load_missing_file_returns_none() {
         let result = load_config_file(Path::new("/nonexistent/path/config.toml")).unwrap();
@@ -334,16 +709,56 @@ allow_path_escape = true
         assert!(config.security.allow_path_escape);
     }
 
+    #[test]
+    fn parse_security_ts_guards() {
+        let toml_str = r#"
+[security]
+ts_sandbox = true
+ts_config_hashes = ["abc123"]
+"#;
+        let config: GlobalConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.security.ts_sandbox);
+        assert_eq!(config.security.ts_config_hashes, ["abc123"]);
+    }
+
     #[test]
     fn merge_overrides_security() {
         let mut base = GlobalConfig::default();
         let overlay = GlobalConfig {
             security: SecurityConfig {
                 allow_path_escape: true,
+                ts_sandbox: true,
+                ts_config_hashes: vec!["00".to_string()],
             },
             ..Default::default()
         };
         base.merge(&overlay);
         assert!(base.security.allow_path_escape);
+        // Repo-level files cannot change the TypeScript guards.
+        assert!(!base.security.ts_sandbox);
+        assert!(base.security.ts_config_hashes.is_empty());
+    }
+
+    #[test]
+    fn protected_branch_patterns() {
+        let toml_str = r#"
+[setup]
+protected_branches = ["main", "release/*"]
+"#;
+        let config: GlobalConfig = toml::from_str(toml_str).unwrap();
+        let setup = &config.setup;
+        assert_eq!(setup.protected_branch_pattern("main"), Some("main"));
+        assert_eq!(
+            setup.protected_branch_pattern("release/1.0"),
+            Some("release/*")
+        );
+        assert_eq!(setup.protected_branch_pattern("release/1.0/hotfix"), None);
+        assert_eq!(setup.protected_branch_pattern("feature/main"), None);
+        assert_eq!(
+            GlobalConfig::default()
+                .setup
+                .protected_branch_pattern("main"),
+            None
+        );
     }
 }
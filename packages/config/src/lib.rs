@@ -1,12 +1,19 @@
 //! Configuration loading for worktree-setup.
 //!
 //! This crate provides configuration types and loading functionality for the worktree-setup CLI.
-//! It supports both TOML and TypeScript configuration files.
+//! It supports TOML, YAML, TypeScript, and JavaScript configuration files,
+//! and configs embedded in `package.json`.
 //!
 //! # Supported Config Formats
 //!
 //! * TOML (`worktree.config.toml`) - Native Rust parsing
+//! * YAML (`worktree.config.yaml` or `.yml`) - Native Rust parsing
 //! * TypeScript (`worktree.config.ts`) - Evaluated via bun subprocess
+//! * JavaScript (`worktree.config.{js,mjs,cjs}`) - Evaluated like TypeScript
+//! * `package.json` - The `"worktreeSetup"` field, parsed natively
+//!
+//! [`load_configs_parallel`] loads many configs at once, evaluating
+//! TypeScript configs concurrently.
 //!
 //! # Example
 //!
@@ -26,25 +33,59 @@
 
 mod discovery;
 mod error;
+mod extends;
 mod global;
+mod naming;
+mod package_json;
+mod pathspec;
 mod profiles;
+mod root_defaults;
+mod schema;
+mod selection;
 mod toml_loader;
 mod ts_loader;
+mod ts_trust;
 mod types;
+mod typescript;
+mod workspaces;
+mod yaml_loader;
 
-pub use discovery::{discover_configs, get_config_display_name};
+pub use discovery::{
+    DiscoveryOptions, discover_configs, discover_configs_with_options, get_config_display_name,
+};
 pub use error::ConfigError;
+pub use extends::{RemoteOptions, remote_options, resolve_extends, set_remote_options};
 pub use global::{
-    BranchDeletePolicy, GlobalConfig, RemoveConfig, SecurityConfig, global_config_path,
+    BranchDeletePolicy, ByteSize, CreateConfig, DEFAULT_GC_IDLE_DAYS, GcConfig, GlobalConfig,
+    RemoveConfig, SecurityConfig, SetupConfig, UnstagedScope, global_config_path,
     load_global_config, repo_config_path,
 };
-pub use profiles::resolve_profiles;
+pub use naming::{check_worktree_name, sanitize_worktree_name};
+pub use package_json::{
+    PACKAGE_JSON, PACKAGE_JSON_FIELD, has_package_json_config, is_package_json,
+    load_package_json_config,
+};
+pub use pathspec::{PathAnchor, PathSpec};
+pub use profiles::{apply_profile_excludes, resolve_profiles};
+pub use root_defaults::{ROOT_CONFIG_PATHS, apply_root_defaults, is_root_config, root_defaults};
+pub use schema::{UnknownKey, set_strict_schema, strict_schema};
+pub use selection::{select_configs_by_patterns, suggest_configs};
 pub use toml_loader::load_toml_config;
-pub use ts_loader::load_ts_config;
+pub use ts_loader::{
+    SCRIPT_CONFIG_EXTENSIONS, is_script_config, load_ts_config, load_ts_configs_batched,
+};
+pub use ts_trust::{
+    TsOptions, check_ts_allowlist, record_ts_configs, set_ts_options, ts_config_hash, ts_options,
+};
 pub use types::{
-    Config, CreationMethod, LoadedConfig, PostSetupKeyword, PostSetupMode, ProfileDefaults,
-    ProfileDefinition, ResolvedProfile, TemplateMapping,
+    Condition, Config, ConfigDefaults, CreationMethod, EntryHooks, ExtendsEntry, FileMode,
+    LoadedConfig, OperationEntry, PostSetupKeyword, PostSetupMode, ProfileDefaults,
+    ProfileDefinition, RefCopyEntry, ResolvedProfile, TemplateEngine, TemplateMapping,
+};
+pub use workspaces::{
+    Workspace, WorkspaceRepo, WorkspacesFile, expand_home, load_workspaces, workspaces_path,
 };
+pub use yaml_loader::load_yaml_config;
 
 use std::path::Path;
 
@@ -60,14 +101,28 @@ use std::path::Path;
 /// * If the file extension is not supported
 /// * If the file cannot be read
 /// * If the file cannot be parsed
+/// * If a config named in its `extends` cannot be loaded
 pub fn load_config(path: &Path, repo_root: &Path) -> Result<LoadedConfig, ConfigError> {
     let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
     let config = match extension {
+        _ if is_package_json(path) => load_package_json_config(path)?,
         "toml" => load_toml_config(path)?,
-        "ts" => load_ts_config(path)?,
+        "ts" | "js" | "mjs" | "cjs" => load_ts_config(path)?,
+        "yaml" | "yml" => load_yaml_config(path)?,
         _ => return Err(ConfigError::UnsupportedFormat(extension.to_string())),
     };
+    loaded_config(config, path, repo_root)
+}
+
+/// Resolve the `extends` of a config read from `path` and wrap it in a
+/// [`LoadedConfig`].
+fn loaded_config(
+    config: types::Config,
+    path: &Path,
+    repo_root: &Path,
+) -> Result<LoadedConfig, ConfigError> {
+    let config = resolve_extends(config, path, remote_options())?;
 
     let config_dir = path
         .parent()
@@ -86,3 +141,130 @@ pub fn load_config(path: &Path, repo_root: &Path) -> Result<LoadedConfig, Config
         relative_path,
     })
 }
+
+/// Most configs [`load_configs_parallel`] loads at the same time.
+pub const MAX_PARALLEL_CONFIG_LOADS: usize = 8;
+
+/// Load several configuration files concurrently (see [`load_config`]).
+///
+/// Configs are loaded on up to [`MAX_PARALLEL_CONFIG_LOADS`] threads (fewer
+/// on machines with fewer cores), so a repository with many TypeScript
+/// configs does not wait for each in turn. TypeScript configs the embedded
+/// evaluator cannot handle are then evaluated together in a single `bun`
+/// or `deno` process (see [`load_ts_configs_batched`]). Results are
+/// returned in the order of `paths`.
+#[must_use]
+pub fn load_configs_parallel(
+    paths: &[std::path::PathBuf],
+    repo_root: &Path,
+) -> Vec<Result<LoadedConfig, ConfigError>> {
+    let mut results = parallel_map(paths, |path| {
+        if !is_script_config(path) {
+            return Some(load_config(path, repo_root));
+        }
+        if let Err(e) = check_ts_allowlist(path, &ts_options().allowed_hashes) {
+            return Some(Err(e));
+        }
+        match ts_loader::load_ts_embedded(path) {
+            Ok(config) => Some(loaded_config(config, path, repo_root)),
+            Err(e) => {
+                log::debug!("Embedded TS eval of {} failed: {e}", path.display());
+                None
+            }
+        }
+    });
+
+    let pending: Vec<&Path> = paths
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_none())
+        .map(|(path, _)| path.as_path())
+        .collect();
+    let mut evaluated = load_ts_configs_batched(&pending).into_iter();
+    for (path, result) in paths.iter().zip(&mut results) {
+        if result.is_none() {
+            let config = evaluated.next().unwrap_or(Err(ConfigError::NoJsRuntime));
+            *result = Some(config.and_then(|config| loaded_config(config, path, repo_root)));
+        }
+    }
+    results.into_iter().flatten().collect()
+}
+
+/// Apply `f` to every path on up to [`MAX_PARALLEL_CONFIG_LOADS`] threads,
+/// keeping the order of `paths`.
+fn parallel_map<T, F>(paths: &[std::path::PathBuf], f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let threads = std::thread::available_parallelism()
+        .map_or(1, std::num::NonZeroUsize::get)
+        .min(MAX_PARALLEL_CONFIG_LOADS)
+        .min(paths.len());
+    if threads <= 1 {
+        return paths.iter().map(|path| f(path)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new(paths.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+                    let result = f(path);
+                    if let Ok(mut results) = results.lock() {
+                        results[index] = Some(result);
+                    }
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .into_iter()
+        .zip(paths)
+        .map(|(result, path)| result.unwrap_or_else(|| f(path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_configs_parallel_keeps_order() {
+        let dir = TempDir::new().unwrap();
+        let mut paths = Vec::new();
+        for index in 0..12 {
+            let config_dir = dir.path().join(format!("app{index}"));
+            std::fs::create_dir_all(&config_dir).unwrap();
+            let path = config_dir.join("worktree.config.toml");
+            std::fs::write(&path, format!("description = \"app {index}\"\n")).unwrap();
+            paths.push(path);
+        }
+        paths.push(dir.path().join("missing/worktree.config.toml"));
+
+        let loaded = load_configs_parallel(&paths, dir.path());
+
+        assert_eq!(loaded.len(), 13);
+        for (index, config) in loaded[..12].iter().enumerate() {
+            let config = config.as_ref().unwrap();
+            assert_eq!(config.config.description, format!("app {index}"));
+            assert_eq!(
+                config.relative_path,
+                format!("app{index}/worktree.config.toml")
+            );
+        }
+        assert!(loaded[12].is_err());
+    }
+}
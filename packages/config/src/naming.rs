@@ -0,0 +1,103 @@
+//! Worktree naming conventions.
+//!
+//! When `create.worktree_name_pattern` is set, every worktree the tool
+//! creates must have a directory name matching it. Rejected names come
+//! with a sanitized suggestion when one can be derived.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use regex::Regex;
+
+use crate::ConfigError;
+
+/// Check that `name` matches the worktree name `pattern`.
+///
+/// # Errors
+///
+/// * If `pattern` is not a valid regular expression
+/// * If `name` does not match; the error carries a sanitized suggestion
+pub fn check_worktree_name(name: &str, pattern: &str) -> Result<(), ConfigError> {
+    let regex = compile(pattern)?;
+    if regex.is_match(name) {
+        return Ok(());
+    }
+
+    Err(ConfigError::WorktreeNameMismatch {
+        name: name.to_string(),
+        pattern: pattern.to_string(),
+        suggestion: sanitize(name, &regex),
+    })
+}
+
+/// Derive a name matching `pattern` from `name`, if possible.
+///
+/// Tries, in order: lowercasing, replacing every run of characters other
+/// than ASCII letters and digits with `-`, and both.
+///
+/// # Errors
+///
+/// * If `pattern` is not a valid regular expression
+pub fn sanitize_worktree_name(name: &str, pattern: &str) -> Result<Option<String>, ConfigError> {
+    Ok(sanitize(name, &compile(pattern)?))
+}
+
+fn compile(pattern: &str) -> Result<Regex, ConfigError> {
+    Regex::new(pattern).map_err(|e| ConfigError::InvalidNamePattern {
+        pattern: pattern.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn sanitize(name: &str, regex: &Regex) -> Option<String> {
+    let lower = name.to_lowercase();
+    [lower.clone(), slugify(name), slugify(&lower)]
+        .into_iter()
+        .find(|candidate| !candidate.is_empty() && candidate != name && regex.is_match(candidate))
+}
+
+/// Replace runs of characters other than ASCII letters and digits with a
+/// single `-`, trimming it from both ends.
+fn slugify(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERN: &str = "^[a-z0-9-]+$";
+
+    #[test]
+    fn test_matching_name_passes() {
+        assert!(check_worktree_name("feature-login", PATTERN).is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_suggests_sanitized_name() {
+        let err = check_worktree_name("Feature/Login_Page", PATTERN).unwrap_err();
+        assert!(matches!(
+            &err,
+            ConfigError::WorktreeNameMismatch { suggestion: Some(s), .. } if s == "feature-login-page"
+        ));
+        assert!(err.to_string().ends_with("Try 'feature-login-page'."));
+
+        assert_eq!(
+            sanitize_worktree_name("FIX-1", PATTERN).unwrap(),
+            Some("fix-1".to_string())
+        );
+        assert_eq!(sanitize_worktree_name("ünïcode", "^[a-z]+$").unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_reported() {
+        assert!(matches!(
+            check_worktree_name("x", "^[a-z"),
+            Err(ConfigError::InvalidNamePattern { .. })
+        ));
+    }
+}
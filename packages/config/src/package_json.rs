@@ -0,0 +1,144 @@
+//! Configs embedded in `package.json`.
+//!
+//! A `package.json` with a `"worktreeSetup"` field is a config like any
+//! other, so JavaScript packages need no separate config file. The field
+//! holds the same camelCase fields as a JSON-shaped TOML config.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::ConfigError;
+use crate::schema::check_unknown_keys;
+use crate::types::Config;
+
+/// File name of package manifests that can embed a config.
+pub const PACKAGE_JSON: &str = "package.json";
+
+/// Field of `package.json` holding the config.
+pub const PACKAGE_JSON_FIELD: &str = "worktreeSetup";
+
+/// The part of `package.json` read for configs.
+#[derive(Deserialize)]
+struct PackageJson {
+    #[serde(rename = "worktreeSetup")]
+    worktree_setup: Option<serde_json::Value>,
+}
+
+/// Whether `path` names a `package.json` file.
+#[must_use]
+pub fn is_package_json(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == PACKAGE_JSON)
+}
+
+/// Whether the `package.json` at `path` has a `"worktreeSetup"` field.
+///
+/// Files that cannot be read or are not JSON objects have none.
+#[must_use]
+pub fn has_package_json_config(path: &Path) -> bool {
+    let Ok(content) = fs::read_to_string(path) else {
+        return false;
+    };
+    // Most manifests never mention the field, so skip parsing them.
+    if !content.contains(PACKAGE_JSON_FIELD) {
+        return false;
+    }
+    serde_json::from_str::<serde_json::Value>(&content)
+        .is_ok_and(|value| value.get(PACKAGE_JSON_FIELD).is_some())
+}
+
+/// Load the config embedded in a `package.json` file.
+///
+/// A file without a `"worktreeSetup"` field is an empty config.
+///
+/// # Arguments
+///
+/// * `path` - Path to the `package.json` file
+///
+/// # Errors
+///
+/// * If the file cannot be read
+/// * If the file is not JSON, or its `"worktreeSetup"` field is not a
+///   valid config
+pub fn load_package_json_config(path: &Path) -> Result<Config, ConfigError> {
+    log::debug!("Loading package.json config from {}", path.display());
+
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::ReadError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    let parse_error = |e| ConfigError::PackageJsonParseError {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    let package: PackageJson = serde_json::from_str(&content).map_err(parse_error)?;
+    let raw = package
+        .worktree_setup
+        .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
+    let mut config = Config::deserialize(&raw).map_err(parse_error)?;
+    check_unknown_keys(path, &raw, &mut config, Some(&content))?;
+
+    log::debug!("Loaded config: {:?}", config.description);
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_package_json_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(PACKAGE_JSON);
+        fs::write(
+            &path,
+            r#"{
+  "name": "web",
+  "scripts": { "dev": "vite" },
+  "worktreeSetup": {
+    "description": "Web app",
+    "symlinks": ["node_modules"],
+    "copy": [".env.local", { "path": "fixtures", "target": "db/seed" }],
+    "postSetup": ["npm install"]
+  }
+}"#,
+        )
+        .unwrap();
+
+        assert!(is_package_json(&path));
+        assert!(has_package_json_config(&path));
+        let config = load_package_json_config(&path).unwrap();
+        assert_eq!(config.description, "Web app");
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+        assert_eq!(config.copy.len(), 2);
+        assert_eq!(config.copy[1].target(), Some("db/seed"));
+        assert_eq!(config.post_setup, vec!["npm install"]);
+    }
+
+    #[test]
+    fn test_package_json_without_config() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(PACKAGE_JSON);
+
+        // Mentioning the field elsewhere does not make a config.
+        fs::write(&path, r#"{"name": "web", "keywords": ["worktreeSetup"]}"#).unwrap();
+        assert!(!has_package_json_config(&path));
+        assert!(load_package_json_config(&path).unwrap().copy.is_empty());
+
+        fs::write(&path, r#"{"worktreeSetup": {"copy": 3}}"#).unwrap();
+        assert!(has_package_json_config(&path));
+        let err = load_package_json_config(&path).unwrap_err();
+        assert_eq!(err.code(), "WTS-CFG-021");
+
+        fs::write(&path, "{ not json worktreeSetup").unwrap();
+        assert!(!has_package_json_config(&path));
+    }
+}
@@ -0,0 +1,184 @@
+//! Paths and glob patterns written in configs.
+//!
+//! Every path a config names (entries, templates, `clean` patterns, profile
+//! `configs` patterns) follows the same rules, implemented here so each
+//! consumer resolves them identically:
+//!
+//! * A leading `/` makes the path relative to the repository root (the
+//!   root of the submodule, for configs inside one); repeated leading
+//!   slashes count as one.
+//! * Any other path is relative to the config file's directory.
+//! * Leading `./` segments and trailing slashes are dropped, so `./data/`
+//!   and `data` name the same path. `.`, `/`, and the empty string name
+//!   the base directory itself.
+//! * `..` segments are kept as written, so containment checks still see
+//!   paths that leave the base directory.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::{Path, PathBuf};
+
+/// What a config path is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathAnchor {
+    /// The directory containing the config file.
+    ConfigDir,
+    /// The repository root (written with a leading `/`).
+    RepoRoot,
+}
+
+/// A config path split into its anchor and the normalized path below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathSpec<'a> {
+    anchor: PathAnchor,
+    path: &'a str,
+}
+
+impl<'a> PathSpec<'a> {
+    /// Parse a path or glob pattern as written in a config.
+    #[must_use]
+    pub fn parse(spec: &'a str) -> Self {
+        let (anchor, mut path) = if spec.starts_with('/') {
+            (PathAnchor::RepoRoot, spec.trim_start_matches('/'))
+        } else {
+            (PathAnchor::ConfigDir, spec)
+        };
+        loop {
+            if path == "." {
+                path = "";
+            }
+            match path.strip_prefix("./") {
+                Some(rest) => path = rest.trim_start_matches('/'),
+                None => break,
+            }
+        }
+        Self {
+            anchor,
+            path: path.trim_end_matches('/'),
+        }
+    }
+
+    /// What the path is relative to.
+    #[must_use]
+    pub const fn anchor(&self) -> PathAnchor {
+        self.anchor
+    }
+
+    /// The path relative to its anchor, empty for the anchor itself.
+    #[must_use]
+    pub const fn path(&self) -> &'a str {
+        self.path
+    }
+
+    /// The anchor directory, relative to the worktree.
+    ///
+    /// `root_relative_dir` is the repository root of the config (empty
+    /// unless the config is inside a submodule) and `config_relative_dir`
+    /// the config's directory, both relative to the worktree.
+    #[must_use]
+    pub fn base_dir(&self, root_relative_dir: &Path, config_relative_dir: &Path) -> PathBuf {
+        match self.anchor {
+            PathAnchor::ConfigDir => config_relative_dir.to_path_buf(),
+            PathAnchor::RepoRoot => root_relative_dir.to_path_buf(),
+        }
+    }
+
+    /// The path relative to the worktree (see [`Self::base_dir`]).
+    #[must_use]
+    pub fn worktree_relative(
+        &self,
+        root_relative_dir: &Path,
+        config_relative_dir: &Path,
+    ) -> PathBuf {
+        let base = self.base_dir(root_relative_dir, config_relative_dir);
+        if self.path.is_empty() {
+            base
+        } else {
+            base.join(self.path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(spec: &str) -> (PathAnchor, &str, PathBuf) {
+        let parsed = PathSpec::parse(spec);
+        (
+            parsed.anchor(),
+            parsed.path(),
+            parsed.worktree_relative(Path::new("libs/sub"), Path::new("libs/sub/app")),
+        )
+    }
+
+    #[test]
+    fn test_parse_config_relative_paths() {
+        use PathAnchor::ConfigDir;
+        let app = |p: &str| Path::new("libs/sub/app").join(p);
+
+        assert_eq!(resolve("data"), (ConfigDir, "data", app("data")));
+        assert_eq!(
+            resolve("data/x.env"),
+            (ConfigDir, "data/x.env", app("data/x.env"))
+        );
+        assert_eq!(resolve("./data"), (ConfigDir, "data", app("data")));
+        assert_eq!(resolve("././data"), (ConfigDir, "data", app("data")));
+        assert_eq!(resolve(".//data"), (ConfigDir, "data", app("data")));
+        assert_eq!(resolve("data/"), (ConfigDir, "data", app("data")));
+        assert_eq!(resolve("./data//"), (ConfigDir, "data", app("data")));
+        assert_eq!(resolve(".env"), (ConfigDir, ".env", app(".env")));
+        assert_eq!(resolve("..env"), (ConfigDir, "..env", app("..env")));
+        assert_eq!(
+            resolve("../shared"),
+            (ConfigDir, "../shared", app("../shared"))
+        );
+        assert_eq!(resolve("a/../b"), (ConfigDir, "a/../b", app("a/../b")));
+        assert_eq!(
+            resolve("**/*.env"),
+            (ConfigDir, "**/*.env", app("**/*.env"))
+        );
+        assert_eq!(resolve("./"), (ConfigDir, "", app("")));
+        assert_eq!(resolve("."), (ConfigDir, "", app("")));
+        assert_eq!(resolve(""), (ConfigDir, "", app("")));
+    }
+
+    #[test]
+    fn test_parse_root_relative_paths() {
+        use PathAnchor::RepoRoot;
+        let root = |p: &str| Path::new("libs/sub").join(p);
+
+        assert_eq!(resolve("/.nix"), (RepoRoot, ".nix", root(".nix")));
+        assert_eq!(resolve("//.nix"), (RepoRoot, ".nix", root(".nix")));
+        assert_eq!(resolve("/./.nix"), (RepoRoot, ".nix", root(".nix")));
+        assert_eq!(resolve("/tools/"), (RepoRoot, "tools", root("tools")));
+        assert_eq!(
+            resolve("/../outside"),
+            (RepoRoot, "../outside", root("../outside"))
+        );
+        assert_eq!(resolve("/*.toml"), (RepoRoot, "*.toml", root("*.toml")));
+        assert_eq!(resolve("/"), (RepoRoot, "", root("")));
+        assert_eq!(resolve("/."), (RepoRoot, "", root("")));
+    }
+
+    #[test]
+    fn test_worktree_relative_without_submodule() {
+        let spec = PathSpec::parse("/.envrc");
+        assert_eq!(
+            spec.worktree_relative(Path::new(""), Path::new("apps/web")),
+            Path::new(".envrc")
+        );
+        assert_eq!(
+            spec.base_dir(Path::new(""), Path::new("apps/web")),
+            Path::new("")
+        );
+
+        let spec = PathSpec::parse("data");
+        assert_eq!(
+            spec.worktree_relative(Path::new(""), Path::new("")),
+            Path::new("data")
+        );
+    }
+}
@@ -11,6 +11,7 @@ use std::collections::BTreeSet;
 use std::path::Path;
 
 use crate::error::ConfigError;
+use crate::pathspec::{PathAnchor, PathSpec};
 use crate::types::{LoadedConfig, ResolvedProfile};
 
 /// Resolve one or more profile names into a single `ResolvedProfile`.
@@ -100,6 +101,49 @@ pub fn resolve_profiles(
     Ok(resolved)
 }
 
+/// Drop the entries that the selected profiles exclude from the configs
+/// declaring them.
+///
+/// Each profile's `exclude` patterns apply only to the config they are
+/// declared in, and cover its `symlinks`, `copy`, `overwrite`, `copyGlob`,
+/// `templates`, and `copyFromRef` entries. A pattern matches an entry whose
+/// path it matches as a glob, or an entry under the directory it names.
+/// Invalid patterns are ignored with a warning.
+pub fn apply_profile_excludes(configs: &mut [LoadedConfig], profile_names: &[String]) {
+    for loaded in configs {
+        let patterns: Vec<glob::Pattern> = profile_names
+            .iter()
+            .filter_map(|name| loaded.config.profiles.get(name))
+            .flat_map(|def| &def.exclude)
+            .filter_map(|pattern| {
+                glob::Pattern::new(pattern.trim_end_matches('/'))
+                    .inspect_err(|_| log::warn!("Invalid glob pattern in exclude: {pattern}"))
+                    .ok()
+            })
+            .collect();
+        if patterns.is_empty() {
+            continue;
+        }
+
+        let excluded = |path: &str| {
+            let path = path.trim_start_matches("./");
+            patterns.iter().any(|pat| {
+                pat.matches(path)
+                    || path
+                        .strip_prefix(pat.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+        };
+        let config = &mut loaded.config;
+        config.symlinks.retain(|entry| !excluded(entry.path()));
+        config.copy.retain(|entry| !excluded(entry.path()));
+        config.overwrite.retain(|entry| !excluded(entry.path()));
+        config.copy_glob.retain(|entry| !excluded(entry.path()));
+        config.templates.retain(|entry| !excluded(&entry.source));
+        config.copy_from_ref.retain(|entry| !excluded(&entry.path));
+    }
+}
+
 /// Match a config pattern against the discovered config list.
 ///
 /// * No leading `/` — relative to `declaring_config`'s directory
@@ -110,9 +154,9 @@ fn match_config_pattern(
     all_configs: &[LoadedConfig],
     repo_root: &Path,
 ) -> Vec<usize> {
-    let (glob_pattern, use_repo_root) = pattern
-        .strip_prefix('/')
-        .map_or((pattern, false), |stripped| (stripped, true));
+    let spec = PathSpec::parse(pattern);
+    let glob_pattern = spec.path();
+    let use_repo_root = spec.anchor() == PathAnchor::RepoRoot;
 
     let Ok(pat) = glob::Pattern::new(glob_pattern) else {
         log::warn!("Invalid glob pattern in configs: {pattern}");
@@ -238,6 +282,44 @@ mod tests {
         PathBuf::from("/repo")
     }
 
+    // ─── Excludes ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_apply_profile_excludes() {
+        let mut minimal = ProfileDefinition {
+            exclude: vec!["fixtures".to_string(), "*.db".to_string()],
+            ..Default::default()
+        };
+        let mut configs = vec![
+            make_loaded_config_with_profiles(
+                "worktree.config.toml",
+                "root",
+                BTreeMap::from([("minimal".to_string(), minimal.clone())]),
+            ),
+            make_loaded_config("apps/web/worktree.config.toml", "web"),
+        ];
+        for loaded in &mut configs {
+            let config = &mut loaded.config;
+            config.copy = vec![".env".into(), "fixtures/db".into(), "test.db".into()];
+            config.symlinks = vec!["fixtures".into(), "node_modules".into()];
+        }
+
+        // Profiles that are not selected change nothing.
+        apply_profile_excludes(&mut configs, &["full".to_string()]);
+        assert_eq!(configs[0].config.copy.len(), 3);
+
+        apply_profile_excludes(&mut configs, &["minimal".to_string()]);
+        assert_eq!(configs[0].config.copy, vec![".env"]);
+        assert_eq!(configs[0].config.symlinks, vec!["node_modules"]);
+        // Excludes only apply to the declaring config.
+        assert_eq!(configs[1].config.copy.len(), 3);
+
+        minimal.exclude = vec!["[".to_string()];
+        configs[1].config.profiles = BTreeMap::from([("minimal".to_string(), minimal)]);
+        apply_profile_excludes(&mut configs, &["minimal".to_string()]);
+        assert_eq!(configs[1].config.copy.len(), 3);
+    }
+
     // ─── Basic resolution ──────────────────────────────────────────────
 
     #[test]
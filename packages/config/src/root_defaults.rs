@@ -0,0 +1,154 @@
+//! Repo-wide defaults from the root config.
+//!
+//! The `defaults` table of the root `worktree.config.*` (any supported
+//! format) or root `package.json` config at the repository root applies to
+//! every config, so repo-wide policies don't need repeating in each
+//! package's config.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use crate::types::{ConfigDefaults, LoadedConfig};
+
+/// Relative paths of the configs whose `defaults` table is honored, in
+/// order of preference.
+pub const ROOT_CONFIG_PATHS: [&str; 8] = [
+    "worktree.config.toml",
+    "worktree.config.ts",
+    "worktree.config.js",
+    "worktree.config.mjs",
+    "worktree.config.cjs",
+    "worktree.config.yaml",
+    "worktree.config.yml",
+    "package.json",
+];
+
+/// Whether `config` is the root config.
+#[must_use]
+pub fn is_root_config(config: &LoadedConfig) -> bool {
+    ROOT_CONFIG_PATHS.contains(&config.relative_path.as_str())
+}
+
+/// The root config's `defaults` table, if a root config was loaded and
+/// sets any default.
+///
+/// With several root configs, the first in [`ROOT_CONFIG_PATHS`] wins, so a
+/// `worktree.config.*` file takes precedence over `package.json`.
+#[must_use]
+pub fn root_defaults(configs: &[LoadedConfig]) -> Option<&ConfigDefaults> {
+    ROOT_CONFIG_PATHS
+        .iter()
+        .find_map(|path| configs.iter().find(|c| c.relative_path == *path))
+        .map(|c| &c.config.defaults)
+        .filter(|defaults| !defaults.is_empty())
+}
+
+/// Fill the per-config settings every config leaves unset from the root
+/// config's `defaults` table.
+///
+/// Covers `copyUnstaged`, `allowPathEscape`, and `strictGlobs`; a value
+/// set in a config always wins. `defaults` tables in other configs are
+/// ignored; their relative paths are returned so callers can warn.
+pub fn apply_root_defaults(configs: &mut [LoadedConfig]) -> Vec<String> {
+    let ignored: Vec<String> = configs
+        .iter()
+        .filter(|c| !is_root_config(c) && !c.config.defaults.is_empty())
+        .map(|c| c.relative_path.clone())
+        .collect();
+
+    if let Some(defaults) = root_defaults(configs).cloned() {
+        for config in configs {
+            let config = &mut config.config;
+            config.copy_unstaged = config.copy_unstaged.or(defaults.copy_unstaged);
+            config.allow_path_escape = config.allow_path_escape.or(defaults.allow_path_escape);
+            config.strict_globs = config.strict_globs.or(defaults.strict_globs);
+        }
+    }
+    ignored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Config;
+    use std::path::PathBuf;
+
+    fn loaded(relative_path: &str, config: Config) -> LoadedConfig {
+        LoadedConfig {
+            config,
+            config_path: PathBuf::from("/repo").join(relative_path),
+            config_dir: PathBuf::from("/repo"),
+            relative_path: relative_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_root_defaults_fills_unset_settings() {
+        let root = Config {
+            defaults: ConfigDefaults {
+                copy_unstaged: Some(true),
+                strict_globs: Some(true),
+                ..ConfigDefaults::default()
+            },
+            ..Config::default()
+        };
+        let opted_out = Config {
+            copy_unstaged: Some(false),
+            ..Config::default()
+        };
+        let mut configs = vec![
+            loaded("apps/web/worktree.config.toml", Config::default()),
+            loaded("apps/api/worktree.config.toml", opted_out),
+            loaded("worktree.config.toml", root),
+        ];
+
+        assert!(apply_root_defaults(&mut configs).is_empty());
+
+        assert_eq!(configs[0].config.copy_unstaged, Some(true));
+        assert_eq!(configs[0].config.strict_globs, Some(true));
+        assert_eq!(configs[0].config.allow_path_escape, None);
+        assert_eq!(configs[1].config.copy_unstaged, Some(false));
+        assert_eq!(configs[2].config.copy_unstaged, Some(true));
+    }
+
+    #[test]
+    fn test_root_defaults_ignores_nested_configs() {
+        let nested = Config {
+            defaults: ConfigDefaults {
+                copy_unstaged: Some(true),
+                ..ConfigDefaults::default()
+            },
+            ..Config::default()
+        };
+        let mut configs = vec![
+            loaded("worktree.config.toml", Config::default()),
+            loaded("apps/web/worktree.config.toml", nested),
+            loaded("apps/api/worktree.config.toml", Config::default()),
+        ];
+
+        assert!(root_defaults(&configs).is_none());
+        assert_eq!(
+            apply_root_defaults(&mut configs),
+            ["apps/web/worktree.config.toml"]
+        );
+        assert_eq!(configs[2].config.copy_unstaged, None);
+    }
+
+    #[test]
+    fn test_root_defaults_prefers_worktree_config_over_package_json() {
+        let with_defaults = |copy_unstaged| Config {
+            defaults: ConfigDefaults {
+                copy_unstaged: Some(copy_unstaged),
+                ..ConfigDefaults::default()
+            },
+            ..Config::default()
+        };
+        let mut configs = vec![loaded("package.json", with_defaults(false))];
+        assert_eq!(root_defaults(&configs).unwrap().copy_unstaged, Some(false));
+
+        configs.push(loaded("worktree.config.toml", with_defaults(true)));
+        assert_eq!(root_defaults(&configs).unwrap().copy_unstaged, Some(true));
+        assert!(apply_root_defaults(&mut configs).is_empty());
+    }
+}
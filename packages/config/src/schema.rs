@@ -0,0 +1,281 @@
+//! The config file schema, and reporting unknown keys in config files.
+//!
+//! [`Config::json_schema`] describes every key a config file may set, for
+//! editors to validate and complete `worktree.config.toml` files against.
+//!
+//! Config fields are optional, so a misspelled key (`copyGob` for
+//! `copyGlob`) parses fine and its operations silently never run. After a
+//! config is parsed, the keys of the file are compared against the keys
+//! its [`Config`] reads; every key left over is recorded on the config as
+//! an [`UnknownKey`] for callers to warn about, or, with
+//! [`set_strict_schema`], fails the load.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::error::ConfigError;
+use crate::selection::edit_distance;
+use crate::types::Config;
+
+/// Alternative key names accepted for a field: `(alias, field)`.
+const ALIASES: [(&str, &str); 2] = [("comment", "label"), ("source", "path")];
+
+static STRICT_SCHEMA: OnceLock<bool> = OnceLock::new();
+
+/// Make unknown keys fail the config load ([`ConfigError::UnknownKeys`])
+/// instead of being recorded for a warning. Only the first call has an
+/// effect.
+pub fn set_strict_schema(strict: bool) {
+    let _ = STRICT_SCHEMA.set(strict);
+}
+
+/// Whether unknown keys fail the config load (see [`set_strict_schema`]).
+#[must_use]
+pub fn strict_schema() -> bool {
+    STRICT_SCHEMA.get().copied().unwrap_or(false)
+}
+
+impl Config {
+    /// JSON Schema (draft 2020-12) of a config file.
+    ///
+    /// Generated from the config types, so it always matches what the
+    /// loaders accept; the TOML, YAML, and JSON forms of a config share it.
+    #[must_use]
+    pub fn json_schema() -> Value {
+        let mut schema = schemars::schema_for!(Self);
+        schema.insert("title".into(), "worktree-setup config".into());
+        schema.to_value()
+    }
+}
+
+/// A key in a config file that no config field reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Config file (or URL) containing the key.
+    pub file: PathBuf,
+    /// Path of the key in the config, e.g. `symlinks[1].unlesExists`.
+    pub key: String,
+    /// 1-based line and column of the key in the file, if it was found.
+    pub position: Option<(usize, usize)>,
+    /// Known key of the same table with the closest spelling.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        write!(f, ": unknown key `{}`", self.key)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{suggestion}`?)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Record the keys of `raw`, the file at `file` as parsed, that `config`
+/// does not read, locating them in `source`, the file's text.
+///
+/// # Errors
+///
+/// * If there are unknown keys and [`strict_schema`] is set
+pub fn check_unknown_keys(
+    file: &Path,
+    raw: &Value,
+    config: &mut Config,
+    source: Option<&str>,
+) -> Result<(), ConfigError> {
+    let known = serde_json::to_value(&*config).unwrap_or(Value::Null);
+    let unknown = find_unknown_keys(file, raw, &known, source.unwrap_or(""));
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    if strict_schema() {
+        return Err(ConfigError::UnknownKeys(unknown));
+    }
+    for key in &unknown {
+        log::debug!("{key}");
+    }
+    config.unknown_keys.extend(unknown);
+    Ok(())
+}
+
+/// The keys of `raw` missing from `known`, the parsed config serialized
+/// back, in the order they appear in `source`.
+fn find_unknown_keys(file: &Path, raw: &Value, known: &Value, source: &str) -> Vec<UnknownKey> {
+    let mut found = Vec::new();
+    collect_unknown(raw, known, "", &mut found);
+
+    // A key name that is unknown several times is located at successive
+    // occurrences in the file.
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    let mut unknown: Vec<UnknownKey> = found
+        .into_iter()
+        .map(|(key, name, suggestion)| {
+            let occurrence = seen.entry(name.clone()).or_default();
+            let position = locate_key(source, &name, *occurrence);
+            *occurrence += 1;
+            UnknownKey {
+                file: file.to_path_buf(),
+                key,
+                position,
+                suggestion,
+            }
+        })
+        .collect();
+    unknown.sort_by_key(|key| key.position.unwrap_or((usize::MAX, 0)));
+    unknown
+}
+
+/// Walk `raw` and `known` together, pushing `(path, name, suggestion)`
+/// for every key of a `raw` table missing from the `known` one.
+fn collect_unknown(
+    raw: &Value,
+    known: &Value,
+    path: &str,
+    found: &mut Vec<(String, String, Option<String>)>,
+) {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            for (name, value) in raw {
+                let key_path = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{path}.{name}")
+                };
+                let field = known.get(name).or_else(|| {
+                    ALIASES
+                        .iter()
+                        .find(|(alias, _)| alias == name)
+                        .and_then(|(_, field)| known.get(*field))
+                });
+                if let Some(field) = field {
+                    collect_unknown(value, field, &key_path, found);
+                } else {
+                    let suggestion = closest_key(name, known.keys());
+                    found.push((key_path, name.clone(), suggestion));
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(known)) => {
+            for (index, (raw, known)) in raw.iter().zip(known).enumerate() {
+                collect_unknown(raw, known, &format!("{path}[{index}]"), found);
+            }
+        }
+        // A single value given where a list is accepted.
+        (raw, Value::Array(known)) if known.len() == 1 => {
+            collect_unknown(raw, &known[0], path, found);
+        }
+        _ => {}
+    }
+}
+
+/// The key among `candidates` spelled most like `name`, if any is close.
+fn closest_key<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<String> {
+    let threshold = (name.chars().count() / 3).max(2);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// 1-based line and column of the `occurrence`-th (0-based) place `name`
+/// is written as a key in `source`: followed by `=` or `:`, possibly
+/// quoted.
+fn locate_key(source: &str, name: &str, occurrence: usize) -> Option<(usize, usize)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    source
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            text.match_indices(name)
+                .filter(move |(start, _)| {
+                    let before = text[..*start].chars().next_back();
+                    let after = text[start + name.len()..]
+                        .trim_start_matches(['"', '\''])
+                        .trim_start();
+                    !before.is_some_and(is_word)
+                        && (after.starts_with('=') || after.starts_with(':'))
+                })
+                .map(move |(start, _)| (line + 1, text[..start].chars().count() + 1))
+        })
+        .nth(occurrence)
+}
+
+/// Render unknown keys for an error message, one per line.
+pub fn format_unknown_keys(keys: &[UnknownKey]) -> String {
+    keys.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unknown_keys() {
+        let source = r#"description = "web"
+copyGob = ["**/*.env"]
+symlinks = [
+  "node_modules",
+  { path = "data", unlesExists = "data/.keep", comment = "seed data" },
+]
+extends = { url = "https://example.com/base.toml", checksm = "abc" }
+
+[profiles.dev]
+description = "Dev"
+configz = ["web"]
+"#;
+        let raw: Value = toml::from_str(source).unwrap();
+        let config: Config = toml::from_str(source).unwrap();
+        let known = serde_json::to_value(&config).unwrap();
+
+        let unknown = find_unknown_keys(Path::new("web.toml"), &raw, &known, source);
+        let rendered: Vec<String> = unknown.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "web.toml:2:1: unknown key `copyGob` (did you mean `copyGlob`?)",
+                "web.toml:5:20: unknown key `symlinks[1].unlesExists` (did you mean `unlessExists`?)",
+                "web.toml:7:52: unknown key `extends.checksm` (did you mean `checksum`?)",
+                "web.toml:11:1: unknown key `profiles.dev.configz` (did you mean `configs`?)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_covers_config_keys() {
+        let schema = Config::json_schema();
+        let properties = schema["properties"].as_object().unwrap();
+        let config = serde_json::to_value(Config::default()).unwrap();
+        for key in config.as_object().unwrap().keys() {
+            assert!(properties.contains_key(key), "schema lacks `{key}`");
+        }
+        assert!(!properties.contains_key("unknownKeys"));
+
+        // Fields read as one value or a list accept both.
+        let extends = serde_json::to_string(&properties["extends"]).unwrap();
+        assert!(extends.contains("anyOf") && extends.contains("array"));
+    }
+
+    #[test]
+    fn test_locate_key() {
+        let source = "a = 1\n  \"copyGob\": [],\nxcopyGob = 2\ncopyGob: 3\n";
+        assert_eq!(locate_key(source, "copyGob", 0), Some((2, 4)));
+        assert_eq!(locate_key(source, "copyGob", 1), Some((4, 1)));
+        assert_eq!(locate_key(source, "copyGob", 2), None);
+    }
+}
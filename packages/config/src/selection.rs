@@ -0,0 +1,293 @@
+//! Selecting loaded configs with `--config` patterns.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeSet;
+
+use crate::ConfigError;
+use crate::discovery::get_config_display_name;
+use crate::types::LoadedConfig;
+
+/// Prefix marking a `--config` pattern as a glob.
+const GLOB_PREFIX: &str = "glob:";
+
+/// Maximum number of "did you mean?" suggestions per pattern.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Select configs matching any of `patterns`. Returns indices into `configs`.
+///
+/// Each pattern is resolved in order of precedence:
+///
+/// * `glob:<pattern>` — glob-matched against each config's relative path
+///   and relative directory (`apps/*`, `**/web`); may select several configs
+/// * An exact relative path (`apps/web/worktree.config.toml`), absolute
+///   config path, or relative directory (`apps/web`)
+/// * A display name (`web`, see [`get_config_display_name`])
+/// * A substring of the relative path, only if it identifies a single config
+///
+/// # Errors
+///
+/// * If a pattern matches no config ([`ConfigError::NoConfigMatch`], with
+///   suggestions for similarly named configs)
+/// * If a non-glob pattern matches more than one config
+///   ([`ConfigError::AmbiguousConfigPattern`])
+/// * If a `glob:` pattern is not a valid glob
+pub fn select_configs_by_patterns(
+    configs: &[LoadedConfig],
+    patterns: &[String],
+) -> Result<Vec<usize>, ConfigError> {
+    let mut selected = BTreeSet::new();
+
+    for pattern in patterns {
+        let matches = match pattern.strip_prefix(GLOB_PREFIX) {
+            Some(glob) => match_glob(configs, glob)?,
+            None => match_name(configs, pattern)?,
+        };
+
+        if matches.is_empty() {
+            return Err(ConfigError::NoConfigMatch {
+                pattern: pattern.clone(),
+                suggestions: suggest_configs(configs, pattern),
+            });
+        }
+        selected.extend(matches);
+    }
+
+    Ok(selected.into_iter().collect())
+}
+
+/// Configs whose relative path or directory matches `pattern`.
+fn match_glob(configs: &[LoadedConfig], pattern: &str) -> Result<Vec<usize>, ConfigError> {
+    let glob = glob::Pattern::new(pattern).map_err(|e| ConfigError::InvalidConfigPattern {
+        pattern: format!("{GLOB_PREFIX}{pattern}"),
+        message: e.to_string(),
+    })?;
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    Ok(configs
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| {
+            glob.matches_with(&c.relative_path, options)
+                || glob.matches_with(relative_dir(c), options)
+        })
+        .map(|(i, _)| i)
+        .collect())
+}
+
+/// The config identified by exact path, directory, display name, or a
+/// unique substring.
+fn match_name(configs: &[LoadedConfig], pattern: &str) -> Result<Vec<usize>, ConfigError> {
+    let rules: [&dyn Fn(&LoadedConfig) -> bool; 3] = [
+        &|c| {
+            c.relative_path == pattern
+                || c.config_path.to_string_lossy() == pattern
+                || relative_dir(c) == pattern.trim_end_matches('/')
+        },
+        &|c| get_config_display_name(c) == pattern,
+        &|c| c.relative_path.contains(pattern),
+    ];
+
+    for rule in rules {
+        let matches: Vec<usize> = configs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| rule(c))
+            .map(|(i, _)| i)
+            .collect();
+
+        if matches.len() > 1 {
+            return Err(ConfigError::AmbiguousConfigPattern {
+                pattern: pattern.to_string(),
+                matches: matches
+                    .iter()
+                    .map(|&i| configs[i].relative_path.clone())
+                    .collect(),
+            });
+        }
+        if !matches.is_empty() {
+            return Ok(matches);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// A config's directory relative to the repository root (`""` at the root).
+fn relative_dir(config: &LoadedConfig) -> &str {
+    config
+        .relative_path
+        .rsplit_once('/')
+        .map_or("", |(dir, _)| dir)
+}
+
+/// Relative paths of the configs whose path, directory, or display name is
+/// closest to `pattern` by edit distance, best first.
+#[must_use]
+pub fn suggest_configs(configs: &[LoadedConfig], pattern: &str) -> Vec<String> {
+    let pattern = pattern.to_lowercase();
+    let threshold = (pattern.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = configs
+        .iter()
+        .filter_map(|c| {
+            let distance = [
+                c.relative_path.clone(),
+                relative_dir(c).to_string(),
+                get_config_display_name(c),
+            ]
+            .iter()
+            .filter(|candidate| !candidate.is_empty())
+            .map(|candidate| edit_distance(&pattern, &candidate.to_lowercase()))
+            .min()?;
+            (distance <= threshold).then_some((distance, c.relative_path.as_str()))
+        })
+        .collect();
+
+    scored.sort_unstable();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, path)| path.to_string())
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_loaded_config(relative_path: &str) -> LoadedConfig {
+        let config_path = PathBuf::from("/repo").join(relative_path);
+        LoadedConfig {
+            config: crate::types::Config::default(),
+            config_dir: config_path.parent().unwrap().to_path_buf(),
+            config_path,
+            relative_path: relative_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    fn sample_configs() -> Vec<LoadedConfig> {
+        vec![
+            make_loaded_config("worktree.config.toml"),
+            make_loaded_config("apps/web/worktree.config.toml"),
+            make_loaded_config("apps/api/worktree.config.toml"),
+            make_loaded_config("webapp/worktree.config.toml"),
+            make_loaded_config("legacy/web/worktree.config.toml"),
+        ]
+    }
+
+    fn select(patterns: &[&str]) -> Result<Vec<usize>, ConfigError> {
+        let patterns: Vec<String> = patterns.iter().map(ToString::to_string).collect();
+        select_configs_by_patterns(&sample_configs(), &patterns)
+    }
+
+    #[test]
+    fn test_select_exact_path_and_directory() {
+        assert_eq!(select(&["apps/web/worktree.config.toml"]).unwrap(), vec![1]);
+        assert_eq!(select(&["apps/web"]).unwrap(), vec![1]);
+        assert_eq!(select(&["worktree.config.toml"]).unwrap(), vec![0]);
+        assert_eq!(
+            select(&["/repo/webapp/worktree.config.toml"]).unwrap(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn test_select_by_display_name() {
+        assert_eq!(select(&["api"]).unwrap(), vec![2]);
+        assert_eq!(select(&["webapp"]).unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn test_select_unique_substring() {
+        assert_eq!(select(&["legacy"]).unwrap(), vec![4]);
+    }
+
+    #[test]
+    fn test_select_ambiguous_display_name_is_error() {
+        let err = select(&["web"]).unwrap_err();
+        let ConfigError::AmbiguousConfigPattern { matches, .. } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(
+            matches,
+            &[
+                "apps/web/worktree.config.toml".to_string(),
+                "legacy/web/worktree.config.toml".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_ambiguous_substring_is_error() {
+        assert!(matches!(
+            select(&["app"]),
+            Err(ConfigError::AmbiguousConfigPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_glob() {
+        assert_eq!(select(&["glob:apps/*"]).unwrap(), vec![1, 2]);
+        assert_eq!(select(&["glob:**/web"]).unwrap(), vec![1, 4]);
+        assert_eq!(select(&["glob:apps/*", "api"]).unwrap(), vec![1, 2]);
+        assert!(matches!(
+            select(&["glob:apps/["]),
+            Err(ConfigError::InvalidConfigPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unmatched_pattern_suggests_similar_configs() {
+        let configs = vec![
+            make_loaded_config("apps/web/worktree.config.toml"),
+            make_loaded_config("apps/api/worktree.config.toml"),
+            make_loaded_config("tools/scripts/worktree.config.toml"),
+        ];
+
+        let err = select_configs_by_patterns(&configs, &["apps/wbe".to_string()]).unwrap_err();
+        let ConfigError::NoConfigMatch {
+            pattern,
+            suggestions,
+        } = &err
+        else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(pattern, "apps/wbe");
+        assert_eq!(suggestions[0], "apps/web/worktree.config.toml");
+        assert!(!suggestions.contains(&"tools/scripts/worktree.config.toml".to_string()));
+        assert!(err.to_string().contains("Did you mean"));
+    }
+}
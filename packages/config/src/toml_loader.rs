@@ -8,6 +8,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::error::ConfigError;
+use crate::schema::check_unknown_keys;
 use crate::types::Config;
 
 /// Load a TOML configuration file.
@@ -28,19 +29,35 @@ pub fn load_toml_config(path: &Path) -> Result<Config, ConfigError> {
         source: e,
     })?;
 
-    let config: Config = toml::from_str(&content).map_err(|e| ConfigError::TomlParseError {
-        path: path.to_path_buf(),
-        source: e,
-    })?;
+    let config = parse_toml_config(path, &content)?;
 
     log::debug!("Loaded config: {:?}", config.description);
 
     Ok(config)
 }
 
+/// Parse `content`, the TOML config read from `path`, recording keys no
+/// field reads (see [`check_unknown_keys`]).
+///
+/// # Errors
+///
+/// * If `content` cannot be parsed as a TOML config
+/// * If it has unknown keys and strict schema checking is on
+pub fn parse_toml_config(path: &Path, content: &str) -> Result<Config, ConfigError> {
+    let parse_error = |e| ConfigError::TomlParseError {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    let mut config: Config = toml::from_str(content).map_err(parse_error)?;
+    let raw = toml::from_str(content).map_err(parse_error)?;
+    check_unknown_keys(path, &raw, &mut config, Some(content))?;
+    Ok(config)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FileMode;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -68,7 +85,7 @@ postSetup = ["npm install"]
         assert_eq!(config.copy, vec![".env.local"]);
         assert_eq!(config.overwrite, vec!["config.json"]);
         assert_eq!(config.copy_glob, vec!["**/*.env"]);
-        assert!(config.copy_unstaged);
+        assert_eq!(config.copy_unstaged, Some(true));
         assert_eq!(config.post_setup, vec!["npm install"]);
         assert!(config.clean.is_empty());
     }
@@ -99,6 +116,183 @@ cleanIgnore = [".opencode/node_modules", "**/.cache"]
         );
     }
 
+    #[test]
+    fn test_load_toml_config_with_metadata() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+name = "Web app"
+description = "Env files and node_modules"
+owner = "@web-platform"
+docsUrl = "https://example.com/web-setup"
+estimatedSetupTime = "5 min"
+tags = ["frontend", "node"]
+"#
+        )
+        .unwrap();
+
+        let config = load_toml_config(file.path()).unwrap();
+
+        assert_eq!(config.label(), "Web app: Env files and node_modules");
+        assert_eq!(
+            config.metadata_summary().as_deref(),
+            Some(
+                "owner: @web-platform · setup: ~5 min · docs: https://example.com/web-setup · \
+                 tags: frontend, node"
+            )
+        );
+
+        let plain = Config::default();
+        assert_eq!(plain.label(), "");
+        assert_eq!(plain.metadata_summary(), None);
+    }
+
+    #[test]
+    fn test_load_toml_config_with_template_directory() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+templates = [
+    {{ source = ".env.example", target = ".env", mode = "0600", dirMode = 0o700 }},
+    {{ source = "templates", target = ".", stripSuffix = ".example" }},
+]
+"#
+        )
+        .unwrap();
+
+        let config = load_toml_config(file.path()).unwrap();
+
+        assert_eq!(config.templates.len(), 2);
+        assert_eq!(config.templates[0].strip_suffix, None);
+        assert_eq!(config.templates[0].mode, Some(FileMode(0o600)));
+        assert_eq!(config.templates[0].dir_mode, Some(FileMode(0o700)));
+        assert_eq!(config.templates[1].mode, None);
+        assert_eq!(config.templates[1].source, "templates");
+        assert_eq!(
+            config.templates[1].strip_suffix.as_deref(),
+            Some(".example")
+        );
+    }
+
+    #[test]
+    fn test_load_toml_config_rejects_invalid_mode() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"templates = [{{ source = "a", target = "b", mode = "0899" }}]"#
+        )
+        .unwrap();
+
+        let err = load_toml_config(file.path()).unwrap_err();
+        assert!(err.to_string().contains("invalid mode '0899'"), "{err}");
+    }
+
+    #[test]
+    fn test_load_toml_config_with_copy_from_ref() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+copyFromRef = [
+    {{ ref = "main", path = "configs/dev" }},
+    {{ ref = "v1.0", path = "/.env", target = ".env.v1", ifEnv = "CI" }},
+]
+"#
+        )
+        .unwrap();
+
+        let config = load_toml_config(file.path()).unwrap();
+
+        assert_eq!(config.copy_from_ref.len(), 2);
+        assert_eq!(config.copy_from_ref[0].git_ref, "main");
+        assert_eq!(config.copy_from_ref[0].path, "configs/dev");
+        assert_eq!(config.copy_from_ref[0].target, None);
+        assert_eq!(config.copy_from_ref[1].target.as_deref(), Some(".env.v1"));
+        assert_eq!(
+            config.copy_from_ref[1].condition.if_env.as_deref(),
+            Some("CI")
+        );
+    }
+
+    #[test]
+    fn test_load_toml_config_with_conditions() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+copy = [
+    "config.json",
+    {{ path = ".env", unlessExists = ".env.local" }},
+    {{ path = "datasets", maxDepth = 2, label = "sample data (~1 min)" }},
+    {{ source = "configs/dev.json", target = "config.json" }},
+]
+symlinks = [{{ path = "data", ifEnv = "CI", ifCommand = "which docker" }}]
+templates = [{{ source = ".env.example", target = ".env", ifEnv = "CI", order = -1, comment = "env" }}]
+"#
+        )
+        .unwrap();
+
+        let config = load_toml_config(file.path()).unwrap();
+
+        assert_eq!(config.copy[0], "config.json");
+        assert_eq!(config.copy[1].path(), ".env");
+        assert_eq!(
+            config.copy[1]
+                .condition()
+                .and_then(|c| c.unless_exists.as_deref()),
+            Some(".env.local")
+        );
+        let condition = config.symlinks[0].condition().unwrap();
+        assert_eq!(condition.if_env.as_deref(), Some("CI"));
+        assert_eq!(condition.if_command.as_deref(), Some("which docker"));
+        assert_eq!(config.templates[0].condition.if_env.as_deref(), Some("CI"));
+        assert!(config.templates[0].condition.unless_exists.is_none());
+        assert_eq!(config.templates[0].order, -1);
+        assert_eq!(config.copy[1].order(), 0);
+        assert_eq!(config.copy[1].max_depth(), None);
+        assert_eq!(config.copy[2].max_depth(), Some(2));
+        assert_eq!(config.copy[2].label(), Some("sample data (~1 min)"));
+        assert_eq!(config.templates[0].label.as_deref(), Some("env"));
+        assert_eq!(config.copy[3].path(), "configs/dev.json");
+        assert_eq!(config.copy[3].target(), Some("config.json"));
+        assert_eq!(config.copy[1].target(), None);
+    }
+
+    #[test]
+    fn test_load_toml_config_with_hooks() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+copy = [{{ path = ".envrc", after = "direnv allow" }}]
+overwrite = [{{ path = "dev.db", before = "pkill -f watcher", ifEnv = "CI" }}]
+templates = [{{ source = ".env.example", target = ".env", after = "echo done" }}]
+"#
+        )
+        .unwrap();
+
+        let config = load_toml_config(file.path()).unwrap();
+
+        let hooks = config.copy[0].hooks().unwrap();
+        assert_eq!(hooks.after.as_deref(), Some("direnv allow"));
+        assert!(hooks.before.is_none());
+        assert_ne!(config.copy[0], ".envrc");
+        let hooks = config.overwrite[0].hooks().unwrap();
+        assert_eq!(hooks.before.as_deref(), Some("pkill -f watcher"));
+        assert_eq!(
+            config.overwrite[0]
+                .condition()
+                .and_then(|c| c.if_env.as_deref()),
+            Some("CI")
+        );
+        assert_eq!(
+            config.templates[0].hooks.after.as_deref(),
+            Some("echo done")
+        );
+    }
+
     #[test]
     fn test_load_minimal_toml_config() {
         let mut file = NamedTempFile::new().unwrap();
@@ -109,7 +303,7 @@ cleanIgnore = [".opencode/node_modules", "**/.cache"]
         assert_eq!(config.description, "Minimal");
         assert!(config.symlinks.is_empty());
         assert!(config.copy.is_empty());
-        assert!(!config.copy_unstaged);
+        assert_eq!(config.copy_unstaged, None);
         assert!(config.clean.is_empty());
         assert!(config.clean_ignore.is_empty());
         assert!(config.profiles.is_empty());
@@ -9,58 +9,98 @@
 //! file, reducing N × 100–150 ms to a single ~5–10 ms in-process evaluation.
 //!
 //! Falls back to `bun`/`deno` subprocesses if embedded evaluation fails
-//! (e.g., the config uses features unsupported by `QuickJS`).
+//! (e.g., the config uses features unsupported by `QuickJS`). With the
+//! sandbox enabled (see [`crate::TsOptions`]), only `deno` is tried, with
+//! no permissions; the embedded pipeline has no host APIs besides loading
+//! the config's own imports.
+//!
+//! When several configs need the fallback, [`load_ts_configs_batched`]
+//! evaluates them all in one subprocess instead of one each.
+//!
+//! Plain JavaScript configs (`.js`, `.mjs`, `.cjs`) take the same path
+//! without type stripping. `.cjs` configs are wrapped so `module.exports`
+//! becomes the default export, and JavaScript configs fall back to `node`
+//! when neither `bun` nor `deno` is installed.
 
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
+use std::fmt::Write as _;
+use std::io::Write as _;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use rquickjs::loader::{Loader, Resolver};
 use rquickjs::{Context, Ctx, Module, Runtime, Value};
+use serde::Deserialize as _;
 
 use crate::error::ConfigError;
+use crate::schema::check_unknown_keys;
+use crate::ts_trust::{check_ts_allowlist, ts_options};
 use crate::types::Config;
 
 // ─── Public API ─────────────────────────────────────────────────────────────
 
-/// Load a TypeScript configuration file.
+/// Extensions of configs evaluated as scripts: TypeScript and plain
+/// JavaScript.
+pub const SCRIPT_CONFIG_EXTENSIONS: [&str; 4] = ["ts", "js", "mjs", "cjs"];
+
+/// Defines the global `defineConfig` helper declared by
+/// [`Config::typescript_declarations`], which returns its argument.
+const DEFINE_CONFIG: &str = "globalThis.defineConfig = (config) => config;";
+
+/// [`DEFINE_CONFIG`] as an import, for scripts whose static imports are
+/// evaluated before their own code.
+const DEFINE_CONFIG_IMPORT: &str =
+    r#"import "data:text/javascript,globalThis.defineConfig = (config) => config;";"#;
+
+/// Whether `path` is a TypeScript or JavaScript config.
+#[must_use]
+pub fn is_script_config(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| SCRIPT_CONFIG_EXTENSIONS.contains(&ext))
+}
+
+/// Whether `path` is a plain JavaScript config.
+fn is_js_config(path: &Path) -> bool {
+    is_script_config(path) && extension(path) != Some("ts")
+}
+
+/// The extension of `path`, if it is valid UTF-8.
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|e| e.to_str())
+}
+
+/// Load a TypeScript or JavaScript configuration file.
 ///
 /// Tries the embedded SWC + `QuickJS` pipeline first, falling back to
-/// `bun`/`deno` subprocesses if the embedded approach fails.
+/// `bun`/`deno` subprocesses (or `node`, for JavaScript) if the embedded
+/// approach fails.
 ///
 /// # Arguments
 ///
-/// * `path` - Path to the TypeScript configuration file
+/// * `path` - Path to the TypeScript or JavaScript configuration file
 ///
 /// # Errors
 ///
+/// * If the config's hash is not in the allowlist
 /// * If no evaluation method succeeds
 /// * If the output cannot be parsed as a `Config`
 pub fn load_ts_config(path: &Path) -> Result<Config, ConfigError> {
     log::debug!("Loading TypeScript config from {}", path.display());
 
+    let options = ts_options();
+    check_ts_allowlist(path, &options.allowed_hashes)?;
+
     // Try embedded SWC + QuickJS first (fast path)
     match load_ts_embedded(path) {
         Ok(config) => return Ok(config),
         Err(e) => log::debug!("Embedded TS eval failed, falling back to subprocess: {e}"),
     }
 
-    // Fall back to bun
-    match try_load_with_bun(path) {
-        Ok(config) => return Ok(config),
-        Err(e) => log::debug!("bun failed: {e}"),
-    }
-
-    // Fall back to deno
-    match try_load_with_deno(path) {
-        Ok(config) => return Ok(config),
-        Err(e) => log::debug!("deno failed: {e}"),
-    }
-
-    Err(ConfigError::NoJsRuntime)
+    // Fall back to bun, which cannot be restricted (not when sandboxed),
+    // then deno, then node for JavaScript
+    load_ts_with_subprocess(path)
 }
 
 // ─── Embedded SWC + QuickJS pipeline ────────────────────────────────────────
@@ -70,7 +110,7 @@ pub fn load_ts_config(path: &Path) -> Result<Config, ConfigError> {
 /// This replaces TypeScript-specific syntax (type annotations, `as const`,
 /// `import type`, interfaces, type aliases, etc.) with whitespace, preserving
 /// byte offsets. The result is valid JavaScript.
-fn strip_ts_types(source: &str) -> Result<String, ConfigError> {
+pub fn strip_ts_types(source: &str) -> Result<String, ConfigError> {
     use swc_common::SourceMap;
     use swc_common::errors::{HANDLER, Handler};
     use swc_common::sync::Lrc;
@@ -122,7 +162,7 @@ impl Resolver for TsResolver {
         let candidate = base_dir.join(name);
 
         // Try exact path first, then with extensions
-        let extensions = ["", ".ts", ".js", "/index.ts", "/index.js"];
+        let extensions = ["", ".ts", ".js", ".mjs", "/index.ts", "/index.js"];
         for ext in &extensions {
             let path = if ext.is_empty() {
                 candidate.clone()
@@ -170,8 +210,22 @@ impl Loader for TsLoader {
     }
 }
 
+/// Wrap a `.cjs` module so `module.exports` is its default export.
+///
+/// `require` is not provided, so configs using it fall back to a
+/// subprocess.
+fn commonjs_module(source: &str) -> String {
+    format!(
+        "const module = {{ exports: {{}} }};\nlet exports = module.exports;\n{source}\n\
+         export default module.exports;\n"
+    )
+}
+
 /// Load a TypeScript config using the embedded SWC + `QuickJS` pipeline.
-fn load_ts_embedded(path: &Path) -> Result<Config, ConfigError> {
+///
+/// Unlike [`load_ts_config`], neither checks the allowlist nor falls back
+/// to a subprocess.
+pub fn load_ts_embedded(path: &Path) -> Result<Config, ConfigError> {
     // Canonicalize the entry path so relative imports resolve correctly
     let canonical_path = path
         .canonicalize()
@@ -181,16 +235,19 @@ fn load_ts_embedded(path: &Path) -> Result<Config, ConfigError> {
         })?;
     let module_name = canonical_path.to_string_lossy().to_string();
 
-    // Read and strip the entry file
-    let ts_source =
-        std::fs::read_to_string(&canonical_path).map_err(|e| ConfigError::ReadError {
-            path: path.to_path_buf(),
-            source: e,
-        })?;
-    let js_source = strip_ts_types(&ts_source).map_err(|e| ConfigError::TypeScriptEvalError {
+    // Read and strip (or wrap) the entry file
+    let source = std::fs::read_to_string(&canonical_path).map_err(|e| ConfigError::ReadError {
         path: path.to_path_buf(),
-        message: format!("{e}"),
+        source: e,
     })?;
+    let js_source = match extension(path) {
+        Some("cjs") => commonjs_module(&source),
+        Some("js" | "mjs") => source,
+        _ => strip_ts_types(&source).map_err(|e| ConfigError::TypeScriptEvalError {
+            path: path.to_path_buf(),
+            message: format!("{e}"),
+        })?,
+    };
 
     // Create QuickJS runtime with custom loader for imports
     let rt = Runtime::new().map_err(|e| ConfigError::TypeScriptEvalError {
@@ -205,6 +262,12 @@ fn load_ts_embedded(path: &Path) -> Result<Config, ConfigError> {
     })?;
 
     ctx.with(|ctx| {
+        ctx.eval::<(), _>(DEFINE_CONFIG)
+            .map_err(|e| ConfigError::TypeScriptEvalError {
+                path: path.to_path_buf(),
+                message: format!("Failed to define defineConfig: {e}"),
+            })?;
+
         // Declare and evaluate the module
         let module =
             Module::declare(ctx.clone(), module_name.as_str(), js_source).map_err(|e| {
@@ -257,13 +320,27 @@ fn load_ts_embedded(path: &Path) -> Result<Config, ConfigError> {
 
         log::debug!("Embedded eval output: {json_string}");
 
-        serde_json::from_str(&json_string).map_err(|e| ConfigError::JsonParseError {
-            path: path.to_path_buf(),
-            source: e,
-        })
+        serde_json::from_str(&json_string)
+            .map_err(|e| ConfigError::JsonParseError {
+                path: path.to_path_buf(),
+                source: e,
+            })
+            .and_then(|value| parse_config_json(path, &value))
     })
 }
 
+/// Parse the JSON a config at `path` evaluated to, recording keys no
+/// field reads against the config's source (see [`check_unknown_keys`]).
+fn parse_config_json(path: &Path, value: &serde_json::Value) -> Result<Config, ConfigError> {
+    let mut config = Config::deserialize(value).map_err(|e| ConfigError::JsonParseError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    let source = std::fs::read_to_string(path).ok();
+    check_unknown_keys(path, value, &mut config, source.as_deref())?;
+    Ok(config)
+}
+
 // ─── Subprocess fallbacks ───────────────────────────────────────────────────
 
 /// Try to load the config using bun.
@@ -272,70 +349,281 @@ fn try_load_with_bun(path: &Path) -> Result<Config, ConfigError> {
 
     // Use dynamic import and handle both default and named exports
     let script = format!(
-        r#"const m = await import("file://{path_str}"); console.log(JSON.stringify(m.default ?? m));"#
+        r#"{DEFINE_CONFIG} const m = await import("file://{path_str}"); console.log(JSON.stringify(m.default ?? m));"#
     );
 
     log::debug!("Evaluating with bun: {script}");
+    let stdout = run_with_bun(&script).map_err(|message| ConfigError::TypeScriptEvalError {
+        path: path.to_path_buf(),
+        message,
+    })?;
+    log::debug!("bun output: {}", stdout.trim());
 
-    let output = Command::new("bun")
-        .args(["-e", &script])
-        .output()
-        .map_err(|e| ConfigError::TypeScriptEvalError {
+    serde_json::from_str(stdout.trim())
+        .map_err(|e| ConfigError::JsonParseError {
             path: path.to_path_buf(),
-            message: format!("Failed to run bun: {e}"),
-        })?;
+            source: e,
+        })
+        .and_then(|value| parse_config_json(path, &value))
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(ConfigError::TypeScriptEvalError {
+/// Try to load the config using deno.
+///
+/// `sandboxed` drops `--allow-read`, so the config runs with no
+/// permissions (its static imports still load).
+fn try_load_with_deno(path: &Path, sandboxed: bool) -> Result<Config, ConfigError> {
+    let path_str = path.to_string_lossy();
+    let script = if sandboxed {
+        // A static import needs no read permission.
+        format!(
+            r#"{DEFINE_CONFIG_IMPORT} import * as m from "file://{path_str}"; console.log(JSON.stringify(m.default ?? m));"#
+        )
+    } else {
+        format!(
+            r#"{DEFINE_CONFIG} const m = await import("file://{path_str}"); console.log(JSON.stringify(m.default ?? m));"#
+        )
+    };
+
+    log::debug!("Evaluating with deno: {script}");
+    let stdout =
+        run_with_deno(&script, sandboxed).map_err(|message| ConfigError::TypeScriptEvalError {
             path: path.to_path_buf(),
-            message: stderr.to_string(),
-        });
-    }
+            message,
+        })?;
+    log::debug!("deno output: {}", stdout.trim());
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    log::debug!("bun output: {}", stdout.trim());
+    serde_json::from_str(stdout.trim())
+        .map_err(|e| ConfigError::JsonParseError {
+            path: path.to_path_buf(),
+            source: e,
+        })
+        .and_then(|value| parse_config_json(path, &value))
+}
 
-    serde_json::from_str(stdout.trim()).map_err(|e| ConfigError::JsonParseError {
-        path: path.to_path_buf(),
-        source: e,
-    })
+/// Run `script` with bun and return its standard output.
+fn run_with_bun(script: &str) -> Result<String, String> {
+    let output = Command::new("bun")
+        .args(["-e", script])
+        .output()
+        .map_err(|e| format!("Failed to run bun: {e}"))?;
+    script_output(&output)
 }
 
-/// Try to load the config using deno.
-fn try_load_with_deno(path: &Path) -> Result<Config, ConfigError> {
+/// Try to load a JavaScript config using node.
+fn try_load_with_node(path: &Path) -> Result<Config, ConfigError> {
     let path_str = path.to_string_lossy();
-
-    // Deno script with explicit allow flags
     let script = format!(
-        r#"const m = await import("file://{path_str}"); console.log(JSON.stringify(m.default ?? m));"#
+        r#"{DEFINE_CONFIG} const m = await import("file://{path_str}"); console.log(JSON.stringify(m.default ?? m));"#
     );
 
-    log::debug!("Evaluating with deno: {script}");
-
-    let output = Command::new("deno")
-        .args(["eval", "--allow-read", &script])
+    log::debug!("Evaluating with node: {script}");
+    let output = Command::new("node")
+        .args(["--input-type=module", "-e", &script])
         .output()
-        .map_err(|e| ConfigError::TypeScriptEvalError {
+        .map_err(|e| format!("Failed to run node: {e}"))
+        .and_then(|output| script_output(&output))
+        .map_err(|message| ConfigError::TypeScriptEvalError {
             path: path.to_path_buf(),
-            message: format!("Failed to run deno: {e}"),
+            message,
         })?;
+    log::debug!("node output: {}", output.trim());
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(ConfigError::TypeScriptEvalError {
+    serde_json::from_str(output.trim())
+        .map_err(|e| ConfigError::JsonParseError {
             path: path.to_path_buf(),
-            message: stderr.to_string(),
-        });
+            source: e,
+        })
+        .and_then(|value| parse_config_json(path, &value))
+}
+
+/// Run `script` with deno and return its standard output.
+///
+/// `sandboxed` runs it with no permissions.
+fn run_with_deno(script: &str, sandboxed: bool) -> Result<String, String> {
+    let deno_error = |e: std::io::Error| format!("Failed to run deno: {e}");
+
+    let output = if sandboxed {
+        // `deno eval` always grants every permission, so run the script from
+        // stdin instead.
+        let mut child = Command::new("deno")
+            .args(["run", "--no-prompt", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(deno_error)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(script.as_bytes()).map_err(deno_error)?;
+        }
+        child.wait_with_output().map_err(deno_error)?
+    } else {
+        Command::new("deno")
+            .args(["eval", "--allow-read", script])
+            .output()
+            .map_err(deno_error)?
+    };
+    script_output(&output)
+}
+
+/// The standard output of a finished script, or its standard error if it
+/// failed.
+fn script_output(output: &std::process::Output) -> Result<String, String> {
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
     }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    log::debug!("deno output: {}", stdout.trim());
+// ─── Batched subprocess evaluation ──────────────────────────────────────────
+
+/// Evaluate TypeScript configs that the embedded pipeline could not, all in
+/// one `bun` (or `deno`) process.
+///
+/// Starting a JavaScript runtime takes 100–150 ms, so rather than one
+/// process per config, a generated driver script imports every config and
+/// prints a JSON map from path to default export. With the sandbox enabled
+/// a config that fails to import fails the whole driver, and the configs
+/// are then evaluated one by one. Results are in the order of `paths`; the
+/// configs must already have passed [`check_ts_allowlist`].
+#[must_use]
+pub fn load_ts_configs_batched(paths: &[&Path]) -> Vec<Result<Config, ConfigError>> {
+    match paths {
+        [] => return Vec::new(),
+        [path] => return vec![load_ts_with_subprocess(path)],
+        _ => {}
+    }
+
+    let sandbox = ts_options().sandbox;
+    let script = batch_script(paths, sandbox);
+    log::debug!("Evaluating {} TS configs in one process", paths.len());
+
+    let stdout = if sandbox {
+        run_with_deno(&script, true)
+    } else {
+        run_with_bun(&script).or_else(|e| {
+            log::debug!("bun failed: {e}");
+            run_with_deno(&script, false)
+        })
+    };
+    match stdout {
+        Ok(stdout) => parse_batch_output(paths, &stdout),
+        Err(e) => {
+            log::debug!("Batched TS evaluation failed: {e}");
+            paths
+                .iter()
+                .map(|path| load_ts_with_subprocess(path))
+                .collect()
+        }
+    }
+}
+
+/// Load one config with `bun` or `deno`, as [`load_ts_config`] does once
+/// the embedded pipeline has failed.
+fn load_ts_with_subprocess(path: &Path) -> Result<Config, ConfigError> {
+    let sandbox = ts_options().sandbox;
+    if !sandbox {
+        match try_load_with_bun(path) {
+            Ok(config) => return Ok(config),
+            Err(e) => log::debug!("bun failed: {e}"),
+        }
+    }
+    try_load_with_deno(path, sandbox)
+        .or_else(|e| {
+            log::debug!("deno failed: {e}");
+            // node cannot be sandboxed and does not run TypeScript.
+            if sandbox || !is_js_config(path) {
+                return Err(e);
+            }
+            try_load_with_node(path)
+        })
+        .map_err(|e| {
+            log::debug!("No runtime could evaluate {}: {e}", path.display());
+            ConfigError::NoJsRuntime
+        })
+}
+
+/// Driver script printing `{ "<path>": { "config": … } | { "error": "…" } }`
+/// for every path.
+///
+/// Sandboxed scripts use static imports, which need no read permission, so
+/// one failing config fails the script; otherwise each config is imported
+/// on its own and its error recorded.
+fn batch_script(paths: &[&Path], sandboxed: bool) -> String {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let list = serde_json::to_string(&paths).unwrap_or_else(|_| "[]".to_string());
+
+    if sandboxed {
+        let mut script = format!("{DEFINE_CONFIG_IMPORT}\n");
+        for (index, path) in paths.iter().enumerate() {
+            let url = serde_json::to_string(&format!("file://{path}")).unwrap_or_default();
+            let _ = writeln!(script, "import * as m{index} from {url};");
+        }
+        let _ = writeln!(
+            script,
+            "const paths = {list};\nconst modules = [{}];",
+            (0..paths.len())
+                .map(|index| format!("m{index}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        script.push_str(
+            "const out = {};\n\
+             paths.forEach((p, i) => { out[p] = { config: modules[i].default ?? modules[i] }; });\n\
+             console.log(JSON.stringify(out));\n",
+        );
+        return script;
+    }
+
+    format!(
+        "{DEFINE_CONFIG}\n\
+         const paths = {list};\n\
+         const out = {{}};\n\
+         for (const p of paths) {{\n\
+         \x20 try {{ const m = await import(\"file://\" + p); out[p] = {{ config: m.default ?? m }}; }}\n\
+         \x20 catch (e) {{ out[p] = {{ error: String(e?.stack ?? e) }}; }}\n\
+         }}\n\
+         console.log(JSON.stringify(out));\n"
+    )
+}
+
+/// What the driver script printed for one config.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BatchResult {
+    Config(serde_json::Value),
+    Error(String),
+}
 
-    serde_json::from_str(stdout.trim()).map_err(|e| ConfigError::JsonParseError {
+/// Split the driver script's output into one result per path.
+fn parse_batch_output(paths: &[&Path], stdout: &str) -> Vec<Result<Config, ConfigError>> {
+    let eval_error = |path: &Path, message: String| ConfigError::TypeScriptEvalError {
         path: path.to_path_buf(),
-        source: e,
-    })
+        message,
+    };
+    let mut results: std::collections::BTreeMap<String, BatchResult> =
+        match serde_json::from_str(stdout.trim()) {
+            Ok(results) => results,
+            Err(e) => {
+                return paths
+                    .iter()
+                    .map(|path| Err(eval_error(path, format!("Invalid batch output: {e}"))))
+                    .collect();
+            }
+        };
+
+    paths
+        .iter()
+        .map(
+            |path| match results.remove(path.to_string_lossy().as_ref()) {
+                Some(BatchResult::Config(value)) => parse_config_json(path, &value),
+                Some(BatchResult::Error(message)) => Err(eval_error(path, message)),
+                None => Err(eval_error(path, "Missing from batch output".to_string())),
+            },
+        )
+        .collect()
 }
 
 #[cfg(test)]
@@ -393,7 +681,89 @@ export default {{
         let config = load_ts_embedded(&path).unwrap();
         assert_eq!(config.description, "Test TS config");
         assert_eq!(config.symlinks, vec!["data/cache"]);
-        assert!(config.copy_unstaged);
+        assert_eq!(config.copy_unstaged, Some(true));
+    }
+
+    #[test]
+    fn test_load_ts_embedded_records_unknown_keys() {
+        let dir = Builder::new().prefix("worktree-test").tempdir().unwrap();
+        let path = dir.path().join("worktree.config.ts");
+        std::fs::write(
+            &path,
+            "const env: string[] = [\".env\"];\nexport default {\n    copyGob: env,\n};\n",
+        )
+        .unwrap();
+
+        let config = load_ts_embedded(&path).unwrap();
+        assert!(config.copy_glob.is_empty());
+        let unknown = &config.unknown_keys;
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].key, "copyGob");
+        assert_eq!(unknown[0].position, Some((3, 5)));
+        assert_eq!(unknown[0].suggestion.as_deref(), Some("copyGlob"));
+    }
+
+    #[test]
+    fn test_load_ts_embedded_with_define_config() {
+        let dir = Builder::new().prefix("worktree-test").tempdir().unwrap();
+        let path = dir.path().join("worktree.config.ts");
+        std::fs::write(
+            &path,
+            "/// <reference path=\"./worktree-setup.d.ts\" />\n\
+             const base: WorktreeSetup.Config = { description: \"typed\" };\n\
+             export default defineConfig({ ...base, symlinks: [\"node_modules\"] });\n",
+        )
+        .unwrap();
+
+        let config = load_ts_embedded(&path).unwrap();
+        assert_eq!(config.description, "typed");
+        assert_eq!(config.symlinks, vec!["node_modules"]);
+    }
+
+    #[test]
+    fn test_load_js_embedded() {
+        let dir = Builder::new().prefix("worktree-test").tempdir().unwrap();
+        let mjs = dir.path().join("worktree.config.mjs");
+        std::fs::write(
+            &mjs,
+            "const shared = [\"node_modules\"];\nexport default { symlinks: shared };\n",
+        )
+        .unwrap();
+        let cjs = dir.path().join("worktree.local.config.cjs");
+        std::fs::write(
+            &cjs,
+            "module.exports = { description: \"CommonJS\", copy: [\".env\"] };\n",
+        )
+        .unwrap();
+
+        assert!(is_script_config(&mjs) && is_js_config(&cjs));
+        assert!(!is_js_config(Path::new("worktree.config.ts")));
+        assert!(!is_script_config(Path::new("worktree.config.toml")));
+        assert_eq!(
+            load_ts_embedded(&mjs).unwrap().symlinks,
+            vec!["node_modules"]
+        );
+        let config = load_ts_embedded(&cjs).unwrap();
+        assert_eq!(config.description, "CommonJS");
+        assert_eq!(config.copy, vec![".env"]);
+    }
+
+    #[test]
+    fn test_load_js_with_node() {
+        if Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = Builder::new().prefix("worktree-test").tempdir().unwrap();
+        let path = dir.path().join("worktree.config.cjs");
+        std::fs::write(
+            &path,
+            "const path = require(\"node:path\");\n\
+             module.exports = defineConfig({ copy: [path.join(\"config\", \".env\")] });\n",
+        )
+        .unwrap();
+
+        assert!(load_ts_embedded(&path).is_err());
+        assert_eq!(try_load_with_node(&path).unwrap().copy, vec!["config/.env"]);
     }
 
     #[test]
@@ -462,6 +832,54 @@ export default {{
         assert_eq!(config.symlinks, vec!["data/cache"]);
     }
 
+    #[test]
+    fn test_parse_batch_output() {
+        let good = Path::new("/repo/a/worktree.config.ts");
+        let failed = Path::new("/repo/b/worktree.config.ts");
+        let missing = Path::new("/repo/c/worktree.config.ts");
+        let stdout = r#"{
+            "/repo/a/worktree.config.ts": { "config": { "description": "A", "copy": [".env"] } },
+            "/repo/b/worktree.config.ts": { "error": "ReferenceError: x is not defined" }
+        }"#;
+
+        let results = parse_batch_output(&[good, failed, missing], stdout);
+
+        let config = results[0].as_ref().unwrap();
+        assert_eq!(config.description, "A");
+        assert_eq!(config.copy, vec![".env"]);
+        assert!(matches!(
+            &results[1],
+            Err(ConfigError::TypeScriptEvalError { message, .. }) if message.contains("ReferenceError")
+        ));
+        assert!(results[2].is_err());
+
+        let invalid = parse_batch_output(&[good], "not json");
+        assert!(invalid[0].is_err());
+    }
+
+    #[test]
+    fn test_batch_script_imports_every_config() {
+        let paths = [
+            Path::new("/repo/a/worktree.config.ts"),
+            Path::new("/repo/b \"q\"/worktree.config.ts"),
+        ];
+
+        let script = batch_script(&paths, false);
+        assert!(
+            script.contains(r#"["/repo/a/worktree.config.ts","/repo/b \"q\"/worktree.config.ts"]"#)
+        );
+        assert!(script.contains("catch (e)"));
+        assert!(script.starts_with(DEFINE_CONFIG));
+
+        let sandboxed = batch_script(&paths, true);
+        assert!(sandboxed.contains(r#"import * as m0 from "file:///repo/a/worktree.config.ts";"#));
+        assert!(
+            sandboxed.contains(r#"import * as m1 from "file:///repo/b \"q\"/worktree.config.ts";"#)
+        );
+        assert!(sandboxed.contains("const modules = [m0, m1];"));
+        assert!(sandboxed.starts_with(DEFINE_CONFIG_IMPORT));
+    }
+
     #[test]
     fn test_load_ts_config_with_bun() {
         // Skip if bun is not installed
@@ -490,6 +908,6 @@ export default {{
 
         assert_eq!(config.description, "Test TS config");
         assert_eq!(config.symlinks, vec!["data/cache"]);
-        assert!(config.copy_unstaged);
+        assert_eq!(config.copy_unstaged, Some(true));
     }
 }
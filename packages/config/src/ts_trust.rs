@@ -0,0 +1,165 @@
+//! Guards around evaluating TypeScript configs.
+//!
+//! A TypeScript config is code: loading it runs it. These settings limit
+//! what that code can do and which configs run at all:
+//!
+//! * **Sandbox**: never fall back to `bun` (which has no permission
+//!   model), and run `deno` with no permissions.
+//! * **Allowlist**: only evaluate configs whose BLAKE3 hash is listed.
+//! * **Change tracking**: remember the hash each config had when it last
+//!   ran, so callers can warn before a new or edited config runs.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::ConfigError;
+use crate::ts_loader::is_script_config;
+
+/// How TypeScript configs may be evaluated.
+#[derive(Debug, Clone, Default)]
+pub struct TsOptions {
+    /// Evaluate without host access: no `bun` fallback, `deno` without
+    /// permissions.
+    pub sandbox: bool,
+    /// Hex BLAKE3 hashes of the configs allowed to run; empty allows all.
+    pub allowed_hashes: Vec<String>,
+}
+
+/// Options used by [`crate::load_ts_config`], set once by the caller.
+static TS_OPTIONS: OnceLock<TsOptions> = OnceLock::new();
+
+/// Set how [`crate::load_ts_config`] evaluates configs. Only the first
+/// call has an effect; without one, configs run unrestricted.
+pub fn set_ts_options(options: TsOptions) {
+    if TS_OPTIONS.set(options).is_err() {
+        log::debug!("TypeScript config options were already set");
+    }
+}
+
+/// Options set with [`set_ts_options`].
+pub fn ts_options() -> &'static TsOptions {
+    TS_OPTIONS.get_or_init(TsOptions::default)
+}
+
+/// Hex BLAKE3 hash of the config file at `path`.
+///
+/// # Errors
+///
+/// * If the file cannot be read
+pub fn ts_config_hash(path: &Path) -> Result<String, ConfigError> {
+    let content = fs::read(path).map_err(|e| ConfigError::ReadError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    Ok(blake3::hash(&content).to_hex().to_string())
+}
+
+/// Refuse to evaluate `path` unless its hash is in `allowed_hashes`.
+///
+/// # Errors
+///
+/// * If the file cannot be read
+/// * If `allowed_hashes` is not empty and does not list the file's hash
+pub fn check_ts_allowlist(path: &Path, allowed_hashes: &[String]) -> Result<(), ConfigError> {
+    if allowed_hashes.is_empty() {
+        return Ok(());
+    }
+    let hash = ts_config_hash(path)?;
+    if allowed_hashes
+        .iter()
+        .any(|allowed| allowed.trim().eq_ignore_ascii_case(&hash))
+    {
+        Ok(())
+    } else {
+        Err(ConfigError::UntrustedTsConfig {
+            path: path.to_path_buf(),
+            hash,
+        })
+    }
+}
+
+/// Record the current hashes of the TypeScript configs in `paths` in
+/// `store`, returning the ones that are new or changed since they were
+/// last recorded.
+///
+/// Paths that are not TypeScript or JavaScript configs or cannot be read
+/// are skipped. Failing
+/// to read or write `store` only loses the history, so it is logged
+/// rather than returned.
+#[must_use]
+pub fn record_ts_configs(paths: &[PathBuf], store: &Path) -> Vec<PathBuf> {
+    let mut seen: BTreeMap<String, String> = fs::read_to_string(store)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let mut changed = Vec::new();
+    for path in paths.iter().filter(|p| is_script_config(p)) {
+        let Ok(hash) = ts_config_hash(path) else {
+            continue;
+        };
+        let key = path.to_string_lossy().to_string();
+        if seen.get(&key) != Some(&hash) {
+            changed.push(path.clone());
+            seen.insert(key, hash);
+        }
+    }
+
+    if !changed.is_empty() {
+        let written = store
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                let json = serde_json::to_string_pretty(&seen).map_err(std::io::Error::other)?;
+                fs::write(store, json)
+            });
+        if let Err(e) = written {
+            log::debug!("Failed to record config hashes in {}: {e}", store.display());
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_ts_allowlist() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("worktree.config.ts");
+        fs::write(&path, "export default {};\n").unwrap();
+        let hash = ts_config_hash(&path).unwrap();
+
+        assert!(check_ts_allowlist(&path, &[]).is_ok());
+        assert!(check_ts_allowlist(&path, &[hash.to_uppercase()]).is_ok());
+        assert!(matches!(
+            check_ts_allowlist(&path, &["00".to_string()]),
+            Err(ConfigError::UntrustedTsConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_record_ts_configs_reports_new_and_changed_configs() {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join("cache/ts-configs.json");
+        let ts = dir.path().join("worktree.config.ts");
+        let toml = dir.path().join("worktree.config.toml");
+        fs::write(&ts, "export default {};\n").unwrap();
+        fs::write(&toml, "").unwrap();
+        let paths = [ts.clone(), toml];
+
+        assert_eq!(record_ts_configs(&paths, &store), std::slice::from_ref(&ts));
+        assert!(record_ts_configs(&paths, &store).is_empty());
+
+        fs::write(&ts, "export default { copy: [\".env\"] };\n").unwrap();
+        assert_eq!(record_ts_configs(&paths, &store), [ts]);
+    }
+}
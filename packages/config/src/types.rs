@@ -7,49 +7,473 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::schema::UnknownKey;
+
 /// A template file mapping from source to target.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `source` may also be a directory or a glob pattern, in which case every
+/// matched file is copied into the `target` directory, keeping its path
+/// relative to the source directory (or the glob's literal prefix).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct TemplateMapping {
-    /// Source file path (relative to config directory).
+    /// Source file, directory, or glob pattern (relative to config directory).
     pub source: String,
-    /// Target file path (relative to config directory).
+    /// Target file path, or target directory for directory/glob sources
+    /// (relative to config directory).
     pub target: String,
+    /// Suffix removed from each file name when `source` is a directory or
+    /// glob (e.g., `".example"` turns `.env.example` into `.env`).
+    #[serde(default)]
+    pub strip_suffix: Option<String>,
+    /// How file contents are rendered; copied as is by default.
+    #[serde(default)]
+    pub engine: TemplateEngine,
+    /// Permissions of the written file (e.g., `"0600"`); the source's
+    /// permissions are kept by default.
+    #[serde(default)]
+    pub mode: Option<FileMode>,
+    /// Permissions of the directories created to hold the target (e.g.,
+    /// `"0700"` for a secrets directory). Directories that already exist
+    /// are left alone.
+    #[serde(default)]
+    pub dir_mode: Option<FileMode>,
+    /// Note shown next to the entry's progress and plan lines (e.g.,
+    /// `"seed database (large, ~2 min)"`).
+    #[serde(default, alias = "comment")]
+    pub label: Option<String>,
+    /// Position in the combined plan; lower runs first (default 0).
+    #[serde(default)]
+    pub order: i32,
+    /// Condition that must hold for this template to be applied.
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Commands run before and after each file is rendered.
+    #[serde(flatten)]
+    pub hooks: EntryHooks,
+}
+
+/// Files to copy as they are committed at a git ref.
+///
+/// Useful when the new branch intentionally lacks files that exist on
+/// another branch (e.g., dev configs kept only on `main`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefCopyEntry {
+    /// Branch, tag, or commit to read from.
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// File or directory at that ref (relative to config directory).
+    pub path: String,
+    /// Where to write it in the target worktree; defaults to `path`.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Run files through the smudge filters and end-of-line conversion
+    /// that `.gitattributes` assigns them (e.g. Git LFS), as a checkout
+    /// would, instead of writing the committed blobs as-is.
+    #[serde(default)]
+    pub filters: bool,
+    /// Note shown next to the entry's progress and plan lines (e.g.,
+    /// `"seed database (large, ~2 min)"`).
+    #[serde(default, alias = "comment")]
+    pub label: Option<String>,
+    /// Position in the combined plan; lower runs first (default 0).
+    #[serde(default)]
+    pub order: i32,
+    /// Condition that must hold for this entry to be applied.
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Commands run before and after each file is written.
+    #[serde(flatten)]
+    pub hooks: EntryHooks,
+}
+
+/// Template engine used to render a template's files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEngine {
+    /// Copy files unchanged.
+    #[default]
+    Copy,
+    /// Replace `${NAME}` with a template variable or environment variable.
+    Simple,
+    /// Render with Handlebars (`{{NAME}}`).
+    Handlebars,
+    /// Render with Tera (`{{ NAME }}`, Jinja2-like).
+    Tera,
+}
+
+/// Condition gating an operation entry, evaluated during planning.
+///
+/// Every field that is set must hold for the entry to apply; an empty
+/// condition always holds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    /// Apply only if this path does not exist in the target worktree
+    /// (relative to config directory, or to the repo root with a leading `/`).
+    #[serde(default)]
+    pub unless_exists: Option<String>,
+    /// Apply only if this environment variable is set and non-empty.
+    #[serde(default)]
+    pub if_env: Option<String>,
+    /// Apply only if this shell command exits successfully.
+    #[serde(default)]
+    pub if_command: Option<String>,
+}
+
+impl Condition {
+    /// Whether no condition fields are set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.unless_exists.is_none() && self.if_env.is_none() && self.if_command.is_none()
+    }
+}
+
+/// Shell commands run around the operations of a single entry.
+///
+/// Both run through `sh -c` in the target worktree, with the operation's
+/// paths in `WORKTREE_SETUP_*` environment variables. `before` runs only
+/// when the operation is about to run and `after` only when it created or
+/// overwrote something; either failing fails the operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryHooks {
+    /// Command run before the operation (e.g., stopping a watcher before
+    /// a database file is overwritten).
+    #[serde(default)]
+    pub before: Option<String>,
+    /// Command run after the operation (e.g., `direnv allow` after copying
+    /// `.envrc`).
+    #[serde(default)]
+    pub after: Option<String>,
+}
+
+impl EntryHooks {
+    /// Whether neither command is set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.before.is_none() && self.after.is_none()
+    }
+}
+
+/// A path entry in `symlinks`, `copy`, `overwrite`, or `copyGlob`.
+///
+/// Either a plain path string, or a table with a `path`, condition fields,
+/// an `order`, a `maxDepth`, a `label`, and `before`/`after` hooks (e.g.,
+/// `{ path = ".env", unlessExists = ".env.local" }`). `copy` and
+/// `overwrite` tables may also name a different `target` to copy to, with
+/// `source` accepted in place of `path` (e.g.,
+/// `{ source = "configs/dev.json", target = "config.json" }`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum OperationEntry {
+    /// Unconditional path.
+    Path(String),
+    /// Path applied only when its condition holds, at its `order`.
+    Conditional {
+        /// Path or glob pattern (relative to config directory).
+        #[serde(alias = "source")]
+        path: String,
+        /// Where to copy `path` to in the target worktree, if not the same
+        /// relative path (`copy` and `overwrite` only; not for globs).
+        #[serde(default)]
+        target: Option<String>,
+        /// Note shown next to the entry's progress and plan lines.
+        #[serde(default, alias = "comment")]
+        label: Option<String>,
+        /// Position in the combined plan; lower runs first (default 0).
+        #[serde(default)]
+        order: i32,
+        /// How many levels below a copied directory to descend; 1 copies
+        /// only its direct files. Unlimited by default.
+        #[serde(default, rename = "maxDepth")]
+        max_depth: Option<usize>,
+        /// Condition that must hold for the entry to apply.
+        #[serde(flatten)]
+        condition: Condition,
+        /// Commands run around the entry's operations.
+        #[serde(flatten)]
+        hooks: EntryHooks,
+    },
+}
+
+impl OperationEntry {
+    /// Path or glob pattern of this entry.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::Conditional { path, .. } => path,
+        }
+    }
+
+    /// Target path of this entry, if it differs from [`Self::path`].
+    #[must_use]
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::Conditional { target, .. } => target.as_deref(),
+        }
+    }
+
+    /// Note shown next to this entry's progress and plan lines, if any.
+    #[must_use]
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::Conditional { label, .. } => label.as_deref(),
+        }
+    }
+
+    /// Condition of this entry, if any.
+    #[must_use]
+    pub const fn condition(&self) -> Option<&Condition> {
+        match self {
+            Self::Path(_) => None,
+            Self::Conditional { condition, .. } => Some(condition),
+        }
+    }
+
+    /// Hooks of this entry, if any.
+    #[must_use]
+    pub const fn hooks(&self) -> Option<&EntryHooks> {
+        match self {
+            Self::Path(_) => None,
+            Self::Conditional { hooks, .. } => Some(hooks),
+        }
+    }
+
+    /// Position of this entry in the combined plan; lower runs first.
+    #[must_use]
+    pub const fn order(&self) -> i32 {
+        match self {
+            Self::Path(_) => 0,
+            Self::Conditional { order, .. } => *order,
+        }
+    }
+
+    /// How many levels below a copied directory to descend, if limited.
+    #[must_use]
+    pub const fn max_depth(&self) -> Option<usize> {
+        match self {
+            Self::Path(_) => None,
+            Self::Conditional { max_depth, .. } => *max_depth,
+        }
+    }
+}
+
+impl From<&str> for OperationEntry {
+    fn from(path: &str) -> Self {
+        Self::Path(path.to_string())
+    }
+}
+
+impl From<String> for OperationEntry {
+    fn from(path: String) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl PartialEq<&str> for OperationEntry {
+    fn eq(&self, other: &&str) -> bool {
+        self.condition().is_none_or(Condition::is_empty)
+            && self.hooks().is_none_or(EntryHooks::is_empty)
+            && self.order() == 0
+            && self.max_depth().is_none()
+            && self.target().is_none()
+            && self.label().is_none()
+            && self.path() == *other
+    }
+}
+
+/// Unix permission bits, written in config files as an octal string
+/// (`"0600"`, `"700"`) or a number (TOML `0o600`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(try_from = "RawFileMode", into = "String")]
+pub struct FileMode(pub u32);
+
+/// How a [`FileMode`] may be written in a config file.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum RawFileMode {
+    Bits(u32),
+    Octal(String),
+}
+
+impl TryFrom<RawFileMode> for FileMode {
+    type Error = String;
+
+    fn try_from(raw: RawFileMode) -> Result<Self, Self::Error> {
+        let bits = match raw {
+            RawFileMode::Bits(bits) => bits,
+            RawFileMode::Octal(text) => {
+                let digits = text.trim();
+                let digits = digits.strip_prefix("0o").unwrap_or(digits);
+                u32::from_str_radix(digits, 8)
+                    .map_err(|_| format!("invalid mode '{text}': expected octal like \"0600\""))?
+            }
+        };
+        if bits > 0o7777 {
+            return Err(format!("invalid mode {bits:o}: at most 4 octal digits"));
+        }
+        Ok(Self(bits))
+    }
+}
+
+impl From<FileMode> for String {
+    fn from(mode: FileMode) -> Self {
+        format!("{:04o}", mode.0)
+    }
+}
+
+/// A base config named in `extends`.
+///
+/// Either a URL or path string, or a table pinning a URL to the BLAKE3
+/// checksum of its content (e.g.,
+/// `{ url = "https://example.com/node.toml", checksum = "…" }`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ExtendsEntry {
+    /// `http(s)://` URL, or path relative to the extending config's directory.
+    Location(String),
+    /// URL whose content must hash to `checksum`.
+    Pinned {
+        /// `http(s)://` URL, or path relative to the extending config's
+        /// directory.
+        url: String,
+        /// Hex BLAKE3 hash the content must have.
+        #[serde(default)]
+        checksum: Option<String>,
+    },
+}
+
+impl ExtendsEntry {
+    /// URL or path of the base config.
+    #[must_use]
+    pub fn location(&self) -> &str {
+        match self {
+            Self::Location(location) | Self::Pinned { url: location, .. } => location,
+        }
+    }
+
+    /// Pinned checksum, if any.
+    #[must_use]
+    pub fn checksum(&self) -> Option<&str> {
+        match self {
+            Self::Location(_) => None,
+            Self::Pinned { checksum, .. } => checksum.as_deref(),
+        }
+    }
+}
+
+/// Deserialize a single value or a list of values into a list.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Schema of a field read with [`one_or_many`]: a single `T` or a list.
+fn one_or_many_schema<T: JsonSchema>(
+    generator: &mut schemars::SchemaGenerator,
+) -> schemars::Schema {
+    schemars::json_schema!({
+        "anyOf": [generator.subschema_for::<T>(), generator.subschema_for::<Vec<T>>()]
+    })
 }
 
 /// Worktree setup configuration.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    /// Base configs merged under this one, in order (see
+    /// [`Config::extend_from`]). Empty once the config has been loaded.
+    #[serde(default, deserialize_with = "one_or_many")]
+    #[schemars(schema_with = "one_or_many_schema::<ExtendsEntry>")]
+    pub extends: Vec<ExtendsEntry>,
+
+    /// Fragments spliced into this config, in order (see
+    /// [`Config::include_from`]). Empty once the config has been loaded.
+    #[serde(default, deserialize_with = "one_or_many")]
+    #[schemars(schema_with = "one_or_many_schema::<String>")]
+    pub include: Vec<String>,
+
     /// Human-readable description of this configuration.
     #[serde(default)]
     pub description: String,
 
+    /// Short display name, shown before the description.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Team or person maintaining this config (e.g. `@web-platform`).
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    /// Link to documentation about what this config sets up.
+    #[serde(default)]
+    pub docs_url: Option<String>,
+
+    /// Rough time a setup with this config takes (e.g. `"5 min"`).
+    #[serde(default)]
+    pub estimated_setup_time: Option<String>,
+
+    /// Free-form tags for filtering config lists (e.g. `frontend`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+
     /// Paths to symlink from the main worktree (relative to config directory).
     #[serde(default)]
-    pub symlinks: Vec<String>,
+    pub symlinks: Vec<OperationEntry>,
 
     /// Paths to copy only if they don't exist in target (relative to config directory).
     #[serde(default)]
-    pub copy: Vec<String>,
+    pub copy: Vec<OperationEntry>,
 
     /// Paths to always overwrite in target (relative to config directory).
     #[serde(default)]
-    pub overwrite: Vec<String>,
+    pub overwrite: Vec<OperationEntry>,
 
     /// Glob patterns to copy (relative to config directory).
     #[serde(default)]
-    pub copy_glob: Vec<String>,
+    pub copy_glob: Vec<OperationEntry>,
 
     /// Whether to copy unstaged/untracked files from main worktree.
+    ///
+    /// `None` inherits the root config's [`ConfigDefaults::copy_unstaged`],
+    /// and is treated as `false` when that is unset too.
     #[serde(default)]
-    pub copy_unstaged: bool,
+    pub copy_unstaged: Option<bool>,
+
+    /// Glob patterns (relative to config directory) limiting which
+    /// unstaged/untracked files `copy_unstaged` replicates for this config.
+    /// Empty means no limit beyond `setup.unstaged_scope`.
+    #[serde(default)]
+    pub unstaged_include: Vec<String>,
 
     /// Template file mappings (copy source to target if target doesn't exist).
     #[serde(default)]
     pub templates: Vec<TemplateMapping>,
 
+    /// Files copied from a git ref (skipped if the target exists).
+    #[serde(default)]
+    pub copy_from_ref: Vec<RefCopyEntry>,
+
     /// Commands to run after setup completes.
     #[serde(default)]
     pub post_setup: Vec<String>,
@@ -92,6 +516,232 @@ pub struct Config {
     /// Can also be set globally in the global config under `[security]`.
     #[serde(default)]
     pub allow_path_escape: Option<bool>,
+
+    /// Fail planning when a glob pattern cannot search part of the tree.
+    ///
+    /// Unreadable directories (and invalid patterns) are otherwise planned
+    /// as skipped operations with the error as the reason. `None` inherits
+    /// the root config's [`ConfigDefaults::strict_globs`].
+    #[serde(default)]
+    pub strict_globs: Option<bool>,
+
+    /// Repo-wide defaults for every other config.
+    ///
+    /// Only honored in the repository root's `worktree.config.toml` or
+    /// `worktree.config.ts`; see [`crate::apply_root_defaults`].
+    #[serde(default)]
+    pub defaults: ConfigDefaults,
+
+    /// Settings merged over this config only on Windows (see
+    /// [`Config::apply_os_section`]).
+    #[serde(default)]
+    pub windows: Option<Box<Self>>,
+
+    /// Settings merged over this config only on macOS.
+    #[serde(default)]
+    pub macos: Option<Box<Self>>,
+
+    /// Settings merged over this config only on Linux.
+    #[serde(default)]
+    pub linux: Option<Box<Self>>,
+
+    /// Keys of this config's file, and of the configs it extends or
+    /// includes, that no field reads.
+    #[serde(skip)]
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+impl Config {
+    /// Merge `base` under this config, as named in [`Self::extends`].
+    ///
+    /// Lists get `base`'s entries first; settings this config leaves unset
+    /// (including display fields, profiles, and `defaults`) come from
+    /// `base`. `base`'s paths are kept as written, so they resolve against
+    /// this config's directory.
+    pub fn extend_from(&mut self, base: Self) {
+        self.merge_under(base, true);
+    }
+
+    /// Splice `fragment` into this config, as named in [`Self::include`].
+    ///
+    /// Like [`Self::extend_from`], except that `fragment`'s list entries
+    /// come after this config's, as if they were written at the end of
+    /// each list.
+    pub fn include_from(&mut self, fragment: Self) {
+        self.merge_under(fragment, false);
+    }
+
+    /// Merge `base` under this config, with `base`'s list entries first if
+    /// `base_first` is set and last otherwise.
+    fn merge_under(&mut self, base: Self, base_first: bool) {
+        fn join<T>(list: &mut Vec<T>, mut base: Vec<T>, base_first: bool) {
+            if base_first {
+                base.append(list);
+                *list = base;
+            } else {
+                list.append(&mut base);
+            }
+        }
+
+        if self.description.is_empty() {
+            self.description = base.description;
+        }
+        self.name = self.name.take().or(base.name);
+        self.owner = self.owner.take().or(base.owner);
+        self.docs_url = self.docs_url.take().or(base.docs_url);
+        self.estimated_setup_time = self
+            .estimated_setup_time
+            .take()
+            .or(base.estimated_setup_time);
+        join(&mut self.tags, base.tags, base_first);
+
+        join(&mut self.symlinks, base.symlinks, base_first);
+        join(&mut self.copy, base.copy, base_first);
+        join(&mut self.overwrite, base.overwrite, base_first);
+        join(&mut self.copy_glob, base.copy_glob, base_first);
+        join(&mut self.templates, base.templates, base_first);
+        join(&mut self.copy_from_ref, base.copy_from_ref, base_first);
+        join(&mut self.post_setup, base.post_setup, base_first);
+        join(&mut self.clean, base.clean, base_first);
+        join(&mut self.clean_ignore, base.clean_ignore, base_first);
+        join(
+            &mut self.unstaged_include,
+            base.unstaged_include,
+            base_first,
+        );
+        join(&mut self.unknown_keys, base.unknown_keys, base_first);
+
+        self.copy_unstaged = self.copy_unstaged.or(base.copy_unstaged);
+        self.allow_path_escape = self.allow_path_escape.or(base.allow_path_escape);
+        self.strict_globs = self.strict_globs.or(base.strict_globs);
+
+        for (name, profile) in base.profiles {
+            self.profiles.entry(name).or_insert(profile);
+        }
+        self.defaults.extend_from(base.defaults);
+    }
+
+    /// Merge the section for the current OS ([`Self::windows`],
+    /// [`Self::macos`], or [`Self::linux`]) over this config, and drop the
+    /// sections for other systems.
+    ///
+    /// The section's list entries come after this config's, and settings it
+    /// sets win, as if this config extended it. Sections nested inside a
+    /// section are ignored.
+    pub fn apply_os_section(&mut self) {
+        self.apply_os_section_for(std::env::consts::OS);
+    }
+
+    fn apply_os_section_for(&mut self, os: &str) {
+        let windows = self.windows.take();
+        let macos = self.macos.take();
+        let linux = self.linux.take();
+        let section = match os {
+            "windows" => windows,
+            "macos" => macos,
+            "linux" => linux,
+            _ => None,
+        };
+        let Some(section) = section else {
+            return;
+        };
+
+        let mut merged = *section;
+        let mut extends = std::mem::take(&mut self.extends);
+        extends.append(&mut merged.extends);
+        let mut include = std::mem::take(&mut self.include);
+        include.append(&mut merged.include);
+        merged.extend_from(std::mem::take(self));
+        merged.extends = extends;
+        merged.include = include;
+        merged.windows = None;
+        merged.macos = None;
+        merged.linux = None;
+        *self = merged;
+    }
+
+    /// Label shown in config lists: `name: description`, or whichever of
+    /// the two is set.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self.name.as_deref() {
+            Some(name) if self.description.is_empty() => name.to_string(),
+            Some(name) => format!("{name}: {}", self.description),
+            None => self.description.clone(),
+        }
+    }
+
+    /// Owner, estimated setup time, docs link, and tags joined for display,
+    /// or `None` if none of them is set.
+    #[must_use]
+    pub fn metadata_summary(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            self.owner.as_ref().map(|owner| format!("owner: {owner}")),
+            self.estimated_setup_time
+                .as_ref()
+                .map(|time| format!("setup: ~{time}")),
+            self.docs_url.as_ref().map(|url| format!("docs: {url}")),
+            (!self.tags.is_empty()).then(|| format!("tags: {}", self.tags.join(", "))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+}
+
+/// The `defaults` table of the root config.
+///
+/// Per-config settings fill in every config that leaves them unset; the
+/// remaining fields are run policies that apply below profile defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDefaults {
+    /// Default for [`Config::copy_unstaged`].
+    pub copy_unstaged: Option<bool>,
+    /// Default for [`Config::allow_path_escape`].
+    pub allow_path_escape: Option<bool>,
+    /// Default for [`Config::strict_globs`].
+    pub strict_globs: Option<bool>,
+
+    /// Overwrite existing files during file operations.
+    pub overwrite_existing: Option<bool>,
+    /// Which post-setup commands to run (see [`ProfileDefaults::post_setup`]).
+    pub post_setup: Option<PostSetupMode>,
+    /// Post-setup commands to skip when `post_setup = "all"`.
+    #[serde(default)]
+    pub skip_post_setup: Vec<String>,
+}
+
+impl ConfigDefaults {
+    /// Whether no default is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Fill the defaults this table leaves unset from `base`.
+    pub fn extend_from(&mut self, base: Self) {
+        self.copy_unstaged = self.copy_unstaged.or(base.copy_unstaged);
+        self.allow_path_escape = self.allow_path_escape.or(base.allow_path_escape);
+        self.strict_globs = self.strict_globs.or(base.strict_globs);
+        self.overwrite_existing = self.overwrite_existing.or(base.overwrite_existing);
+        self.post_setup = self.post_setup.take().or(base.post_setup);
+        if self.skip_post_setup.is_empty() {
+            self.skip_post_setup = base.skip_post_setup;
+        }
+    }
+
+    /// The run policies, as profile defaults for a profile to override.
+    #[must_use]
+    pub fn policy(&self) -> ProfileDefaults {
+        ProfileDefaults {
+            overwrite_existing: self.overwrite_existing,
+            post_setup: self.post_setup.clone(),
+            skip_post_setup: self.skip_post_setup.clone(),
+            ..ProfileDefaults::default()
+        }
+    }
 }
 
 /// A loaded configuration with metadata.
@@ -108,7 +758,7 @@ pub struct LoadedConfig {
 }
 
 /// How the worktree should be created.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum CreationMethod {
     /// New branch, auto-named after the worktree directory.
@@ -122,7 +772,7 @@ pub enum CreationMethod {
 }
 
 /// Keyword for post-setup command behavior.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum PostSetupKeyword {
     /// Run all post-setup commands without prompting.
@@ -138,7 +788,7 @@ pub enum PostSetupKeyword {
 /// * `["cmd1", "cmd2"]` — run only these specific commands (exact match)
 ///
 /// When not set, the user is prompted interactively.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum PostSetupMode {
     /// `"all"` or `"none"` keyword.
@@ -151,7 +801,7 @@ pub enum PostSetupMode {
 ///
 /// All fields are `Option` — only set values are applied. Unset values
 /// fall through to CLI flags, interactive prompts, or builtin defaults.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileDefaults {
     /// Copy unstaged/untracked files from main worktree.
@@ -221,7 +871,7 @@ impl ProfileDefaults {
 }
 
 /// A profile definition as declared in a configuration file.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfileDefinition {
     /// Human-readable description.
@@ -236,6 +886,13 @@ pub struct ProfileDefinition {
     /// The declaring config is always implicitly included.
     #[serde(default)]
     pub configs: Vec<String>,
+    /// Entries of the declaring config to leave out while this profile is
+    /// selected (see [`crate::apply_profile_excludes`]).
+    ///
+    /// Glob patterns matched against each entry's path as written in the
+    /// config (`source` for templates).
+    #[serde(default)]
+    pub exclude: Vec<String>,
     /// Default settings for this profile.
     #[serde(default, flatten)]
     pub defaults: ProfileDefaults,
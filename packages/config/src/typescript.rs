@@ -0,0 +1,260 @@
+//! TypeScript declarations for script configs.
+//!
+//! [`Config::typescript_declarations`] renders the config schema
+//! ([`Config::json_schema`]) as a `.d.ts` file, so `worktree.config.ts`
+//! files are type checked against the same model the loaders deserialize.
+//! The types are declared in a global `WorktreeSetup` namespace, next to
+//! the global `defineConfig` helper every config evaluator defines, so a
+//! config needs no import that a runtime would have to resolve.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fmt::Write as _;
+
+use serde_json::Value;
+
+use crate::types::Config;
+
+/// Namespace holding the config types.
+const NAMESPACE: &str = "WorktreeSetup";
+
+/// First lines of the declarations file.
+const HEADER: &str = "\
+// Types for worktree-setup TypeScript and JavaScript configs.
+// Generated by `worktree-setup schema --typescript`; do not edit.
+
+";
+
+/// Declaration of the `defineConfig` helper.
+const DEFINE_CONFIG: &str = "
+/**
+ * Returns `config` unchanged, so `export default defineConfig({ ... })`
+ * type checks the config. worktree-setup defines it while evaluating
+ * configs.
+ */
+declare function defineConfig(config: WorktreeSetup.Config): WorktreeSetup.Config;
+";
+
+impl Config {
+    /// TypeScript declarations (a `.d.ts` file) of a config and the
+    /// `defineConfig` helper.
+    ///
+    /// Generated from [`Self::json_schema`]: the config is
+    /// `WorktreeSetup.Config` and every type it uses is declared next to
+    /// it under the same name as in the schema.
+    #[must_use]
+    pub fn typescript_declarations() -> String {
+        let schema = Self::json_schema();
+        let mut out = String::from(HEADER);
+        let _ = writeln!(out, "declare namespace {NAMESPACE} {{");
+        declaration(&mut out, "Config", &schema);
+        if let Some(defs) = schema.get("$defs").and_then(Value::as_object) {
+            for (name, def) in defs {
+                out.push('\n');
+                declaration(&mut out, name, def);
+            }
+        }
+        out.push_str("}\n");
+        out.push_str(DEFINE_CONFIG);
+        out
+    }
+}
+
+/// Append the declaration of the type `name` described by `schema`: an
+/// interface for tables, a type alias for anything else.
+fn declaration(out: &mut String, name: &str, schema: &Value) {
+    let indent = "  ";
+    doc_comment(out, schema, indent);
+    if schema.get("properties").is_some() {
+        let _ = writeln!(out, "{indent}export interface {name} {{");
+        properties(out, schema, "    ");
+        let _ = writeln!(out, "{indent}}}");
+    } else {
+        let _ = writeln!(
+            out,
+            "{indent}export type {name} = {};",
+            type_of(schema, indent)
+        );
+    }
+}
+
+/// Append the properties of the table `schema`, one per line at `indent`;
+/// those not listed as required are optional.
+fn properties(out: &mut String, schema: &Value, indent: &str) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (key, property) in properties {
+        doc_comment(out, property, indent);
+        let optional = if required.contains(&key.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        let _ = writeln!(
+            out,
+            "{indent}{}{optional}: {};",
+            property_name(key),
+            type_of(property, indent)
+        );
+    }
+}
+
+/// Append the schema's description as a doc comment at `indent`.
+fn doc_comment(out: &mut String, schema: &Value, indent: &str) {
+    let Some(description) = schema.get("description").and_then(Value::as_str) else {
+        return;
+    };
+    let description = description.replace("*/", "*\\/");
+    let lines: Vec<&str> = description.lines().collect();
+    if let [line] = lines.as_slice() {
+        let _ = writeln!(out, "{indent}/** {line} */");
+        return;
+    }
+    let _ = writeln!(out, "{indent}/**");
+    for line in lines {
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent} *");
+        } else {
+            let _ = writeln!(out, "{indent} * {line}");
+        }
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+/// The TypeScript type of a value matching `schema`; object literal types
+/// are laid out for a declaration at `indent`.
+fn type_of(schema: &Value, indent: &str) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        // `#` is the config itself, as in the per-OS sections.
+        return reference
+            .strip_prefix("#/$defs/")
+            .unwrap_or("Config")
+            .to_string();
+    }
+    // JSON literals are TypeScript literal types as well.
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(ToString::to_string));
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+            return union(variants.iter().map(|variant| type_of(variant, indent)));
+        }
+    }
+    match schema.get("type") {
+        Some(Value::String(name)) => named_type(name, schema, indent),
+        Some(Value::Array(names)) => union(
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|name| named_type(name, schema, indent)),
+        ),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// The TypeScript type of a value of the JSON Schema type `name`.
+fn named_type(name: &str, schema: &Value, indent: &str) -> String {
+    match name {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let item = schema
+                .get("items")
+                .map_or_else(|| "unknown".to_string(), |items| type_of(items, indent));
+            if item.contains(' ') {
+                format!("({item})[]")
+            } else {
+                format!("{item}[]")
+            }
+        }
+        "object" if schema.get("properties").is_some() => {
+            let mut literal = String::from("{\n");
+            properties(&mut literal, schema, &format!("{indent}  "));
+            let _ = write!(literal, "{indent}}}");
+            literal
+        }
+        "object" => {
+            let value = schema
+                .get("additionalProperties")
+                .filter(|value| value.is_object())
+                .map_or_else(|| "unknown".to_string(), |value| type_of(value, indent));
+            format!("Record<string, {value}>")
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+/// `types` joined into a union, without repeats.
+fn union(types: impl Iterator<Item = String>) -> String {
+    let mut members: Vec<String> = Vec::new();
+    for member in types {
+        if !members.contains(&member) {
+            members.push(member);
+        }
+    }
+    members.join(" | ")
+}
+
+/// `key` as a property name, quoted unless it is an identifier.
+fn property_name(key: &str) -> String {
+    let is_identifier = key.chars().enumerate().all(|(index, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (index > 0 && c.is_ascii_digit())
+    });
+    if is_identifier && !key.is_empty() {
+        key.to_string()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_declarations_are_current() {
+        assert!(
+            Config::typescript_declarations() == include_str!("../worktree-setup.d.ts"),
+            "packages/config/worktree-setup.d.ts is out of date; regenerate it with \
+             `cargo run -- schema --typescript > packages/config/worktree-setup.d.ts`"
+        );
+    }
+
+    #[test]
+    fn test_typescript_declarations() {
+        let declarations = Config::typescript_declarations();
+        assert!(declarations.contains("  export interface Config {\n"));
+        assert!(declarations.contains("    copyGlob?: OperationEntry[];\n"));
+        assert!(declarations.contains("    extends?: ExtendsEntry | ExtendsEntry[];\n"));
+        assert!(declarations.contains("    windows?: Config | null;\n"));
+        assert!(declarations.contains("    profiles?: Record<string, ProfileDefinition>;\n"));
+        assert!(declarations.contains(
+            "  export type TemplateEngine = \"copy\" | \"simple\" | \"handlebars\" | \"tera\";\n"
+        ));
+        assert!(declarations.contains("    source: string;\n"));
+
+        // The declarations parse as TypeScript.
+        crate::ts_loader::strip_ts_types(&declarations).unwrap();
+    }
+
+    #[test]
+    fn test_property_name() {
+        assert_eq!(property_name("copyGlob"), "copyGlob");
+        assert_eq!(property_name("$schema"), "$schema");
+        assert_eq!(property_name("dir-mode"), "\"dir-mode\"");
+        assert_eq!(property_name("1st"), "\"1st\"");
+    }
+}
@@ -0,0 +1,212 @@
+//! Multi-repo workspace definitions.
+//!
+//! A workspace groups several related repositories that branch in lockstep.
+//! Workspaces are defined in `$XDG_CONFIG_HOME/worktree-setup/workspaces.toml`
+//! (typically `~/.config/worktree-setup/workspaces.toml`).
+//!
+//! # Example
+//!
+//! ```toml
+//! [workspaces.platform]
+//! description = "API + web app"
+//! root = "~/worktrees"
+//!
+//! [[workspaces.platform.repos]]
+//! path = "~/code/api"
+//! profile = ["dev"]
+//!
+//! [[workspaces.platform.repos]]
+//! path = "~/code/web"
+//! base_branch = "develop"
+//! ```
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ConfigError;
+
+/// A repository that is part of a workspace.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceRepo {
+    /// Path to the repository's main worktree (`~` is expanded).
+    pub path: PathBuf,
+
+    /// Profiles used to select configs and defaults in this repo.
+    ///
+    /// When empty, every config in the repo is applied.
+    #[serde(default)]
+    pub profile: Vec<String>,
+
+    /// Branch to base new branches on (defaults to the repo's current HEAD).
+    #[serde(default)]
+    pub base_branch: Option<String>,
+}
+
+/// A named group of repositories.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+
+    /// Directory to create worktrees under, as `<root>/<name>/<repo-dir>`.
+    ///
+    /// When unset, each worktree is created next to its repository as
+    /// `<repo-dir>-<name>`.
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+
+    /// Repositories in this workspace.
+    #[serde(default)]
+    pub repos: Vec<WorkspaceRepo>,
+}
+
+impl Workspace {
+    /// Path of the worktree named `name` for `repo`.
+    #[must_use]
+    pub fn worktree_path(&self, repo: &WorkspaceRepo, name: &str) -> PathBuf {
+        let repo_path = expand_home(&repo.path);
+        let repo_dir = repo_path
+            .file_name()
+            .map_or_else(|| "repo".into(), |n| n.to_string_lossy().to_string());
+
+        self.root.as_ref().map_or_else(
+            || {
+                repo_path
+                    .parent()
+                    .unwrap_or(&repo_path)
+                    .join(format!("{repo_dir}-{name}"))
+            },
+            |root| expand_home(root).join(name).join(&repo_dir),
+        )
+    }
+}
+
+/// Contents of `workspaces.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspacesFile {
+    /// Workspaces keyed by name.
+    #[serde(default)]
+    pub workspaces: BTreeMap<String, Workspace>,
+}
+
+/// Return the path to the workspaces file.
+///
+/// Returns `None` if the platform config directory cannot be determined.
+#[must_use]
+pub fn workspaces_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("worktree-setup").join("workspaces.toml"))
+}
+
+/// Load workspace definitions, returning an empty set if the file is missing.
+///
+/// # Errors
+///
+/// * If the file exists but cannot be read
+/// * If the file exists but contains invalid TOML
+pub fn load_workspaces(path: &Path) -> Result<WorkspacesFile, ConfigError> {
+    if !path.is_file() {
+        return Ok(WorkspacesFile::default());
+    }
+
+    let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::ReadError {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    toml::from_str(&contents).map_err(|source| ConfigError::TomlParseError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Expand a leading `~` to the home directory.
+#[must_use]
+pub fn expand_home(path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_parse_workspaces_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[workspaces.platform]
+description = "API + web"
+root = "/tmp/worktrees"
+
+[[workspaces.platform.repos]]
+path = "/code/api"
+profile = ["dev"]
+
+[[workspaces.platform.repos]]
+path = "/code/web"
+base_branch = "develop"
+"#
+        )
+        .unwrap();
+
+        let file = load_workspaces(file.path()).unwrap();
+        let workspace = &file.workspaces["platform"];
+
+        assert_eq!(workspace.description, "API + web");
+        assert_eq!(workspace.repos.len(), 2);
+        assert_eq!(workspace.repos[0].profile, vec!["dev"]);
+        assert_eq!(workspace.repos[1].base_branch.as_deref(), Some("develop"));
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let file = load_workspaces(Path::new("/nonexistent/workspaces.toml")).unwrap();
+        assert!(file.workspaces.is_empty());
+    }
+
+    #[test]
+    fn test_worktree_path_with_root() {
+        let workspace = Workspace {
+            root: Some(PathBuf::from("/tmp/worktrees")),
+            ..Default::default()
+        };
+        let repo = WorkspaceRepo {
+            path: PathBuf::from("/code/api"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            workspace.worktree_path(&repo, "feature-x"),
+            PathBuf::from("/tmp/worktrees/feature-x/api")
+        );
+    }
+
+    #[test]
+    fn test_worktree_path_without_root_is_sibling() {
+        let workspace = Workspace::default();
+        let repo = WorkspaceRepo {
+            path: PathBuf::from("/code/api"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            workspace.worktree_path(&repo, "feature-x"),
+            PathBuf::from("/code/api-feature-x")
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! YAML configuration file loader.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::ConfigError;
+use crate::schema::check_unknown_keys;
+use crate::types::Config;
+
+/// Load a YAML configuration file (`.yaml` or `.yml`).
+///
+/// Fields use the same camelCase names as TOML configs.
+///
+/// # Arguments
+///
+/// * `path` - Path to the YAML configuration file
+///
+/// # Errors
+///
+/// * If the file cannot be read
+/// * If the file cannot be parsed as YAML
+pub fn load_yaml_config(path: &Path) -> Result<Config, ConfigError> {
+    log::debug!("Loading YAML config from {}", path.display());
+
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::ReadError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    // An empty file is an empty config, as it is for TOML.
+    if content.trim().is_empty() {
+        return Ok(Config::default());
+    }
+
+    let parse_error = |e| ConfigError::YamlParseError {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    let mut config: Config = serde_yaml::from_str(&content).map_err(parse_error)?;
+    let raw = serde_yaml::from_str(&content).map_err(parse_error)?;
+    check_unknown_keys(path, &raw, &mut config, Some(&content))?;
+
+    log::debug!("Loaded config: {:?}", config.description);
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileMode;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_load_yaml_config() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+description: Test config
+symlinks: [data/cache]
+copy:
+  - .env.local
+overwrite: [config.json]
+copyGlob: ["**/*.env"]
+copyUnstaged: true
+postSetup:
+  - npm install
+templates:
+  - source: .env.example
+    target: .env
+    mode: "0600"
+"#
+        )
+        .unwrap();
+
+        let config = load_yaml_config(file.path()).unwrap();
+
+        assert_eq!(config.description, "Test config");
+        assert_eq!(config.symlinks, vec!["data/cache"]);
+        assert_eq!(config.copy, vec![".env.local"]);
+        assert_eq!(config.overwrite, vec!["config.json"]);
+        assert_eq!(config.copy_glob, vec!["**/*.env"]);
+        assert_eq!(config.copy_unstaged, Some(true));
+        assert_eq!(config.post_setup, vec!["npm install"]);
+        assert_eq!(config.templates[0].target, ".env");
+        assert_eq!(config.templates[0].mode, Some(FileMode(0o600)));
+    }
+
+    #[test]
+    fn test_load_yaml_config_empty_and_invalid() {
+        let empty = NamedTempFile::new().unwrap();
+        assert!(load_yaml_config(empty.path()).unwrap().copy.is_empty());
+
+        let mut invalid = NamedTempFile::new().unwrap();
+        writeln!(invalid, "copy: [.env").unwrap();
+        let err = load_yaml_config(invalid.path()).unwrap_err();
+        assert_eq!(err.code(), "WTS-CFG-020");
+    }
+}
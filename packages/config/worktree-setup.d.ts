@@ -0,0 +1,401 @@
+// Types for worktree-setup TypeScript and JavaScript configs.
+// Generated by `worktree-setup schema --typescript`; do not edit.
+
+declare namespace WorktreeSetup {
+  /** Worktree setup configuration. */
+  export interface Config {
+    /**
+     * Allow file operations to reference paths outside the worktree boundary.
+     *
+     * * `None` (default when omitted) — inherit from the global config's
+     *   `[security] allow_path_escape` setting.
+     * * `Some(true)` — allow paths to escape the worktree boundary for
+     *   this config, overriding a global `false`.
+     * * `Some(false)` — enforce containment for this config, overriding a
+     *   global `true`.
+     *
+     * Can also be set globally in the global config under `[security]`.
+     */
+    allowPathEscape?: boolean | null;
+    /**
+     * Paths and glob patterns to delete when running `clean`.
+     *
+     * Supports exact relative paths (e.g., `"node_modules"`, `".turbo"`) and
+     * glob patterns (e.g., `"**\/dist"`, `"*.log"`). Paths are relative to the
+     * config directory. Resolved paths must remain within the target worktree
+     * directory (containment check).
+     */
+    clean?: string[];
+    /**
+     * Paths and glob patterns to preserve when running `clean`.
+     *
+     * These patterns use the same path rules as [`Self::clean`]. Any clean
+     * match that equals, contains, or is contained by an ignored path is
+     * excluded from deletion so ignored content is never removed via an
+     * ancestor clean match.
+     */
+    cleanIgnore?: string[];
+    /** Paths to copy only if they don't exist in target (relative to config directory). */
+    copy?: OperationEntry[];
+    /** Files copied from a git ref (skipped if the target exists). */
+    copyFromRef?: RefCopyEntry[];
+    /** Glob patterns to copy (relative to config directory). */
+    copyGlob?: OperationEntry[];
+    /**
+     * Whether to copy unstaged/untracked files from main worktree.
+     *
+     * `None` inherits the root config's [`ConfigDefaults::copy_unstaged`],
+     * and is treated as `false` when that is unset too.
+     */
+    copyUnstaged?: boolean | null;
+    /**
+     * Repo-wide defaults for every other config.
+     *
+     * Only honored in the repository root's `worktree.config.toml` or
+     * `worktree.config.ts`; see [`crate::apply_root_defaults`].
+     */
+    defaults?: ConfigDefaults;
+    /** Human-readable description of this configuration. */
+    description?: string;
+    /** Link to documentation about what this config sets up. */
+    docsUrl?: string | null;
+    /** Rough time a setup with this config takes (e.g. `"5 min"`). */
+    estimatedSetupTime?: string | null;
+    /**
+     * Base configs merged under this one, in order (see
+     * [`Config::extend_from`]). Empty once the config has been loaded.
+     */
+    extends?: ExtendsEntry | ExtendsEntry[];
+    /**
+     * Fragments spliced into this config, in order (see
+     * [`Config::include_from`]). Empty once the config has been loaded.
+     */
+    include?: string | string[];
+    /** Settings merged over this config only on Linux. */
+    linux?: Config | null;
+    /** Settings merged over this config only on macOS. */
+    macos?: Config | null;
+    /** Short display name, shown before the description. */
+    name?: string | null;
+    /** Paths to always overwrite in target (relative to config directory). */
+    overwrite?: OperationEntry[];
+    /** Team or person maintaining this config (e.g. `@web-platform`). */
+    owner?: string | null;
+    /** Commands to run after setup completes. */
+    postSetup?: string[];
+    /**
+     * Profile names this config belongs to.
+     *
+     * Keys are profile names. When a user runs `--profile foo`, any config
+     * that has a `"foo"` key here is auto-selected. The `ProfileDefinition`
+     * value carries optional defaults and additional config patterns.
+     */
+    profiles?: Record<string, ProfileDefinition>;
+    /**
+     * Fail planning when a glob pattern cannot search part of the tree.
+     *
+     * Unreadable directories (and invalid patterns) are otherwise planned
+     * as skipped operations with the error as the reason. `None` inherits
+     * the root config's [`ConfigDefaults::strict_globs`].
+     */
+    strictGlobs?: boolean | null;
+    /** Paths to symlink from the main worktree (relative to config directory). */
+    symlinks?: OperationEntry[];
+    /** Free-form tags for filtering config lists (e.g. `frontend`). */
+    tags?: string[];
+    /** Template file mappings (copy source to target if target doesn't exist). */
+    templates?: TemplateMapping[];
+    /**
+     * Glob patterns (relative to config directory) limiting which
+     * unstaged/untracked files `copy_unstaged` replicates for this config.
+     * Empty means no limit beyond `setup.unstaged_scope`.
+     */
+    unstagedInclude?: string[];
+    /**
+     * Settings merged over this config only on Windows (see
+     * [`Config::apply_os_section`]).
+     */
+    windows?: Config | null;
+  }
+
+  /**
+   * The `defaults` table of the root config.
+   *
+   * Per-config settings fill in every config that leaves them unset; the
+   * remaining fields are run policies that apply below profile defaults.
+   */
+  export interface ConfigDefaults {
+    /** Default for [`Config::allow_path_escape`]. */
+    allowPathEscape?: boolean | null;
+    /** Default for [`Config::copy_unstaged`]. */
+    copyUnstaged?: boolean | null;
+    /** Overwrite existing files during file operations. */
+    overwriteExisting?: boolean | null;
+    /** Which post-setup commands to run (see [`ProfileDefaults::post_setup`]). */
+    postSetup?: PostSetupMode | null;
+    /** Post-setup commands to skip when `post_setup = "all"`. */
+    skipPostSetup?: string[];
+    /** Default for [`Config::strict_globs`]. */
+    strictGlobs?: boolean | null;
+  }
+
+  /** How the worktree should be created. */
+  export type CreationMethod = "auto" | "current" | "remote" | "detach";
+
+  /**
+   * A base config named in `extends`.
+   *
+   * Either a URL or path string, or a table pinning a URL to the BLAKE3
+   * checksum of its content (e.g.,
+   * `{ url = "https://example.com/node.toml", checksum = "…" }`).
+   */
+  export type ExtendsEntry = string | {
+    /** Hex BLAKE3 hash the content must have. */
+    checksum?: string | null;
+    /**
+     * `http(s)://` URL, or path relative to the extending config's
+     * directory.
+     */
+    url: string;
+  };
+
+  /**
+   * Unix permission bits, written in config files as an octal string
+   * (`"0600"`, `"700"`) or a number (TOML `0o600`).
+   */
+  export type FileMode = number | string;
+
+  /**
+   * A path entry in `symlinks`, `copy`, `overwrite`, or `copyGlob`.
+   *
+   * Either a plain path string, or a table with a `path`, condition fields,
+   * an `order`, a `maxDepth`, a `label`, and `before`/`after` hooks (e.g.,
+   * `{ path = ".env", unlessExists = ".env.local" }`). `copy` and
+   * `overwrite` tables may also name a different `target` to copy to, with
+   * `source` accepted in place of `path` (e.g.,
+   * `{ source = "configs/dev.json", target = "config.json" }`).
+   */
+  export type OperationEntry = string | {
+    /**
+     * Command run after the operation (e.g., `direnv allow` after copying
+     * `.envrc`).
+     */
+    after?: string | null;
+    /**
+     * Command run before the operation (e.g., stopping a watcher before
+     * a database file is overwritten).
+     */
+    before?: string | null;
+    /** Apply only if this shell command exits successfully. */
+    ifCommand?: string | null;
+    /** Apply only if this environment variable is set and non-empty. */
+    ifEnv?: string | null;
+    /** Note shown next to the entry's progress and plan lines. */
+    label?: string | null;
+    /**
+     * How many levels below a copied directory to descend; 1 copies
+     * only its direct files. Unlimited by default.
+     */
+    maxDepth?: number | null;
+    /** Position in the combined plan; lower runs first (default 0). */
+    order?: number;
+    /** Path or glob pattern (relative to config directory). */
+    path: string;
+    /**
+     * Where to copy `path` to in the target worktree, if not the same
+     * relative path (`copy` and `overwrite` only; not for globs).
+     */
+    target?: string | null;
+    /**
+     * Apply only if this path does not exist in the target worktree
+     * (relative to config directory, or to the repo root with a leading `/`).
+     */
+    unlessExists?: string | null;
+  };
+
+  /** Keyword for post-setup command behavior. */
+  export type PostSetupKeyword = "all" | "none";
+
+  /**
+   * Controls which post-setup commands to run.
+   *
+   * * `"all"` — run all commands (optionally filtered by `skipPostSetup`)
+   * * `"none"` — skip all commands
+   * * `["cmd1", "cmd2"]` — run only these specific commands (exact match)
+   *
+   * When not set, the user is prompted interactively.
+   */
+  export type PostSetupMode = PostSetupKeyword | string[];
+
+  /** A profile definition as declared in a configuration file. */
+  export interface ProfileDefinition {
+    /** Skip the "Create worktree?" confirmation and create automatically. */
+    autoCreate?: boolean | null;
+    /** Base branch for new worktree branches. */
+    baseBranch?: string | null;
+    /**
+     * Additional config file patterns to auto-select.
+     *
+     * Patterns without a leading `/` are matched relative to the declaring
+     * config's directory. Patterns with a leading `/` are matched relative
+     * to the repository root. Uses glob syntax (e.g., `"*.config.toml"`).
+     *
+     * The declaring config is always implicitly included.
+     */
+    configs?: string[];
+    /** Copy unstaged/untracked files from main worktree. */
+    copyUnstaged?: boolean | null;
+    /** How to create the worktree (skips the creation method picker). */
+    creationMethod?: CreationMethod | null;
+    /** Human-readable description. */
+    description?: string;
+    /**
+     * Entries of the declaring config to leave out while this profile is
+     * selected (see [`crate::apply_profile_excludes`]).
+     *
+     * Glob patterns matched against each entry's path as written in the
+     * config (`source` for templates).
+     */
+    exclude?: string[];
+    /** Always create a new branch (auto-named after worktree directory). */
+    newBranch?: boolean | null;
+    /** Overwrite existing files during file operations. */
+    overwriteExisting?: boolean | null;
+    /**
+     * Which post-setup commands to run.
+     *
+     * * `"all"` — run all without prompting
+     * * `"none"` — skip all without prompting
+     * * `["cmd1", "cmd2"]` — run only these (exact match), no prompt
+     * * Not set — prompt the user
+     */
+    postSetup?: PostSetupMode | null;
+    /** Remote name to use for remote branch operations. */
+    remote?: string | null;
+    /**
+     * Specific post-setup commands to skip (exact match).
+     *
+     * Only meaningful when `post_setup = "all"`. Commands listed here
+     * are excluded from the set of commands that would otherwise run.
+     */
+    skipPostSetup?: string[];
+  }
+
+  /**
+   * Files to copy as they are committed at a git ref.
+   *
+   * Useful when the new branch intentionally lacks files that exist on
+   * another branch (e.g., dev configs kept only on `main`).
+   */
+  export interface RefCopyEntry {
+    /**
+     * Command run after the operation (e.g., `direnv allow` after copying
+     * `.envrc`).
+     */
+    after?: string | null;
+    /**
+     * Command run before the operation (e.g., stopping a watcher before
+     * a database file is overwritten).
+     */
+    before?: string | null;
+    /**
+     * Run files through the smudge filters and end-of-line conversion
+     * that `.gitattributes` assigns them (e.g. Git LFS), as a checkout
+     * would, instead of writing the committed blobs as-is.
+     */
+    filters?: boolean;
+    /** Apply only if this shell command exits successfully. */
+    ifCommand?: string | null;
+    /** Apply only if this environment variable is set and non-empty. */
+    ifEnv?: string | null;
+    /**
+     * Note shown next to the entry's progress and plan lines (e.g.,
+     * `"seed database (large, ~2 min)"`).
+     */
+    label?: string | null;
+    /** Position in the combined plan; lower runs first (default 0). */
+    order?: number;
+    /** File or directory at that ref (relative to config directory). */
+    path: string;
+    /** Branch, tag, or commit to read from. */
+    ref: string;
+    /** Where to write it in the target worktree; defaults to `path`. */
+    target?: string | null;
+    /**
+     * Apply only if this path does not exist in the target worktree
+     * (relative to config directory, or to the repo root with a leading `/`).
+     */
+    unlessExists?: string | null;
+  }
+
+  /** Template engine used to render a template's files. */
+  export type TemplateEngine = "copy" | "simple" | "handlebars" | "tera";
+
+  /**
+   * A template file mapping from source to target.
+   *
+   * `source` may also be a directory or a glob pattern, in which case every
+   * matched file is copied into the `target` directory, keeping its path
+   * relative to the source directory (or the glob's literal prefix).
+   */
+  export interface TemplateMapping {
+    /**
+     * Command run after the operation (e.g., `direnv allow` after copying
+     * `.envrc`).
+     */
+    after?: string | null;
+    /**
+     * Command run before the operation (e.g., stopping a watcher before
+     * a database file is overwritten).
+     */
+    before?: string | null;
+    /**
+     * Permissions of the directories created to hold the target (e.g.,
+     * `"0700"` for a secrets directory). Directories that already exist
+     * are left alone.
+     */
+    dirMode?: FileMode | null;
+    /** How file contents are rendered; copied as is by default. */
+    engine?: TemplateEngine;
+    /** Apply only if this shell command exits successfully. */
+    ifCommand?: string | null;
+    /** Apply only if this environment variable is set and non-empty. */
+    ifEnv?: string | null;
+    /**
+     * Note shown next to the entry's progress and plan lines (e.g.,
+     * `"seed database (large, ~2 min)"`).
+     */
+    label?: string | null;
+    /**
+     * Permissions of the written file (e.g., `"0600"`); the source's
+     * permissions are kept by default.
+     */
+    mode?: FileMode | null;
+    /** Position in the combined plan; lower runs first (default 0). */
+    order?: number;
+    /** Source file, directory, or glob pattern (relative to config directory). */
+    source: string;
+    /**
+     * Suffix removed from each file name when `source` is a directory or
+     * glob (e.g., `".example"` turns `.env.example` into `.env`).
+     */
+    stripSuffix?: string | null;
+    /**
+     * Target file path, or target directory for directory/glob sources
+     * (relative to config directory).
+     */
+    target: string;
+    /**
+     * Apply only if this path does not exist in the target worktree
+     * (relative to config directory, or to the repo root with a leading `/`).
+     */
+    unlessExists?: string | null;
+  }
+}
+
+/**
+ * Returns `config` unchanged, so `export default defineConfig({ ... })`
+ * type checks the config. worktree-setup defines it while evaluating
+ * configs.
+ */
+declare function defineConfig(config: WorktreeSetup.Config): WorktreeSetup.Config;
@@ -5,20 +5,48 @@
 #![allow(clippy::multiple_crate_versions)]
 
 use std::fs;
-use std::path::Path;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 use rayon::prelude::*;
 
 use crate::error::CopyError;
 use crate::progress::{CopyProgress, ProgressTracker};
 
+/// Single files at least this large are streamed in chunks, reporting
+/// byte progress, when they cannot be reflinked.
+///
+/// Smaller files are copied in one call; they finish quickly enough that a
+/// silent wait does not look like a hang.
+pub const STREAMING_COPY_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Chunk size for streaming copies, and so how often they report progress.
+const STREAM_CHUNK_BYTES: usize = 1024 * 1024;
+
 /// Result of a copy operation.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CopyResult {
     /// Files were copied successfully.
     Created {
-        /// Number of files copied.
+        /// Number of files copied (including symlinks).
         files_copied: u64,
+        /// How many of `files_copied` were symlinks recreated as links
+        /// rather than followed.
+        symlinks_copied: u64,
+        /// Number of entries left out by the copy filter; a filtered
+        /// directory counts once.
+        files_filtered: u64,
+        /// Sockets and device nodes that cannot be copied and were left out.
+        specials_skipped: u64,
+        /// Files deleted from the source between enumeration and copying
+        /// (e.g. by a build running in the source worktree) and so left
+        /// out.
+        files_vanished: u64,
+        /// How a single file was created; `None` for directory copies,
+        /// whose files can each use a different mechanism.
+        method: Option<CloneMethod>,
     },
     /// Target already exists, operation skipped.
     Exists,
@@ -26,15 +54,52 @@ pub enum CopyResult {
     SourceNotFound,
 }
 
+/// What kind of filesystem entry a [`FileEntry`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A regular file, copied by content.
+    File,
+    /// A symlink, recreated as a link.
+    Symlink,
+    /// A named pipe, recreated as an empty pipe.
+    Fifo,
+    /// A socket or device node, which cannot be copied and is skipped.
+    Special,
+}
+
+impl EntryKind {
+    /// Classify a file type reported by a directory walk.
+    #[must_use]
+    pub fn of(file_type: fs::FileType) -> Self {
+        if file_type.is_symlink() {
+            return Self::Symlink;
+        }
+        if file_type.is_file() {
+            return Self::File;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return Self::Fifo;
+            }
+            if file_type.is_socket() || file_type.is_block_device() || file_type.is_char_device() {
+                return Self::Special;
+            }
+        }
+        Self::File
+    }
+}
+
 /// Entry collected during directory enumeration.
 #[derive(Debug, Clone)]
-struct FileEntry {
+pub struct FileEntry {
     /// Source path.
-    source: std::path::PathBuf,
+    pub source: std::path::PathBuf,
     /// Target path.
-    target: std::path::PathBuf,
-    /// Whether this is a symlink.
-    is_symlink: bool,
+    pub target: std::path::PathBuf,
+    /// What kind of entry this is.
+    pub kind: EntryKind,
 }
 
 /// Copy a single file with progress callback.
@@ -81,8 +146,15 @@ where
         })?;
     }
 
-    // Try reflink first, fall back to regular copy
-    copy_file_with_reflink(source, target)?;
+    // Try reflink first, fall back to regular (or streaming) copy
+    let method = match copy_single_file(source, target, &on_progress) {
+        Ok(method) => method,
+        Err(e) if vanished(&e, source) => {
+            log::debug!("Source vanished while copying");
+            return Ok(CopyResult::SourceNotFound);
+        }
+        Err(e) => return Err(e),
+    };
 
     // Report complete
     on_progress(&CopyProgress::new(
@@ -91,7 +163,14 @@ where
         Some(source.to_string_lossy().to_string()),
     ));
 
-    Ok(CopyResult::Created { files_copied: 1 })
+    Ok(CopyResult::Created {
+        files_copied: 1,
+        symlinks_copied: 0,
+        files_filtered: 0,
+        specials_skipped: 0,
+        files_vanished: 0,
+        method: Some(method),
+    })
 }
 
 /// Copy a single file, overwriting if it exists.
@@ -139,8 +218,15 @@ where
         })?;
     }
 
-    // Try reflink first, fall back to regular copy
-    copy_file_with_reflink(source, target)?;
+    // Try reflink first, fall back to regular (or streaming) copy
+    let method = match copy_single_file(source, target, &on_progress) {
+        Ok(method) => method,
+        Err(e) if vanished(&e, source) => {
+            log::debug!("Source vanished while copying");
+            return Ok(CopyResult::SourceNotFound);
+        }
+        Err(e) => return Err(e),
+    };
 
     // Report complete
     on_progress(&CopyProgress::new(
@@ -149,7 +235,14 @@ where
         Some(source.to_string_lossy().to_string()),
     ));
 
-    Ok(CopyResult::Created { files_copied: 1 })
+    Ok(CopyResult::Created {
+        files_copied: 1,
+        symlinks_copied: 0,
+        files_filtered: 0,
+        specials_skipped: 0,
+        files_vanished: 0,
+        method: Some(method),
+    })
 }
 
 /// Copy a directory with parallel enumeration and copying.
@@ -173,6 +266,73 @@ pub fn copy_directory<F>(
 ) -> Result<CopyResult, CopyError>
 where
     F: Fn(&CopyProgress) + Sync,
+{
+    copy_directory_filtered(source, target, None, |_, _| true, on_progress)
+}
+
+/// Copy a directory, skipping entries rejected by `filter`.
+///
+/// `filter` receives each source path and whether it is a directory, and
+/// returns `true` to copy it. Rejected directories are pruned as they are
+/// listed, so nothing beneath them is read.
+///
+/// # Arguments
+///
+/// * `source` - Source directory path
+/// * `target` - Target directory path
+/// * `max_depth` - How many levels below `source` to descend (`Some(1)`
+///   copies only its direct entries); deeper entries are never read
+/// * `filter` - Predicate deciding which entries are copied
+/// * `on_progress` - Callback for progress updates (called periodically, not for every file)
+///
+/// # Errors
+///
+/// * If enumeration fails
+/// * If any file copy fails (fail-fast behavior)
+pub fn copy_directory_filtered<P, F>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+    on_progress: F,
+) -> Result<CopyResult, CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+    F: Fn(&CopyProgress) + Sync,
+{
+    copy_directory_cancellable(
+        source,
+        target,
+        max_depth,
+        filter,
+        &AtomicBool::new(false),
+        on_progress,
+    )
+}
+
+/// Copy a directory like [`copy_directory_filtered`], stopping once
+/// `cancel` is set.
+///
+/// `cancel` is checked before each file is copied, so at most the files
+/// already being copied when it is set are still written. A cancelled copy
+/// fails with [`CopyError::Cancelled`].
+///
+/// # Errors
+///
+/// * If enumeration fails
+/// * If any file copy fails (fail-fast behavior)
+/// * If `cancel` is set before the copy finishes
+pub fn copy_directory_cancellable<P, F>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<CopyResult, CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+    F: Fn(&CopyProgress) + Sync,
 {
     log::debug!(
         "Copying directory: {} -> {}",
@@ -190,11 +350,152 @@ where
         return Ok(CopyResult::Exists);
     }
 
+    copy_entries(source, target, max_depth, filter, cancel, &on_progress)
+}
+
+/// Whether files under `source_dir` can be cloned (reflinked) into
+/// `target_dir` instead of copied byte by byte.
+///
+/// Clones only work within a single filesystem that supports them, so this
+/// checks that both directories are on the same device and then clones a
+/// small probe file inside the target (its nearest existing ancestor, if
+/// it does not exist yet). Any failure counts as unsupported.
+#[must_use]
+pub fn supports_reflink(source_dir: &Path, target_dir: &Path) -> bool {
+    let Some(target_dir) = target_dir.ancestors().find(|p| p.is_dir()) else {
+        return false;
+    };
+    if !same_device(source_dir, target_dir) {
+        return false;
+    }
+
+    let probe = target_dir.join(format!(".worktree-setup-reflink-{}", std::process::id()));
+    let clone = probe.with_extension("clone");
+    let supported = fs::write(&probe, b"reflink probe").is_ok()
+        && reflink_copy::reflink(&probe, &clone).is_ok();
+    let _ = fs::remove_file(&probe);
+    let _ = fs::remove_file(&clone);
+    log::debug!(
+        "Reflink {} from {} to {}",
+        if supported {
+            "supported"
+        } else {
+            "unsupported"
+        },
+        source_dir.display(),
+        target_dir.display()
+    );
+    supported
+}
+
+/// Whether `a` and `b` are on the same filesystem.
+#[cfg(unix)]
+fn same_device(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    matches!((fs::metadata(a), fs::metadata(b)), (Ok(a), Ok(b)) if a.dev() == b.dev())
+}
+
+/// Without device ids, leave the decision to the probe clone.
+#[cfg(not(unix))]
+const fn same_device(_a: &Path, _b: &Path) -> bool {
+    true
+}
+
+/// Clone a directory's contents into `target`, which may already exist.
+///
+/// Used to reflink a whole worktree into a freshly registered one. Entries
+/// rejected by `filter` (like the source's `.git`) are skipped, and so are
+/// files that already exist in `target`; both count as filtered. Files are
+/// reflinked where the filesystem supports it and copied otherwise.
+/// `max_depth` limits the descent as for [`copy_directory_filtered`].
+///
+/// # Errors
+///
+/// * If enumeration fails
+/// * If any file copy fails (fail-fast behavior)
+pub fn clone_directory_into<P, F>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+    on_progress: F,
+) -> Result<CopyResult, CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+    F: Fn(&CopyProgress) + Sync,
+{
+    log::debug!(
+        "Cloning directory: {} -> {}",
+        source.display(),
+        target.display()
+    );
+
+    if !source.is_dir() {
+        log::debug!("Source is not a directory");
+        return Ok(CopyResult::SourceNotFound);
+    }
+
+    let (source_dir, target_dir) = (source.to_path_buf(), target.to_path_buf());
+    let keep = move |path: &Path, is_dir: bool| {
+        filter(path, is_dir)
+            && (is_dir
+                || path
+                    .strip_prefix(&source_dir)
+                    .map_or(true, |rel| target_dir.join(rel).symlink_metadata().is_err()))
+    };
+    copy_entries(
+        source,
+        target,
+        max_depth,
+        keep,
+        &AtomicBool::new(false),
+        &on_progress,
+    )
+}
+
+/// Enumerate `source` and copy every entry `filter` accepts into `target`.
+///
+/// Entries that cannot be read or written for lack of permission do not
+/// stop the copy; once everything else is copied they are reported
+/// together as [`CopyError::PermissionDenied`]. Once `cancel` is set, no
+/// further files are started and the copy fails with
+/// [`CopyError::Cancelled`].
+fn copy_entries<P, F>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+    cancel: &AtomicBool,
+    on_progress: &F,
+) -> Result<CopyResult, CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+    F: Fn(&CopyProgress) + Sync,
+{
+    let cancelled = || {
+        if cancel.load(Ordering::Relaxed) {
+            log::debug!("Cancelled copy to {}", target.display());
+            Err(CopyError::Cancelled {
+                path: target.to_path_buf(),
+            })
+        } else {
+            Ok(())
+        }
+    };
+
     // Phase 1: Enumerate all files using jwalk (parallel)
-    let entries = enumerate_directory(source, target)?;
+    let (mut entries, files_filtered, denied_dirs) =
+        enumerate_directory(source, target, max_depth, filter)?;
+    let denied = Mutex::new(denied_dirs);
+    let specials_skipped = skip_specials(&mut entries);
     let total_files = entries.len() as u64;
+    let symlinks_copied = entries
+        .iter()
+        .filter(|e| e.kind == EntryKind::Symlink)
+        .count() as u64;
 
-    log::debug!("Found {total_files} files to copy");
+    log::debug!("Found {total_files} files to copy ({files_filtered} filtered)");
+    cancelled()?;
 
     if total_files == 0 {
         // Empty directory - just create the target
@@ -202,7 +503,15 @@ where
             path: target.to_path_buf(),
             io_error: e,
         })?;
-        return Ok(CopyResult::Created { files_copied: 0 });
+        permission_check(source, denied)?;
+        return Ok(CopyResult::Created {
+            files_copied: 0,
+            symlinks_copied: 0,
+            files_filtered,
+            specials_skipped,
+            files_vanished: 0,
+            method: None,
+        });
     }
 
     // Create progress tracker
@@ -231,15 +540,31 @@ where
 
     // Copy files in parallel
     let tracker_ref = &tracker;
-    let on_progress_ref = &on_progress;
+    let on_progress_ref = on_progress;
+    let files_vanished = AtomicU64::new(0);
+    let symlinks_vanished = AtomicU64::new(0);
 
     entries
         .par_iter()
         .try_for_each(|entry| -> Result<(), CopyError> {
-            if entry.is_symlink {
-                copy_symlink(&entry.source, &entry.target)?;
-            } else {
-                copy_file_with_reflink(&entry.source, &entry.target)?;
+            cancelled()?;
+            match copy_entry(entry) {
+                Ok(()) => {}
+                Err(e) if vanished(&e, &entry.source) => {
+                    log::debug!("Skipping vanished {}", entry.source.display());
+                    files_vanished.fetch_add(1, Ordering::Relaxed);
+                    if entry.kind == EntryKind::Symlink {
+                        symlinks_vanished.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Err(e) if e.is_permission_denied() => {
+                    log::debug!("{e}");
+                    denied
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .push(entry.source.clone());
+                }
+                Err(e) => return Err(e),
             }
 
             tracker_ref.increment_copied();
@@ -257,42 +582,137 @@ where
 
     // Final progress report
     on_progress(&tracker.snapshot(None));
+    permission_check(source, denied)?;
 
+    let files_vanished = files_vanished.into_inner();
     Ok(CopyResult::Created {
-        files_copied: total_files,
+        files_copied: total_files - files_vanished,
+        symlinks_copied: symlinks_copied - symlinks_vanished.into_inner(),
+        files_filtered,
+        specials_skipped,
+        files_vanished,
+        method: None,
+    })
+}
+
+/// Copy one enumerated entry to its target, according to its kind.
+fn copy_entry(entry: &FileEntry) -> Result<(), CopyError> {
+    match entry.kind {
+        EntryKind::Symlink => copy_symlink(&entry.source, &entry.target),
+        EntryKind::Fifo => create_fifo(&entry.source, &entry.target),
+        EntryKind::File | EntryKind::Special => {
+            copy_file_with_reflink(&entry.source, &entry.target)
+        }
+    }
+}
+
+/// Fail with [`CopyError::PermissionDenied`] if any paths under `source`
+/// were denied.
+fn permission_check(source: &Path, denied: Mutex<Vec<PathBuf>>) -> Result<(), CopyError> {
+    let mut paths = denied
+        .into_inner()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if paths.is_empty() {
+        return Ok(());
+    }
+    paths.sort();
+    Err(CopyError::PermissionDenied {
+        path: source.to_path_buf(),
+        paths,
     })
 }
 
+/// Drop sockets and device nodes from `entries`, logging each one.
+///
+/// Returns how many were dropped. A socket only works while its server is
+/// listening and a device node needs privileges to create, so neither can
+/// be meaningfully copied.
+fn skip_specials(entries: &mut Vec<FileEntry>) -> u64 {
+    let before = entries.len();
+    entries.retain(|entry| {
+        let special = entry.kind == EntryKind::Special;
+        if special {
+            log::warn!(
+                "Skipping special file (socket or device) {}",
+                entry.source.display()
+            );
+        }
+        !special
+    });
+    (before - entries.len()) as u64
+}
+
 /// Enumerate all files in a directory using jwalk.
 ///
+/// Returns the entries to copy, the number of entries the filter left out
+/// (a filtered directory counts once), and the directories that could not
+/// be read for lack of permission. Directories the filter rejects are
+/// pruned as they are listed, so nothing beneath them is read. Entries
+/// more than `max_depth` levels below `source` are not walked or counted.
+///
 /// Uses `Parallelism::Serial` to avoid the shared-rayon-pool busy-timeout
 /// bug that causes silent empty results under concurrent load (see
 /// `test_resolve_glob_concurrent_callers` in `worktree_setup_glob`).
 /// Caller-level parallelism is the correct level to coordinate concurrent
 /// directory enumeration.
-fn enumerate_directory(source: &Path, target: &Path) -> Result<Vec<FileEntry>, CopyError> {
+pub fn enumerate_directory<P>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+) -> Result<(Vec<FileEntry>, u64, Vec<PathBuf>), CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+{
     let mut entries = Vec::new();
-
-    for entry in jwalk::WalkDir::new(source)
-        .skip_hidden(false)
-        .follow_links(false)
-        .parallelism(jwalk::Parallelism::Serial)
-    {
-        let entry = entry.map_err(|e| CopyError::EnumerationError {
-            path: source.to_path_buf(),
-            message: e.to_string(),
-        })?;
+    let mut filtered = 0u64;
+    let mut denied = Vec::new();
+
+    for entry in filtered_walk(source, max_depth, filter) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // Deleted after its parent was listed; nothing left to copy.
+            Err(e) if e.io_error().map(std::io::Error::kind) == Some(ErrorKind::NotFound) => {
+                log::debug!("Skipping vanished entry: {e}");
+                continue;
+            }
+            Err(e) => {
+                return Err(CopyError::EnumerationError {
+                    path: source.to_path_buf(),
+                    message: e.to_string(),
+                });
+            }
+        };
 
         let source_path = entry.path();
+        let unreadable = entry
+            .read_children_error
+            .as_ref()
+            .and_then(jwalk::Error::io_error)
+            .is_some_and(|e| e.kind() == ErrorKind::PermissionDenied);
 
         // Skip the root directory itself
         if source_path == source {
+            if unreadable {
+                denied.push(source_path);
+            }
             continue;
         }
 
-        // Skip directories (we only copy files and symlinks)
         let file_type = entry.file_type();
+
+        if entry.client_state {
+            log::trace!("Filtered {}", source_path.display());
+            filtered += 1;
+            continue;
+        }
+
+        // Skip directories (we only copy files and symlinks)
         if file_type.is_dir() {
+            if unreadable {
+                log::debug!("Cannot read directory {}", source_path.display());
+                denied.push(source_path);
+            }
             continue;
         }
 
@@ -309,30 +729,236 @@ fn enumerate_directory(source: &Path, target: &Path) -> Result<Vec<FileEntry>, C
         entries.push(FileEntry {
             source: source_path.clone(),
             target: target_path,
-            is_symlink: file_type.is_symlink(),
+            kind: EntryKind::of(file_type),
         });
     }
 
-    Ok(entries)
+    Ok((entries, filtered, denied))
+}
+
+/// Serial walk of `root` that marks the entries `filter` rejects.
+///
+/// Rejected entries are still yielded, with their `client_state` set, so
+/// callers can count them; rejected directories are not descended into.
+pub fn filtered_walk<P>(
+    root: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+) -> jwalk::WalkDirGeneric<((), bool)>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+{
+    jwalk::WalkDirGeneric::<((), bool)>::new(root)
+        .skip_hidden(false)
+        .sort(false)
+        .follow_links(false)
+        .max_depth(max_depth.unwrap_or(usize::MAX))
+        .parallelism(jwalk::Parallelism::Serial)
+        .process_read_dir(move |depth, _path, _state, children| {
+            // jwalk lists the root's parent first (depth `None`); the root
+            // itself is never filtered.
+            if depth.is_none() {
+                return;
+            }
+            for entry in children.iter_mut().flatten() {
+                let is_dir = entry.file_type.is_dir();
+                if !filter(&entry.path(), is_dir) {
+                    entry.client_state = true;
+                    entry.read_children_path = None;
+                }
+            }
+        })
+}
+
+/// Whether `error` came from `source` having been deleted after it was
+/// found: the copy failed for a missing path and `source` is indeed gone.
+/// Other failures (e.g. a full disk) still count if the source happens to
+/// be deleted meanwhile.
+fn vanished(error: &CopyError, source: &Path) -> bool {
+    error.is_not_found()
+        && matches!(source.symlink_metadata(), Err(e) if e.kind() == ErrorKind::NotFound)
+}
+
+/// Which mechanism [`clone_or_copy_file`] tries first.
+///
+/// Every strategy falls back to the cheaper-to-support mechanisms after it,
+/// ending with a regular copy, so it only fails when copying itself does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CloneStrategy {
+    /// Hard link, then reflink, then copy. The target shares the source's
+    /// data and metadata, so writes to either show in both.
+    Hardlink,
+    /// Reflink (copy-on-write, instant on APFS, Btrfs, `ReFS`), then copy.
+    #[default]
+    Reflink,
+    /// Regular copy only.
+    Copy,
+}
+
+/// How [`clone_or_copy_file`] created the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloneMethod {
+    /// Hard-linked to the source.
+    Hardlink,
+    /// Cloned with copy-on-write.
+    Reflink,
+    /// Copied byte by byte.
+    Copy,
+}
+
+impl CloneMethod {
+    /// Lowercase name of the mechanism (`"hardlink"`, `"reflink"`, `"copy"`).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hardlink => "hardlink",
+            Self::Reflink => "reflink",
+            Self::Copy => "copy",
+        }
+    }
+}
+
+impl std::fmt::Display for CloneMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Create `target` from the file `source` with the first mechanism of
+/// `strategy` that works, and return which one did.
+///
+/// Hard links and reflinks need `target` not to exist and fail across
+/// filesystems; the regular copy that ends every fallback chain overwrites
+/// an existing `target`. The parent directory of `target` must exist.
+///
+/// # Errors
+///
+/// * If the final fallback, a regular copy, fails
+pub fn clone_or_copy_file(
+    source: &Path,
+    target: &Path,
+    strategy: CloneStrategy,
+) -> Result<CloneMethod, CopyError> {
+    if strategy == CloneStrategy::Hardlink && fs::hard_link(source, target).is_ok() {
+        log::trace!("Hard-linked {} -> {}", source.display(), target.display());
+        return Ok(CloneMethod::Hardlink);
+    }
+    if strategy != CloneStrategy::Copy && reflink_copy::reflink(source, target).is_ok() {
+        log::trace!("Reflinked {} -> {}", source.display(), target.display());
+        return Ok(CloneMethod::Reflink);
+    }
+    fs::copy(source, target).map_err(|e| CopyError::FileCopyError {
+        source_path: source.to_path_buf(),
+        target_path: target.to_path_buf(),
+        io_error: e,
+    })?;
+    log::trace!("Copied {} -> {}", source.display(), target.display());
+    Ok(CloneMethod::Copy)
 }
 
 /// Copy a single file, trying reflink first then falling back to regular copy.
 fn copy_file_with_reflink(source: &Path, target: &Path) -> Result<(), CopyError> {
-    // Try reflink first (copy-on-write, instant on APFS/Btrfs/ReFS)
+    clone_or_copy_file(source, target, CloneStrategy::Reflink).map(|_| ())
+}
+
+/// Copy one file for [`copy_file`] and [`overwrite_file`].
+///
+/// Files below [`STREAMING_COPY_THRESHOLD`] go through
+/// [`clone_or_copy_file`]. Larger files are still reflinked when the
+/// filesystem allows it, and otherwise streamed so `on_progress` sees the
+/// bytes as they are written. Returns how the target was created.
+fn copy_single_file<F>(
+    source: &Path,
+    target: &Path,
+    on_progress: &F,
+) -> Result<CloneMethod, CopyError>
+where
+    F: Fn(&CopyProgress),
+{
+    let size = fs::metadata(source).map_or(0, |m| m.len());
+    if size < STREAMING_COPY_THRESHOLD {
+        return clone_or_copy_file(source, target, CloneStrategy::Reflink);
+    }
     if matches!(reflink_copy::reflink(source, target), Ok(())) {
         log::trace!("Reflinked {} -> {}", source.display(), target.display());
-    } else {
-        // Fall back to regular copy
-        fs::copy(source, target).map_err(|e| CopyError::FileCopyError {
-            source_path: source.to_path_buf(),
-            target_path: target.to_path_buf(),
-            io_error: e,
-        })?;
-        log::trace!("Copied {} -> {}", source.display(), target.display());
+        return Ok(CloneMethod::Reflink);
+    }
+
+    let io_error = |e| CopyError::FileCopyError {
+        source_path: source.to_path_buf(),
+        target_path: target.to_path_buf(),
+        io_error: e,
+    };
+    let current_file = source.to_string_lossy().to_string();
+    let mut reader = fs::File::open(source).map_err(io_error)?;
+    let mut writer = fs::File::create(target).map_err(io_error)?;
+    let mut buffer = vec![0; STREAM_CHUNK_BYTES];
+    let mut copied = 0u64;
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_error(e)),
+        };
+        writer.write_all(&buffer[..read]).map_err(io_error)?;
+        copied += read as u64;
+        on_progress(
+            &CopyProgress::new(1, 0, Some(current_file.clone()))
+                .with_bytes(copied, size.max(copied)),
+        );
+    }
+
+    writer.flush().map_err(io_error)?;
+    if let Ok(metadata) = fs::metadata(source) {
+        fs::set_permissions(target, metadata.permissions()).map_err(io_error)?;
+    }
+    log::trace!("Streamed {} -> {}", source.display(), target.display());
+    Ok(CloneMethod::Copy)
+}
+
+/// Recreate a named pipe at `target` with the permissions of `source`.
+///
+/// Reading a FIFO blocks until something writes to it, so its (transient)
+/// contents are never copied; the new pipe starts out empty.
+#[cfg(unix)]
+fn create_fifo(source: &Path, target: &Path) -> Result<(), CopyError> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::symlink_metadata(source).map_or(0o644, |m| m.permissions().mode() & 0o7777);
+    let c_path = std::ffi::CString::new(target.as_os_str().as_bytes()).map_err(|e| {
+        CopyError::CreateFifoError {
+            path: target.to_path_buf(),
+            io_error: std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+        }
+    })?;
+    // SAFETY: `c_path` is a valid NUL-terminated path.
+    #[allow(clippy::useless_conversion)]
+    let rc = unsafe {
+        libc::mkfifo(
+            c_path.as_ptr(),
+            libc::mode_t::try_from(mode).unwrap_or(0o644),
+        )
+    };
+    if rc != 0 {
+        return Err(CopyError::CreateFifoError {
+            path: target.to_path_buf(),
+            io_error: std::io::Error::last_os_error(),
+        });
     }
+
+    log::trace!("Created FIFO {}", target.display());
     Ok(())
 }
 
+/// FIFOs are never reported on platforms without them.
+#[cfg(not(unix))]
+fn create_fifo(source: &Path, target: &Path) -> Result<(), CopyError> {
+    copy_file_with_reflink(source, target)
+}
+
 /// Copy a symlink, preserving it as a symlink.
 fn copy_symlink(source: &Path, target: &Path) -> Result<(), CopyError> {
     let link_target = fs::read_link(source).map_err(|e| CopyError::ReadLinkError {
@@ -385,9 +1011,45 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
     use std::sync::Arc;
-    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
     use tempfile::TempDir;
 
+    #[test]
+    fn test_copy_file_streams_large_files_with_byte_progress() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("large.db");
+        let target = dir.path().join("copy.db");
+        let file = fs::File::create(&source).unwrap();
+        file.set_len(STREAMING_COPY_THRESHOLD).unwrap();
+        drop(file);
+
+        let last_bytes = AtomicU64::new(0);
+        let result = copy_file(&source, &target, |progress| {
+            if progress.bytes_total > 0 {
+                assert_eq!(progress.bytes_total, STREAMING_COPY_THRESHOLD);
+                last_bytes.fetch_max(progress.bytes_copied, Ordering::SeqCst);
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            fs::metadata(&target).unwrap().len(),
+            STREAMING_COPY_THRESHOLD
+        );
+        // A reflinked copy finishes instantly and reports no bytes.
+        let streamed = last_bytes.load(Ordering::SeqCst);
+        let method = if streamed == 0 {
+            CloneMethod::Reflink
+        } else {
+            assert_eq!(streamed, STREAMING_COPY_THRESHOLD);
+            CloneMethod::Copy
+        };
+        assert!(matches!(
+            result,
+            CopyResult::Created { method: Some(m), .. } if m == method
+        ));
+    }
+
     #[test]
     fn test_copy_file_creates_new() {
         let dir = TempDir::new().unwrap();
@@ -402,7 +1064,13 @@ mod tests {
         })
         .unwrap();
 
-        assert!(matches!(result, CopyResult::Created { files_copied: 1 }));
+        assert!(matches!(
+            result,
+            CopyResult::Created {
+                files_copied: 1,
+                ..
+            }
+        ));
         assert!(target.exists());
         assert_eq!(fs::read_to_string(&target).unwrap(), "hello world");
         assert!(progress_count.load(Ordering::SeqCst) >= 1);
@@ -453,7 +1121,13 @@ mod tests {
         })
         .unwrap();
 
-        assert!(matches!(result, CopyResult::Created { files_copied: 2 }));
+        assert!(matches!(
+            result,
+            CopyResult::Created {
+                files_copied: 2,
+                ..
+            }
+        ));
         assert!(target.join("file1.txt").exists());
         assert!(target.join("subdir/file2.txt").exists());
         assert_eq!(
@@ -480,6 +1154,327 @@ mod tests {
         assert_eq!(result, CopyResult::Exists);
     }
 
+    #[test]
+    fn test_copy_directory_filtered_prunes_rejected_entries() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(source.join("cache/deep")).unwrap();
+        fs::write(source.join("keep.txt"), "keep").unwrap();
+        fs::write(source.join("skip.log"), "skip").unwrap();
+        fs::write(source.join("cache/deep/file.txt"), "cached").unwrap();
+
+        let result = copy_directory_filtered(
+            &source,
+            &target,
+            None,
+            |path, is_dir| {
+                let name = path.file_name().unwrap().to_string_lossy();
+                !(name.ends_with(".log") || is_dir && name == "cache")
+            },
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 1,
+                symlinks_copied: 0,
+                files_filtered: 2,
+                specials_skipped: 0,
+                files_vanished: 0,
+                method: None,
+            }
+        );
+        assert!(target.join("keep.txt").exists());
+        assert!(!target.join("skip.log").exists());
+        assert!(!target.join("cache").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_reports_every_permission_denied_entry() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(source.join("locked")).unwrap();
+        fs::write(source.join("ok.txt"), "ok").unwrap();
+        fs::write(source.join("secret.txt"), "secret").unwrap();
+        fs::write(source.join("locked/inner.txt"), "inner").unwrap();
+        fs::set_permissions(source.join("secret.txt"), fs::Permissions::from_mode(0o000)).unwrap();
+        fs::set_permissions(source.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let result = copy_directory(&source, &target, |_| {});
+        fs::set_permissions(source.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Permissions do not apply to root.
+        if fs::read(source.join("secret.txt")).is_ok() {
+            return;
+        }
+        match result {
+            Err(CopyError::PermissionDenied { path, paths }) => {
+                assert_eq!(path, source);
+                assert_eq!(paths, [source.join("locked"), source.join("secret.txt")]);
+            }
+            other => panic!("expected PermissionDenied, got {other:?}"),
+        }
+        assert_eq!(fs::read_to_string(target.join("ok.txt")).unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_copy_directory_filtered_stops_at_max_depth() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(source.join("a/b")).unwrap();
+        fs::write(source.join("top.txt"), "1").unwrap();
+        fs::write(source.join("a/mid.txt"), "2").unwrap();
+        fs::write(source.join("a/b/deep.txt"), "3").unwrap();
+
+        let counts = crate::count_entries_with_progress(&source, Some(2), |_, _| true, |_| {});
+        assert_eq!((counts.files, counts.filtered), (2, 0));
+
+        let result =
+            copy_directory_filtered(&source, &target, Some(2), |_, _| true, |_| {}).unwrap();
+
+        assert!(matches!(
+            result,
+            CopyResult::Created {
+                files_copied: 2,
+                files_filtered: 0,
+                ..
+            }
+        ));
+        assert!(target.join("top.txt").exists());
+        assert!(target.join("a/mid.txt").exists());
+        assert!(!target.join("a/b").exists());
+    }
+
+    #[test]
+    fn test_copy_directory_skips_files_deleted_after_enumeration() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("keep.txt"), "keep").unwrap();
+        fs::write(source.join("build.tmp"), "temp").unwrap();
+
+        // The first report comes after enumeration, before any copying.
+        let result = copy_directory(&source, &target, |progress| {
+            if progress.files_copied == 0 {
+                let _ = fs::remove_file(source.join("build.tmp"));
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 1,
+                symlinks_copied: 0,
+                files_filtered: 0,
+                specials_skipped: 0,
+                files_vanished: 1,
+                method: None,
+            }
+        );
+        assert!(target.join("keep.txt").exists());
+        assert!(!target.join("build.tmp").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_skips_symlinks_deleted_after_enumeration() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("keep.txt"), "keep").unwrap();
+        std::os::unix::fs::symlink("keep.txt", source.join("kept-link")).unwrap();
+        std::os::unix::fs::symlink("keep.txt", source.join("stale-link")).unwrap();
+
+        let result = copy_directory(&source, &target, |progress| {
+            if progress.files_copied == 0 {
+                let _ = fs::remove_file(source.join("stale-link"));
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 2,
+                symlinks_copied: 1,
+                files_filtered: 0,
+                specials_skipped: 0,
+                files_vanished: 1,
+                method: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_vanished_needs_a_missing_path_error() {
+        let dir = TempDir::new().unwrap();
+        let gone = dir.path().join("gone.txt");
+        let copy_error = |kind| CopyError::FileCopyError {
+            source_path: gone.clone(),
+            target_path: dir.path().join("target.txt"),
+            io_error: std::io::Error::from(kind),
+        };
+
+        assert!(vanished(&copy_error(ErrorKind::NotFound), &gone));
+        assert!(!vanished(&copy_error(ErrorKind::StorageFull), &gone));
+        fs::write(&gone, "back").unwrap();
+        assert!(!vanished(&copy_error(ErrorKind::NotFound), &gone));
+    }
+
+    #[test]
+    fn test_copy_directory_cancellable_stops_copying() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(&source).unwrap();
+        for i in 0..10 {
+            fs::write(source.join(format!("{i}.txt")), "data").unwrap();
+        }
+
+        // Cancel at the first report, after enumeration and before copying.
+        let cancel = AtomicBool::new(false);
+        let result = copy_directory_cancellable(
+            &source,
+            &target,
+            None,
+            |_, _| true,
+            &cancel,
+            |_| cancel.store(true, Ordering::Relaxed),
+        );
+
+        assert!(matches!(result, Err(CopyError::Cancelled { .. })));
+        let written = fs::read_dir(&target).map_or(0, Iterator::count);
+        assert_eq!(written, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_reports_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("real.txt"), "real").unwrap();
+        std::os::unix::fs::symlink("real.txt", source.join("link.txt")).unwrap();
+
+        let result = copy_directory(&source, &target, |_| {}).unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 2,
+                symlinks_copied: 1,
+                files_filtered: 0,
+                specials_skipped: 0,
+                files_vanished: 0,
+                method: None,
+            }
+        );
+        assert!(target.join("link.txt").is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_recreates_fifos_and_skips_sockets() {
+        use std::os::unix::fs::FileTypeExt;
+
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("real.txt"), "real").unwrap();
+        let fifo =
+            std::ffi::CString::new(source.join("pipe").to_string_lossy().as_bytes()).unwrap();
+        // SAFETY: `fifo` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+        let _listener = std::os::unix::net::UnixListener::bind(source.join("app.sock")).unwrap();
+
+        let result = copy_directory(&source, &target, |_| {}).unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 2,
+                symlinks_copied: 0,
+                files_filtered: 0,
+                specials_skipped: 1,
+                files_vanished: 0,
+                method: None,
+            }
+        );
+        assert!(
+            fs::symlink_metadata(target.join("pipe"))
+                .unwrap()
+                .file_type()
+                .is_fifo()
+        );
+        assert!(!target.join("app.sock").exists());
+    }
+
+    #[test]
+    fn test_clone_directory_into_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source_dir");
+        let target = dir.path().join("target_dir");
+
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::write(source.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        fs::write(source.join("src/lib.rs"), "source").unwrap();
+        fs::write(source.join("README.md"), "source").unwrap();
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("README.md"), "target").unwrap();
+
+        let result = clone_directory_into(
+            &source,
+            &target,
+            None,
+            |path, _| !path.ends_with(".git"),
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            CopyResult::Created {
+                files_copied: 1,
+                symlinks_copied: 0,
+                files_filtered: 2,
+                specials_skipped: 0,
+                files_vanished: 0,
+                method: None,
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("src/lib.rs")).unwrap(),
+            "source"
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("README.md")).unwrap(),
+            "target"
+        );
+        assert!(!target.join(".git").exists());
+    }
+
     #[test]
     fn test_overwrite_file() {
         let dir = TempDir::new().unwrap();
@@ -491,10 +1486,52 @@ mod tests {
 
         let result = overwrite_file(&source, &target, |_| {}).unwrap();
 
-        assert!(matches!(result, CopyResult::Created { files_copied: 1 }));
+        assert!(matches!(
+            result,
+            CopyResult::Created {
+                files_copied: 1,
+                ..
+            }
+        ));
         assert_eq!(fs::read_to_string(&target).unwrap(), "new content");
     }
 
+    #[test]
+    fn test_clone_or_copy_file_falls_back() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "content").unwrap();
+
+        let linked = dir.path().join("linked.txt");
+        assert_eq!(
+            clone_or_copy_file(&source, &linked, CloneStrategy::Hardlink).unwrap(),
+            CloneMethod::Hardlink
+        );
+        assert_eq!(fs::read_to_string(&linked).unwrap(), "content");
+
+        let copied = dir.path().join("copied.txt");
+        assert_eq!(
+            clone_or_copy_file(&source, &copied, CloneStrategy::Copy).unwrap(),
+            CloneMethod::Copy
+        );
+
+        // A hard link onto an existing file fails; the copy fallback
+        // overwrites it.
+        fs::write(&copied, "old").unwrap();
+        let method = clone_or_copy_file(&source, &copied, CloneStrategy::Hardlink).unwrap();
+        assert_ne!(method, CloneMethod::Hardlink);
+        assert_eq!(fs::read_to_string(&copied).unwrap(), "content");
+
+        assert!(
+            clone_or_copy_file(
+                &dir.path().join("missing"),
+                &dir.path().join("out"),
+                CloneStrategy::Reflink
+            )
+            .is_err()
+        );
+    }
+
     /// **Regression test for jwalk busy-timeout bug**, analogous to
     /// `test_resolve_glob_concurrent_callers` in the `glob` crate.
     ///
@@ -534,7 +1571,7 @@ mod tests {
             handles.push(std::thread::spawn(move || {
                 let result = copy_directory(&source, &target, |_| {}).unwrap();
                 match result {
-                    CopyResult::Created { files_copied } => {
+                    CopyResult::Created { files_copied, .. } => {
                         let expected = u64::try_from(FILES_PER_SOURCE).unwrap();
                         if files_copied != expected {
                             eprintln!(
@@ -564,4 +1601,17 @@ mod tests {
              files — likely a regression of the jwalk busy-timeout bug"
         );
     }
+
+    #[test]
+    fn test_supports_reflink_leaves_no_probe_files() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("source");
+        fs::create_dir(&source).unwrap();
+
+        // Whether clones work depends on the filesystem running the test.
+        let _ = supports_reflink(&source, &temp.path().join("missing/target"));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+        assert!(!supports_reflink(&temp.path().join("gone"), temp.path()));
+    }
 }
@@ -6,6 +6,8 @@
 
 use std::path::Path;
 
+use crate::copy::{EntryKind, filtered_walk};
+
 /// Count files in a path.
 ///
 /// - If path is a file: returns 1
@@ -80,6 +82,114 @@ where
     count
 }
 
+/// Breakdown of the entries a directory copy will see.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryCounts {
+    /// Regular files that will be copied.
+    pub files: u64,
+    /// Symlinks that will be recreated as links rather than followed.
+    pub symlinks: u64,
+    /// Entries left out by the filter; a filtered directory counts once,
+    /// since nothing beneath it is walked.
+    pub filtered: u64,
+    /// Total size in bytes of the regular files that will be copied.
+    pub bytes: u64,
+    /// Sockets and device nodes, which are skipped rather than copied.
+    pub specials: u64,
+}
+
+impl EntryCounts {
+    /// Number of entries that will actually be copied.
+    #[must_use]
+    pub const fn copied(&self) -> u64 {
+        self.files + self.symlinks
+    }
+}
+
+/// Count the entries under a path the way `copy_directory_filtered` sees them.
+///
+/// Unlike [`count_files_with_progress`], symlinks inside a directory are
+/// counted (they are copied as links) and entries rejected by `filter` are
+/// tallied separately, so the totals line up with the eventual copy.
+///
+/// `filter` receives each path and whether it is a directory, and returns
+/// `true` to include it. Rejected directories are pruned, so nothing
+/// beneath them is read.
+///
+/// # Arguments
+///
+/// * `path` - Path to count entries in
+/// * `max_depth` - How many levels below `path` to descend; deeper entries
+///   are neither walked nor counted
+/// * `filter` - Predicate deciding which entries are included
+/// * `on_progress` - Callback invoked every 100 entries with the current copy count
+pub fn count_entries_with_progress<P, F>(
+    path: &Path,
+    max_depth: Option<usize>,
+    filter: P,
+    on_progress: F,
+) -> EntryCounts
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+    F: Fn(u64),
+{
+    let mut counts = EntryCounts::default();
+
+    if path.is_symlink() {
+        counts.symlinks = 1;
+        return counts;
+    }
+
+    if path.is_file() {
+        on_progress(1);
+        counts.files = 1;
+        counts.bytes = path.metadata().map_or(0, |m| m.len());
+        return counts;
+    }
+
+    if !path.is_dir() {
+        return counts;
+    }
+
+    for entry in filtered_walk(path, max_depth, filter)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        if entry.path() == path {
+            continue;
+        }
+
+        if entry.client_state {
+            counts.filtered += 1;
+            continue;
+        }
+
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            continue;
+        }
+
+        match EntryKind::of(file_type) {
+            EntryKind::Symlink => counts.symlinks += 1,
+            EntryKind::Special => {
+                counts.specials += 1;
+                continue;
+            }
+            EntryKind::File | EntryKind::Fifo => {
+                counts.files += 1;
+                counts.bytes += entry.metadata().map_or(0, |m| m.len());
+            }
+        }
+
+        if counts.copied().is_multiple_of(100) {
+            on_progress(counts.copied());
+        }
+    }
+
+    on_progress(counts.copied());
+    counts
+}
+
 /// Calculate the disk usage of a path (file or directory, recursive).
 ///
 /// Uses `jwalk` for parallel directory traversal with `follow_links(false)`
@@ -152,6 +262,7 @@ fn file_disk_usage(meta: &std::fs::Metadata) -> u64 {
 mod tests {
     use super::*;
     use std::fs;
+    use std::sync::{Arc, Mutex};
     use tempfile::TempDir;
 
     #[test]
@@ -186,6 +297,66 @@ mod tests {
         assert_eq!(count_files(path), 0);
     }
 
+    #[test]
+    fn test_count_entries_with_filter() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("keep.txt"), "1").unwrap();
+        fs::write(dir.path().join("drop.log"), "2").unwrap();
+        fs::create_dir_all(dir.path().join("cache/nested")).unwrap();
+        fs::write(dir.path().join("cache/a"), "3").unwrap();
+        fs::write(dir.path().join("cache/nested/b"), "4").unwrap();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_by_filter = Arc::clone(&seen);
+        let counts = count_entries_with_progress(
+            dir.path(),
+            None,
+            move |path, is_dir| {
+                seen_by_filter.lock().unwrap().push(path.to_path_buf());
+                let name = path.file_name().unwrap().to_string_lossy();
+                !(name.ends_with(".log") || is_dir && name == "cache")
+            },
+            |_| {},
+        );
+
+        assert_eq!(
+            counts,
+            EntryCounts {
+                files: 1,
+                symlinks: 0,
+                filtered: 2,
+                bytes: 1,
+                specials: 0,
+            }
+        );
+        // The filtered directory is pruned rather than walked.
+        let cache = dir.path().join("cache");
+        assert!(
+            !seen
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|path| path.starts_with(&cache) && *path != cache)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_count_entries_includes_symlinks() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("real.txt"), "1").unwrap();
+        std::os::unix::fs::symlink("real.txt", dir.path().join("link.txt")).unwrap();
+
+        let counts = count_entries_with_progress(dir.path(), None, |_, _| true, |_| {});
+
+        assert_eq!(counts.files, 1);
+        assert_eq!(counts.symlinks, 1);
+        assert_eq!(counts.copied(), 2);
+        assert_eq!(counts.bytes, 1, "symlinks add no bytes");
+        // count_files only sees regular files
+        assert_eq!(count_files(dir.path()), 1);
+    }
+
     #[test]
     fn test_disk_usage_single_file() {
         let dir = TempDir::new().unwrap();
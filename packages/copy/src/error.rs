@@ -7,6 +7,9 @@
 use std::path::PathBuf;
 
 /// Errors that can occur during copy operations.
+///
+/// Each variant has a stable [`code`](Self::code). New variants take the
+/// next unused number; codes are never reused.
 #[derive(Debug, thiserror::Error)]
 pub enum CopyError {
     /// Failed to read source directory.
@@ -56,6 +59,15 @@ pub enum CopyError {
         io_error: std::io::Error,
     },
 
+    /// Failed to recreate a named pipe.
+    #[error("Failed to create FIFO {}: {io_error}", path.display())]
+    CreateFifoError {
+        /// The FIFO path.
+        path: PathBuf,
+        /// The underlying IO error.
+        io_error: std::io::Error,
+    },
+
     /// Failed to get file metadata.
     #[error("Failed to get metadata for {}: {io_error}", path.display())]
     MetadataError {
@@ -73,4 +85,130 @@ pub enum CopyError {
         /// Error message.
         message: String,
     },
+
+    /// Entries of a directory copy could not be read or written for lack of
+    /// permission (e.g. root-owned files in a Docker volume). Everything
+    /// else was copied.
+    #[error(
+        "Permission denied for {} under {}:{}",
+        plural(paths.len(), "entry", "entries"),
+        path.display(),
+        path_list(paths)
+    )]
+    PermissionDenied {
+        /// The directory being copied.
+        path: PathBuf,
+        /// Source paths of the files and directories that were denied.
+        paths: Vec<PathBuf>,
+    },
+
+    /// A directory copy was cancelled before it finished (e.g. because its
+    /// operation timed out). Files copied before that are left in place.
+    #[error("Copy to {} was cancelled", path.display())]
+    Cancelled {
+        /// The target directory.
+        path: PathBuf,
+    },
+}
+
+/// Most denied paths listed in a [`CopyError::PermissionDenied`] message.
+const MAX_LISTED_PATHS: usize = 20;
+
+/// `count` followed by the singular or plural noun.
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{count} {}", if count == 1 { singular } else { plural })
+}
+
+/// One indented line per path, eliding all but the first
+/// [`MAX_LISTED_PATHS`].
+fn path_list(paths: &[PathBuf]) -> String {
+    use std::fmt::Write as _;
+
+    let mut list = String::new();
+    for path in paths.iter().take(MAX_LISTED_PATHS) {
+        let _ = write!(list, "\n  {}", path.display());
+    }
+    if paths.len() > MAX_LISTED_PATHS {
+        let _ = write!(list, "\n  … and {} more", paths.len() - MAX_LISTED_PATHS);
+    }
+    list
+}
+
+impl CopyError {
+    /// Stable code identifying the kind of error, such as `WTS-CPY-001`.
+    ///
+    /// Codes never change meaning, so scripts can match on them and users
+    /// can search for them in the documentation.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::ReadDirError { .. } => "WTS-CPY-001",
+            Self::CreateDirError { .. } => "WTS-CPY-002",
+            Self::FileCopyError { .. } => "WTS-CPY-003",
+            Self::ReadLinkError { .. } => "WTS-CPY-004",
+            Self::CreateSymlinkError { .. } => "WTS-CPY-005",
+            Self::CreateFifoError { .. } => "WTS-CPY-006",
+            Self::MetadataError { .. } => "WTS-CPY-007",
+            Self::EnumerationError { .. } => "WTS-CPY-008",
+            Self::PermissionDenied { .. } => "WTS-CPY-009",
+            Self::Cancelled { .. } => "WTS-CPY-010",
+        }
+    }
+
+    /// A suggestion for fixing the error.
+    #[must_use]
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::ReadDirError { .. } => "Check that the directory exists and is readable.",
+            Self::CreateDirError { .. } => "Check the permissions and free space of the target.",
+            Self::FileCopyError { .. } => {
+                "Check that the source is readable and the target writable."
+            }
+            Self::ReadLinkError { .. } => "Check that the symlink is readable.",
+            Self::CreateSymlinkError { .. } => {
+                "Check that the target's parent directory is writable and nothing else is in the way."
+            }
+            Self::CreateFifoError { .. } => "Check that the target's parent directory is writable.",
+            Self::MetadataError { .. } => "Check that the path still exists and is readable.",
+            Self::EnumerationError { .. } => "Check the permissions of the directory being copied.",
+            Self::PermissionDenied { .. } => {
+                "Fix the ownership of the listed paths, or rerun with --sudo-fallback to copy them with sudo."
+            }
+            Self::Cancelled { .. } => "Rerun setup to finish the copy.",
+        }
+    }
+
+    /// Whether the error was caused by missing permissions.
+    #[must_use]
+    pub fn is_permission_denied(&self) -> bool {
+        match self {
+            Self::PermissionDenied { .. } => true,
+            _ => self
+                .io_error()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::PermissionDenied),
+        }
+    }
+
+    /// Whether the error was caused by a path that does not exist.
+    #[must_use]
+    pub fn is_not_found(&self) -> bool {
+        self.io_error()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+    }
+
+    /// The underlying IO error, if any.
+    const fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::ReadDirError { io_error, .. }
+            | Self::CreateDirError { io_error, .. }
+            | Self::FileCopyError { io_error, .. }
+            | Self::ReadLinkError { io_error, .. }
+            | Self::CreateSymlinkError { io_error, .. }
+            | Self::CreateFifoError { io_error, .. }
+            | Self::MetadataError { io_error, .. } => Some(io_error),
+            Self::EnumerationError { .. }
+            | Self::Cancelled { .. }
+            | Self::PermissionDenied { .. } => None,
+        }
+    }
 }
@@ -5,8 +5,10 @@
 //! * Parallel directory enumeration using `jwalk`
 //! * Parallel file copying using `rayon`
 //! * Copy-on-write support via `reflink-copy` (APFS, Btrfs, `ReFS`)
+//! * Single-file cloning with hard link, reflink, and copy fallbacks
 //! * Progress callbacks for UI integration
 //! * Fast file counting
+//! * Full or sampled post-copy verification
 //!
 //! # Example
 //!
@@ -29,8 +31,16 @@ mod copy;
 mod count;
 mod error;
 mod progress;
+mod verify;
 
-pub use copy::{CopyResult, copy_directory, copy_file, overwrite_file};
-pub use count::{count_files, count_files_with_progress, disk_usage};
+pub use copy::{
+    CloneMethod, CloneStrategy, CopyResult, STREAMING_COPY_THRESHOLD, clone_directory_into,
+    clone_or_copy_file, copy_directory, copy_directory_cancellable, copy_directory_filtered,
+    copy_file, overwrite_file, supports_reflink,
+};
+pub use count::{
+    EntryCounts, count_entries_with_progress, count_files, count_files_with_progress, disk_usage,
+};
 pub use error::CopyError;
 pub use progress::{CopyProgress, ProgressTracker};
+pub use verify::{SMALL_FILE_BYTES, VerifyMode, VerifyReport, verify_copy};
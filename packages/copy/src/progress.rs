@@ -16,6 +16,11 @@ pub struct CopyProgress {
     pub files_copied: u64,
     /// Current file being copied (if any).
     pub current_file: Option<String>,
+    /// Total size in bytes of the file being streamed, or 0 when the copy
+    /// does not report bytes.
+    pub bytes_total: u64,
+    /// Bytes of the current file streamed so far.
+    pub bytes_copied: u64,
 }
 
 impl CopyProgress {
@@ -26,9 +31,19 @@ impl CopyProgress {
             files_total,
             files_copied,
             current_file,
+            bytes_total: 0,
+            bytes_copied: 0,
         }
     }
 
+    /// Attach byte progress for a single file being streamed.
+    #[must_use]
+    pub const fn with_bytes(mut self, bytes_copied: u64, bytes_total: u64) -> Self {
+        self.bytes_copied = bytes_copied;
+        self.bytes_total = bytes_total;
+        self
+    }
+
     /// Calculate progress as a percentage (0.0 to 100.0).
     #[must_use]
     #[allow(clippy::cast_precision_loss)]
@@ -0,0 +1,335 @@
+//! Post-copy verification by content hashing.
+//!
+//! Full verification hashes every copied file on both sides. Sampled
+//! verification hashes a random subset of large files plus every small file
+//! (configs, lockfiles, `.env` files), where a corrupt copy is most likely
+//! to matter and hashing is cheap.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fmt;
+use std::fs::File;
+use std::hash::{BuildHasher, RandomState};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::copy::{EntryKind, enumerate_directory};
+use crate::error::CopyError;
+
+/// Files at or below this size are always hashed, even when sampling.
+pub const SMALL_FILE_BYTES: u64 = 64 * 1024;
+
+/// How much of a copy to verify.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// Hash every file.
+    Full,
+    /// Hash every small file and roughly `percent`% of the others.
+    Sample {
+        /// Share of large files to hash, from 1 to 100.
+        percent: u8,
+    },
+}
+
+impl VerifyMode {
+    /// Whether a file of `size` bytes at `rel_path` should be hashed.
+    fn selects(self, rel_path: &Path, size: u64, state: &RandomState) -> bool {
+        match self {
+            Self::Full => true,
+            Self::Sample { percent } => {
+                size <= SMALL_FILE_BYTES || state.hash_one(rel_path) % 100 < u64::from(percent)
+            }
+        }
+    }
+}
+
+impl fmt::Display for VerifyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "full"),
+            Self::Sample { percent } => write!(f, "sample:{percent}%"),
+        }
+    }
+}
+
+impl FromStr for VerifyMode {
+    type Err = String;
+
+    /// Parse `full` or `sample:<percent>%` (the `%` is optional).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "full" {
+            return Ok(Self::Full);
+        }
+        let Some(rate) = s.strip_prefix("sample:") else {
+            return Err(format!(
+                "invalid verify mode '{s}': expected 'full' or 'sample:<percent>%'"
+            ));
+        };
+        let rate = rate.strip_suffix('%').unwrap_or(rate);
+        match rate.parse::<u8>() {
+            Ok(percent @ 1..=100) => Ok(Self::Sample { percent }),
+            _ => Err(format!(
+                "invalid sample rate '{rate}': expected a percentage from 1 to 100"
+            )),
+        }
+    }
+}
+
+/// Outcome of verifying one or more copies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Regular files that were copied.
+    pub files_total: u64,
+    /// Files whose contents were hashed.
+    pub files_checked: u64,
+    /// Bytes hashed on the source side.
+    pub bytes_checked: u64,
+    /// Target files whose contents differ from the source.
+    pub mismatched: Vec<PathBuf>,
+    /// Target files that are missing or unreadable.
+    pub missing: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    /// Whether every checked file matched its source.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+
+    /// Add the counts and failures of `other` to this report.
+    pub fn merge(&mut self, other: Self) {
+        self.files_total += other.files_total;
+        self.files_checked += other.files_checked;
+        self.bytes_checked += other.bytes_checked;
+        self.mismatched.extend(other.mismatched);
+        self.missing.extend(other.missing);
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked {} of {} files",
+            self.files_checked, self.files_total
+        )?;
+        if !self.mismatched.is_empty() {
+            write!(f, ", {} mismatched", self.mismatched.len())?;
+        }
+        if !self.missing.is_empty() {
+            write!(f, ", {} missing", self.missing.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// A file to compare, with its size.
+struct Candidate {
+    source: PathBuf,
+    target: PathBuf,
+    size: u64,
+}
+
+/// Compare a copied file or directory with its source.
+///
+/// For directories, `max_depth` and `filter` must be the ones the copy
+/// used, so files it left out are not reported as missing. Symlinks are not followed or
+/// compared. Sampling picks a different subset on every call.
+///
+/// # Errors
+///
+/// * If `source` cannot be read or enumerated
+pub fn verify_copy<P>(
+    source: &Path,
+    target: &Path,
+    mode: VerifyMode,
+    max_depth: Option<usize>,
+    filter: P,
+) -> Result<VerifyReport, CopyError>
+where
+    P: Fn(&Path, bool) -> bool + Send + Sync + 'static,
+{
+    let meta = source
+        .symlink_metadata()
+        .map_err(|e| CopyError::MetadataError {
+            path: source.to_path_buf(),
+            io_error: e,
+        })?;
+
+    let candidates: Vec<Candidate> = if meta.is_dir() {
+        let (entries, _filtered, _denied) = enumerate_directory(source, target, max_depth, filter)?;
+        entries
+            .into_iter()
+            .filter(|entry| entry.kind == EntryKind::File)
+            .filter_map(|entry| {
+                let size = entry.source.metadata().ok()?.len();
+                Some(Candidate {
+                    source: entry.source,
+                    target: entry.target,
+                    size,
+                })
+            })
+            .collect()
+    } else if meta.is_file() {
+        vec![Candidate {
+            source: source.to_path_buf(),
+            target: target.to_path_buf(),
+            size: meta.len(),
+        }]
+    } else {
+        Vec::new()
+    };
+
+    let files_total = candidates.len() as u64;
+    let state = RandomState::new();
+    let selected: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|c| {
+            let rel = c.source.strip_prefix(source).unwrap_or(&c.source);
+            mode.selects(rel, c.size, &state)
+        })
+        .collect();
+
+    let outcomes: Vec<(Candidate, Result<bool, CopyError>)> = selected
+        .into_par_iter()
+        .map(|c| {
+            let outcome = contents_match(&c.source, &c.target);
+            (c, outcome)
+        })
+        .collect();
+
+    let mut report = VerifyReport {
+        files_total,
+        ..VerifyReport::default()
+    };
+    for (candidate, outcome) in outcomes {
+        report.files_checked += 1;
+        report.bytes_checked += candidate.size;
+        match outcome {
+            Ok(true) => {}
+            Ok(false) => report.mismatched.push(candidate.target),
+            Err(e) => {
+                log::debug!("{e}");
+                report.missing.push(candidate.target);
+            }
+        }
+    }
+    report.mismatched.sort();
+    report.missing.sort();
+
+    Ok(report)
+}
+
+/// Whether `source` and `target` have the same contents.
+///
+/// Returns an error if `target` cannot be read; a source that cannot be
+/// read counts as a mismatch.
+fn contents_match(source: &Path, target: &Path) -> Result<bool, CopyError> {
+    let target_hash = hash_file(target)?;
+    Ok(hash_file(source).is_ok_and(|source_hash| source_hash == target_hash))
+}
+
+/// BLAKE3 hash of a file's contents.
+fn hash_file(path: &Path) -> Result<blake3::Hash, CopyError> {
+    let to_error = |e| CopyError::MetadataError {
+        path: path.to_path_buf(),
+        io_error: e,
+    };
+    let file = File::open(path).map_err(to_error)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(file).map_err(to_error)?;
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_verify_mode() {
+        assert_eq!("full".parse(), Ok(VerifyMode::Full));
+        assert_eq!("sample:5%".parse(), Ok(VerifyMode::Sample { percent: 5 }));
+        assert_eq!(
+            "sample:100".parse(),
+            Ok(VerifyMode::Sample { percent: 100 })
+        );
+        assert!("sample:0%".parse::<VerifyMode>().is_err());
+        assert!("sample:101%".parse::<VerifyMode>().is_err());
+        assert!("partial".parse::<VerifyMode>().is_err());
+        assert_eq!(VerifyMode::Sample { percent: 5 }.to_string(), "sample:5%");
+    }
+
+    #[test]
+    fn test_verify_full_reports_mismatch_and_missing() {
+        let source = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        fs::write(source.path().join("same.txt"), "same").unwrap();
+        fs::write(source.path().join("changed.txt"), "before").unwrap();
+        fs::write(source.path().join("gone.txt"), "gone").unwrap();
+        fs::write(target.path().join("same.txt"), "same").unwrap();
+        fs::write(target.path().join("changed.txt"), "after").unwrap();
+
+        let report = verify_copy(
+            source.path(),
+            target.path(),
+            VerifyMode::Full,
+            None,
+            |_, _| true,
+        )
+        .unwrap();
+
+        assert_eq!(report.files_total, 3);
+        assert_eq!(report.files_checked, 3);
+        assert_eq!(report.mismatched, vec![target.path().join("changed.txt")]);
+        assert_eq!(report.missing, vec![target.path().join("gone.txt")]);
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_verify_sample_always_checks_small_files() {
+        let source = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        let large = vec![7u8; usize::try_from(SMALL_FILE_BYTES).unwrap() + 1];
+        for i in 0..20 {
+            fs::write(source.path().join(format!("large{i}.bin")), &large).unwrap();
+            fs::write(target.path().join(format!("large{i}.bin")), &large).unwrap();
+        }
+        fs::write(source.path().join(".env"), "A=1").unwrap();
+        fs::write(target.path().join(".env"), "A=2").unwrap();
+
+        let mode = VerifyMode::Sample { percent: 1 };
+        let report = verify_copy(source.path(), target.path(), mode, None, |_, _| true).unwrap();
+
+        assert_eq!(report.files_total, 21);
+        assert!(report.files_checked < report.files_total);
+        assert_eq!(report.mismatched, vec![target.path().join(".env")]);
+    }
+
+    #[test]
+    fn test_verify_respects_filter() {
+        let source = TempDir::new().unwrap();
+        let target = TempDir::new().unwrap();
+        fs::write(source.path().join("kept.txt"), "kept").unwrap();
+        fs::write(source.path().join("ignored.txt"), "ignored").unwrap();
+        fs::write(target.path().join("kept.txt"), "kept").unwrap();
+
+        let report = verify_copy(
+            source.path(),
+            target.path(),
+            VerifyMode::Full,
+            None,
+            |p, _| !p.ends_with("ignored.txt"),
+        )
+        .unwrap();
+
+        assert_eq!(report.files_total, 1);
+        assert!(report.is_ok());
+    }
+}
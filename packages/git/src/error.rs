@@ -9,6 +9,9 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 /// Errors that can occur during git operations.
+///
+/// Each variant has a stable [`code`](Self::code). New variants take the
+/// next unused number; codes are never reused.
 #[derive(Debug, Error)]
 pub enum GitError {
     /// Failed to open repository.
@@ -49,6 +52,15 @@ pub enum GitError {
         source: git2::Error,
     },
 
+    /// Failed to finish a worktree cloned from another one.
+    #[error("Failed to set up cloned worktree at {}: {message}", path.display())]
+    WorktreeCloneError {
+        /// The cloned worktree.
+        path: PathBuf,
+        /// Error message from the git CLI.
+        message: String,
+    },
+
     /// Failed to prune stale worktrees.
     #[error("Failed to prune worktrees: {0}")]
     WorktreePruneError(#[source] git2::Error),
@@ -105,7 +117,164 @@ pub enum GitError {
         message: String,
     },
 
+    /// Failed to initialize submodules.
+    #[error("Failed to initialize submodules in {}: {message}", path.display())]
+    SubmoduleInitError {
+        /// Worktree whose submodules were being initialized.
+        path: PathBuf,
+        /// Error message from the git CLI.
+        message: String,
+    },
+
+    /// Failed to read a path from a git ref.
+    #[error("Failed to read {} at '{rev}': {source}", path.display())]
+    TreeReadError {
+        /// The ref (branch, tag, or commit) that was read.
+        rev: String,
+        /// Path within the repository.
+        path: PathBuf,
+        /// The underlying git2 error.
+        #[source]
+        source: git2::Error,
+    },
+
+    /// Failed to read a blob.
+    #[error("Failed to read blob {oid}: {source}")]
+    BlobReadError {
+        /// Object id of the blob.
+        oid: String,
+        /// The underlying git2 error.
+        #[source]
+        source: git2::Error,
+    },
+
     /// Path error.
     #[error("Invalid path: {}", .0.display())]
     InvalidPath(PathBuf),
+
+    /// Failed to compare a worktree's commit with a base branch.
+    #[error("Failed to compare with '{base}': {source}")]
+    CompareError {
+        /// The base branch or commit.
+        base: String,
+        /// The underlying git2 error.
+        #[source]
+        source: git2::Error,
+    },
+
+    /// Failed to update `.git/info/exclude`.
+    #[error("Failed to update {}: {source}", path.display())]
+    ExcludeWriteError {
+        /// Path of the exclude file.
+        path: PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Failed to turn an existing directory into a worktree.
+    #[error("Failed to adopt {} as a worktree: {message}", path.display())]
+    WorktreeAdoptError {
+        /// The directory being adopted.
+        path: PathBuf,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+impl GitError {
+    /// Stable code identifying the kind of error, such as `WTS-GIT-001`.
+    ///
+    /// Codes never change meaning, so scripts can match on them and users
+    /// can search for them in the documentation.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::OpenError { .. } => "WTS-GIT-001",
+            Self::DiscoverError { .. } => "WTS-GIT-002",
+            Self::BareRepository => "WTS-GIT-003",
+            Self::WorktreeListError(_) => "WTS-GIT-004",
+            Self::WorktreeCreateError { .. } => "WTS-GIT-005",
+            Self::WorktreeCloneError { .. } => "WTS-GIT-006",
+            Self::WorktreePruneError(_) => "WTS-GIT-007",
+            Self::StatusError(_) => "WTS-GIT-008",
+            Self::HeadError(_) => "WTS-GIT-009",
+            Self::BranchListError(_) => "WTS-GIT-010",
+            Self::RemoteListError(_) => "WTS-GIT-011",
+            Self::FetchError { .. } => "WTS-GIT-012",
+            Self::WorktreeNotFound(_) => "WTS-GIT-013",
+            Self::WorktreeRemoveError { .. } => "WTS-GIT-014",
+            Self::CannotRemoveMainWorktree(_) => "WTS-GIT-015",
+            Self::BranchDeleteError { .. } => "WTS-GIT-016",
+            Self::SubmoduleInitError { .. } => "WTS-GIT-017",
+            Self::TreeReadError { .. } => "WTS-GIT-018",
+            Self::BlobReadError { .. } => "WTS-GIT-019",
+            Self::InvalidPath(_) => "WTS-GIT-020",
+            Self::CompareError { .. } => "WTS-GIT-021",
+            Self::ExcludeWriteError { .. } => "WTS-GIT-022",
+            Self::WorktreeAdoptError { .. } => "WTS-GIT-023",
+        }
+    }
+
+    /// A suggestion for fixing the error.
+    #[must_use]
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::OpenError { .. } => "Check that the path is a git repository you can read.",
+            Self::DiscoverError { .. } => {
+                "Run from inside a git repository, or pass a path inside one."
+            }
+            Self::BareRepository => "Run from a repository with a working tree.",
+            Self::WorktreeListError(_) => {
+                "Run `git worktree list` to check the repository's worktree metadata."
+            }
+            Self::WorktreeCreateError { .. } => {
+                "Check that the target path is free and the branch is not checked out elsewhere."
+            }
+            Self::WorktreeCloneError { .. } => {
+                "Remove the partially created worktree with `git worktree remove --force` and retry."
+            }
+            Self::WorktreePruneError(_) => {
+                "Run `git worktree prune` manually to see the underlying problem."
+            }
+            Self::StatusError(_) => {
+                "Run `git status` in the worktree to see the underlying problem."
+            }
+            Self::HeadError(_) => "Make sure the repository has at least one commit.",
+            Self::BranchListError(_) => "Run `git branch` to check the repository's refs.",
+            Self::RemoteListError(_) => "Run `git remote -v` to check the repository's remotes.",
+            Self::FetchError { .. } => {
+                "Check the remote URL, your credentials, and your network connection."
+            }
+            Self::WorktreeNotFound(_) => "Run `git worktree list` to see the existing worktrees.",
+            Self::WorktreeRemoveError { .. } => {
+                "Commit or discard the worktree's changes, or remove it with --force."
+            }
+            Self::CannotRemoveMainWorktree(_) => {
+                "Remove linked worktrees only; the main worktree holds the repository."
+            }
+            Self::BranchDeleteError { .. } => {
+                "Merge the branch first, or delete it with `git branch -D`."
+            }
+            Self::SubmoduleInitError { .. } => {
+                "Run `git submodule update --init --recursive` in the worktree to see the underlying problem."
+            }
+            Self::TreeReadError { .. } => {
+                "Check that the ref exists (`git rev-parse <ref>`) and contains the path."
+            }
+            Self::BlobReadError { .. } => {
+                "Run `git fsck` to check the repository for missing objects."
+            }
+            Self::InvalidPath(_) => "Use a path inside the repository.",
+            Self::CompareError { .. } => {
+                "Check that the base branch exists (`git rev-parse <branch>`), or pass --base."
+            }
+            Self::ExcludeWriteError { .. } => {
+                "Check that the repository's .git/info directory is writable."
+            }
+            Self::WorktreeAdoptError { .. } => {
+                "Move the directory aside so setup can create the worktree there, or pass --existing-target files-only."
+            }
+        }
+    }
 }
@@ -4,7 +4,10 @@
 //!
 //! * Repository discovery and information
 //! * Worktree listing, creation, and management
-//! * File status detection (unstaged, untracked)
+//! * Worktree activity (uncommitted changes, ahead/behind a base branch)
+//! * File status detection (unstaged, untracked, ignored)
+//! * Submodule initialization
+//! * Reading committed files from other refs
 //!
 //! # Example
 //!
@@ -23,16 +26,25 @@
 mod error;
 mod repo;
 mod status;
+mod tree;
 mod worktree;
 
 pub use error::GitError;
 pub use git2::Repository;
 pub use repo::{
-    discover_repo, fetch_remote, get_current_branch, get_default_branch, get_local_branches,
-    get_recent_branches, get_remote_branches, get_remotes, get_repo_root, get_workdir, open_repo,
+    discover_repo, fetch_remote, get_current_branch, get_default_branch, get_hooks_dir,
+    get_hooks_path, get_local_branches, get_recent_branches, get_remote_branches, get_remotes,
+    get_repo_root, get_workdir, open_env_repo, open_repo,
+};
+pub use status::{
+    UntrackedPath, add_to_info_exclude, get_changed_files, get_unstaged_and_untracked_files,
+    get_untracked_and_ignored_paths, has_uncommitted_changes, untracked_unignored,
+};
+pub use tree::{
+    TreeFile, TreeFileKind, checkout_filter, list_tree_files, read_blob, read_blob_filtered,
 };
-pub use status::get_unstaged_and_untracked_files;
 pub use worktree::{
-    WorktreeCreateOptions, WorktreeInfo, create_worktree, delete_branch, get_main_worktree,
-    get_worktrees, prune_worktrees, remove_worktree,
+    WorktreeActivity, WorktreeCreateOptions, WorktreeInfo, adopt_cloned_files, adopt_directory,
+    create_worktree, delete_branch, get_main_worktree, get_worktrees, init_submodules,
+    prune_worktrees, remove_worktree, worktree_activity,
 };
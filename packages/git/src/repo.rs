@@ -47,6 +47,52 @@ pub fn discover_repo(path: &Path) -> Result<Repository, GitError> {
     })
 }
 
+/// Open the repository named by the `GIT_DIR` and `GIT_WORK_TREE`
+/// environment variables, as git itself would.
+///
+/// Returns `None` when neither variable is set, so callers fall back to
+/// discovering from a directory.
+///
+/// # Errors
+///
+/// * If the variables do not name a repository
+pub fn open_env_repo() -> Result<Option<Repository>, GitError> {
+    let git_dir = std::env::var_os("GIT_DIR").map(PathBuf::from);
+    let work_tree = std::env::var_os("GIT_WORK_TREE").map(PathBuf::from);
+    open_repo_from_vars(git_dir.as_deref(), work_tree.as_deref())
+}
+
+/// Open the repository named by `GIT_DIR` and `GIT_WORK_TREE` values.
+///
+/// Without a git directory, the repository is discovered from the current
+/// directory, as git does when only `GIT_WORK_TREE` is set.
+fn open_repo_from_vars(
+    git_dir: Option<&Path>,
+    work_tree: Option<&Path>,
+) -> Result<Option<Repository>, GitError> {
+    let repo = match (git_dir, work_tree) {
+        (None, None) => return Ok(None),
+        (Some(git_dir), _) => {
+            log::debug!("Opening repository from GIT_DIR {}", git_dir.display());
+            Repository::open(git_dir).map_err(|e| GitError::DiscoverError {
+                path: git_dir.to_path_buf(),
+                source: e,
+            })?
+        }
+        (None, Some(_)) => discover_repo(Path::new("."))?,
+    };
+
+    if let Some(work_tree) = work_tree {
+        log::debug!("Using GIT_WORK_TREE {}", work_tree.display());
+        repo.set_workdir(work_tree, false)
+            .map_err(|e| GitError::DiscoverError {
+                path: work_tree.to_path_buf(),
+                source: e,
+            })?;
+    }
+    Ok(Some(repo))
+}
+
 /// Get the root directory of the main worktree for a repository.
 ///
 /// This always returns the main worktree root, even when the repository
@@ -177,6 +223,36 @@ pub fn get_default_branch(repo: &Repository) -> Option<String> {
     None
 }
 
+/// Get the configured `core.hooksPath`, if any.
+///
+/// Returns the raw value; relative paths are relative to each worktree's
+/// root.
+#[must_use]
+pub fn get_hooks_path(repo: &Repository) -> Option<String> {
+    repo.config()
+        .ok()?
+        .get_string("core.hooksPath")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the directory Git runs hooks from for this worktree.
+///
+/// This is `core.hooksPath` when set (relative paths resolved against the
+/// worktree root), and otherwise the `hooks` directory of the shared
+/// `.git` directory, so every worktree of a repository uses the same hooks.
+#[must_use]
+pub fn get_hooks_dir(repo: &Repository) -> PathBuf {
+    get_hooks_path(repo).map_or_else(
+        || repo.commondir().join("hooks"),
+        |path| {
+            repo.workdir()
+                .unwrap_or_else(|| repo.commondir())
+                .join(path)
+        },
+    )
+}
+
 /// Get a list of remote names configured for the repository.
 ///
 /// # Arguments
@@ -410,4 +486,25 @@ mod tests {
             .output()
             .unwrap();
     }
+
+    #[test]
+    fn test_open_repo_from_vars() {
+        let (dir, _repo) = create_test_repo();
+        let other = TempDir::new().unwrap();
+        let workdir = |repo: &Repository| repo.workdir().unwrap().canonicalize().unwrap();
+
+        assert!(open_repo_from_vars(None, None).unwrap().is_none());
+
+        let repo = open_repo_from_vars(Some(&dir.path().join(".git")), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(workdir(&repo), dir.path().canonicalize().unwrap());
+
+        let repo = open_repo_from_vars(Some(&dir.path().join(".git")), Some(other.path()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(workdir(&repo), other.path().canonicalize().unwrap());
+
+        assert!(open_repo_from_vars(Some(other.path()), None).is_err());
+    }
 }
@@ -4,6 +4,11 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
 use git2::{Repository, Status, StatusOptions};
 
 use crate::error::GitError;
@@ -19,48 +24,319 @@ use crate::error::GitError;
 /// # Errors
 ///
 /// * If the repository status cannot be read
-pub fn get_unstaged_and_untracked_files(repo: &Repository) -> Result<Vec<String>, GitError> {
-    log::debug!("Getting unstaged and untracked files");
+pub fn get_unstaged_and_untracked_files(repo: &Repository) -> Result<Vec<PathBuf>, GitError> {
+    get_changed_files(repo, false)
+}
+
+/// Whether the worktree has uncommitted changes: staged, unstaged, or
+/// untracked files.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+///
+/// # Errors
+///
+/// * If the repository status cannot be read
+pub fn has_uncommitted_changes(repo: &Repository) -> Result<bool, GitError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).exclude_submodules(true);
+
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(GitError::StatusError)?;
+
+    let changed = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE
+        | Status::WT_MODIFIED
+        | Status::WT_DELETED
+        | Status::WT_TYPECHANGE
+        | Status::WT_RENAMED
+        | Status::WT_NEW;
+    Ok(statuses
+        .iter()
+        .any(|entry| entry.status().intersects(changed)))
+}
+
+/// Get a list of unstaged and untracked files, optionally including files
+/// ignored by `.gitignore`.
+///
+/// With `include_ignored`, every ignored file is listed individually
+/// (ignored directories are recursed into), so callers can pick out e.g.
+/// ignored `.env` files precisely. Returns file paths relative to the
+/// repository root, sorted.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+/// * `include_ignored` - Also list files ignored by `.gitignore`
+///
+/// # Errors
+///
+/// * If the repository status cannot be read
+pub fn get_changed_files(
+    repo: &Repository,
+    include_ignored: bool,
+) -> Result<Vec<PathBuf>, GitError> {
+    log::debug!("Getting unstaged and untracked files (include_ignored: {include_ignored})");
 
     let mut opts = StatusOptions::new();
     opts.include_untracked(true)
         .recurse_untracked_dirs(true)
+        .include_ignored(include_ignored)
+        .recurse_ignored_dirs(include_ignored)
         .exclude_submodules(true);
 
     let statuses = repo
         .statuses(Some(&mut opts))
         .map_err(GitError::StatusError)?;
 
+    let mut wanted = Status::WT_MODIFIED
+        | Status::WT_DELETED
+        | Status::WT_TYPECHANGE
+        | Status::WT_RENAMED
+        | Status::WT_NEW;
+    if include_ignored {
+        wanted |= Status::IGNORED;
+    }
+
     let mut files = Vec::new();
 
     for entry in statuses.iter() {
-        let status = entry.status();
-
-        // Check for unstaged modifications or untracked files
-        let dominated_by_unstaged = status.intersects(
-            Status::WT_MODIFIED
-                | Status::WT_DELETED
-                | Status::WT_TYPECHANGE
-                | Status::WT_RENAMED
-                | Status::WT_NEW,
-        );
-
-        if dominated_by_unstaged && let Some(path) = entry.path() {
-            files.push(path.to_string());
+        // Check for unstaged modifications, untracked, or ignored files
+        if entry.status().intersects(wanted) {
+            files.push(path_from_bytes(entry.path_bytes()));
         }
     }
 
     files.sort();
     files.dedup();
 
-    log::debug!("Found {} unstaged/untracked files", files.len());
+    log::debug!("Found {} changed files", files.len());
     Ok(files)
 }
 
+/// Convert a path reported by git to a [`PathBuf`] without losing bytes.
+///
+/// Git stores paths as raw bytes, so names that are not valid UTF-8 are
+/// kept as they are on Unix. Elsewhere git paths are UTF-8.
+#[must_use]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Of `paths` (relative to the worktree root), the ones `git status` lists
+/// as untracked: not in the index and not ignored.
+///
+/// Symlinks are checked as git sees them, as files, so a directory-only
+/// pattern such as `cache/` does not cover a symlink named `cache`. A
+/// directory whose contents are all untracked is returned as one entry
+/// ending in `/`, as `git status` shows it.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+/// * `paths` - Paths to check, relative to the worktree root
+///
+/// # Errors
+///
+/// * If the index or the ignore rules cannot be read
+pub fn untracked_unignored(repo: &Repository, paths: &[PathBuf]) -> Result<Vec<PathBuf>, GitError> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let workdir = repo.workdir().ok_or(GitError::BareRepository)?;
+
+    // libgit2 follows symlinks when matching ignore rules, so ask the git
+    // CLI, which judges a symlink as the file it is.
+    let output = Command::new("git")
+        .args([
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--untracked-files=normal",
+            "--",
+        ])
+        .args(paths)
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::StatusError(git2::Error::from_str(&e.to_string())))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::StatusError(git2::Error::from_str(stderr.trim())));
+    }
+
+    let mut untracked: Vec<PathBuf> = output
+        .stdout
+        .split(|&byte| byte == 0)
+        .filter_map(|entry| entry.strip_prefix(b"?? "))
+        .map(path_from_bytes)
+        .collect();
+    untracked.sort();
+    Ok(untracked)
+}
+
+/// Append `paths` (relative to the worktree root) to the repository's
+/// `info/exclude` file as anchored patterns, skipping those already there.
+///
+/// The file lives in the shared `.git` directory, so the patterns apply
+/// to every worktree of the repository. Returns the number of patterns
+/// added.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+/// * `paths` - Paths to exclude, relative to the worktree root; a
+///   trailing `/` limits the pattern to directories
+///
+/// # Errors
+///
+/// * If the exclude file cannot be read or written
+pub fn add_to_info_exclude(repo: &Repository, paths: &[PathBuf]) -> Result<usize, GitError> {
+    let path = repo.commondir().join("info").join("exclude");
+    let write_error = |source| GitError::ExcludeWriteError {
+        path: path.clone(),
+        source,
+    };
+
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(write_error(e)),
+    };
+    let present: BTreeSet<&str> = existing.lines().map(str::trim_end).collect();
+    let mut added = BTreeSet::new();
+    for path in paths {
+        let pattern = exclude_pattern(path);
+        if !present.contains(pattern.as_str()) {
+            added.insert(pattern);
+        }
+    }
+    if added.is_empty() {
+        return Ok(0);
+    }
+
+    let mut appended = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        appended.push('\n');
+    }
+    if !present.contains(EXCLUDE_HEADER) {
+        appended.push_str(EXCLUDE_HEADER);
+        appended.push('\n');
+    }
+    for pattern in &added {
+        appended.push_str(pattern);
+        appended.push('\n');
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(write_error)?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(appended.as_bytes()))
+        .map_err(write_error)?;
+    Ok(added.len())
+}
+
+/// Comment written above the patterns `add_to_info_exclude` adds.
+const EXCLUDE_HEADER: &str = "# Added by worktree-setup";
+
+/// An exclude pattern matching exactly `path`: anchored with a leading
+/// `/`, with glob and comment characters escaped.
+fn exclude_pattern(path: &Path) -> String {
+    let is_dir = path.to_string_lossy().ends_with('/');
+    let path = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut pattern = String::from("/");
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    if is_dir {
+        pattern.push('/');
+    }
+    pattern
+}
+
+/// An untracked or ignored path in a worktree.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UntrackedPath {
+    /// Path relative to the worktree root, without a trailing `/`.
+    pub path: String,
+    /// Whether the path is ignored by `.gitignore` (as opposed to untracked).
+    pub ignored: bool,
+    /// Whether the path is a directory whose contents are all untracked or
+    /// ignored (reported as one entry).
+    pub is_dir: bool,
+}
+
+/// Get untracked and ignored paths, collapsing wholly untracked or ignored
+/// directories into a single entry.
+///
+/// Returns paths relative to the repository root, sorted.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+///
+/// # Errors
+///
+/// * If the repository status cannot be read
+pub fn get_untracked_and_ignored_paths(repo: &Repository) -> Result<Vec<UntrackedPath>, GitError> {
+    log::debug!("Getting untracked and ignored paths");
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .include_ignored(true)
+        .recurse_untracked_dirs(false)
+        .recurse_ignored_dirs(false)
+        .exclude_submodules(true);
+
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(GitError::StatusError)?;
+
+    let mut paths: Vec<UntrackedPath> = statuses
+        .iter()
+        .filter(|entry| entry.status().intersects(Status::WT_NEW | Status::IGNORED))
+        .filter_map(|entry| {
+            let path = entry.path()?;
+            Some(UntrackedPath {
+                path: path.trim_end_matches('/').to_string(),
+                ignored: entry.status().contains(Status::IGNORED),
+                is_dir: path.ends_with('/'),
+            })
+        })
+        .collect();
+
+    paths.sort();
+
+    log::debug!("Found {} untracked/ignored paths", paths.len());
+    Ok(paths)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::process::Command;
     use tempfile::TempDir;
 
     fn create_test_repo() -> (TempDir, Repository) {
@@ -119,7 +395,98 @@ mod tests {
 
         let files = get_unstaged_and_untracked_files(&repo).unwrap();
         assert_eq!(files.len(), 2);
-        assert!(files.contains(&"README.md".to_string()));
-        assert!(files.contains(&"untracked.txt".to_string()));
+        assert!(files.contains(&PathBuf::from("README.md")));
+        assert!(files.contains(&PathBuf::from("untracked.txt")));
+    }
+
+    #[test]
+    fn test_get_changed_files_include_ignored() {
+        let (dir, repo) = create_test_repo();
+
+        std::fs::write(dir.path().join(".gitignore"), ".env\ncache/\n").unwrap();
+        std::fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+        std::fs::create_dir_all(dir.path().join("cache/nested")).unwrap();
+        std::fs::write(dir.path().join("cache/nested/a.bin"), "a").unwrap();
+
+        let without = get_changed_files(&repo, false).unwrap();
+        assert_eq!(without, vec![PathBuf::from(".gitignore")]);
+
+        let with = get_changed_files(&repo, true).unwrap();
+        assert_eq!(
+            with,
+            vec![
+                PathBuf::from(".env"),
+                PathBuf::from(".gitignore"),
+                PathBuf::from("cache/nested/a.bin"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_untracked_unignored() {
+        let (dir, repo) = create_test_repo();
+
+        std::fs::write(dir.path().join(".gitignore"), "/ignored\ncache/\n").unwrap();
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        for link in ["ignored", "cache", "shown"] {
+            std::os::unix::fs::symlink(dir.path().join("target"), dir.path().join(link)).unwrap();
+        }
+
+        let paths: Vec<PathBuf> = ["README.md", "ignored", "cache", "shown"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(
+            untracked_unignored(&repo, &paths).unwrap(),
+            vec![PathBuf::from("cache"), PathBuf::from("shown")]
+        );
+    }
+
+    #[test]
+    fn test_add_to_info_exclude() {
+        let (dir, repo) = create_test_repo();
+        std::fs::write(dir.path().join(".env"), "").unwrap();
+        std::fs::create_dir(dir.path().join("data")).unwrap();
+        std::fs::write(dir.path().join("data").join("a.db"), "").unwrap();
+        std::fs::write(dir.path().join("new[1].txt"), "").unwrap();
+
+        let paths = untracked_unignored(
+            &repo,
+            &[
+                PathBuf::from(".env"),
+                PathBuf::from("data"),
+                PathBuf::from("new[1].txt"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(add_to_info_exclude(&repo, &paths).unwrap(), 3);
+        assert_eq!(add_to_info_exclude(&repo, &paths).unwrap(), 0);
+
+        let exclude = std::fs::read_to_string(dir.path().join(".git/info/exclude")).unwrap();
+        assert!(exclude.contains("# Added by worktree-setup\n/.env\n/data/\n/new\\[1].txt\n"));
+        assert_eq!(exclude.matches(EXCLUDE_HEADER).count(), 1);
+        assert!(untracked_unignored(&repo, &paths).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_untracked_and_ignored_paths() {
+        let (dir, repo) = create_test_repo();
+
+        std::fs::write(dir.path().join(".gitignore"), ".env\ncache/\n").unwrap();
+        std::fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+        std::fs::create_dir_all(dir.path().join("cache/nested")).unwrap();
+        std::fs::write(dir.path().join("cache/nested/a.bin"), "a").unwrap();
+
+        let paths = get_untracked_and_ignored_paths(&repo).unwrap();
+        let find = |p: &str| paths.iter().find(|u| u.path == p).cloned();
+
+        let env = find(".env").unwrap();
+        assert!(env.ignored && !env.is_dir);
+        let cache = find("cache").unwrap();
+        assert!(cache.ignored && cache.is_dir);
+        let gitignore = find(".gitignore").unwrap();
+        assert!(!gitignore.ignored);
+        assert!(find("cache/nested/a.bin").is_none());
     }
 }
@@ -0,0 +1,278 @@
+//! Reading committed files from any ref.
+//!
+//! Lets setup copy files as they are committed on another branch, tag, or
+//! commit, without checking that ref out.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::{Path, PathBuf};
+
+use git2::{AttrCheckFlags, AttrValue, ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+
+use crate::error::GitError;
+use crate::status::path_from_bytes;
+
+/// How a file is stored in a git tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeFileKind {
+    /// A regular file.
+    File,
+    /// A regular file with the executable bit set.
+    Executable,
+    /// A symlink; the blob holds the link target.
+    Symlink,
+}
+
+/// A file committed at some ref.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeFile {
+    /// Path relative to the repository root.
+    pub path: PathBuf,
+    /// Object id of the file's blob (see [`read_blob`]).
+    pub oid: String,
+    /// How the file is stored.
+    pub kind: TreeFileKind,
+    /// Size of the blob in bytes.
+    pub size: u64,
+}
+
+/// List the files committed at `rev` under `path`.
+///
+/// `path` is relative to the repository root and may name a single file or
+/// a directory, whose files are listed recursively; an empty path lists the
+/// whole tree. Submodule entries are left out.
+///
+/// # Errors
+///
+/// * If `rev` does not resolve to a commit or tree
+/// * If `path` does not exist at `rev`
+pub fn list_tree_files(
+    repo: &Repository,
+    rev: &str,
+    path: &Path,
+) -> Result<Vec<TreeFile>, GitError> {
+    let error = |source| GitError::TreeReadError {
+        rev: rev.to_string(),
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let tree = repo
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(error)?;
+    let odb = repo.odb().map_err(error)?;
+    let file = |path: PathBuf, oid: Oid, filemode: i32| -> Result<TreeFile, git2::Error> {
+        let (size, _) = odb.read_header(oid)?;
+        Ok(TreeFile {
+            path,
+            oid: oid.to_string(),
+            kind: match filemode {
+                0o100_755 => TreeFileKind::Executable,
+                0o120_000 => TreeFileKind::Symlink,
+                _ => TreeFileKind::File,
+            },
+            size: size as u64,
+        })
+    };
+
+    let subtree = if path.as_os_str().is_empty() {
+        tree
+    } else {
+        let entry = tree.get_path(path).map_err(error)?;
+        match entry.kind() {
+            Some(ObjectType::Blob) => {
+                return Ok(vec![
+                    file(path.to_path_buf(), entry.id(), entry.filemode()).map_err(error)?,
+                ]);
+            }
+            Some(ObjectType::Tree) => repo.find_tree(entry.id()).map_err(error)?,
+            _ => return Ok(Vec::new()),
+        }
+    };
+
+    let mut files = Vec::new();
+    let mut walk_error = None;
+    subtree
+        .walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() != Some(ObjectType::Blob) {
+                return TreeWalkResult::Ok;
+            }
+            let relative = Path::new(root).join(path_from_bytes(entry.name_bytes()));
+            match file(path.join(relative), entry.id(), entry.filemode()) {
+                Ok(file) => {
+                    files.push(file);
+                    TreeWalkResult::Ok
+                }
+                Err(e) => {
+                    walk_error = Some(e);
+                    TreeWalkResult::Abort
+                }
+            }
+        })
+        .map_err(error)?;
+    if let Some(e) = walk_error {
+        return Err(error(e));
+    }
+    Ok(files)
+}
+
+/// Read the contents of the blob `oid` (see [`TreeFile::oid`]).
+///
+/// # Errors
+///
+/// * If `oid` is not a valid object id or names no blob
+pub fn read_blob(repo: &Repository, oid: &str) -> Result<Vec<u8>, GitError> {
+    let error = |source| GitError::BlobReadError {
+        oid: oid.to_string(),
+        source,
+    };
+    let oid = Oid::from_str(oid).map_err(error)?;
+    let blob = repo.find_blob(oid).map_err(error)?;
+    Ok(blob.content().to_vec())
+}
+
+/// Read the blob `oid` as a checkout of `path` would write it.
+///
+/// Runs the blob through the smudge filters and end-of-line conversion
+/// that `.gitattributes` in the working tree of `repo_dir` assigns to
+/// `path` (e.g. Git LFS), using `git cat-file --filters`.
+///
+/// # Errors
+///
+/// * If git cannot be run or a filter fails
+pub fn read_blob_filtered(repo_dir: &Path, oid: &str, path: &Path) -> Result<Vec<u8>, GitError> {
+    let error = |message: &str| GitError::BlobReadError {
+        oid: oid.to_string(),
+        source: git2::Error::from_str(message),
+    };
+    let output = std::process::Command::new("git")
+        .arg("cat-file")
+        .arg("--filters")
+        .arg(format!("--path={}", path.display()))
+        .arg(oid)
+        .current_dir(repo_dir)
+        .output()
+        .map_err(|e| error(&e.to_string()))?;
+    if !output.status.success() {
+        return Err(error(String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// The `.gitattributes` setting that makes a checkout of `path` differ from
+/// its committed blob, such as `filter=lfs` or `eol=crlf`.
+///
+/// Returns `None` when a checkout writes the blob unchanged.
+#[must_use]
+pub fn checkout_filter(repo: &Repository, path: &Path) -> Option<String> {
+    let attr = |name: &str| {
+        repo.get_attr(path, name, AttrCheckFlags::FILE_THEN_INDEX)
+            .ok()
+            .flatten()
+    };
+    if let AttrValue::String(driver) = AttrValue::from_string(attr("filter")) {
+        return Some(format!("filter={driver}"));
+    }
+    (attr("eol") == Some("crlf")).then(|| "eol=crlf".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_list_tree_files_reads_other_branch() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q", "-b", "main"]);
+        git(root, &["config", "user.email", "test@test.com"]);
+        git(root, &["config", "user.name", "Test"]);
+        std::fs::create_dir_all(root.join("configs/dev/nested")).unwrap();
+        std::fs::write(root.join("configs/dev/app.env"), "A=1\n").unwrap();
+        std::fs::write(root.join("configs/dev/nested/db.env"), "B=2\n").unwrap();
+        std::fs::write(root.join("README.md"), "# Test\n").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "Initial commit"]);
+        git(root, &["checkout", "-q", "-b", "feature"]);
+        git(root, &["rm", "-q", "-r", "configs"]);
+        git(root, &["commit", "-q", "-m", "Drop configs"]);
+
+        let repo = Repository::open(root).unwrap();
+
+        let files = list_tree_files(&repo, "main", Path::new("configs/dev")).unwrap();
+        let paths: Vec<&Path> = files.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("configs/dev/app.env"),
+                Path::new("configs/dev/nested/db.env")
+            ]
+        );
+        assert_eq!(files[0].kind, TreeFileKind::File);
+        assert_eq!(files[0].size, 4);
+        assert_eq!(read_blob(&repo, &files[0].oid).unwrap(), b"A=1\n");
+
+        let single = list_tree_files(&repo, "main", Path::new("README.md")).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].path, Path::new("README.md"));
+
+        assert!(list_tree_files(&repo, "HEAD", Path::new("configs/dev")).is_err());
+        assert!(list_tree_files(&repo, "no-such-branch", Path::new("")).is_err());
+    }
+
+    #[test]
+    fn test_read_blob_filtered_applies_gitattributes() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q", "-b", "main"]);
+        git(root, &["config", "user.email", "test@test.com"]);
+        git(root, &["config", "user.name", "Test"]);
+        git(root, &["config", "filter.upper.smudge", "tr a-z A-Z"]);
+        git(root, &["config", "filter.upper.clean", "cat"]);
+        std::fs::write(
+            root.join(".gitattributes"),
+            "*.bat eol=crlf\n*.up filter=upper\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("run.bat"), "a\nb\n").unwrap();
+        std::fs::write(root.join("data.up"), "abc\n").unwrap();
+        std::fs::write(root.join("plain.txt"), "abc\n").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "Initial commit"]);
+
+        let repo = Repository::open(root).unwrap();
+        let files = list_tree_files(&repo, "main", Path::new("")).unwrap();
+        let read = |name: &str| {
+            let file = files.iter().find(|f| f.path == Path::new(name)).unwrap();
+            (
+                checkout_filter(&repo, &file.path),
+                read_blob_filtered(root, &file.oid, &file.path).unwrap(),
+            )
+        };
+
+        assert_eq!(
+            read("run.bat"),
+            (Some("eol=crlf".to_string()), b"a\r\nb\r\n".to_vec())
+        );
+        assert_eq!(
+            read("data.up"),
+            (Some("filter=upper".to_string()), b"ABC\n".to_vec())
+        );
+        assert_eq!(read("plain.txt"), (None, b"abc\n".to_vec()));
+    }
+}
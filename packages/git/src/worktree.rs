@@ -6,11 +6,13 @@
 
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::UNIX_EPOCH;
 
 use git2::Repository;
 
 use crate::error::GitError;
-use crate::repo::get_repo_root;
+use crate::repo::{get_repo_root, open_repo};
+use crate::status::{has_uncommitted_changes, path_from_bytes};
 
 /// Information about a git worktree.
 #[derive(Debug, Clone)]
@@ -36,6 +38,9 @@ pub struct WorktreeCreateOptions {
     pub detach: bool,
     /// Force creation even if the path is already registered as a worktree.
     pub force: bool,
+    /// Register the worktree without checking out any files (used when the
+    /// files are cloned from another worktree, see [`adopt_cloned_files`]).
+    pub no_checkout: bool,
 }
 
 /// Get a list of all worktrees for a repository.
@@ -75,6 +80,91 @@ pub fn get_worktrees(repo: &Repository) -> Result<Vec<WorktreeInfo>, GitError> {
     Ok(worktrees)
 }
 
+/// How a worktree stands: uncommitted changes, and its commit compared
+/// with a base branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorktreeActivity {
+    /// Whether the worktree has staged, unstaged, or untracked changes.
+    pub dirty: bool,
+    /// Commits at the worktree's HEAD that the base does not contain.
+    pub ahead: usize,
+    /// Commits on the base that the worktree's HEAD does not contain.
+    pub behind: usize,
+    /// Commit time of the worktree's HEAD, in seconds since the Unix epoch.
+    pub last_commit: i64,
+    /// When the worktree was last worked in, in seconds since the Unix
+    /// epoch: the later of `last_commit` and the last change to its git
+    /// directory (creating the worktree, a checkout, or staging files).
+    pub last_active: i64,
+}
+
+impl WorktreeActivity {
+    /// Whether everything committed in the worktree is in the base.
+    #[must_use]
+    pub const fn is_merged(&self) -> bool {
+        self.ahead == 0
+    }
+}
+
+/// Inspect the worktree at `worktree_path` against the branch or commit
+/// `base`.
+///
+/// # Errors
+///
+/// * If the worktree cannot be opened or has no commit checked out
+/// * If `base` does not resolve to a commit
+/// * If the worktree's status cannot be read
+pub fn worktree_activity(worktree_path: &Path, base: &str) -> Result<WorktreeActivity, GitError> {
+    let repo = open_repo(worktree_path)?;
+    // Read before the status check below, which may refresh the index.
+    let git_dir_changed = git_dir_changed(&repo);
+    let error = |source| GitError::CompareError {
+        base: base.to_string(),
+        source,
+    };
+
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(GitError::HeadError)?;
+    let base = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(error)?;
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head.id(), base.id())
+        .map_err(error)?;
+
+    Ok(WorktreeActivity {
+        dirty: has_uncommitted_changes(&repo)?,
+        ahead,
+        behind,
+        last_commit: head.time().seconds(),
+        last_active: head.time().seconds().max(git_dir_changed),
+    })
+}
+
+/// When the git directory of `repo`, its `HEAD`, or its index last
+/// changed, in seconds since the Unix epoch (0 if unknown).
+fn git_dir_changed(repo: &Repository) -> i64 {
+    let git_dir = repo.path();
+    [
+        git_dir.to_path_buf(),
+        git_dir.join("HEAD"),
+        git_dir.join("index"),
+    ]
+    .iter()
+    .filter_map(|path| {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    })
+    .filter_map(|modified| modified.duration_since(UNIX_EPOCH).ok())
+    .map(|age| i64::try_from(age.as_secs()).unwrap_or(i64::MAX))
+    .max()
+    .unwrap_or(0)
+}
+
 /// Get worktree info from a repository.
 fn get_worktree_info_from_repo(repo: &Repository, path: &Path, is_main: bool) -> WorktreeInfo {
     let branch = repo.head().ok().and_then(|head| {
@@ -156,30 +246,29 @@ pub fn create_worktree(
         args.push("--detach");
     }
 
-    // Convert path to string for the command
-    let path_str = path.to_string_lossy();
+    if options.no_checkout {
+        args.push("--no-checkout");
+    }
 
     // Handle new branch
-    let new_branch_owned: String;
     if let Some(ref branch_name) = options.new_branch {
         args.push("-b");
-        new_branch_owned = branch_name.clone();
-        args.push(&new_branch_owned);
-    }
-
-    args.push(&path_str);
-
-    // Handle existing branch
-    let branch_owned: String;
-    if let Some(ref branch_name) = options.branch {
-        branch_owned = branch_name.clone();
-        args.push(&branch_owned);
+        args.push(branch_name);
     }
 
-    log::debug!("Running: git {}", args.join(" "));
+    // The path is passed as an `OsStr` so non-UTF-8 names survive.
+    let branch = options.branch.as_deref();
+    log::debug!(
+        "Running: git {} {} {}",
+        args.join(" "),
+        path.display(),
+        branch.unwrap_or_default()
+    );
 
     let output = Command::new("git")
         .args(&args)
+        .arg(path)
+        .args(branch)
         .current_dir(&repo_root)
         .output()
         .map_err(|e| GitError::WorktreeCreateError {
@@ -272,13 +361,15 @@ pub fn remove_worktree(
         args.push("--force");
     }
 
-    let path_str = worktree_path.to_string_lossy();
-    args.push(&path_str);
-
-    log::debug!("Running: git {}", args.join(" "));
+    log::debug!(
+        "Running: git {} {}",
+        args.join(" "),
+        worktree_path.display()
+    );
 
     let output = Command::new("git")
         .args(&args)
+        .arg(worktree_path)
         .current_dir(&repo_root)
         .output()
         .map_err(|e| GitError::WorktreeRemoveError {
@@ -339,6 +430,206 @@ pub fn delete_branch(repo: &Repository, branch: &str, force: bool) -> Result<(),
     Ok(())
 }
 
+/// Bring a worktree whose files were cloned from another one in sync with
+/// its own HEAD.
+///
+/// The worktree must have been created with `no_checkout`. Copies the
+/// source worktree's index into the new worktree's git directory and runs
+/// `git reset --hard`, so tracked files end up matching HEAD while
+/// untracked and ignored files (dependencies, build caches) are kept as
+/// cloned. Starting from the source index means only tracked files whose
+/// contents differ from HEAD are rewritten.
+///
+/// # Arguments
+///
+/// * `source_worktree` - Worktree the files were cloned from
+/// * `worktree_path` - The new worktree
+///
+/// # Errors
+///
+/// * If either worktree's index path cannot be resolved
+/// * If the index cannot be copied
+/// * If `git reset --hard` fails
+pub fn adopt_cloned_files(source_worktree: &Path, worktree_path: &Path) -> Result<(), GitError> {
+    log::info!(
+        "Adopting files cloned from {} into {}",
+        source_worktree.display(),
+        worktree_path.display()
+    );
+
+    let to_error = |message: String| GitError::WorktreeCloneError {
+        path: worktree_path.to_path_buf(),
+        message,
+    };
+
+    let source_index = index_path(source_worktree).map_err(to_error)?;
+    let target_index = index_path(worktree_path).map_err(to_error)?;
+    if source_index.is_file() {
+        std::fs::copy(&source_index, &target_index).map_err(|e| {
+            to_error(format!(
+                "failed to copy index {}: {e}",
+                source_index.display()
+            ))
+        })?;
+    }
+
+    run_git(worktree_path, &["reset", "--hard", "--quiet"]).map_err(to_error)?;
+
+    log::info!("Adopted cloned files in {}", worktree_path.display());
+    Ok(())
+}
+
+/// Register an existing directory that is not a worktree as one, keeping
+/// its files.
+///
+/// A directory with a `.git` file (a worktree whose registration was
+/// pruned or that was moved by hand) is reconnected with
+/// `git worktree repair`. Any other directory gets a worktree created
+/// next to it without a checkout, whose `.git` file is then moved in.
+/// Tracked files missing from the directory are checked out; files
+/// already there are kept, so those that differ from the checked-out
+/// commit show up in `git status` as changes.
+///
+/// # Arguments
+///
+/// * `repo` - The repository
+/// * `path` - The directory to adopt
+/// * `options` - Branch to check out; `no_checkout` is implied
+///
+/// # Errors
+///
+/// * If the directory has a `.git` that `git worktree repair` cannot fix
+/// * If the worktree cannot be created next to the directory
+/// * If the `.git` file cannot be moved or the index cannot be reset
+pub fn adopt_directory(
+    repo: &Repository,
+    path: &Path,
+    options: &WorktreeCreateOptions,
+) -> Result<(), GitError> {
+    log::info!("Adopting {} as a worktree", path.display());
+
+    let to_error = |message: String| GitError::WorktreeAdoptError {
+        path: path.to_path_buf(),
+        message,
+    };
+    let repo_root = get_repo_root(repo)?;
+    let path_arg = path.to_string_lossy();
+
+    if path.join(".git").exists() {
+        run_git(&repo_root, &["worktree", "repair", &path_arg]).map_err(to_error)?;
+        return Ok(());
+    }
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| to_error("the path has no directory name".to_string()))?;
+    let mut staging_name = std::ffi::OsString::from(".");
+    staging_name.push(name);
+    staging_name.push(".adopt");
+    let staging = path.with_file_name(staging_name);
+    if staging.exists() {
+        return Err(to_error(format!("{} already exists", staging.display())));
+    }
+
+    let staged = WorktreeCreateOptions {
+        no_checkout: true,
+        ..options.clone()
+    };
+    create_worktree(repo, &staging, &staged)?;
+
+    let moved = std::fs::rename(staging.join(".git"), path.join(".git"))
+        .and_then(|()| std::fs::remove_dir(&staging));
+    if let Err(e) = moved {
+        // Leave nothing half-registered behind.
+        let _ = run_git(
+            &repo_root,
+            &["worktree", "remove", "--force", &staging.to_string_lossy()],
+        );
+        return Err(to_error(format!("failed to move the .git file: {e}")));
+    }
+
+    run_git(&repo_root, &["worktree", "repair", &path_arg]).map_err(to_error)?;
+    run_git(path, &["reset", "--quiet"]).map_err(to_error)?;
+    let missing = run_git(path, &["ls-files", "--deleted", "-z"]).map_err(to_error)?;
+    let missing: Vec<&str> = missing.split('\0').filter(|f| !f.is_empty()).collect();
+    for chunk in missing.chunks(500) {
+        let mut args = vec!["checkout-index", "--"];
+        args.extend(chunk);
+        run_git(path, &args).map_err(to_error)?;
+    }
+
+    log::info!("Adopted {} as a worktree", path.display());
+    Ok(())
+}
+
+/// Absolute path of the index file of the worktree at `worktree_path`.
+fn index_path(worktree_path: &Path) -> Result<PathBuf, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--path-format=absolute", "--git-path", "index"])
+        .current_dir(worktree_path)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    // Parsed from the raw bytes: the repository path may not be UTF-8.
+    let stdout = output.stdout.trim_ascii_end();
+    Ok(path_from_bytes(stdout))
+}
+
+/// Run git in `dir` and return its trimmed stdout, or its stderr on failure.
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    log::debug!("Running: git {}", args.join(" "));
+
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Initialize and update submodules in a worktree using the git CLI.
+///
+/// Runs `git submodule update --init --recursive` so that submodule paths
+/// are populated before configs found inside them are applied.
+///
+/// # Arguments
+///
+/// * `worktree_path` - Path to the worktree
+///
+/// # Errors
+///
+/// * If the git CLI command fails
+pub fn init_submodules(worktree_path: &Path) -> Result<(), GitError> {
+    log::info!("Initializing submodules in {}", worktree_path.display());
+
+    let output = Command::new("git")
+        .args(["submodule", "update", "--init", "--recursive"])
+        .current_dir(worktree_path)
+        .output()
+        .map_err(|e| GitError::SubmoduleInitError {
+            path: worktree_path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::SubmoduleInitError {
+            path: worktree_path.to_path_buf(),
+            message: stderr.trim().to_string(),
+        });
+    }
+
+    log::info!("Initialized submodules in {}", worktree_path.display());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -440,6 +731,158 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_adopt_cloned_files() {
+        let (dir, repo) = create_test_repo();
+        std::fs::write(dir.path().join("README.md"), "# Modified").unwrap();
+        std::fs::write(dir.path().join("cache.bin"), "cached").unwrap();
+
+        let wt_path = dir.path().join("cloned-wt");
+        let options = WorktreeCreateOptions {
+            new_branch: Some("cloned-branch".to_string()),
+            no_checkout: true,
+            ..Default::default()
+        };
+        create_worktree(&repo, &wt_path, &options).unwrap();
+        assert!(!wt_path.join("README.md").exists());
+
+        for name in ["README.md", "cache.bin"] {
+            std::fs::copy(dir.path().join(name), wt_path.join(name)).unwrap();
+        }
+        adopt_cloned_files(dir.path(), &wt_path).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(wt_path.join("README.md")).unwrap(),
+            "# Test"
+        );
+        assert!(wt_path.join("cache.bin").exists());
+        let status = run_git(&wt_path, &["status", "--porcelain"]).unwrap();
+        assert_eq!(status, "?? cache.bin");
+    }
+
+    #[test]
+    fn test_adopt_directory() {
+        let (dir, repo) = create_test_repo();
+        std::fs::write(dir.path().join("LICENSE"), "MIT").unwrap();
+        run_git(dir.path(), &["add", "LICENSE"]).unwrap();
+        run_git(dir.path(), &["commit", "-m", "Add license"]).unwrap();
+        let plain = dir.path().join("plain");
+        std::fs::create_dir(&plain).unwrap();
+        std::fs::write(plain.join("README.md"), "# Local").unwrap();
+        std::fs::write(plain.join("notes.txt"), "notes").unwrap();
+
+        let options = WorktreeCreateOptions {
+            new_branch: Some("adopted".to_string()),
+            ..Default::default()
+        };
+        adopt_directory(&repo, &plain, &options).unwrap();
+
+        assert!(!dir.path().join(".plain.adopt").exists());
+        let worktrees = get_worktrees(&repo).unwrap();
+        let adopted = worktrees
+            .iter()
+            .find(|wt| wt.path.ends_with("plain"))
+            .unwrap();
+        assert_eq!(adopted.branch.as_deref(), Some("adopted"));
+        assert_eq!(
+            std::fs::read_to_string(plain.join("README.md")).unwrap(),
+            "# Local"
+        );
+        assert!(plain.join("LICENSE").exists());
+        let status = run_git(&plain, &["status", "--porcelain"]).unwrap();
+        assert_eq!(status, "M README.md\n?? notes.txt");
+
+        // Moving a worktree by hand leaves it unregistered until repaired.
+        let moved = dir.path().join("moved");
+        std::fs::rename(&plain, &moved).unwrap();
+        adopt_directory(&repo, &moved, &options).unwrap();
+        assert!(
+            get_worktrees(&repo)
+                .unwrap()
+                .iter()
+                .any(|wt| wt.path.ends_with("moved"))
+        );
+    }
+
+    #[test]
+    fn test_worktree_activity() {
+        let (dir, _repo) = create_test_repo();
+        let base = run_git(dir.path(), &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap();
+        let wt_path = dir.path().join("activity-wt");
+        run_git(
+            dir.path(),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "activity",
+                &wt_path.to_string_lossy(),
+            ],
+        )
+        .unwrap();
+
+        let activity = worktree_activity(&wt_path, &base).unwrap();
+        assert!(activity.is_merged());
+        assert!(!activity.dirty);
+        assert!(activity.last_commit > 0);
+
+        std::fs::write(wt_path.join("notes.txt"), "wip").unwrap();
+        assert!(worktree_activity(&wt_path, &base).unwrap().dirty);
+
+        // Staged changes count too.
+        run_git(&wt_path, &["add", "notes.txt"]).unwrap();
+        assert!(worktree_activity(&wt_path, &base).unwrap().dirty);
+
+        run_git(&wt_path, &["commit", "-q", "-m", "Add notes"]).unwrap();
+        std::fs::write(dir.path().join("README.md"), "# Changed").unwrap();
+        run_git(dir.path(), &["commit", "-q", "-am", "Change readme"]).unwrap();
+        let activity = worktree_activity(&wt_path, &base).unwrap();
+        assert_eq!((activity.ahead, activity.behind), (1, 1));
+        assert!(!activity.is_merged());
+        assert!(!activity.dirty);
+
+        assert!(matches!(
+            worktree_activity(&wt_path, "no-such-branch"),
+            Err(GitError::CompareError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_worktree_activity_of_new_worktree_on_old_commit() {
+        let (dir, _repo) = create_test_repo();
+        let base = run_git(dir.path(), &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap();
+        let old = Command::new("git")
+            .args(["commit", "-q", "--allow-empty", "-m", "Old"])
+            .env("GIT_COMMITTER_DATE", "946684800 +0000")
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(old.success());
+        let wt_path = dir.path().join("old-wt");
+        run_git(
+            dir.path(),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "old",
+                &wt_path.to_string_lossy(),
+            ],
+        )
+        .unwrap();
+
+        // The commit is old, but the worktree was just created.
+        let activity = worktree_activity(&wt_path, &base).unwrap();
+        assert_eq!(activity.last_commit, 946_684_800);
+        let now = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(activity.last_active >= i64::try_from(now).unwrap() - 3600);
+    }
+
     #[test]
     fn test_remove_worktree() {
         let (dir, repo) = create_test_repo();
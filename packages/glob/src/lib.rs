@@ -31,8 +31,11 @@
 #![allow(clippy::multiple_crate_versions)]
 
 mod resolve;
+mod worktree_ignore;
 
 pub use resolve::{
-    DEFAULT_SKIP_DIRS, GlobResolver, GlobResolverOptions, ResolvedPath, filter_descendants,
-    is_glob_pattern, resolve_exact, resolve_glob, resolve_globs_batched,
+    DEFAULT_SKIP_DIRS, GlobResolver, GlobResolverOptions, GlobWalkError, ResolvedPath,
+    filter_descendants, is_glob_pattern, list_files, literal_prefix, resolve_exact, resolve_glob,
+    resolve_glob_with_errors, resolve_globs_batched,
 };
+pub use worktree_ignore::{WORKTREE_IGNORE_FILE, WorktreeIgnore};
@@ -26,6 +26,41 @@ pub struct ResolvedPath {
     pub display: String,
 }
 
+/// A problem that kept part of the tree from being searched.
+///
+/// Reported for invalid patterns, directories that could not be read, and
+/// entries whose metadata could not be read. Matches below such a path
+/// are missing from the results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobWalkError {
+    /// Path that could not be read, if the error concerns one.
+    pub path: Option<PathBuf>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl GlobWalkError {
+    /// Describe a walker error concerning `path`, preferring the plain IO
+    /// error message.
+    fn from_walk(path: Option<PathBuf>, err: &jwalk::Error) -> Self {
+        Self {
+            path,
+            message: err
+                .io_error()
+                .map_or_else(|| err.to_string(), ToString::to_string),
+        }
+    }
+}
+
+impl std::fmt::Display for GlobWalkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
 /// Options controlling glob resolution behavior.
 #[derive(Debug, Clone)]
 pub struct GlobResolverOptions {
@@ -186,6 +221,103 @@ pub fn is_glob_pattern(pattern: &str) -> bool {
     pattern.contains('*') || pattern.contains('?') || pattern.contains('[')
 }
 
+/// Return the leading components of `pattern` that contain no glob
+/// metacharacters.
+///
+/// For example, `"templates/**/*.example"` yields `"templates"` and
+/// `"*.log"` yields an empty path.
+#[must_use]
+pub fn literal_prefix(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| !is_glob_pattern(&c.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// List every non-directory entry beneath `dir`.
+///
+/// Honors [`GlobResolverOptions::skip_symlinks`],
+/// [`GlobResolverOptions::skip_dirs`] and containment the same way as
+/// [`resolve_glob`]. Results are sorted by path. Unlike glob resolution,
+/// the `seen` set is not consulted — callers listing a directory want
+/// all of its files.
+///
+/// # Arguments
+///
+/// * `dir` - Directory to list.
+/// * `containment_root` - Canonical boundary path for containment checks.
+/// * `options` - Resolution options.
+#[must_use]
+pub fn list_files(
+    dir: &Path,
+    containment_root: &Path,
+    options: &GlobResolverOptions,
+) -> Vec<ResolvedPath> {
+    let skip_symlinks = options.skip_symlinks;
+    let skip_dirs = options.skip_dirs.clone();
+
+    let walker = jwalk::WalkDirGeneric::<((), ())>::new(dir)
+        .skip_hidden(false)
+        .follow_links(false)
+        .sort(true)
+        // Serial for the busy-timeout reason documented on
+        // `resolve_with_matcher`.
+        .parallelism(jwalk::Parallelism::Serial)
+        .process_read_dir(move |depth, _path, _state, children| {
+            children.retain(|entry_result| {
+                let Ok(entry) = entry_result.as_ref() else {
+                    return false;
+                };
+                if skip_symlinks && entry.file_type.is_symlink() {
+                    return false;
+                }
+                if depth.is_some() && entry.file_type.is_dir() {
+                    return !skip_dirs
+                        .iter()
+                        .any(|skip| entry.file_name == skip.as_str());
+                }
+                true
+            });
+        });
+
+    let mut results = Vec::new();
+
+    for entry_result in walker {
+        let entry = match entry_result {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::warn!("Walker error listing {}: {err}", dir.display());
+                continue;
+            }
+        };
+
+        if entry.depth == 0 || entry.file_type().is_dir() {
+            continue;
+        }
+
+        let path = entry.path();
+        let Ok(canonical) = path.canonicalize() else {
+            continue;
+        };
+
+        if options.enforce_containment && !canonical.starts_with(containment_root) {
+            log::warn!(
+                "File escapes containment boundary, skipping: {}",
+                path.display()
+            );
+            continue;
+        }
+
+        let display = canonical.strip_prefix(containment_root).map_or_else(
+            |_| path.to_string_lossy().to_string(),
+            |r| r.to_string_lossy().to_string(),
+        );
+        results.push(ResolvedPath { canonical, display });
+    }
+
+    results
+}
+
 /// Filter out paths that are descendants of other paths in the list.
 ///
 /// For example, if the list contains both `node_modules/` and
@@ -277,11 +409,48 @@ pub fn resolve_glob(
     containment_root: &Path,
     seen: &mut BTreeSet<PathBuf>,
     options: &GlobResolverOptions,
+) -> Vec<ResolvedPath> {
+    resolve_glob_with_errors(
+        pattern,
+        base_dir,
+        containment_root,
+        seen,
+        options,
+        &mut Vec::new(),
+    )
+}
+
+/// Resolve a glob pattern like [`resolve_glob`], collecting the problems
+/// that left parts of the tree unsearched in `errors`.
+///
+/// An invalid pattern yields no matches and one error. Unreadable
+/// directories and entries are reported and skipped; the walk goes on.
+///
+/// # Arguments
+///
+/// * `pattern` - The glob pattern (e.g., `"**/dist"`, `"*.log"`).
+/// * `base_dir` - Directory to walk and match against.
+/// * `containment_root` - Canonical boundary path for containment checks.
+/// * `seen` - Set of already-resolved canonical paths (for dedup and
+///   ancestor checks).
+/// * `options` - Resolution options.
+/// * `errors` - Receives every problem met during the walk.
+pub fn resolve_glob_with_errors(
+    pattern: &str,
+    base_dir: &Path,
+    containment_root: &Path,
+    seen: &mut BTreeSet<PathBuf>,
+    options: &GlobResolverOptions,
+    errors: &mut Vec<GlobWalkError>,
 ) -> Vec<ResolvedPath> {
     let matcher = match globset::Glob::new(pattern) {
         Ok(g) => Matcher::Single(g.compile_matcher()),
         Err(e) => {
             log::warn!("Invalid glob pattern '{pattern}': {e}");
+            errors.push(GlobWalkError {
+                path: None,
+                message: format!("invalid glob pattern '{pattern}': {e}"),
+            });
             return Vec::new();
         }
     };
@@ -293,6 +462,7 @@ pub fn resolve_glob(
         containment_root,
         seen,
         options,
+        errors,
     )
 }
 
@@ -357,6 +527,7 @@ pub fn resolve_globs_batched(
         containment_root,
         seen,
         options,
+        &mut Vec::new(),
     )
 }
 
@@ -414,6 +585,7 @@ fn resolve_with_matcher(
     containment_root: &Path,
     seen: &mut BTreeSet<PathBuf>,
     options: &GlobResolverOptions,
+    errors: &mut Vec<GlobWalkError>,
 ) -> Vec<ResolvedPath> {
     let skip_symlinks = options.skip_symlinks;
     let skip_dirs = options.skip_dirs.clone();
@@ -427,8 +599,9 @@ fn resolve_with_matcher(
         .parallelism(jwalk::Parallelism::Serial)
         .process_read_dir(move |depth, path, _state, children| {
             children.retain_mut(|entry_result| {
+                // Errors are kept so the walk loop below reports them
                 let Ok(entry) = entry_result.as_mut() else {
-                    return false;
+                    return true;
                 };
 
                 // Skip symlinks if configured
@@ -436,8 +609,6 @@ fn resolve_with_matcher(
                     return false;
                 }
 
-                let name = entry.file_name.to_string_lossy();
-
                 if entry.file_type.is_dir() {
                     // jwalk calls process_read_dir for the walk root's
                     // *parent* first (depth=None). Skip pruning there.
@@ -446,7 +617,10 @@ fn resolve_with_matcher(
                     }
 
                     // Prune skip_dirs entirely (don't yield, don't descend)
-                    if skip_dirs.iter().any(|skip| name.as_ref() == skip.as_str()) {
+                    if skip_dirs
+                        .iter()
+                        .any(|skip| entry.file_name == skip.as_str())
+                    {
                         return false;
                     }
 
@@ -455,7 +629,7 @@ fn resolve_with_matcher(
                     let relative = path
                         .strip_prefix(&base_dir_owned)
                         .unwrap_or(path)
-                        .join(name.as_ref());
+                        .join(&entry.file_name);
                     if matcher_for_prune.is_match(&relative) {
                         entry.read_children_path = None;
                     }
@@ -478,10 +652,23 @@ fn resolve_with_matcher(
                     "Glob walker error for {label} in {}: {err}",
                     base_dir.display()
                 );
+                errors.push(GlobWalkError::from_walk(
+                    err.path().map(Path::to_path_buf),
+                    &err,
+                ));
                 continue;
             }
         };
 
+        // A directory whose children could not be listed is still yielded
+        if let Some(err) = &entry.read_children_error {
+            log::warn!(
+                "Glob walker could not read {} for {label}: {err}",
+                entry.path().display()
+            );
+            errors.push(GlobWalkError::from_walk(Some(entry.path()), err));
+        }
+
         // Skip the root directory itself (depth 0)
         if entry.depth == 0 {
             continue;
@@ -553,6 +740,38 @@ mod tests {
         assert!(!is_glob_pattern("path/to/file"));
     }
 
+    #[test]
+    fn test_literal_prefix() {
+        assert_eq!(
+            literal_prefix("templates/**/*.example"),
+            PathBuf::from("templates")
+        );
+        assert_eq!(literal_prefix("a/b/*.txt"), PathBuf::from("a/b"));
+        assert_eq!(literal_prefix("*.log"), PathBuf::new());
+    }
+
+    #[test]
+    fn test_list_files_recurses_and_skips() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("tpl/nested")).unwrap();
+        fs::create_dir_all(root.join("tpl/node_modules")).unwrap();
+        fs::write(root.join("tpl/a.example"), "a").unwrap();
+        fs::write(root.join("tpl/nested/b.example"), "b").unwrap();
+        fs::write(root.join("tpl/node_modules/c"), "c").unwrap();
+        symlink(root.join("tpl/a.example"), root.join("tpl/link")).unwrap();
+
+        let canonical_root = root.canonicalize().unwrap();
+        let options = GlobResolverOptions {
+            skip_dirs: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+        let files = list_files(&root.join("tpl"), &canonical_root, &options);
+
+        let displays: Vec<&str> = files.iter().map(|f| f.display.as_str()).collect();
+        assert_eq!(displays, vec!["tpl/a.example", "tpl/nested/b.example"]);
+    }
+
     #[test]
     fn test_resolve_exact_existing_path() {
         let tmp = tempfile::tempdir().unwrap();
@@ -638,6 +857,58 @@ mod tests {
         assert!(displays.contains("b/dist"));
     }
 
+    #[test]
+    fn test_resolve_glob_with_errors_reports_unsearched_paths() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("open/dist")).unwrap();
+        fs::create_dir_all(root.join("locked/dist")).unwrap();
+        let canonical = root.canonicalize().unwrap();
+        let options = GlobResolverOptions::default();
+
+        let mut errors = Vec::new();
+        let results = resolve_glob_with_errors(
+            "[",
+            root,
+            &canonical,
+            &mut BTreeSet::new(),
+            &options,
+            &mut errors,
+        );
+        assert!(results.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, None);
+
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(root.join("locked")).is_ok();
+        let mut errors = Vec::new();
+        let results = resolve_glob_with_errors(
+            "*/dist",
+            root,
+            &canonical,
+            &mut BTreeSet::new(),
+            &options,
+            &mut errors,
+        );
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Permissions do not apply to root, which can read everything
+        if readable {
+            assert_eq!(results.len(), 2);
+            assert!(errors.is_empty());
+        } else {
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].display, "open/dist");
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                errors[0].path.as_deref(),
+                Some(root.join("locked").as_path())
+            );
+        }
+    }
+
     #[test]
     fn test_resolve_glob_skips_symlinks() {
         let tmp = tempfile::tempdir().unwrap();
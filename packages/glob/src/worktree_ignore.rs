@@ -0,0 +1,334 @@
+//! `.worktreeignore` support.
+//!
+//! A `.worktreeignore` file uses gitignore syntax to exclude paths from
+//! every file operation (symlinks, copies, globs, templates and unstaged
+//! replication). Files may live at the repository root and in any
+//! subdirectory; patterns are relative to the directory containing the
+//! file, and deeper files take precedence over shallower ones — exactly
+//! like `.gitignore`.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::resolve::DEFAULT_SKIP_DIRS;
+
+/// File name of the per-directory ignore file.
+pub const WORKTREE_IGNORE_FILE: &str = ".worktreeignore";
+
+/// Compiled set of `.worktreeignore` files for a repository.
+///
+/// Load once per run with [`WorktreeIgnore::load`] and share the result
+/// between the planner and the copy engine. The default value ignores
+/// nothing.
+#[derive(Debug, Clone, Default)]
+pub struct WorktreeIgnore {
+    /// Root the ignore files were loaded from.
+    root: PathBuf,
+    /// Canonical form of `root`, used to match canonicalized paths.
+    canonical_root: Option<PathBuf>,
+    /// Matchers keyed by their directory relative to `root`, shallowest first.
+    matchers: Vec<(PathBuf, Gitignore)>,
+    /// Directories relative to `root` ignored with everything beneath them
+    /// (see [`WorktreeIgnore::exclude_dir`]).
+    excluded: Vec<PathBuf>,
+    /// Each loaded ignore file and its contents, shallowest first.
+    sources: Vec<(PathBuf, String)>,
+}
+
+impl WorktreeIgnore {
+    /// Load every `.worktreeignore` file under `root`.
+    ///
+    /// Directories in [`DEFAULT_SKIP_DIRS`] are not searched. Invalid
+    /// patterns are logged and skipped rather than failing the run, which
+    /// mirrors how git treats a malformed `.gitignore` line.
+    #[must_use]
+    pub fn load(root: &Path) -> Self {
+        log::debug!(
+            "Loading {WORKTREE_IGNORE_FILE} files from {}",
+            root.display()
+        );
+
+        let mut files: Vec<PathBuf> = jwalk::WalkDirGeneric::<((), ())>::new(root)
+            .skip_hidden(false)
+            .sort(false)
+            // Serial for the same reason as `resolve_glob`: the default
+            // rayon pool's busy timeout silently drops results under load.
+            .parallelism(jwalk::Parallelism::Serial)
+            .process_read_dir(|_depth, _path, _state, children| {
+                children.retain(|entry_result| {
+                    let Ok(entry) = entry_result.as_ref() else {
+                        return false;
+                    };
+                    !(entry.file_type.is_dir()
+                        && DEFAULT_SKIP_DIRS
+                            .iter()
+                            .any(|&skip| entry.file_name == skip))
+                });
+            })
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry.file_type().is_file() && entry.file_name() == WORKTREE_IGNORE_FILE
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        // Shallowest first so that deeper files are consulted last and win.
+        files.sort_by_key(|path| path.components().count());
+
+        let mut matchers = Vec::new();
+        let mut sources = Vec::new();
+        for file in files {
+            let Some(dir) = file.parent() else {
+                continue;
+            };
+            sources.push((
+                file.clone(),
+                std::fs::read_to_string(&file).unwrap_or_default(),
+            ));
+            let mut builder = GitignoreBuilder::new(dir);
+            if let Some(e) = builder.add(&file) {
+                log::warn!("Invalid pattern in {}: {e}", file.display());
+            }
+            match builder.build() {
+                Ok(matcher) if !matcher.is_empty() => {
+                    let rel_dir = dir.strip_prefix(root).unwrap_or(dir).to_path_buf();
+                    matchers.push((rel_dir, matcher));
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to load {}: {e}", file.display()),
+            }
+        }
+
+        log::debug!("Loaded {} {WORKTREE_IGNORE_FILE} file(s)", matchers.len());
+
+        Self {
+            root: root.to_path_buf(),
+            canonical_root: root.canonicalize().ok(),
+            matchers,
+            excluded: Vec::new(),
+            sources,
+        }
+    }
+
+    /// Every loaded ignore file with its contents, shallowest first, so
+    /// callers can tell when the rules change.
+    #[must_use]
+    pub fn sources(&self) -> &[(PathBuf, String)] {
+        &self.sources
+    }
+
+    /// Directories excluded with [`WorktreeIgnore::exclude_dir`], relative
+    /// to the root.
+    #[must_use]
+    pub fn excluded_dirs(&self) -> &[PathBuf] {
+        &self.excluded
+    }
+
+    /// Also ignore `dir` and everything beneath it, such as a worktree
+    /// created inside the main one.
+    ///
+    /// `dir` may be relative to the root or absolute; directories outside
+    /// the root are ignored already.
+    pub fn exclude_dir(&mut self, dir: &Path) {
+        if let Some(rel) = self.relative(dir) {
+            log::debug!("Excluding {}", rel.display());
+            self.excluded.push(rel.to_path_buf());
+        }
+    }
+
+    /// Whether nothing is ignored.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.matchers.is_empty() && self.excluded.is_empty()
+    }
+
+    /// Check whether `path` is excluded by a `.worktreeignore` file or
+    /// [`WorktreeIgnore::exclude_dir`].
+    ///
+    /// `path` may be relative to the root or absolute (either as given to
+    /// [`WorktreeIgnore::load`] or canonicalized). Paths outside the root
+    /// are never ignored. A path is ignored when it matches directly or
+    /// when any of its parent directories does.
+    #[must_use]
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let Some(rel) = self.relative(path) else {
+            return false;
+        };
+        if self.excluded.iter().any(|dir| rel.starts_with(dir)) {
+            return true;
+        }
+
+        // Parent directories first: once a directory is ignored nothing
+        // beneath it can be re-included.
+        let mut ancestors: Vec<&Path> = rel
+            .ancestors()
+            .skip(1)
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect();
+        ancestors.reverse();
+        if ancestors.iter().any(|dir| self.matches(dir, true)) {
+            return true;
+        }
+
+        self.matches(rel, is_dir)
+    }
+
+    /// Strip the root (raw or canonical) from `path`.
+    fn relative<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        if path.is_relative() {
+            return Some(path);
+        }
+        path.strip_prefix(&self.root).ok().or_else(|| {
+            self.canonical_root
+                .as_ref()
+                .and_then(|root| path.strip_prefix(root).ok())
+        })
+    }
+
+    /// Match a root-relative path against the deepest applicable matcher.
+    fn matches(&self, rel: &Path, is_dir: bool) -> bool {
+        for (dir, matcher) in self.matchers.iter().rev() {
+            let Ok(local) = rel.strip_prefix(dir) else {
+                continue;
+            };
+            if local.as_os_str().is_empty() {
+                continue;
+            }
+            let matched = matcher.matched(local, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_no_ignore_files_ignores_nothing() {
+        let dir = TempDir::new().unwrap();
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_empty());
+        assert!(!ignore.is_ignored(Path::new("anything"), false));
+    }
+
+    #[test]
+    fn test_root_patterns() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(WORKTREE_IGNORE_FILE), "*.log\ncache/\n").unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_ignored(Path::new("debug.log"), false));
+        assert!(ignore.is_ignored(Path::new("nested/debug.log"), false));
+        assert!(ignore.is_ignored(Path::new("cache"), true));
+        assert!(!ignore.is_ignored(Path::new("cache"), false));
+        assert!(!ignore.is_ignored(Path::new("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_excluded_dir() {
+        let dir = TempDir::new().unwrap();
+        let mut ignore = WorktreeIgnore::load(dir.path());
+        ignore.exclude_dir(&dir.path().join("worktrees/feature"));
+        ignore.exclude_dir(Path::new("/elsewhere"));
+
+        assert!(!ignore.is_empty());
+        assert!(ignore.is_ignored(Path::new("worktrees/feature"), true));
+        assert!(ignore.is_ignored(&dir.path().join("worktrees/feature/.env"), false));
+        assert!(!ignore.is_ignored(Path::new("worktrees"), true));
+        assert!(!ignore.is_ignored(Path::new("worktrees/feature-2"), true));
+    }
+
+    #[test]
+    fn test_parent_directory_ignored() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(WORKTREE_IGNORE_FILE), "data/\n").unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_ignored(Path::new("data/a/b.txt"), false));
+        assert!(ignore.is_ignored(&dir.path().join("data/a.txt"), false));
+    }
+
+    #[test]
+    fn test_nested_file_is_relative_to_its_directory() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("apps/web")).unwrap();
+        fs::write(
+            dir.path().join("apps/web").join(WORKTREE_IGNORE_FILE),
+            "/dist\n",
+        )
+        .unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_ignored(Path::new("apps/web/dist"), true));
+        assert!(!ignore.is_ignored(Path::new("dist"), true));
+        assert!(!ignore.is_ignored(Path::new("apps/dist"), true));
+    }
+
+    #[test]
+    fn test_nested_negation_overrides_root() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("keep")).unwrap();
+        fs::write(dir.path().join(WORKTREE_IGNORE_FILE), "*.env\n").unwrap();
+        fs::write(
+            dir.path().join("keep").join(WORKTREE_IGNORE_FILE),
+            "!local.env\n",
+        )
+        .unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_ignored(Path::new("local.env"), false));
+        assert!(!ignore.is_ignored(Path::new("keep/local.env"), false));
+        assert!(ignore.is_ignored(Path::new("keep/other.env"), false));
+    }
+
+    #[test]
+    fn test_paths_outside_root_are_not_ignored() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(WORKTREE_IGNORE_FILE), "*\n").unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(!ignore.is_ignored(Path::new("/somewhere/else.txt"), false));
+    }
+
+    #[test]
+    fn test_skip_dirs_are_not_searched() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(
+            dir.path()
+                .join("node_modules/pkg")
+                .join(WORKTREE_IGNORE_FILE),
+            "*\n",
+        )
+        .unwrap();
+
+        let ignore = WorktreeIgnore::load(dir.path());
+
+        assert!(ignore.is_empty());
+    }
+}
@@ -4,19 +4,37 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
 
 use worktree_setup_config::LoadedConfig;
-use worktree_setup_copy::CopyProgress;
+use worktree_setup_copy::{
+    CopyError, CopyProgress, CopyResult, VerifyMode, VerifyReport, clone_directory_into,
+    verify_copy,
+};
 use worktree_setup_git::{get_unstaged_and_untracked_files, open_repo};
+use worktree_setup_glob::WorktreeIgnore;
 
-use crate::OperationResult;
 use crate::copy::{
-    copy_directory_with_progress, copy_file_with_progress, overwrite_file_with_progress,
+    copy_directory_outcome, copy_file_outcome, ignore_filter, overwrite_file_outcome,
 };
+use crate::count_cache::CountCache;
+use crate::entry_hooks::{run_after_hook, run_before_hook};
 use crate::error::OperationError;
-use crate::plan::{OperationType, PlannedOperation, plan_operations, plan_unstaged_operations};
+use crate::journal::Journal;
+use crate::plan::{
+    OperationType, PlannedOperation, TargetModes, load_worktree_ignore, plan_operations,
+    plan_unstaged_operations,
+};
+use crate::ref_file::write_ref_file;
+use crate::source::SourceProviders;
 use crate::symlink::{create_symlink, force_create_symlink};
+use crate::template::{create_target_dirs, render_template_file, set_mode};
+use crate::{OperationOutcome, OperationResult};
 
 /// Record of a single file operation.
 #[derive(Debug, Clone)]
@@ -25,6 +43,8 @@ pub struct OperationRecord {
     pub path: String,
     /// Result of the operation.
     pub result: OperationResult,
+    /// Files that vanished from the source during a directory copy.
+    pub files_vanished: u64,
 }
 
 /// Options for applying a configuration.
@@ -39,6 +59,33 @@ pub struct ApplyConfigOptions {
     /// When `false` (default), resolved paths that escape the worktree
     /// root are skipped with a `"path escapes worktree boundary"` reason.
     pub allow_path_escape: bool,
+    /// Pre-loaded `.worktreeignore` rules for the main worktree.
+    ///
+    /// When `None`, the ignore files are loaded from the main worktree
+    /// during planning. Callers planning several configs should load the
+    /// rules once and share them here.
+    pub worktree_ignore: Option<Arc<WorktreeIgnore>>,
+    /// Cache of directory counts from earlier runs.
+    ///
+    /// When `Some`, directories whose counts are cached and still valid
+    /// are not rescanned, and fresh counts are recorded in the cache. The
+    /// caller is responsible for saving it afterwards.
+    pub count_cache: Option<Arc<CountCache>>,
+    /// How many levels below a directory to copy, for entries that set no
+    /// `maxDepth` of their own (`None` for no limit).
+    pub max_depth: Option<usize>,
+    /// Providers for `<scheme>:` sources of copy, overwrite and template
+    /// entries.
+    ///
+    /// When `None`, the built-in providers are used (see
+    /// [`SourceProviders::default`]).
+    pub source_providers: Option<Arc<SourceProviders>>,
+    /// How long a glob pattern may search before it is given up on.
+    ///
+    /// A pattern that times out matches nothing and is planned as a glob
+    /// error (or fails planning under `strictGlobs`). `None` waits for
+    /// every search to finish.
+    pub glob_timeout: Option<Duration>,
 }
 
 /// Result of applying a configuration.
@@ -58,7 +105,11 @@ pub struct ApplyResult {
 
 /// Apply a loaded configuration to a target worktree.
 ///
-/// This is a convenience wrapper around `plan_operations` + `execute_operation`.
+/// This is a convenience wrapper that plans with [`plan_operations`] (and
+/// [`plan_unstaged_operations`] when unstaged files are copied) and runs the
+/// plan through [`execute_plan`], so paths resolve exactly as they do for
+/// the CLI, including `/`-prefixed repo-root-relative paths. Callers that
+/// need progress, verification or rollback should call those directly.
 ///
 /// # Arguments
 ///
@@ -83,18 +134,40 @@ pub fn apply_config(
         target_worktree.display()
     );
 
-    let mut result = ApplyResult::default();
+    // Load ignore rules once for both planning passes
+    let ignore = options
+        .worktree_ignore
+        .clone()
+        .unwrap_or_else(|| Arc::new(load_worktree_ignore(main_worktree, target_worktree)));
+    let options = ApplyConfigOptions {
+        worktree_ignore: Some(Arc::clone(&ignore)),
+        ..options.clone()
+    };
 
-    // Plan and execute regular operations
-    let operations = plan_operations(config, main_worktree, target_worktree, options)?;
+    let mut operations = plan_operations(config, main_worktree, target_worktree, &options)?;
 
-    for op in &operations {
-        let op_result = execute_operation(op, |_, _| {})?;
-        let record = OperationRecord {
-            path: op.display_path.clone(),
-            result: op_result,
-        };
+    // Unstaged files come from git rather than the config
+    if options
+        .copy_unstaged
+        .or(config.config.copy_unstaged)
+        .unwrap_or(false)
+    {
+        log::info!("Copying unstaged and untracked files");
+
+        let repo = open_repo(main_worktree)?;
+        let files = get_unstaged_and_untracked_files(&repo)?;
+        operations.extend(plan_unstaged_operations(
+            &files,
+            main_worktree,
+            target_worktree,
+            &ignore,
+        ));
+    }
 
+    let executed = execute_plan(&operations, &ExecutionOptions::default())?;
+
+    let mut result = ApplyResult::default();
+    for (op, record) in operations.iter().zip(executed.records) {
         match op.operation_type {
             OperationType::Symlink => result.symlinks.push(record),
             OperationType::Copy | OperationType::CopyGlob => result.copies.push(record),
@@ -104,38 +177,222 @@ pub fn apply_config(
         }
     }
 
-    // Handle unstaged files separately (requires git operations)
-    let should_copy_unstaged = options.copy_unstaged.unwrap_or(config.config.copy_unstaged);
+    Ok(result)
+}
 
-    if should_copy_unstaged {
-        log::info!("Copying unstaged and untracked files");
+/// Options for [`execute_plan`].
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    /// Give up on an operation that runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Compare each executed copy with its source afterwards.
+    pub verify: Option<VerifyMode>,
+    /// Worktree root whose newly created paths are deleted again, and whose
+    /// replaced files are restored, if an operation fails (see
+    /// [`Journal`]). `None` leaves partial results in place.
+    pub rollback_root: Option<PathBuf>,
+}
 
-        let repo = open_repo(main_worktree)?;
-        let files = get_unstaged_and_untracked_files(&repo)?;
-        let unstaged_ops = plan_unstaged_operations(&files, main_worktree, target_worktree);
-
-        for op in &unstaged_ops {
-            let op_result = execute_operation(op, |_, _| {})?;
-            result.unstaged.push(OperationRecord {
-                path: op.display_path.clone(),
-                result: op_result,
-            });
+/// Result of [`execute_plan`].
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionResult {
+    /// One record per operation, in plan order.
+    pub records: Vec<OperationRecord>,
+    /// Combined verification report, when `verify` was set.
+    pub verify: Option<VerifyReport>,
+}
+
+/// What to do after an operation fails, as decided by
+/// [`PlanRunner::failed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureAction {
+    /// Stop and return the error, leaving what the run did in place.
+    Stop,
+    /// Undo everything the run changed under the rollback root, then stop
+    /// and return the error.
+    RollBack,
+    /// Undo the failed operation's partial output and go on with the next
+    /// one.
+    Continue,
+}
+
+/// Executes the steps of a plan for [`execute_plan_with`], which handles
+/// rollback.
+pub trait PlanRunner {
+    /// Execute the non-skipped operation at `index` in the plan.
+    ///
+    /// # Errors
+    ///
+    /// * If the operation fails; [`PlanRunner::failed`] decides what happens
+    ///   next
+    fn execute(&mut self, index: usize, op: &PlannedOperation) -> Result<(), OperationError>;
+
+    /// Called instead of [`PlanRunner::execute`] for operations planned to
+    /// be skipped.
+    fn skipped(&mut self, _index: usize, _op: &PlannedOperation) {}
+
+    /// Decide how to go on after the operation at `index` failed with
+    /// `error`. Rolls the run back by default.
+    fn failed(
+        &mut self,
+        _index: usize,
+        _op: &PlannedOperation,
+        _error: &OperationError,
+    ) -> FailureAction {
+        FailureAction::RollBack
+    }
+
+    /// Called after a rollback undid up to `changed` created or replaced
+    /// paths, with the ones that could not be undone. Logs those by
+    /// default.
+    fn rolled_back(&mut self, _changed: usize, failures: &[(PathBuf, io::Error)]) {
+        for (path, e) in failures {
+            log::warn!("Failed to roll back {}: {e}", path.display());
         }
     }
+}
 
-    Ok(result)
+/// Execute planned operations in order.
+///
+/// Operations can come from [`plan_operations`] or from the single-kind
+/// planners such as [`crate::plan_templates`], so callers can run any
+/// subset of a config. Stops at the first failing operation; with
+/// `rollback_root` set, everything created under it is deleted and
+/// everything replaced is restored first.
+///
+/// # Errors
+///
+/// * If an operation fails or times out
+/// * If verification cannot read a source
+pub fn execute_plan(
+    operations: &[PlannedOperation],
+    options: &ExecutionOptions,
+) -> Result<ExecutionResult, OperationError> {
+    let mut runner = PlanSteps {
+        options,
+        result: ExecutionResult {
+            verify: options.verify.map(|_| VerifyReport::default()),
+            ..ExecutionResult::default()
+        },
+    };
+    execute_plan_with(operations, options.rollback_root.as_deref(), &mut runner)?;
+    Ok(runner.result)
+}
+
+/// Execute planned operations in order through `runner`.
+///
+/// With `rollback_root` set, each operation's changes under it are
+/// recorded in a [`Journal`] first, and a failure is undone as
+/// [`PlanRunner::failed`] decides. Without it, nothing is rolled back.
+///
+/// # Errors
+///
+/// * If an operation fails and `runner` does not continue past it
+pub fn execute_plan_with<R: PlanRunner + ?Sized>(
+    operations: &[PlannedOperation],
+    rollback_root: Option<&Path>,
+    runner: &mut R,
+) -> Result<(), OperationError> {
+    let mut journal = rollback_root.map(Journal::new);
+
+    for (index, op) in operations.iter().enumerate() {
+        if op.will_skip {
+            runner.skipped(index, op);
+            continue;
+        }
+
+        let mut op_journal = rollback_root.map(Journal::new);
+        let result = match op_journal.as_mut().map(|journal| journal.begin(op)) {
+            Some(Err(e)) => Err(e),
+            _ => runner.execute(index, op),
+        };
+        let error = match result {
+            Ok(()) => {
+                if let (Some(journal), Some(op_journal)) = (journal.as_mut(), op_journal) {
+                    journal.append(op_journal);
+                }
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        match runner.failed(index, op, &error) {
+            FailureAction::Stop => return Err(error),
+            FailureAction::Continue => {
+                if let Some(op_journal) = op_journal {
+                    roll_back(op_journal, runner);
+                }
+            }
+            FailureAction::RollBack => {
+                if let (Some(mut journal), Some(op_journal)) = (journal, op_journal) {
+                    journal.append(op_journal);
+                    roll_back(journal, runner);
+                }
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Undo what `journal` recorded and report the outcome to `runner`.
+fn roll_back<R: PlanRunner + ?Sized>(journal: Journal, runner: &mut R) {
+    let changed = journal.created().len() + journal.replaced().len();
+    let failures = journal.rollback();
+    runner.rolled_back(changed, &failures);
+}
+
+/// [`PlanRunner`] behind [`execute_plan`].
+struct PlanSteps<'a> {
+    options: &'a ExecutionOptions,
+    result: ExecutionResult,
+}
+
+impl PlanRunner for PlanSteps<'_> {
+    fn execute(&mut self, _index: usize, op: &PlannedOperation) -> Result<(), OperationError> {
+        let outcome = match self.options.timeout {
+            Some(timeout) => execute_operation_with_timeout(op, timeout, |_, _| {})?,
+            None => execute_operation_outcome(op, |_, _| {})?,
+        };
+        if let (Some(mode), Some(report)) = (self.options.verify, self.result.verify.as_mut()) {
+            report.merge(verify_operation(op, mode)?);
+        }
+        self.record(op, outcome);
+        Ok(())
+    }
+
+    fn skipped(&mut self, _index: usize, op: &PlannedOperation) {
+        // Planned skips report themselves without touching the filesystem.
+        if let Ok(outcome) = execute_operation_outcome(op, |_, _| {}) {
+            self.record(op, outcome);
+        }
+    }
+}
+
+impl PlanSteps<'_> {
+    /// Record the outcome of `op`.
+    fn record(&mut self, op: &PlannedOperation, outcome: OperationOutcome) {
+        self.result.records.push(OperationRecord {
+            path: op.display_path.clone(),
+            result: outcome.result,
+            files_vanished: outcome.files_vanished,
+        });
+    }
 }
 
 /// Execute a single planned operation with progress callback.
 ///
 /// This function executes one operation that was previously planned by `plan_operations`.
 /// For directory operations, the progress callback will be called periodically with
-/// (`files_completed`, `files_total`).
+/// (`files_completed`, `files_total`). A single file of at least
+/// [`worktree_setup_copy::STREAMING_COPY_THRESHOLD`] bytes that cannot be
+/// reflinked reports (`bytes_copied`, `bytes_total`) instead.
 ///
 /// # Arguments
 ///
 /// * `op` - The planned operation to execute
-/// * `on_progress` - Progress callback for directory operations
+/// * `on_progress` - Progress callback for directory and large file operations
 ///
 /// # Returns
 ///
@@ -151,69 +408,355 @@ pub fn execute_operation<F>(
 where
     F: Fn(u64, u64) + Sync,
 {
+    execute_operation_outcome(op, on_progress).map(|outcome| outcome.result)
+}
+
+/// Execute a single planned operation like [`execute_operation`], also
+/// reporting files that vanished from a directory's source while it was
+/// being copied (e.g. build output deleted by a running build).
+///
+/// # Errors
+///
+/// * If the operation fails
+/// * If the entry's `before` or `after` hook fails
+pub fn execute_operation_outcome<F>(
+    op: &PlannedOperation,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
+where
+    F: Fn(u64, u64) + Sync,
+{
+    execute_operation_cancellable(op, None, on_progress)
+}
+
+/// Execute a planned operation like [`execute_operation_outcome`],
+/// stopping once `cancel` is set.
+///
+/// `cancel` is checked after the `before` hook, before each file of a
+/// directory copy, and before the `after` hook; a running hook command is
+/// killed when it is set.
+fn execute_operation_cancellable<F>(
+    op: &PlannedOperation,
+    cancel: Option<&AtomicBool>,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
+where
+    F: Fn(u64, u64) + Sync,
+{
+    let never = AtomicBool::new(false);
+    let cancel_flag = cancel.unwrap_or(&never);
+    let check_cancelled = || {
+        if cancel_flag.load(Ordering::Relaxed) {
+            Err(OperationError::from(CopyError::Cancelled {
+                path: op.target.clone(),
+            }))
+        } else {
+            Ok(())
+        }
+    };
+
     // If already marked as skip, return appropriate result
     if op.will_skip {
         return Ok(match op.skip_reason.as_deref() {
             Some("exists") => OperationResult::Exists,
             _ => OperationResult::Skipped,
-        });
+        }
+        .into());
     }
 
+    run_before_hook(op, cancel)?;
+    check_cancelled()?;
+    let created_dirs = match op.modes {
+        Some(TargetModes { dirs: Some(_), .. }) => create_target_dirs(&op.target)?,
+        _ => Vec::new(),
+    };
+    let outcome = run_operation(op, cancel_flag, on_progress)?;
+    apply_modes(op, &created_dirs, outcome.result)?;
+    check_cancelled()?;
+    run_after_hook(op, outcome.result, cancel)?;
+    Ok(outcome)
+}
+
+/// Give a written target its entry's `mode`, and the directories created
+/// for it their `dirMode`.
+///
+/// Directory modes are set after the file is written (innermost first), so
+/// read-only modes such as `0500` do not stop the write.
+fn apply_modes(
+    op: &PlannedOperation,
+    created_dirs: &[PathBuf],
+    result: OperationResult,
+) -> Result<(), OperationError> {
+    let Some(modes) = op.modes else {
+        return Ok(());
+    };
+    if let Some(mode) = modes.file
+        && matches!(
+            result,
+            OperationResult::Created | OperationResult::Overwritten
+        )
+    {
+        set_mode(&op.target, mode)?;
+    }
+    if let Some(mode) = modes.dirs {
+        for dir in created_dirs.iter().rev() {
+            set_mode(dir, mode)?;
+        }
+    }
+    Ok(())
+}
+
+/// Perform a planned operation that is not skipped.
+fn run_operation<F>(
+    op: &PlannedOperation,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
+where
+    F: Fn(u64, u64) + Sync,
+{
+    let overwrite = op.force_overwrite || op.operation_type == OperationType::Overwrite;
+    if let Some(source) = &op.ref_source {
+        let result = write_ref_file(source, &op.target, overwrite)?;
+        on_progress(1, 1);
+        return Ok(result.into());
+    }
+
+    if let Some(context) = &op.template
+        && !op.is_directory
+    {
+        let result = render_template_file(&op.source, &op.target, context, overwrite)?;
+        on_progress(1, 1);
+        return Ok(result.into());
+    }
+
+    if op.is_directory && op.operation_type != OperationType::Symlink {
+        // Directory overwrites are rare, so they copy like regular copies
+        // (only into a missing target) rather than deleting first.
+        let outcome = copy_directory_outcome(
+            &op.source,
+            &op.target,
+            op.max_depth,
+            op.ignore.clone(),
+            cancel,
+            |progress: &CopyProgress| {
+                on_progress(progress.files_copied, progress.files_total);
+            },
+        )?;
+        // Report completion
+        on_progress(op.file_count, op.file_count);
+        return Ok(outcome);
+    }
+
+    let forward = |progress: &CopyProgress| forward_bytes(progress, &on_progress);
     match op.operation_type {
         OperationType::Symlink => {
-            if op.force_overwrite {
+            let result = if op.force_overwrite {
                 force_create_symlink(&op.source, &op.target)
             } else {
                 create_symlink(&op.source, &op.target)
-            }
+            };
+            result.map(OperationOutcome::from)
         }
         OperationType::Copy | OperationType::CopyGlob | OperationType::Template => {
-            if op.is_directory {
-                // Directory copy with progress
-                let result = copy_directory_with_progress(
-                    &op.source,
-                    &op.target,
-                    |progress: &CopyProgress| {
-                        on_progress(progress.files_copied, progress.files_total);
-                    },
-                )?;
-                // Report completion
-                on_progress(op.file_count, op.file_count);
-                Ok(result)
-            } else {
-                // Single file copy
-                copy_file_with_progress(&op.source, &op.target, |progress: &CopyProgress| {
-                    on_progress(progress.files_copied, progress.files_total);
-                })
-            }
+            copy_file_outcome(&op.source, &op.target, forward)
         }
         OperationType::Overwrite | OperationType::Unstaged => {
-            if op.is_directory {
-                // For overwrite, we'd need to delete first then copy
-                // For now, treat as regular copy (directory overwrites are rare)
-                let result = copy_directory_with_progress(
-                    &op.source,
-                    &op.target,
-                    |progress: &CopyProgress| {
-                        on_progress(progress.files_copied, progress.files_total);
-                    },
-                )?;
-                on_progress(op.file_count, op.file_count);
-                Ok(result)
-            } else {
-                overwrite_file_with_progress(&op.source, &op.target, |progress: &CopyProgress| {
-                    on_progress(progress.files_copied, progress.files_total);
-                })
+            overwrite_file_outcome(&op.source, &op.target, forward)
+        }
+    }
+}
+
+/// Copy files added to a directory operation's source since it was copied.
+///
+/// Re-enumerates the source and copies every entry missing from the
+/// target, so files a running build created during a long copy are not
+/// left out. Returns how many were copied; operations other than
+/// directory copies copy nothing.
+///
+/// # Errors
+///
+/// * If the source cannot be enumerated or a file cannot be copied
+pub fn rescan_operation(op: &PlannedOperation) -> Result<u64, OperationError> {
+    if !op.is_directory || op.operation_type == OperationType::Symlink || op.will_skip {
+        return Ok(0);
+    }
+
+    let result = clone_directory_into(
+        &op.source,
+        &op.target,
+        op.max_depth,
+        ignore_filter(op.ignore.clone()),
+        |_| {},
+    )
+    .map_err(OperationError::from)?;
+
+    Ok(match result {
+        CopyResult::Created { files_copied, .. } => files_copied,
+        CopyResult::Exists | CopyResult::SourceNotFound => 0,
+    })
+}
+
+/// Compare the output of an executed copy operation with its source.
+///
+/// Symlink operations have nothing to verify and return an empty report.
+/// Directory copies are checked with the same `.worktreeignore` rules the
+/// copy applied.
+///
+/// # Errors
+///
+/// * If the source cannot be read
+pub fn verify_operation(
+    op: &PlannedOperation,
+    mode: VerifyMode,
+) -> Result<VerifyReport, OperationError> {
+    // Rendered templates differ from their sources by design, and files
+    // from another ref are not copies of the main worktree's files.
+    if op.operation_type == OperationType::Symlink
+        || op.template.is_some()
+        || op.ref_source.is_some()
+    {
+        return Ok(VerifyReport::default());
+    }
+
+    verify_copy(
+        &op.source,
+        &op.target,
+        mode,
+        op.max_depth,
+        ignore_filter(op.ignore.clone()),
+    )
+    .map_err(|e| OperationError::CopyModuleError(e.to_string()))
+}
+
+/// Forward byte progress from a streamed single-file copy.
+///
+/// Files that are copied in one call report no bytes and are left out.
+fn forward_bytes(progress: &CopyProgress, on_progress: &impl Fn(u64, u64)) {
+    if progress.bytes_total > 0 {
+        on_progress(progress.bytes_copied, progress.bytes_total);
+    }
+}
+
+/// Message from an operation running on a worker thread.
+enum WorkerMessage {
+    Progress(u64, u64),
+    Done(Result<OperationOutcome, OperationError>),
+}
+
+/// How long a cancelled operation may take to stop before its worker is
+/// abandoned.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// Execute a planned operation, cancelling it after `timeout`.
+///
+/// The operation runs on a worker thread while progress is forwarded to
+/// `on_progress` on the calling thread. If it has not finished when
+/// `timeout` elapses, the worker is cancelled, a running hook command is
+/// killed, and [`OperationError::Timeout`] is returned once the worker has
+/// stopped. A cancelled directory copy starts no further files, so nothing
+/// more is written into the target. A filesystem call that is blocked (for
+/// example, a read from a hung mount) cannot be interrupted, so a worker
+/// that has not stopped within a grace period is left behind; it may still
+/// finish the one file it was writing.
+///
+/// # Errors
+///
+/// * If the operation fails
+/// * If the operation does not finish within `timeout`
+pub fn execute_operation_with_timeout<F>(
+    op: &PlannedOperation,
+    timeout: Duration,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
+where
+    F: Fn(u64, u64),
+{
+    execute_with_deadline(op, timeout, CANCEL_GRACE, on_progress)
+}
+
+/// [`execute_operation_with_timeout`], waiting up to `grace` for a
+/// cancelled worker to stop.
+fn execute_with_deadline<F>(
+    op: &PlannedOperation,
+    timeout: Duration,
+    grace: Duration,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
+where
+    F: Fn(u64, u64),
+{
+    let (tx, rx) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let worker_op = op.clone();
+    let worker_cancel = Arc::clone(&cancel);
+    let worker = std::thread::spawn(move || {
+        let progress_tx = tx.clone();
+        let result = execute_operation_cancellable(
+            &worker_op,
+            Some(&worker_cancel),
+            move |completed, total| {
+                let _ = progress_tx.send(WorkerMessage::Progress(completed, total));
+            },
+        );
+        let _ = tx.send(WorkerMessage::Done(result));
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(WorkerMessage::Progress(completed, total)) => on_progress(completed, total),
+            Ok(WorkerMessage::Done(result)) => return result,
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!(
+                    "Cancelling {} after {timeout:?}; waiting for it to stop",
+                    op.display_path
+                );
+                cancel.store(true, Ordering::Relaxed);
+                if wait_for_worker(&rx, grace) {
+                    let _ = worker.join();
+                } else {
+                    log::warn!(
+                        "{} did not stop within {grace:?}; leaving it behind",
+                        op.display_path
+                    );
+                }
+                return Err(OperationError::Timeout {
+                    path: op.target.clone(),
+                    timeout,
+                });
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(OperationError::CopyModuleError(format!(
+                    "worker for {} exited without a result",
+                    op.display_path
+                )));
             }
         }
     }
 }
 
+/// Wait up to `grace` for a cancelled worker to report that it is done,
+/// returning whether it did.
+fn wait_for_worker(rx: &mpsc::Receiver<WorkerMessage>, grace: Duration) -> bool {
+    let deadline = Instant::now() + grace;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(WorkerMessage::Progress(..)) => {}
+            Ok(WorkerMessage::Done(_)) | Err(RecvTimeoutError::Disconnected) => return true,
+            Err(RecvTimeoutError::Timeout) => return false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
 
+    use crate::OperationHooks;
     use tempfile::TempDir;
     use worktree_setup_config::Config;
 
@@ -221,9 +764,9 @@ mod tests {
         LoadedConfig {
             config: Config {
                 description: "Test".to_string(),
-                symlinks: vec!["data".to_string()],
-                copy: vec!["config.json".to_string()],
-                overwrite: vec!["settings.json".to_string()],
+                symlinks: vec!["data".into()],
+                copy: vec!["config.json".into()],
+                overwrite: vec!["settings.json".into()],
                 ..Default::default()
             },
             config_path: dir.join("worktree.config.toml"),
@@ -267,4 +810,361 @@ mod tests {
         assert!(!result.copies.is_empty());
         assert!(target_dir.path().join("config.json").exists());
     }
+
+    #[test]
+    fn test_apply_config_directory_copy_honors_worktreeignore() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        fs::create_dir_all(main_dir.path().join("assets/cache")).unwrap();
+        fs::write(main_dir.path().join("assets/logo.svg"), "<svg/>").unwrap();
+        fs::write(main_dir.path().join("assets/cache/blob"), "x").unwrap();
+        fs::write(main_dir.path().join(".worktreeignore"), "assets/cache/\n").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["assets".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let result = apply_config(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(result.copies[0].result, OperationResult::Created);
+        assert!(target_dir.path().join("assets/logo.svg").exists());
+        assert!(!target_dir.path().join("assets/cache").exists());
+    }
+
+    #[test]
+    fn test_apply_config_resolves_root_relative_paths() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        fs::create_dir_all(main_dir.path().join("apps/web")).unwrap();
+        fs::write(main_dir.path().join("apps/web/.env"), "PORT=1").unwrap();
+        fs::write(main_dir.path().join(".tool-versions"), "node 22").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec![".env".into(), "/.tool-versions".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("apps/web/worktree.config.toml"),
+            config_dir: main_dir.path().join("apps/web"),
+            relative_path: "apps/web/worktree.config.toml".to_string(),
+        };
+
+        let result = apply_config(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+
+        let paths: Vec<_> = result.copies.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["apps/web/.env", ".tool-versions"]);
+        assert!(target_dir.path().join("apps/web/.env").exists());
+        assert!(target_dir.path().join(".tool-versions").exists());
+        assert!(!target_dir.path().join("apps/web/.tool-versions").exists());
+    }
+
+    fn planned_copy(main: &Path, target: &Path) -> PlannedOperation {
+        PlannedOperation {
+            display_path: "file.txt".to_string(),
+            operation_type: OperationType::Copy,
+            source: main.join("file.txt"),
+            target: target.join("file.txt"),
+            file_count: 1,
+            symlink_count: 0,
+            byte_count: 5,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    #[test]
+    fn test_execute_plan_verifies_and_rolls_back() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("file.txt"), "hello").unwrap();
+
+        let copy = planned_copy(main_dir.path(), target_dir.path());
+        let options = ExecutionOptions {
+            verify: Some(VerifyMode::Full),
+            rollback_root: Some(target_dir.path().to_path_buf()),
+            ..ExecutionOptions::default()
+        };
+        let result = execute_plan(std::slice::from_ref(&copy), &options).unwrap();
+        assert_eq!(result.records[0].result, OperationResult::Created);
+        assert!(result.verify.unwrap().is_ok());
+        fs::remove_file(target_dir.path().join("file.txt")).unwrap();
+
+        // The second copy fails because its parent is the file the first
+        // one created, so the first copy is rolled back.
+        let blocked = PlannedOperation {
+            target: target_dir.path().join("file.txt/nested.txt"),
+            ..copy.clone()
+        };
+        assert!(execute_plan(&[copy, blocked], &options).is_err());
+        assert!(!target_dir.path().join("file.txt").exists());
+    }
+
+    #[test]
+    fn test_rescan_operation_copies_files_added_after_the_copy() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = main_dir.path().join("dist");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.js"), "a").unwrap();
+
+        let op = PlannedOperation {
+            source: source.clone(),
+            target: target_dir.path().join("dist"),
+            is_directory: true,
+            ..planned_copy(main_dir.path(), target_dir.path())
+        };
+        assert_eq!(
+            execute_operation(&op, |_, _| {}).unwrap(),
+            OperationResult::Created
+        );
+
+        fs::write(source.join("b.js"), "b").unwrap();
+        fs::write(target_dir.path().join("dist/a.js"), "edited").unwrap();
+        assert_eq!(rescan_operation(&op).unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("dist/b.js")).unwrap(),
+            "b"
+        );
+        // Files already in the target are left alone.
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("dist/a.js")).unwrap(),
+            "edited"
+        );
+        assert_eq!(rescan_operation(&op).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_execute_operation_runs_entry_hooks() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("file.txt"), "hello").unwrap();
+
+        let hooks = |before: Option<&str>| {
+            Some(Arc::new(OperationHooks {
+                before: before.map(str::to_string),
+                after: Some(
+                    "echo \"$WORKTREE_SETUP_PATH $WORKTREE_SETUP_RESULT\" > hook.txt".to_string(),
+                ),
+                cwd: target_dir.path().to_path_buf(),
+            }))
+        };
+        let op = PlannedOperation {
+            hooks: hooks(None),
+            ref_source: None,
+            max_depth: None,
+            ..planned_copy(main_dir.path(), target_dir.path())
+        };
+        assert_eq!(
+            execute_operation(&op, |_, _| {}).unwrap(),
+            OperationResult::Created
+        );
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("hook.txt")).unwrap(),
+            "file.txt created\n"
+        );
+
+        // A failing `before` hook stops the operation.
+        fs::remove_file(target_dir.path().join("file.txt")).unwrap();
+        let op = PlannedOperation {
+            hooks: hooks(Some("exit 3")),
+            ref_source: None,
+            max_depth: None,
+            ..op
+        };
+        assert!(matches!(
+            execute_operation(&op, |_, _| {}),
+            Err(OperationError::HookFailed {
+                phase: "before",
+                ..
+            })
+        ));
+        assert!(!target_dir.path().join("file.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_operation_sets_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("file.txt"), "hello").unwrap();
+        let target = target_dir.path().join("secrets/dev/file.txt");
+
+        let op = PlannedOperation {
+            operation_type: OperationType::Template,
+            target: target.clone(),
+            modes: Some(TargetModes {
+                file: Some(0o600),
+                dirs: Some(0o500),
+            }),
+            ..planned_copy(main_dir.path(), target_dir.path())
+        };
+        assert_eq!(
+            execute_operation(&op, |_, _| {}).unwrap(),
+            OperationResult::Created
+        );
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode(&target), 0o600);
+        assert_eq!(mode(&target_dir.path().join("secrets/dev")), 0o500);
+        assert_eq!(mode(&target_dir.path().join("secrets")), 0o500);
+
+        // Let the temporary directory be removed.
+        for dir in ["secrets/dev", "secrets"] {
+            fs::set_permissions(
+                target_dir.path().join(dir),
+                fs::Permissions::from_mode(0o700),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_execute_operation_with_timeout_completes() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("file.txt"), "hello").unwrap();
+
+        let op = planned_copy(main_dir.path(), target_dir.path());
+        let result =
+            execute_operation_with_timeout(&op, Duration::from_secs(30), |_, _| {}).unwrap();
+
+        assert_eq!(result.result, OperationResult::Created);
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file.txt")).unwrap(),
+            "hello"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_operation_with_timeout_gives_up() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = main_dir.path().join("dist");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.js"), "a").unwrap();
+
+        // The `before` hook outlasts the timeout, standing in for a slow
+        // copy. Cancelling kills it along with the subshell it started.
+        let op = PlannedOperation {
+            source,
+            target: target_dir.path().join("dist"),
+            is_directory: true,
+            hooks: Some(Arc::new(OperationHooks {
+                before: Some("(sleep 0.5; touch late.txt) & wait".to_string()),
+                after: Some("touch after.txt".to_string()),
+                cwd: target_dir.path().to_path_buf(),
+            })),
+            ..planned_copy(main_dir.path(), target_dir.path())
+        };
+        let started = Instant::now();
+        let err =
+            execute_operation_with_timeout(&op, Duration::from_millis(100), |_, _| {}).unwrap_err();
+
+        assert!(matches!(err, OperationError::Timeout { .. }), "{err}");
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        // Nothing is written once the timeout has been reported.
+        std::thread::sleep(Duration::from_millis(700));
+        assert!(!target_dir.path().join("dist").exists());
+        assert!(!target_dir.path().join("late.txt").exists());
+        assert!(!target_dir.path().join("after.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_operation_with_timeout_abandons_blocked_worker() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        // Opening a FIFO for reading blocks until a writer appears, which
+        // stands in for a read from a hung mount.
+        let fifo = main_dir.path().join("file.txt");
+        let c_path = std::ffi::CString::new(fifo.to_string_lossy().as_bytes()).unwrap();
+        // SAFETY: `c_path` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let op = planned_copy(main_dir.path(), target_dir.path());
+        let started = Instant::now();
+        let err = execute_with_deadline(
+            &op,
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            |_, _| {},
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, OperationError::Timeout { .. }), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Unblock the abandoned worker so it does not outlive the test.
+        let _ = fs::write(&fifo, "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_plan_rolls_back_after_timed_out_worker_stops() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = main_dir.path().join("dist");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.js"), "a").unwrap();
+
+        // The `before` hook would write into the target after the timeout;
+        // it is killed before the rollback runs.
+        let op = PlannedOperation {
+            source,
+            target: target_dir.path().join("dist"),
+            is_directory: true,
+            hooks: Some(Arc::new(OperationHooks {
+                before: Some("sleep 0.5; mkdir dist; touch dist/late.txt".to_string()),
+                after: None,
+                cwd: target_dir.path().to_path_buf(),
+            })),
+            ..planned_copy(main_dir.path(), target_dir.path())
+        };
+        let options = ExecutionOptions {
+            timeout: Some(Duration::from_millis(100)),
+            rollback_root: Some(target_dir.path().to_path_buf()),
+            ..ExecutionOptions::default()
+        };
+
+        let err = execute_plan(&[op], &options).unwrap_err();
+        assert!(matches!(err, OperationError::Timeout { .. }), "{err}");
+        std::thread::sleep(Duration::from_millis(700));
+        assert!(!target_dir.path().join("dist").exists());
+    }
 }
@@ -6,11 +6,14 @@
 
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use worktree_setup_copy::CopyProgress;
+use worktree_setup_glob::WorktreeIgnore;
 
-use crate::OperationResult;
 use crate::error::OperationError;
+use crate::{OperationOutcome, OperationResult};
 
 /// Copy a file from source to target.
 ///
@@ -46,6 +49,23 @@ pub fn copy_file_with_progress<F>(
     target: &Path,
     on_progress: F,
 ) -> Result<OperationResult, OperationError>
+where
+    F: Fn(&CopyProgress),
+{
+    copy_file_outcome(source, target, on_progress).map(|outcome| outcome.result)
+}
+
+/// Copy a file like [`copy_file_with_progress`], also reporting how the
+/// target was created.
+///
+/// # Errors
+///
+/// * If the copy operation fails
+pub fn copy_file_outcome<F>(
+    source: &Path,
+    target: &Path,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
 where
     F: Fn(&CopyProgress),
 {
@@ -55,9 +75,12 @@ where
         .map_err(|e| OperationError::CopyModuleError(e.to_string()))?;
 
     Ok(match result {
-        worktree_setup_copy::CopyResult::Created { .. } => OperationResult::Created,
-        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists,
-        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped,
+        worktree_setup_copy::CopyResult::Created { method, .. } => OperationOutcome {
+            method,
+            ..OperationResult::Created.into()
+        },
+        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists.into(),
+        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped.into(),
     })
 }
 
@@ -91,6 +114,23 @@ pub fn overwrite_file_with_progress<F>(
     target: &Path,
     on_progress: F,
 ) -> Result<OperationResult, OperationError>
+where
+    F: Fn(&CopyProgress),
+{
+    overwrite_file_outcome(source, target, on_progress).map(|outcome| outcome.result)
+}
+
+/// Overwrite a file like [`overwrite_file_with_progress`], also reporting
+/// how the target was created.
+///
+/// # Errors
+///
+/// * If the copy operation fails
+pub fn overwrite_file_outcome<F>(
+    source: &Path,
+    target: &Path,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
 where
     F: Fn(&CopyProgress),
 {
@@ -102,7 +142,7 @@ where
 
     if !source.exists() {
         log::debug!("Source does not exist");
-        return Ok(OperationResult::Skipped);
+        return Ok(OperationResult::Skipped.into());
     }
 
     let existed = target.exists();
@@ -111,15 +151,17 @@ where
         .map_err(|e| OperationError::CopyModuleError(e.to_string()))?;
 
     Ok(match result {
-        worktree_setup_copy::CopyResult::Created { .. } => {
-            if existed {
+        worktree_setup_copy::CopyResult::Created { method, .. } => OperationOutcome {
+            method,
+            ..if existed {
                 OperationResult::Overwritten
             } else {
                 OperationResult::Created
             }
-        }
-        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists,
-        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped,
+            .into()
+        },
+        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists.into(),
+        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped.into(),
     })
 }
 
@@ -157,6 +199,64 @@ pub fn copy_directory_with_progress<F>(
     target: &Path,
     on_progress: F,
 ) -> Result<OperationResult, OperationError>
+where
+    F: Fn(&CopyProgress) + Sync,
+{
+    copy_directory_filtered_with_progress(source, target, None, on_progress)
+}
+
+/// Copy a directory recursively, leaving out `.worktreeignore` matches.
+///
+/// Only copies if the target doesn't exist. Entries under `source` that
+/// `ignore` excludes are not copied; pass `None` to copy everything.
+///
+/// # Arguments
+///
+/// * `source` - Source directory path
+/// * `target` - Target directory path
+/// * `ignore` - Optional ignore rules for the main worktree
+/// * `on_progress` - Progress callback (called periodically, not for every file)
+///
+/// # Errors
+///
+/// * If the copy operation fails
+pub fn copy_directory_filtered_with_progress<F>(
+    source: &Path,
+    target: &Path,
+    ignore: Option<&WorktreeIgnore>,
+    on_progress: F,
+) -> Result<OperationResult, OperationError>
+where
+    F: Fn(&CopyProgress) + Sync,
+{
+    copy_directory_outcome(
+        source,
+        target,
+        None,
+        ignore.map(|ignore| Arc::new(ignore.clone())),
+        &AtomicBool::new(false),
+        on_progress,
+    )
+    .map(|outcome| outcome.result)
+}
+
+/// Copy a directory like [`copy_directory_filtered_with_progress`], also
+/// reporting how many files vanished from the source during the copy.
+///
+/// `max_depth` limits how many levels below `source` are copied (`None`
+/// for no limit). Setting `cancel` stops the copy before its next file.
+///
+/// # Errors
+///
+/// * If the copy operation fails or is cancelled
+pub fn copy_directory_outcome<F>(
+    source: &Path,
+    target: &Path,
+    max_depth: Option<usize>,
+    ignore: Option<Arc<WorktreeIgnore>>,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<OperationOutcome, OperationError>
 where
     F: Fn(&CopyProgress) + Sync,
 {
@@ -174,16 +274,38 @@ where
         })?;
     }
 
-    let result = worktree_setup_copy::copy_directory(source, target, on_progress)
-        .map_err(|e| OperationError::CopyModuleError(e.to_string()))?;
+    let result = worktree_setup_copy::copy_directory_cancellable(
+        source,
+        target,
+        max_depth,
+        ignore_filter(ignore),
+        cancel,
+        on_progress,
+    )
+    .map_err(OperationError::from)?;
 
     Ok(match result {
-        worktree_setup_copy::CopyResult::Created { .. } => OperationResult::Created,
-        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists,
-        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped,
+        worktree_setup_copy::CopyResult::Created { files_vanished, .. } => OperationOutcome {
+            files_vanished,
+            ..OperationResult::Created.into()
+        },
+        worktree_setup_copy::CopyResult::Exists => OperationResult::Exists.into(),
+        worktree_setup_copy::CopyResult::SourceNotFound => OperationResult::Skipped.into(),
     })
 }
 
+/// Copy filter that leaves out what `ignore` excludes; `None` keeps
+/// everything.
+pub fn ignore_filter(
+    ignore: Option<Arc<WorktreeIgnore>>,
+) -> impl Fn(&Path, bool) -> bool + Send + Sync + 'static {
+    move |path, is_dir| {
+        ignore
+            .as_ref()
+            .is_none_or(|ignore| !ignore.is_ignored(path, is_dir))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,260 @@
+//! Cache of directory entry counts shared across runs.
+//!
+//! Counting a large directory such as `node_modules` dominates planning.
+//! Counts are stored in the repository's cache directory (see
+//! [`crate::repository_cache_dir`]), keyed by the directory path, and reused while the directory's modification time is unchanged and the
+//! entry is younger than [`COUNT_CACHE_TTL`]. A root's mtime only changes
+//! when its direct children do, so the age limit bounds how stale a count
+//! from a deeper change can get. Counts also depend on the
+//! `.worktreeignore` rules they were made with (see
+//! [`CountCache::rules_key`]), so editing those rules forces a recount.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use worktree_setup_copy::EntryCounts;
+use worktree_setup_git::Repository;
+use worktree_setup_glob::WorktreeIgnore;
+
+use crate::error::OperationError;
+use crate::manifest::unix_now;
+use crate::paths::repository_cache_dir;
+
+/// File name of the count cache inside the repository's cache directory.
+pub const COUNT_CACHE_FILE: &str = "count-cache.json";
+
+/// Seconds a cached count stays valid, even if the directory's mtime is
+/// unchanged.
+pub const COUNT_CACHE_TTL: u64 = 60 * 60;
+
+/// Counts recorded for one directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedCount {
+    /// Modification time of the directory (nanoseconds since the epoch).
+    mtime: u64,
+    /// Unix timestamp (seconds) of the count.
+    counted_at: u64,
+    /// [`CountCache::rules_key`] of the ignore rules counted with.
+    #[serde(default)]
+    rules: String,
+    files: u64,
+    symlinks: u64,
+    filtered: u64,
+    bytes: u64,
+    #[serde(default)]
+    specials: u64,
+}
+
+/// Directory entry counts reused across runs.
+///
+/// Lookups and inserts are thread-safe, so one cache can be shared by
+/// directories counted in parallel. Changes are only persisted by
+/// [`CountCache::save`].
+#[derive(Debug, Default)]
+pub struct CountCache {
+    path: PathBuf,
+    entries: Mutex<BTreeMap<PathBuf, CachedCount>>,
+}
+
+impl CountCache {
+    /// Load the count cache of `repo` (shared by all its worktrees).
+    ///
+    /// A missing or unreadable cache file yields an empty cache.
+    #[must_use]
+    pub fn for_repository(repo: &Repository) -> Self {
+        Self::load(repository_cache_dir(repo).join(COUNT_CACHE_FILE))
+    }
+
+    /// Load the count cache stored at `path`.
+    ///
+    /// A missing or unreadable cache file yields an empty cache.
+    #[must_use]
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| {
+                serde_json::from_str(&content)
+                    .map_err(|e| log::warn!("Ignoring unreadable count cache: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Key identifying the ignore rules counts are made with: a hash of
+    /// every `.worktreeignore` file and excluded directory.
+    #[must_use]
+    pub fn rules_key(ignore: &WorktreeIgnore) -> String {
+        let mut hasher = blake3::Hasher::new();
+        for (path, contents) in ignore.sources() {
+            hasher.update(path.as_os_str().as_encoded_bytes());
+            hasher.update(&[0]);
+            hasher.update(contents.as_bytes());
+            hasher.update(&[0]);
+        }
+        for dir in ignore.excluded_dirs() {
+            hasher.update(dir.as_os_str().as_encoded_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.finalize().to_hex()[..16].to_string()
+    }
+
+    /// Cached counts for `dir` made with the ignore rules `rules` (see
+    /// [`Self::rules_key`]), if still valid.
+    #[must_use]
+    pub fn get(&self, dir: &Path, rules: &str) -> Option<EntryCounts> {
+        let mtime = dir_mtime(dir)?;
+        let cached = self.entries.lock().ok()?.get(dir)?.clone();
+        let fresh = cached.mtime == mtime
+            && cached.rules == rules
+            && unix_now().saturating_sub(cached.counted_at) < COUNT_CACHE_TTL;
+        fresh.then_some(EntryCounts {
+            files: cached.files,
+            symlinks: cached.symlinks,
+            filtered: cached.filtered,
+            bytes: cached.bytes,
+            specials: cached.specials,
+        })
+    }
+
+    /// Record freshly computed counts for `dir`, made with the ignore
+    /// rules `rules`.
+    pub fn insert(&self, dir: &Path, rules: &str, counts: EntryCounts) {
+        let Some(mtime) = dir_mtime(dir) else {
+            return;
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                dir.to_path_buf(),
+                CachedCount {
+                    mtime,
+                    counted_at: unix_now(),
+                    rules: rules.to_string(),
+                    files: counts.files,
+                    symlinks: counts.symlinks,
+                    filtered: counts.filtered,
+                    bytes: counts.bytes,
+                    specials: counts.specials,
+                },
+            );
+        }
+    }
+
+    /// Write the cache, dropping entries for directories that are gone.
+    ///
+    /// # Errors
+    ///
+    /// * If the cache directory or file cannot be written
+    pub fn save(&self) -> Result<(), OperationError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_or_else(|e| e.into_inner().clone(), |entries| entries.clone());
+        entries.retain(|dir, _| dir.is_dir());
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| OperationError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(&entries).map_err(|e| OperationError::ManifestError {
+                path: self.path.clone(),
+                message: e.to_string(),
+            })?;
+        fs::write(&self.path, content).map_err(|e| OperationError::IoError {
+            path: self.path.clone(),
+            source: e,
+        })
+    }
+}
+
+/// Modification time of `dir` in nanoseconds since the epoch.
+fn dir_mtime(dir: &Path) -> Option<u64> {
+    let modified = fs::metadata(dir).ok()?.modified().ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    Some(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_counts_round_trip_until_directory_changes() {
+        let repo = TempDir::new().unwrap();
+        let dir = repo.path().join("node_modules");
+        fs::create_dir(&dir).unwrap();
+        let cache_path = repo.path().join("cache").join(COUNT_CACHE_FILE);
+        let counts = EntryCounts {
+            files: 3,
+            symlinks: 1,
+            filtered: 2,
+            bytes: 42,
+            specials: 0,
+        };
+
+        let rules = CountCache::rules_key(&WorktreeIgnore::default());
+
+        let cache = CountCache::load(cache_path.clone());
+        assert_eq!(cache.get(&dir, &rules), None);
+        cache.insert(&dir, &rules, counts);
+        cache.save().unwrap();
+
+        let reloaded = CountCache::load(cache_path);
+        assert_eq!(reloaded.get(&dir, &rules), Some(counts));
+
+        // Move the mtime back so the change is visible on coarse clocks.
+        let earlier =
+            fs::metadata(&dir).unwrap().modified().unwrap() - std::time::Duration::from_secs(10);
+        fs::File::open(&dir).unwrap().set_modified(earlier).unwrap();
+        assert_eq!(reloaded.get(&dir, &rules), None);
+    }
+
+    #[test]
+    fn test_counts_are_stale_once_ignore_rules_change() {
+        let repo = TempDir::new().unwrap();
+        let dir = repo.path().join("node_modules");
+        fs::create_dir(&dir).unwrap();
+        fs::write(repo.path().join(".worktreeignore"), "*.log\n").unwrap();
+        let counts = EntryCounts {
+            files: 3,
+            ..EntryCounts::default()
+        };
+
+        let rules = CountCache::rules_key(&WorktreeIgnore::load(repo.path()));
+        let cache = CountCache::load(repo.path().join(COUNT_CACHE_FILE));
+        cache.insert(&dir, &rules, counts);
+        assert_eq!(
+            cache.get(
+                &dir,
+                &CountCache::rules_key(&WorktreeIgnore::load(repo.path()))
+            ),
+            Some(counts)
+        );
+
+        fs::write(repo.path().join(".worktreeignore"), "*.log\ncache/\n").unwrap();
+        let edited = CountCache::rules_key(&WorktreeIgnore::load(repo.path()));
+        assert_ne!(edited, rules);
+        assert_eq!(cache.get(&dir, &edited), None);
+
+        let mut excluding = WorktreeIgnore::load(repo.path());
+        excluding.exclude_dir(Path::new("worktrees/feature"));
+        assert_ne!(CountCache::rules_key(&excluding), edited);
+    }
+}
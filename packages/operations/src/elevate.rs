@@ -0,0 +1,153 @@
+//! Copying permission-denied paths through `sudo`.
+//!
+//! A directory copy that meets files it may not read (root-owned Docker
+//! volumes, build sandboxes) copies everything else and reports the denied
+//! paths as [`OperationError::PermissionDenied`]. These are retried here
+//! with `sudo cp`, one path at a time, instead of failing the whole copy.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use worktree_setup_copy::CopyError;
+
+use crate::error::OperationError;
+use crate::plan::PlannedOperation;
+
+/// The paths a failed directory copy was denied, if `error` is a
+/// [`OperationError::PermissionDenied`].
+#[must_use]
+pub fn denied_paths(error: &OperationError) -> Option<&[PathBuf]> {
+    match error {
+        OperationError::PermissionDenied(CopyError::PermissionDenied { paths, .. }) => Some(paths),
+        _ => None,
+    }
+}
+
+/// Copy the `denied` paths of `op` with `sudo cp -pR`.
+///
+/// Each path lands at its place relative to the operation's source, keeping
+/// its mode, and is then handed to the current user with `sudo chown -R`,
+/// so the worktree can be edited and removed without `sudo`. Missing parent
+/// directories are created as the current user. With `non_interactive`, `sudo` fails instead of prompting
+/// for a password.
+///
+/// # Errors
+///
+/// * If a parent directory cannot be created
+/// * If `sudo` cannot run or fails to copy or chown a path
+pub fn copy_denied_with_sudo(
+    op: &PlannedOperation,
+    denied: &[PathBuf],
+    non_interactive: bool,
+) -> Result<(), OperationError> {
+    for path in denied {
+        let target = path
+            .strip_prefix(&op.source)
+            .map_or_else(|_| op.target.clone(), |relative| op.target.join(relative));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| OperationError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+
+        // `dir/.` copies a directory's contents, so an existing target
+        // directory is filled rather than nested into.
+        let source = if path.is_dir() {
+            path.join(".")
+        } else {
+            path.clone()
+        };
+        log::debug!(
+            "Copying {} -> {} with sudo",
+            source.display(),
+            target.display()
+        );
+
+        let failed = |reason: String| OperationError::SudoFailed {
+            path: path.clone(),
+            reason,
+        };
+        run_sudo(
+            &[
+                "cp".as_ref(),
+                "-pR".as_ref(),
+                "--".as_ref(),
+                source.as_os_str(),
+                target.as_os_str(),
+            ],
+            non_interactive,
+        )
+        .map_err(failed)?;
+
+        // `cp -p` run as root keeps the files owned by root, which would
+        // leave them read-only to the user and block removing the worktree.
+        #[cfg(unix)]
+        {
+            // SAFETY: `getuid` and `getgid` cannot fail.
+            let owner = unsafe { format!("{}:{}", libc::getuid(), libc::getgid()) };
+            run_sudo(
+                &[
+                    "chown".as_ref(),
+                    "-R".as_ref(),
+                    owner.as_ref(),
+                    "--".as_ref(),
+                    target.as_os_str(),
+                ],
+                non_interactive,
+            )
+            .map_err(failed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run `sudo` with `args`, returning its error output (or exit status) if
+/// it fails.
+fn run_sudo(args: &[&OsStr], non_interactive: bool) -> Result<(), String> {
+    let mut command = Command::new("sudo");
+    if non_interactive {
+        command.arg("-n");
+    }
+    let output = command.args(args).output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(if stderr.is_empty() {
+        output.status.to_string()
+    } else {
+        stderr
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_denied_paths_only_for_permission_errors() {
+        let denied = OperationError::from(CopyError::PermissionDenied {
+            path: PathBuf::from("/repo/volumes"),
+            paths: vec![PathBuf::from("/repo/volumes/db/pg_hba.conf")],
+        });
+        assert_eq!(
+            denied_paths(&denied),
+            Some([PathBuf::from("/repo/volumes/db/pg_hba.conf")].as_slice())
+        );
+        assert_eq!(denied.code(), "WTS-CPY-009");
+
+        let other = OperationError::from(CopyError::EnumerationError {
+            path: PathBuf::from("/repo/volumes"),
+            message: "gone".to_string(),
+        });
+        assert!(matches!(other, OperationError::CopyModuleError(_)));
+        assert_eq!(denied_paths(&other), None);
+    }
+}
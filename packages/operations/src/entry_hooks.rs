@@ -0,0 +1,197 @@
+//! `before`/`after` commands attached to config entries.
+//!
+//! The planner copies an entry's hooks onto each operation planned from it
+//! (see [`PlannedOperation::hooks`]); executing the operation runs them
+//! around it. The operation is described to the commands through
+//! environment variables:
+//!
+//! * `WORKTREE_SETUP_OPERATION` - `symlink`, `copy`, `overwrite`, or
+//!   `template`
+//! * `WORKTREE_SETUP_PATH` - the operation's display path
+//! * `WORKTREE_SETUP_SOURCE` - absolute source path
+//! * `WORKTREE_SETUP_TARGET` - absolute target path
+//! * `WORKTREE_SETUP_RESULT` - `created` or `overwritten` (`after` only)
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use worktree_setup_copy::CopyError;
+
+use crate::OperationResult;
+use crate::error::OperationError;
+use crate::plan::PlannedOperation;
+
+/// How often a cancellable hook checks whether it was cancelled.
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+/// Commands run around one planned operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationHooks {
+    /// Run before the operation.
+    pub before: Option<String>,
+    /// Run after the operation created or overwrote its target.
+    pub after: Option<String>,
+    /// Directory the commands run in (the target worktree).
+    pub cwd: PathBuf,
+}
+
+/// Run the `before` hook of `op`, if it has one.
+///
+/// With `cancel`, the command is killed once it is set (see [`run_hook`]).
+///
+/// # Errors
+///
+/// * If the command cannot be started or exits unsuccessfully
+/// * If the command was killed because `cancel` was set
+pub fn run_before_hook(
+    op: &PlannedOperation,
+    cancel: Option<&AtomicBool>,
+) -> Result<(), OperationError> {
+    match op.hooks.as_deref() {
+        Some(OperationHooks {
+            before: Some(command),
+            cwd,
+            ..
+        }) => run_hook(op, command, cwd, "before", None, cancel),
+        _ => Ok(()),
+    }
+}
+
+/// Run the `after` hook of `op` if the operation produced `result`.
+///
+/// Only operations that created or overwrote their target run it. With
+/// `cancel`, the command is killed once it is set (see [`run_hook`]).
+///
+/// # Errors
+///
+/// * If the command cannot be started or exits unsuccessfully
+/// * If the command was killed because `cancel` was set
+pub fn run_after_hook(
+    op: &PlannedOperation,
+    result: OperationResult,
+    cancel: Option<&AtomicBool>,
+) -> Result<(), OperationError> {
+    if !matches!(
+        result,
+        OperationResult::Created | OperationResult::Overwritten
+    ) {
+        return Ok(());
+    }
+    match op.hooks.as_deref() {
+        Some(OperationHooks {
+            after: Some(command),
+            cwd,
+            ..
+        }) => run_hook(op, command, cwd, "after", Some(result), cancel),
+        _ => Ok(()),
+    }
+}
+
+/// Run one hook command through `sh -c`.
+///
+/// A cancellable command runs in its own process group, which is killed
+/// as a whole once `cancel` is set, so commands the shell started stop
+/// too. Commands that cannot be cancelled stay in the caller's process
+/// group and receive a Ctrl-C meant for it.
+fn run_hook(
+    op: &PlannedOperation,
+    command: &str,
+    cwd: &std::path::Path,
+    phase: &'static str,
+    result: Option<OperationResult>,
+    cancel: Option<&AtomicBool>,
+) -> Result<(), OperationError> {
+    log::debug!("Running {phase} hook for {}: {command}", op.display_path);
+
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command])
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .env("WORKTREE_SETUP_OPERATION", op.operation_type.to_string())
+        .env("WORKTREE_SETUP_PATH", &op.display_path)
+        .env("WORKTREE_SETUP_SOURCE", &op.source)
+        .env("WORKTREE_SETUP_TARGET", &op.target);
+    if let Some(result) = result {
+        cmd.env("WORKTREE_SETUP_RESULT", result.to_string());
+    }
+
+    let failed = |e: std::io::Error| OperationError::HookFailed {
+        phase,
+        command: command.to_string(),
+        reason: e.to_string(),
+    };
+    let status = match cancel {
+        Some(cancel) => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::process::CommandExt;
+
+                cmd.process_group(0);
+            }
+            let child = cmd.spawn().map_err(failed)?;
+            match wait_unless_cancelled(child, cancel).map_err(failed)? {
+                Some(status) => status,
+                None => {
+                    return Err(OperationError::from(CopyError::Cancelled {
+                        path: op.target.clone(),
+                    }));
+                }
+            }
+        }
+        None => cmd.status().map_err(failed)?,
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(OperationError::HookFailed {
+            phase,
+            command: command.to_string(),
+            reason: status.to_string(),
+        })
+    }
+}
+
+/// Wait for `child` to exit, killing its process group and returning
+/// `None` if `cancel` is set first.
+fn wait_unless_cancelled(
+    mut child: Child,
+    cancel: &AtomicBool,
+) -> std::io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if cancel.load(Ordering::Relaxed) {
+            kill_process_group(&mut child);
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(CANCEL_POLL);
+    }
+}
+
+/// Kill `child` and every process in its process group.
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    match libc::pid_t::try_from(child.id()) {
+        // SAFETY: `kill` has no memory-safety preconditions; the negative
+        // pid names the process group `child` leads.
+        Ok(pid) => unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        },
+        Err(_) => {
+            let _ = child.kill();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
@@ -9,6 +9,9 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 /// Errors that can occur during file operations.
+///
+/// Each variant has a stable [`code`](Self::code). New variants take the
+/// next unused number; codes are never reused.
 #[derive(Debug, Error)]
 pub enum OperationError {
     /// IO error during file operation.
@@ -49,7 +52,184 @@ pub enum OperationError {
     #[error("Git error: {0}")]
     GitError(#[from] worktree_setup_git::GitError),
 
+    /// Manifest could not be parsed or serialized.
+    #[error("Invalid manifest at {}: {message}", path.display())]
+    ManifestError {
+        /// Path to the manifest.
+        path: PathBuf,
+        /// Error message.
+        message: String,
+    },
+
     /// Copy module error.
     #[error("Copy error: {0}")]
     CopyModuleError(String),
+
+    /// A template could not be rendered.
+    #[error("Failed to render template {}: {message}", path.display())]
+    TemplateError {
+        /// The template source file.
+        path: PathBuf,
+        /// What went wrong.
+        message: String,
+    },
+
+    /// An operation did not finish within its timeout.
+    #[error("Timed out after {}s: {}", timeout.as_secs_f64(), path.display())]
+    Timeout {
+        /// Target path of the operation.
+        path: PathBuf,
+        /// The timeout that elapsed.
+        timeout: std::time::Duration,
+    },
+
+    /// A glob pattern could not search part of the tree and `strictGlobs`
+    /// is set.
+    #[error("Glob pattern {pattern} failed: {error}")]
+    GlobError {
+        /// The pattern as written in the config.
+        pattern: String,
+        /// What could not be searched, and why.
+        error: String,
+    },
+
+    /// An entry's `before` or `after` command failed.
+    #[error("{phase} hook `{command}` failed: {reason}")]
+    HookFailed {
+        /// `before` or `after`.
+        phase: &'static str,
+        /// The command as written in the config.
+        command: String,
+        /// Exit status or why the command could not start.
+        reason: String,
+    },
+
+    /// Part of a directory copy was denied for lack of permission; the
+    /// rest was copied.
+    #[error(transparent)]
+    PermissionDenied(worktree_setup_copy::CopyError),
+
+    /// Copying denied paths through `sudo` failed.
+    #[error("sudo could not copy {}: {reason}", path.display())]
+    SudoFailed {
+        /// The source path being copied.
+        path: PathBuf,
+        /// Exit status or why `sudo` could not start.
+        reason: String,
+    },
+
+    /// The saved progress of an interrupted run could not be read or
+    /// written.
+    #[error("Invalid run state at {}: {message}", path.display())]
+    RunStateError {
+        /// Path to the run state file.
+        path: PathBuf,
+        /// Error message.
+        message: String,
+    },
+
+    /// Entries whose source does not exist, reported by
+    /// `--check-sources`.
+    #[error("{count} config {} a missing source", if *count == 1 { "entry has" } else { "entries have" })]
+    MissingSources {
+        /// Number of entries reported.
+        count: usize,
+    },
+
+    /// A `<scheme>:<location>` source could not be made available.
+    #[error("Could not provide source {spec}: {message}")]
+    SourceError {
+        /// The source as written in the config.
+        spec: String,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+impl OperationError {
+    /// Stable code identifying the kind of error, such as `WTS-OPS-001`.
+    ///
+    /// Codes never change meaning, so scripts can match on them and users
+    /// can search for them in the documentation.
+    ///
+    /// Git errors keep their own `WTS-GIT-*` code, and denied copies their
+    /// `WTS-CPY-*` code.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::IoError { .. } => "WTS-OPS-001",
+            Self::SymlinkError { .. } => "WTS-OPS-002",
+            Self::CopyError { .. } => "WTS-OPS-003",
+            Self::GitError(e) => e.code(),
+            Self::ManifestError { .. } => "WTS-OPS-005",
+            Self::CopyModuleError(_) => "WTS-OPS-006",
+            Self::TemplateError { .. } => "WTS-OPS-007",
+            Self::Timeout { .. } => "WTS-OPS-008",
+            Self::GlobError { .. } => "WTS-OPS-009",
+            Self::HookFailed { .. } => "WTS-OPS-010",
+            Self::PermissionDenied(e) => e.code(),
+            Self::SudoFailed { .. } => "WTS-OPS-011",
+            Self::RunStateError { .. } => "WTS-OPS-012",
+            Self::MissingSources { .. } => "WTS-OPS-013",
+            Self::SourceError { .. } => "WTS-OPS-014",
+        }
+    }
+
+    /// A suggestion for fixing the error.
+    #[must_use]
+    pub const fn help(&self) -> &'static str {
+        match self {
+            Self::IoError { .. } => "Check the permissions and free space at the reported path.",
+            Self::SymlinkError { .. } => {
+                "Check that the target's parent directory is writable and nothing else is in the way."
+            }
+            Self::CopyError { .. } | Self::CopyModuleError(_) => {
+                "Check that the source is readable and the target writable."
+            }
+            Self::GitError(e) => e.help(),
+            Self::ManifestError { .. } => {
+                "Delete the manifest; the next setup run writes a fresh one."
+            }
+            Self::TemplateError { .. } => {
+                "Fix the template syntax, or define the variables it uses."
+            }
+            Self::Timeout { .. } => {
+                "Raise setup.operation_timeout, or check for a hung filesystem."
+            }
+            Self::GlobError { .. } => {
+                "Fix the permissions of the unreadable directory, or unset strictGlobs."
+            }
+            Self::HookFailed { .. } => {
+                "Run the hook command by hand from the worktree root to see why it fails."
+            }
+            Self::PermissionDenied(e) => e.help(),
+            Self::SudoFailed { .. } => {
+                "Check that sudo works here; non-interactive runs need passwordless sudo."
+            }
+            Self::RunStateError { .. } => {
+                "Delete the run state and run setup again without --resume."
+            }
+            Self::MissingSources { .. } => {
+                "Fix or remove the reported entries, or create their sources in the main worktree."
+            }
+            Self::SourceError { .. } => {
+                "Check the source's path and format; tar sources need the tar command."
+            }
+        }
+    }
+}
+
+impl From<worktree_setup_copy::CopyError> for OperationError {
+    /// Keep denied paths as [`Self::PermissionDenied`] so they can be
+    /// retried; every other copy error becomes [`Self::CopyModuleError`].
+    fn from(error: worktree_setup_copy::CopyError) -> Self {
+        if matches!(
+            error,
+            worktree_setup_copy::CopyError::PermissionDenied { .. }
+        ) {
+            Self::PermissionDenied(error)
+        } else {
+            Self::CopyModuleError(error.to_string())
+        }
+    }
 }
@@ -0,0 +1,531 @@
+//! Journal of paths changed during a run, for transactional rollback.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::{NamedTempFile, TempPath};
+use worktree_setup_copy::{CloneStrategy, clone_or_copy_file};
+
+use crate::error::OperationError;
+use crate::plan::{OperationType, PlannedOperation};
+
+/// Paths under a target worktree that this run created or replaced.
+///
+/// Call [`Journal::begin`] before executing each operation. If the run
+/// fails, [`Journal::rollback`] deletes everything the run created,
+/// including parent directories created for nested targets and partial
+/// output of the operation that failed, and entries a directory operation
+/// copied into a directory that already existed. Files and symlinks that
+/// operations replace are backed up first and restored. Entries that have
+/// no counterpart in the copied source are never deleted, so files other
+/// processes write into an existing directory meanwhile are kept; neither
+/// are directories replaced by a forced symlink restored.
+#[derive(Debug, Default)]
+pub struct Journal {
+    /// Root of the target worktree; only paths under it are recorded.
+    root: PathBuf,
+    /// Created paths, in creation order.
+    created: Vec<PathBuf>,
+    /// Directory targets that existed before their operation ran.
+    existing: Vec<ExistingDir>,
+    /// Replaced files and symlinks, with their original content.
+    replaced: Vec<(PathBuf, Backup)>,
+}
+
+/// A directory target that existed before its operation copied into it.
+#[derive(Debug)]
+struct ExistingDir {
+    /// The target directory.
+    target: PathBuf,
+    /// The directory copied into it.
+    source: PathBuf,
+    /// Every path under the target before the copy.
+    before: BTreeSet<PathBuf>,
+}
+
+/// The original of a replaced path.
+#[derive(Debug)]
+enum Backup {
+    /// Copy of a regular file, deleted when the journal is dropped.
+    File(TempPath),
+    /// Where a symlink pointed.
+    Symlink(PathBuf),
+}
+
+impl Journal {
+    /// Create an empty journal for the worktree at `root`.
+    #[must_use]
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    /// Record the paths `op` is about to create or replace.
+    ///
+    /// Records the outermost missing ancestor of the operation's target
+    /// (or the target itself), so directories created along the way are
+    /// removed on rollback too. If the target already exists and is a
+    /// directory the operation copies into, the paths under it are listed
+    /// instead, so whatever the operation adds there is found by comparing
+    /// against that list. An existing file or symlink that the operation
+    /// replaces is backed up.
+    ///
+    /// # Errors
+    ///
+    /// * If a file the operation replaces cannot be backed up
+    pub fn begin(&mut self, op: &PlannedOperation) -> Result<(), OperationError> {
+        if !op.target.starts_with(&self.root) {
+            return Ok(());
+        }
+        if exists(&op.target) {
+            self.snapshot(op);
+            return self.back_up(op);
+        }
+
+        let mut outermost = op.target.as_path();
+        while let Some(parent) = outermost.parent() {
+            if parent == self.root || !parent.starts_with(&self.root) || exists(parent) {
+                break;
+            }
+            outermost = parent;
+        }
+
+        if !self.created.iter().any(|p| outermost.starts_with(p)) {
+            self.created.push(outermost.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Add what `other` recorded, such as the journal of one operation
+    /// that succeeded, to this journal.
+    pub fn append(&mut self, other: Self) {
+        for path in other.created {
+            if !self.created.iter().any(|p| path.starts_with(p)) {
+                self.created.push(path);
+            }
+        }
+        self.existing.extend(other.existing);
+        for (path, backup) in other.replaced {
+            if !self.replaced.iter().any(|(p, _)| *p == path) {
+                self.replaced.push((path, backup));
+            }
+        }
+    }
+
+    /// Whether `path` is new in this run.
+    fn is_new(&self, path: &Path) -> bool {
+        self.created.iter().any(|p| path.starts_with(p))
+    }
+
+    /// List the entries under an existing directory target of `op`.
+    fn snapshot(&mut self, op: &PlannedOperation) {
+        let is_dir = op.target.symlink_metadata().is_ok_and(|meta| meta.is_dir());
+        if !op.is_directory
+            || op.operation_type == OperationType::Symlink
+            || !is_dir
+            || self.is_new(&op.target)
+            || self.existing.iter().any(|dir| dir.target == op.target)
+        {
+            return;
+        }
+
+        let mut before = BTreeSet::new();
+        list_entries(&op.target, &mut before);
+        self.existing.push(ExistingDir {
+            target: op.target.clone(),
+            source: op.source.clone(),
+            before,
+        });
+    }
+
+    /// Back up an existing file or symlink that `op` replaces.
+    fn back_up(&mut self, op: &PlannedOperation) -> Result<(), OperationError> {
+        if !op.is_destructive()
+            || self.is_new(&op.target)
+            || self.replaced.iter().any(|(p, _)| *p == op.target)
+        {
+            return Ok(());
+        }
+        let Ok(meta) = op.target.symlink_metadata() else {
+            return Ok(());
+        };
+
+        let io_error = |e| OperationError::IoError {
+            path: op.target.clone(),
+            source: e,
+        };
+        let backup = if meta.is_symlink() {
+            Backup::Symlink(fs::read_link(&op.target).map_err(io_error)?)
+        } else if meta.is_file() {
+            let copy = NamedTempFile::new().map_err(io_error)?.into_temp_path();
+            // Cloning needs a missing target; the temporary file is still
+            // removed on drop.
+            fs::remove_file(&copy).map_err(io_error)?;
+            clone_or_copy_file(&op.target, &copy, CloneStrategy::Reflink)?;
+            Backup::File(copy)
+        } else {
+            return Ok(());
+        };
+        self.replaced.push((op.target.clone(), backup));
+        Ok(())
+    }
+
+    /// Paths created so far, in creation order.
+    ///
+    /// Entries copied into existing directory targets come last; each is
+    /// listed once, as the outermost new path.
+    #[must_use]
+    pub fn created(&self) -> Vec<PathBuf> {
+        let mut created = self.created.clone();
+        for dir in &self.existing {
+            created.extend(dir.added().into_iter().map(|(path, _)| path));
+        }
+        created
+    }
+
+    /// Files and symlinks replaced so far, which rollback restores.
+    #[must_use]
+    pub fn replaced(&self) -> Vec<PathBuf> {
+        self.replaced.iter().map(|(path, _)| path.clone()).collect()
+    }
+
+    /// Delete every created path, newest first, then restore the replaced
+    /// ones.
+    ///
+    /// Paths that no longer exist are ignored. Returns the paths that could
+    /// not be removed or restored together with the error for each.
+    #[must_use]
+    pub fn rollback(self) -> Vec<(PathBuf, io::Error)> {
+        let mut failures = Vec::new();
+        let mut note = |path: &Path, result: io::Result<()>| {
+            if let Err(e) = result
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                failures.push((path.to_path_buf(), e));
+            }
+        };
+
+        for dir in self.existing.iter().rev() {
+            for (path, source) in dir.added().into_iter().rev() {
+                note(&path, remove_copied(&path, &source));
+            }
+        }
+        for path in self.created.iter().rev() {
+            note(path, remove(path));
+        }
+        for (path, backup) in self.replaced.iter().rev() {
+            note(path, backup.restore(path));
+        }
+        failures
+    }
+}
+
+impl ExistingDir {
+    /// The outermost paths copied into the directory since the snapshot,
+    /// with the source path each was copied from.
+    fn added(&self) -> Vec<(PathBuf, PathBuf)> {
+        let mut added = Vec::new();
+        added_entries(&self.target, &self.source, &self.before, &mut added);
+        added
+    }
+}
+
+impl Backup {
+    /// Put the original back at `path`.
+    fn restore(&self, path: &Path) -> io::Result<()> {
+        match remove(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        match self {
+            Self::File(copy) => clone_or_copy_file(copy, path, CloneStrategy::Reflink)
+                .map(drop)
+                .map_err(|e| io::Error::other(e.to_string())),
+            Self::Symlink(link) => restore_symlink(link, path),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restore_symlink(link: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(link, path)
+}
+
+#[cfg(windows)]
+fn restore_symlink(link: &Path, path: &Path) -> io::Result<()> {
+    let resolved = path
+        .parent()
+        .map_or_else(|| link.to_path_buf(), |dir| dir.join(link));
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(link, path)
+    } else {
+        std::os::windows::fs::symlink_file(link, path)
+    }
+}
+
+/// Whether `path` exists, without following a final symlink.
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+/// Add every path under `dir` to `entries`, without following symlinks.
+fn list_entries(dir: &Path, entries: &mut BTreeSet<PathBuf>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            list_entries(&path, entries);
+        }
+        entries.insert(path);
+    }
+}
+
+/// Add the outermost paths under `dir` that are not in `before` but exist
+/// under `source`, with their source paths, to `added`.
+fn added_entries(
+    dir: &Path,
+    source: &Path,
+    before: &BTreeSet<PathBuf>,
+    added: &mut Vec<(PathBuf, PathBuf)>,
+) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let source_path = source.join(entry.file_name());
+        if !exists(&source_path) {
+            continue;
+        }
+        if !before.contains(&path) {
+            added.push((path, source_path));
+        } else if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            added_entries(&path, &source_path, before, added);
+        }
+    }
+}
+
+/// Remove what a copy from `source` put at `path`.
+///
+/// Entries of a directory with no counterpart under `source` were written
+/// by something else and are kept, along with the directories holding
+/// them.
+fn remove_copied(path: &Path, source: &Path) -> io::Result<()> {
+    if !path.symlink_metadata()?.is_dir() {
+        return fs::remove_file(path);
+    }
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let source_path = source.join(entry.file_name());
+        if exists(&source_path) {
+            remove_copied(&entry.path(), &source_path)?;
+        }
+    }
+    match fs::remove_dir(path) {
+        Err(e) if e.kind() == io::ErrorKind::DirectoryNotEmpty => Ok(()),
+        result => result,
+    }
+}
+
+/// Remove a file, symlink, or directory tree at `path`.
+pub fn remove(path: &Path) -> io::Result<()> {
+    let meta = path.symlink_metadata()?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn op_for(target: PathBuf) -> PlannedOperation {
+        PlannedOperation {
+            display_path: target.display().to_string(),
+            operation_type: OperationType::Copy,
+            source: PathBuf::new(),
+            target,
+            file_count: 1,
+            symlink_count: 0,
+            byte_count: 0,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    #[test]
+    fn test_rollback_removes_created_paths_only() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("existing.txt"), "keep").unwrap();
+
+        let mut journal = Journal::new(root);
+        journal.begin(&op_for(root.join("existing.txt"))).unwrap();
+        journal.begin(&op_for(root.join("new.txt"))).unwrap();
+        journal.begin(&op_for(root.join("a/b/nested.txt"))).unwrap();
+        journal.begin(&op_for(root.join("a/b/other.txt"))).unwrap();
+        assert_eq!(journal.created(), [root.join("new.txt"), root.join("a")]);
+
+        fs::write(root.join("existing.txt"), "overwritten").unwrap();
+        fs::write(root.join("new.txt"), "new").unwrap();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join("a/b/nested.txt"), "nested").unwrap();
+
+        assert!(journal.rollback().is_empty());
+        assert!(root.join("existing.txt").exists());
+        assert!(!root.join("new.txt").exists());
+        assert!(!root.join("a").exists());
+    }
+
+    #[test]
+    fn test_rollback_removes_entries_added_to_existing_directory() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("dist/assets")).unwrap();
+        fs::write(root.join("dist/index.html"), "keep").unwrap();
+        fs::write(root.join("dist/assets/app.js"), "keep").unwrap();
+        let source = TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("assets")).unwrap();
+        fs::create_dir_all(source.path().join("fonts/woff")).unwrap();
+        fs::write(source.path().join("new.html"), "new").unwrap();
+        fs::write(source.path().join("assets/new.js"), "new").unwrap();
+        fs::write(source.path().join("fonts/woff/a.woff"), "new").unwrap();
+
+        let mut journal = Journal::new(root);
+        journal
+            .begin(&PlannedOperation {
+                is_directory: true,
+                source: source.path().to_path_buf(),
+                ..op_for(root.join("dist"))
+            })
+            .unwrap();
+        assert!(journal.created().is_empty());
+
+        // The copy adds files next to and below the existing ones.
+        fs::write(root.join("dist/new.html"), "new").unwrap();
+        fs::write(root.join("dist/assets/new.js"), "new").unwrap();
+        fs::create_dir_all(root.join("dist/fonts/woff")).unwrap();
+        fs::write(root.join("dist/fonts/woff/a.woff"), "new").unwrap();
+        let mut created = journal.created();
+        created.sort();
+        assert_eq!(
+            created,
+            [
+                root.join("dist/assets/new.js"),
+                root.join("dist/fonts"),
+                root.join("dist/new.html"),
+            ]
+        );
+
+        assert!(journal.rollback().is_empty());
+        assert!(root.join("dist/index.html").exists());
+        assert!(root.join("dist/assets/app.js").exists());
+        assert!(!root.join("dist/new.html").exists());
+        assert!(!root.join("dist/assets/new.js").exists());
+        assert!(!root.join("dist/fonts").exists());
+    }
+
+    #[test]
+    fn test_rollback_keeps_files_written_by_others() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        let source = TempDir::new().unwrap();
+        fs::create_dir_all(source.path().join("fonts")).unwrap();
+        fs::write(source.path().join("fonts/a.woff"), "new").unwrap();
+
+        let mut journal = Journal::new(root);
+        journal
+            .begin(&PlannedOperation {
+                is_directory: true,
+                source: source.path().to_path_buf(),
+                ..op_for(root.join("dist"))
+            })
+            .unwrap();
+
+        // A build writes into the same directories while the copy runs.
+        fs::create_dir_all(root.join("dist/fonts")).unwrap();
+        fs::write(root.join("dist/fonts/a.woff"), "new").unwrap();
+        fs::write(root.join("dist/fonts/b.woff"), "build").unwrap();
+        fs::write(root.join("dist/bundle.js"), "build").unwrap();
+
+        assert!(journal.rollback().is_empty());
+        assert!(!root.join("dist/fonts/a.woff").exists());
+        assert!(root.join("dist/fonts/b.woff").exists());
+        assert!(root.join("dist/bundle.js").exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_overwritten_files() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".env"), "original").unwrap();
+
+        let mut journal = Journal::new(root);
+        let overwrite = PlannedOperation {
+            operation_type: OperationType::Overwrite,
+            ..op_for(root.join(".env"))
+        };
+        journal.begin(&overwrite).unwrap();
+        assert_eq!(journal.replaced(), [root.join(".env")]);
+        fs::write(root.join(".env"), "replaced").unwrap();
+
+        // A second overwrite of the same file keeps the first backup.
+        journal.begin(&overwrite).unwrap();
+        fs::write(root.join(".env"), "replaced again").unwrap();
+
+        assert!(journal.rollback().is_empty());
+        assert_eq!(fs::read_to_string(root.join(".env")).unwrap(), "original");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rollback_restores_replaced_symlinks() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::os::unix::fs::symlink("old-target", root.join("link")).unwrap();
+
+        let mut journal = Journal::new(root);
+        journal
+            .begin(&PlannedOperation {
+                operation_type: OperationType::Symlink,
+                force_overwrite: true,
+                ..op_for(root.join("link"))
+            })
+            .unwrap();
+        fs::remove_file(root.join("link")).unwrap();
+        std::os::unix::fs::symlink("new-target", root.join("link")).unwrap();
+
+        assert!(journal.rollback().is_empty());
+        assert_eq!(
+            fs::read_link(root.join("link")).unwrap(),
+            Path::new("old-target")
+        );
+    }
+}
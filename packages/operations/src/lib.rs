@@ -4,13 +4,28 @@
 //!
 //! * Symlink creation
 //! * File and directory copying
-//! * Configuration application
-//! * Operation planning with file counts
+//! * Configuration application (`apply_config`, a plan + `execute_plan` wrapper)
+//! * Operation planning with file counts, for whole configs or single
+//!   entry kinds (`plan_symlinks`, `plan_templates`, ...)
+//! * Setup manifests for change detection
+//! * Per-worktree state shared across the repository
+//! * Cached directory counts reused across runs
+//! * Cache and state locations (`XDG_CACHE_HOME` aware)
+//! * Template rendering (`${VAR}`, Handlebars, or Tera)
+//! * Files copied from another git ref (`copyFromRef`)
+//! * `before`/`after` commands attached to config entries
+//! * Disk-space checks before copying
+//! * Retrying permission-denied copies through `sudo`
+//! * Rollback journals for transactional runs
+//! * Saved run progress for resuming interrupted runs
+//! * Entry sources read through providers (`tar:` archives)
 //!
 //! # Example
 //!
 //! ```rust,ignore
-//! use worktree_setup_operations::{plan_operations, execute_operation};
+//! use worktree_setup_operations::{
+//!     ExecutionOptions, execute_operation, execute_plan, plan_operations, plan_templates,
+//! };
 //!
 //! // Plan operations first (for progress display)
 //! let operations = plan_operations(&config, &main_path, &target_path, &options)?;
@@ -21,6 +36,13 @@
 //!         println!("{}/{} files", completed, total);
 //!     })?;
 //! }
+//!
+//! // Or render only the templates, rolling back on failure
+//! let templates = plan_templates(&config, &main_path, &target_path, &options);
+//! let result = execute_plan(&templates, &ExecutionOptions {
+//!     rollback_root: Some(target_path.clone()),
+//!     ..ExecutionOptions::default()
+//! })?;
 //! ```
 
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
@@ -29,24 +51,62 @@
 
 mod apply;
 mod copy;
+mod count_cache;
+mod elevate;
+mod entry_hooks;
 mod error;
+mod journal;
+mod manifest;
+mod paths;
 mod plan;
+mod ref_file;
+mod run_state;
+mod source;
+mod space;
+mod state;
 mod symlink;
+mod template;
 
 pub use apply::{
-    ApplyConfigOptions, ApplyResult, OperationRecord, apply_config, execute_operation,
+    ApplyConfigOptions, ApplyResult, ExecutionOptions, ExecutionResult, FailureAction,
+    OperationRecord, PlanRunner, apply_config, execute_operation, execute_operation_outcome,
+    execute_operation_with_timeout, execute_plan, execute_plan_with, rescan_operation,
+    verify_operation,
 };
 pub use copy::{
-    copy_directory, copy_directory_with_progress, copy_file, copy_file_with_progress,
-    overwrite_file, overwrite_file_with_progress,
+    copy_directory, copy_directory_filtered_with_progress, copy_directory_with_progress, copy_file,
+    copy_file_outcome, copy_file_with_progress, overwrite_file, overwrite_file_outcome,
+    overwrite_file_with_progress,
 };
+pub use count_cache::{COUNT_CACHE_FILE, COUNT_CACHE_TTL, CountCache};
+pub use elevate::{copy_denied_with_sudo, denied_paths};
+pub use entry_hooks::OperationHooks;
 pub use error::OperationError;
+pub use journal::Journal;
+pub use manifest::{
+    MANIFEST_DIR, MANIFEST_FILE, Manifest, ManifestChanges, ManifestEntry, ManifestEntryKind,
+    SkippedEntry, config_entry_keys,
+};
+pub use paths::{
+    CACHE_DIR_ENV, cache_root, remote_config_cache_dir, repository_cache_dir, repository_data_dir,
+    source_cache_dir, ts_config_store, worktree_data_dir,
+};
 pub use plan::{
-    OperationType, PlannedOperation, plan_operations, plan_operations_with_progress,
-    plan_unstaged_operations,
+    EntryOrigin, OperationType, PlannedOperation, TargetModes, UnstagedFilter,
+    load_worktree_ignore, nested_target, plan_copies, plan_copy_globs, plan_hooks_operation,
+    plan_operations, plan_operations_with_progress, plan_overlay_operations, plan_overwrites,
+    plan_ref_copies, plan_symlinks, plan_templates, plan_unstaged_operations, sort_by_order,
+};
+pub use ref_file::{RefSource, write_ref_file};
+pub use run_state::{RUN_STATE_FILE, RunState};
+pub use source::{FilesystemProvider, ResolvePath, SourceProvider, SourceProviders, TarProvider};
+pub use space::{SpaceCheck, available_space, planned_copy_bytes};
+pub use state::{
+    AppliedConfig, Label, STATE_DIR, StateLock, StateStore, WorktreeState, config_hash,
 };
 pub use symlink::{create_symlink, force_create_symlink};
-pub use worktree_setup_copy::CopyProgress;
+pub use template::{TemplateContext, render_template_file};
+pub use worktree_setup_copy::{CloneMethod, CopyProgress, VerifyMode, VerifyReport};
 
 /// Result of a single file operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -71,3 +131,26 @@ impl std::fmt::Display for OperationResult {
         }
     }
 }
+
+/// What executing a single file operation did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationOutcome {
+    /// Result of the operation.
+    pub result: OperationResult,
+    /// Files deleted from the source while a directory was being copied;
+    /// they were left out of the copy.
+    pub files_vanished: u64,
+    /// How a single copied or overwritten file was created; `None` for
+    /// directories, symlinks, templates, and files written from a git ref.
+    pub method: Option<CloneMethod>,
+}
+
+impl From<OperationResult> for OperationOutcome {
+    fn from(result: OperationResult) -> Self {
+        Self {
+            result,
+            files_vanished: 0,
+            method: None,
+        }
+    }
+}
@@ -0,0 +1,981 @@
+//! Setup manifest - a record of what file operations wrote into a worktree.
+//!
+//! The manifest lives in the worktree's private git directory
+//! (`<git-dir>/worktree-setup/manifest.json`), so it never shows up as an
+//! untracked file. It stores a content hash for every copied file, the
+//! config entry that wrote it, and the config entries that were applied,
+//! which lets `status --changes` report edits on either side since setup
+//! and entries added to configs since.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use worktree_setup_config::{Config, LoadedConfig};
+
+use crate::CloneMethod;
+use crate::error::OperationError;
+use crate::plan::{EntryOrigin, OperationType, PlannedOperation};
+
+/// Directory (inside the worktree's git dir) holding worktree-setup data.
+pub const MANIFEST_DIR: &str = "worktree-setup";
+
+/// File name of the manifest.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Current manifest format version.
+const MANIFEST_VERSION: u32 = 1;
+
+/// Kind of entry recorded in the manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ManifestEntryKind {
+    /// A regular file copied from the main worktree.
+    File,
+    /// A symlink (created by a `symlinks` entry or copied inside a directory).
+    Symlink,
+    /// A file rendered from a template; its hash is of the rendered output,
+    /// so only the source's existence is checked.
+    Rendered,
+    /// A file written from a git ref (`copyFromRef`); the main worktree's
+    /// copy is not its source, so only the target is checked.
+    FromRef,
+}
+
+/// A single file or symlink written into the worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// Source path relative to the main worktree.
+    pub source: String,
+    /// What was written.
+    pub kind: ManifestEntryKind,
+    /// BLAKE3 hash of the contents at copy time (files only).
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Config entry that wrote the file (`None` for unstaged files, hooks,
+    /// and manifests written before attribution was recorded).
+    #[serde(default)]
+    pub origin: Option<EntryOrigin>,
+    /// How a single-file copy or overwrite created the file (`hardlink`,
+    /// `reflink`, or `copy`); `None` for files of directory copies and
+    /// everything else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+}
+
+/// A planned path the last setup run that planned it did not write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEntry {
+    /// Why it was skipped, such as `not found` or `exists`.
+    pub reason: String,
+    /// Unix timestamp (seconds) of the run that skipped it.
+    pub skipped_at: u64,
+    /// Config entry the path was planned from.
+    #[serde(default)]
+    pub origin: Option<EntryOrigin>,
+}
+
+/// Record of file operations applied to a worktree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Manifest format version.
+    pub version: u32,
+    /// Unix timestamp (seconds) of the most recent apply.
+    pub applied_at: u64,
+    /// Applied config entries (see [`config_entry_keys`]), keyed by config
+    /// path relative to the repository root.
+    pub configs: BTreeMap<String, BTreeSet<String>>,
+    /// Files written, keyed by path relative to the target worktree.
+    pub files: BTreeMap<String, ManifestEntry>,
+    /// Paths skipped instead of written, keyed by path relative to the
+    /// target worktree. Writing a path later removes it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub skipped: BTreeMap<String, SkippedEntry>,
+    /// Keys of the entries of the configs passed to
+    /// [`Self::record_config`], by config, field and index, for
+    /// [`Self::record_operation`] to mark as applied. Not saved.
+    #[serde(skip)]
+    entry_keys: BTreeMap<(String, String, usize), String>,
+}
+
+/// Differences between a worktree and its manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestChanges {
+    /// Files modified in the worktree since setup.
+    pub modified: Vec<String>,
+    /// Files removed from the worktree since setup.
+    pub deleted: Vec<String>,
+    /// Files whose main-worktree source changed since they were copied.
+    pub source_changed: Vec<String>,
+    /// Files whose main-worktree source no longer exists.
+    pub source_removed: Vec<String>,
+    /// Config entries not yet applied, as `(config, entry)` pairs.
+    pub new_entries: Vec<(String, String)>,
+}
+
+impl ManifestChanges {
+    /// Whether nothing changed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.modified.is_empty()
+            && self.deleted.is_empty()
+            && self.source_changed.is_empty()
+            && self.source_removed.is_empty()
+            && self.new_entries.is_empty()
+    }
+}
+
+impl Manifest {
+    /// Create an empty manifest.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            ..Default::default()
+        }
+    }
+
+    /// Path of the manifest for the worktree at `worktree`.
+    ///
+    /// # Errors
+    ///
+    /// * If `worktree` is not inside a git repository
+    pub fn path_for(worktree: &Path) -> Result<PathBuf, OperationError> {
+        let repo = worktree_setup_git::open_repo(worktree)?;
+        Ok(crate::paths::worktree_data_dir(&repo).join(MANIFEST_FILE))
+    }
+
+    /// Load a manifest, returning `None` if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read
+    /// * If the file is not a valid manifest
+    pub fn load(path: &Path) -> Result<Option<Self>, OperationError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(OperationError::IoError {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+            }
+        };
+
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| OperationError::ManifestError {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Write the manifest, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// * If the directory or file cannot be written
+    pub fn save(&self, path: &Path) -> Result<(), OperationError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| OperationError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| OperationError::ManifestError {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })?;
+
+        fs::write(path, content).map_err(|e| OperationError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })
+    }
+
+    /// Mark the manifest as applied now.
+    pub fn touch(&mut self) {
+        self.version = MANIFEST_VERSION;
+        self.applied_at = unix_now();
+    }
+
+    /// Note that `config` is being applied, forgetting applied entries it
+    /// no longer has.
+    ///
+    /// Its entries count as applied once [`Self::record_operation`]
+    /// records an operation planned from them, so entries that are
+    /// skipped or fail stay new.
+    pub fn record_config(&mut self, config: &LoadedConfig) {
+        let keys = indexed_entry_keys(&config.config);
+        self.configs
+            .entry(config.relative_path.clone())
+            .or_default()
+            .retain(|applied| keys.iter().any(|(_, _, key)| key == applied));
+        for (field, index, key) in keys {
+            self.entry_keys.insert(
+                (config.relative_path.clone(), field.to_string(), index),
+                key,
+            );
+        }
+    }
+
+    /// Record the files written by an executed operation, and its config
+    /// entry as applied.
+    ///
+    /// Directory operations record every file and symlink found in the
+    /// target that also exists in the source. Files of rendered templates
+    /// are recorded as [`ManifestEntryKind::Rendered`] and files written
+    /// from a git ref as [`ManifestEntryKind::FromRef`]. `method` is how a
+    /// single file was created, from [`crate::OperationOutcome::method`].
+    ///
+    /// # Errors
+    ///
+    /// * If a copied file cannot be read for hashing
+    pub fn record_operation(
+        &mut self,
+        op: &PlannedOperation,
+        method: Option<CloneMethod>,
+        main_worktree: &Path,
+        target_worktree: &Path,
+    ) -> Result<(), OperationError> {
+        if let Some(origin) = &op.origin
+            && let Some(key) =
+                self.entry_keys
+                    .get(&(origin.config.clone(), origin.field.clone(), origin.index))
+        {
+            self.configs
+                .entry(origin.config.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+
+        let Some(target_rel) = relative_to(&op.target, target_worktree) else {
+            return Ok(());
+        };
+        self.skipped.remove(&path_key(&target_rel));
+        let source_rel = relative_to(&op.source, main_worktree).unwrap_or_default();
+
+        if op.operation_type == OperationType::Symlink {
+            self.files.insert(
+                path_key(&target_rel),
+                ManifestEntry {
+                    source: path_key(&source_rel),
+                    kind: ManifestEntryKind::Symlink,
+                    hash: None,
+                    origin: op.origin.clone(),
+                    method: None,
+                },
+            );
+            return Ok(());
+        }
+
+        let rendered = op.template.is_some();
+        if !op.is_directory {
+            self.record_path(&op.target, &target_rel, &source_rel, rendered, op)?;
+            if let Some(entry) = self.files.get_mut(&path_key(&target_rel)) {
+                entry.method = method.map(|method| method.as_str().to_string());
+            }
+            return Ok(());
+        }
+
+        for rel in walk_relative(&op.target) {
+            if fs::symlink_metadata(op.source.join(&rel)).is_err() {
+                continue;
+            }
+            self.record_path(
+                &op.target.join(&rel),
+                &target_rel.join(&rel),
+                &source_rel.join(&rel),
+                rendered,
+                op,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Record that `op` was skipped, and why.
+    pub fn record_skip(&mut self, op: &PlannedOperation, target_worktree: &Path, reason: &str) {
+        let Some(target_rel) = relative_to(&op.target, target_worktree) else {
+            return;
+        };
+        self.skipped.insert(
+            path_key(&target_rel),
+            SkippedEntry {
+                reason: reason.to_string(),
+                skipped_at: unix_now(),
+                origin: op.origin.clone(),
+            },
+        );
+    }
+
+    /// The file recorded for `path` (relative to the target worktree) or
+    /// for the nearest directory above it, such as a symlinked directory,
+    /// with the path it was recorded for.
+    #[must_use]
+    pub fn file_covering(&self, path: &str) -> Option<(&str, &ManifestEntry)> {
+        covering(&self.files, path)
+    }
+
+    /// The skip recorded for `path` (relative to the target worktree) or
+    /// for the nearest directory above it, with the path it was recorded
+    /// for.
+    #[must_use]
+    pub fn skip_covering(&self, path: &str) -> Option<(&str, &SkippedEntry)> {
+        covering(&self.skipped, path)
+    }
+
+    /// Files `op` would replace that were edited in the target worktree
+    /// since setup last ran, as paths relative to the worktree.
+    ///
+    /// A file setup wrote counts as edited when its contents no longer
+    /// match the recorded hash, any other file when it was modified after
+    /// the last run. Edited files whose contents already equal their
+    /// source are left out, since replacing them loses nothing.
+    #[must_use]
+    pub fn local_edits(&self, op: &PlannedOperation, target_worktree: &Path) -> Vec<String> {
+        let Some(target_rel) = relative_to(&op.target, target_worktree) else {
+            return Vec::new();
+        };
+        let is_symlink = op.operation_type == OperationType::Symlink;
+        if is_symlink && op.target.is_symlink() {
+            return Vec::new();
+        }
+        // A symlink replaces whatever is at the target; a copy only the
+        // files it has a source for.
+        let candidates = if is_symlink && op.target.is_dir() {
+            walk_relative(&op.target)
+        } else if op.is_directory {
+            walk_relative(&op.source)
+        } else {
+            vec![PathBuf::new()]
+        };
+        let compare_source = !is_symlink && op.template.is_none() && op.ref_source.is_none();
+
+        // `Path::join("")` would add a trailing `/`.
+        let under = |root: &Path, rel: &Path| {
+            if rel.as_os_str().is_empty() {
+                root.to_path_buf()
+            } else {
+                root.join(rel)
+            }
+        };
+
+        let mut edits = Vec::new();
+        for rel in candidates {
+            let target = under(&op.target, &rel);
+            if target.is_symlink() || !target.is_file() {
+                continue;
+            }
+            let key = path_key(&target_rel.join(&rel));
+            let edited = self.files.get(&key).map_or_else(
+                || modified_after(&target, self.applied_at),
+                |entry| entry.hash.is_none() || entry.hash != hash_file(&target).ok(),
+            );
+            if !edited {
+                continue;
+            }
+            let source = under(&op.source, &rel);
+            if compare_source && hash_file(&source).ok() == hash_file(&target).ok() {
+                continue;
+            }
+            edits.push(key);
+        }
+        edits
+    }
+
+    /// Record a single written path, attributed to the entry `op` was
+    /// planned from.
+    fn record_path(
+        &mut self,
+        target: &Path,
+        target_rel: &Path,
+        source_rel: &Path,
+        rendered: bool,
+        op: &PlannedOperation,
+    ) -> Result<(), OperationError> {
+        let is_symlink = target.is_symlink();
+        let hash = if is_symlink {
+            None
+        } else {
+            Some(hash_file(target)?)
+        };
+
+        self.files.insert(
+            path_key(target_rel),
+            ManifestEntry {
+                source: path_key(source_rel),
+                kind: if is_symlink {
+                    ManifestEntryKind::Symlink
+                } else if rendered {
+                    ManifestEntryKind::Rendered
+                } else if op.ref_source.is_some() {
+                    ManifestEntryKind::FromRef
+                } else {
+                    ManifestEntryKind::File
+                },
+                hash,
+                origin: op.origin.clone(),
+                method: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Number of recorded files per config that wrote them.
+    ///
+    /// Files without an origin are counted under `None`.
+    #[must_use]
+    pub fn files_by_config(&self) -> BTreeMap<Option<&str>, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in self.files.values() {
+            *counts
+                .entry(entry.origin.as_ref().map(|o| o.config.as_str()))
+                .or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Compare the manifest against the current worktrees and configs.
+    ///
+    /// Only configs that were applied before are checked for new entries.
+    #[must_use]
+    pub fn changes(
+        &self,
+        main_worktree: &Path,
+        target_worktree: &Path,
+        configs: &[LoadedConfig],
+    ) -> ManifestChanges {
+        let mut changes = ManifestChanges::default();
+
+        for (rel, entry) in &self.files {
+            let target = target_worktree.join(rel);
+            match entry.kind {
+                ManifestEntryKind::Symlink => {
+                    if !target.is_symlink() {
+                        if fs::symlink_metadata(&target).is_ok() {
+                            changes.modified.push(rel.clone());
+                        } else {
+                            changes.deleted.push(rel.clone());
+                        }
+                    }
+                }
+                ManifestEntryKind::File
+                | ManifestEntryKind::Rendered
+                | ManifestEntryKind::FromRef => {
+                    match hash_file(&target) {
+                        Ok(hash) if Some(&hash) != entry.hash.as_ref() => {
+                            changes.modified.push(rel.clone());
+                        }
+                        Ok(_) => {}
+                        Err(_) => changes.deleted.push(rel.clone()),
+                    }
+
+                    if entry.kind == ManifestEntryKind::FromRef {
+                        continue;
+                    }
+                    let source = main_worktree.join(&entry.source);
+                    if entry.kind == ManifestEntryKind::Rendered {
+                        if !source.exists() {
+                            changes.source_removed.push(rel.clone());
+                        }
+                        continue;
+                    }
+                    match hash_file(&source) {
+                        Ok(hash) if Some(&hash) != entry.hash.as_ref() => {
+                            changes.source_changed.push(rel.clone());
+                        }
+                        Ok(_) => {}
+                        Err(_) => changes.source_removed.push(rel.clone()),
+                    }
+                }
+            }
+        }
+
+        for config in configs {
+            let Some(applied) = self.configs.get(&config.relative_path) else {
+                continue;
+            };
+            for key in config_entry_keys(&config.config) {
+                if !applied.contains(&key) {
+                    changes
+                        .new_entries
+                        .push((config.relative_path.clone(), key));
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+/// Stable keys identifying each file-operation entry of a config.
+///
+/// Keys have the form `<field>:<path>` (e.g., `copy:.env`,
+/// `templates:.env.example -> .env`, `copyFromRef:main:configs/dev -> configs/dev`).
+#[must_use]
+pub fn config_entry_keys(config: &Config) -> BTreeSet<String> {
+    indexed_entry_keys(config)
+        .into_iter()
+        .map(|(_, _, key)| key)
+        .collect()
+}
+
+/// [`config_entry_keys`] with the field and index of each entry.
+fn indexed_entry_keys(config: &Config) -> Vec<(&'static str, usize, String)> {
+    let mut keys = Vec::new();
+    for (field, entries) in [
+        ("symlinks", &config.symlinks),
+        ("copy", &config.copy),
+        ("overwrite", &config.overwrite),
+        ("copyGlob", &config.copy_glob),
+    ] {
+        for (index, entry) in entries.iter().enumerate() {
+            keys.push((field, index, format!("{field}:{}", entry.path())));
+        }
+    }
+    for (index, template) in config.templates.iter().enumerate() {
+        keys.push((
+            "templates",
+            index,
+            format!("templates:{} -> {}", template.source, template.target),
+        ));
+    }
+    for (index, entry) in config.copy_from_ref.iter().enumerate() {
+        keys.push((
+            "copyFromRef",
+            index,
+            format!(
+                "copyFromRef:{}:{} -> {}",
+                entry.git_ref,
+                entry.path,
+                entry.target.as_deref().unwrap_or(&entry.path)
+            ),
+        ));
+    }
+    keys
+}
+
+/// Current time as Unix seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Hash a file's contents with BLAKE3.
+pub fn hash_file(path: &Path) -> Result<String, OperationError> {
+    let mut file = fs::File::open(path).map_err(|e| OperationError::IoError {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+    let mut hasher = blake3::Hasher::new();
+    hasher
+        .update_reader(&mut file)
+        .map_err(|e| OperationError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Whether `path` was modified after the Unix timestamp `time`.
+fn modified_after(path: &Path, time: u64) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .is_some_and(|modified| modified.as_secs() > time)
+}
+
+/// Strip `root` (as given or canonicalized) from `path`.
+fn relative_to(path: &Path, root: &Path) -> Option<PathBuf> {
+    if let Ok(rel) = path.strip_prefix(root) {
+        return Some(rel.to_path_buf());
+    }
+    let canonical = root.canonicalize().ok()?;
+    path.strip_prefix(canonical).ok().map(Path::to_path_buf)
+}
+
+/// The entry for `path` or its nearest parent directory in `entries`.
+fn covering<'a, T>(entries: &'a BTreeMap<String, T>, path: &str) -> Option<(&'a str, &'a T)> {
+    let mut key = path;
+    loop {
+        if let Some((recorded, entry)) = entries.get_key_value(key) {
+            return Some((recorded.as_str(), entry));
+        }
+        key = &key[..key.rfind('/')?];
+    }
+}
+
+/// Manifest key for a relative path (always `/`-separated).
+fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// List files and symlinks under `dir`, relative to it. Symlinked
+/// directories are listed, not followed. FIFOs and other special files are
+/// left out, since reading them to hash could block.
+fn walk_relative(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![PathBuf::new()];
+
+    while let Some(rel) = stack.pop() {
+        let Ok(entries) = fs::read_dir(dir.join(&rel)) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let child = rel.join(entry.file_name());
+            if file_type.is_dir() {
+                stack.push(child);
+            } else if file_type.is_file() || file_type.is_symlink() {
+                files.push(child);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ApplyConfigOptions, execute_operation, execute_operation_outcome, plan_operations,
+    };
+    use tempfile::TempDir;
+
+    fn loaded(dir: &Path, config: Config) -> LoadedConfig {
+        LoadedConfig {
+            config,
+            config_path: dir.join("worktree.config.toml"),
+            config_dir: dir.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        }
+    }
+
+    fn apply(config: &LoadedConfig, main: &Path, target: &Path) -> Manifest {
+        let ops = plan_operations(config, main, target, &ApplyConfigOptions::default()).unwrap();
+        let mut manifest = Manifest::new();
+        manifest.record_config(config);
+        for op in ops.iter().filter(|op| !op.will_skip) {
+            let outcome = execute_operation_outcome(op, |_, _| {}).unwrap();
+            manifest
+                .record_operation(op, outcome.method, main, target)
+                .unwrap();
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_record_and_detect_changes() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        fs::write(main_dir.path().join(".env"), "A=1").unwrap();
+        fs::create_dir_all(main_dir.path().join("assets/img")).unwrap();
+        fs::write(main_dir.path().join("assets/a.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("assets/img/b.png"), "b").unwrap();
+        fs::create_dir_all(main_dir.path().join("data")).unwrap();
+
+        let config = loaded(
+            main_dir.path(),
+            Config {
+                copy: vec![".env".into(), "assets".into()],
+                symlinks: vec!["data".into()],
+                ..Default::default()
+            },
+        );
+
+        let manifest = apply(&config, main_dir.path(), target_dir.path());
+
+        assert_eq!(
+            manifest.files.keys().cloned().collect::<Vec<_>>(),
+            vec![".env", "assets/a.txt", "assets/img/b.png", "data"]
+        );
+        assert_eq!(manifest.files["data"].kind, ManifestEntryKind::Symlink);
+        let origin = |path: &str| {
+            let origin = manifest.files[path].origin.as_ref().unwrap();
+            (origin.field.as_str(), origin.index)
+        };
+        assert_eq!(origin("data"), ("symlinks", 0));
+        assert_eq!(origin(".env"), ("copy", 0));
+        assert_eq!(origin("assets/img/b.png"), ("copy", 1));
+        // Only single-file copies record how the file was created.
+        assert!(matches!(
+            manifest.files[".env"].method.as_deref(),
+            Some("hardlink" | "reflink" | "copy")
+        ));
+        assert_eq!(manifest.files["assets/a.txt"].method, None);
+        assert_eq!(
+            manifest.files_by_config(),
+            BTreeMap::from([(Some("worktree.config.toml"), 4)])
+        );
+        assert!(
+            manifest
+                .changes(
+                    main_dir.path(),
+                    target_dir.path(),
+                    std::slice::from_ref(&config)
+                )
+                .is_empty()
+        );
+
+        fs::write(target_dir.path().join(".env"), "A=2").unwrap();
+        fs::remove_file(target_dir.path().join("assets/a.txt")).unwrap();
+        fs::write(main_dir.path().join("assets/img/b.png"), "changed").unwrap();
+
+        let mut updated = config;
+        updated.config.overwrite = vec!["settings.json".into()];
+
+        let changes = manifest.changes(main_dir.path(), target_dir.path(), &[updated]);
+
+        assert_eq!(changes.modified, vec![".env"]);
+        assert_eq!(changes.deleted, vec!["assets/a.txt"]);
+        assert_eq!(changes.source_changed, vec!["assets/img/b.png"]);
+        assert!(changes.source_removed.is_empty());
+        assert_eq!(
+            changes.new_entries,
+            vec![(
+                "worktree.config.toml".to_string(),
+                "overwrite:settings.json".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_failed_and_skipped_entries_stay_new() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join(".env"), "A=1").unwrap();
+        fs::write(main_dir.path().join("secret.txt"), "s").unwrap();
+
+        let config = loaded(
+            main_dir.path(),
+            Config {
+                copy: vec![".env".into(), "secret.txt".into(), "missing".into()],
+                ..Default::default()
+            },
+        );
+        let mut ops = plan_operations(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+        // The `secret.txt` entry fails in its `before` hook.
+        let failing = ops
+            .iter_mut()
+            .find(|op| op.display_path == "secret.txt")
+            .unwrap();
+        failing.hooks = Some(std::sync::Arc::new(crate::OperationHooks {
+            before: Some("exit 1".to_string()),
+            after: None,
+            cwd: target_dir.path().to_path_buf(),
+        }));
+
+        let mut manifest = Manifest::new();
+        manifest.record_config(&config);
+        for op in ops.iter().filter(|op| !op.will_skip) {
+            if execute_operation(op, |_, _| {}).is_ok() {
+                manifest
+                    .record_operation(op, None, main_dir.path(), target_dir.path())
+                    .unwrap();
+            }
+        }
+
+        let changes = manifest.changes(
+            main_dir.path(),
+            target_dir.path(),
+            std::slice::from_ref(&config),
+        );
+        assert_eq!(
+            changes.new_entries,
+            vec![
+                (
+                    "worktree.config.toml".to_string(),
+                    "copy:missing".to_string()
+                ),
+                (
+                    "worktree.config.toml".to_string(),
+                    "copy:secret.txt".to_string()
+                ),
+            ]
+        );
+
+        // Applying the config again keeps the entries already applied.
+        manifest.record_config(&config);
+        assert!(manifest.configs["worktree.config.toml"].contains("copy:.env"));
+    }
+
+    #[test]
+    fn test_record_skip() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(main_dir.path().join("assets")).unwrap();
+        fs::write(main_dir.path().join("assets/a.txt"), "a").unwrap();
+
+        let config = loaded(
+            main_dir.path(),
+            Config {
+                copy: vec!["assets".into()],
+                ..Default::default()
+            },
+        );
+        let ops = plan_operations(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+        let mut manifest = Manifest::new();
+        manifest.record_skip(&ops[0], target_dir.path(), "exists");
+
+        let (recorded, entry) = manifest.skip_covering("assets/img/b.png").unwrap();
+        assert_eq!(recorded, "assets");
+        assert_eq!(entry.reason, "exists");
+        assert_eq!(entry.origin.as_ref().unwrap().field, "copy");
+        assert!(manifest.skip_covering("other.txt").is_none());
+
+        execute_operation(&ops[0], |_, _| {}).unwrap();
+        manifest
+            .record_operation(&ops[0], None, main_dir.path(), target_dir.path())
+            .unwrap();
+        assert!(manifest.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_local_edits() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(main_dir.path().join("assets")).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(main_dir.path().join("assets").join(name), name).unwrap();
+        }
+
+        fs::write(main_dir.path().join(".env"), "A=1").unwrap();
+
+        let config = loaded(
+            main_dir.path(),
+            Config {
+                overwrite: vec!["assets".into(), ".env".into()],
+                ..Default::default()
+            },
+        );
+        let ops = plan_operations(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+        let mut manifest = Manifest::new();
+        for op in &ops {
+            execute_operation(op, |_, _| {}).unwrap();
+            manifest
+                .record_operation(op, None, main_dir.path(), target_dir.path())
+                .unwrap();
+            assert!(manifest.local_edits(op, target_dir.path()).is_empty());
+        }
+        fs::write(target_dir.path().join(".env"), "A=2").unwrap();
+        assert_eq!(manifest.local_edits(&ops[1], target_dir.path()), [".env"]);
+
+        // Edited since setup wrote it, and not recorded but modified after
+        // the last run.
+        fs::write(target_dir.path().join("assets/a.txt"), "local").unwrap();
+        manifest.files.remove("assets/c.txt");
+        fs::write(target_dir.path().join("assets/c.txt"), "local").unwrap();
+        manifest.applied_at = 1;
+        assert_eq!(
+            manifest.local_edits(&ops[0], target_dir.path()),
+            ["assets/a.txt", "assets/c.txt"]
+        );
+
+        // An edit that matches the source loses nothing.
+        fs::write(target_dir.path().join("assets/c.txt"), "c.txt").unwrap();
+        assert_eq!(
+            manifest.local_edits(&ops[0], target_dir.path()),
+            ["assets/a.txt"]
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(MANIFEST_DIR).join(MANIFEST_FILE);
+
+        assert_eq!(Manifest::load(&path).unwrap(), None);
+
+        let mut manifest = Manifest::new();
+        manifest.touch();
+        manifest.files.insert(
+            "a.txt".to_string(),
+            ManifestEntry {
+                source: "a.txt".to_string(),
+                kind: ManifestEntryKind::File,
+                hash: Some("abc".to_string()),
+                origin: Some(EntryOrigin {
+                    config: "worktree.config.toml".to_string(),
+                    field: "copy".to_string(),
+                    index: 0,
+                    label: None,
+                }),
+                method: Some("reflink".to_string()),
+            },
+        );
+        manifest.save(&path).unwrap();
+
+        assert_eq!(Manifest::load(&path).unwrap(), Some(manifest));
+    }
+
+    #[test]
+    fn test_config_entry_keys() {
+        let config = Config {
+            copy: vec![".env".into()],
+            copy_glob: vec!["**/*.local".into()],
+            templates: vec![worktree_setup_config::TemplateMapping {
+                source: ".env.example".to_string(),
+                target: ".env".to_string(),
+                strip_suffix: None,
+                engine: worktree_setup_config::TemplateEngine::default(),
+                mode: None,
+                dir_mode: None,
+                label: None,
+                order: 0,
+                condition: worktree_setup_config::Condition::default(),
+                hooks: worktree_setup_config::EntryHooks::default(),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config_entry_keys(&config).into_iter().collect::<Vec<_>>(),
+            vec![
+                "copy:.env",
+                "copyGlob:**/*.local",
+                "templates:.env.example -> .env"
+            ]
+        );
+    }
+}
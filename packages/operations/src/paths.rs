@@ -0,0 +1,168 @@
+//! Locations of the files worktree-setup generates.
+//!
+//! * **Caches** are disposable and live in the user cache directory:
+//!   `$WORKTREE_SETUP_CACHE_DIR` if set, else `$XDG_CACHE_HOME/worktree-setup`,
+//!   else the platform cache directory (`~/.cache/worktree-setup`,
+//!   `~/Library/Caches/worktree-setup`), with one subdirectory per
+//!   repository, one for base configs fetched for `extends`, and one for
+//!   archives extracted for `tar:` sources, plus the hashes TypeScript
+//!   configs had when they last ran.
+//! * **State** (applied configs, setup manifests) describes a repository
+//!   and its worktrees, so it stays in the git directory and is removed
+//!   with them: `<common-dir>/worktree-setup` for state shared by all
+//!   worktrees and `<git-dir>/worktree-setup` for a single worktree's.
+//!
+//! Everything that reads or writes these files gets its location here.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use worktree_setup_git::Repository;
+
+use crate::manifest::MANIFEST_DIR;
+
+/// Environment variable overriding the cache directory.
+pub const CACHE_DIR_ENV: &str = "WORKTREE_SETUP_CACHE_DIR";
+
+/// Subdirectory of the cache directory holding per-repository caches.
+const REPOS_DIR: &str = "repos";
+
+/// Subdirectory of the cache directory holding fetched base configs.
+const REMOTE_CONFIGS_DIR: &str = "remote-configs";
+
+/// Subdirectory of the cache directory holding extracted source archives.
+const SOURCES_DIR: &str = "sources";
+
+/// File of the hashes TypeScript configs had when they last ran.
+const TS_CONFIG_STORE: &str = "ts-configs.json";
+
+/// Root of every cache, or `None` if no home directory can be found.
+#[must_use]
+pub fn cache_root() -> Option<PathBuf> {
+    resolve_cache_root(
+        std::env::var_os(CACHE_DIR_ENV),
+        std::env::var_os("XDG_CACHE_HOME"),
+        dirs::cache_dir(),
+    )
+}
+
+/// Pick the cache root from the override, `$XDG_CACHE_HOME` and the
+/// platform cache directory, in that order.
+///
+/// Empty and relative values are ignored, as the XDG spec asks.
+fn resolve_cache_root(
+    override_dir: Option<OsString>,
+    xdg_cache_home: Option<OsString>,
+    platform_cache_dir: Option<PathBuf>,
+) -> Option<PathBuf> {
+    let absolute =
+        |value: Option<OsString>| value.map(PathBuf::from).filter(|path| path.is_absolute());
+
+    absolute(override_dir).or_else(|| {
+        absolute(xdg_cache_home)
+            .or(platform_cache_dir)
+            .map(|dir| dir.join("worktree-setup"))
+    })
+}
+
+/// Cache directory of base configs fetched from URLs in `extends`, or
+/// `None` if there is no cache root.
+#[must_use]
+pub fn remote_config_cache_dir() -> Option<PathBuf> {
+    cache_root().map(|root| root.join(REMOTE_CONFIGS_DIR))
+}
+
+/// Cache directory of archives extracted for `tar:` sources, or `None` if
+/// there is no cache root.
+#[must_use]
+pub fn source_cache_dir() -> Option<PathBuf> {
+    cache_root().map(|root| root.join(SOURCES_DIR))
+}
+
+/// File recording the hash each TypeScript config had when it last ran,
+/// or `None` if there is no cache root.
+#[must_use]
+pub fn ts_config_store() -> Option<PathBuf> {
+    cache_root().map(|root| root.join(TS_CONFIG_STORE))
+}
+
+/// Cache directory of `repo`, shared by all its worktrees.
+///
+/// Falls back to [`repository_data_dir`] when there is no cache root.
+#[must_use]
+pub fn repository_cache_dir(repo: &Repository) -> PathBuf {
+    cache_root().map_or_else(
+        || repository_data_dir(repo),
+        |root| root.join(REPOS_DIR).join(repository_key(repo.commondir())),
+    )
+}
+
+/// Directory holding state shared by all worktrees of `repo`.
+#[must_use]
+pub fn repository_data_dir(repo: &Repository) -> PathBuf {
+    repo.commondir().join(MANIFEST_DIR)
+}
+
+/// Directory holding state of the worktree `repo` was opened from.
+#[must_use]
+pub fn worktree_data_dir(repo: &Repository) -> PathBuf {
+    repo.path().join(MANIFEST_DIR)
+}
+
+/// Name of a repository's cache directory.
+///
+/// The main worktree's directory name keeps it recognizable; a hash of the
+/// common git directory keeps repositories with the same name apart.
+fn repository_key(common_dir: &Path) -> String {
+    let canonical = common_dir
+        .canonicalize()
+        .unwrap_or_else(|_| common_dir.to_path_buf());
+    let hash = blake3::hash(canonical.to_string_lossy().as_bytes()).to_hex();
+    let name = canonical
+        .parent()
+        .and_then(Path::file_name)
+        .map_or_else(|| "repo".to_string(), |n| n.to_string_lossy().to_string());
+    format!("{name}-{}", &hash[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_root_precedence() {
+        let platform = Some(PathBuf::from("/home/u/.cache"));
+
+        assert_eq!(
+            resolve_cache_root(
+                Some("/tmp/wts".into()),
+                Some("/xdg".into()),
+                platform.clone()
+            ),
+            Some(PathBuf::from("/tmp/wts"))
+        );
+        assert_eq!(
+            resolve_cache_root(None, Some("/xdg".into()), platform.clone()),
+            Some(PathBuf::from("/xdg/worktree-setup"))
+        );
+        assert_eq!(
+            resolve_cache_root(Some("".into()), Some("relative".into()), platform),
+            Some(PathBuf::from("/home/u/.cache/worktree-setup"))
+        );
+        assert_eq!(resolve_cache_root(None, None, None), None);
+    }
+
+    #[test]
+    fn test_repository_key_is_named_and_distinct() {
+        let a = repository_key(Path::new("/src/a/app/.git"));
+        let b = repository_key(Path::new("/src/b/app/.git"));
+
+        assert!(a.starts_with("app-"));
+        assert_eq!(a.len(), "app-".len() + 16);
+        assert_ne!(a, b);
+    }
+}
@@ -4,18 +4,40 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions)]
 
-use std::collections::BTreeSet;
-use std::path::{Path, PathBuf};
-
-use worktree_setup_config::LoadedConfig;
-use worktree_setup_copy::{count_files, count_files_with_progress};
-use worktree_setup_glob::{GlobResolverOptions, is_glob_pattern, resolve_glob};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use worktree_setup_config::{
+    Condition, EntryHooks, LoadedConfig, OperationEntry, PathSpec, RefCopyEntry, TemplateEngine,
+    UnstagedScope,
+};
+use worktree_setup_copy::{EntryCounts, count_entries_with_progress};
+use worktree_setup_git::{TreeFile, TreeFileKind, checkout_filter, list_tree_files, open_repo};
+use worktree_setup_glob::{
+    GlobResolverOptions, GlobWalkError, WorktreeIgnore, is_glob_pattern, list_files,
+    literal_prefix, resolve_glob_with_errors,
+};
 
 use crate::ApplyConfigOptions;
+use crate::copy::ignore_filter;
+use crate::count_cache::CountCache;
+use crate::entry_hooks::OperationHooks;
 use crate::error::OperationError;
+use crate::ref_file::RefSource;
+use crate::source::SourceProviders;
+use crate::template::TemplateContext;
 
 /// Type of operation to perform.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum OperationType {
     /// Create a symlink.
     Symlink,
@@ -55,7 +77,17 @@ pub struct PlannedOperation {
     /// Target path (absolute).
     pub target: PathBuf,
     /// Number of files (1 for single files, N for directories).
+    ///
+    /// For directories this includes symlinks, which are recreated as links.
     pub file_count: u64,
+    /// How many of `file_count` are symlinks.
+    pub symlink_count: u64,
+    /// Bytes of regular file content the operation copies (0 for symlinks).
+    pub byte_count: u64,
+    /// Files inside a directory left out by `.worktreeignore`.
+    pub filtered_count: u64,
+    /// Sockets and device nodes inside a directory, which are skipped.
+    pub special_count: u64,
     /// Whether this is a directory operation.
     pub is_directory: bool,
     /// Whether this operation will be skipped.
@@ -64,34 +96,130 @@ pub struct PlannedOperation {
     pub skip_reason: Option<String>,
     /// Whether to force-overwrite existing targets.
     pub force_overwrite: bool,
+    /// `.worktreeignore` rules applied to entries inside directory copies.
+    pub ignore: Option<Arc<WorktreeIgnore>>,
+    /// How a template file is rendered (`None` copies it unchanged).
+    pub template: Option<Arc<TemplateContext>>,
+    /// The config entry's `order`; the plan is sorted by it, lower first.
+    pub order: i32,
+    /// The config entry the operation was planned from (`None` for
+    /// unstaged files and hooks).
+    pub origin: Option<EntryOrigin>,
+    /// The entry's `before`/`after` commands, run by the executor.
+    pub hooks: Option<Arc<OperationHooks>>,
+    /// The committed file written instead of copying `source` (set for
+    /// `copyFromRef` entries).
+    pub ref_source: Option<Arc<RefSource>>,
+    /// How many levels below a directory source to copy (`None` for no
+    /// limit).
+    pub max_depth: Option<usize>,
+    /// Permissions for the target and the directories created for it
+    /// (template `mode` and `dirMode`).
+    pub modes: Option<TargetModes>,
+}
+
+impl PlannedOperation {
+    /// The `label` of the entry the operation was planned from, if any.
+    #[must_use]
+    pub fn label(&self) -> Option<&str> {
+        self.origin
+            .as_ref()
+            .and_then(|origin| origin.label.as_deref())
+    }
+
+    /// Whether running the operation replaces something already in the
+    /// target worktree: overwrites, forced symlinks, and unstaged files
+    /// copied over the checkout.
+    #[must_use]
+    pub const fn is_destructive(&self) -> bool {
+        !self.will_skip
+            && (self.force_overwrite
+                || matches!(
+                    self.operation_type,
+                    OperationType::Overwrite | OperationType::Unstaged
+                ))
+    }
+}
+
+/// Permissions a template entry sets on what it writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetModes {
+    /// Mode of the written file, instead of its source's.
+    pub file: Option<u32>,
+    /// Mode of each directory created to hold the file.
+    pub dirs: Option<u32>,
+}
+
+impl TargetModes {
+    /// The modes set by `template`, or `None` if it sets neither.
+    #[must_use]
+    pub fn of(template: &worktree_setup_config::TemplateMapping) -> Option<Self> {
+        let modes = Self {
+            file: template.mode.map(|mode| mode.0),
+            dirs: template.dir_mode.map(|mode| mode.0),
+        };
+        (modes.file.is_some() || modes.dirs.is_some()).then_some(modes)
+    }
+}
+
+/// The config entry an operation was planned from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryOrigin {
+    /// Config path relative to the repository root.
+    pub config: String,
+    /// Config field holding the entry (`symlinks`, `copy`, `overwrite`,
+    /// `copyGlob`, `templates` or `copyFromRef`).
+    pub field: String,
+    /// Index of the entry within the field.
+    pub index: usize,
+    /// The entry's `label`, shown next to its progress and plan lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 /// Resolve a path from config, handling repo-root-relative paths.
 ///
-/// Paths starting with `/` are relative to the base (repo root).
-/// Other paths are relative to the config file's directory.
+/// Paths starting with `/` are relative to the config's repository root,
+/// which is the submodule root for configs inside a submodule.
+/// Other paths are relative to the config file's directory. See
+/// [`PathSpec`] for the full rules.
 ///
 /// # Arguments
 ///
 /// * `base` - The base path (`main_worktree` or `target_worktree`)
+/// * `root_relative_dir` - Relative path from the worktree to the config's
+///   repository root (empty unless the config is inside a submodule)
 /// * `config_relative_dir` - Relative path from repo root to config directory
 /// * `path` - The path from the config file
 ///
 /// # Returns
 ///
 /// A tuple of (`resolved_path`, `display_path`)
-fn resolve_path(base: &Path, config_relative_dir: &Path, path: &str) -> (PathBuf, String) {
-    path.strip_prefix('/').map_or_else(
-        || {
-            // Config-relative path (e.g., "data" -> "apps/myapp/data")
-            let display = config_relative_dir.join(path);
-            (base.join(&display), display.to_string_lossy().to_string())
-        },
-        |stripped| {
-            // Repo-root-relative path (e.g., "/.nix" -> ".nix")
-            (base.join(stripped), stripped.to_string())
-        },
-    )
+fn resolve_path(
+    base: &Path,
+    root_relative_dir: &Path,
+    config_relative_dir: &Path,
+    path: &str,
+) -> (PathBuf, String) {
+    // "data" -> "apps/myapp/data"; "/.nix" -> ".nix", or "libs/sub/.nix"
+    // for a config inside submodule "libs/sub"
+    let display = PathSpec::parse(path).worktree_relative(root_relative_dir, config_relative_dir);
+    (base.join(&display), display.to_string_lossy().to_string())
+}
+
+/// Find the repository root that owns a config directory.
+///
+/// Walks up from `config_relative_dir` looking for a `.git` entry below
+/// `main_worktree`; the nearest one marks a submodule root. Returns an
+/// empty path when the config belongs to the superproject itself.
+fn config_root_relative_dir(main_worktree: &Path, config_relative_dir: &Path) -> PathBuf {
+    config_relative_dir
+        .ancestors()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .find(|dir| main_worktree.join(dir).join(".git").exists())
+        .map_or_else(PathBuf::new, Path::to_path_buf)
 }
 
 /// Check whether a resolved path escapes the containment boundary.
@@ -113,7 +241,8 @@ fn escapes_containment(path: &Path, containment_root: Option<&PathBuf>) -> bool
 /// Plan all operations for a config without executing.
 ///
 /// This enumerates all operations that would be performed, along with file counts
-/// for progress display. Operations are returned in execution order.
+/// for progress display. Operations are returned in execution order (see
+/// [`sort_by_order`]).
 ///
 /// # Arguments
 ///
@@ -125,6 +254,8 @@ fn escapes_containment(path: &Path, containment_root: Option<&PathBuf>) -> bool
 /// # Errors
 ///
 /// * If file counting fails during directory scanning
+/// * If a glob pattern cannot search part of the tree and `strictGlobs`
+///   is set
 pub fn plan_operations(
     config: &LoadedConfig,
     main_worktree: &Path,
@@ -140,9 +271,26 @@ pub fn plan_operations(
     )
 }
 
+/// Where a `copy`, `overwrite` or template entry reads from.
+struct EntrySource {
+    /// Local path of the source (empty if it could not be provided).
+    path: PathBuf,
+    /// Whether a provider made the source available, in which case it may
+    /// lie outside the worktree and `.worktreeignore` does not apply.
+    provided: bool,
+    /// Where entries without a `target` write to, relative to the config
+    /// directory.
+    default_target: Option<String>,
+    /// Why the provider could not make the source available.
+    error: Option<String>,
+}
+
 /// Shared context for planning operations.
 struct PlanContext<'a, F> {
     config_relative_dir: &'a Path,
+    /// Root that `/`-prefixed paths resolve against, relative to the
+    /// worktree (the submodule path for configs inside a submodule).
+    root_relative_dir: PathBuf,
     main_worktree: &'a Path,
     target_worktree: &'a Path,
     overwrite: bool,
@@ -151,10 +299,548 @@ struct PlanContext<'a, F> {
     /// When `Some`, resolved paths must be descendants of this root.
     /// When `None`, containment is not enforced (path escape allowed).
     containment_root: Option<PathBuf>,
+    /// `.worktreeignore` rules for the main worktree.
+    ignore: Arc<WorktreeIgnore>,
+    /// Providers for `<scheme>:` entry sources.
+    sources: Arc<SourceProviders>,
+    /// Directory counts reused from earlier runs.
+    count_cache: Option<Arc<CountCache>>,
+    /// Depth limit for directory copies whose entry sets no `maxDepth`.
+    max_depth: Option<usize>,
+    /// How long a glob pattern may search before it is given up on.
+    glob_timeout: Option<Duration>,
+    /// Exit status of each `ifCommand` already run, keyed by command.
+    command_results: RefCell<BTreeMap<String, bool>>,
+    /// Config path relative to the repository root.
+    config_path: &'a str,
+    /// Entry currently being planned (see [`Self::begin_entry`]).
+    entry: RefCell<Option<EntryOrigin>>,
+    /// Hooks of the entry currently being planned.
+    entry_hooks: RefCell<Option<Arc<OperationHooks>>>,
+    /// Whether glob errors fail planning (`strictGlobs`).
+    strict_globs: bool,
+    /// Problems that kept glob patterns from searching part of the tree.
+    glob_errors: RefCell<Vec<UnsearchedGlob>>,
     on_progress: &'a F,
     total_ops: usize,
 }
 
+impl<'a, F> PlanContext<'a, F>
+where
+    F: Fn(usize, usize, &str, Option<u64>),
+{
+    /// Build the planning context for `config`.
+    fn new(
+        config: &'a LoadedConfig,
+        main_worktree: &'a Path,
+        target_worktree: &'a Path,
+        options: &ApplyConfigOptions,
+        on_progress: &'a F,
+        total_ops: usize,
+    ) -> Self {
+        let config_relative_dir = config
+            .config_dir
+            .strip_prefix(main_worktree)
+            .unwrap_or(&config.config_dir);
+
+        let containment_root = if options.allow_path_escape {
+            None
+        } else {
+            main_worktree.canonicalize().ok()
+        };
+
+        let ignore = options
+            .worktree_ignore
+            .clone()
+            .unwrap_or_else(|| Arc::new(load_worktree_ignore(main_worktree, target_worktree)));
+
+        Self {
+            config_relative_dir,
+            root_relative_dir: config_root_relative_dir(main_worktree, config_relative_dir),
+            main_worktree,
+            target_worktree,
+            overwrite: options.overwrite_existing,
+            containment_root,
+            ignore,
+            sources: options.source_providers.clone().unwrap_or_default(),
+            count_cache: options.count_cache.clone(),
+            max_depth: options.max_depth,
+            glob_timeout: options.glob_timeout,
+            command_results: RefCell::new(BTreeMap::new()),
+            config_path: &config.relative_path,
+            entry: RefCell::new(None),
+            entry_hooks: RefCell::new(None),
+            strict_globs: config.config.strict_globs.unwrap_or(false),
+            glob_errors: RefCell::new(Vec::new()),
+            on_progress,
+            total_ops,
+        }
+    }
+
+    /// Resolve a config path against `base` (see [`resolve_path`]).
+    fn resolve(&self, base: &Path, path: &str) -> (PathBuf, String) {
+        resolve_path(
+            base,
+            &self.root_relative_dir,
+            self.config_relative_dir,
+            path,
+        )
+    }
+
+    /// Resolve the source of an entry: a path in the main worktree, or one
+    /// made available by the provider of a `<scheme>:` source.
+    ///
+    /// Provider sources are displayed as written.
+    fn resolve_source(&self, spec: &str) -> (EntrySource, String) {
+        let Some((provider, location)) = self.sources.split(spec) else {
+            let (path, display) = self.resolve(self.main_worktree, spec);
+            let source = EntrySource {
+                path,
+                provided: false,
+                default_target: Some(spec.to_string()),
+                error: None,
+            };
+            return (source, display);
+        };
+
+        let resolve = |path: &str| {
+            let (resolved, _) = self.resolve(self.main_worktree, path);
+            if escapes_containment(&resolved, self.containment_root.as_ref()) {
+                return Err(OperationError::SourceError {
+                    spec: spec.to_string(),
+                    message: "path escapes worktree boundary".to_string(),
+                });
+            }
+            Ok(resolved)
+        };
+        let (path, error) = match provider.provide(location, &resolve) {
+            Ok(path) => (path, None),
+            Err(e) => (PathBuf::new(), Some(e.to_string())),
+        };
+        let source = EntrySource {
+            path,
+            provided: true,
+            default_target: provider.default_target(location).map(str::to_string),
+            error,
+        };
+        (source, spec.to_string())
+    }
+
+    /// Why an entry reading from `source` is skipped, before looking at its
+    /// target.
+    fn source_skip_reason(&self, source: &EntrySource) -> Option<String> {
+        if source.error.is_some() {
+            source.error.clone()
+        } else if !source.provided
+            && escapes_containment(&source.path, self.containment_root.as_ref())
+        {
+            Some("path escapes worktree boundary".to_string())
+        } else if !source.provided && self.ignore.is_ignored(&source.path, source.path.is_dir()) {
+            Some("ignored".to_string())
+        } else if source.path.exists() {
+            None
+        } else {
+            Some("not found".to_string())
+        }
+    }
+
+    /// Resolve the source and target of a `copy` or `overwrite` entry.
+    ///
+    /// The target is the entry's `target`, or the same relative path as the
+    /// source (for provider sources, the path their provider names). Renamed
+    /// entries are displayed as `source -> target`.
+    fn resolve_entry(&self, entry: &OperationEntry) -> (EntrySource, PathBuf, String) {
+        let (mut source, source_display) = self.resolve_source(entry.path());
+        let Some(target_path) = entry.target() else {
+            let Some(default_target) = source.default_target.clone() else {
+                source
+                    .error
+                    .get_or_insert_with(|| "a target is required for this source".to_string());
+                return (source, self.target_worktree.to_path_buf(), source_display);
+            };
+            let (target, target_display) = self.resolve(self.target_worktree, &default_target);
+            if !source.provided {
+                return (source, target, source_display);
+            }
+            return (
+                source,
+                target,
+                format!("{source_display} -> {target_display}"),
+            );
+        };
+        let (target, target_display) = self.resolve(self.target_worktree, target_path);
+        (
+            source,
+            target,
+            format!("{source_display} -> {target_display}"),
+        )
+    }
+
+    /// Split a glob entry into the directory it is matched in, the prefix
+    /// shown before matches, and the pattern relative to that directory.
+    ///
+    /// Patterns with a leading `/` are matched in the config's repository
+    /// root, others in the config's directory.
+    fn glob_base<'p>(&self, pattern: &'p str) -> (PathBuf, PathBuf, &'p str) {
+        let spec = PathSpec::parse(pattern);
+        let prefix = spec.base_dir(&self.root_relative_dir, self.config_relative_dir);
+        (self.main_worktree.join(&prefix), prefix, spec.path())
+    }
+
+    /// Expand a glob `pattern` into the paths it matches.
+    ///
+    /// Uses `worktree_setup_glob::resolve_glob` for directory traversal
+    /// with `jwalk` + `globset`, the same rules for every entry kind:
+    ///
+    /// * Symlinks are neither followed nor matched
+    /// * Matched directories are not descended into
+    /// * Paths outside the worktree boundary are skipped (unless escape is allowed)
+    /// * Paths ignored by `.worktreeignore` are left out
+    /// * Paths already in `seen` are skipped, deduplicating across patterns
+    ///
+    /// Parts of the tree the walk could not read are recorded (see
+    /// [`Self::record_glob_errors`]) as `operation_type` operations, and so
+    /// is a walk that outlasts the glob timeout, which then matches
+    /// nothing.
+    fn expand_glob(
+        &self,
+        pattern: &str,
+        seen: &mut BTreeSet<PathBuf>,
+        operation_type: OperationType,
+    ) -> Vec<GlobMatch> {
+        let (search_dir, display_prefix, glob_pattern) = self.glob_base(pattern);
+
+        let options = GlobResolverOptions {
+            skip_symlinks: true,
+            enforce_containment: self.containment_root.is_some(),
+            ..Default::default()
+        };
+        // Fallback containment root (only used when enforce_containment is true)
+        let containment_root = self.containment_root.clone().unwrap_or_default();
+
+        let resolve = {
+            let glob_pattern = glob_pattern.to_string();
+            let search_dir = search_dir.clone();
+            let mut seen = seen.clone();
+            move || {
+                let mut errors = Vec::new();
+                let resolved = resolve_glob_with_errors(
+                    &glob_pattern,
+                    &search_dir,
+                    &containment_root,
+                    &mut seen,
+                    &options,
+                    &mut errors,
+                );
+                (resolved, seen, errors)
+            }
+        };
+        let outcome = match self.glob_timeout {
+            Some(timeout) => run_with_timeout(timeout, resolve),
+            None => Some(resolve()),
+        };
+        let Some((resolved, now_seen, errors)) = outcome else {
+            let timeout = self.glob_timeout.unwrap_or_default();
+            self.record_glob_errors(
+                pattern,
+                operation_type,
+                vec![GlobWalkError {
+                    path: Some(search_dir),
+                    message: format!("timed out after {}s", timeout.as_secs()),
+                }],
+            );
+            return Vec::new();
+        };
+        *seen = now_seen;
+        self.record_glob_errors(pattern, operation_type, errors);
+
+        let canonical_search = search_dir
+            .canonicalize()
+            .unwrap_or_else(|_| search_dir.clone());
+        let target_dir = self.target_worktree.join(&display_prefix);
+
+        resolved
+            .iter()
+            .filter_map(|entry| {
+                let rel_path = entry.canonical.strip_prefix(&canonical_search).ok()?;
+                if self
+                    .ignore
+                    .is_ignored(&entry.canonical, entry.canonical.is_dir())
+                {
+                    log::debug!("Ignored by .worktreeignore: {}", entry.display);
+                    return None;
+                }
+
+                let display_path = if display_prefix.as_os_str().is_empty() {
+                    rel_path.to_path_buf()
+                } else {
+                    display_prefix.join(rel_path)
+                };
+                Some(GlobMatch {
+                    source: entry.canonical.clone(),
+                    target: target_dir.join(rel_path),
+                    display_path: display_path.to_string_lossy().to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Remember the errors met while expanding `pattern`.
+    fn record_glob_errors(
+        &self,
+        pattern: &str,
+        operation_type: OperationType,
+        errors: Vec<GlobWalkError>,
+    ) {
+        self.glob_errors
+            .borrow_mut()
+            .extend(errors.into_iter().map(|error| UnsearchedGlob {
+                pattern: pattern.to_string(),
+                operation_type,
+                origin: self.origin(),
+                error,
+            }));
+    }
+
+    /// Fail with the first recorded glob error if `strictGlobs` is set.
+    fn check_strict_globs(&self) -> Result<(), OperationError> {
+        match self.glob_errors.borrow().first() {
+            Some(unsearched) if self.strict_globs => Err(OperationError::GlobError {
+                pattern: unsearched.pattern.clone(),
+                error: unsearched.error.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Turn the recorded glob errors into skipped operations, so they show
+    /// up in the plan.
+    fn glob_error_ops(&self) -> Vec<PlannedOperation> {
+        self.glob_errors
+            .take()
+            .into_iter()
+            .map(|unsearched| {
+                log::warn!(
+                    "Glob pattern {} failed: {}",
+                    unsearched.pattern,
+                    unsearched.error
+                );
+                let source = unsearched
+                    .error
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| self.main_worktree.to_path_buf());
+                let display_path = unsearched.error.path.as_ref().map_or_else(
+                    || unsearched.pattern.clone(),
+                    |path| {
+                        path.strip_prefix(self.main_worktree)
+                            .unwrap_or(path)
+                            .to_string_lossy()
+                            .to_string()
+                    },
+                );
+                PlannedOperation {
+                    target: self.target_worktree.join(&display_path),
+                    display_path,
+                    operation_type: unsearched.operation_type,
+                    source,
+                    file_count: 0,
+                    symlink_count: 0,
+                    byte_count: 0,
+                    filtered_count: 0,
+                    special_count: 0,
+                    template: None,
+                    is_directory: false,
+                    will_skip: true,
+                    skip_reason: Some(format!("glob error: {}", unsearched.error.message)),
+                    force_overwrite: false,
+                    ignore: None,
+                    order: 0,
+                    origin: unsearched.origin,
+                    hooks: None,
+                    ref_source: None,
+                    max_depth: None,
+                    modes: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Evaluate an entry condition, returning the skip reason if it fails.
+    fn unmet_condition(&self, condition: Option<&Condition>) -> Option<String> {
+        let condition = condition?;
+
+        if let Some(path) = &condition.unless_exists {
+            let (resolved, _) = self.resolve(self.target_worktree, path);
+            if resolved.exists() || resolved.is_symlink() {
+                return Some(format!("condition not met: unlessExists {path}"));
+            }
+        }
+
+        if let Some(var) = &condition.if_env
+            && std::env::var_os(var).is_none_or(|value| value.is_empty())
+        {
+            return Some(format!("condition not met: ifEnv {var}"));
+        }
+
+        if let Some(cmd) = &condition.if_command {
+            let succeeded = *self
+                .command_results
+                .borrow_mut()
+                .entry(cmd.clone())
+                .or_insert_with(|| run_condition_command(cmd, self.main_worktree));
+            if !succeeded {
+                return Some(format!("condition not met: ifCommand {cmd}"));
+            }
+        }
+
+        None
+    }
+}
+
+impl<F> PlanContext<'_, F> {
+    /// Attribute the operations planned from now on to entry `index` of
+    /// `field`, labelled `label` and running `hooks` around each.
+    fn begin_entry(
+        &self,
+        field: &str,
+        index: usize,
+        label: Option<&str>,
+        hooks: Option<&EntryHooks>,
+    ) {
+        self.entry.replace(Some(EntryOrigin {
+            config: self.config_path.to_string(),
+            field: field.to_string(),
+            index,
+            label: label.map(str::to_string),
+        }));
+        self.entry_hooks
+            .replace(hooks.filter(|hooks| !hooks.is_empty()).map(|hooks| {
+                Arc::new(OperationHooks {
+                    before: hooks.before.clone(),
+                    after: hooks.after.clone(),
+                    cwd: self.target_worktree.to_path_buf(),
+                })
+            }));
+    }
+
+    /// The entry being planned.
+    fn origin(&self) -> Option<EntryOrigin> {
+        self.entry.borrow().clone()
+    }
+
+    /// Hooks of the entry being planned.
+    fn hooks(&self) -> Option<Arc<OperationHooks>> {
+        self.entry_hooks.borrow().clone()
+    }
+}
+
+/// Run `f` on a worker thread, returning `None` if it has not finished
+/// within `timeout`.
+///
+/// A worker that outlasts the timeout (for example, one blocked listing a
+/// directory on a hung mount) is left to finish on its own.
+fn run_with_timeout<T, F>(timeout: Duration, f: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(timeout).ok()
+}
+
+/// A glob pattern that could not search part of the tree.
+struct UnsearchedGlob {
+    /// The pattern as written in the config.
+    pattern: String,
+    /// Kind of entry the pattern belongs to.
+    operation_type: OperationType,
+    /// Entry the pattern belongs to.
+    origin: Option<EntryOrigin>,
+    /// What could not be searched, and why.
+    error: GlobWalkError,
+}
+
+/// A path matched by a glob entry and where it goes in the target.
+struct GlobMatch {
+    /// Canonical path of the match in the main worktree.
+    source: PathBuf,
+    /// Matching path in the target worktree.
+    target: PathBuf,
+    /// Path shown to the user.
+    display_path: String,
+}
+
+/// Run an `ifCommand` condition through `sh -c` with output discarded.
+fn run_condition_command(cmd: &str, cwd: &Path) -> bool {
+    log::debug!("Evaluating condition command: {cmd}");
+    Command::new("sh")
+        .args(["-c", cmd])
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Counts known at planning time for an entry.
+///
+/// Directories start out empty and are filled in by [`count_directories`]
+/// once every entry has been resolved.
+fn planned_counts(source: &Path, is_dir: bool) -> EntryCounts {
+    if is_dir {
+        EntryCounts::default()
+    } else {
+        single_file(source)
+    }
+}
+
+/// Counts for a single (non-directory) file operation.
+fn single_file(source: &Path) -> EntryCounts {
+    EntryCounts {
+        files: 1,
+        bytes: file_size(source),
+        ..EntryCounts::default()
+    }
+}
+
+/// Size of the file at `path` in bytes (0 if it cannot be read).
+fn file_size(path: &Path) -> u64 {
+    path.metadata().map_or(0, |m| m.len())
+}
+
+/// The target worktree's path relative to the main worktree, when the
+/// target was created inside it.
+///
+/// Directory copies and unstaged replication would otherwise copy the new
+/// worktree into itself.
+#[must_use]
+pub fn nested_target(main_worktree: &Path, target_worktree: &Path) -> Option<PathBuf> {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let main = canonical(main_worktree);
+    let target = canonical(target_worktree);
+    target
+        .strip_prefix(&main)
+        .ok()
+        .filter(|rel| !rel.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+}
+
+/// Load the `.worktreeignore` files of `main_worktree`, also ignoring
+/// `target_worktree` when it is nested inside (see [`nested_target`]).
+#[must_use]
+pub fn load_worktree_ignore(main_worktree: &Path, target_worktree: &Path) -> WorktreeIgnore {
+    let mut ignore = WorktreeIgnore::load(main_worktree);
+    if let Some(nested) = nested_target(main_worktree, target_worktree) {
+        ignore.exclude_dir(&nested);
+    }
+    ignore
+}
+
 /// Plan all operations for a config with progress reporting.
 ///
 /// This is like `plan_operations` but reports progress during scanning,
@@ -166,6 +852,9 @@ struct PlanContext<'a, F> {
 /// - `path`: Path being scanned
 /// - `file_count`: Current file count (Some during directory scan, None for quick checks)
 ///
+/// Directories are counted in parallel after all entries are resolved, so
+/// the callback may be invoked from several threads at once.
+///
 /// # Arguments
 ///
 /// * `config` - The loaded configuration
@@ -177,6 +866,8 @@ struct PlanContext<'a, F> {
 /// # Errors
 ///
 /// * If file counting fails during directory scanning
+/// * If a glob pattern cannot search part of the tree and `strictGlobs`
+///   is set
 pub fn plan_operations_with_progress<F>(
     config: &LoadedConfig,
     main_worktree: &Path,
@@ -185,34 +876,23 @@ pub fn plan_operations_with_progress<F>(
     on_progress: &F,
 ) -> Result<Vec<PlannedOperation>, OperationError>
 where
-    F: Fn(usize, usize, &str, Option<u64>),
+    F: Fn(usize, usize, &str, Option<u64>) + Sync,
 {
-    let config_relative_dir = config
-        .config_dir
-        .strip_prefix(main_worktree)
-        .unwrap_or(&config.config_dir);
-
-    let containment_root = if options.allow_path_escape {
-        None
-    } else {
-        main_worktree.canonicalize().ok()
-    };
-
     let total_ops = config.config.symlinks.len()
         + config.config.copy.len()
         + config.config.overwrite.len()
         + config.config.copy_glob.len()
-        + config.config.templates.len();
+        + config.config.templates.len()
+        + config.config.copy_from_ref.len();
 
-    let ctx = PlanContext {
-        config_relative_dir,
+    let ctx = PlanContext::new(
+        config,
         main_worktree,
         target_worktree,
-        overwrite: options.overwrite_existing,
-        containment_root,
+        options,
         on_progress,
         total_ops,
-    };
+    );
 
     let mut current_op = 0usize;
     let mut operations = Vec::new();
@@ -238,142 +918,437 @@ where
         &mut current_op,
         &config.config.templates,
     ));
+    operations.extend(plan_ref_copy_ops(
+        &ctx,
+        &mut current_op,
+        &config.config.copy_from_ref,
+    ));
+
+    ctx.check_strict_globs()?;
+    operations.extend(ctx.glob_error_ops());
+
+    count_directories(&mut operations, &ctx, on_progress);
+    sort_by_order(&mut operations);
 
     Ok(operations)
 }
 
-/// Plan symlink operations.
-fn plan_symlink_ops<F>(
-    ctx: &PlanContext<'_, F>,
-    current_op: &mut usize,
-    symlinks: &[String],
+/// Sort operations by their entry's `order`, lower first.
+///
+/// The sort is stable, so operations with the same order keep the default
+/// sequence: symlinks, copies, overwrites, `copyGlob` matches, templates and
+/// `copyFromRef` files, each in the order listed, and configs in the order
+/// they were given.
+pub fn sort_by_order(operations: &mut [PlannedOperation]) {
+    operations.sort_by_key(|op| op.order);
+}
+
+/// Progress callback for the single-kind planners, which report nothing.
+type NoProgress = fn(usize, usize, &str, Option<u64>);
+
+/// Plan one kind of config entry, counting directories afterwards.
+fn plan_entries<S>(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+    total_ops: usize,
+    plan: S,
 ) -> Vec<PlannedOperation>
 where
-    F: Fn(usize, usize, &str, Option<u64>),
+    S: FnOnce(&PlanContext<'_, NoProgress>, &mut usize) -> Vec<PlannedOperation>,
 {
-    let mut operations = Vec::new();
+    let on_progress: NoProgress = |_, _, _, _| {};
+    let ctx = PlanContext::new(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        &on_progress,
+        total_ops,
+    );
+    let mut operations = plan(&ctx, &mut 0);
+    operations.extend(ctx.glob_error_ops());
+    count_directories(&mut operations, &ctx, &on_progress);
+    sort_by_order(&mut operations);
+    operations
+}
 
-    for symlink_path in symlinks {
-        *current_op += 1;
-        let (source, display_str) =
-            resolve_path(ctx.main_worktree, ctx.config_relative_dir, symlink_path);
-        let (target, _) = resolve_path(ctx.target_worktree, ctx.config_relative_dir, symlink_path);
+/// Plan only the `symlinks` entries of a config.
+///
+/// Takes the same arguments as [`plan_operations`] and returns the
+/// operations it would plan for `symlinks`, so callers can build their own
+/// pipelines from individual entry kinds. Glob errors are always planned
+/// as skipped operations, whatever `strictGlobs` says.
+#[must_use]
+pub fn plan_symlinks(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.symlinks;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_symlink_ops(ctx, current_op, entries),
+    )
+}
 
-        (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, None);
+/// Plan only the `copy` entries of a config (see [`plan_symlinks`]).
+#[must_use]
+pub fn plan_copies(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.copy;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_copy_ops(ctx, current_op, entries),
+    )
+}
 
-        let (will_skip, skip_reason, force) =
-            if escapes_containment(&source, ctx.containment_root.as_ref()) {
-                (
-                    true,
-                    Some("path escapes worktree boundary".to_string()),
-                    false,
-                )
-            } else if !source.exists() {
-                (true, Some("not found".to_string()), false)
-            } else if target.exists() || target.is_symlink() {
-                if ctx.overwrite {
-                    (false, None, true)
-                } else {
-                    (true, Some("exists".to_string()), false)
-                }
-            } else {
-                (false, None, false)
-            };
+/// Plan only the `overwrite` entries of a config (see [`plan_symlinks`]).
+#[must_use]
+pub fn plan_overwrites(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.overwrite;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_overwrite_ops(ctx, current_op, entries),
+    )
+}
 
-        operations.push(PlannedOperation {
-            display_path: display_str,
-            operation_type: OperationType::Symlink,
-            source,
-            target,
-            file_count: 0,
-            is_directory: false,
-            will_skip,
-            skip_reason,
-            force_overwrite: force,
-        });
-    }
+/// Plan only the `copyGlob` entries of a config (see [`plan_symlinks`]).
+#[must_use]
+pub fn plan_copy_globs(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.copy_glob;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_glob_ops(ctx, current_op, entries),
+    )
+}
 
-    operations
+/// Plan only the `templates` entries of a config (see [`plan_symlinks`]).
+#[must_use]
+pub fn plan_templates(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.templates;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_template_ops(ctx, current_op, entries),
+    )
 }
 
-/// Plan explicit copy operations.
-fn plan_copy_ops<F>(
-    ctx: &PlanContext<'_, F>,
-    current_op: &mut usize,
-    copies: &[String],
+/// Plan only the `copyFromRef` entries of a config (see [`plan_symlinks`]).
+#[must_use]
+pub fn plan_ref_copies(
+    config: &LoadedConfig,
+    main_worktree: &Path,
+    target_worktree: &Path,
+    options: &ApplyConfigOptions,
+) -> Vec<PlannedOperation> {
+    let entries = &config.config.copy_from_ref;
+    plan_entries(
+        config,
+        main_worktree,
+        target_worktree,
+        options,
+        entries.len(),
+        |ctx, current_op| plan_ref_copy_ops(ctx, current_op, entries),
+    )
+}
+
+/// Count the contents of every directory that will be copied, in parallel.
+///
+/// Walking large directories dominates planning, so it is deferred until
+/// all entries are resolved and then spread across threads. Operations keep
+/// their order. Progress reports the total number of entries found so far
+/// across all directories, together with the directory that found them.
+///
+/// Directories with a valid entry in the context's count cache are not
+/// walked; fresh counts are added to the cache. Depth-limited copies are
+/// always walked, since the cache holds full counts.
+fn count_directories<C, F>(
+    operations: &mut [PlannedOperation],
+    ctx: &PlanContext<'_, C>,
+    on_progress: &F,
+) where
+    F: Fn(usize, usize, &str, Option<u64>) + Sync,
+{
+    let found = AtomicU64::new(0);
+    let total_ops = ctx.total_ops;
+    let ignore = &ctx.ignore;
+    let cache = ctx.count_cache.as_deref();
+    let rules = cache
+        .map(|_| CountCache::rules_key(ignore))
+        .unwrap_or_default();
+
+    operations
+        .par_iter_mut()
+        .filter(|op| op.is_directory && !op.will_skip)
+        .for_each(|op| {
+            let cache = cache.filter(|_| op.max_depth.is_none());
+            let counts = if let Some(counts) = cache.and_then(|c| c.get(&op.source, &rules)) {
+                let total = found.fetch_add(counts.copied(), Ordering::Relaxed) + counts.copied();
+                on_progress(total_ops, total_ops, &op.display_path, Some(total));
+                counts
+            } else {
+                let last = Cell::new(0u64);
+                let counts = count_entries_with_progress(
+                    &op.source,
+                    op.max_depth,
+                    ignore_filter(Some(Arc::clone(ignore))),
+                    |n| {
+                        let delta = n.saturating_sub(last.replace(n));
+                        let total = found.fetch_add(delta, Ordering::Relaxed) + delta;
+                        on_progress(total_ops, total_ops, &op.display_path, Some(total));
+                    },
+                );
+                if let Some(cache) = cache {
+                    cache.insert(&op.source, &rules, counts);
+                }
+                counts
+            };
+            op.file_count = counts.copied();
+            op.symlink_count = counts.symlinks;
+            op.byte_count = counts.bytes;
+            op.filtered_count = counts.filtered;
+            op.special_count = counts.specials;
+        });
+}
+
+/// Plan symlink operations.
+///
+/// Glob patterns expand to one symlink per match (see
+/// [`PlanContext::expand_glob`]).
+fn plan_symlink_ops<F>(
+    ctx: &PlanContext<'_, F>,
+    current_op: &mut usize,
+    symlinks: &[OperationEntry],
 ) -> Vec<PlannedOperation>
 where
     F: Fn(usize, usize, &str, Option<u64>),
 {
     let mut operations = Vec::new();
+    let mut seen = BTreeSet::new();
 
-    for copy_path in copies {
+    for (index, entry) in symlinks.iter().enumerate() {
         *current_op += 1;
-        let (source, display_str) =
-            resolve_path(ctx.main_worktree, ctx.config_relative_dir, copy_path);
-        let (target, _) = resolve_path(ctx.target_worktree, ctx.config_relative_dir, copy_path);
+        ctx.begin_entry("symlinks", index, entry.label(), entry.hooks());
+        let symlink_path = entry.path();
+
+        if is_glob_pattern(symlink_path) {
+            (ctx.on_progress)(*current_op, ctx.total_ops, symlink_path, None);
+            if let Some(reason) = ctx.unmet_condition(entry.condition()) {
+                log::debug!("Skipping symlinks pattern {symlink_path}: {reason}");
+                continue;
+            }
+            for matched in ctx.expand_glob(symlink_path, &mut seen, OperationType::Symlink) {
+                let status = symlink_target_status(&matched.target, ctx.overwrite);
+                operations.push(symlink_op(
+                    ctx,
+                    matched.display_path,
+                    matched.source,
+                    matched.target,
+                    status,
+                    entry.order(),
+                ));
+            }
+            continue;
+        }
+
+        let (source, display_str) = ctx.resolve(ctx.main_worktree, symlink_path);
+        let (target, _) = ctx.resolve(ctx.target_worktree, symlink_path);
 
         (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, None);
 
-        let (will_skip, skip_reason, file_count, is_directory, op_type) =
-            if escapes_containment(&source, ctx.containment_root.as_ref()) {
-                (
-                    true,
-                    Some("path escapes worktree boundary".to_string()),
-                    0,
-                    false,
-                    OperationType::Copy,
-                )
-            } else if !source.exists() {
+        let unmet = ctx.unmet_condition(entry.condition());
+        let status = if unmet.is_some() {
+            (true, unmet, false)
+        } else if escapes_containment(&source, ctx.containment_root.as_ref()) {
+            (
+                true,
+                Some("path escapes worktree boundary".to_string()),
+                false,
+            )
+        } else if ctx.ignore.is_ignored(&source, source.is_dir()) {
+            (true, Some("ignored".to_string()), false)
+        } else if !source.exists() {
+            (true, Some("not found".to_string()), false)
+        } else {
+            symlink_target_status(&target, ctx.overwrite)
+        };
+
+        operations.push(symlink_op(
+            ctx,
+            display_str,
+            source,
+            target,
+            status,
+            entry.order(),
+        ));
+    }
+
+    operations
+}
+
+/// Determine the skip/force status for a symlink whose source exists.
+///
+/// An existing target is replaced with `overwrite` and skipped otherwise.
+fn symlink_target_status(target: &Path, overwrite: bool) -> (bool, Option<String>, bool) {
+    if target.exists() || target.is_symlink() {
+        if overwrite {
+            (false, None, true)
+        } else {
+            (true, Some("exists".to_string()), false)
+        }
+    } else {
+        (false, None, false)
+    }
+}
+
+/// Build a symlink operation from its `(will_skip, skip_reason, force)`
+/// status.
+fn symlink_op<F>(
+    ctx: &PlanContext<'_, F>,
+    display_path: String,
+    source: PathBuf,
+    target: PathBuf,
+    (will_skip, skip_reason, force): (bool, Option<String>, bool),
+    order: i32,
+) -> PlannedOperation {
+    PlannedOperation {
+        display_path,
+        operation_type: OperationType::Symlink,
+        source,
+        target,
+        file_count: 0,
+        symlink_count: 0,
+        byte_count: 0,
+        filtered_count: 0,
+        special_count: 0,
+        template: None,
+        is_directory: false,
+        will_skip,
+        skip_reason,
+        force_overwrite: force,
+        ignore: Some(Arc::clone(&ctx.ignore)),
+        order,
+        origin: ctx.origin(),
+        hooks: ctx.hooks(),
+        ref_source: None,
+        max_depth: None,
+        modes: None,
+    }
+}
+
+/// Plan explicit copy operations.
+fn plan_copy_ops<F>(
+    ctx: &PlanContext<'_, F>,
+    current_op: &mut usize,
+    copies: &[OperationEntry],
+) -> Vec<PlannedOperation>
+where
+    F: Fn(usize, usize, &str, Option<u64>),
+{
+    let mut operations = Vec::new();
+
+    for (index, entry) in copies.iter().enumerate() {
+        *current_op += 1;
+        ctx.begin_entry("copy", index, entry.label(), entry.hooks());
+        let (entry_source, target, display_str) = ctx.resolve_entry(entry);
+
+        (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, None);
+
+        let skip = ctx
+            .unmet_condition(entry.condition())
+            .or_else(|| ctx.source_skip_reason(&entry_source));
+        let source = entry_source.path;
+        let (will_skip, skip_reason, counts, is_directory, op_type) = if skip.is_some() {
+            (
+                true,
+                skip,
+                EntryCounts::default(),
+                false,
+                OperationType::Copy,
+            )
+        } else if target.exists() {
+            if ctx.overwrite {
+                let is_dir = source.is_dir();
+                let counts = planned_counts(&source, is_dir);
+                (false, None, counts, is_dir, OperationType::Overwrite)
+            } else {
                 (
                     true,
-                    Some("not found".to_string()),
-                    0,
+                    Some("exists".to_string()),
+                    EntryCounts::default(),
                     false,
                     OperationType::Copy,
                 )
-            } else if target.exists() {
-                if ctx.overwrite {
-                    let is_dir = source.is_dir();
-                    let count = if is_dir {
-                        count_files_with_progress(&source, |n| {
-                            (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, Some(n));
-                        })
-                    } else {
-                        1
-                    };
-                    (false, None, count, is_dir, OperationType::Overwrite)
-                } else {
-                    (
-                        true,
-                        Some("exists".to_string()),
-                        0,
-                        false,
-                        OperationType::Copy,
-                    )
-                }
-            } else {
-                let is_dir = source.is_dir();
-                let count = if is_dir {
-                    count_files_with_progress(&source, |n| {
-                        (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, Some(n));
-                    })
-                } else {
-                    1
-                };
-                (false, None, count, is_dir, OperationType::Copy)
-            };
+            }
+        } else {
+            let is_dir = source.is_dir();
+            let counts = planned_counts(&source, is_dir);
+            (false, None, counts, is_dir, OperationType::Copy)
+        };
 
         operations.push(PlannedOperation {
             display_path: display_str,
             operation_type: op_type,
             source,
             target,
-            file_count,
+            file_count: counts.copied(),
+            symlink_count: counts.symlinks,
+            byte_count: counts.bytes,
+            filtered_count: counts.filtered,
+            special_count: counts.specials,
+            template: None,
             is_directory,
             will_skip,
             skip_reason,
             force_overwrite: false,
+            ignore: Some(Arc::clone(&ctx.ignore)),
+            order: entry.order(),
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: None,
+            max_depth: entry.max_depth().or(ctx.max_depth),
+            modes: None,
         });
     }
 
@@ -381,63 +1356,111 @@ where
 }
 
 /// Plan overwrite operations.
+///
+/// Glob patterns expand to one overwrite per match (see
+/// [`PlanContext::expand_glob`]).
 fn plan_overwrite_ops<F>(
     ctx: &PlanContext<'_, F>,
     current_op: &mut usize,
-    overwrites: &[String],
+    overwrites: &[OperationEntry],
 ) -> Vec<PlannedOperation>
 where
     F: Fn(usize, usize, &str, Option<u64>),
 {
     let mut operations = Vec::new();
+    let mut seen = BTreeSet::new();
 
-    for overwrite_path in overwrites {
+    for (index, entry) in overwrites.iter().enumerate() {
         *current_op += 1;
-        let (source, display_str) =
-            resolve_path(ctx.main_worktree, ctx.config_relative_dir, overwrite_path);
-        let (target, _) =
-            resolve_path(ctx.target_worktree, ctx.config_relative_dir, overwrite_path);
+        ctx.begin_entry("overwrite", index, entry.label(), entry.hooks());
+        let overwrite_path = entry.path();
+
+        if is_glob_pattern(overwrite_path) {
+            (ctx.on_progress)(*current_op, ctx.total_ops, overwrite_path, None);
+            if let Some(reason) = ctx.unmet_condition(entry.condition()) {
+                log::debug!("Skipping overwrite pattern {overwrite_path}: {reason}");
+                continue;
+            }
+            if entry.target().is_some() {
+                log::warn!("Ignoring target of overwrite pattern {overwrite_path}");
+            }
+            for matched in ctx.expand_glob(overwrite_path, &mut seen, OperationType::Overwrite) {
+                operations.push(overwrite_op(
+                    ctx,
+                    matched.display_path,
+                    matched.source,
+                    matched.target,
+                    None,
+                    entry,
+                ));
+            }
+            continue;
+        }
+
+        let (source, target, display_str) = ctx.resolve_entry(entry);
 
         (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, None);
 
-        let (will_skip, skip_reason, file_count, is_directory) =
-            if escapes_containment(&source, ctx.containment_root.as_ref()) {
-                (
-                    true,
-                    Some("path escapes worktree boundary".to_string()),
-                    0,
-                    false,
-                )
-            } else if source.exists() {
-                let is_dir = source.is_dir();
-                let count = if is_dir {
-                    count_files_with_progress(&source, |n| {
-                        (ctx.on_progress)(*current_op, ctx.total_ops, &display_str, Some(n));
-                    })
-                } else {
-                    1
-                };
-                (false, None, count, is_dir)
-            } else {
-                (true, Some("not found".to_string()), 0, false)
-            };
+        let skip_reason = ctx
+            .unmet_condition(entry.condition())
+            .or_else(|| ctx.source_skip_reason(&source));
 
-        operations.push(PlannedOperation {
-            display_path: display_str,
-            operation_type: OperationType::Overwrite,
-            source,
+        operations.push(overwrite_op(
+            ctx,
+            display_str,
+            source.path,
             target,
-            file_count,
-            is_directory,
-            will_skip,
             skip_reason,
-            force_overwrite: false,
-        });
+            entry,
+        ));
     }
 
     operations
 }
 
+/// Build an overwrite operation, skipped if there is a `skip_reason`.
+///
+/// Directory contents are counted later by `count_directories`.
+fn overwrite_op<F>(
+    ctx: &PlanContext<'_, F>,
+    display_path: String,
+    source: PathBuf,
+    target: PathBuf,
+    skip_reason: Option<String>,
+    entry: &OperationEntry,
+) -> PlannedOperation {
+    let (counts, is_directory) = if skip_reason.is_none() {
+        let is_dir = source.is_dir();
+        (planned_counts(&source, is_dir), is_dir)
+    } else {
+        (EntryCounts::default(), false)
+    };
+
+    PlannedOperation {
+        display_path,
+        operation_type: OperationType::Overwrite,
+        source,
+        target,
+        file_count: counts.copied(),
+        symlink_count: counts.symlinks,
+        byte_count: counts.bytes,
+        filtered_count: counts.filtered,
+        special_count: counts.specials,
+        template: None,
+        is_directory,
+        will_skip: skip_reason.is_some(),
+        skip_reason,
+        force_overwrite: false,
+        ignore: Some(Arc::clone(&ctx.ignore)),
+        order: entry.order(),
+        origin: ctx.origin(),
+        hooks: ctx.hooks(),
+        ref_source: None,
+        max_depth: entry.max_depth().or(ctx.max_depth),
+        modes: None,
+    }
+}
+
 /// Determine the skip/overwrite status for a glob target path.
 fn glob_target_status(target: &Path, overwrite: bool) -> (bool, Option<String>, OperationType) {
     if target.exists() {
@@ -453,17 +1476,12 @@ fn glob_target_status(target: &Path, overwrite: bool) -> (bool, Option<String>,
 
 /// Plan glob copy operations.
 ///
-/// Uses `worktree_setup_glob::resolve_glob` for directory traversal with
-/// `jwalk` + `globset`, providing:
-///
-/// * Consistent symlink handling (`follow_links(false)`)
-/// * Directory pruning (matched directories are not descended into)
-/// * Containment enforcement (paths outside the worktree boundary are skipped)
-/// * Deduplication across patterns via a shared `seen` set
+/// Patterns expand to one copy per match (see
+/// [`PlanContext::expand_glob`]); exact paths share the deduplication.
 fn plan_glob_ops<F>(
     ctx: &PlanContext<'_, F>,
     current_op: &mut usize,
-    patterns: &[String],
+    patterns: &[OperationEntry],
 ) -> Vec<PlannedOperation>
 where
     F: Fn(usize, usize, &str, Option<u64>),
@@ -471,47 +1489,22 @@ where
     let mut operations = Vec::new();
     let mut seen = BTreeSet::new();
 
-    // Build options: skip symlinks always, enforce containment if we have a root
-    let options = GlobResolverOptions {
-        skip_symlinks: true,
-        enforce_containment: ctx.containment_root.is_some(),
-        ..Default::default()
-    };
-
-    // Fallback containment root (only used when enforce_containment is true)
-    let empty_root = PathBuf::new();
-    let containment_root = ctx.containment_root.as_ref().unwrap_or(&empty_root);
-
-    for pattern in patterns {
+    for (index, entry) in patterns.iter().enumerate() {
         *current_op += 1;
-
-        // Determine search directory and display prefix based on root-relative
-        // vs config-relative path
-        let (search_dir, display_prefix, glob_pattern) = pattern.strip_prefix('/').map_or_else(
-            || {
-                (
-                    ctx.main_worktree.join(ctx.config_relative_dir),
-                    ctx.config_relative_dir.to_path_buf(),
-                    pattern.as_str(),
-                )
-            },
-            |stripped| (ctx.main_worktree.to_path_buf(), PathBuf::new(), stripped),
-        );
+        ctx.begin_entry("copyGlob", index, entry.label(), entry.hooks());
+        let pattern = entry.path();
+        let (search_dir, display_prefix, glob_pattern) = ctx.glob_base(pattern);
 
         (ctx.on_progress)(*current_op, ctx.total_ops, pattern, None);
 
+        if let Some(reason) = ctx.unmet_condition(entry.condition()) {
+            log::debug!("Skipping copyGlob pattern {pattern}: {reason}");
+            continue;
+        }
+
+        let planned_before = operations.len();
         if is_glob_pattern(glob_pattern) {
-            plan_glob_pattern(
-                ctx,
-                &mut operations,
-                &mut seen,
-                &options,
-                containment_root,
-                pattern,
-                &search_dir,
-                &display_prefix,
-                glob_pattern,
-            );
+            plan_glob_pattern(ctx, &mut operations, &mut seen, pattern);
         } else {
             plan_glob_exact(
                 ctx,
@@ -523,6 +1516,9 @@ where
                 glob_pattern,
             );
         }
+        for op in &mut operations[planned_before..] {
+            op.order = entry.order();
+        }
     }
 
     operations
@@ -551,6 +1547,11 @@ fn plan_glob_exact<F>(
         return;
     }
 
+    if ctx.ignore.is_ignored(&source, source.is_dir()) {
+        log::debug!("copyGlob exact path is ignored by .worktreeignore, skipping: {pattern}");
+        return;
+    }
+
     // Dedup via canonical path
     if let Ok(canonical) = source.canonicalize()
         && !seen.insert(canonical)
@@ -559,13 +1560,7 @@ fn plan_glob_exact<F>(
     }
 
     let rel_path = glob_pattern;
-    let target = if pattern.starts_with('/') {
-        ctx.target_worktree.join(rel_path)
-    } else {
-        ctx.target_worktree
-            .join(ctx.config_relative_dir)
-            .join(rel_path)
-    };
+    let target = ctx.target_worktree.join(display_prefix).join(rel_path);
     let display_path = if display_prefix.as_os_str().is_empty() {
         PathBuf::from(rel_path)
     } else {
@@ -575,88 +1570,78 @@ fn plan_glob_exact<F>(
     let (will_skip, skip_reason, op_type) = glob_target_status(&target, ctx.overwrite);
 
     let is_directory = source.is_dir();
-    let file_count = if is_directory {
-        count_files(&source)
-    } else {
-        1
-    };
+    let counts = planned_counts(&source, is_directory);
 
     operations.push(PlannedOperation {
         display_path: display_path.to_string_lossy().to_string(),
         operation_type: op_type,
         source,
         target,
-        file_count,
+        file_count: counts.copied(),
+        symlink_count: counts.symlinks,
+        byte_count: counts.bytes,
+        filtered_count: counts.filtered,
+        special_count: counts.specials,
+        template: None,
         is_directory,
         will_skip,
         skip_reason,
         force_overwrite: false,
+        ignore: Some(Arc::clone(&ctx.ignore)),
+        order: 0,
+        origin: ctx.origin(),
+        hooks: ctx.hooks(),
+        ref_source: None,
+        max_depth: None,
+        modes: None,
     });
 }
 
-/// Plan a glob pattern within `plan_glob_ops` using `jwalk` + `globset`.
-#[allow(clippy::too_many_arguments)]
+/// Plan one copy per match of a glob pattern within `plan_glob_ops`.
 fn plan_glob_pattern<F>(
     ctx: &PlanContext<'_, F>,
     operations: &mut Vec<PlannedOperation>,
     seen: &mut BTreeSet<PathBuf>,
-    options: &GlobResolverOptions,
-    containment_root: &Path,
     pattern: &str,
-    search_dir: &Path,
-    display_prefix: &Path,
-    glob_pattern: &str,
 ) where
     F: Fn(usize, usize, &str, Option<u64>),
 {
-    let resolved = resolve_glob(glob_pattern, search_dir, containment_root, seen, options);
-
-    let canonical_search = search_dir
-        .canonicalize()
-        .unwrap_or_else(|_| search_dir.to_path_buf());
-
-    for entry in &resolved {
-        let Ok(rel_path) = entry.canonical.strip_prefix(&canonical_search) else {
-            continue;
-        };
-
-        let target = if pattern.starts_with('/') {
-            ctx.target_worktree.join(rel_path)
-        } else {
-            ctx.target_worktree
-                .join(ctx.config_relative_dir)
-                .join(rel_path)
-        };
-        let display_path = if display_prefix.as_os_str().is_empty() {
-            rel_path.to_path_buf()
-        } else {
-            display_prefix.join(rel_path)
-        };
-
-        let (will_skip, skip_reason, op_type) = glob_target_status(&target, ctx.overwrite);
+    for matched in ctx.expand_glob(pattern, seen, OperationType::CopyGlob) {
+        let (will_skip, skip_reason, op_type) = glob_target_status(&matched.target, ctx.overwrite);
 
-        let is_directory = entry.canonical.is_dir();
-        let file_count = if is_directory {
-            count_files(&entry.canonical)
-        } else {
-            1
-        };
+        let is_directory = matched.source.is_dir();
+        let counts = planned_counts(&matched.source, is_directory);
 
         operations.push(PlannedOperation {
-            display_path: display_path.to_string_lossy().to_string(),
+            display_path: matched.display_path,
             operation_type: op_type,
-            source: entry.canonical.clone(),
-            target,
-            file_count,
+            source: matched.source,
+            target: matched.target,
+            file_count: counts.copied(),
+            symlink_count: counts.symlinks,
+            byte_count: counts.bytes,
+            filtered_count: counts.filtered,
+            special_count: counts.specials,
+            template: None,
             is_directory,
             will_skip,
             skip_reason,
             force_overwrite: false,
+            ignore: Some(Arc::clone(&ctx.ignore)),
+            order: 0,
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: None,
+            max_depth: None,
+            modes: None,
         });
     }
 }
 
 /// Plan template operations.
+///
+/// A template whose source is a directory or glob pattern expands into one
+/// operation per file (see [`plan_template_expansion`]).
 fn plan_template_ops<F>(
     ctx: &PlanContext<'_, F>,
     current_op: &mut usize,
@@ -667,131 +1652,1808 @@ where
 {
     let mut operations = Vec::new();
 
-    for template in templates {
+    for (index, template) in templates.iter().enumerate() {
         *current_op += 1;
-        let (source, source_display) =
-            resolve_path(ctx.main_worktree, ctx.config_relative_dir, &template.source);
-        let (target, target_display) = resolve_path(
-            ctx.target_worktree,
-            ctx.config_relative_dir,
-            &template.target,
+        ctx.begin_entry(
+            "templates",
+            index,
+            template.label.as_deref(),
+            Some(&template.hooks),
         );
+        let (entry_source, source_display) = ctx.resolve_source(&template.source);
+        let (target, target_display) = ctx.resolve(ctx.target_worktree, &template.target);
         let display_path = format!("{source_display} -> {target_display}");
 
         (ctx.on_progress)(*current_op, ctx.total_ops, &display_path, None);
 
-        let (will_skip, skip_reason, op_type) =
-            if escapes_containment(&source, ctx.containment_root.as_ref()) {
-                (
-                    true,
-                    Some("path escapes worktree boundary".to_string()),
-                    OperationType::Template,
-                )
-            } else if !source.exists() {
-                (true, Some("not found".to_string()), OperationType::Template)
-            } else if target.exists() {
-                if ctx.overwrite {
-                    (false, None, OperationType::Overwrite)
-                } else {
-                    (true, Some("exists".to_string()), OperationType::Template)
-                }
-            } else {
-                (false, None, OperationType::Template)
-            };
+        let unmet = ctx.unmet_condition(Some(&template.condition));
+        let source = &entry_source.path;
+        let escapes =
+            !entry_source.provided && escapes_containment(source, ctx.containment_root.as_ref());
+        // Patterns are only expanded in the main worktree.
+        let is_pattern = !entry_source.provided && is_glob_pattern(&template.source);
+        let context = (template.engine != TemplateEngine::Copy).then(|| {
+            Arc::new(TemplateContext::new(
+                template.engine,
+                ctx.main_worktree,
+                ctx.target_worktree,
+            ))
+        });
+
+        if unmet.is_none()
+            && entry_source.error.is_none()
+            && !escapes
+            && (is_pattern || source.is_dir())
+        {
+            operations.extend(plan_template_expansion(
+                ctx,
+                template,
+                &entry_source,
+                &source_display,
+                &target,
+                &target_display,
+                context.as_ref(),
+            ));
+            continue;
+        }
+
+        let skip = unmet.or_else(|| ctx.source_skip_reason(&entry_source));
+        let (will_skip, skip_reason, op_type) = if skip.is_some() {
+            (true, skip, OperationType::Template)
+        } else {
+            template_target_status(&target, ctx.overwrite)
+        };
+
+        let byte_count = file_size(source);
 
         operations.push(PlannedOperation {
             display_path,
             operation_type: op_type,
-            source,
+            source: entry_source.path,
             target,
             file_count: 1,
+            symlink_count: 0,
+            byte_count,
+            filtered_count: 0,
+            special_count: 0,
+            template: context,
             is_directory: false,
             will_skip,
             skip_reason,
             force_overwrite: false,
+            ignore: Some(Arc::clone(&ctx.ignore)),
+            order: template.order,
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: None,
+            max_depth: None,
+            modes: TargetModes::of(template),
         });
     }
 
     operations
 }
 
-/// Plan unstaged file operations.
-///
-/// This is separate from `plan_operations` so the caller can show a different
-/// progress indicator for the git status check.
-///
-/// # Arguments
-///
-/// * `unstaged_files` - List of unstaged/untracked file paths from git
-/// * `main_worktree` - Path to the main worktree (source)
-/// * `target_worktree` - Path to the target worktree (destination)
-///
-/// # Returns
+/// Plan `copyFromRef` operations.
 ///
-/// Vector of planned operations for unstaged files
-#[must_use]
-pub fn plan_unstaged_operations(
-    unstaged_files: &[String],
-    main_worktree: &Path,
-    target_worktree: &Path,
-) -> Vec<PlannedOperation> {
+/// Each entry expands to one operation per file committed under its path
+/// at its ref, written to the same place below the entry's `target` (its
+/// `path` by default). Entries whose ref or path cannot be read are planned
+/// as a single skipped operation.
+fn plan_ref_copy_ops<F>(
+    ctx: &PlanContext<'_, F>,
+    current_op: &mut usize,
+    entries: &[RefCopyEntry],
+) -> Vec<PlannedOperation>
+where
+    F: Fn(usize, usize, &str, Option<u64>),
+{
     let mut operations = Vec::new();
 
-    for file in unstaged_files {
-        let source = main_worktree.join(file);
-        let target = target_worktree.join(file);
+    for (index, entry) in entries.iter().enumerate() {
+        *current_op += 1;
+        ctx.begin_entry(
+            "copyFromRef",
+            index,
+            entry.label.as_deref(),
+            Some(&entry.hooks),
+        );
+        let worktree_relative = |path: &str| {
+            PathSpec::parse(path).worktree_relative(&ctx.root_relative_dir, ctx.config_relative_dir)
+        };
+        let source_rel = worktree_relative(&entry.path);
+        let target_rel = entry
+            .target
+            .as_deref()
+            .map_or_else(|| source_rel.clone(), worktree_relative);
+        let repo_dir = ctx.main_worktree.join(&ctx.root_relative_dir);
+        // Paths in the ref's tree are relative to the config's repository,
+        // which is the submodule for configs inside one.
+        let tree_path = source_rel
+            .strip_prefix(&ctx.root_relative_dir)
+            .unwrap_or(&source_rel);
+        let entry_op = RefEntry {
+            entry,
+            repo_dir: &repo_dir,
+            tree_path,
+            target_rel: &target_rel,
+        };
 
-        // Only plan if source exists
-        if source.exists() {
-            operations.push(PlannedOperation {
-                display_path: file.clone(),
-                operation_type: OperationType::Unstaged,
-                source,
-                target,
-                file_count: 1,
-                is_directory: false,
-                will_skip: false,
-                skip_reason: None,
-                force_overwrite: false,
-            });
+        let display = entry_op.display(tree_path, &target_rel);
+        (ctx.on_progress)(*current_op, ctx.total_ops, &display, None);
+
+        let escapes = |path: &Path| path.components().any(|c| c == Component::ParentDir);
+        let escaping =
+            ctx.containment_root.is_some() && (escapes(&source_rel) || escapes(&target_rel));
+        let skip_reason = ctx
+            .unmet_condition(Some(&entry.condition))
+            .or_else(|| escaping.then(|| "path escapes worktree boundary".to_string()));
+        if let Some(reason) = skip_reason {
+            operations.push(entry_op.skipped(ctx, display, reason));
+            continue;
+        }
+
+        let listed = open_repo(&repo_dir).and_then(|repo| {
+            list_tree_files(&repo, &entry.git_ref, tree_path).map(|files| (repo, files))
+        });
+        match listed {
+            Ok((repo, files)) => {
+                for file in files {
+                    let unapplied_filter = (!entry.filters)
+                        .then(|| checkout_filter(&repo, &file.path))
+                        .flatten();
+                    operations.push(entry_op.file_op(ctx, file, unapplied_filter));
+                }
+            }
+            Err(e) => {
+                log::debug!("copyFromRef {display}: {e}");
+                let reason = format!("not found at {}", entry.git_ref);
+                operations.push(entry_op.skipped(ctx, display, reason));
+            }
         }
     }
 
     operations
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::BTreeSet;
-    use std::fs;
-    use tempfile::TempDir;
-    use worktree_setup_config::Config;
+/// A `copyFromRef` entry being planned, with its paths resolved.
+struct RefEntry<'a> {
+    entry: &'a RefCopyEntry,
+    /// Repository the ref is read from.
+    repo_dir: &'a Path,
+    /// The entry's path within the repository.
+    tree_path: &'a Path,
+    /// Where the entry's path is written, relative to the worktree.
+    target_rel: &'a Path,
+}
 
-    fn create_test_config(dir: &Path) -> LoadedConfig {
-        LoadedConfig {
-            config: Config {
-                description: "Test".to_string(),
-                symlinks: vec!["data".to_string()],
-                copy: vec!["config.json".to_string()],
-                overwrite: vec!["settings.json".to_string()],
-                ..Default::default()
-            },
-            config_path: dir.join("worktree.config.toml"),
+impl RefEntry<'_> {
+    /// `ref:path`, followed by the target when it differs.
+    fn display(&self, tree_path: &Path, target_rel: &Path) -> String {
+        let source = format!("{}:{}", self.entry.git_ref, tree_path.display());
+        if self.entry.target.is_some() {
+            format!("{source} -> {}", target_rel.display())
+        } else {
+            source
+        }
+    }
+
+    /// The operation writing one committed `file`.
+    fn file_op<F>(
+        &self,
+        ctx: &PlanContext<'_, F>,
+        file: TreeFile,
+        unapplied_filter: Option<String>,
+    ) -> PlannedOperation
+    where
+        F: Fn(usize, usize, &str, Option<u64>),
+    {
+        let below = file.path.strip_prefix(self.tree_path).unwrap_or(&file.path);
+        let target_rel = if below.as_os_str().is_empty() {
+            self.target_rel.to_path_buf()
+        } else {
+            self.target_rel.join(below)
+        };
+        let target = ctx.target_worktree.join(&target_rel);
+        let display_path = self.display(&file.path, &target_rel);
+
+        let (will_skip, skip_reason, operation_type) = if fs_entry_exists(&target) {
+            if ctx.overwrite {
+                (false, None, OperationType::Overwrite)
+            } else {
+                (true, Some("exists".to_string()), OperationType::Copy)
+            }
+        } else {
+            (false, None, OperationType::Copy)
+        };
+        let is_symlink = file.kind == TreeFileKind::Symlink;
+
+        PlannedOperation {
+            display_path,
+            operation_type,
+            source: self.repo_dir.join(&file.path),
+            target,
+            file_count: 1,
+            symlink_count: u64::from(is_symlink),
+            byte_count: if is_symlink { 0 } else { file.size },
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip,
+            skip_reason,
+            force_overwrite: false,
+            ignore: None,
+            order: self.entry.order,
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: Some(Arc::new(RefSource {
+                repo: self.repo_dir.to_path_buf(),
+                git_ref: self.entry.git_ref.clone(),
+                file,
+                filters: self.entry.filters,
+                unapplied_filter,
+            })),
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    /// A skipped operation standing for the whole entry.
+    fn skipped<F>(
+        &self,
+        ctx: &PlanContext<'_, F>,
+        display_path: String,
+        reason: String,
+    ) -> PlannedOperation
+    where
+        F: Fn(usize, usize, &str, Option<u64>),
+    {
+        PlannedOperation {
+            display_path,
+            operation_type: OperationType::Copy,
+            source: self.repo_dir.join(self.tree_path),
+            target: ctx.target_worktree.join(self.target_rel),
+            file_count: 0,
+            symlink_count: 0,
+            byte_count: 0,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip: true,
+            skip_reason: Some(reason),
+            force_overwrite: false,
+            ignore: None,
+            order: self.entry.order,
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+}
+
+/// Whether anything (including a dangling symlink) exists at `path`.
+fn fs_entry_exists(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok()
+}
+
+/// Determine the skip/overwrite status for a template target path.
+fn template_target_status(target: &Path, overwrite: bool) -> (bool, Option<String>, OperationType) {
+    if target.exists() {
+        if overwrite {
+            (false, None, OperationType::Overwrite)
+        } else {
+            (true, Some("exists".to_string()), OperationType::Template)
+        }
+    } else {
+        (false, None, OperationType::Template)
+    }
+}
+
+/// Expand a directory or glob template source into per-file operations.
+///
+/// Each file keeps its path relative to the source directory — or, for
+/// globs, relative to the pattern's literal prefix — beneath `target_dir`,
+/// with `strip_suffix` removed from the file name. Symlinks and files
+/// excluded by `.worktreeignore` are skipped silently, as with `copyGlob`.
+/// Every file is rendered with `context`, if any. Files of provider
+/// sources are displayed below `source_display`.
+fn plan_template_expansion<F>(
+    ctx: &PlanContext<'_, F>,
+    template: &worktree_setup_config::TemplateMapping,
+    source: &EntrySource,
+    source_display: &str,
+    target_dir: &Path,
+    target_display: &str,
+    context: Option<&Arc<TemplateContext>>,
+) -> Vec<PlannedOperation>
+where
+    F: Fn(usize, usize, &str, Option<u64>),
+{
+    let options = GlobResolverOptions {
+        skip_symlinks: true,
+        enforce_containment: ctx.containment_root.is_some() && !source.provided,
+        ..Default::default()
+    };
+    let empty_root = PathBuf::new();
+    let containment_root = ctx.containment_root.as_ref().unwrap_or(&empty_root);
+
+    let (base_dir, files) = if !source.provided && is_glob_pattern(&template.source) {
+        let (search_dir, _, glob_pattern) = ctx.glob_base(&template.source);
+        let base_dir = search_dir.join(literal_prefix(glob_pattern));
+
+        let mut seen = BTreeSet::new();
+        let mut errors = Vec::new();
+        let mut files = Vec::new();
+        for matched in resolve_glob_with_errors(
+            glob_pattern,
+            &search_dir,
+            containment_root,
+            &mut seen,
+            &options,
+            &mut errors,
+        ) {
+            if matched.canonical.is_dir() {
+                files.extend(list_files(&matched.canonical, containment_root, &options));
+            } else {
+                files.push(matched);
+            }
+        }
+        ctx.record_glob_errors(&template.source, OperationType::Template, errors);
+        (base_dir, files)
+    } else {
+        let files = list_files(&source.path, containment_root, &options);
+        (source.path.clone(), files)
+    };
+
+    let canonical_base = base_dir.canonicalize().unwrap_or(base_dir);
+    let canonical_main = ctx
+        .main_worktree
+        .canonicalize()
+        .unwrap_or_else(|_| ctx.main_worktree.to_path_buf());
+    let source_display = if source.provided {
+        PathBuf::from(source_display)
+    } else {
+        canonical_base
+            .strip_prefix(&canonical_main)
+            .unwrap_or(&canonical_base)
+            .to_path_buf()
+    };
+
+    let mut operations = Vec::new();
+
+    for file in files {
+        if ctx.ignore.is_ignored(&file.canonical, false) {
+            log::debug!("Ignored by .worktreeignore: {}", file.display);
+            continue;
+        }
+
+        let Ok(rel_path) = file.canonical.strip_prefix(&canonical_base) else {
+            continue;
+        };
+        let rel_target = strip_file_suffix(rel_path, template.strip_suffix.as_deref());
+        let target = target_dir.join(&rel_target);
+
+        let display_path = format!(
+            "{} -> {}",
+            source_display.join(rel_path).display(),
+            Path::new(target_display).join(&rel_target).display()
+        );
+
+        let (will_skip, skip_reason, op_type) = template_target_status(&target, ctx.overwrite);
+
+        let byte_count = file_size(&file.canonical);
+
+        operations.push(PlannedOperation {
+            display_path,
+            operation_type: op_type,
+            source: file.canonical,
+            target,
+            file_count: 1,
+            symlink_count: 0,
+            byte_count,
+            filtered_count: 0,
+            special_count: 0,
+            template: context.cloned(),
+            is_directory: false,
+            will_skip,
+            skip_reason,
+            force_overwrite: false,
+            ignore: Some(Arc::clone(&ctx.ignore)),
+            order: template.order,
+            origin: ctx.origin(),
+            hooks: ctx.hooks(),
+            ref_source: None,
+            max_depth: None,
+            modes: TargetModes::of(template),
+        });
+    }
+
+    operations
+}
+
+/// Remove `suffix` from the file name of `path`, if present.
+///
+/// The suffix is left alone when it is the whole file name, so a file
+/// literally named `.example` is not turned into an empty name.
+fn strip_file_suffix(path: &Path, suffix: Option<&str>) -> PathBuf {
+    let Some(suffix) = suffix.filter(|s| !s.is_empty()) else {
+        return path.to_path_buf();
+    };
+    let Some(name) = path.file_name() else {
+        return path.to_path_buf();
+    };
+    // Compared as bytes so names that are not valid UTF-8 still match.
+    match name.as_encoded_bytes().strip_suffix(suffix.as_bytes()) {
+        Some(stripped) if !stripped.is_empty() => {
+            // SAFETY: `stripped` comes from an `OsStr` and is split off right
+            // before a non-empty UTF-8 substring, which is a valid boundary.
+            path.with_file_name(unsafe { OsStr::from_encoded_bytes_unchecked(stripped) })
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Which unstaged/untracked files `copyUnstaged` replicates.
+///
+/// Each config enabling `copyUnstaged` allows the files matching its
+/// `unstagedInclude` globs, or, without any, the files under its directory
+/// ([`UnstagedScope::Configs`]) or the whole repository
+/// ([`UnstagedScope::Repo`]).
+#[derive(Debug, Clone, Default)]
+pub struct UnstagedFilter {
+    /// Whether some config allows every file.
+    all: bool,
+    /// Directories whose files are allowed, relative to the repository root.
+    dirs: Vec<PathBuf>,
+    /// Patterns matching allowed files or one of their parent directories.
+    patterns: Vec<glob::Pattern>,
+}
+
+impl UnstagedFilter {
+    /// Build the filter for the selected configs.
+    ///
+    /// `copy_unstaged_override` is `--unstaged`/`--no-unstaged`; configs
+    /// that end up not copying unstaged files allow nothing. Invalid
+    /// `unstagedInclude` patterns are skipped with a warning.
+    #[must_use]
+    pub fn for_configs(
+        configs: &[&LoadedConfig],
+        copy_unstaged_override: Option<bool>,
+        scope: UnstagedScope,
+    ) -> Self {
+        let mut filter = Self::default();
+        for config in configs {
+            if !copy_unstaged_override
+                .or(config.config.copy_unstaged)
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            let dir = Path::new(&config.relative_path)
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            if config.config.unstaged_include.is_empty() {
+                match scope {
+                    UnstagedScope::Repo => filter.all = true,
+                    UnstagedScope::Configs => filter.dirs.push(dir.to_path_buf()),
+                }
+                continue;
+            }
+            let prefix = if dir.as_os_str().is_empty() {
+                String::new()
+            } else {
+                format!("{}/", glob::Pattern::escape(&dir.to_string_lossy()))
+            };
+            for include in &config.config.unstaged_include {
+                match glob::Pattern::new(&format!("{prefix}{include}")) {
+                    Ok(pattern) => filter.patterns.push(pattern),
+                    Err(e) => log::warn!(
+                        "Ignoring unstagedInclude pattern '{include}' in {}: {e}",
+                        config.relative_path
+                    ),
+                }
+            }
+        }
+        filter
+    }
+
+    /// Whether `file` (relative to the repository root) may be replicated.
+    #[must_use]
+    pub fn allows(&self, file: &Path) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        self.all
+            || self.dirs.iter().any(|dir| file.starts_with(dir))
+            || file.ancestors().any(|path| {
+                self.patterns
+                    .iter()
+                    .any(|pattern| pattern.matches_path_with(path, options))
+            })
+    }
+}
+
+/// Plan unstaged file operations.
+///
+/// This is separate from `plan_operations` so the caller can show a different
+/// progress indicator for the git status check.
+///
+/// # Arguments
+///
+/// * `unstaged_files` - List of unstaged/untracked file paths from git
+/// * `main_worktree` - Path to the main worktree (source)
+/// * `target_worktree` - Path to the target worktree (destination)
+/// * `ignore` - `.worktreeignore` rules; matching files are left out
+///
+/// # Returns
+///
+/// Vector of planned operations for unstaged files
+#[must_use]
+pub fn plan_unstaged_operations(
+    unstaged_files: &[PathBuf],
+    main_worktree: &Path,
+    target_worktree: &Path,
+    ignore: &WorktreeIgnore,
+) -> Vec<PlannedOperation> {
+    let mut operations = Vec::new();
+
+    for file in unstaged_files {
+        if ignore.is_ignored(file, false) {
+            log::debug!("Ignored by .worktreeignore: {}", file.display());
+            continue;
+        }
+
+        let source = main_worktree.join(file);
+        let target = target_worktree.join(file);
+
+        // Only plan if source exists
+        if source.exists() {
+            let byte_count = file_size(&source);
+            operations.push(PlannedOperation {
+                display_path: file.to_string_lossy().to_string(),
+                operation_type: OperationType::Unstaged,
+                source,
+                target,
+                file_count: 1,
+                symlink_count: 0,
+                byte_count,
+                filtered_count: 0,
+                special_count: 0,
+                template: None,
+                is_directory: false,
+                will_skip: false,
+                skip_reason: None,
+                force_overwrite: false,
+                ignore: None,
+                order: 0,
+                origin: None,
+                hooks: None,
+                ref_source: None,
+                max_depth: None,
+                modes: None,
+            });
+        }
+    }
+
+    operations
+}
+
+/// Plan copying the files of an unpacked overlay archive (as written by
+/// `worktree-setup export`) into a worktree.
+///
+/// Each file becomes its own operation, so a file already in the worktree
+/// is skipped as `"exists"` (or replaced when `overwrite` is set) without
+/// affecting the rest of its directory. Operations are sorted by path.
+///
+/// # Arguments
+///
+/// * `overlay` - Directory the overlay was unpacked into
+/// * `target_worktree` - Path to the target worktree (destination)
+/// * `overwrite` - Replace files that already exist in the worktree
+///
+/// # Errors
+///
+/// * If a directory of the overlay cannot be read
+/// * If the overlay contains a symlink; `export` never writes one, and
+///   copying it would follow the link to wherever it points
+pub fn plan_overlay_operations(
+    overlay: &Path,
+    target_worktree: &Path,
+    overwrite: bool,
+) -> Result<Vec<PlannedOperation>, OperationError> {
+    let mut files = Vec::new();
+    collect_overlay_files(overlay, Path::new(""), &mut files)?;
+    files.sort();
+
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let source = overlay.join(&file);
+            let target = target_worktree.join(&file);
+            let (will_skip, skip_reason, operation_type) = glob_target_status(&target, overwrite);
+            let operation_type = match operation_type {
+                OperationType::CopyGlob => OperationType::Copy,
+                other => other,
+            };
+            PlannedOperation {
+                display_path: file.to_string_lossy().to_string(),
+                operation_type,
+                byte_count: file_size(&source),
+                source,
+                target,
+                file_count: 1,
+                symlink_count: 0,
+                filtered_count: 0,
+                special_count: 0,
+                template: None,
+                is_directory: false,
+                will_skip,
+                skip_reason,
+                force_overwrite: false,
+                ignore: None,
+                order: 0,
+                origin: None,
+                hooks: None,
+                ref_source: None,
+                max_depth: None,
+                modes: None,
+            }
+        })
+        .collect())
+}
+
+/// Add the paths of the files below `dir` (relative to the overlay root)
+/// to `files`; `relative` is the path of `dir` itself.
+fn collect_overlay_files(
+    dir: &Path,
+    relative: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), OperationError> {
+    let io_error = |source| OperationError::IoError {
+        path: dir.to_path_buf(),
+        source,
+    };
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let path = relative.join(entry.file_name());
+        let file_type = entry.file_type().map_err(io_error)?;
+        if file_type.is_symlink() {
+            return Err(OperationError::IoError {
+                path: entry.path(),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "overlay archives must not contain symlinks",
+                ),
+            });
+        }
+        if file_type.is_dir() {
+            collect_overlay_files(&entry.path(), &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Plan copying the hooks directory named by `core.hooksPath`.
+///
+/// A relative `core.hooksPath` resolves against each worktree's root, so a
+/// hooks directory that is not committed (e.g. husky's generated `.husky/_`)
+/// is missing in new worktrees and hooks silently stop running. Returns
+/// `None` when nothing needs to be copied: the path is absolute (shared by
+/// every worktree already), escapes the worktree, or does not exist in the
+/// main worktree.
+///
+/// # Arguments
+///
+/// * `hooks_path` - The configured `core.hooksPath` value
+/// * `main_worktree` - Path to the main worktree (source)
+/// * `target_worktree` - Path to the target worktree (destination)
+#[must_use]
+pub fn plan_hooks_operation(
+    hooks_path: &str,
+    main_worktree: &Path,
+    target_worktree: &Path,
+) -> Option<PlannedOperation> {
+    let relative = Path::new(hooks_path);
+    if relative.is_absolute()
+        || hooks_path.starts_with('~')
+        || relative
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        log::debug!("core.hooksPath {hooks_path} is shared by all worktrees");
+        return None;
+    }
+
+    let source = main_worktree.join(relative);
+    if !source.is_dir() {
+        log::debug!("Hooks directory {} not found", source.display());
+        return None;
+    }
+
+    let target = target_worktree.join(relative);
+    let exists = target.exists();
+    let counts = if exists {
+        EntryCounts::default()
+    } else {
+        count_entries_with_progress(&source, None, |_, _| true, |_| {})
+    };
+
+    Some(PlannedOperation {
+        display_path: hooks_path.trim_end_matches('/').to_string(),
+        operation_type: OperationType::Copy,
+        source,
+        target,
+        file_count: counts.copied(),
+        symlink_count: counts.symlinks,
+        byte_count: counts.bytes,
+        filtered_count: 0,
+        special_count: 0,
+        template: None,
+        is_directory: true,
+        will_skip: exists,
+        skip_reason: exists.then(|| "exists".to_string()),
+        force_overwrite: false,
+        ignore: None,
+        order: 0,
+        origin: None,
+        hooks: None,
+        ref_source: None,
+        max_depth: None,
+        modes: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use tempfile::TempDir;
+    use worktree_setup_config::Config;
+
+    fn create_test_config(dir: &Path) -> LoadedConfig {
+        LoadedConfig {
+            config: Config {
+                description: "Test".to_string(),
+                symlinks: vec!["data".into()],
+                copy: vec!["config.json".into()],
+                overwrite: vec!["settings.json".into()],
+                ..Default::default()
+            },
+            config_path: dir.join("worktree.config.toml"),
             config_dir: dir.to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         }
     }
 
     #[test]
-    fn test_plan_operations_basic() {
+    fn test_plan_operations_basic() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create source files
+        fs::create_dir_all(main_dir.path().join("data")).unwrap();
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("settings.json"), "{}").unwrap();
+
+        let config = create_test_config(main_dir.path());
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[0].operation_type, OperationType::Symlink);
+        assert_eq!(ops[1].operation_type, OperationType::Copy);
+        assert_eq!(ops[2].operation_type, OperationType::Overwrite);
+    }
+
+    #[test]
+    fn test_plan_operations_records_entry_origin() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(main_dir.path().join("data")).unwrap();
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("a.env"), "A=1").unwrap();
+        fs::write(main_dir.path().join("b.env"), "B=1").unwrap();
+
+        let mut config = create_test_config(main_dir.path());
+        config.config.copy = vec!["missing.json".into(), "config.json".into()];
+        config.config.overwrite.clear();
+        config.config.copy_glob = vec!["*.env".into()];
+        config.relative_path = "apps/web/worktree.config.toml".to_string();
+
+        let ops = plan_operations(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+        let origins: Vec<(&str, &str, usize)> = ops
+            .iter()
+            .map(|op| {
+                let origin = op.origin.as_ref().unwrap();
+                assert_eq!(origin.config, "apps/web/worktree.config.toml");
+                (
+                    op.display_path.as_str(),
+                    origin.field.as_str(),
+                    origin.index,
+                )
+            })
+            .collect();
+        assert_eq!(
+            origins,
+            [
+                ("data", "symlinks", 0),
+                ("missing.json", "copy", 0),
+                ("config.json", "copy", 1),
+                ("a.env", "copyGlob", 0),
+                ("b.env", "copyGlob", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_single_entry_kinds() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        fs::create_dir_all(main_dir.path().join("data")).unwrap();
+        fs::write(main_dir.path().join("data/a.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("settings.json"), "{}").unwrap();
+
+        let config = create_test_config(main_dir.path());
+        let options = ApplyConfigOptions::default();
+        let (main, target) = (main_dir.path(), target_dir.path());
+
+        let symlinks = plan_symlinks(&config, main, target, &options);
+        assert_eq!(symlinks.len(), 1);
+        assert_eq!(symlinks[0].operation_type, OperationType::Symlink);
+
+        let copies = plan_copies(&config, main, target, &options);
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies[0].display_path, "config.json");
+
+        let overwrites = plan_overwrites(&config, main, target, &options);
+        assert_eq!(overwrites[0].operation_type, OperationType::Overwrite);
+
+        assert!(plan_copy_globs(&config, main, target, &options).is_empty());
+        assert!(plan_templates(&config, main, target, &options).is_empty());
+    }
+
+    #[test]
+    fn test_plan_operations_skip_existing() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create source and target files
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(target_dir.path().join("config.json"), "existing").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["config.json".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].will_skip);
+        assert_eq!(ops[0].skip_reason, Some("exists".to_string()));
+    }
+
+    #[test]
+    fn test_plan_operations_directory_file_count() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create a directory with files
+        let data_dir = main_dir.path().join("data");
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("file1.txt"), "1").unwrap();
+        fs::write(data_dir.join("file2.txt"), "2").unwrap();
+        fs::create_dir(data_dir.join("subdir")).unwrap();
+        fs::write(data_dir.join("subdir/file3.txt"), "3").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["data".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].is_directory);
+        assert_eq!(ops[0].file_count, 3);
+    }
+
+    #[test]
+    fn test_plan_operations_with_progress_callback() {
+        use std::sync::Mutex;
+
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create source files
+        fs::create_dir_all(main_dir.path().join("data")).unwrap();
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                symlinks: vec!["data".into()],
+                copy: vec!["config.json".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let progress_calls = Mutex::new(Vec::new());
+        let ops = plan_operations_with_progress(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &options,
+            &|current, total, path, _file_count| {
+                progress_calls
+                    .lock()
+                    .unwrap()
+                    .push((current, total, path.to_string()));
+            },
+        )
+        .unwrap();
+
+        let calls = progress_calls.into_inner().unwrap();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0], (1, 2, "data".to_string()));
+        assert_eq!(calls[1], (2, 2, "config.json".to_string()));
+    }
+
+    #[test]
+    fn test_plan_counts_directories_in_order() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let mut copy = Vec::new();
+        for i in 0..8u64 {
+            let dir = main_dir.path().join(format!("dir{i}"));
+            fs::create_dir_all(&dir).unwrap();
+            for j in 0..=i {
+                fs::write(dir.join(format!("f{j}")), "x").unwrap();
+            }
+            copy.push(format!("dir{i}").into());
+        }
+
+        let config = LoadedConfig {
+            config: Config {
+                copy,
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let ops = plan_operations(
+            &config,
+            main_dir.path(),
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
+
+        let planned: Vec<(String, u64, u64)> = ops
+            .iter()
+            .map(|op| (op.display_path.clone(), op.file_count, op.byte_count))
+            .collect();
+        let expected: Vec<(String, u64, u64)> = (0..8u64)
+            .map(|i| (format!("dir{i}"), i + 1, i + 1))
+            .collect();
+        assert_eq!(planned, expected);
+    }
+
+    #[test]
+    fn test_plan_hooks_operation() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(main_dir.path().join(".husky/_")).unwrap();
+        fs::write(main_dir.path().join(".husky/_/pre-commit"), "#!/bin/sh\n").unwrap();
+
+        let op = plan_hooks_operation(".husky/_", main_dir.path(), target_dir.path()).unwrap();
+        assert_eq!(op.target, target_dir.path().join(".husky/_"));
+        assert_eq!(op.file_count, 1);
+        assert!(!op.will_skip);
+
+        fs::create_dir_all(target_dir.path().join(".husky/_")).unwrap();
+        let op = plan_hooks_operation(".husky/_", main_dir.path(), target_dir.path()).unwrap();
+        assert!(op.will_skip);
+
+        assert!(plan_hooks_operation("/etc/hooks", main_dir.path(), target_dir.path()).is_none());
+        assert!(plan_hooks_operation("../hooks", main_dir.path(), target_dir.path()).is_none());
+        assert!(plan_hooks_operation("missing", main_dir.path(), target_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_unstaged_filter() {
+        let loaded = |relative_path: &str, copy_unstaged, include: &[&str]| LoadedConfig {
+            config: Config {
+                copy_unstaged,
+                unstaged_include: include.iter().map(ToString::to_string).collect(),
+                ..Default::default()
+            },
+            config_path: PathBuf::from(relative_path),
+            config_dir: PathBuf::new(),
+            relative_path: relative_path.to_string(),
+        };
+        let web = loaded("apps/web/worktree.config.toml", Some(true), &[]);
+        let api = loaded(
+            "apps/api/worktree.config.toml",
+            Some(true),
+            &["*.env", "notes"],
+        );
+        let docs = loaded("docs/worktree.config.toml", None, &[]);
+        let configs = [&web, &api, &docs];
+
+        let scoped = UnstagedFilter::for_configs(&configs, None, UnstagedScope::Configs);
+        assert!(scoped.allows(Path::new("apps/web/src/scratch.ts")));
+        assert!(scoped.allows(Path::new("apps/api/.env")));
+        assert!(scoped.allows(Path::new("apps/api/notes/todo.md")));
+        assert!(!scoped.allows(Path::new("apps/api/src/main.rs")));
+        assert!(!scoped.allows(Path::new("apps/api/src/.env")));
+        assert!(!scoped.allows(Path::new("apps/webapp/x")));
+        assert!(!scoped.allows(Path::new("docs/draft.md")));
+
+        let repo = UnstagedFilter::for_configs(&configs, None, UnstagedScope::Repo);
+        assert!(repo.allows(Path::new("docs/draft.md")));
+
+        let only_api = UnstagedFilter::for_configs(&[&api, &docs], None, UnstagedScope::Repo);
+        assert!(!only_api.allows(Path::new("docs/draft.md")));
+        let forced = UnstagedFilter::for_configs(&[&api, &docs], Some(true), UnstagedScope::Repo);
+        assert!(forced.allows(Path::new("docs/draft.md")));
+    }
+
+    #[test]
+    fn test_plan_unstaged_operations() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create source files
+        fs::write(main_dir.path().join("modified.txt"), "content").unwrap();
+        fs::write(main_dir.path().join("untracked.txt"), "content").unwrap();
+
+        let unstaged = vec![
+            PathBuf::from("modified.txt"),
+            PathBuf::from("untracked.txt"),
+        ];
+        let ops = plan_unstaged_operations(
+            &unstaged,
+            main_dir.path(),
+            target_dir.path(),
+            &WorktreeIgnore::default(),
+        );
+
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].operation_type, OperationType::Unstaged);
+        assert_eq!(ops[1].operation_type, OperationType::Unstaged);
+    }
+
+    #[test]
+    fn test_plan_overlay_operations() {
+        let overlay = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(overlay.path().join("config/nested")).unwrap();
+        fs::write(overlay.path().join(".env"), "A=1").unwrap();
+        fs::write(overlay.path().join("config/app.json"), "{}").unwrap();
+        fs::write(overlay.path().join("config/nested/db.json"), "{}").unwrap();
+        fs::create_dir_all(target_dir.path().join("config")).unwrap();
+        fs::write(target_dir.path().join("config/app.json"), "old").unwrap();
+
+        let ops = plan_overlay_operations(overlay.path(), target_dir.path(), false).unwrap();
+        let paths: Vec<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
+        assert_eq!(paths, [".env", "config/app.json", "config/nested/db.json"]);
+        assert_eq!(ops[0].operation_type, OperationType::Copy);
+        assert_eq!(ops[0].target, target_dir.path().join(".env"));
+        assert!(ops[1].will_skip);
+        assert_eq!(ops[1].skip_reason.as_deref(), Some("exists"));
+        assert!(!ops[2].will_skip);
+
+        let ops = plan_overlay_operations(overlay.path(), target_dir.path(), true).unwrap();
+        assert!(!ops[1].will_skip);
+        assert_eq!(ops[1].operation_type, OperationType::Overwrite);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plan_overlay_operations_rejects_symlinks() {
+        let overlay = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let secret = TempDir::new().unwrap();
+        fs::write(secret.path().join("id_rsa"), "private").unwrap();
+        fs::create_dir_all(overlay.path().join("config")).unwrap();
+        fs::write(overlay.path().join(".env"), "A=1").unwrap();
+        std::os::unix::fs::symlink(
+            secret.path().join("id_rsa"),
+            overlay.path().join("config/key"),
+        )
+        .unwrap();
+
+        let err = plan_overlay_operations(overlay.path(), target_dir.path(), false).unwrap_err();
+        assert!(
+            matches!(&err, OperationError::IoError { path, .. } if path.ends_with("config/key")),
+            "{err}"
+        );
+        assert!(!target_dir.path().join("config/key").exists());
+    }
+
+    #[test]
+    fn test_plan_operations_repo_root_relative_paths() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create repo structure:
+        // main_dir/
+        //   .nix/
+        //     flake.nix
+        //   .envrc
+        //   apps/
+        //     myapp/
+        //       worktree.config.toml (config is here)
+
+        // Create root-level files
+        fs::create_dir_all(main_dir.path().join(".nix")).unwrap();
+        fs::write(main_dir.path().join(".nix/flake.nix"), "{}").unwrap();
+        fs::write(main_dir.path().join(".envrc"), "use flake").unwrap();
+
+        // Create app directory structure
+        let app_dir = main_dir.path().join("apps/myapp");
+        fs::create_dir_all(&app_dir).unwrap();
+
+        // Config in subdirectory referencing root files with /
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["/.nix".into(), "/.envrc".into()],
+                ..Default::default()
+            },
+            config_path: app_dir.join("worktree.config.toml"),
+            config_dir: app_dir.clone(),
+            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 2);
+
+        // Check .nix directory
+        assert_eq!(ops[0].display_path, ".nix");
+        assert_eq!(ops[0].source, main_dir.path().join(".nix"));
+        assert_eq!(ops[0].target, target_dir.path().join(".nix"));
+        assert!(ops[0].is_directory);
+        assert!(!ops[0].will_skip);
+
+        // Check .envrc file
+        assert_eq!(ops[1].display_path, ".envrc");
+        assert_eq!(ops[1].source, main_dir.path().join(".envrc"));
+        assert_eq!(ops[1].target, target_dir.path().join(".envrc"));
+        assert!(!ops[1].is_directory);
+        assert!(!ops[1].will_skip);
+    }
+
+    #[test]
+    fn test_plan_operations_submodule_root_relative_paths() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Submodule at libs/sub (marked by a `.git` file) with its config in
+        // a subdirectory; `/` paths resolve against the submodule root.
+        let sub_dir = main_dir.path().join("libs/sub");
+        let config_dir = sub_dir.join("tools");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(sub_dir.join(".git"), "gitdir: ../../.git/modules/sub").unwrap();
+        fs::write(sub_dir.join(".env"), "A=1").unwrap();
+        fs::create_dir_all(sub_dir.join("data")).unwrap();
+        fs::write(sub_dir.join("data/seed.json"), "{}").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["/.env".into()],
+                copy_glob: vec!["/data/*.json".into()],
+                ..Default::default()
+            },
+            config_path: config_dir.join("worktree.config.toml"),
+            config_dir: config_dir.clone(),
+            relative_path: "libs/sub/tools/worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].display_path, "libs/sub/.env");
+        assert_eq!(ops[0].source, sub_dir.join(".env"));
+        assert_eq!(ops[0].target, target_dir.path().join("libs/sub/.env"));
+        assert_eq!(ops[1].display_path, "libs/sub/data/seed.json");
+        assert_eq!(
+            ops[1].target,
+            target_dir.path().join("libs/sub/data/seed.json")
+        );
+    }
+
+    #[test]
+    fn test_plan_operations_mixed_paths() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create repo structure with both root and app-level files
+        fs::write(main_dir.path().join(".envrc"), "use flake").unwrap();
+
+        let app_dir = main_dir.path().join("apps/myapp");
+        fs::create_dir_all(&app_dir).unwrap();
+        fs::write(app_dir.join("local.config"), "app config").unwrap();
+
+        // Config with mixed paths: one root-relative, one config-relative
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec![
+                    "/.envrc".into(),      // root-relative
+                    "local.config".into(), // config-relative
+                ],
+                ..Default::default()
+            },
+            config_path: app_dir.join("worktree.config.toml"),
+            config_dir: app_dir.clone(),
+            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 2);
+
+        // Root-relative path: /.envrc -> .envrc
+        assert_eq!(ops[0].display_path, ".envrc");
+        assert_eq!(ops[0].source, main_dir.path().join(".envrc"));
+        assert_eq!(ops[0].target, target_dir.path().join(".envrc"));
+
+        // Config-relative path: local.config -> apps/myapp/local.config
+        assert_eq!(ops[1].display_path, "apps/myapp/local.config");
+        assert_eq!(ops[1].source, app_dir.join("local.config"));
+        assert_eq!(
+            ops[1].target,
+            target_dir.path().join("apps/myapp/local.config")
+        );
+    }
+
+    #[test]
+    fn test_plan_operations_template_with_root_paths() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create template at root
+        fs::write(main_dir.path().join(".env.template"), "KEY=value").unwrap();
+
+        let app_dir = main_dir.path().join("apps/myapp");
+        fs::create_dir_all(&app_dir).unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                templates: vec![worktree_setup_config::TemplateMapping {
+                    source: "/.env.template".to_string(), // root-relative source
+                    target: ".env.local".to_string(),     // config-relative target
+                    strip_suffix: None,
+                    engine: worktree_setup_config::TemplateEngine::default(),
+                    mode: None,
+                    dir_mode: None,
+                    label: None,
+                    order: 0,
+                    condition: worktree_setup_config::Condition::default(),
+                    hooks: worktree_setup_config::EntryHooks::default(),
+                }],
+                ..Default::default()
+            },
+            config_path: app_dir.join("worktree.config.toml"),
+            config_dir: app_dir.clone(),
+            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].operation_type, OperationType::Template);
+        assert_eq!(
+            ops[0].display_path,
+            ".env.template -> apps/myapp/.env.local"
+        );
+        assert_eq!(ops[0].source, main_dir.path().join(".env.template"));
+        assert_eq!(
+            ops[0].target,
+            target_dir.path().join("apps/myapp/.env.local")
+        );
+    }
+
+    #[test]
+    fn test_containment_symlink_escapes_boundary() {
+        let root = TempDir::new().unwrap();
+        let main_dir = root.path().join("main");
+        let outer_dir = root.path().join("outer");
+        let target_dir = root.path().join("target");
+        fs::create_dir_all(&main_dir).unwrap();
+        fs::create_dir_all(&outer_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        // Create a file outside the main worktree
+        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                symlinks: vec!["../outer/secret.txt".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.join("worktree.config.toml"),
+            config_dir: main_dir.clone(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        // Default: containment enforced (allow_path_escape = false)
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+
+        // The operation should be planned but skipped due to containment
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].will_skip);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("path escapes worktree boundary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_containment_copy_escapes_boundary() {
+        let root = TempDir::new().unwrap();
+        let main_dir = root.path().join("main");
+        let outer_dir = root.path().join("outer");
+        let target_dir = root.path().join("target");
+        fs::create_dir_all(&main_dir).unwrap();
+        fs::create_dir_all(&outer_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["../outer/secret.txt".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.join("worktree.config.toml"),
+            config_dir: main_dir.clone(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].will_skip);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("path escapes worktree boundary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_containment_overwrite_escapes_boundary() {
+        let root = TempDir::new().unwrap();
+        let main_dir = root.path().join("main");
+        let outer_dir = root.path().join("outer");
+        let target_dir = root.path().join("target");
+        fs::create_dir_all(&main_dir).unwrap();
+        fs::create_dir_all(&outer_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                overwrite: vec!["../outer/secret.txt".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.join("worktree.config.toml"),
+            config_dir: main_dir.clone(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].will_skip);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("path escapes worktree boundary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_containment_template_escapes_boundary() {
+        let root = TempDir::new().unwrap();
+        let main_dir = root.path().join("main");
+        let outer_dir = root.path().join("outer");
+        let target_dir = root.path().join("target");
+        fs::create_dir_all(&main_dir).unwrap();
+        fs::create_dir_all(&outer_dir).unwrap();
+        fs::create_dir_all(&target_dir).unwrap();
+
+        fs::write(outer_dir.join("template.txt"), "secret").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                templates: vec![worktree_setup_config::TemplateMapping {
+                    source: "../outer/template.txt".to_string(),
+                    target: "output.txt".to_string(),
+                    strip_suffix: None,
+                    engine: worktree_setup_config::TemplateEngine::default(),
+                    mode: None,
+                    dir_mode: None,
+                    label: None,
+                    order: 0,
+                    condition: worktree_setup_config::Condition::default(),
+                    hooks: worktree_setup_config::EntryHooks::default(),
+                }],
+                ..Default::default()
+            },
+            config_path: main_dir.join("worktree.config.toml"),
+            config_dir: main_dir.clone(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].will_skip);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("path escapes worktree boundary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_containment_allow_path_escape_permits_outside_paths() {
+        let root = TempDir::new().unwrap();
+        let main_dir = root.path().join("main");
+        let outer_dir = root.path().join("outer");
+        let target_dir = TempDir::new().unwrap();
+        fs::create_dir_all(&main_dir).unwrap();
+        fs::create_dir_all(&outer_dir).unwrap();
+
+        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["../outer/secret.txt".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.join("worktree.config.toml"),
+            config_dir: main_dir.clone(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        // Allow path escape
+        let options = ApplyConfigOptions {
+            allow_path_escape: true,
+            ..Default::default()
+        };
+        let ops = plan_operations(&config, &main_dir, target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        // Should NOT be skipped — path escape allowed and target is separate
+        assert!(!ops[0].will_skip);
+    }
+
+    #[test]
+    fn test_glob_errors_are_planned_or_fail_with_strict_globs() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("a.env"), "A=1").unwrap();
+
+        let mut config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["*.env".into(), "[".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].display_path, "a.env");
+        assert_eq!(ops[1].display_path, "[");
+        assert!(ops[1].will_skip);
+        assert!(
+            ops[1]
+                .skip_reason
+                .as_deref()
+                .unwrap()
+                .starts_with("glob error: invalid glob pattern")
+        );
+
+        config.config.strict_globs = Some(true);
+        let err = plan_operations(&config, main_dir.path(), target_dir.path(), &options);
+        assert!(matches!(err, Err(OperationError::GlobError { pattern, .. }) if pattern == "["));
+
+        // The single-kind planners cannot fail and plan the error instead
+        let ops = plan_copy_globs(&config, main_dir.path(), target_dir.path(), &options);
+        assert!(ops.iter().any(|op| op.display_path == "[" && op.will_skip));
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        assert_eq!(run_with_timeout(Duration::from_secs(30), || 7), Some(7));
+        let slow = run_with_timeout(Duration::from_millis(50), || {
+            std::thread::sleep(Duration::from_secs(1));
+        });
+        assert_eq!(slow, None);
+    }
+
+    #[test]
+    fn test_glob_timeout_keeps_matches_of_fast_walks() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        fs::write(main_dir.path().join("a.env"), "A=1").unwrap();
+        fs::write(main_dir.path().join("b.env"), "B=1").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["*.env".into(), "a.*".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions {
+            glob_timeout: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let paths: Vec<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
+        // `a.*` finds nothing new: matches seen on the worker carry over.
+        assert_eq!(paths, ["a.env", "b.env"]);
+    }
+
+    #[test]
+    fn test_symlink_and_overwrite_globs_expand_per_match() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        fs::create_dir_all(main_dir.path().join("configs")).unwrap();
+        fs::create_dir_all(main_dir.path().join("apps/web")).unwrap();
+        fs::write(main_dir.path().join("configs/a.local.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("configs/b.local.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("configs/shared.json"), "{}").unwrap();
+        fs::write(main_dir.path().join(".tool-versions"), "node 22").unwrap();
+        fs::write(main_dir.path().join("apps/web/.tool-versions"), "node 20").unwrap();
+        fs::create_dir_all(target_dir.path().join("configs")).unwrap();
+        fs::write(target_dir.path().join("configs/b.local.json"), "old").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                symlinks: vec!["configs/*.local.json".into()],
+                overwrite: vec!["**/.tool-versions".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let options = ApplyConfigOptions::default();
+
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let mut summary: Vec<(&str, OperationType, bool)> = ops
+            .iter()
+            .map(|op| (op.display_path.as_str(), op.operation_type, op.will_skip))
+            .collect();
+        summary.sort_by_key(|(path, _, _)| *path);
+
+        assert_eq!(
+            summary,
+            [
+                (".tool-versions", OperationType::Overwrite, false),
+                ("apps/web/.tool-versions", OperationType::Overwrite, false),
+                ("configs/a.local.json", OperationType::Symlink, false),
+                ("configs/b.local.json", OperationType::Symlink, true),
+            ]
+        );
+        let web = ops
+            .iter()
+            .find(|op| op.display_path == "apps/web/.tool-versions")
+            .unwrap();
+        assert_eq!(
+            web.target,
+            target_dir.path().join("apps/web/.tool-versions")
+        );
+    }
+
+    #[test]
+    fn test_plan_glob_ops_with_walkdir() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create files matching a glob pattern
+        fs::write(main_dir.path().join("file1.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("file2.txt"), "b").unwrap();
+        fs::write(main_dir.path().join("file3.log"), "c").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["*.txt".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        // Should match file1.txt and file2.txt, but not file3.log
+        assert_eq!(ops.len(), 2);
+        assert!(
+            ops.iter()
+                .all(|op| op.operation_type == OperationType::CopyGlob)
+        );
+
+        let display_paths: BTreeSet<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
+        assert!(display_paths.contains("file1.txt"));
+        assert!(display_paths.contains("file2.txt"));
+    }
+
+    #[test]
+    fn test_plan_glob_ops_nested_pattern() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create nested directory structure
+        fs::create_dir_all(main_dir.path().join("a/dist")).unwrap();
+        fs::write(main_dir.path().join("a/dist/bundle.js"), "code").unwrap();
+        fs::create_dir_all(main_dir.path().join("b/dist")).unwrap();
+        fs::write(main_dir.path().join("b/dist/bundle.js"), "code").unwrap();
+        fs::create_dir_all(main_dir.path().join("c/src")).unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["**/dist".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        // Should match a/dist and b/dist directories
+        assert_eq!(ops.len(), 2);
+
+        let display_paths: BTreeSet<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
+        assert!(display_paths.contains("a/dist"));
+        assert!(display_paths.contains("b/dist"));
+
+        // Directories should have is_directory = true and correct file_count
+        for op in &ops {
+            assert!(op.is_directory, "{} should be a directory", op.display_path);
+            assert_eq!(op.file_count, 1, "{} should have 1 file", op.display_path);
+        }
+    }
+
+    #[test]
+    fn test_plan_glob_exact_directory_handling() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create a directory with multiple files (exact path, not a glob pattern)
+        let dist_dir = main_dir.path().join("dist");
+        fs::create_dir_all(&dist_dir).unwrap();
+        fs::write(dist_dir.join("bundle.js"), "code").unwrap();
+        fs::write(dist_dir.join("style.css"), "css").unwrap();
+        fs::create_dir_all(dist_dir.join("assets")).unwrap();
+        fs::write(dist_dir.join("assets/logo.png"), "img").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["dist".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 1);
+        assert!(ops[0].is_directory);
+        assert_eq!(ops[0].file_count, 3); // 3 files in the directory
+        assert_eq!(ops[0].operation_type, OperationType::CopyGlob);
+    }
+
+    #[test]
+    fn test_plan_glob_pattern_file_not_directory() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        // Create regular files (not directories) matching a glob
+        fs::write(main_dir.path().join("app.js"), "code").unwrap();
+        fs::write(main_dir.path().join("lib.js"), "code").unwrap();
+
+        let config = LoadedConfig {
+            config: Config {
+                copy_glob: vec!["*.js".into()],
+                ..Default::default()
+            },
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 2);
+        for op in &ops {
+            assert!(
+                !op.is_directory,
+                "{} should not be a directory",
+                op.display_path
+            );
+            assert_eq!(op.file_count, 1);
+        }
+    }
+
+    #[test]
+    fn test_worktreeignore_skips_explicit_entries() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create source files
         fs::create_dir_all(main_dir.path().join("data")).unwrap();
         fs::write(main_dir.path().join("config.json"), "{}").unwrap();
         fs::write(main_dir.path().join("settings.json"), "{}").unwrap();
+        fs::write(
+            main_dir.path().join(".worktreeignore"),
+            "data/\nconfig.json\n",
+        )
+        .unwrap();
 
         let config = create_test_config(main_dir.path());
         let options = ApplyConfigOptions::default();
@@ -799,453 +3461,627 @@ mod tests {
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
         assert_eq!(ops.len(), 3);
-        assert_eq!(ops[0].operation_type, OperationType::Symlink);
-        assert_eq!(ops[1].operation_type, OperationType::Copy);
-        assert_eq!(ops[2].operation_type, OperationType::Overwrite);
+        assert_eq!(ops[0].skip_reason, Some("ignored".to_string()));
+        assert_eq!(ops[1].skip_reason, Some("ignored".to_string()));
+        assert!(!ops[2].will_skip);
     }
 
     #[test]
-    fn test_plan_operations_skip_existing() {
+    fn test_directory_counts_report_filtered_files() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create source and target files
-        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
-        fs::write(target_dir.path().join("config.json"), "existing").unwrap();
+        fs::create_dir_all(main_dir.path().join("assets/cache")).unwrap();
+        fs::write(main_dir.path().join("assets/a.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("assets/b.txt"), "b").unwrap();
+        fs::write(main_dir.path().join("assets/cache/c"), "c").unwrap();
+        fs::write(main_dir.path().join(".worktreeignore"), "cache/\n").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                copy: vec!["config.json".to_string()],
+                copy: vec!["assets".into()],
                 ..Default::default()
             },
             config_path: main_dir.path().join("worktree.config.toml"),
             config_dir: main_dir.path().to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         };
-        let options = ApplyConfigOptions::default();
 
+        let options = ApplyConfigOptions::default();
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
         assert_eq!(ops.len(), 1);
-        assert!(ops[0].will_skip);
-        assert_eq!(ops[0].skip_reason, Some("exists".to_string()));
+        assert_eq!(ops[0].file_count, 2);
+        assert_eq!(ops[0].filtered_count, 1);
+        assert_eq!(ops[0].symlink_count, 0);
     }
 
     #[test]
-    fn test_plan_operations_directory_file_count() {
+    fn test_cached_directory_counts_follow_ignore_edits() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
+        let cache_dir = TempDir::new().unwrap();
 
-        // Create a directory with files
-        let data_dir = main_dir.path().join("data");
-        fs::create_dir_all(&data_dir).unwrap();
-        fs::write(data_dir.join("file1.txt"), "1").unwrap();
-        fs::write(data_dir.join("file2.txt"), "2").unwrap();
-        fs::create_dir(data_dir.join("subdir")).unwrap();
-        fs::write(data_dir.join("subdir/file3.txt"), "3").unwrap();
+        fs::create_dir_all(main_dir.path().join("assets/cache")).unwrap();
+        fs::write(main_dir.path().join("assets/a.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("assets/b.txt"), "b").unwrap();
+        fs::write(main_dir.path().join("assets/cache/c"), "c").unwrap();
+        fs::write(main_dir.path().join(".worktreeignore"), "cache/\n").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                copy: vec!["data".to_string()],
+                copy: vec!["assets".into()],
                 ..Default::default()
             },
             config_path: main_dir.path().join("worktree.config.toml"),
             config_dir: main_dir.path().to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         };
-        let options = ApplyConfigOptions::default();
+        let options = ApplyConfigOptions {
+            count_cache: Some(Arc::new(CountCache::load(
+                cache_dir.path().join("count-cache.json"),
+            ))),
+            ..Default::default()
+        };
 
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        assert_eq!((ops[0].file_count, ops[0].filtered_count), (2, 1));
 
-        assert_eq!(ops.len(), 1);
-        assert!(ops[0].is_directory);
-        assert_eq!(ops[0].file_count, 3);
+        // The edit leaves `assets` untouched, so only the rules changed.
+        fs::write(main_dir.path().join(".worktreeignore"), "cache/\nb.txt\n").unwrap();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        assert_eq!((ops[0].file_count, ops[0].filtered_count), (1, 2));
     }
 
     #[test]
-    fn test_plan_operations_with_progress_callback() {
-        use std::cell::RefCell;
-
+    fn test_worktreeignore_filters_glob_matches() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create source files
-        fs::create_dir_all(main_dir.path().join("data")).unwrap();
-        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(main_dir.path().join("keep.txt"), "a").unwrap();
+        fs::write(main_dir.path().join("secret.txt"), "b").unwrap();
+        fs::write(main_dir.path().join(".worktreeignore"), "secret.txt\n").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                symlinks: vec!["data".to_string()],
-                copy: vec!["config.json".to_string()],
+                copy_glob: vec!["*.txt".into()],
                 ..Default::default()
             },
             config_path: main_dir.path().join("worktree.config.toml"),
             config_dir: main_dir.path().to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         };
-        let options = ApplyConfigOptions::default();
 
-        let progress_calls = RefCell::new(Vec::new());
-        let ops = plan_operations_with_progress(
-            &config,
-            main_dir.path(),
-            target_dir.path(),
-            &options,
-            &|current, total, path, _file_count| {
-                progress_calls
-                    .borrow_mut()
-                    .push((current, total, path.to_string()));
-            },
-        )
-        .unwrap();
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
-        let calls = progress_calls.into_inner();
-        assert_eq!(ops.len(), 2);
-        assert_eq!(calls.len(), 2);
-        assert_eq!(calls[0], (1, 2, "data".to_string()));
-        assert_eq!(calls[1], (2, 2, "config.json".to_string()));
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].display_path, "keep.txt");
     }
 
     #[test]
-    fn test_plan_unstaged_operations() {
+    fn test_worktreeignore_filters_unstaged_files() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create source files
-        fs::write(main_dir.path().join("modified.txt"), "content").unwrap();
-        fs::write(main_dir.path().join("untracked.txt"), "content").unwrap();
+        fs::write(main_dir.path().join("wip.rs"), "a").unwrap();
+        fs::write(main_dir.path().join("scratch.log"), "b").unwrap();
+        fs::write(main_dir.path().join(".worktreeignore"), "*.log\n").unwrap();
 
-        let unstaged = vec!["modified.txt".to_string(), "untracked.txt".to_string()];
-        let ops = plan_unstaged_operations(&unstaged, main_dir.path(), target_dir.path());
+        let ignore = WorktreeIgnore::load(main_dir.path());
+        let unstaged = vec![PathBuf::from("wip.rs"), PathBuf::from("scratch.log")];
+        let ops = plan_unstaged_operations(&unstaged, main_dir.path(), target_dir.path(), &ignore);
 
-        assert_eq!(ops.len(), 2);
-        assert_eq!(ops[0].operation_type, OperationType::Unstaged);
-        assert_eq!(ops[1].operation_type, OperationType::Unstaged);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].display_path, "wip.rs");
     }
 
     #[test]
-    fn test_plan_operations_repo_root_relative_paths() {
+    fn test_plan_copy_with_target() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
-
-        // Create repo structure:
-        // main_dir/
-        //   .nix/
-        //     flake.nix
-        //   .envrc
-        //   apps/
-        //     myapp/
-        //       worktree.config.toml (config is here)
-
-        // Create root-level files
-        fs::create_dir_all(main_dir.path().join(".nix")).unwrap();
-        fs::write(main_dir.path().join(".nix/flake.nix"), "{}").unwrap();
-        fs::write(main_dir.path().join(".envrc"), "use flake").unwrap();
-
-        // Create app directory structure
-        let app_dir = main_dir.path().join("apps/myapp");
-        fs::create_dir_all(&app_dir).unwrap();
-
-        // Config in subdirectory referencing root files with /
+        let main = main_dir.path();
+        fs::create_dir_all(main.join("configs/fixtures")).unwrap();
+        fs::write(main.join("configs/dev.json"), "{}").unwrap();
+        fs::write(main.join("configs/fixtures/a.sql"), "1").unwrap();
+        fs::write(main.join("configs/fixtures/b.sql"), "2").unwrap();
+        fs::write(target_dir.path().join("config.json"), "old").unwrap();
+
+        let renamed = |source: &str, target: &str| OperationEntry::Conditional {
+            path: source.to_string(),
+            target: Some(target.to_string()),
+            label: Some(format!("{source} fixture")),
+            order: 0,
+            max_depth: None,
+            condition: worktree_setup_config::Condition::default(),
+            hooks: worktree_setup_config::EntryHooks::default(),
+        };
         let config = LoadedConfig {
             config: Config {
-                copy: vec!["/.nix".to_string(), "/.envrc".to_string()],
+                copy: vec![renamed("configs/fixtures", "db/seed")],
+                overwrite: vec![renamed("configs/dev.json", "config.json")],
                 ..Default::default()
             },
-            config_path: app_dir.join("worktree.config.toml"),
-            config_dir: app_dir.clone(),
-            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+            config_path: main.join("worktree.config.toml"),
+            config_dir: main.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
         };
-        let options = ApplyConfigOptions::default();
 
-        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let ops = plan_operations(
+            &config,
+            main,
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        )
+        .unwrap();
 
         assert_eq!(ops.len(), 2);
-
-        // Check .nix directory
-        assert_eq!(ops[0].display_path, ".nix");
-        assert_eq!(ops[0].source, main_dir.path().join(".nix"));
-        assert_eq!(ops[0].target, target_dir.path().join(".nix"));
+        assert_eq!(ops[0].display_path, "configs/fixtures -> db/seed");
+        assert_eq!(ops[0].target, target_dir.path().join("db/seed"));
         assert!(ops[0].is_directory);
+        assert_eq!(ops[0].file_count, 2);
+        assert_eq!(ops[0].label(), Some("configs/fixtures fixture"));
         assert!(!ops[0].will_skip);
-
-        // Check .envrc file
-        assert_eq!(ops[1].display_path, ".envrc");
-        assert_eq!(ops[1].source, main_dir.path().join(".envrc"));
-        assert_eq!(ops[1].target, target_dir.path().join(".envrc"));
-        assert!(!ops[1].is_directory);
-        assert!(!ops[1].will_skip);
+        assert_eq!(ops[1].display_path, "configs/dev.json -> config.json");
+        assert_eq!(ops[1].source, main.join("configs/dev.json"));
+        assert_eq!(ops[1].target, target_dir.path().join("config.json"));
+        assert_eq!(ops[1].operation_type, OperationType::Overwrite);
     }
 
     #[test]
-    fn test_plan_operations_mixed_paths() {
+    fn test_plan_provider_sources() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
+        let cache_dir = TempDir::new().unwrap();
+        let main = main_dir.path();
+        fs::create_dir_all(main.join("seed/data")).unwrap();
+        fs::write(main.join("seed/data/users.json"), "[]").unwrap();
+        fs::write(main.join("seed/data/app.env.tmpl"), "PORT=1").unwrap();
+        let status = Command::new("tar")
+            .args(["-czf", "fixtures.tar.gz", "-C", "seed", "data"])
+            .current_dir(main)
+            .status()
+            .unwrap();
+        assert!(status.success());
 
-        // Create repo structure with both root and app-level files
-        fs::write(main_dir.path().join(".envrc"), "use flake").unwrap();
-
-        let app_dir = main_dir.path().join("apps/myapp");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::write(app_dir.join("local.config"), "app config").unwrap();
-
-        // Config with mixed paths: one root-relative, one config-relative
         let config = LoadedConfig {
             config: Config {
                 copy: vec![
-                    "/.envrc".to_string(),      // root-relative
-                    "local.config".to_string(), // config-relative
+                    OperationEntry::Path("tar:fixtures.tar.gz#data".to_string()),
+                    OperationEntry::Path("tar:fixtures.tar.gz".to_string()),
+                    OperationEntry::Path("tar:missing.tar#data".to_string()),
+                    OperationEntry::Path("file:seed/data/users.json".to_string()),
                 ],
+                templates: vec![worktree_setup_config::TemplateMapping {
+                    source: "tar:fixtures.tar.gz#data".to_string(),
+                    target: "config".to_string(),
+                    strip_suffix: Some(".tmpl".to_string()),
+                    engine: worktree_setup_config::TemplateEngine::default(),
+                    mode: None,
+                    dir_mode: None,
+                    label: None,
+                    order: 0,
+                    condition: worktree_setup_config::Condition::default(),
+                    hooks: worktree_setup_config::EntryHooks::default(),
+                }],
                 ..Default::default()
             },
-            config_path: app_dir.join("worktree.config.toml"),
-            config_dir: app_dir.clone(),
-            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+            config_path: main.join("worktree.config.toml"),
+            config_dir: main.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let mut providers = SourceProviders::empty();
+        providers.register(Arc::new(crate::FilesystemProvider));
+        providers.register(Arc::new(crate::TarProvider::new(Some(
+            cache_dir.path().to_path_buf(),
+        ))));
+        let options = ApplyConfigOptions {
+            source_providers: Some(Arc::new(providers)),
+            ..Default::default()
         };
-        let options = ApplyConfigOptions::default();
-
-        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
-        assert_eq!(ops.len(), 2);
+        let ops = plan_operations(&config, main, target_dir.path(), &options).unwrap();
+        let op = |display: &str| {
+            ops.iter()
+                .find(|op| op.display_path == display)
+                .unwrap_or_else(|| panic!("no operation for {display}"))
+        };
 
-        // Root-relative path: /.envrc -> .envrc
-        assert_eq!(ops[0].display_path, ".envrc");
-        assert_eq!(ops[0].source, main_dir.path().join(".envrc"));
-        assert_eq!(ops[0].target, target_dir.path().join(".envrc"));
+        let data = op("tar:fixtures.tar.gz#data -> data");
+        assert!(!data.will_skip);
+        assert!(data.is_directory);
+        assert_eq!(data.file_count, 2);
+        assert!(data.source.starts_with(cache_dir.path()));
+        assert_eq!(data.target, target_dir.path().join("data"));
 
-        // Config-relative path: local.config -> apps/myapp/local.config
-        assert_eq!(ops[1].display_path, "apps/myapp/local.config");
-        assert_eq!(ops[1].source, app_dir.join("local.config"));
+        let whole = op("tar:fixtures.tar.gz");
         assert_eq!(
-            ops[1].target,
-            target_dir.path().join("apps/myapp/local.config")
+            whole.skip_reason.as_deref(),
+            Some("a target is required for this source")
+        );
+        let missing = op("tar:missing.tar#data -> data");
+        assert!(
+            missing
+                .skip_reason
+                .as_deref()
+                .unwrap()
+                .contains("not found")
         );
+
+        let file = op("file:seed/data/users.json -> seed/data/users.json");
+        assert_eq!(file.source, main.join("seed/data/users.json"));
+
+        let template = op("tar:fixtures.tar.gz#data/app.env.tmpl -> config/app.env");
+        assert_eq!(template.operation_type, OperationType::Template);
+        assert!(!template.will_skip);
     }
 
     #[test]
-    fn test_plan_operations_template_with_root_paths() {
+    fn test_max_depth_limits_directory_counts() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
-
-        // Create template at root
-        fs::write(main_dir.path().join(".env.template"), "KEY=value").unwrap();
-
-        let app_dir = main_dir.path().join("apps/myapp");
-        fs::create_dir_all(&app_dir).unwrap();
+        let main = main_dir.path();
+        fs::create_dir_all(main.join("datasets/a/b")).unwrap();
+        fs::create_dir_all(main.join("cache/x")).unwrap();
+        fs::write(main.join("datasets/index.json"), "{}").unwrap();
+        fs::write(main.join("datasets/a/part.csv"), "1").unwrap();
+        fs::write(main.join("datasets/a/b/rows.csv"), "2").unwrap();
+        fs::write(main.join("cache/top"), "3").unwrap();
+        fs::write(main.join("cache/x/deep"), "4").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                templates: vec![worktree_setup_config::TemplateMapping {
-                    source: "/.env.template".to_string(), // root-relative source
-                    target: ".env.local".to_string(),     // config-relative target
-                }],
+                copy: vec![
+                    OperationEntry::Conditional {
+                        path: "datasets".to_string(),
+                        target: None,
+                        label: None,
+                        order: 0,
+                        max_depth: Some(1),
+                        condition: worktree_setup_config::Condition::default(),
+                        hooks: worktree_setup_config::EntryHooks::default(),
+                    },
+                    "cache".into(),
+                ],
                 ..Default::default()
             },
-            config_path: app_dir.join("worktree.config.toml"),
-            config_dir: app_dir.clone(),
-            relative_path: "apps/myapp/worktree.config.toml".to_string(),
+            config_path: main.join("worktree.config.toml"),
+            config_dir: main.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
         };
-        let options = ApplyConfigOptions::default();
 
-        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let counts = |options: &ApplyConfigOptions| {
+            plan_operations(&config, main, target_dir.path(), options)
+                .unwrap()
+                .iter()
+                .map(|op| (op.file_count, op.max_depth))
+                .collect::<Vec<_>>()
+        };
 
-        assert_eq!(ops.len(), 1);
-        assert_eq!(ops[0].operation_type, OperationType::Template);
         assert_eq!(
-            ops[0].display_path,
-            ".env.template -> apps/myapp/.env.local"
-        );
-        assert_eq!(ops[0].source, main_dir.path().join(".env.template"));
-        assert_eq!(
-            ops[0].target,
-            target_dir.path().join("apps/myapp/.env.local")
+            counts(&ApplyConfigOptions::default()),
+            [(1, Some(1)), (2, None)]
         );
+        let options = ApplyConfigOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(counts(&options), [(1, Some(1)), (2, Some(2))]);
+        let options = ApplyConfigOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(counts(&options), [(1, Some(1)), (1, Some(1))]);
     }
 
     #[test]
-    fn test_containment_symlink_escapes_boundary() {
-        let root = TempDir::new().unwrap();
-        let main_dir = root.path().join("main");
-        let outer_dir = root.path().join("outer");
-        let target_dir = root.path().join("target");
-        fs::create_dir_all(&main_dir).unwrap();
-        fs::create_dir_all(&outer_dir).unwrap();
-        fs::create_dir_all(&target_dir).unwrap();
+    fn test_nested_target_is_excluded() {
+        let main_dir = TempDir::new().unwrap();
+        let main = main_dir.path();
+        let target = main.join("work/feature");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(main.join("work/a.txt"), "a").unwrap();
+        fs::write(target.join("b.txt"), "b").unwrap();
+
+        assert_eq!(
+            nested_target(main, &target),
+            Some(PathBuf::from("work/feature"))
+        );
+        assert_eq!(nested_target(&target, main), None);
+        assert_eq!(nested_target(main, main), None);
+
+        let config = LoadedConfig {
+            config: Config {
+                copy: vec!["work".into()],
+                ..Default::default()
+            },
+            config_path: main.join("worktree.config.toml"),
+            config_dir: main.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        };
+        let ops = plan_operations(&config, main, &target, &ApplyConfigOptions::default()).unwrap();
 
-        // Create a file outside the main worktree
-        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].file_count, 1);
+        assert_eq!(ops[0].filtered_count, 1);
+
+        let unstaged = vec![
+            PathBuf::from("work/a.txt"),
+            PathBuf::from("work/feature/b.txt"),
+        ];
+        let ignore = load_worktree_ignore(main, &target);
+        let ops = plan_unstaged_operations(&unstaged, main, &target, &ignore);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].display_path, "work/a.txt");
+    }
 
-        let config = LoadedConfig {
+    fn template_config(
+        dir: &Path,
+        template: worktree_setup_config::TemplateMapping,
+    ) -> LoadedConfig {
+        LoadedConfig {
             config: Config {
-                symlinks: vec!["../outer/secret.txt".to_string()],
+                templates: vec![template],
                 ..Default::default()
             },
-            config_path: main_dir.join("worktree.config.toml"),
-            config_dir: main_dir.clone(),
+            config_path: dir.join("worktree.config.toml"),
+            config_dir: dir.to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
-        };
+        }
+    }
 
-        // Default: containment enforced (allow_path_escape = false)
-        let options = ApplyConfigOptions::default();
-        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+    #[test]
+    fn test_template_directory_source_expands_per_file() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
 
-        // The operation should be planned but skipped due to containment
-        assert_eq!(ops.len(), 1);
-        assert!(ops[0].will_skip);
-        assert_eq!(
-            ops[0].skip_reason,
-            Some("path escapes worktree boundary".to_string())
+        fs::create_dir_all(main_dir.path().join("templates/nested")).unwrap();
+        fs::write(main_dir.path().join("templates/.env.example"), "A=1").unwrap();
+        fs::write(
+            main_dir.path().join("templates/nested/app.json.example"),
+            "{}",
+        )
+        .unwrap();
+        fs::create_dir_all(target_dir.path().join("config")).unwrap();
+        fs::write(target_dir.path().join("config/.env"), "existing").unwrap();
+
+        let config = template_config(
+            main_dir.path(),
+            worktree_setup_config::TemplateMapping {
+                source: "templates".to_string(),
+                target: "config".to_string(),
+                strip_suffix: Some(".example".to_string()),
+                engine: worktree_setup_config::TemplateEngine::default(),
+                mode: None,
+                dir_mode: None,
+                label: None,
+                order: 0,
+                condition: worktree_setup_config::Condition::default(),
+                hooks: worktree_setup_config::EntryHooks::default(),
+            },
         );
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+
+        assert_eq!(ops.len(), 2);
+        let env = ops
+            .iter()
+            .find(|op| op.target.ends_with("config/.env"))
+            .unwrap();
+        assert!(env.will_skip);
+        assert_eq!(env.skip_reason, Some("exists".to_string()));
+        assert_eq!(env.display_path, "templates/.env.example -> config/.env");
+
+        let app = ops
+            .iter()
+            .find(|op| op.target.ends_with("config/nested/app.json"))
+            .unwrap();
+        assert!(!app.will_skip);
+        assert_eq!(app.operation_type, OperationType::Template);
     }
 
     #[test]
-    fn test_containment_copy_escapes_boundary() {
-        let root = TempDir::new().unwrap();
-        let main_dir = root.path().join("main");
-        let outer_dir = root.path().join("outer");
-        let target_dir = root.path().join("target");
-        fs::create_dir_all(&main_dir).unwrap();
-        fs::create_dir_all(&outer_dir).unwrap();
-        fs::create_dir_all(&target_dir).unwrap();
+    fn test_template_engine_renders_expanded_files() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
 
-        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+        fs::create_dir_all(main_dir.path().join("templates")).unwrap();
+        fs::write(
+            main_dir.path().join("templates/.env.example"),
+            "DIR=${WORKTREE_PATH}\nKEEP=${NOT_A_WORKTREE_VAR}\n",
+        )
+        .unwrap();
 
-        let config = LoadedConfig {
-            config: Config {
-                copy: vec!["../outer/secret.txt".to_string()],
-                ..Default::default()
+        let config = template_config(
+            main_dir.path(),
+            worktree_setup_config::TemplateMapping {
+                source: "templates".to_string(),
+                target: ".".to_string(),
+                strip_suffix: Some(".example".to_string()),
+                engine: worktree_setup_config::TemplateEngine::Simple,
+                mode: None,
+                dir_mode: None,
+                label: None,
+                order: 0,
+                condition: worktree_setup_config::Condition::default(),
+                hooks: worktree_setup_config::EntryHooks::default(),
             },
-            config_path: main_dir.join("worktree.config.toml"),
-            config_dir: main_dir.clone(),
-            relative_path: "worktree.config.toml".to_string(),
-        };
-
+        );
         let options = ApplyConfigOptions::default();
-        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
         assert_eq!(ops.len(), 1);
-        assert!(ops[0].will_skip);
+        assert!(ops[0].template.is_some());
+        crate::execute_operation(&ops[0], |_, _| {}).unwrap();
         assert_eq!(
-            ops[0].skip_reason,
-            Some("path escapes worktree boundary".to_string())
+            fs::read_to_string(target_dir.path().join(".env")).unwrap(),
+            format!(
+                "DIR={}\nKEEP=${{NOT_A_WORKTREE_VAR}}\n",
+                target_dir.path().display()
+            )
         );
     }
 
     #[test]
-    fn test_containment_overwrite_escapes_boundary() {
-        let root = TempDir::new().unwrap();
-        let main_dir = root.path().join("main");
-        let outer_dir = root.path().join("outer");
-        let target_dir = root.path().join("target");
-        fs::create_dir_all(&main_dir).unwrap();
-        fs::create_dir_all(&outer_dir).unwrap();
-        fs::create_dir_all(&target_dir).unwrap();
+    fn test_template_glob_source_is_relative_to_literal_prefix() {
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
 
-        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+        fs::create_dir_all(main_dir.path().join("tpl/sub")).unwrap();
+        fs::write(main_dir.path().join("tpl/a.example"), "a").unwrap();
+        fs::write(main_dir.path().join("tpl/sub/b.example"), "b").unwrap();
+        fs::write(main_dir.path().join("tpl/readme.md"), "r").unwrap();
 
-        let config = LoadedConfig {
-            config: Config {
-                overwrite: vec!["../outer/secret.txt".to_string()],
-                ..Default::default()
+        let config = template_config(
+            main_dir.path(),
+            worktree_setup_config::TemplateMapping {
+                source: "tpl/**/*.example".to_string(),
+                target: ".".to_string(),
+                strip_suffix: Some(".example".to_string()),
+                engine: worktree_setup_config::TemplateEngine::default(),
+                mode: None,
+                dir_mode: None,
+                label: None,
+                order: 0,
+                condition: worktree_setup_config::Condition::default(),
+                hooks: worktree_setup_config::EntryHooks::default(),
             },
-            config_path: main_dir.join("worktree.config.toml"),
-            config_dir: main_dir.clone(),
-            relative_path: "worktree.config.toml".to_string(),
-        };
-
+        );
         let options = ApplyConfigOptions::default();
-        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
-        assert_eq!(ops.len(), 1);
-        assert!(ops[0].will_skip);
+        let targets: BTreeSet<PathBuf> = ops.iter().map(|op| op.target.clone()).collect();
         assert_eq!(
-            ops[0].skip_reason,
-            Some("path escapes worktree boundary".to_string())
+            targets,
+            BTreeSet::from([
+                target_dir.path().join("./a"),
+                target_dir.path().join("./sub/b"),
+            ])
         );
     }
 
     #[test]
-    fn test_containment_template_escapes_boundary() {
-        let root = TempDir::new().unwrap();
-        let main_dir = root.path().join("main");
-        let outer_dir = root.path().join("outer");
-        let target_dir = root.path().join("target");
-        fs::create_dir_all(&main_dir).unwrap();
-        fs::create_dir_all(&outer_dir).unwrap();
-        fs::create_dir_all(&target_dir).unwrap();
+    fn test_strip_file_suffix() {
+        assert_eq!(
+            strip_file_suffix(Path::new("a/.env.example"), Some(".example")),
+            PathBuf::from("a/.env")
+        );
+        assert_eq!(
+            strip_file_suffix(Path::new(".example"), Some(".example")),
+            PathBuf::from(".example")
+        );
+        assert_eq!(
+            strip_file_suffix(Path::new("file.txt"), None),
+            PathBuf::from("file.txt")
+        );
+    }
 
-        fs::write(outer_dir.join("template.txt"), "secret").unwrap();
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_glob_keeps_non_utf8_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let main_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let name = OsStr::from_bytes(b"caf\xe9.env.example");
+        fs::write(main_dir.path().join(name), "x").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                templates: vec![worktree_setup_config::TemplateMapping {
-                    source: "../outer/template.txt".to_string(),
-                    target: "output.txt".to_string(),
-                }],
+                copy_glob: vec!["*.example".into()],
                 ..Default::default()
             },
-            config_path: main_dir.join("worktree.config.toml"),
-            config_dir: main_dir.clone(),
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         };
 
         let options = ApplyConfigOptions::default();
-        let ops = plan_operations(&config, &main_dir, &target_dir, &options).unwrap();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
         assert_eq!(ops.len(), 1);
-        assert!(ops[0].will_skip);
+        assert_eq!(ops[0].target, target_dir.path().join(name));
         assert_eq!(
-            ops[0].skip_reason,
-            Some("path escapes worktree boundary".to_string())
+            strip_file_suffix(Path::new(name), Some(".example")),
+            PathBuf::from(OsStr::from_bytes(b"caf\xe9.env"))
         );
     }
 
+    fn conditional(path: &str, condition: worktree_setup_config::Condition) -> OperationEntry {
+        OperationEntry::Conditional {
+            path: path.to_string(),
+            target: None,
+            label: None,
+            order: 0,
+            max_depth: None,
+            condition,
+            hooks: worktree_setup_config::EntryHooks::default(),
+        }
+    }
+
     #[test]
-    fn test_containment_allow_path_escape_permits_outside_paths() {
-        let root = TempDir::new().unwrap();
-        let main_dir = root.path().join("main");
-        let outer_dir = root.path().join("outer");
+    fn test_order_sorts_plan_stably() {
+        let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
-        fs::create_dir_all(&main_dir).unwrap();
-        fs::create_dir_all(&outer_dir).unwrap();
 
-        fs::write(outer_dir.join("secret.txt"), "secret").unwrap();
+        for name in ["cache", "link", ".env", "late.json"] {
+            fs::write(main_dir.path().join(name), "x").unwrap();
+        }
 
+        let ordered = |path: &str, order: i32| OperationEntry::Conditional {
+            path: path.to_string(),
+            target: None,
+            label: None,
+            order,
+            max_depth: None,
+            condition: worktree_setup_config::Condition::default(),
+            hooks: worktree_setup_config::EntryHooks::default(),
+        };
         let config = LoadedConfig {
             config: Config {
-                copy: vec!["../outer/secret.txt".to_string()],
+                symlinks: vec!["link".into()],
+                copy: vec![ordered("late.json", 5), ".env".into(), ordered("cache", -1)],
                 ..Default::default()
             },
-            config_path: main_dir.join("worktree.config.toml"),
-            config_dir: main_dir.clone(),
+            config_path: main_dir.path().join("worktree.config.toml"),
+            config_dir: main_dir.path().to_path_buf(),
             relative_path: "worktree.config.toml".to_string(),
         };
 
-        // Allow path escape
-        let options = ApplyConfigOptions {
-            allow_path_escape: true,
-            ..Default::default()
-        };
-        let ops = plan_operations(&config, &main_dir, target_dir.path(), &options).unwrap();
+        let options = ApplyConfigOptions::default();
+        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let paths: Vec<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
 
-        assert_eq!(ops.len(), 1);
-        // Should NOT be skipped — path escape allowed and target is separate
-        assert!(!ops[0].will_skip);
+        assert_eq!(paths, ["cache", "link", ".env", "late.json"]);
     }
 
     #[test]
-    fn test_plan_glob_ops_with_walkdir() {
+    fn test_condition_unless_exists() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create files matching a glob pattern
-        fs::write(main_dir.path().join("file1.txt"), "a").unwrap();
-        fs::write(main_dir.path().join("file2.txt"), "b").unwrap();
-        fs::write(main_dir.path().join("file3.log"), "c").unwrap();
+        fs::write(main_dir.path().join(".env"), "A=1").unwrap();
+        fs::write(main_dir.path().join("config.json"), "{}").unwrap();
+        fs::write(target_dir.path().join(".env.local"), "B=2").unwrap();
 
         let config = LoadedConfig {
             config: Config {
-                copy_glob: vec!["*.txt".to_string()],
+                copy: vec![
+                    conditional(
+                        ".env",
+                        worktree_setup_config::Condition {
+                            unless_exists: Some(".env.local".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    conditional(
+                        "config.json",
+                        worktree_setup_config::Condition {
+                            unless_exists: Some("/config.local.json".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                ],
                 ..Default::default()
             },
             config_path: main_dir.path().join("worktree.config.toml"),
@@ -1256,33 +4092,56 @@ mod tests {
         let options = ApplyConfigOptions::default();
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
-        // Should match file1.txt and file2.txt, but not file3.log
         assert_eq!(ops.len(), 2);
-        assert!(
-            ops.iter()
-                .all(|op| op.operation_type == OperationType::CopyGlob)
+        assert!(ops[0].will_skip);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("condition not met: unlessExists .env.local".to_string())
         );
-
-        let display_paths: BTreeSet<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
-        assert!(display_paths.contains("file1.txt"));
-        assert!(display_paths.contains("file2.txt"));
+        assert!(!ops[1].will_skip);
     }
 
     #[test]
-    fn test_plan_glob_ops_nested_pattern() {
+    fn test_condition_if_env_and_if_command() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create nested directory structure
-        fs::create_dir_all(main_dir.path().join("a/dist")).unwrap();
-        fs::write(main_dir.path().join("a/dist/bundle.js"), "code").unwrap();
-        fs::create_dir_all(main_dir.path().join("b/dist")).unwrap();
-        fs::write(main_dir.path().join("b/dist/bundle.js"), "code").unwrap();
-        fs::create_dir_all(main_dir.path().join("c/src")).unwrap();
+        for name in ["a", "b", "c", "d"] {
+            fs::write(main_dir.path().join(name), name).unwrap();
+        }
 
         let config = LoadedConfig {
             config: Config {
-                copy_glob: vec!["**/dist".to_string()],
+                symlinks: vec![
+                    conditional(
+                        "a",
+                        worktree_setup_config::Condition {
+                            if_env: Some("WORKTREE_SETUP_TEST_UNSET_VAR".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    conditional(
+                        "b",
+                        worktree_setup_config::Condition {
+                            if_env: Some("PATH".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    conditional(
+                        "c",
+                        worktree_setup_config::Condition {
+                            if_command: Some("exit 1".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    conditional(
+                        "d",
+                        worktree_setup_config::Condition {
+                            if_command: Some("exit 0".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                ],
                 ..Default::default()
             },
             config_path: main_dir.path().join("worktree.config.toml"),
@@ -1293,82 +4152,159 @@ mod tests {
         let options = ApplyConfigOptions::default();
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
-        // Should match a/dist and b/dist directories
-        assert_eq!(ops.len(), 2);
-
-        let display_paths: BTreeSet<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
-        assert!(display_paths.contains("a/dist"));
-        assert!(display_paths.contains("b/dist"));
-
-        // Directories should have is_directory = true and correct file_count
-        for op in &ops {
-            assert!(op.is_directory, "{} should be a directory", op.display_path);
-            assert_eq!(op.file_count, 1, "{} should have 1 file", op.display_path);
-        }
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("condition not met: ifEnv WORKTREE_SETUP_TEST_UNSET_VAR".to_string())
+        );
+        assert!(!ops[1].will_skip);
+        assert_eq!(
+            ops[2].skip_reason,
+            Some("condition not met: ifCommand exit 1".to_string())
+        );
+        assert!(!ops[3].will_skip);
     }
 
     #[test]
-    fn test_plan_glob_exact_directory_handling() {
+    fn test_condition_skips_template_and_glob() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
 
-        // Create a directory with multiple files (exact path, not a glob pattern)
-        let dist_dir = main_dir.path().join("dist");
-        fs::create_dir_all(&dist_dir).unwrap();
-        fs::write(dist_dir.join("bundle.js"), "code").unwrap();
-        fs::write(dist_dir.join("style.css"), "css").unwrap();
-        fs::create_dir_all(dist_dir.join("assets")).unwrap();
-        fs::write(dist_dir.join("assets/logo.png"), "img").unwrap();
+        fs::write(main_dir.path().join(".env.example"), "A=1").unwrap();
+        fs::write(main_dir.path().join("a.txt"), "a").unwrap();
 
-        let config = LoadedConfig {
-            config: Config {
-                copy_glob: vec!["dist".to_string()],
+        let mut config = template_config(
+            main_dir.path(),
+            worktree_setup_config::TemplateMapping {
+                source: ".env.example".to_string(),
+                target: ".env".to_string(),
+                strip_suffix: None,
+                engine: worktree_setup_config::TemplateEngine::default(),
+                mode: None,
+                dir_mode: None,
+                label: None,
+                order: 0,
+                condition: worktree_setup_config::Condition {
+                    if_command: Some("false".to_string()),
+                    ..Default::default()
+                },
+                hooks: worktree_setup_config::EntryHooks::default(),
+            },
+        );
+        config.config.copy_glob = vec![conditional(
+            "*.txt",
+            worktree_setup_config::Condition {
+                if_command: Some("false".to_string()),
                 ..Default::default()
             },
-            config_path: main_dir.path().join("worktree.config.toml"),
-            config_dir: main_dir.path().to_path_buf(),
-            relative_path: "worktree.config.toml".to_string(),
-        };
+        )];
 
         let options = ApplyConfigOptions::default();
         let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
 
         assert_eq!(ops.len(), 1);
-        assert!(ops[0].is_directory);
-        assert_eq!(ops[0].file_count, 3); // 3 files in the directory
-        assert_eq!(ops[0].operation_type, OperationType::CopyGlob);
+        assert_eq!(ops[0].operation_type, OperationType::Template);
+        assert_eq!(
+            ops[0].skip_reason,
+            Some("condition not met: ifCommand false".to_string())
+        );
     }
 
     #[test]
-    fn test_plan_glob_pattern_file_not_directory() {
+    fn test_plan_copy_from_ref_writes_committed_files() {
         let main_dir = TempDir::new().unwrap();
         let target_dir = TempDir::new().unwrap();
-
-        // Create regular files (not directories) matching a glob
-        fs::write(main_dir.path().join("app.js"), "code").unwrap();
-        fs::write(main_dir.path().join("lib.js"), "code").unwrap();
-
+        let main = main_dir.path();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(main)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q", "-b", "main"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test"]);
+        let dev = main.join("apps/web/configs/dev");
+        fs::create_dir_all(&dev).unwrap();
+        fs::write(dev.join("app.env"), "A=1\n").unwrap();
+        fs::write(dev.join("run.sh"), "#!/bin/sh\n").unwrap();
+        git(&["add", "."]);
+        git(&["update-index", "--chmod=+x", "apps/web/configs/dev/run.sh"]);
+        git(&["commit", "-q", "-m", "Add dev configs"]);
+        git(&["checkout", "-q", "-b", "feature"]);
+        git(&["rm", "-q", "-r", "-f", "apps/web/configs"]);
+        git(&["commit", "-q", "-m", "Drop dev configs"]);
+
+        let entry = |git_ref: &str, path: &str, target: Option<&str>| RefCopyEntry {
+            git_ref: git_ref.to_string(),
+            path: path.to_string(),
+            target: target.map(ToString::to_string),
+            filters: false,
+            label: None,
+            order: 0,
+            condition: Condition::default(),
+            hooks: EntryHooks::default(),
+        };
+        let app_dir = main.join("apps/web");
         let config = LoadedConfig {
             config: Config {
-                copy_glob: vec!["*.js".to_string()],
+                copy_from_ref: vec![
+                    entry("main", "configs/dev", None),
+                    entry("main", "configs/dev/app.env", Some("/.env")),
+                    entry("no-such-branch", "configs/dev", None),
+                ],
                 ..Default::default()
             },
-            config_path: main_dir.path().join("worktree.config.toml"),
-            config_dir: main_dir.path().to_path_buf(),
-            relative_path: "worktree.config.toml".to_string(),
+            config_path: app_dir.join("worktree.config.toml"),
+            config_dir: app_dir,
+            relative_path: "apps/web/worktree.config.toml".to_string(),
         };
 
-        let options = ApplyConfigOptions::default();
-        let ops = plan_operations(&config, main_dir.path(), target_dir.path(), &options).unwrap();
+        let ops = plan_ref_copies(
+            &config,
+            main,
+            target_dir.path(),
+            &ApplyConfigOptions::default(),
+        );
+
+        let displays: Vec<&str> = ops.iter().map(|op| op.display_path.as_str()).collect();
+        assert_eq!(
+            displays,
+            [
+                "main:apps/web/configs/dev/app.env",
+                "main:apps/web/configs/dev/run.sh",
+                "main:apps/web/configs/dev/app.env -> .env",
+                "no-such-branch:apps/web/configs/dev",
+            ]
+        );
+        assert_eq!(ops[0].byte_count, 4);
+        assert_eq!(
+            ops[3].skip_reason.as_deref(),
+            Some("not found at no-such-branch")
+        );
 
-        assert_eq!(ops.len(), 2);
         for op in &ops {
-            assert!(
-                !op.is_directory,
-                "{} should not be a directory",
-                op.display_path
-            );
-            assert_eq!(op.file_count, 1);
+            crate::execute_operation(op, |_, _| {}).unwrap();
         }
+        let target = target_dir.path();
+        assert_eq!(
+            fs::read_to_string(target.join("apps/web/configs/dev/app.env")).unwrap(),
+            "A=1\n"
+        );
+        assert_eq!(fs::read_to_string(target.join(".env")).unwrap(), "A=1\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(target.join("apps/web/configs/dev/run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Existing targets are kept unless overwriting.
+        let ops = plan_ref_copies(&config, main, target, &ApplyConfigOptions::default());
+        assert_eq!(ops[0].skip_reason.as_deref(), Some("exists"));
     }
 }
@@ -0,0 +1,133 @@
+//! Writing files committed at a git ref for `copyFromRef` entries.
+//!
+//! Planning lists the files at the ref (see
+//! [`worktree_setup_git::list_tree_files`]); each becomes an operation
+//! carrying a [`RefSource`], and executing it writes the committed blob
+//! into the target worktree with its executable bit or as a symlink.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use worktree_setup_git::{TreeFile, TreeFileKind, open_repo, read_blob, read_blob_filtered};
+
+use crate::OperationResult;
+use crate::error::OperationError;
+
+/// A committed file an operation writes instead of copying its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefSource {
+    /// Repository the file is read from.
+    pub repo: PathBuf,
+    /// Branch, tag, or commit the file was listed at.
+    pub git_ref: String,
+    /// The committed file.
+    pub file: TreeFile,
+    /// Run the file through its checkout filters (see
+    /// [`worktree_setup_git::read_blob_filtered`]).
+    pub filters: bool,
+    /// The `.gitattributes` filter a checkout would apply but `filters` is
+    /// off for, such as `filter=lfs`.
+    pub unapplied_filter: Option<String>,
+}
+
+/// Write the committed file `source` to `target`.
+///
+/// Skips an existing target unless `overwrite` is set.
+///
+/// # Errors
+///
+/// * If the repository or blob cannot be read
+/// * If the target cannot be written
+pub fn write_ref_file(
+    source: &RefSource,
+    target: &Path,
+    overwrite: bool,
+) -> Result<OperationResult, OperationError> {
+    log::debug!(
+        "Writing {} at {} -> {}",
+        source.file.path.display(),
+        source.git_ref,
+        target.display()
+    );
+
+    let existed = fs::symlink_metadata(target).is_ok();
+    if existed && !overwrite {
+        return Ok(OperationResult::Exists);
+    }
+
+    let content = if source.filters {
+        read_blob_filtered(&source.repo, &source.file.oid, &source.file.path)?
+    } else {
+        read_blob(&open_repo(&source.repo)?, &source.file.oid)?
+    };
+
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| OperationError::IoError { path, source }
+    };
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(io_error(parent))?;
+    }
+    if existed {
+        remove_existing(target).map_err(io_error(target))?;
+    }
+
+    match source.file.kind {
+        TreeFileKind::Symlink => write_symlink(&content, target).map_err(io_error(target))?,
+        TreeFileKind::File | TreeFileKind::Executable => {
+            fs::write(target, content).map_err(io_error(target))?;
+            if source.file.kind == TreeFileKind::Executable {
+                make_executable(target).map_err(io_error(target))?;
+            }
+        }
+    }
+
+    Ok(if existed {
+        OperationResult::Overwritten
+    } else {
+        OperationResult::Created
+    })
+}
+
+/// Remove the file, symlink, or directory at `path`.
+fn remove_existing(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Create a symlink at `target` pointing to the committed link `content`.
+#[cfg(unix)]
+fn write_symlink(content: &[u8], target: &Path) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(content), target)
+}
+
+/// Without symlink support, write the link target as the file's content,
+/// as git does with `core.symlinks = false`.
+#[cfg(not(unix))]
+fn write_symlink(content: &[u8], target: &Path) -> std::io::Result<()> {
+    fs::write(target, content)
+}
+
+/// Set the executable bits on `path`.
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o111);
+    fs::set_permissions(path, permissions)
+}
+
+/// Files have no executable bit here.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+const fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
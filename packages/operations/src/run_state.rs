@@ -0,0 +1,585 @@
+//! Progress of a setup run, saved so an interrupted run can resume.
+//!
+//! Before the first operation runs, the plan is written to
+//! [`RUN_STATE_FILE`] in the worktree's data directory. One line lists
+//! every operation, and one more line is appended as each operation
+//! starts and finishes. A run that completes removes the file. After an
+//! interruption, the file is what lets `--resume` rebuild the operations
+//! still pending instead of planning and scanning everything again.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use worktree_setup_config::TemplateEngine;
+use worktree_setup_git::{TreeFile, TreeFileKind};
+
+use crate::entry_hooks::OperationHooks;
+use crate::error::OperationError;
+use crate::journal::remove;
+use crate::manifest::unix_now;
+use crate::plan::{
+    EntryOrigin, OperationType, PlannedOperation, TargetModes, load_worktree_ignore,
+};
+use crate::ref_file::RefSource;
+use crate::template::TemplateContext;
+
+/// Run state file name within the worktree's data directory.
+pub const RUN_STATE_FILE: &str = "run.jsonl";
+
+/// The plan of a run and which of its operations have finished.
+#[derive(Debug)]
+pub struct RunState {
+    /// Where the state is saved.
+    path: PathBuf,
+    /// The plan, as saved when the run began.
+    plan: SavedPlan,
+    /// Operations started with their target missing.
+    started: BTreeSet<usize>,
+    /// Operations that finished.
+    done: BTreeSet<usize>,
+}
+
+/// First line of the run state file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedPlan {
+    main_worktree: PathBuf,
+    target: PathBuf,
+    /// Applied config paths, relative to the repository root.
+    configs: Vec<String>,
+    started_at: u64,
+    operations: Vec<SavedOperation>,
+}
+
+/// A line appended while the run progresses.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Progress {
+    /// The operation at `index` started; `fresh` if its target did not
+    /// exist yet.
+    Started { index: usize, fresh: bool },
+    /// The operation at `index` finished.
+    Done { index: usize },
+}
+
+/// A [`PlannedOperation`] as saved. Shared rules (ignore files, template
+/// variables) are rebuilt on resume instead of being saved.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedOperation {
+    display_path: String,
+    operation_type: OperationType,
+    source: PathBuf,
+    target: PathBuf,
+    file_count: u64,
+    symlink_count: u64,
+    byte_count: u64,
+    filtered_count: u64,
+    special_count: u64,
+    is_directory: bool,
+    force_overwrite: bool,
+    ignore: bool,
+    template: Option<TemplateEngine>,
+    order: i32,
+    origin: Option<EntryOrigin>,
+    hooks: Option<SavedHooks>,
+    ref_source: Option<SavedRefSource>,
+    max_depth: Option<usize>,
+    #[serde(default)]
+    modes: Option<TargetModes>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedHooks {
+    before: Option<String>,
+    after: Option<String>,
+    cwd: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedRefSource {
+    repo: PathBuf,
+    git_ref: String,
+    path: PathBuf,
+    oid: String,
+    kind: SavedFileKind,
+    size: u64,
+    filters: bool,
+    unapplied_filter: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SavedFileKind {
+    File,
+    Executable,
+    Symlink,
+}
+
+impl RunState {
+    /// Path of the run state for the worktree at `worktree`.
+    ///
+    /// # Errors
+    ///
+    /// * If `worktree` is not inside a git repository
+    pub fn path_for(worktree: &Path) -> Result<PathBuf, OperationError> {
+        let repo = worktree_setup_git::open_repo(worktree)?;
+        Ok(crate::paths::worktree_data_dir(&repo).join(RUN_STATE_FILE))
+    }
+
+    /// Save `operations` as the plan of a new run, replacing any earlier
+    /// run state at `path`.
+    ///
+    /// Skipped operations are left out. `configs` are the applied config
+    /// paths, relative to the repository root.
+    ///
+    /// # Errors
+    ///
+    /// * If the directory or file cannot be written
+    pub fn begin(
+        path: &Path,
+        main_worktree: &Path,
+        target: &Path,
+        configs: Vec<String>,
+        operations: &[PlannedOperation],
+    ) -> Result<Self, OperationError> {
+        let plan = SavedPlan {
+            main_worktree: main_worktree.to_path_buf(),
+            target: target.to_path_buf(),
+            configs,
+            started_at: unix_now(),
+            operations: operations
+                .iter()
+                .filter(|op| !op.will_skip)
+                .map(SavedOperation::from)
+                .collect(),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| OperationError::IoError {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+        let mut line = serde_json::to_string(&plan).map_err(|e| OperationError::RunStateError {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        line.push('\n');
+        fs::write(path, line).map_err(|e| OperationError::IoError {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            plan,
+            started: BTreeSet::new(),
+            done: BTreeSet::new(),
+        })
+    }
+
+    /// Load the run state at `path`, returning `None` if there is none.
+    ///
+    /// A progress line cut short by an interruption is ignored.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read
+    /// * If the saved plan is not valid
+    pub fn load(path: &Path) -> Result<Option<Self>, OperationError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(OperationError::IoError {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+            }
+        };
+
+        let mut lines = content.lines();
+        let plan: SavedPlan =
+            serde_json::from_str(lines.next().unwrap_or_default()).map_err(|e| {
+                OperationError::RunStateError {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                }
+            })?;
+
+        let mut state = Self {
+            path: path.to_path_buf(),
+            plan,
+            started: BTreeSet::new(),
+            done: BTreeSet::new(),
+        };
+        for line in lines {
+            match serde_json::from_str(line) {
+                Ok(Progress::Started { index, fresh: true }) => {
+                    state.started.insert(index);
+                }
+                Ok(Progress::Done { index }) => {
+                    state.done.insert(index);
+                }
+                Ok(Progress::Started { .. }) | Err(_) => {}
+            }
+        }
+        Ok(Some(state))
+    }
+
+    /// The main worktree the run copies from.
+    #[must_use]
+    pub fn main_worktree(&self) -> &Path {
+        &self.plan.main_worktree
+    }
+
+    /// The worktree the run sets up.
+    #[must_use]
+    pub fn target(&self) -> &Path {
+        &self.plan.target
+    }
+
+    /// Applied config paths, relative to the repository root.
+    #[must_use]
+    pub fn configs(&self) -> &[String] {
+        &self.plan.configs
+    }
+
+    /// When the run began, in seconds since the Unix epoch.
+    #[must_use]
+    pub const fn started_at(&self) -> u64 {
+        self.plan.started_at
+    }
+
+    /// Number of operations in the run.
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.plan.operations.len()
+    }
+
+    /// Number of operations that finished.
+    #[must_use]
+    pub fn completed(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Record that `op` is starting.
+    ///
+    /// # Errors
+    ///
+    /// * If the run state cannot be written
+    pub fn start(&mut self, op: &PlannedOperation) -> Result<(), OperationError> {
+        let Some(index) = self.index_of(op) else {
+            return Ok(());
+        };
+        let fresh = op.target.symlink_metadata().is_err();
+        if fresh {
+            self.started.insert(index);
+        }
+        self.append(&Progress::Started { index, fresh })
+    }
+
+    /// Record that `op` finished, so a resumed run skips it.
+    ///
+    /// # Errors
+    ///
+    /// * If the run state cannot be written
+    pub fn finish_operation(&mut self, op: &PlannedOperation) -> Result<(), OperationError> {
+        let Some(index) = self.index_of(op) else {
+            return Ok(());
+        };
+        self.done.insert(index);
+        self.append(&Progress::Done { index })
+    }
+
+    /// Remove what the interrupted operations left behind.
+    ///
+    /// An operation that started with its target missing and did not
+    /// finish may have copied part of a directory, which a plain copy
+    /// would then skip as existing. Those targets are removed so the
+    /// resumed run copies them whole. Returns the removed paths.
+    ///
+    /// # Errors
+    ///
+    /// * If a partial target cannot be removed
+    pub fn remove_partial(&self) -> Result<Vec<PathBuf>, OperationError> {
+        let mut removed = Vec::new();
+        for index in self.started.difference(&self.done) {
+            let target = &self.plan.operations[*index].target;
+            match remove(target) {
+                Ok(()) => removed.push(target.clone()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(OperationError::IoError {
+                        path: target.clone(),
+                        source: e,
+                    });
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    /// The operations that have not finished, rebuilt from the saved plan.
+    #[must_use]
+    pub fn pending(&self) -> Vec<PlannedOperation> {
+        let main = &self.plan.main_worktree;
+        let target = &self.plan.target;
+        let ignore = self
+            .plan
+            .operations
+            .iter()
+            .any(|op| op.ignore)
+            .then(|| Arc::new(load_worktree_ignore(main, target)));
+
+        self.plan
+            .operations
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.done.contains(index))
+            .map(|(_, op)| op.restore(main, target, ignore.as_ref()))
+            .collect()
+    }
+
+    /// Remove the run state once the run is complete.
+    ///
+    /// # Errors
+    ///
+    /// * If the file exists but cannot be removed
+    pub fn finish(self) -> Result<(), OperationError> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(OperationError::IoError {
+                path: self.path,
+                source: e,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Index of the first unfinished saved operation matching `op`.
+    fn index_of(&self, op: &PlannedOperation) -> Option<usize> {
+        self.plan
+            .operations
+            .iter()
+            .enumerate()
+            .position(|(index, saved)| {
+                !self.done.contains(&index)
+                    && saved.target == op.target
+                    && saved.display_path == op.display_path
+            })
+    }
+
+    /// Append a progress line.
+    fn append(&self, progress: &Progress) -> Result<(), OperationError> {
+        let io_error = |e| OperationError::IoError {
+            path: self.path.clone(),
+            source: e,
+        };
+        let line = serde_json::to_string(progress).map_err(|e| OperationError::RunStateError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        writeln!(file, "{line}").map_err(io_error)
+    }
+}
+
+impl From<&PlannedOperation> for SavedOperation {
+    fn from(op: &PlannedOperation) -> Self {
+        Self {
+            display_path: op.display_path.clone(),
+            operation_type: op.operation_type,
+            source: op.source.clone(),
+            target: op.target.clone(),
+            file_count: op.file_count,
+            symlink_count: op.symlink_count,
+            byte_count: op.byte_count,
+            filtered_count: op.filtered_count,
+            special_count: op.special_count,
+            is_directory: op.is_directory,
+            force_overwrite: op.force_overwrite,
+            ignore: op.ignore.is_some(),
+            template: op.template.as_deref().map(TemplateContext::engine),
+            order: op.order,
+            origin: op.origin.clone(),
+            hooks: op.hooks.as_deref().map(|hooks| SavedHooks {
+                before: hooks.before.clone(),
+                after: hooks.after.clone(),
+                cwd: hooks.cwd.clone(),
+            }),
+            ref_source: op.ref_source.as_deref().map(|source| SavedRefSource {
+                repo: source.repo.clone(),
+                git_ref: source.git_ref.clone(),
+                path: source.file.path.clone(),
+                oid: source.file.oid.clone(),
+                kind: match source.file.kind {
+                    TreeFileKind::File => SavedFileKind::File,
+                    TreeFileKind::Executable => SavedFileKind::Executable,
+                    TreeFileKind::Symlink => SavedFileKind::Symlink,
+                },
+                size: source.file.size,
+                filters: source.filters,
+                unapplied_filter: source.unapplied_filter.clone(),
+            }),
+            max_depth: op.max_depth,
+            modes: op.modes,
+        }
+    }
+}
+
+impl SavedOperation {
+    /// Rebuild the planned operation, sharing `ignore` if it had rules.
+    fn restore(
+        &self,
+        main_worktree: &Path,
+        target_worktree: &Path,
+        ignore: Option<&Arc<worktree_setup_glob::WorktreeIgnore>>,
+    ) -> PlannedOperation {
+        PlannedOperation {
+            display_path: self.display_path.clone(),
+            operation_type: self.operation_type,
+            source: self.source.clone(),
+            target: self.target.clone(),
+            file_count: self.file_count,
+            symlink_count: self.symlink_count,
+            byte_count: self.byte_count,
+            filtered_count: self.filtered_count,
+            special_count: self.special_count,
+            is_directory: self.is_directory,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: self.force_overwrite,
+            ignore: ignore.filter(|_| self.ignore).cloned(),
+            template: self.template.map(|engine| {
+                Arc::new(TemplateContext::new(engine, main_worktree, target_worktree))
+            }),
+            order: self.order,
+            origin: self.origin.clone(),
+            hooks: self.hooks.as_ref().map(|hooks| {
+                Arc::new(OperationHooks {
+                    before: hooks.before.clone(),
+                    after: hooks.after.clone(),
+                    cwd: hooks.cwd.clone(),
+                })
+            }),
+            ref_source: self.ref_source.as_ref().map(|source| {
+                Arc::new(RefSource {
+                    repo: source.repo.clone(),
+                    git_ref: source.git_ref.clone(),
+                    file: TreeFile {
+                        path: source.path.clone(),
+                        oid: source.oid.clone(),
+                        kind: match source.kind {
+                            SavedFileKind::File => TreeFileKind::File,
+                            SavedFileKind::Executable => TreeFileKind::Executable,
+                            SavedFileKind::Symlink => TreeFileKind::Symlink,
+                        },
+                        size: source.size,
+                    },
+                    filters: source.filters,
+                    unapplied_filter: source.unapplied_filter.clone(),
+                })
+            }),
+            max_depth: self.max_depth,
+            modes: self.modes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn op(root: &Path, name: &str, is_directory: bool) -> PlannedOperation {
+        PlannedOperation {
+            display_path: name.to_string(),
+            operation_type: OperationType::Copy,
+            source: root.join("main").join(name),
+            target: root.join("target").join(name),
+            file_count: 1,
+            symlink_count: 0,
+            byte_count: 0,
+            filtered_count: 0,
+            special_count: 0,
+            is_directory,
+            will_skip: false,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            template: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: Some(2),
+            modes: Some(TargetModes {
+                file: Some(0o600),
+                dirs: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_resume_skips_finished_and_removes_partial_operations() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let path = root.join("data").join(RUN_STATE_FILE);
+        let operations = vec![
+            op(root, ".env", false),
+            op(root, "cache", true),
+            PlannedOperation {
+                will_skip: true,
+                ..op(root, "skipped", false)
+            },
+            op(root, "node_modules", true),
+        ];
+
+        let mut state = RunState::begin(
+            &path,
+            &root.join("main"),
+            &root.join("target"),
+            vec!["worktree.config.toml".to_string()],
+            &operations,
+        )
+        .unwrap();
+        assert_eq!(state.total(), 3);
+        state.start(&operations[0]).unwrap();
+        state.finish_operation(&operations[0]).unwrap();
+        state.start(&operations[1]).unwrap();
+        // Interrupted while copying `cache`, mid-write of a progress line.
+        fs::create_dir_all(root.join("target/cache/partial")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"done\":").unwrap();
+        drop(state);
+
+        let state = RunState::load(&path).unwrap().unwrap();
+        assert_eq!(state.configs(), ["worktree.config.toml"]);
+        assert_eq!((state.completed(), state.total()), (1, 3));
+        assert_eq!(state.remove_partial().unwrap(), [root.join("target/cache")]);
+        assert!(!root.join("target/cache").exists());
+
+        let pending = state.pending();
+        let names: Vec<&str> = pending.iter().map(|op| op.display_path.as_str()).collect();
+        assert_eq!(names, ["cache", "node_modules"]);
+        assert_eq!(pending[0].max_depth, Some(2));
+        assert_eq!(pending[0].modes.and_then(|modes| modes.file), Some(0o600));
+
+        state.finish().unwrap();
+        assert!(RunState::load(&path).unwrap().is_none());
+    }
+}
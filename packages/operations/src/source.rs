@@ -0,0 +1,310 @@
+//! Sources that `copy`, `overwrite`, and template entries read from.
+//!
+//! A source is normally a path in the main worktree. One written as
+//! `<scheme>:<location>` is handed to the [`SourceProvider`] registered for
+//! that scheme, which makes its files available as a local path; planning
+//! and copying then treat that path like any other source. Sources whose
+//! prefix names no registered provider stay plain paths.
+//!
+//! The built-in providers are [`FilesystemProvider`] (`file:`) and
+//! [`TarProvider`] (`tar:fixtures.tar.gz#dir`), which extracts each archive
+//! once into the cache directory.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::OperationError;
+use crate::paths::source_cache_dir;
+
+/// Distinguishes staging directories of extractions running at once.
+static STAGING_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Resolves a path written in a config to a path in the main worktree, as
+/// for plain sources, failing if it escapes the worktree.
+pub type ResolvePath<'a> = &'a dyn Fn(&str) -> Result<PathBuf, OperationError>;
+
+/// Makes the files of a `<scheme>:<location>` source available locally.
+pub trait SourceProvider: std::fmt::Debug + Send + Sync {
+    /// Prefix selecting this provider, without the `:`.
+    fn scheme(&self) -> &str;
+
+    /// Return the local path holding the files at `location` (the source
+    /// after `<scheme>:`).
+    ///
+    /// The path may not exist, in which case the entry is skipped as not
+    /// found. Paths inside `location` should be resolved with `resolve`.
+    ///
+    /// # Errors
+    ///
+    /// * If the source cannot be made available
+    fn provide(&self, location: &str, resolve: ResolvePath<'_>) -> Result<PathBuf, OperationError>;
+
+    /// Where entries without a `target` write `location` to, relative to
+    /// the config directory, or `None` if such entries need a `target`.
+    fn default_target<'l>(&self, _location: &'l str) -> Option<&'l str> {
+        None
+    }
+}
+
+/// The providers sources are resolved with, by scheme.
+#[derive(Debug, Clone)]
+pub struct SourceProviders {
+    providers: BTreeMap<String, Arc<dyn SourceProvider>>,
+}
+
+impl Default for SourceProviders {
+    /// The built-in providers: `file:` and `tar:`.
+    fn default() -> Self {
+        let mut providers = Self::empty();
+        providers.register(Arc::new(FilesystemProvider));
+        providers.register(Arc::new(TarProvider::new(source_cache_dir())));
+        providers
+    }
+}
+
+impl SourceProviders {
+    /// A registry without any provider, where every source is a path.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            providers: BTreeMap::new(),
+        }
+    }
+
+    /// Register `provider`, replacing any provider for the same scheme.
+    pub fn register(&mut self, provider: Arc<dyn SourceProvider>) {
+        self.providers
+            .insert(provider.scheme().to_string(), provider);
+    }
+
+    /// The provider for `source` and the location after its scheme, or
+    /// `None` if `source` is a plain path.
+    #[must_use]
+    pub fn split<'s>(&self, source: &'s str) -> Option<(&dyn SourceProvider, &'s str)> {
+        let (scheme, location) = source.split_once(':')?;
+        let provider = self.providers.get(scheme)?;
+        Some((provider.as_ref(), location))
+    }
+}
+
+/// `file:<path>`: a path in the main worktree, the same as writing the
+/// path alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FilesystemProvider;
+
+impl SourceProvider for FilesystemProvider {
+    fn scheme(&self) -> &'static str {
+        "file"
+    }
+
+    fn provide(&self, location: &str, resolve: ResolvePath<'_>) -> Result<PathBuf, OperationError> {
+        resolve(location)
+    }
+
+    fn default_target<'l>(&self, location: &'l str) -> Option<&'l str> {
+        Some(location)
+    }
+}
+
+/// `tar:<archive>#<path>`: `path` inside a tar archive (the whole archive
+/// without `#<path>`), written to `path` unless the entry sets a `target`.
+///
+/// The archive path is resolved like any source path. Archives are
+/// extracted with the system `tar` command, which detects compression, into
+/// a cache directory named after their contents, so an unchanged archive is
+/// extracted once.
+#[derive(Debug, Default)]
+pub struct TarProvider {
+    /// Where archives are extracted, or `None` if there is no cache root.
+    cache_dir: Option<PathBuf>,
+    /// Extracted directory of each archive seen by this provider.
+    extracted: Mutex<BTreeMap<PathBuf, PathBuf>>,
+}
+
+impl TarProvider {
+    /// A provider extracting archives into `cache_dir`.
+    #[must_use]
+    pub const fn new(cache_dir: Option<PathBuf>) -> Self {
+        Self {
+            cache_dir,
+            extracted: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Extract `archive` unless it already is, returning its directory.
+    ///
+    /// The lock is held throughout, so configs planned in parallel never
+    /// extract the same archive twice.
+    fn extract(&self, archive: &Path) -> Result<PathBuf, String> {
+        let mut extracted = self.lock();
+        if let Some(dir) = extracted.get(archive) {
+            return Ok(dir.clone());
+        }
+        let cache_dir = self
+            .cache_dir
+            .as_ref()
+            .ok_or("no cache directory to extract archives into")?;
+        let mut hasher = blake3::Hasher::new();
+        let mut file =
+            fs::File::open(archive).map_err(|e| format!("{}: {e}", archive.display()))?;
+        std::io::copy(&mut file, &mut hasher).map_err(|e| format!("{}: {e}", archive.display()))?;
+        let dir = cache_dir.join(&hasher.finalize().to_hex()[..32]);
+
+        if !dir.is_dir() {
+            log::debug!("Extracting {} into {}", archive.display(), dir.display());
+            // Extract next to the final directory and rename, so an
+            // interrupted extraction is never mistaken for a complete one.
+            let staging = dir.with_extension(format!(
+                "tmp-{}-{}",
+                std::process::id(),
+                STAGING_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&staging).map_err(|e| format!("{}: {e}", staging.display()))?;
+            let result = run_tar(archive, &staging).and_then(|()| {
+                fs::rename(&staging, &dir)
+                    .or_else(|e| if dir.is_dir() { Ok(()) } else { Err(e) })
+                    .map_err(|e| format!("{}: {e}", dir.display()))
+            });
+            if result.is_err() {
+                let _ = fs::remove_dir_all(&staging);
+            }
+            result?;
+        }
+
+        extracted.insert(archive.to_path_buf(), dir.clone());
+        drop(extracted);
+        Ok(dir)
+    }
+
+    /// Lock the extracted directories, even if a panicking thread poisoned
+    /// them.
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, PathBuf>> {
+        self.extracted
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl SourceProvider for TarProvider {
+    fn scheme(&self) -> &'static str {
+        "tar"
+    }
+
+    fn provide(&self, location: &str, resolve: ResolvePath<'_>) -> Result<PathBuf, OperationError> {
+        let source_error = |message: String| OperationError::SourceError {
+            spec: format!("tar:{location}"),
+            message,
+        };
+        let (archive, inner) = location.split_once('#').unwrap_or((location, ""));
+        let inner = Path::new(inner);
+        if !inner
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(source_error(format!(
+                "'{}' is not a relative path inside the archive",
+                inner.display()
+            )));
+        }
+
+        let archive = resolve(archive)?;
+        if !archive.is_file() {
+            return Err(source_error(format!(
+                "archive {} not found",
+                archive.display()
+            )));
+        }
+        let dir = self.extract(&archive).map_err(source_error)?;
+        Ok(dir.join(inner))
+    }
+
+    fn default_target<'l>(&self, location: &'l str) -> Option<&'l str> {
+        location
+            .split_once('#')
+            .map(|(_, inner)| inner)
+            .filter(|inner| !inner.is_empty())
+    }
+}
+
+/// Extract `archive` into `dir` with the system `tar` command.
+fn run_tar(archive: &Path, dir: &Path) -> Result<(), String> {
+    let output = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .output()
+        .map_err(|e| format!("failed to run tar: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_split() {
+        let providers = SourceProviders::default();
+        let (provider, location) = providers.split("tar:fixtures.tgz#data").unwrap();
+        assert_eq!(provider.scheme(), "tar");
+        assert_eq!(location, "fixtures.tgz#data");
+        assert!(providers.split("data/fixtures").is_none());
+        assert!(providers.split("s3:bucket/key").is_none());
+        assert!(SourceProviders::empty().split("tar:a.tar").is_none());
+
+        assert_eq!(provider.default_target(location), Some("data"));
+        assert_eq!(provider.default_target("fixtures.tgz"), None);
+        let (file, location) = providers.split("file:data/fixtures").unwrap();
+        assert_eq!(file.default_target(location), Some("data/fixtures"));
+    }
+
+    #[test]
+    fn test_tar_provider() {
+        let main = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        fs::create_dir_all(main.path().join("seed/data")).unwrap();
+        fs::write(main.path().join("seed/data/users.json"), "[]").unwrap();
+        let status = Command::new("tar")
+            .args(["-czf", "fixtures.tar.gz", "-C", "seed", "data"])
+            .current_dir(main.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let provider = TarProvider::new(Some(cache.path().to_path_buf()));
+        let resolve = |path: &str| Ok(main.path().join(path));
+        let data = provider.provide("fixtures.tar.gz#data", &resolve).unwrap();
+        assert!(data.starts_with(cache.path()));
+        assert_eq!(fs::read_to_string(data.join("users.json")).unwrap(), "[]");
+
+        // A second provider reuses the extracted archive.
+        let again = TarProvider::new(Some(cache.path().to_path_buf()))
+            .provide("fixtures.tar.gz", &resolve)
+            .unwrap();
+        assert_eq!(again.join("data"), data);
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 1);
+
+        let err = provider.provide("missing.tar#data", &resolve).unwrap_err();
+        assert!(err.to_string().contains("not found"), "{err}");
+        let err = provider
+            .provide("fixtures.tar.gz#../etc", &resolve)
+            .unwrap_err();
+        assert_eq!(err.code(), "WTS-OPS-014");
+    }
+}
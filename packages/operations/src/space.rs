@@ -0,0 +1,206 @@
+//! Disk-space checks for planned operations.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::path::Path;
+
+use crate::plan::{OperationType, PlannedOperation};
+
+/// Bytes a plan will write compared with free space on the target
+/// filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpaceCheck {
+    /// Bytes the non-skipped operations will copy.
+    pub required: u64,
+    /// Bytes available to unprivileged users on the target filesystem, or
+    /// `None` if it could not be determined.
+    pub available: Option<u64>,
+}
+
+impl SpaceCheck {
+    /// Compare the bytes `operations` will copy with the free space at
+    /// `target`.
+    #[must_use]
+    pub fn new(operations: &[PlannedOperation], target: &Path) -> Self {
+        Self {
+            required: planned_copy_bytes(operations),
+            available: available_space(target),
+        }
+    }
+
+    /// Whether the target has room for the plan (or free space is unknown).
+    #[must_use]
+    pub fn is_sufficient(&self) -> bool {
+        self.available
+            .is_none_or(|available| self.required <= available)
+    }
+
+    /// Whether the plan would use more than 90% of the free space.
+    #[must_use]
+    pub fn is_tight(&self) -> bool {
+        self.available
+            .is_some_and(|available| self.required > available / 10 * 9)
+    }
+
+    /// Whether the plan must be refused for lack of space.
+    ///
+    /// A plan that does not fit is still allowed when `reflinked` reports
+    /// that copies to the target share extents with their sources, since
+    /// reflinked files use little extra space. `reflinked` is only called
+    /// when the plan does not fit.
+    #[must_use]
+    pub fn refuses(&self, reflinked: impl FnOnce() -> bool) -> bool {
+        !self.is_sufficient() && !reflinked()
+    }
+}
+
+/// Net bytes the non-skipped copy operations in `operations` will add.
+///
+/// `Overwrite` operations replace an existing file, so only the growth over
+/// the current target size is counted.
+#[must_use]
+pub fn planned_copy_bytes(operations: &[PlannedOperation]) -> u64 {
+    operations
+        .iter()
+        .filter(|op| !op.will_skip && op.operation_type != OperationType::Symlink)
+        .map(|op| op.byte_count.saturating_sub(replaced_bytes(op)))
+        .sum()
+}
+
+/// Bytes freed by replacing the existing target of an `Overwrite`.
+fn replaced_bytes(op: &PlannedOperation) -> u64 {
+    if op.operation_type != OperationType::Overwrite {
+        return 0;
+    }
+    std::fs::symlink_metadata(&op.target)
+        .ok()
+        .filter(std::fs::Metadata::is_file)
+        .map_or(0, |metadata| metadata.len())
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+///
+/// Uses the nearest existing ancestor when `path` does not exist yet.
+/// Returns `None` on platforms without `statvfs` or if the query fails.
+#[must_use]
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    statvfs_available(existing)
+}
+
+#[cfg(unix)]
+fn statvfs_available(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` points to
+    // writable memory large enough for a `statvfs` struct.
+    let rc = unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+    if rc != 0 {
+        return None;
+    }
+    // SAFETY: `statvfs` returned success, so it initialized `stat`.
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast, clippy::useless_conversion)]
+    Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+#[cfg(not(unix))]
+const fn statvfs_available(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn op(operation_type: OperationType, byte_count: u64, will_skip: bool) -> PlannedOperation {
+        PlannedOperation {
+            display_path: String::new(),
+            operation_type,
+            source: PathBuf::new(),
+            target: PathBuf::new(),
+            file_count: 1,
+            symlink_count: 0,
+            byte_count,
+            filtered_count: 0,
+            special_count: 0,
+            template: None,
+            is_directory: false,
+            will_skip,
+            skip_reason: None,
+            force_overwrite: false,
+            ignore: None,
+            order: 0,
+            origin: None,
+            hooks: None,
+            ref_source: None,
+            max_depth: None,
+            modes: None,
+        }
+    }
+
+    #[test]
+    fn test_planned_copy_bytes_skips_symlinks_and_skipped_ops() {
+        let ops = vec![
+            op(OperationType::Copy, 100, false),
+            op(OperationType::Overwrite, 20, false),
+            op(OperationType::Copy, 1000, true),
+            op(OperationType::Symlink, 5000, false),
+        ];
+        assert_eq!(planned_copy_bytes(&ops), 120);
+    }
+
+    #[test]
+    fn test_planned_copy_bytes_counts_net_overwrite_growth() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let existing = dir.path().join("existing.txt");
+        std::fs::write(&existing, [0_u8; 30]).unwrap();
+
+        let mut grows = op(OperationType::Overwrite, 50, false);
+        grows.target.clone_from(&existing);
+        let mut shrinks = op(OperationType::Overwrite, 10, false);
+        shrinks.target = existing;
+        assert_eq!(planned_copy_bytes(&[grows, shrinks]), 20);
+    }
+
+    #[test]
+    fn test_space_check_allows_reflinked_plans_that_do_not_fit() {
+        let check = SpaceCheck {
+            required: 2000,
+            available: Some(1000),
+        };
+        assert!(check.refuses(|| false));
+        assert!(!check.refuses(|| true));
+
+        let fits = SpaceCheck {
+            required: 100,
+            available: Some(1000),
+        };
+        assert!(!fits.refuses(|| panic!("reflinks probed for a plan that fits")));
+    }
+
+    #[test]
+    fn test_space_check_thresholds() {
+        let check = |required, available| SpaceCheck {
+            required,
+            available,
+        };
+        assert!(check(100, Some(1000)).is_sufficient());
+        assert!(!check(100, Some(1000)).is_tight());
+        assert!(check(950, Some(1000)).is_tight());
+        assert!(!check(1001, Some(1000)).is_sufficient());
+        assert!(check(u64::MAX, None).is_sufficient());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_available_space_uses_existing_ancestor() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(available_space(&dir.path().join("missing/child")).is_some());
+    }
+}
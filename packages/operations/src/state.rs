@@ -0,0 +1,528 @@
+//! Repository-wide state store keyed by worktree.
+//!
+//! State lives in the repository's common git directory
+//! (`.git/worktree-setup/state/`), one JSON file per worktree, and records
+//! which configs were applied to each worktree, when, and at what config
+//! hash, along with the worktree's labels. Unlike the per-worktree [`Manifest`](crate::Manifest), it is
+//! visible from every worktree of the repository.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use worktree_setup_config::LoadedConfig;
+use worktree_setup_git::Repository;
+
+use crate::error::OperationError;
+use crate::manifest::{hash_file, unix_now};
+use crate::paths::repository_data_dir;
+
+/// Subdirectory of the repository's data directory holding per-worktree
+/// state files.
+pub const STATE_DIR: &str = "state";
+
+/// A config applied to a worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedConfig {
+    /// Unix timestamp (seconds) of the most recent apply.
+    pub applied_at: u64,
+    /// BLAKE3 hash of the config file at apply time.
+    pub config_hash: String,
+}
+
+/// A `key=value` label attached to a worktree, such as `ticket=ABC-123`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The label's name.
+    pub key: String,
+    /// The label's value.
+    pub value: String,
+}
+
+impl FromStr for Label {
+    type Err = String;
+
+    /// Parse `key=value`; the key may not be empty.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(Self {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }),
+            _ => Err(format!("invalid label '{s}': expected KEY=VALUE")),
+        }
+    }
+}
+
+impl std::fmt::Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+/// Everything recorded about one worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeState {
+    /// Canonical path of the worktree.
+    pub worktree: PathBuf,
+    /// Applied configs, keyed by config path relative to the repository root.
+    pub configs: BTreeMap<String, AppliedConfig>,
+    /// Labels describing the worktree (purpose, ticket, owner), by key.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl WorktreeState {
+    /// Create an empty state for `worktree`.
+    #[must_use]
+    pub fn new(worktree: &Path) -> Self {
+        Self {
+            worktree: canonical(worktree),
+            configs: BTreeMap::new(),
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Record that `config` was applied now.
+    pub fn record(&mut self, config: &LoadedConfig) {
+        self.configs.insert(
+            config.relative_path.clone(),
+            AppliedConfig {
+                applied_at: unix_now(),
+                config_hash: config_hash(config),
+            },
+        );
+    }
+
+    /// Set `labels`, replacing earlier values of the same keys.
+    pub fn add_labels(&mut self, labels: &[Label]) {
+        for label in labels {
+            self.labels.insert(label.key.clone(), label.value.clone());
+        }
+    }
+
+    /// Whether the worktree carries every one of `labels`.
+    #[must_use]
+    pub fn has_labels(&self, labels: &[Label]) -> bool {
+        labels
+            .iter()
+            .all(|label| self.labels.get(&label.key) == Some(&label.value))
+    }
+
+    /// Most recent apply time across all configs.
+    #[must_use]
+    pub fn last_applied(&self) -> Option<u64> {
+        self.configs.values().map(|c| c.applied_at).max()
+    }
+
+    /// Whether `config` changed since it was applied to this worktree.
+    ///
+    /// Returns `None` if the config was never applied here.
+    #[must_use]
+    pub fn config_changed(&self, config: &LoadedConfig) -> Option<bool> {
+        self.configs
+            .get(&config.relative_path)
+            .map(|applied| applied.config_hash != config_hash(config))
+    }
+}
+
+/// Hash of a config file's contents, or an empty string if unreadable.
+#[must_use]
+pub fn config_hash(config: &LoadedConfig) -> String {
+    hash_file(&config.config_path).unwrap_or_default()
+}
+
+/// Handle to the state directory of a repository.
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    dir: PathBuf,
+}
+
+impl StateStore {
+    /// Open the state store of the repository containing `worktree`.
+    ///
+    /// # Errors
+    ///
+    /// * If `worktree` is not inside a git repository
+    pub fn for_worktree(worktree: &Path) -> Result<Self, OperationError> {
+        let repo = worktree_setup_git::open_repo(worktree)?;
+        Ok(Self::for_repository(&repo))
+    }
+
+    /// Open the state store of `repo` (shared by all its worktrees).
+    #[must_use]
+    pub fn for_repository(repo: &Repository) -> Self {
+        Self::new(repository_data_dir(repo).join(STATE_DIR))
+    }
+
+    /// Open a state store rooted at `dir`.
+    #[must_use]
+    pub const fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Load the state of `worktree`, returning `None` if nothing is recorded.
+    ///
+    /// # Errors
+    ///
+    /// * If the state file cannot be read or parsed
+    pub fn load(&self, worktree: &Path) -> Result<Option<WorktreeState>, OperationError> {
+        read_state(&self.state_path(worktree))
+    }
+
+    /// Write the state of a worktree.
+    ///
+    /// # Errors
+    ///
+    /// * If the state directory or file cannot be written
+    pub fn save(&self, state: &WorktreeState) -> Result<(), OperationError> {
+        fs::create_dir_all(&self.dir).map_err(|e| OperationError::IoError {
+            path: self.dir.clone(),
+            source: e,
+        })?;
+
+        let path = self.state_path(&state.worktree);
+        let content =
+            serde_json::to_string_pretty(state).map_err(|e| OperationError::ManifestError {
+                path: path.clone(),
+                message: e.to_string(),
+            })?;
+
+        fs::write(&path, content).map_err(|e| OperationError::IoError { path, source: e })
+    }
+
+    /// List the state of every recorded worktree, sorted by path.
+    ///
+    /// Unreadable state files are skipped.
+    #[must_use]
+    pub fn list(&self) -> Vec<WorktreeState> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut states: Vec<WorktreeState> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| match read_state(&entry.path()) {
+                Ok(state) => state,
+                Err(e) => {
+                    log::warn!("Skipping unreadable state file: {e}");
+                    None
+                }
+            })
+            .collect();
+        states.sort_by(|a, b| a.worktree.cmp(&b.worktree));
+        states
+    }
+
+    /// Atomically take a worktree carrying every one of `from` labels,
+    /// replacing those labels with `to`, and return its new state.
+    ///
+    /// Worktrees are tried in path order and must still exist. Each is
+    /// locked while it is relabeled, so concurrent claims never take the
+    /// same worktree. Returns `None` if no worktree is left to take.
+    ///
+    /// # Errors
+    ///
+    /// * If a lock or state file cannot be written
+    pub fn claim(
+        &self,
+        from: &[Label],
+        to: &[Label],
+    ) -> Result<Option<WorktreeState>, OperationError> {
+        for candidate in self.list() {
+            if !candidate.has_labels(from) || !candidate.worktree.exists() {
+                continue;
+            }
+            let Some(lock) =
+                StateLock::acquire(self.state_path(&candidate.worktree).with_extension("lock"))?
+            else {
+                continue;
+            };
+
+            // Another claim may have relabeled it since it was listed.
+            let claimed = self
+                .load(&candidate.worktree)
+                .and_then(|state| match state {
+                    Some(mut state) if state.has_labels(from) => {
+                        for label in from {
+                            state.labels.remove(&label.key);
+                        }
+                        state.add_labels(to);
+                        self.save(&state).map(|()| Some(state))
+                    }
+                    _ => Ok(None),
+                });
+            drop(lock);
+            if let Some(state) = claimed? {
+                return Ok(Some(state));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Take the repository-wide lock `name`, returning `None` if it is
+    /// already held. The lock is released when the returned guard is
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// * If the state directory or lock file cannot be written
+    pub fn try_lock(&self, name: &str) -> Result<Option<StateLock>, OperationError> {
+        fs::create_dir_all(&self.dir).map_err(|e| OperationError::IoError {
+            path: self.dir.clone(),
+            source: e,
+        })?;
+        StateLock::acquire(self.lock_path(name))
+    }
+
+    /// Path of the lock file taken by [`Self::try_lock`] for `name`.
+    #[must_use]
+    pub fn lock_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.lock"))
+    }
+
+    /// Delete state for worktrees that no longer exist on disk.
+    ///
+    /// Returns the number of entries removed.
+    ///
+    /// # Errors
+    ///
+    /// * If a stale state file cannot be deleted
+    pub fn prune(&self) -> Result<usize, OperationError> {
+        let mut removed = 0;
+        for state in self.list() {
+            if state.worktree.exists() {
+                continue;
+            }
+            let path = self.state_path(&state.worktree);
+            // The lock file `claim` leaves next to the state file, if any.
+            let _ = fs::remove_file(path.with_extension("lock"));
+            fs::remove_file(&path).map_err(|e| OperationError::IoError { path, source: e })?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// State file path for a worktree (named by a hash of its canonical path).
+    fn state_path(&self, worktree: &Path) -> PathBuf {
+        let key = blake3::hash(canonical(worktree).as_os_str().as_encoded_bytes()).to_hex();
+        self.dir.join(format!("{}.json", &key[..16]))
+    }
+}
+
+/// An advisory lock on a file in the state directory.
+///
+/// The lock is released when dropped, or by the operating system when the
+/// holding process exits, so a crashed holder never leaves it stuck. The
+/// file itself stays in place for the next holder.
+#[derive(Debug)]
+pub struct StateLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl StateLock {
+    /// Lock the file at `path`, creating it if needed, returning `None` if
+    /// another holder has it locked.
+    fn acquire(path: PathBuf) -> Result<Option<Self>, OperationError> {
+        let file = match OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) => return Err(OperationError::IoError { path, source: e }),
+        };
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { path, _file: file })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(OperationError::IoError { path, source: e }),
+        }
+    }
+
+    /// Path of the lock file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Read a state file, returning `None` if it does not exist.
+fn read_state(path: &Path) -> Result<Option<WorktreeState>, OperationError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(OperationError::IoError {
+                path: path.to_path_buf(),
+                source: e,
+            });
+        }
+    };
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| OperationError::ManifestError {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+}
+
+/// Canonicalize `path`, falling back to the path as given.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use worktree_setup_config::Config;
+
+    fn loaded(dir: &Path, content: &str) -> LoadedConfig {
+        let config_path = dir.join("worktree.config.toml");
+        fs::write(&config_path, content).unwrap();
+        LoadedConfig {
+            config: Config::default(),
+            config_path,
+            config_dir: dir.to_path_buf(),
+            relative_path: "worktree.config.toml".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_save_and_load() {
+        let state_dir = TempDir::new().unwrap();
+        let worktree = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().join("state"));
+
+        assert_eq!(store.load(worktree.path()).unwrap(), None);
+
+        let config = loaded(worktree.path(), "copy = [\".env\"]");
+        let mut state = WorktreeState::new(worktree.path());
+        state.record(&config);
+        store.save(&state).unwrap();
+
+        let loaded_state = store.load(worktree.path()).unwrap().unwrap();
+        assert_eq!(loaded_state, state);
+        assert_eq!(loaded_state.config_changed(&config), Some(false));
+        assert!(loaded_state.last_applied().is_some());
+
+        let changed = loaded(worktree.path(), "copy = [\".env\", \"x\"]");
+        assert_eq!(loaded_state.config_changed(&changed), Some(true));
+    }
+
+    #[test]
+    fn test_labels() {
+        let worktree = TempDir::new().unwrap();
+        let label = |s: &str| s.parse::<Label>().unwrap();
+
+        let mut state = WorktreeState::new(worktree.path());
+        state.add_labels(&[label("ticket=ABC-123"), label("purpose=review")]);
+        state.add_labels(&[label(" purpose = agent ")]);
+        assert_eq!(state.labels["purpose"], "agent");
+        assert!(state.has_labels(&[label("purpose=agent"), label("ticket=ABC-123")]));
+        assert!(!state.has_labels(&[label("purpose=review")]));
+        assert!(state.has_labels(&[]));
+
+        assert_eq!(label("empty=").value, "");
+        assert!("=value".parse::<Label>().is_err());
+        assert!("purpose".parse::<Label>().is_err());
+        assert_eq!(label("url=a=b").to_string(), "url=a=b");
+    }
+
+    #[test]
+    fn test_list_and_prune() {
+        let state_dir = TempDir::new().unwrap();
+        let kept = TempDir::new().unwrap();
+        let gone = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().to_path_buf());
+
+        store.save(&WorktreeState::new(kept.path())).unwrap();
+        store.save(&WorktreeState::new(gone.path())).unwrap();
+        assert_eq!(store.list().len(), 2);
+
+        let gone_path = gone.path().to_path_buf();
+        drop(gone);
+        assert!(!gone_path.exists());
+
+        assert_eq!(store.prune().unwrap(), 1);
+        let remaining = store.list();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].worktree, kept.path().canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_claim() {
+        let state_dir = TempDir::new().unwrap();
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().to_path_buf());
+        let label = |s: &str| s.parse::<Label>().unwrap();
+        let ready = [label("pool=ready")];
+        let claimed = [label("pool=claimed"), label("owner=agent-1")];
+
+        for worktree in [&first, &second] {
+            let mut state = WorktreeState::new(worktree.path());
+            state.add_labels(&ready);
+            store.save(&state).unwrap();
+        }
+
+        // A worktree locked by another claim is passed over.
+        let first_path = first.path().canonicalize().unwrap();
+        let lock = StateLock::acquire(store.state_path(&first_path).with_extension("lock"))
+            .unwrap()
+            .unwrap();
+        let state = store.claim(&ready, &claimed).unwrap().unwrap();
+        assert_eq!(state.worktree, second.path().canonicalize().unwrap());
+        assert!(state.has_labels(&claimed));
+        assert_eq!(store.list().len(), 2);
+        assert!(store.claim(&ready, &claimed).unwrap().is_none());
+
+        drop(lock);
+        let state = store.claim(&ready, &claimed).unwrap().unwrap();
+        assert_eq!(state.worktree, first_path);
+        assert!(store.claim(&ready, &claimed).unwrap().is_none());
+        assert!(
+            store
+                .load(&first_path)
+                .unwrap()
+                .unwrap()
+                .has_labels(&claimed)
+        );
+    }
+
+    #[test]
+    fn test_try_lock() {
+        let state_dir = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().join("state"));
+
+        let lock = store.try_lock("pool").unwrap().unwrap();
+        assert!(lock.path().exists());
+        assert!(store.try_lock("pool").unwrap().is_none());
+        assert!(store.list().is_empty());
+
+        drop(lock);
+        assert!(store.try_lock("pool").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_try_lock_ignores_lock_files_left_behind() {
+        let state_dir = TempDir::new().unwrap();
+        let store = StateStore::new(state_dir.path().to_path_buf());
+
+        // A holder that died leaves its file behind but no lock on it.
+        fs::write(store.lock_path("pool"), "").unwrap();
+        assert!(store.try_lock("pool").unwrap().is_some());
+    }
+}
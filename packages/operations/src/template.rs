@@ -0,0 +1,321 @@
+//! Template rendering for `templates` entries.
+//!
+//! A template's `engine` picks how its files are rendered into the target
+//! worktree: copied unchanged (the default), `${NAME}` substitution,
+//! Handlebars, or Tera. Every engine sees the same variables: the
+//! environment, overlaid with `WORKTREE_PATH`, `WORKTREE_NAME`,
+//! `MAIN_WORKTREE_PATH` and (when the target is on a branch) `BRANCH`.
+
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use worktree_setup_config::TemplateEngine;
+
+use crate::OperationResult;
+use crate::error::OperationError;
+
+/// Engine and variables used to render the files of one template entry.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    engine: TemplateEngine,
+    vars: BTreeMap<String, String>,
+}
+
+impl TemplateContext {
+    /// Create a context for rendering into `target_worktree`.
+    ///
+    /// Environment variables that are not valid UTF-8 are left out.
+    #[must_use]
+    pub fn new(engine: TemplateEngine, main_worktree: &Path, target_worktree: &Path) -> Self {
+        let mut vars: BTreeMap<String, String> = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+
+        vars.insert(
+            "WORKTREE_PATH".to_string(),
+            target_worktree.display().to_string(),
+        );
+        vars.insert(
+            "WORKTREE_NAME".to_string(),
+            target_worktree
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        );
+        vars.insert(
+            "MAIN_WORKTREE_PATH".to_string(),
+            main_worktree.display().to_string(),
+        );
+        if let Some(branch) = worktree_setup_git::open_repo(target_worktree)
+            .ok()
+            .and_then(|repo| worktree_setup_git::get_current_branch(&repo).ok().flatten())
+        {
+            vars.insert("BRANCH".to_string(), branch);
+        }
+
+        Self { engine, vars }
+    }
+
+    /// Create a context with exactly the given variables.
+    #[must_use]
+    pub const fn with_vars(engine: TemplateEngine, vars: BTreeMap<String, String>) -> Self {
+        Self { engine, vars }
+    }
+
+    /// The engine files are rendered with.
+    #[must_use]
+    pub const fn engine(&self) -> TemplateEngine {
+        self.engine
+    }
+
+    /// Variables available to templates.
+    #[must_use]
+    pub const fn vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    /// Render `content` with this context's engine.
+    ///
+    /// # Errors
+    ///
+    /// * If the template has a syntax error
+    /// * If Tera finds a variable that is not defined
+    pub fn render(&self, content: &str) -> Result<String, String> {
+        match self.engine {
+            TemplateEngine::Copy => Ok(content.to_string()),
+            TemplateEngine::Simple => Ok(substitute(content, &self.vars)),
+            TemplateEngine::Handlebars => {
+                let mut handlebars = handlebars::Handlebars::new();
+                // Templates are config files, not HTML.
+                handlebars.register_escape_fn(handlebars::no_escape);
+                handlebars
+                    .render_template(content, &self.vars)
+                    .map_err(|e| e.to_string())
+            }
+            TemplateEngine::Tera => {
+                let vars = tera::Context::from_serialize(&self.vars).map_err(|e| e.to_string())?;
+                tera::Tera::one_off(content, &vars, false).map_err(|e| {
+                    // Tera puts the useful part of the message in the source.
+                    std::error::Error::source(&e)
+                        .map_or_else(|| e.to_string(), |source| format!("{e}: {source}"))
+                })
+            }
+        }
+    }
+}
+
+/// Replace each `${NAME}` in `content` whose name is in `vars`.
+///
+/// References to unknown names are left as they are, so files that use
+/// the same syntax for something else survive.
+fn substitute(content: &str, vars: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        if let Some((end, value)) = after
+            .find('}')
+            .and_then(|end| vars.get(&after[..end]).map(|value| (end, value)))
+        {
+            out.push_str(value);
+            rest = &after[end + 1..];
+        } else {
+            out.push_str("${");
+            rest = after;
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Render the template file `source` into `target`.
+///
+/// Without `overwrite`, an existing target is left alone. The rendered file
+/// keeps the permissions of its source.
+///
+/// # Errors
+///
+/// * If the source cannot be read as UTF-8 text
+/// * If rendering fails
+/// * If the target cannot be written
+pub fn render_template_file(
+    source: &Path,
+    target: &Path,
+    context: &TemplateContext,
+    overwrite: bool,
+) -> Result<OperationResult, OperationError> {
+    log::debug!(
+        "Rendering template ({:?}): {} -> {}",
+        context.engine,
+        source.display(),
+        target.display()
+    );
+
+    if !source.exists() {
+        return Ok(OperationResult::Skipped);
+    }
+    let existed = target.exists();
+    if existed && !overwrite {
+        return Ok(OperationResult::Exists);
+    }
+
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| OperationError::IoError { path, source }
+    };
+
+    let text = fs::read_to_string(source).map_err(io_error(source))?;
+    let rendered = context
+        .render(&text)
+        .map_err(|message| OperationError::TemplateError {
+            path: source.to_path_buf(),
+            message,
+        })?;
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(io_error(parent))?;
+    }
+    fs::write(target, rendered).map_err(io_error(target))?;
+    if let Ok(metadata) = fs::metadata(source) {
+        fs::set_permissions(target, metadata.permissions()).map_err(io_error(target))?;
+    }
+
+    Ok(if existed {
+        OperationResult::Overwritten
+    } else {
+        OperationResult::Created
+    })
+}
+
+/// Create the directories missing above `target`, outermost first, and
+/// return them.
+///
+/// # Errors
+///
+/// * If a directory cannot be created
+pub fn create_target_dirs(target: &Path) -> Result<Vec<PathBuf>, OperationError> {
+    let mut missing: Vec<PathBuf> = target
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    for dir in &missing {
+        fs::create_dir(dir).map_err(|source| OperationError::IoError {
+            path: dir.clone(),
+            source,
+        })?;
+    }
+    Ok(missing)
+}
+
+/// Set the permission bits of `path` to `mode`.
+///
+/// # Errors
+///
+/// * If the permissions cannot be changed
+#[cfg(unix)]
+pub fn set_mode(path: &Path, mode: u32) -> Result<(), OperationError> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|source| {
+        OperationError::IoError {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+/// Unix permission bits have no equivalent here, so `mode` is ignored.
+///
+/// # Errors
+///
+/// * Never
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn set_mode(path: &Path, mode: u32) -> Result<(), OperationError> {
+    log::debug!("Ignoring mode {mode:04o} for {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context(engine: TemplateEngine) -> TemplateContext {
+        TemplateContext::with_vars(
+            engine,
+            BTreeMap::from([
+                ("BRANCH".to_string(), "feature/x".to_string()),
+                ("PORT".to_string(), "3001".to_string()),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_simple_engine_substitutes_known_names() {
+        let rendered = context(TemplateEngine::Simple)
+            .render("PORT=${PORT}\nBRANCH=${BRANCH}\nHOME=${UNKNOWN} ${PORT")
+            .unwrap();
+        assert_eq!(
+            rendered,
+            "PORT=3001\nBRANCH=feature/x\nHOME=${UNKNOWN} ${PORT"
+        );
+    }
+
+    #[test]
+    fn test_handlebars_and_tera_engines() {
+        assert_eq!(
+            context(TemplateEngine::Handlebars)
+                .render("{{BRANCH}}:{{PORT}}{{#if MISSING}}!{{/if}}")
+                .unwrap(),
+            "feature/x:3001"
+        );
+        assert_eq!(
+            context(TemplateEngine::Tera)
+                .render("{{ BRANCH | upper }}:{{ PORT }}")
+                .unwrap(),
+            "FEATURE/X:3001"
+        );
+        assert!(
+            context(TemplateEngine::Tera)
+                .render("{{ MISSING }}")
+                .is_err()
+        );
+        assert_eq!(
+            context(TemplateEngine::Copy).render("{{ PORT }}").unwrap(),
+            "{{ PORT }}"
+        );
+    }
+
+    #[test]
+    fn test_render_template_file_respects_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join(".env.example");
+        let target = dir.path().join("out/.env");
+        fs::write(&source, "PORT=${PORT}\n").unwrap();
+        let context = context(TemplateEngine::Simple);
+
+        let result = render_template_file(&source, &target, &context, false).unwrap();
+        assert_eq!(result, OperationResult::Created);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "PORT=3001\n");
+
+        fs::write(&target, "edited").unwrap();
+        let result = render_template_file(&source, &target, &context, false).unwrap();
+        assert_eq!(result, OperationResult::Exists);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "edited");
+
+        let result = render_template_file(&source, &target, &context, true).unwrap();
+        assert_eq!(result, OperationResult::Overwritten);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "PORT=3001\n");
+    }
+}